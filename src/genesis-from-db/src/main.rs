@@ -795,6 +795,7 @@ struct SendEventJson {
     symbol: Address,
     amount: TokenAmount,
     memo: Option<String>,
+    reference: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -939,12 +940,14 @@ impl From<EventInfo> for EventInfoJson {
                 symbol,
                 amount,
                 memo,
+                reference,
             } => Self::Send(SendEventJson {
                 from,
                 to,
                 symbol,
                 amount,
                 memo: get_str_memo(&memo),
+                reference,
             }),
             EventInfo::AccountCreate {
                 account,
@@ -999,6 +1002,7 @@ impl From<EventInfo> for EventInfoJson {
                 execute_automatically,
                 data_,
                 memo,
+                ..
             } => Self::AccountMultisigSubmit(AccountMultisigSubmitEventJson {
                 submitter,
                 account,
@@ -8,7 +8,7 @@ use many_modules::kvstore::{KeyFilterType, TransferArgs};
 use many_modules::r#async::{StatusArgs, StatusReturn};
 use many_modules::{kvstore, r#async};
 use many_protocol::ResponseMessage;
-use many_types::{Either, SortOrder};
+use many_types::{Either, SortOrder, Timestamp};
 use std::collections::BTreeMap;
 use std::io::Read;
 use std::path::PathBuf;
@@ -71,6 +71,9 @@ enum SubCommand {
     /// Disable a value from the store.
     Disable(DisableOpt),
 
+    /// Re-enable a disabled value of the store.
+    Enable(EnableOpt),
+
     /// Transfer ownership of a key.
     Transfer(TransferOpt),
 
@@ -132,6 +135,20 @@ struct DisableOpt {
     /// Reason for disabling the key
     #[clap(long)]
     reason: Option<String>,
+
+    /// Only disable the key until this time, in seconds since the UNIX epoch.
+    #[clap(long)]
+    until: Option<u64>,
+}
+
+#[derive(Debug, Parser)]
+struct EnableOpt {
+    /// The key to enable.
+    key: String,
+
+    /// If the key is a hexadecimal string, pass this flag.
+    #[clap(long)]
+    hex_key: bool,
 }
 
 #[derive(Debug, Parser)]
@@ -202,10 +219,14 @@ fn query(client: ManyClient<impl Identity>, key: &[u8]) -> Result<(), ManyError>
             minicbor::decode(&payload).map_err(ManyError::deserialization_error)?;
 
         let owner = result.owner.to_string();
+        let until = result
+            .disabled_until
+            .map(|until| format!(" until {}", until.secs()))
+            .unwrap_or_default();
 
         match result.disabled {
-            Some(Either::Left(true)) => println!("{owner}, disabled"),
-            Some(Either::Right(reason)) => println!("{owner}, disabled ({reason})"),
+            Some(Either::Left(true)) => println!("{owner}, disabled{until}"),
+            Some(Either::Right(reason)) => println!("{owner}, disabled{until} ({reason})"),
             _ => println!("{owner}"),
         }
 
@@ -223,6 +244,7 @@ fn put(
         key: key.to_vec().into(),
         value: value.into(),
         alternative_owner: alt_owner,
+        roles: None,
     };
 
     let response = client.call("kvstore.put", arguments)?;
@@ -236,11 +258,13 @@ fn disable(
     alt_owner: Option<Address>,
     key: &[u8],
     reason: Option<Reason<u64>>,
+    until: Option<Timestamp>,
 ) -> Result<(), ManyError> {
     let arguments = kvstore::DisableArgs {
         key: key.to_vec().into(),
         alternative_owner: alt_owner,
         reason,
+        until,
     };
 
     let response = client.call("kvstore.disable", arguments)?;
@@ -249,6 +273,22 @@ fn disable(
     Ok(())
 }
 
+fn enable(
+    client: ManyClient<impl Identity>,
+    alt_owner: Option<Address>,
+    key: &[u8],
+) -> Result<(), ManyError> {
+    let arguments = kvstore::EnableArgs {
+        key: key.to_vec().into(),
+        alternative_owner: alt_owner,
+    };
+
+    let response = client.call("kvstore.enable", arguments)?;
+    let payload = wait_response(client, response)?;
+    println!("{}", minicbor::display(&payload));
+    Ok(())
+}
+
 fn transfer(
     client: ManyClient<impl Identity>,
     alt_owner: Option<Address>,
@@ -455,6 +495,7 @@ fn main() {
             key,
             hex_key,
             reason,
+            until,
         }) => {
             let key = if hex_key {
                 hex::decode(&key).unwrap()
@@ -462,7 +503,16 @@ fn main() {
                 key.into_bytes()
             };
             let reason = reason.map(|reason| Reason::new(123456, Some(reason), BTreeMap::new()));
-            disable(client, alt_owner, &key, reason)
+            let until = until.map(|until| Timestamp::new(until).unwrap());
+            disable(client, alt_owner, &key, reason, until)
+        }
+        SubCommand::Enable(EnableOpt { key, hex_key }) => {
+            let key = if hex_key {
+                hex::decode(&key).unwrap()
+            } else {
+                key.into_bytes()
+            };
+            enable(client, alt_owner, &key)
         }
         SubCommand::Transfer(TransferOpt {
             key,
@@ -0,0 +1,114 @@
+//! A cache of the `ledger.info` of each server, kept in `~/.cache/many/` so
+//! that resolving symbol names or decimals doesn't need a call every time.
+use many_cli_helpers::error::ClientServerError;
+use many_client::client::blocking::ManyClient;
+use many_identity::{Address, Identity};
+use many_modules::ledger;
+use minicbor::{Decode, Encode};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use tracing::{debug, warn};
+
+#[derive(Decode, Encode)]
+#[cbor(map)]
+struct CachedInfo {
+    /// When the info was fetched, in seconds since the epoch.
+    #[n(0)]
+    fetched: u64,
+
+    #[n(1)]
+    info: ledger::InfoReturns,
+}
+
+/// The directory of the CLI state, `$XDG_CACHE_HOME/many` or
+/// `$HOME/.cache/many`.
+fn cache_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .map(|dir| dir.join("many"))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+#[derive(Debug)]
+pub struct InfoCache {
+    path: Option<PathBuf>,
+    ttl: Duration,
+}
+
+impl InfoCache {
+    /// A cache for the server, where entries expire after `ttl`. A `ttl` of
+    /// zero disables the cache.
+    pub fn new(server: &str, server_id: &Address, ttl: Duration) -> Self {
+        let mut crc = crc_any::CRCu64::crc64();
+        crc.digest(server.as_bytes());
+        crc.digest(server_id.to_string().as_bytes());
+
+        let path = if ttl.is_zero() {
+            None
+        } else {
+            cache_dir().map(|dir| dir.join(format!("ledger-info-{:016x}.cbor", crc.get_crc())))
+        };
+        Self { path, ttl }
+    }
+
+    fn read(&self) -> Option<ledger::InfoReturns> {
+        let bytes = std::fs::read(self.path.as_ref()?).ok()?;
+        let cached: CachedInfo = minicbor::decode(&bytes).ok()?;
+        if now().saturating_sub(cached.fetched) < self.ttl.as_secs() {
+            Some(cached.info)
+        } else {
+            None
+        }
+    }
+
+    fn write(&self, info: &ledger::InfoReturns) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let cached = CachedInfo {
+            fetched: now(),
+            info: info.clone(),
+        };
+        let result = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| {
+                let bytes = minicbor::to_vec(&cached).map_err(std::io::Error::other)?;
+                std::fs::write(path, bytes)
+            });
+        if let Err(e) = result {
+            warn!(
+                "Could not write the ledger info cache {}: {e}",
+                path.display()
+            );
+        }
+    }
+
+    /// Forget the cached info, e.g. after a token was created or updated.
+    pub fn invalidate(&self) {
+        if let Some(path) = &self.path {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    /// The `ledger.info` of the server, from the cache if it is fresh.
+    pub fn info(
+        &self,
+        client: &ManyClient<impl Identity>,
+    ) -> Result<ledger::InfoReturns, ClientServerError> {
+        if let Some(info) = self.read() {
+            debug!("Using the cached ledger info");
+            return Ok(info);
+        }
+
+        let info: ledger::InfoReturns = minicbor::decode(&client.call_("ledger.info", ())?)?;
+        self.write(&info);
+        Ok(info)
+    }
+}
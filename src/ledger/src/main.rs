@@ -6,7 +6,7 @@ use many_identity::{Address, AnonymousIdentity, Identity};
 use many_identity_dsa::CoseKeyIdentity;
 use many_identity_hsm::{Hsm, HsmIdentity, HsmMechanismType, HsmSessionType, HsmUserType};
 use many_modules::r#async::{StatusArgs, StatusReturn};
-use many_modules::{ledger, r#async};
+use many_modules::{ledger, names, r#async};
 use many_protocol::ResponseMessage;
 use many_types::ledger::{Symbol, TokenAmount};
 use many_types::Memo;
@@ -21,9 +21,12 @@ use std::str::FromStr;
 use std::time::Duration;
 use tracing::{debug, error, info, trace};
 
+mod cache;
 mod multisig;
 mod tokens;
 
+use cache::InfoCache;
+
 #[derive(Clone, Debug)]
 #[repr(transparent)]
 struct Amount(pub BigUint);
@@ -88,6 +91,12 @@ struct Opts {
     #[clap(long, conflicts_with("pem"))]
     keyid: Option<String>,
 
+    /// How long the token information of the server (symbols, names and
+    /// decimals) is cached in `~/.cache/many/`, in seconds. Zero disables
+    /// the cache.
+    #[clap(long, default_value = "300")]
+    cache_ttl: u64,
+
     #[clap(subcommand)]
     subcommand: SubCommand,
 }
@@ -118,6 +127,11 @@ struct BalanceOpt {
     /// additional call will be made to retrieve local names.
     #[clap(last = true)]
     symbols: Vec<String>,
+
+    /// Show the balances after the block at this height instead of the
+    /// current ones.
+    #[clap(long)]
+    height: Option<u64>,
 }
 
 #[derive(Parser)]
@@ -127,11 +141,13 @@ pub(crate) struct TargetCommandOpt {
     #[clap(long)]
     account: Option<Address>,
 
-    /// The account or target identity.
-    identity: Address,
+    /// The account or target identity. This can also be a name registered
+    /// with the name service, which is resolved by an additional call.
+    identity: String,
 
-    /// The amount of tokens.
-    amount: BigUint,
+    /// The amount of tokens. An amount with a decimal point (e.g. `1.5`) is
+    /// interpreted using the decimals of the token.
+    amount: String,
 
     /// The symbol to use.  This can either be an identity or
     /// a local name for a symbol. If it doesn't parse to an identity an
@@ -141,18 +157,22 @@ pub(crate) struct TargetCommandOpt {
     /// Optional memo
     #[clap(long)]
     memo: Option<String>,
+
+    /// Optional reference of the payment, e.g. an invoice number, recorded
+    /// in the event of the transaction.
+    #[clap(long)]
+    reference: Option<String>,
 }
 
 pub fn resolve_symbol(
     client: &ManyClient<impl Identity>,
+    cache: &InfoCache,
     symbol: String,
 ) -> Result<Address, ClientServerError> {
     if let Ok(symbol) = Address::from_str(&symbol) {
         Ok(symbol)
     } else {
-        // Get info.
-        let info: ledger::InfoReturns =
-            minicbor::decode(&client.call_("ledger.info", ())?).unwrap();
+        let info = cache.info(client)?;
         info.local_names
             .into_iter()
             .find(|(_, y)| y == &symbol)
@@ -161,13 +181,49 @@ pub fn resolve_symbol(
     }
 }
 
+/// Resolve an address, or a name registered with the name service.
+pub fn resolve_address(
+    client: &ManyClient<impl Identity>,
+    identity: String,
+) -> Result<Address, ClientServerError> {
+    if let Ok(address) = Address::from_str(&identity) {
+        return Ok(address);
+    }
+    let resolved: names::ResolveReturns =
+        minicbor::decode(&client.call_("names.resolve", names::ResolveArgs { name: identity })?)
+            .map_err(|e| anyhow!("Invalid names.resolve response: {e}"))?;
+    Ok(resolved.owner)
+}
+
+/// Parse an amount of tokens, either in the token's smallest unit or with a
+/// decimal point.
+fn parse_amount(
+    client: &ManyClient<impl Identity>,
+    cache: &InfoCache,
+    symbol: &Symbol,
+    amount: &str,
+) -> Result<TokenAmount, ClientServerError> {
+    if !amount.contains('.') {
+        let amount = BigUint::from_str(amount).map_err(|e| anyhow!("Invalid amount: {e}"))?;
+        return Ok(TokenAmount::from(amount));
+    }
+
+    let info = cache.info(client)?;
+    let summary = info
+        .tokens
+        .get(symbol)
+        .ok_or_else(|| anyhow!("Could not find the decimals of symbol '{symbol}'"))?;
+    TokenAmount::from_decimal_str(amount, summary.decimals).map_err(|e| anyhow!("{e}").into())
+}
+
 fn balance(
     client: ManyClient<impl Identity>,
+    cache: &InfoCache,
     account: Option<Address>,
     symbols: Vec<String>,
+    height: Option<u64>,
 ) -> Result<(), ClientServerError> {
-    // Get info.
-    let info: ledger::InfoReturns = minicbor::decode(&client.call_("ledger.info", ())?)?;
+    let info = cache.info(&client)?;
     let local_names: BTreeMap<String, Symbol> = info
         .local_names
         .iter()
@@ -195,6 +251,7 @@ fn balance(
                     .into(),
             )
         },
+        height,
     };
     let payload = client.call_("ledger.balance", argument)?;
 
@@ -203,6 +260,11 @@ fn balance(
     } else {
         let balance: ledger::BalanceReturns = minicbor::decode(&payload).unwrap();
         for (symbol, amount) in balance.balances {
+            let amount = info
+                .tokens
+                .get(&symbol)
+                .and_then(|summary| amount.to_decimal_string(summary.decimals).ok())
+                .unwrap_or_else(|| amount.to_string());
             if let Some(symbol_name) = info.local_names.get(&symbol) {
                 println!("{amount:>12} {symbol_name} ({symbol})");
             } else {
@@ -275,13 +337,17 @@ pub(crate) fn wait_response(
 
 fn send(
     client: ManyClient<impl Identity>,
+    cache: &InfoCache,
     from: Address,
-    to: Address,
-    amount: BigUint,
+    to: String,
+    amount: String,
     symbol: String,
     memo: Option<Memo>,
+    reference: Option<String>,
 ) -> Result<(), ClientServerError> {
-    let symbol = resolve_symbol(&client, symbol)?;
+    let to = resolve_address(&client, to)?;
+    let symbol = resolve_symbol(&client, cache, symbol)?;
+    let amount = parse_amount(&client, cache, &symbol, &amount)?;
 
     if from.is_anonymous() {
         Err(anyhow!("Cannot send tokens from anonymous.").into())
@@ -290,8 +356,9 @@ fn send(
             from: Some(from),
             to,
             symbol,
-            amount: TokenAmount::from(amount),
+            amount,
             memo,
+            reference,
         };
         let response = client.call("ledger.send", arguments)?;
         let payload = wait_response(client, response)?;
@@ -309,6 +376,7 @@ fn main() {
         keyid,
         server,
         server_id,
+        cache_ttl,
         subcommand,
     } = Opts::parse();
 
@@ -346,9 +414,14 @@ fn main() {
     };
 
     let client_address = key.address();
+    let cache = InfoCache::new(&server, &server_id, Duration::from_secs(cache_ttl));
     let client = ManyClient::new(server, server_id, key).unwrap();
     let result = match subcommand {
-        SubCommand::Balance(BalanceOpt { identity, symbols }) => {
+        SubCommand::Balance(BalanceOpt {
+            identity,
+            symbols,
+            height,
+        }) => {
             let identity = identity.map(|identity| {
                 Address::from_str(&identity)
                     .or_else(|_| {
@@ -360,7 +433,7 @@ fn main() {
                     .expect("Unable to decode identity command-line argument")
             });
 
-            balance(client, identity, symbols)
+            balance(client, &cache, identity, symbols, height)
         }
         SubCommand::Send(TargetCommandOpt {
             account,
@@ -368,19 +441,22 @@ fn main() {
             amount,
             symbol,
             memo,
+            reference,
         }) => {
             let from = account.unwrap_or(client_address);
             send(
                 client,
+                &cache,
                 from,
                 identity,
                 amount,
                 symbol,
                 memo.map(|m| Memo::try_from(m.as_str()).unwrap()),
+                reference,
             )
         }
-        SubCommand::Multisig(opts) => multisig::multisig(client, opts),
-        SubCommand::Token(opts) => tokens::tokens(client, opts),
+        SubCommand::Multisig(opts) => multisig::multisig(client, &cache, opts),
+        SubCommand::Token(opts) => tokens::tokens(client, &cache, opts),
     };
 
     if let Err(err) = result {
@@ -1,3 +1,4 @@
+use crate::cache::InfoCache;
 use crate::TargetCommandOpt;
 use clap::Parser;
 use many_cli_helpers::error::ClientServerError;
@@ -6,7 +7,6 @@ use many_identity::{Address, Identity};
 use many_modules::account::features::multisig;
 use many_modules::{events, ledger};
 use many_protocol::ResponseMessage;
-use many_types::ledger::TokenAmount;
 use many_types::memo::MemoLegacy;
 use many_types::Memo;
 use minicbor::bytes::ByteVec;
@@ -104,6 +104,7 @@ struct MultisigArgOpt {
 
 fn submit_send(
     client: ManyClient<impl Identity>,
+    cache: &InfoCache,
     account: Address,
     multisig_arg: MultisigArgOpt,
     opts: TargetCommandOpt,
@@ -116,19 +117,23 @@ fn submit_send(
         amount,
         symbol,
         memo: send_memo,
+        reference,
     } = opts;
     let MultisigArgOpt {
         threshold,
         timeout,
         execute_automatically,
     } = multisig_arg;
-    let symbol = crate::resolve_symbol(&client, symbol)?;
+    let identity = crate::resolve_address(&client, identity)?;
+    let symbol = crate::resolve_symbol(&client, cache, symbol)?;
+    let amount = crate::parse_amount(&client, cache, &symbol, &amount)?;
     let transaction = events::AccountMultisigTransaction::Send(ledger::SendArgs {
         from: from.or(Some(account)),
         to: identity,
         symbol,
-        amount: TokenAmount::from(amount),
+        amount,
         memo: send_memo.map(|m| Memo::try_from(m.as_str()).unwrap()),
+        reference,
     });
     let arguments = multisig::SubmitTransactionArgs {
         account,
@@ -139,6 +144,7 @@ fn submit_send(
         execute_automatically,
         data_: None,
         memo_: legacy_memo.map(|x| MemoLegacy::try_from(x).unwrap()),
+        attachment: None,
     };
     let response = client.call("account.multisigSubmitTransaction", arguments)?;
 
@@ -181,6 +187,7 @@ fn submit_set_defaults(
         execute_automatically,
         data_: None,
         memo_: None,
+        attachment: None,
     };
     let response = client.call("account.multisigSubmitTransaction", arguments)?;
 
@@ -196,6 +203,7 @@ fn submit_set_defaults(
 
 fn submit(
     client: ManyClient<impl Identity>,
+    cache: &InfoCache,
     account: Address,
     multisig_arg: MultisigArgOpt,
     opts: SubmitOpt,
@@ -203,9 +211,15 @@ fn submit(
     legacy_memo: Option<String>,
 ) -> Result<(), ClientServerError> {
     match opts {
-        SubmitOpt::Send(target) => {
-            submit_send(client, account, multisig_arg, target, memo, legacy_memo)
-        }
+        SubmitOpt::Send(target) => submit_send(
+            client,
+            cache,
+            account,
+            multisig_arg,
+            target,
+            memo,
+            legacy_memo,
+        ),
         SubmitOpt::SetDefaults(SetDefaultsOpt {
             target_account,
             opts,
@@ -291,6 +305,7 @@ fn set_defaults(
 
 pub fn multisig(
     client: ManyClient<impl Identity>,
+    cache: &InfoCache,
     opts: CommandOpt,
 ) -> Result<(), ClientServerError> {
     match opts.subcommand {
@@ -300,7 +315,15 @@ pub fn multisig(
             subcommand,
             memo,
             legacy_memo,
-        } => submit(client, account, multisig_arg, subcommand, memo, legacy_memo),
+        } => submit(
+            client,
+            cache,
+            account,
+            multisig_arg,
+            subcommand,
+            memo,
+            legacy_memo,
+        ),
         SubcommandOpt::Approve(sub_opts) => approve(client, sub_opts),
         SubcommandOpt::Revoke(sub_opts) => revoke(client, sub_opts),
         SubcommandOpt::Execute(sub_opts) => execute(client, sub_opts),
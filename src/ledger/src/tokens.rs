@@ -1,4 +1,4 @@
-use anyhow::anyhow;
+use crate::cache::InfoCache;
 use clap::{Args, Parser};
 use many_cli_helpers::error::ClientServerError;
 use many_client::client::blocking::ManyClient;
@@ -7,14 +7,14 @@ use many_modules::ledger::extended_info::visual_logo::VisualTokenLogo;
 use many_modules::ledger::extended_info::TokenExtendedInfo;
 use many_modules::ledger::{
     TokenAddExtendedInfoArgs, TokenAddExtendedInfoReturns, TokenBurnArgs, TokenBurnReturns,
-    TokenCreateArgs, TokenCreateReturns, TokenInfoArgs, TokenInfoReturns, TokenMintArgs,
-    TokenMintReturns, TokenRemoveExtendedInfoArgs, TokenRemoveExtendedInfoReturns, TokenUpdateArgs,
-    TokenUpdateReturns,
+    TokenCreateArgs, TokenCreateReturns, TokenHistoryArgs, TokenHistoryReturns, TokenInfoArgs,
+    TokenInfoReturns, TokenListArgs, TokenListReturns, TokenMintArgs, TokenMintReturns,
+    TokenRemoveExtendedInfoArgs, TokenRemoveExtendedInfoReturns, TokenSupplyHistoryArgs,
+    TokenSupplyHistoryReturns, TokenUpdateArgs, TokenUpdateReturns,
 };
 use many_types::cbor::CborNull;
 use many_types::ledger::{LedgerTokensAddressMap, TokenAmount, TokenInfoSummary, TokenMaybeOwner};
-use many_types::{AttributeRelatedIndex, Memo};
-use std::collections::BTreeMap;
+use many_types::{AttributeRelatedIndex, Memo, SortOrder};
 use std::path::PathBuf;
 
 #[derive(Parser)]
@@ -41,6 +41,15 @@ enum SubcommandOpt {
     /// Get token info
     Info(InfoOpt),
 
+    /// List all tokens
+    List(ListOpt),
+
+    /// Get the previous names and tickers of a token
+    History(HistoryOpt),
+
+    /// Get the tokens minted and burned at each height
+    SupplyHistory(HistoryOpt),
+
     /// Mint new tokens
     Mint(MintOpt),
 
@@ -75,13 +84,35 @@ struct BurnOpt {
 
 #[derive(Args)]
 struct InfoOpt {
-    symbol: Address,
+    /// The symbol of the token, or its local name.
+    symbol: String,
 
     #[clap(long)]
     #[clap(value_parser = attribute_related_index)]
     indices: Option<Vec<AttributeRelatedIndex>>,
 }
 
+#[derive(Args)]
+struct ListOpt {
+    /// Maximum number of tokens to return.
+    #[clap(long)]
+    count: Option<u64>,
+
+    /// Sort order of the symbols (indeterminate, ascending or descending).
+    #[clap(long)]
+    order: Option<SortOrder>,
+
+    /// Only list the tokens after this symbol.
+    #[clap(long)]
+    after: Option<Address>,
+}
+
+#[derive(Args)]
+struct HistoryOpt {
+    /// The symbol of the token, or its local name.
+    symbol: String,
+}
+
 #[derive(Args)]
 struct InitialDistribution {
     #[clap(long)]
@@ -117,7 +148,8 @@ struct CreateTokenOpt {
 
 #[derive(Parser)]
 struct UpdateTokenOpt {
-    symbol: Address,
+    /// The symbol of the token, or its local name.
+    symbol: String,
 
     #[clap(long)]
     name: Option<String>,
@@ -173,7 +205,8 @@ struct ImageLogoOpt {
 
 #[derive(Parser)]
 struct AddExtInfoOpt {
-    symbol: Address,
+    /// The symbol of the token, or its local name.
+    symbol: String,
 
     #[clap(subcommand)]
     ext_info_type: CreateExtInfoOpt,
@@ -185,7 +218,8 @@ struct AddExtInfoOpt {
 
 #[derive(Parser)]
 struct RemoveExtInfoOpt {
-    symbol: Address,
+    /// The symbol of the token, or its local name.
+    symbol: String,
 
     #[clap(value_parser = attribute_related_index)]
     indices: Vec<AttributeRelatedIndex>,
@@ -235,6 +269,7 @@ fn create_ext_info(opts: CreateExtInfoOpt) -> TokenExtendedInfo {
 
 fn create_token(
     client: ManyClient<impl Identity>,
+    cache: &InfoCache,
     opts: CreateTokenOpt,
 ) -> Result<(), ClientServerError> {
     let extended_info = opts.extended_info.map(create_ext_info);
@@ -254,6 +289,7 @@ fn create_token(
     let response = client.call("tokens.create", args)?;
     let payload = crate::wait_response(client, response)?;
     let result: TokenCreateReturns = minicbor::decode(&payload)?;
+    cache.invalidate();
 
     println!("{result:#?}");
     Ok(())
@@ -261,10 +297,11 @@ fn create_token(
 
 fn update_token(
     client: ManyClient<impl Identity>,
+    cache: &InfoCache,
     opts: UpdateTokenOpt,
 ) -> Result<(), ClientServerError> {
     let args = TokenUpdateArgs {
-        symbol: opts.symbol,
+        symbol: crate::resolve_symbol(&client, cache, opts.symbol)?,
         name: opts.name,
         ticker: opts.ticker,
         decimals: opts.decimals,
@@ -274,18 +311,20 @@ fn update_token(
     let response = client.call("tokens.update", args)?;
     let payload = crate::wait_response(client, response)?;
     let _result: TokenUpdateReturns = minicbor::decode(&payload)?;
+    cache.invalidate();
 
     Ok(())
 }
 
 fn add_ext_info(
     client: ManyClient<impl Identity>,
+    cache: &InfoCache,
     opts: AddExtInfoOpt,
 ) -> Result<(), ClientServerError> {
     let extended_info = create_ext_info(opts.ext_info_type);
 
     let args = TokenAddExtendedInfoArgs {
-        symbol: opts.symbol,
+        symbol: crate::resolve_symbol(&client, cache, opts.symbol)?,
         extended_info,
         memo: opts.memo,
     };
@@ -297,10 +336,11 @@ fn add_ext_info(
 
 fn remove_ext_info(
     client: ManyClient<impl Identity>,
+    cache: &InfoCache,
     opts: RemoveExtInfoOpt,
 ) -> Result<(), ClientServerError> {
     let args = TokenRemoveExtendedInfoArgs {
-        symbol: opts.symbol,
+        symbol: crate::resolve_symbol(&client, cache, opts.symbol)?,
         extended_info: opts.indices,
         memo: opts.memo,
     };
@@ -310,9 +350,13 @@ fn remove_ext_info(
     Ok(())
 }
 
-fn info_token(client: ManyClient<impl Identity>, opts: InfoOpt) -> Result<(), ClientServerError> {
+fn info_token(
+    client: ManyClient<impl Identity>,
+    cache: &InfoCache,
+    opts: InfoOpt,
+) -> Result<(), ClientServerError> {
     let args = TokenInfoArgs {
-        symbol: opts.symbol,
+        symbol: crate::resolve_symbol(&client, cache, opts.symbol)?,
         extended_info: opts.indices,
     };
     let response = client.call("tokens.info", args)?;
@@ -323,21 +367,58 @@ fn info_token(client: ManyClient<impl Identity>, opts: InfoOpt) -> Result<(), Cl
     Ok(())
 }
 
-fn mint_token(client: ManyClient<impl Identity>, opts: MintOpt) -> Result<(), ClientServerError> {
-    let symbol = Address::try_from(opts.symbol.as_str()).or_else(|_| {
-        // Get symbol address from name
-        let info: many_modules::ledger::InfoReturns =
-            minicbor::decode(&client.call_("ledger.info", ())?).unwrap();
-        let local_names: BTreeMap<String, Address> = info
-            .local_names
-            .iter()
-            .map(|(x, y)| (y.clone(), *x))
-            .collect();
-        local_names
-            .get(&opts.symbol)
-            .cloned()
-            .ok_or_else(|| anyhow!("Symbol address not found."))
-    })?;
+fn list_tokens(client: ManyClient<impl Identity>, opts: ListOpt) -> Result<(), ClientServerError> {
+    let args = TokenListArgs {
+        count: opts.count,
+        order: opts.order,
+        after: opts.after,
+    };
+    let response = client.call("tokens.list", args)?;
+    let payload = crate::wait_response(client, response)?;
+    let result: TokenListReturns = minicbor::decode(&payload)?;
+
+    println!("{result:#?}");
+    Ok(())
+}
+
+fn history_token(
+    client: ManyClient<impl Identity>,
+    cache: &InfoCache,
+    opts: HistoryOpt,
+) -> Result<(), ClientServerError> {
+    let args = TokenHistoryArgs {
+        symbol: crate::resolve_symbol(&client, cache, opts.symbol)?,
+    };
+    let response = client.call("tokens.history", args)?;
+    let payload = crate::wait_response(client, response)?;
+    let result: TokenHistoryReturns = minicbor::decode(&payload)?;
+
+    println!("{result:#?}");
+    Ok(())
+}
+
+fn supply_history(
+    client: ManyClient<impl Identity>,
+    cache: &InfoCache,
+    opts: HistoryOpt,
+) -> Result<(), ClientServerError> {
+    let args = TokenSupplyHistoryArgs {
+        symbol: crate::resolve_symbol(&client, cache, opts.symbol)?,
+    };
+    let response = client.call("tokens.supplyHistory", args)?;
+    let payload = crate::wait_response(client, response)?;
+    let result: TokenSupplyHistoryReturns = minicbor::decode(&payload)?;
+
+    println!("{result:#?}");
+    Ok(())
+}
+
+fn mint_token(
+    client: ManyClient<impl Identity>,
+    cache: &InfoCache,
+    opts: MintOpt,
+) -> Result<(), ClientServerError> {
+    let symbol = crate::resolve_symbol(&client, cache, opts.symbol)?;
     let args = TokenMintArgs {
         symbol,
         distribution: opts.distribution,
@@ -351,21 +432,12 @@ fn mint_token(client: ManyClient<impl Identity>, opts: MintOpt) -> Result<(), Cl
     Ok(())
 }
 
-fn burn_token(client: ManyClient<impl Identity>, opts: BurnOpt) -> Result<(), ClientServerError> {
-    let symbol = Address::try_from(opts.symbol.as_str()).or_else(|_| {
-        // Get symbol address from name
-        let info: many_modules::ledger::InfoReturns =
-            minicbor::decode(&client.call_("ledger.info", ())?).unwrap();
-        let local_names: BTreeMap<String, Address> = info
-            .local_names
-            .iter()
-            .map(|(x, y)| (y.clone(), *x))
-            .collect();
-        local_names
-            .get(&opts.symbol)
-            .cloned()
-            .ok_or_else(|| anyhow!("Symbol address not found."))
-    })?;
+fn burn_token(
+    client: ManyClient<impl Identity>,
+    cache: &InfoCache,
+    opts: BurnOpt,
+) -> Result<(), ClientServerError> {
+    let symbol = crate::resolve_symbol(&client, cache, opts.symbol)?;
     let args = TokenBurnArgs {
         symbol,
         distribution: opts.distribution,
@@ -382,15 +454,19 @@ fn burn_token(client: ManyClient<impl Identity>, opts: BurnOpt) -> Result<(), Cl
 
 pub fn tokens(
     client: ManyClient<impl Identity>,
+    cache: &InfoCache,
     opts: CommandOpt,
 ) -> Result<(), ClientServerError> {
     match opts.subcommand {
-        SubcommandOpt::Create(opts) => create_token(client, opts),
-        SubcommandOpt::Update(opts) => update_token(client, opts),
-        SubcommandOpt::AddExtInfo(opts) => add_ext_info(client, opts),
-        SubcommandOpt::RemoveExtInfo(opts) => remove_ext_info(client, opts),
-        SubcommandOpt::Info(opts) => info_token(client, opts),
-        SubcommandOpt::Mint(opts) => mint_token(client, opts),
-        SubcommandOpt::Burn(opts) => burn_token(client, opts),
+        SubcommandOpt::Create(opts) => create_token(client, cache, opts),
+        SubcommandOpt::Update(opts) => update_token(client, cache, opts),
+        SubcommandOpt::AddExtInfo(opts) => add_ext_info(client, cache, opts),
+        SubcommandOpt::RemoveExtInfo(opts) => remove_ext_info(client, cache, opts),
+        SubcommandOpt::Info(opts) => info_token(client, cache, opts),
+        SubcommandOpt::List(opts) => list_tokens(client, opts),
+        SubcommandOpt::History(opts) => history_token(client, cache, opts),
+        SubcommandOpt::SupplyHistory(opts) => supply_history(client, cache, opts),
+        SubcommandOpt::Mint(opts) => mint_token(client, cache, opts),
+        SubcommandOpt::Burn(opts) => burn_token(client, cache, opts),
     }
 }
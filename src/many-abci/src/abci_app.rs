@@ -1,22 +1,23 @@
 use crate::migration::error_code::LEGACY_ERROR_CODE_TRIGGER;
 use crate::migration::{AbciAppMigrations, MIGRATIONS};
+use crate::priority::PriorityPolicy;
+use crate::watchdog::{WatchGuard, Watchdog};
+use crate::webhook::{BlockSummary, BlockWebhooks};
 use coset::{CborSerializable, CoseSign1};
 use many_client::client::blocking::{block_on, ManyClient};
 use many_error::{ManyError, ManyErrorCode};
-use many_identity::{Address, AnonymousIdentity};
+use many_identity::{Address, AnonymousIdentity, Verifier};
 use many_migration::MigrationConfig;
 use many_modules::abci_backend::{AbciBlock, AbciCommitInfo, AbciInfo};
 use many_protocol::{RequestMessage, ResponseMessage};
+use many_server::server::ResponseTimestampPolicy;
 use many_server::RequestValidator;
+use many_types::{BlockTime, NetworkId};
 use reqwest::{IntoUrl, Url};
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
 use tendermint_abci::Application;
 use tendermint_proto::abci::*;
-use tracing::{debug, error};
-
-lazy_static::lazy_static!(
-    static ref EPOCH: many_types::Timestamp = many_types::Timestamp::new(0).unwrap();
-);
+use tracing::{debug, debug_span, error, info};
 
 enum ManyAbciErrorCodes {
     Success = 0,
@@ -31,10 +32,12 @@ enum ManyAbciCheckErrorCodes {
     CoseDeserializeError = 4,
     MessageDeserializeError = 5,
     RwLockPoisonedError = 6,
-    TimestampError = 7,
+    // 7 was used for invalid block times, which can no longer happen.
     CannotGetSystemTimeError = 8,
     TimestampOutsideOfRangeError = 9,
     ValidationError = 10,
+    NetworkIdError = 11,
+    SignatureError = 12,
 }
 
 enum ManyAbciDeliverErrorCodes {
@@ -57,13 +60,34 @@ fn get_abci_info_(client: &ManyClient<AnonymousIdentity>) -> Result<AbciInfo, Ma
 #[derive(Clone)]
 pub struct AbciApp {
     app_name: String,
-    many_client: ManyClient<AnonymousIdentity>,
+    many_client: Arc<RwLock<ManyClient<AnonymousIdentity>>>,
     many_url: Url,
+    server_id: Address,
     cache: Arc<RwLock<dyn RequestValidator + Send + Sync>>,
+    priority: Arc<dyn PriorityPolicy + Send + Sync>,
+
+    /// Verifies the signature of transactions, so the priority policy can
+    /// trust their signer.
+    verifier: Option<Arc<Mutex<dyn Verifier>>>,
+
+    /// The network advertised by the backend, checked against transactions.
+    network_id: Option<NetworkId>,
+    require_network_id: bool,
 
     /// We need interior mutability, safely.
     migrations: Arc<RwLock<AbciAppMigrations>>,
-    block_time: Arc<RwLock<Option<u64>>>,
+    block_time: Arc<RwLock<Option<BlockTime>>>,
+
+    /// The timestamp of delivered transaction results, which must be the
+    /// same on every node.
+    response_timestamp_policy: ResponseTimestampPolicy,
+
+    watchdog: Option<Arc<Watchdog>>,
+
+    /// Where to send the summary of every committed block, and the summary
+    /// of the current block.
+    webhooks: Option<BlockWebhooks>,
+    block_summary: Arc<RwLock<BlockSummary>>,
 }
 
 impl AbciApp {
@@ -88,6 +112,7 @@ impl AbciApp {
         let many_client = ManyClient::new(many_url.clone(), server_id, AnonymousIdentity)?;
         let status = many_client.status().map_err(|x| x.to_string())?;
         let app_name = status.name;
+        let network_id = status.network_id;
 
         let migrations = RwLock::new({
             let AbciInfo { height, .. } = get_abci_info_(&many_client)
@@ -105,10 +130,19 @@ impl AbciApp {
         Ok(Self {
             app_name,
             many_url,
-            many_client,
+            server_id,
+            many_client: Arc::new(RwLock::new(many_client)),
             cache: Arc::new(RwLock::new(())),
+            priority: Arc::new(()),
+            verifier: None,
+            network_id,
+            require_network_id: false,
             migrations: Arc::new(migrations),
             block_time: Arc::new(RwLock::new(None)),
+            response_timestamp_policy: ResponseTimestampPolicy::epoch(),
+            watchdog: None,
+            webhooks: None,
+            block_summary: Default::default(),
         })
     }
 
@@ -117,7 +151,76 @@ impl AbciApp {
         self
     }
 
-    fn do_check_tx(&self, tx: impl AsRef<[u8]>) -> Result<(), (ManyAbciCheckErrorCodes, String)> {
+    pub fn with_priority_policy<P: PriorityPolicy + Send + Sync + 'static>(
+        mut self,
+        policy: P,
+    ) -> Self {
+        self.priority = Arc::new(policy);
+        self
+    }
+
+    /// Verify the signature of transactions during `CheckTx`, refusing the
+    /// ones which do not verify. Without a verifier, the priority policy sees
+    /// all transactions as signed by the anonymous address.
+    pub fn with_verifier<V: Verifier + 'static>(mut self, verifier: V) -> Self {
+        self.verifier = Some(Arc::new(Mutex::new(verifier)));
+        self
+    }
+
+    /// Refuse transactions without a network ID when the backend advertises
+    /// one. Transactions for another network are always refused.
+    pub fn with_required_network_id(mut self, required: bool) -> Self {
+        self.require_network_id = required;
+        self
+    }
+
+    /// The timestamp of delivered transaction results, the epoch by default.
+    /// The results are part of the block, so the wall clock would make nodes
+    /// diverge.
+    pub fn with_response_timestamp_policy(mut self, policy: ResponseTimestampPolicy) -> Self {
+        self.response_timestamp_policy = policy;
+        self
+    }
+
+    /// Report the calls to the MANY application which take too long.
+    pub fn with_watchdog(mut self, watchdog: Arc<Watchdog>) -> Self {
+        self.watchdog = Some(watchdog);
+        self
+    }
+
+    /// Send a summary of every committed block, see
+    /// [`crate::webhook::spawn_block_webhooks`].
+    pub fn with_block_webhooks(mut self, webhooks: BlockWebhooks) -> Self {
+        self.webhooks = Some(webhooks);
+        self
+    }
+
+    fn watch(&self, method: &'static str, request_id: Option<u64>) -> Option<WatchGuard<'_>> {
+        self.watchdog.as_ref().map(|w| w.watch(method, request_id))
+    }
+
+    /// The client of the MANY application, re-created if the watchdog
+    /// requested it after a call got stuck.
+    fn client(&self) -> ManyClient<AnonymousIdentity> {
+        if self.watchdog.as_ref().map_or(false, |w| w.take_restart()) {
+            match ManyClient::new(self.many_url.clone(), self.server_id, AnonymousIdentity) {
+                Ok(client) => {
+                    info!("Restarting the connection to the MANY application.");
+                    if let Ok(mut c) = self.many_client.write() {
+                        *c = client;
+                    }
+                }
+                Err(e) => error!("Could not restart the connection to the MANY application: {e}"),
+            }
+        }
+        self.many_client
+            .read()
+            .map(|c| c.clone())
+            .unwrap_or_else(|e| e.into_inner().clone())
+    }
+
+    /// Check a transaction and returns its mempool priority.
+    fn do_check_tx(&self, tx: impl AsRef<[u8]>) -> Result<i64, (ManyAbciCheckErrorCodes, String)> {
         use many_types::Timestamp;
         let cose = CoseSign1::from_slice(tx.as_ref()).map_err(|log| {
             (
@@ -161,10 +264,7 @@ impl AbciApp {
                 log.to_string(),
             )
         })?;
-        let now = time
-            .as_ref()
-            .map_or_else(|| Ok(Timestamp::now()), |x| Timestamp::new(*x))
-            .map_err(|e| (ManyAbciCheckErrorCodes::TimestampError, e.to_string()))?;
+        let now = time.map_or_else(Timestamp::now, Timestamp::from);
 
         let now = now.as_system_time().map_err(|log| {
             (
@@ -181,7 +281,148 @@ impl AbciApp {
                     log.to_string(),
                 )
             })?;
-        Ok(())
+
+        if let Some(id) = &self.network_id {
+            id.validate(&message.attributes, self.require_network_id)
+                .map_err(|log| (ManyAbciCheckErrorCodes::NetworkIdError, log.to_string()))?;
+        }
+
+        let signer = match &self.verifier {
+            Some(verifier) => verifier
+                .lock()
+                .map_err(|log| {
+                    (
+                        ManyAbciCheckErrorCodes::RwLockPoisonedError,
+                        log.to_string(),
+                    )
+                })?
+                .verify_1(&cose)
+                .map_err(|log| (ManyAbciCheckErrorCodes::SignatureError, log.to_string()))?,
+            None => Address::anonymous(),
+        };
+
+        Ok(self.priority.priority(&signer, &cose, &message))
+    }
+
+    /// Count a delivered transaction in the summary of the current block.
+    fn record_delivered_tx(&self, response: &ResponseDeliverTx) {
+        let failed = response.code != ManyAbciDeliverErrorCodes::Success as u32
+            || ResponseMessage::from_bytes(&response.data).map_or(true, |r| r.data.is_err());
+        if let Ok(mut summary) = self.block_summary.write() {
+            summary.tx_count += 1;
+            if failed {
+                summary.failed_tx_count += 1;
+            }
+        }
+    }
+
+    /// Send a transaction to the MANY application.
+    fn do_deliver_tx(&self, request: RequestDeliverTx) -> ResponseDeliverTx {
+        let cose = match CoseSign1::from_slice(&request.tx) {
+            Ok(x) => x,
+            Err(err) => {
+                return ResponseDeliverTx {
+                    code: ManyAbciDeliverErrorCodes::CoseDeserializeError as u32,
+                    log: err.to_string(),
+                    ..Default::default()
+                }
+            }
+        };
+        let request_id = RequestMessage::try_from(&cose).ok().and_then(|m| m.id);
+        let _span = debug_span!("deliver_tx", request_id).entered();
+        let _watch = self.watch("deliver_tx", request_id);
+
+        match block_on(many_client::client::send_envelope(
+            self.many_url.clone(),
+            cose.clone(),
+        )) {
+            Ok(cose_sign) => {
+                let payload = cose_sign.payload.unwrap_or_default();
+                let mut response = ResponseMessage::from_bytes(&payload).unwrap_or_default();
+
+                // Consensus will sign the result, so the `from` field is unnecessary.
+                response.from = Address::anonymous();
+                // The version is ignored and removed.
+                response.version = None;
+                // The timestamp MIGHT differ between two nodes, so it follows
+                // the policy (the epoch by default) instead.
+                let block_time = self
+                    .block_time
+                    .read()
+                    .ok()
+                    .and_then(|t| t.map(many_types::Timestamp::from));
+                response.timestamp = Some(self.response_timestamp_policy.timestamp(block_time));
+                // The ID used to be dropped when decoding the response, and is part
+                // of the results hash. It is logged in the span of this transaction
+                // instead.
+                response.id = None;
+
+                // Check whether we need to apply a correction to the error code decoding
+                // logic.
+                // A bug in the Error module was fixed in
+                //     https://github.com/liftedinit/many-rs/pull/177
+                // which meant we started decoding errors properly, but in production
+                // the ledger was genesis before.
+                if let Ok(m) = self.migrations.read() {
+                    if m.is_active(&LEGACY_ERROR_CODE_TRIGGER) {
+                        response.data = match response.data {
+                            Err(err) => {
+                                if err.code().is_attribute_specific() {
+                                    Err(err.with_code(ManyErrorCode::Unknown))
+                                } else {
+                                    Err(err)
+                                }
+                            }
+                            x => x,
+                        };
+                    }
+                }
+
+                {
+                    let cache = self.cache.write();
+                    if cache.is_err() {
+                        return ResponseDeliverTx {
+                            code: ManyAbciDeliverErrorCodes::RwLockPoisonedError as u32,
+                            ..Default::default()
+                        };
+                    }
+                    if let Err(e) = cache.unwrap().message_executed(&cose, &response) {
+                        // There's nothing we can do here, since the backend has
+                        // already executed the message and updated its test.
+                        panic!(
+                            "message_executed failed: {e}\n\
+                            The backend and tendermint states might be inconsistent \
+                            and would need to revert to a previous block."
+                        );
+                    }
+                }
+
+                if let Err(err) = &response.data {
+                    debug!("deliver_tx failed: {}", err);
+                }
+
+                if let Ok(data) = response.to_bytes() {
+                    ResponseDeliverTx {
+                        code: ManyAbciDeliverErrorCodes::Success as u32,
+                        data: data.into(),
+                        ..Default::default()
+                    }
+                } else {
+                    ResponseDeliverTx {
+                        code: ManyAbciDeliverErrorCodes::TransportResponseError as u32,
+                        ..Default::default()
+                    }
+                }
+            }
+            Err(err) => {
+                error!("deliver_tx could not reach the backend: {}", err);
+                ResponseDeliverTx {
+                    code: ManyAbciDeliverErrorCodes::TransportRequestError as u32,
+                    log: err.to_string(),
+                    ..Default::default()
+                }
+            }
+        }
     }
 }
 
@@ -192,7 +433,7 @@ impl Application for AbciApp {
             request.version, request.block_version, request.p2p_version
         );
 
-        let AbciInfo { height, hash } = match get_abci_info_(&self.many_client) {
+        let AbciInfo { height, hash, .. } = match get_abci_info_(&self.client()) {
             Ok(x) => x,
             Err(err) => {
                 return ResponseInfo {
@@ -274,23 +515,36 @@ impl Application for AbciApp {
             }
         }
 
+        if let (Some(height), Ok(mut summary)) = (height, self.block_summary.write()) {
+            *summary = BlockSummary {
+                height,
+                ..Default::default()
+            };
+        }
+
         let block = AbciBlock { time };
         self.block_time
             .write()
-            .map(|mut block_time| *block_time = time)
+            .map(|mut block_time| *block_time = time.map(BlockTime::from_secs))
             .unwrap_or_else(|_| error!("Block time: Could not acquire lock"));
-        let _ = self.many_client.call_("abci.beginBlock", block);
+        let _watch = self.watch("begin_block", None);
+        let _ = self.client().call_("abci.beginBlock", block);
         ResponseBeginBlock { events: vec![] }
     }
 
     fn check_tx(&self, request: RequestCheckTx) -> ResponseCheckTx {
         self.do_check_tx(&request.tx)
-            .map(|_| ResponseCheckTx {
+            .map(|priority| ResponseCheckTx {
                 code: ManyAbciCheckErrorCodes::Success as u32,
+                priority,
                 ..Default::default()
             })
             .unwrap_or_else(|(code, log)| {
-                debug!("check_tx failed: {}", log);
+                let request_id = CoseSign1::from_slice(&request.tx)
+                    .ok()
+                    .and_then(|cose| RequestMessage::try_from(&cose).ok())
+                    .and_then(|m| m.id);
+                debug!(request_id, "check_tx failed: {}", log);
                 ResponseCheckTx {
                     code: code as u32,
                     log,
@@ -300,94 +554,16 @@ impl Application for AbciApp {
     }
 
     fn deliver_tx(&self, request: RequestDeliverTx) -> ResponseDeliverTx {
-        let cose = match CoseSign1::from_slice(&request.tx) {
-            Ok(x) => x,
-            Err(err) => {
-                return ResponseDeliverTx {
-                    code: ManyAbciDeliverErrorCodes::CoseDeserializeError as u32,
-                    log: err.to_string(),
-                    ..Default::default()
-                }
-            }
-        };
-        match block_on(many_client::client::send_envelope(
-            self.many_url.clone(),
-            cose.clone(),
-        )) {
-            Ok(cose_sign) => {
-                let payload = cose_sign.payload.unwrap_or_default();
-                let mut response = ResponseMessage::from_bytes(&payload).unwrap_or_default();
-
-                // Consensus will sign the result, so the `from` field is unnecessary.
-                response.from = Address::anonymous();
-                // The version is ignored and removed.
-                response.version = None;
-                // The timestamp MIGHT differ between two nodes so we just force it to be 0.
-                response.timestamp = Some(*EPOCH);
-
-                // Check whether we need to apply a correction to the error code decoding
-                // logic.
-                // A bug in the Error module was fixed in
-                //     https://github.com/liftedinit/many-rs/pull/177
-                // which meant we started decoding errors properly, but in production
-                // the ledger was genesis before.
-                if let Ok(m) = self.migrations.read() {
-                    if m.is_active(&LEGACY_ERROR_CODE_TRIGGER) {
-                        response.data = match response.data {
-                            Err(err) => {
-                                if err.code().is_attribute_specific() {
-                                    Err(err.with_code(ManyErrorCode::Unknown))
-                                } else {
-                                    Err(err)
-                                }
-                            }
-                            x => x,
-                        };
-                    }
-                }
-
-                {
-                    let cache = self.cache.write();
-                    if cache.is_err() {
-                        return ResponseDeliverTx {
-                            code: ManyAbciDeliverErrorCodes::RwLockPoisonedError as u32,
-                            ..Default::default()
-                        };
-                    }
-                    if let Err(e) = cache.unwrap().message_executed(&cose, &response) {
-                        // There's nothing we can do here, since the backend has
-                        // already executed the message and updated its test.
-                        panic!(
-                            "message_executed failed: {e}\n\
-                            The backend and tendermint states might be inconsistent \
-                            and would need to revert to a previous block."
-                        );
-                    }
-                }
-
-                if let Ok(data) = response.to_bytes() {
-                    ResponseDeliverTx {
-                        code: ManyAbciDeliverErrorCodes::Success as u32,
-                        data: data.into(),
-                        ..Default::default()
-                    }
-                } else {
-                    ResponseDeliverTx {
-                        code: ManyAbciDeliverErrorCodes::TransportResponseError as u32,
-                        ..Default::default()
-                    }
-                }
-            }
-            Err(err) => ResponseDeliverTx {
-                code: ManyAbciDeliverErrorCodes::TransportRequestError as u32,
-                log: err.to_string(),
-                ..Default::default()
-            },
+        let response = self.do_deliver_tx(request);
+        if self.webhooks.is_some() {
+            self.record_delivered_tx(&response);
         }
+        response
     }
 
     fn end_block(&self, _request: RequestEndBlock) -> ResponseEndBlock {
-        let _ = self.many_client.call_("abci.endBlock", ());
+        let _watch = self.watch("end_block", None);
+        let _ = self.client().call_("abci.endBlock", ());
         Default::default()
     }
 
@@ -396,13 +572,20 @@ impl Application for AbciApp {
     }
 
     fn commit(&self) -> ResponseCommit {
-        self.many_client.call_("abci.commit", ()).map_or_else(
+        let _watch = self.watch("commit", None);
+        self.client().call_("abci.commit", ()).map_or_else(
             |err| ResponseCommit {
                 data: err.to_string().into_bytes().into(),
                 retain_height: 0,
             },
             |msg| {
                 let info: AbciCommitInfo = minicbor::decode(&msg).unwrap();
+                if let (Some(webhooks), Ok(summary)) = (&self.webhooks, self.block_summary.read()) {
+                    webhooks.send(BlockSummary {
+                        app_hash: info.hash.to_vec(),
+                        ..summary.clone()
+                    });
+                }
                 ResponseCommit {
                     data: info.hash.to_vec().into(),
                     retain_height: info.retain_height as i64,
@@ -0,0 +1,158 @@
+//! An optional task comparing the app hash computed by the MANY application
+//! with the app hash agreed on by peers after each block, so a divergence is
+//! reported as soon as it happens instead of Tendermint halting the node on
+//! a later block without much context.
+use many_client::ManyClient;
+use many_error::ManyError;
+use many_identity::AnonymousIdentity;
+use many_modules::abci_backend::AbciInfo;
+use reqwest::Url;
+use std::time::Duration;
+use tendermint_rpc::{Client, HttpClient};
+use tracing::{debug, error, warn};
+
+/// A peer whose app hash differs from the one computed locally.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Divergence {
+    pub height: u64,
+    pub peer: String,
+    pub local: Vec<u8>,
+    pub remote: Vec<u8>,
+}
+
+impl Divergence {
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "height": self.height,
+            "peer": self.peer,
+            "localAppHash": hex::encode_upper(&self.local),
+            "peerAppHash": hex::encode_upper(&self.remote),
+        })
+    }
+}
+
+pub struct DivergenceDetector {
+    many_client: ManyClient<AnonymousIdentity>,
+    peers: Vec<(String, HttpClient)>,
+    webhook: Option<Url>,
+    http: reqwest::Client,
+
+    /// The last height checked against every peer, to only check (and alert
+    /// on) each height once.
+    checked_height: u64,
+}
+
+impl DivergenceDetector {
+    /// `peers` are the URLs of the Tendermint RPC of other validators.
+    pub fn new(
+        many_client: ManyClient<AnonymousIdentity>,
+        peers: Vec<String>,
+        webhook: Option<Url>,
+    ) -> Result<Self, String> {
+        let peers = peers
+            .into_iter()
+            .map(|url| {
+                HttpClient::new(url.as_str())
+                    .map(|client| (url, client))
+                    .map_err(|e| e.to_string())
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            many_client,
+            peers,
+            webhook,
+            http: reqwest::Client::new(),
+            checked_height: 0,
+        })
+    }
+
+    /// The app hash after `height` according to a peer, i.e. the app hash in
+    /// the header of the next block. `None` if the peer isn't there yet.
+    async fn peer_app_hash(client: &HttpClient, height: u64) -> Result<Option<Vec<u8>>, String> {
+        let status = client.status().await.map_err(|e| e.to_string())?;
+        if status.sync_info.latest_block_height.value() <= height {
+            return Ok(None);
+        }
+        let block = client
+            .block((height + 1) as u32)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(Some(block.block.header.app_hash.as_bytes().to_vec()))
+    }
+
+    /// Compare the last app hash of the application with every peer. Peers
+    /// which are behind or cannot be reached are skipped, and checked again
+    /// with the next height.
+    pub async fn check(&mut self) -> Result<Vec<Divergence>, ManyError> {
+        let AbciInfo { height, hash, .. } =
+            minicbor::decode(&self.many_client.call_("abci.info", ()).await?)
+                .map_err(ManyError::deserialization_error)?;
+        if height <= self.checked_height {
+            return Ok(vec![]);
+        }
+
+        let mut divergences = Vec::new();
+        let mut complete = true;
+        for (peer, client) in &self.peers {
+            match Self::peer_app_hash(client, height).await {
+                Ok(Some(remote)) if remote != hash.as_slice() => divergences.push(Divergence {
+                    height,
+                    peer: peer.clone(),
+                    local: hash.to_vec(),
+                    remote,
+                }),
+                Ok(Some(_)) => {}
+                Ok(None) => complete = false,
+                Err(e) => {
+                    debug!("Could not get the app hash of peer {peer}: {e}");
+                    complete = false;
+                }
+            }
+        }
+
+        if complete || !divergences.is_empty() {
+            self.checked_height = height;
+        }
+        Ok(divergences)
+    }
+
+    async fn alert(&self, divergence: &Divergence) {
+        error!(
+            height = divergence.height,
+            peer = divergence.peer.as_str(),
+            local_app_hash = hex::encode_upper(&divergence.local).as_str(),
+            peer_app_hash = hex::encode_upper(&divergence.remote).as_str(),
+            "The app hash diverges from a peer."
+        );
+
+        if let Some(url) = &self.webhook {
+            let result = self
+                .http
+                .post(url.clone())
+                .header("Content-Type", "application/json")
+                .body(divergence.to_json().to_string())
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            if let Err(e) = result {
+                warn!("Could not call the divergence webhook: {e}");
+            }
+        }
+    }
+
+    /// Check for divergences every `interval`, forever.
+    pub async fn run(mut self, interval: Duration) {
+        loop {
+            match self.check().await {
+                Ok(divergences) => {
+                    for divergence in &divergences {
+                        self.alert(divergence).await;
+                    }
+                }
+                Err(e) => debug!("Could not check for divergences: {e}"),
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+}
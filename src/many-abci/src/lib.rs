@@ -4,3 +4,6 @@ pub mod abci_app;
 pub mod many_app;
 pub mod migration;
 pub mod module;
+pub mod priority;
+pub mod watchdog;
+pub mod webhook;
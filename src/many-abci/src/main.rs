@@ -1,18 +1,18 @@
 #![feature(used_with_arg)]
 
 use clap::Parser;
+use many_cli_helpers::verifiers::VerifierFlags;
 use many_client::ManyClient;
-use many_identity::verifiers::AnonymousVerifier;
 use many_identity::{Address, AnonymousIdentity, Identity};
-use many_identity_dsa::{CoseKeyIdentity, CoseKeyVerifier};
-use many_identity_webauthn::WebAuthnVerifier;
+use many_identity_dsa::CoseKeyIdentity;
 use many_migration::MigrationConfig;
 use many_modules::{base, blockchain, r#async};
 use many_protocol::ManyUrl;
+use many_server::server::{FallbackStatusPolicy, ResponseTimestampPolicy};
 use many_server::transport::http::HttpServer;
 use many_server::ManyServer;
 use many_server_cache::{RequestCacheValidator, SharedRocksDbCacheBackend};
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use tendermint_abci::ServerBuilder;
@@ -20,14 +20,22 @@ use tendermint_rpc::Client;
 use tracing::{debug, error, info, trace};
 
 mod abci_app;
+mod divergence;
 mod many_app;
 mod migration;
 mod module;
+mod priority;
+mod watchdog;
+mod webhook;
 
 use abci_app::AbciApp;
+use divergence::DivergenceDetector;
 use many_app::AbciModuleMany;
 use many_server::validator::ValidateOnlyRequestValidator;
 use module::AbciBlockchainModuleImpl;
+use priority::SenderPriorityPolicy;
+use watchdog::Watchdog;
+use webhook::spawn_block_webhooks;
 
 #[derive(Debug, Parser)]
 struct Opts {
@@ -64,22 +72,88 @@ struct Opts {
     #[clap(long)]
     allow_origin: Option<Vec<ManyUrl>>,
 
+    #[clap(flatten)]
+    verifier_flags: VerifierFlags,
+
     /// Path to a JSON file containing an array of MANY addresses
     /// Only addresses from this array will be able to execute commands, e.g., send, put, ...
     /// Any addresses will be able to execute queries, e.g., balance, get, ...
     #[clap(long)]
     allow_addrs: Option<PathBuf>,
 
+    /// Path to a JSON file containing a map of MANY addresses to their
+    /// mempool priority. Transactions signed by addresses with a higher
+    /// priority are ordered first by Tendermint. Addresses missing from the
+    /// map have a priority of 0.
+    #[clap(long)]
+    priority_addrs: Option<PathBuf>,
+
     /// Path to a JSON file containing the configurations for the
     /// migrations. Migrations are DISABLED unless this configuration file
     /// is given.
     #[clap(long, short)]
     migrations_config: Option<PathBuf>,
 
+    /// What to do when the status of the MANY application differs from the
+    /// status of this frontend (identity or versions), one of `strict`
+    /// (return an error), `merge` (use the frontend identity and versions)
+    /// or `namespace` (return the application status in extras, prefixed
+    /// by `fallback.`).
+    #[clap(long, default_value = "strict")]
+    fallback_status: FallbackStatusPolicy,
+
+    /// The timestamp of delivered transaction results, one of `epoch`,
+    /// `block-time` or `fixed:<seconds>`. All the nodes of a network must use
+    /// the same policy. `wall-clock` would make them diverge.
+    #[clap(long, default_value = "epoch")]
+    response_timestamp: ResponseTimestampPolicy,
+
     /// Database path to the cache. If unspecified, the server will not
     /// verify transactions for duplicate requests.
     #[clap(long)]
     cache_db: PathBuf,
+
+    /// URL of the Tendermint RPC of a peer to compare app hashes with after
+    /// each block. A divergence is logged as an error, and sent to
+    /// `--divergence-webhook` if given. Multiple occurences of this argument
+    /// can be given.
+    #[clap(long)]
+    divergence_peer: Vec<String>,
+
+    /// URL to POST a JSON alert to when the app hash diverges from a peer.
+    #[clap(long, requires("divergence-peer"))]
+    divergence_webhook: Option<reqwest::Url>,
+
+    /// How often to check for divergences, in seconds.
+    #[clap(long, default_value = "5")]
+    divergence_interval: u64,
+
+    /// Refuse transactions without a network ID when the MANY application
+    /// advertises one in its status.
+    #[clap(long)]
+    require_network_id: bool,
+
+    /// Log the calls to the MANY application (deliver_tx, commit, ...) still
+    /// running after this many seconds.
+    #[clap(long)]
+    watchdog_threshold: Option<u64>,
+
+    /// Re-create the connection to the MANY application after a call
+    /// exceeded `--watchdog-threshold`.
+    #[clap(long, requires("watchdog-threshold"))]
+    watchdog_restart: bool,
+
+    /// URL to POST a JSON summary of every committed block to (height,
+    /// transaction count, failed transaction count and app hash). Multiple
+    /// occurences of this argument can be given.
+    #[clap(long)]
+    block_webhook: Vec<reqwest::Url>,
+}
+
+/// Read the map of addresses to their mempool priority.
+fn load_priority_addrs(path: PathBuf) -> Result<BTreeMap<Address, i64>, String> {
+    let content = std::fs::read_to_string(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+    json5::from_str(&content).map_err(|e| format!("{}: {e}", path.display()))
 }
 
 #[tokio::main]
@@ -93,12 +167,26 @@ async fn main() {
         many_pem,
         abci_read_buf_size,
         allow_origin,
+        verifier_flags,
         allow_addrs,
+        priority_addrs,
         migrations_config,
+        fallback_status,
+        response_timestamp,
         cache_db,
+        divergence_peer,
+        divergence_webhook,
+        divergence_interval,
+        require_network_id,
+        watchdog_threshold,
+        watchdog_restart,
+        block_webhook,
     } = Opts::parse();
 
     common_flags.init_logging().unwrap();
+    let verifier_config = verifier_flags
+        .config()
+        .expect("Could not load the verifier config");
 
     debug!("{:?}", Opts::parse());
     info!(
@@ -142,13 +230,55 @@ async fn main() {
         std::thread::sleep(std::time::Duration::from_secs(1));
     };
 
+    if !divergence_peer.is_empty() {
+        let detector =
+            DivergenceDetector::new(many_client.clone(), divergence_peer, divergence_webhook)
+                .expect("Could not create the divergence detector");
+        tokio::spawn(detector.run(std::time::Duration::from_secs(divergence_interval)));
+    }
+
+    let priority_policy = match priority_addrs.map(load_priority_addrs).transpose() {
+        Ok(senders) => SenderPriorityPolicy::new(senders.unwrap_or_default()),
+        Err(e) => {
+            error!("Could not load the file passed to --priority-addrs: {e}");
+            std::process::exit(1);
+        }
+    };
+    let check_tx_verifier = verifier_config.build(allow_origin.clone());
+
+    let block_webhooks = match (!block_webhook.is_empty())
+        .then(|| spawn_block_webhooks(block_webhook))
+        .transpose()
+    {
+        Ok(webhooks) => webhooks,
+        Err(e) => {
+            error!("Could not start the block webhooks: {e}");
+            std::process::exit(1);
+        }
+    };
+
     let rocksdb_cache = SharedRocksDbCacheBackend::new(cache_db);
     let abci_app = {
         let rocksdb_cache = rocksdb_cache.clone();
         tokio::task::spawn_blocking(move || {
-            AbciApp::create(many_app, Address::anonymous(), maybe_migrations)
+            let app = AbciApp::create(many_app, Address::anonymous(), maybe_migrations)
                 .unwrap()
                 .with_validator(RequestCacheValidator::new(rocksdb_cache))
+                .with_priority_policy(priority_policy)
+                .with_verifier(check_tx_verifier)
+                .with_required_network_id(require_network_id)
+                .with_response_timestamp_policy(response_timestamp);
+            let app = match watchdog_threshold {
+                Some(secs) => app.with_watchdog(Watchdog::start(
+                    std::time::Duration::from_secs(secs),
+                    watchdog_restart,
+                )),
+                None => app,
+            };
+            match block_webhooks {
+                Some(webhooks) => app.with_block_webhooks(webhooks),
+                None => app,
+            }
         })
         .await
         .unwrap()
@@ -181,11 +311,7 @@ async fn main() {
     let server = ManyServer::new(
         format!("AbciModule({})", &status.name),
         key.clone(),
-        (
-            AnonymousVerifier,
-            CoseKeyVerifier,
-            WebAuthnVerifier::new(allow_origin.clone()),
-        ),
+        verifier_config.build(allow_origin.clone()),
         key.public_key(),
     );
     let allowed_addrs: Option<BTreeSet<Address>> =
@@ -206,6 +332,7 @@ async fn main() {
         s.add_module(blockchain::BlockchainModule::new(blockchain_impl.clone()));
         s.add_module(r#async::AsyncModule::new(blockchain_impl));
         s.set_fallback_module(backend);
+        s.set_fallback_status_policy(fallback_status);
 
         // The message is executed by the _server_ itself after it's been
         // added to tendermint.
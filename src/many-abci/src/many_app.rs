@@ -8,13 +8,14 @@ use many_identity_webauthn::WebAuthnVerifier;
 use many_modules::abci_backend::{AbciInit, EndpointInfo, ABCI_MODULE_ATTRIBUTE};
 use many_modules::base;
 use many_protocol::{
-    decode_request_from_cose_sign1, decode_response_from_cose_sign1,
+    decode_delegated_request_from_cose_sign1, decode_response_from_cose_sign1,
     encode_cose_sign1_from_request, encode_cose_sign1_from_response, ManyUrl,
     RequestMessageBuilder, ResponseMessage,
 };
 use many_server::transport::LowLevelManyRequestHandler;
 use many_types::attributes::Attribute;
 use many_types::cbor::CborAny;
+use many_types::Timestamp;
 use std::collections::{BTreeMap, BTreeSet};
 use std::default::Default;
 use std::fmt::{Debug, Formatter};
@@ -72,13 +73,16 @@ impl<C: Client + Sync> AbciModuleMany<C> {
     }
 
     async fn execute_message(&self, envelope: CoseSign1) -> Result<CoseSign1, ManyError> {
-        let message = decode_request_from_cose_sign1(
+        // This only filters the requests to forward. The backend checks the
+        // delegations again at the time of the block.
+        let (message, _) = decode_delegated_request_from_cose_sign1(
             &envelope,
             &(
                 AnonymousVerifier,
                 CoseKeyVerifier,
                 WebAuthnVerifier::new(self.allow_origin.clone()),
             ),
+            Timestamp::now(),
         )?;
         if let Some(info) = self.backend_endpoints.get(&message.method) {
             let is_command = info.is_command;
@@ -102,12 +106,17 @@ impl<C: Client + Sync> AbciModuleMany<C> {
                     .map_err(ManyError::unexpected_transport_error)?;
 
                 // A command will always return an empty payload with an ASYNC attribute.
-                let response =
-                    ResponseMessage::from_request(&message, &self.identity.address(), Ok(vec![]))
-                        .with_attribute(
-                            many_modules::r#async::attributes::ASYNC
-                                .with_argument(CborAny::Bytes(response.hash.as_bytes().to_vec())),
-                        );
+                let response = ResponseMessage {
+                    version: Some(1),
+                    from: self.identity.address(),
+                    to: message.from,
+                    id: message.id,
+                    ..Default::default()
+                }
+                .with_attribute(
+                    many_modules::r#async::attributes::ASYNC
+                        .with_argument(CborAny::Bytes(response.hash.as_bytes().to_vec())),
+                );
                 encode_cose_sign1_from_response(response, &self.identity)
                     .map_err(ManyError::unexpected_transport_error)
             } else {
@@ -0,0 +1,147 @@
+use coset::CoseSign1;
+use many_identity::Address;
+use many_protocol::RequestMessage;
+use std::collections::BTreeMap;
+
+/// A policy used to compute the mempool priority of a transaction during
+/// `CheckTx`. Tendermint will order transactions with a higher priority first
+/// when proposing a block.
+///
+/// The policy has access to both the envelope and the decoded message, so it
+/// can look at the endpoint or any fee-related attributes. The `from` field
+/// of the message is not verified; policies keyed on the sender must use
+/// `signer`, the address verified from the signature of the envelope.
+pub trait PriorityPolicy {
+    fn priority(&self, signer: &Address, envelope: &CoseSign1, message: &RequestMessage) -> i64;
+}
+
+/// The default policy; all transactions have the same priority.
+impl PriorityPolicy for () {
+    fn priority(&self, _signer: &Address, _envelope: &CoseSign1, _message: &RequestMessage) -> i64 {
+        0
+    }
+}
+
+impl<A: PriorityPolicy + ?Sized> PriorityPolicy for Box<A> {
+    fn priority(&self, signer: &Address, envelope: &CoseSign1, message: &RequestMessage) -> i64 {
+        self.as_ref().priority(signer, envelope, message)
+    }
+}
+
+/// Combine two policies, keeping the highest priority of both.
+impl<A, B> PriorityPolicy for (A, B)
+where
+    A: PriorityPolicy,
+    B: PriorityPolicy,
+{
+    fn priority(&self, signer: &Address, envelope: &CoseSign1, message: &RequestMessage) -> i64 {
+        self.0
+            .priority(signer, envelope, message)
+            .max(self.1.priority(signer, envelope, message))
+    }
+}
+
+/// A policy that assigns a priority per signer, e.g. to order transactions
+/// from privileged senders first. Signers missing from the map have a
+/// priority of 0.
+#[derive(Clone, Debug, Default)]
+pub struct SenderPriorityPolicy {
+    senders: BTreeMap<Address, i64>,
+}
+
+impl SenderPriorityPolicy {
+    pub fn new(senders: BTreeMap<Address, i64>) -> Self {
+        Self { senders }
+    }
+}
+
+impl PriorityPolicy for SenderPriorityPolicy {
+    fn priority(&self, signer: &Address, _envelope: &CoseSign1, _message: &RequestMessage) -> i64 {
+        self.senders.get(signer).copied().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use many_identity::testing::identity;
+    use many_protocol::RequestMessageBuilder;
+
+    struct Fixed(i64);
+
+    impl PriorityPolicy for Fixed {
+        fn priority(
+            &self,
+            _signer: &Address,
+            _envelope: &CoseSign1,
+            _message: &RequestMessage,
+        ) -> i64 {
+            self.0
+        }
+    }
+
+    fn request(from: u32) -> RequestMessage {
+        RequestMessageBuilder::default()
+            .from(identity(from))
+            .method("test".to_string())
+            .build()
+            .unwrap()
+    }
+
+    fn priority(policy: &impl PriorityPolicy, from: u32) -> i64 {
+        policy.priority(&identity(from), &CoseSign1::default(), &request(from))
+    }
+
+    #[test]
+    fn default_is_zero() {
+        assert_eq!(priority(&(), 1), 0);
+        assert_eq!(priority(&Box::new(()), 1), 0);
+    }
+
+    #[test]
+    fn sender() {
+        let policy =
+            SenderPriorityPolicy::new(BTreeMap::from([(identity(1), 10), (identity(2), -5)]));
+        assert_eq!(priority(&policy, 1), 10);
+        assert_eq!(priority(&policy, 2), -5);
+        assert_eq!(priority(&policy, 3), 0);
+        assert!(priority(&policy, 1) > priority(&policy, 3));
+        assert!(priority(&policy, 3) > priority(&policy, 2));
+    }
+
+    #[test]
+    fn senders_with_the_same_priority_tie() {
+        let policy =
+            SenderPriorityPolicy::new(BTreeMap::from([(identity(1), 7), (identity(2), 7)]));
+        assert_eq!(priority(&policy, 1), priority(&policy, 2));
+        // Senders missing from the map tie with the default policy.
+        assert_eq!(priority(&policy, 3), priority(&(), 3));
+    }
+
+    #[test]
+    fn sender_is_the_signer() {
+        let policy = SenderPriorityPolicy::new(BTreeMap::from([(identity(1), 10)]));
+        // A message claiming to be from a privileged sender, signed by another.
+        let message = request(1);
+        assert_eq!(
+            policy.priority(&identity(2), &CoseSign1::default(), &message),
+            0
+        );
+        assert_eq!(
+            policy.priority(&Address::anonymous(), &CoseSign1::default(), &message),
+            0
+        );
+    }
+
+    #[test]
+    fn combined_keeps_the_highest() {
+        assert_eq!(priority(&(Fixed(3), Fixed(8)), 1), 8);
+        assert_eq!(priority(&(Fixed(8), Fixed(3)), 1), 8);
+        assert_eq!(priority(&(Fixed(-4), ()), 1), 0);
+
+        let policy = SenderPriorityPolicy::new(BTreeMap::from([(identity(1), 10)]));
+        let combined = (policy, Fixed(5));
+        assert_eq!(priority(&combined, 1), 10);
+        assert_eq!(priority(&combined, 2), 5);
+    }
+}
@@ -0,0 +1,114 @@
+//! A thread watching the calls made to the MANY application during block
+//! production (`deliver_tx`, `commit`, ...). Calls taking longer than a
+//! threshold are logged while they are still running, so operators can see
+//! why a block is stalled.
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{error, warn};
+
+struct Call {
+    method: &'static str,
+    request_id: Option<u64>,
+    start: Instant,
+    reported: bool,
+}
+
+#[derive(Default)]
+struct Calls {
+    next_id: AtomicU64,
+    running: Mutex<BTreeMap<u64, Call>>,
+}
+
+pub struct Watchdog {
+    restart: bool,
+    calls: Arc<Calls>,
+    restart_pending: Arc<AtomicBool>,
+}
+
+impl Watchdog {
+    /// Start watching calls exceeding `threshold`. If `restart` is true, the
+    /// connection to the application is re-created once a stuck call returns.
+    pub fn start(threshold: Duration, restart: bool) -> Arc<Self> {
+        let watchdog = Arc::new(Self {
+            restart,
+            calls: Default::default(),
+            restart_pending: Default::default(),
+        });
+
+        let calls = Arc::downgrade(&watchdog.calls);
+        let restart_pending = watchdog.restart_pending.clone();
+        let interval = (threshold / 4).max(Duration::from_millis(100));
+        std::thread::Builder::new()
+            .name("abci-watchdog".to_string())
+            .spawn(move || {
+                // Stop once the watchdog is dropped.
+                while let Some(calls) = calls.upgrade() {
+                    let mut running = calls.running.lock().unwrap();
+                    for call in running.values_mut() {
+                        let elapsed = call.start.elapsed();
+                        if elapsed < threshold || call.reported {
+                            continue;
+                        }
+                        call.reported = true;
+                        error!(
+                            method = call.method,
+                            request_id = call.request_id,
+                            elapsed_ms = elapsed.as_millis() as u64,
+                            "The MANY application is not responding."
+                        );
+                        if restart {
+                            restart_pending.store(true, Ordering::SeqCst);
+                        }
+                    }
+                    drop(running);
+                    drop(calls);
+                    std::thread::sleep(interval);
+                }
+            })
+            .expect("Could not start the watchdog thread");
+
+        watchdog
+    }
+
+    /// Watch a call until the returned guard is dropped.
+    pub fn watch(&self, method: &'static str, request_id: Option<u64>) -> WatchGuard<'_> {
+        let id = self.calls.next_id.fetch_add(1, Ordering::Relaxed);
+        self.calls.running.lock().unwrap().insert(
+            id,
+            Call {
+                method,
+                request_id,
+                start: Instant::now(),
+                reported: false,
+            },
+        );
+        WatchGuard { watchdog: self, id }
+    }
+
+    /// Whether the connection to the application should be re-created,
+    /// because a call got stuck. Only returns true once per stuck call.
+    pub fn take_restart(&self) -> bool {
+        self.restart && self.restart_pending.swap(false, Ordering::SeqCst)
+    }
+}
+
+pub struct WatchGuard<'a> {
+    watchdog: &'a Watchdog,
+    id: u64,
+}
+
+impl Drop for WatchGuard<'_> {
+    fn drop(&mut self) {
+        let call = self.watchdog.calls.running.lock().unwrap().remove(&self.id);
+        if let Some(call) = call.filter(|call| call.reported) {
+            warn!(
+                method = call.method,
+                request_id = call.request_id,
+                elapsed_ms = call.start.elapsed().as_millis() as u64,
+                "The MANY application responded after being reported as stuck."
+            );
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! Posts a summary of every committed block to operator-defined URLs, for
+//! monitoring without a Tendermint indexer.
+use reqwest::Url;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::mpsc::{channel, Receiver, Sender};
+use tracing::warn;
+
+/// The number of summaries waiting to be posted, after which new summaries
+/// are dropped.
+const QUEUE_SIZE: usize = 1024;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A summary of a committed block.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BlockSummary {
+    pub height: u64,
+    pub tx_count: u64,
+    /// Transactions which were delivered but returned an error.
+    pub failed_tx_count: u64,
+    pub app_hash: Vec<u8>,
+}
+
+impl BlockSummary {
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "height": self.height,
+            "txCount": self.tx_count,
+            "failedTxCount": self.failed_tx_count,
+            "appHash": hex::encode_upper(&self.app_hash),
+        })
+    }
+}
+
+/// The queue of summaries to post. When the URLs are slower than blocks are
+/// committed, summaries are dropped instead of piling up in memory.
+#[derive(Clone, Debug)]
+pub struct BlockWebhooks {
+    sender: Sender<BlockSummary>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl BlockWebhooks {
+    fn new() -> (Self, Receiver<BlockSummary>) {
+        let (sender, receiver) = channel(QUEUE_SIZE);
+        let webhooks = Self {
+            sender,
+            dropped: Default::default(),
+        };
+        (webhooks, receiver)
+    }
+
+    /// Queue a summary, without waiting.
+    pub fn send(&self, summary: BlockSummary) {
+        if let Err(TrySendError::Full(summary)) = self.sender.try_send(summary) {
+            let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+            warn!(
+                height = summary.height,
+                dropped, "The block webhooks are too slow, dropping a summary."
+            );
+        }
+    }
+
+    /// The number of summaries dropped since the start.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Start a task posting the summaries sent to the returned queue to every
+/// URL. Summaries are posted in order; a URL failing or timing out is logged
+/// and skipped.
+pub fn spawn_block_webhooks(urls: Vec<Url>) -> Result<BlockWebhooks, String> {
+    let (webhooks, mut receiver) = BlockWebhooks::new();
+    let http = reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    tokio::spawn(async move {
+        while let Some(summary) = receiver.recv().await {
+            let body = summary.to_json().to_string();
+            for url in &urls {
+                let result = http
+                    .post(url.clone())
+                    .header("Content-Type", "application/json")
+                    .body(body.clone())
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status());
+                if let Err(e) = result {
+                    warn!(
+                        height = summary.height,
+                        "Could not call the block webhook {url}: {e}"
+                    );
+                }
+            }
+        }
+    });
+
+    Ok(webhooks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_queue_drops() {
+        let (webhooks, mut receiver) = BlockWebhooks::new();
+        for height in 0..QUEUE_SIZE as u64 + 3 {
+            webhooks.send(BlockSummary {
+                height,
+                ..Default::default()
+            });
+        }
+        assert_eq!(webhooks.dropped(), 3);
+
+        // The oldest summaries are kept, in order.
+        assert_eq!(receiver.try_recv().unwrap().height, 0);
+        assert_eq!(receiver.try_recv().unwrap().height, 1);
+    }
+}
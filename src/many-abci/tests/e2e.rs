@@ -0,0 +1,293 @@
+//! End-to-end tests running a MANY application behind many-abci and a single
+//! Tendermint node, as separate processes.
+//!
+//! They are ignored by default since they need Tendermint and the application
+//! binaries. Build the workspace, then run them with
+//!
+//! ```sh
+//! cargo test -p many-abci --test e2e -- --ignored
+//! ```
+//!
+//! `TENDERMINT_BIN`, `MANY_LEDGER_BIN` and `MANY_KVSTORE_BIN` override the
+//! paths of the binaries. By default Tendermint is looked up in the `PATH`,
+//! and the applications next to the many-abci binary.
+use many_client::client::blocking::{BlockchainClient, LedgerClient, ManyClient};
+use many_client::client::ledger::{BalanceArgs, SendArgs};
+use many_error::ManyError;
+use many_identity::{Address, AnonymousIdentity, Identity};
+use many_identity_dsa::CoseKeyIdentity;
+use many_modules::kvstore::{GetArgs, GetReturns, PutArgs};
+use many_modules::r#async::attributes::AsyncAttribute;
+use many_protocol::{RequestMessageBuilder, ResponseMessage};
+use many_types::ledger::{Symbol, TokenAmount};
+use many_types::proof::Proof;
+use many_types::{ProofOperation, PROOF};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// How long to wait for the network to produce its first blocks.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long to wait for a command to be committed.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+fn repo_path(path: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../..")
+        .join(path)
+}
+
+fn identity(pem: &str) -> CoseKeyIdentity {
+    CoseKeyIdentity::from_pem(std::fs::read_to_string(repo_path(pem)).unwrap()).unwrap()
+}
+
+/// The path of a binary, from an environment variable or next to many-abci.
+fn app_bin(var: &str, name: &str) -> PathBuf {
+    std::env::var_os(var)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(env!("CARGO_BIN_EXE_many-abci")).with_file_name(name))
+}
+
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+}
+
+/// A MANY application, many-abci and Tendermint, killed on drop.
+struct Network {
+    processes: Vec<Child>,
+    url: String,
+    _root: tempfile::TempDir,
+}
+
+impl Network {
+    /// Start the application, given its binary and the arguments to serve
+    /// on an address with the ABCI flag set.
+    fn start(app: PathBuf, app_args: impl FnOnce(&Path) -> Vec<String>) -> Self {
+        let root = tempfile::tempdir().unwrap();
+        let [app_port, abci_port, many_port, rpc_port, p2p_port] = [(); 5].map(|_| free_port());
+        let tendermint = std::env::var_os("TENDERMINT_BIN").unwrap_or_else(|| "tendermint".into());
+        let home = root.path().join("tendermint");
+
+        let status = Command::new(&tendermint)
+            .args(["init", "--home"])
+            .arg(&home)
+            .stdout(Stdio::null())
+            .status()
+            .expect("Could not run tendermint");
+        assert!(status.success(), "Could not initialize tendermint");
+
+        let mut network = Self {
+            processes: vec![],
+            url: format!("http://127.0.0.1:{many_port}/"),
+            _root: root,
+        };
+        let root = network._root.path().to_path_buf();
+
+        network.spawn(Command::new(app).args(app_args(&root)).args([
+            "--abci",
+            "--addr",
+            &format!("127.0.0.1:{app_port}"),
+        ]));
+        network.spawn(
+            Command::new(env!("CARGO_BIN_EXE_many-abci"))
+                .args(["--many", &format!("127.0.0.1:{many_port}")])
+                .args(["--many-app", &format!("http://127.0.0.1:{app_port}")])
+                .arg("--many-pem")
+                .arg(repo_path("keys/id2.pem"))
+                .arg("--cache-db")
+                .arg(root.join("abci_request_cache.db"))
+                .args(["--abci", &format!("127.0.0.1:{abci_port}")])
+                .args(["--tendermint", &format!("http://127.0.0.1:{rpc_port}/")]),
+        );
+        network.spawn(
+            Command::new(&tendermint)
+                .arg("start")
+                .arg("--home")
+                .arg(&home)
+                .args(["--proxy_app", &format!("tcp://127.0.0.1:{abci_port}")])
+                .args(["--rpc.laddr", &format!("tcp://127.0.0.1:{rpc_port}")])
+                .args(["--p2p.laddr", &format!("tcp://127.0.0.1:{p2p_port}")]),
+        );
+
+        network.wait_for_height(2);
+        network
+    }
+
+    fn spawn(&mut self, command: &mut Command) {
+        let child = command
+            .stdout(Stdio::null())
+            .spawn()
+            .unwrap_or_else(|e| panic!("Could not spawn {command:?}: {e}"));
+        self.processes.push(child);
+    }
+
+    fn client<I: Identity>(&self, identity: I) -> ManyClient<I> {
+        ManyClient::new(&self.url, Address::anonymous(), identity).unwrap()
+    }
+
+    fn height(&self) -> Result<u64, ManyError> {
+        BlockchainClient::new(self.client(AnonymousIdentity))
+            .info()
+            .map(|info| info.latest_block.height)
+    }
+
+    fn wait_for_height(&self, height: u64) {
+        let start = Instant::now();
+        while !matches!(self.height(), Ok(h) if h >= height) {
+            assert!(
+                start.elapsed() < STARTUP_TIMEOUT,
+                "The network did not reach height {height}"
+            );
+            std::thread::sleep(Duration::from_millis(500));
+        }
+    }
+
+    /// Send a command, and wait for its response once it is committed.
+    fn command<I: Identity>(
+        &self,
+        identity: I,
+        method: &str,
+        argument: impl minicbor::Encode<()>,
+    ) -> Vec<u8> {
+        let client = self.client(identity);
+        let response = client.call(method, argument).unwrap();
+        let token = response
+            .attributes
+            .get::<AsyncAttribute>()
+            .expect("Commands should return an async token")
+            .token;
+        client
+            .wait_async(token, COMMAND_TIMEOUT)
+            .unwrap()
+            .expect("The command expired")
+            .data
+            .unwrap()
+    }
+}
+
+impl Drop for Network {
+    fn drop(&mut self) {
+        // Stop Tendermint first, then the ABCI and the application.
+        for child in self.processes.iter_mut().rev() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+#[test]
+#[ignore = "needs tendermint and the application binaries"]
+fn ledger_send() {
+    let network = Network::start(app_bin("MANY_LEDGER_BIN", "many-ledger"), |root| {
+        vec![
+            "--pem".to_string(),
+            repo_path("keys/id3.pem").display().to_string(),
+            "--state".to_string(),
+            repo_path("staging/ledger_state.json5")
+                .display()
+                .to_string(),
+            "--persistent".to_string(),
+            root.join("ledger.db").display().to_string(),
+        ]
+    });
+    let sender = identity("keys/id1.pem");
+    let receiver = identity("keys/id2.pem").address();
+    let mfx = Symbol::from_str("mqbfbahksdwaqeenayy2gxke32hgb7aq4ao4wt745lsfs6wiaaaaqnz").unwrap();
+
+    let balance = |account: Address| {
+        LedgerClient::new(network.client(AnonymousIdentity))
+            .balance(BalanceArgs {
+                account: Some(account),
+                symbols: Some(vec![mfx].into()),
+                height: None,
+            })
+            .unwrap()
+            .balances
+            .get(&mfx)
+            .cloned()
+            .unwrap_or_default()
+    };
+    let before = balance(sender.address());
+
+    network.command(
+        sender.clone(),
+        "ledger.send",
+        SendArgs {
+            from: None,
+            to: receiver,
+            amount: 1000u16.into(),
+            symbol: mfx,
+            memo: None,
+            reference: None,
+        },
+    );
+
+    assert_eq!(balance(receiver), TokenAmount::from(1000u16));
+    assert_eq!(balance(sender.address()), before - 1000u16);
+}
+
+#[test]
+#[ignore = "needs tendermint and the application binaries"]
+fn kvstore_put_get_with_proof() {
+    let network = Network::start(app_bin("MANY_KVSTORE_BIN", "many-kvstore"), |root| {
+        vec![
+            "--pem".to_string(),
+            repo_path("keys/id3.pem").display().to_string(),
+            "--state".to_string(),
+            repo_path("staging/kvstore_state.json5")
+                .display()
+                .to_string(),
+            "--persistent".to_string(),
+            root.join("kvstore.db").display().to_string(),
+        ]
+    });
+    let owner = identity("keys/id1.pem");
+
+    network.command(
+        owner.clone(),
+        "kvstore.put",
+        PutArgs {
+            key: b"e2e".to_vec().into(),
+            value: b"value".to_vec().into(),
+            alternative_owner: None,
+            roles: None,
+        },
+    );
+
+    let client = network.client(AnonymousIdentity);
+    let request = RequestMessageBuilder::default()
+        .method("kvstore.get".to_string())
+        .data(
+            minicbor::to_vec(GetArgs {
+                key: b"e2e".to_vec().into(),
+            })
+            .unwrap(),
+        )
+        .build()
+        .unwrap()
+        .with_attribute(PROOF);
+    let ResponseMessage {
+        data, attributes, ..
+    } = client.send_message(request).unwrap();
+
+    let returns: GetReturns = minicbor::decode(&data.unwrap()).unwrap();
+    assert_eq!(returns.value, Some(b"value".to_vec().into()));
+
+    // The proof covers the stored value.
+    let argument = &attributes
+        .get_attribute(PROOF.id)
+        .expect("The response should have a proof")
+        .arguments()[0];
+    let proof: Proof = minicbor::decode(&minicbor::to_vec(argument).unwrap()).unwrap();
+    assert!(proof.operations.iter().any(|op| matches!(
+        op,
+        ProofOperation::KeyValuePair(_, value) if Vec::from(value.clone()) == b"value"
+    )));
+}
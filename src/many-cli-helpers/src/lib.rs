@@ -1,8 +1,11 @@
 use std::os::unix::ffi::OsStrExt;
+use std::str::FromStr;
 use tracing::metadata::LevelFilter;
-use tracing_subscriber::fmt::Subscriber;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{fmt, reload, Registry};
 
 pub mod error;
+pub mod verifiers;
 
 #[derive(clap::ArgEnum, Clone, Debug)]
 enum LogStrategy {
@@ -45,6 +48,23 @@ impl Verbosity {
     }
 }
 
+/// A handle to change the level of the logs after they were initialized.
+#[derive(Clone, Debug)]
+pub struct LogLevelHandle(reload::Handle<LevelFilter, Registry>);
+
+impl LogLevelHandle {
+    /// Set the level of the logs (e.g. `"debug"`) and return the previous one.
+    pub fn set(&self, level: &str) -> Result<String, String> {
+        let level = LevelFilter::from_str(level).map_err(|e| e.to_string())?;
+        let previous = self
+            .0
+            .clone_current()
+            .ok_or_else(|| "Logging was not initialized.".to_string())?;
+        self.0.reload(level).map_err(|e| e.to_string())?;
+        Ok(previous.to_string().to_lowercase())
+    }
+}
+
 #[derive(clap::Args, Debug, Clone)]
 pub struct CommonCliFlags {
     #[clap(flatten)]
@@ -56,13 +76,15 @@ pub struct CommonCliFlags {
 }
 
 impl CommonCliFlags {
-    pub fn init_logging(&self) -> Result<(), String> {
-        let subscriber = Subscriber::builder().with_max_level(self.verbosity.level());
+    pub fn init_logging(&self) -> Result<LogLevelHandle, String> {
+        let (filter, handle) = reload::Layer::new(self.verbosity.level());
+        let subscriber = tracing_subscriber::registry().with(filter);
 
         match self.logmode {
             LogStrategy::Terminal => {
-                let subscriber = subscriber.with_writer(std::io::stderr);
-                subscriber.init();
+                subscriber
+                    .with(fmt::layer().with_writer(std::io::stderr))
+                    .init();
             }
             LogStrategy::Syslog => {
                 let exe_path = std::env::current_exe().map_err(|e| e.to_string())?;
@@ -73,12 +95,13 @@ impl CommonCliFlags {
                 let syslog = syslog_tracing::Syslog::new(identity, options, facility)
                     .ok_or_else(|| "Could not create syslog logger.".to_string())?;
 
-                let subscriber = subscriber.with_ansi(false).with_writer(syslog);
-                subscriber.init();
+                subscriber
+                    .with(fmt::layer().with_ansi(false).with_writer(syslog))
+                    .init();
                 log_panics::init();
             }
         };
 
-        Ok(())
+        Ok(LogLevelHandle(handle))
     }
 }
@@ -0,0 +1,214 @@
+//! The verifiers of the requests of a server, built from a JSON5 file instead
+//! of a hardcoded tuple, e.g.
+//!
+//! ```json5
+//! {
+//!   anonymous: false,
+//!   algorithms: ["ed25519"],
+//!   webauthn: false,
+//!   allow_addrs: ["mahek5lid7ek7ckhq7j77nfwgk3vkspnyppm2u467ne5mwiqys"],
+//! }
+//! ```
+//!
+//! Missing fields keep their default, which accepts all requests that have a
+//! valid signature.
+use coset::{CoseKey, CoseSign, CoseSign1};
+use many_error::ManyError;
+use many_identity::cose::{keyset_from_cose_sign1, signer_key};
+use many_identity::verifiers::AnonymousVerifier;
+use many_identity::{Address, Verifier};
+use many_identity_dsa::{ecdsa, ed25519};
+use many_identity_webauthn::WebAuthnVerifier;
+use many_protocol::ManyUrl;
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// The algorithms of the keys which can sign requests.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyAlgorithm {
+    Ed25519,
+    Ecdsa,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct VerifierConfig {
+    /// Accept anonymous requests.
+    pub anonymous: bool,
+
+    /// The algorithms of the keys accepted for signed requests.
+    pub algorithms: BTreeSet<KeyAlgorithm>,
+
+    /// Accept requests signed by WebAuthn authenticators, from the origins
+    /// allowed by the server.
+    pub webauthn: bool,
+
+    /// Only accept requests signed by these addresses. Anonymous requests
+    /// are still accepted if `anonymous` is true.
+    pub allow_addrs: Option<BTreeSet<Address>>,
+}
+
+impl Default for VerifierConfig {
+    fn default() -> Self {
+        Self {
+            anonymous: true,
+            algorithms: BTreeSet::from([KeyAlgorithm::Ed25519, KeyAlgorithm::Ecdsa]),
+            webauthn: true,
+            allow_addrs: None,
+        }
+    }
+}
+
+impl VerifierConfig {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Could not read {}: {e}", path.display()))?;
+        json5::from_str(&content).map_err(|e| format!("Invalid verifier config: {e}"))
+    }
+
+    /// The verifier of the requests of a server. `allow_origin` are the
+    /// origins accepted for WebAuthn requests.
+    pub fn build(&self, allow_origin: Option<Vec<ManyUrl>>) -> VerifierChain {
+        let mut signed: Vec<Box<dyn Verifier>> = Vec::new();
+        if !self.algorithms.is_empty() {
+            signed.push(Box::new(KeyVerifier(self.algorithms.clone())));
+        }
+        if self.webauthn {
+            signed.push(Box::new(WebAuthnVerifier::new(allow_origin)));
+        }
+
+        let mut verifiers: Vec<Box<dyn Verifier>> = Vec::new();
+        if self.anonymous {
+            verifiers.push(Box::new(AnonymousVerifier));
+        }
+        match &self.allow_addrs {
+            Some(addresses) => verifiers.push(Box::new(AllowlistVerifier {
+                inner: VerifierChain(signed),
+                addresses: addresses.clone(),
+            })),
+            None => verifiers.extend(signed),
+        }
+        VerifierChain(verifiers)
+    }
+}
+
+/// The flags of the server binaries to configure their verifiers.
+#[derive(clap::Args, Debug, Clone)]
+pub struct VerifierFlags {
+    /// A JSON5 file configuring which requests are accepted: anonymous
+    /// requests, key algorithms, WebAuthn and allowed addresses. By default
+    /// all requests with a valid signature are accepted.
+    #[clap(long)]
+    verifier_config: Option<PathBuf>,
+}
+
+impl VerifierFlags {
+    pub fn config(&self) -> Result<VerifierConfig, String> {
+        self.verifier_config
+            .as_deref()
+            .map_or_else(|| Ok(VerifierConfig::default()), VerifierConfig::load)
+    }
+}
+
+/// Verifiers tried in order, like a tuple of verifiers but built at runtime.
+pub struct VerifierChain(Vec<Box<dyn Verifier>>);
+
+impl Verifier for VerifierChain {
+    fn verify_1(&self, envelope: &CoseSign1) -> Result<Address, ManyError> {
+        let mut errs = Vec::new();
+        for verifier in &self.0 {
+            match verifier.verify_1(envelope) {
+                Ok(address) => return Ok(address),
+                Err(e) => errs.push(e.to_string()),
+            }
+        }
+        Err(ManyError::could_not_verify_signature(errs.join(", ")))
+    }
+
+    fn verify_multi(&self, envelope: &CoseSign, index: usize) -> Result<Address, ManyError> {
+        let mut errs = Vec::new();
+        for verifier in &self.0 {
+            match verifier.verify_multi(envelope, index) {
+                Ok(address) => return Ok(address),
+                Err(e) => errs.push(e.to_string()),
+            }
+        }
+        Err(ManyError::could_not_verify_signature(errs.join(", ")))
+    }
+}
+
+/// Verifies requests signed by a key of the keyset of the envelope, for some
+/// algorithms only.
+struct KeyVerifier(BTreeSet<KeyAlgorithm>);
+
+impl KeyVerifier {
+    /// Verify with the first allowed algorithm matching the key.
+    fn verify_with(
+        &self,
+        key: &CoseKey,
+        verify: impl Fn(&dyn Verifier) -> Result<Address, ManyError>,
+    ) -> Result<Address, ManyError> {
+        for algorithm in &self.0 {
+            let result = match algorithm {
+                KeyAlgorithm::Ed25519 => {
+                    ed25519::Ed25519Verifier::from_key(key).map(|v| verify(&v))
+                }
+                KeyAlgorithm::Ecdsa => ecdsa::EcDsaVerifier::from_key(key).map(|v| verify(&v)),
+            };
+            if let Ok(address) = result {
+                return address;
+            }
+        }
+        Err(ManyError::unknown("Algorithm not allowed."))
+    }
+}
+
+impl Verifier for KeyVerifier {
+    fn verify_1(&self, envelope: &CoseSign1) -> Result<Address, ManyError> {
+        let keyid = &envelope.protected.header.key_id;
+        let keyset = keyset_from_cose_sign1(envelope)
+            .ok_or_else(|| ManyError::unknown("Could not find keyset in headers."))?;
+        let key = keyset
+            .0
+            .iter()
+            .find(|key| key.key_id.eq(keyid))
+            .ok_or_else(|| ManyError::unknown("Could not find the key in keyset."))?;
+
+        self.verify_with(key, |v| v.verify_1(envelope))
+    }
+
+    fn verify_multi(&self, envelope: &CoseSign, index: usize) -> Result<Address, ManyError> {
+        let key = signer_key(envelope, index)?;
+        self.verify_with(&key, |v| v.verify_multi(envelope, index))
+    }
+}
+
+/// Only accepts the addresses of a list, once verified by another verifier.
+struct AllowlistVerifier {
+    inner: VerifierChain,
+    addresses: BTreeSet<Address>,
+}
+
+impl AllowlistVerifier {
+    fn allowed(&self, address: Address) -> Result<Address, ManyError> {
+        if self.addresses.contains(&address) {
+            Ok(address)
+        } else {
+            Err(ManyError::unknown(format!(
+                "Address {address} is not allowed."
+            )))
+        }
+    }
+}
+
+impl Verifier for AllowlistVerifier {
+    fn verify_1(&self, envelope: &CoseSign1) -> Result<Address, ManyError> {
+        self.allowed(self.inner.verify_1(envelope)?)
+    }
+
+    fn verify_multi(&self, envelope: &CoseSign, index: usize) -> Result<Address, ManyError> {
+        self.allowed(self.inner.verify_multi(envelope, index)?)
+    }
+}
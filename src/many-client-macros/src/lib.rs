@@ -41,17 +41,19 @@ pub fn many_client(attr: TokenStream, input: TokenStream) -> TokenStream {
         let func = func.to_token_stream();
         let method: syn::TraitItemFn =
             parse2(func)?;
+        let blocking_method = method.sig.clone();
         let mut method = method.sig;
         method.asyncness = parse_quote! { async };
         let mut args_iter = method.inputs.iter();
         let _self_arg = args_iter.next().ok_or_else(|| syn::Error::new(method.span(), "Should have a &self argument"))?;
         let args_param = args_iter.next();
-        let args_var = if let Some(FnArg::Typed(args)) = args_param {
+        let (args_var, args_pass) = if let Some(FnArg::Typed(args)) = args_param {
             let args = &args.pat;
-            quote! { #args }
+            (quote! { #args }, quote! { #args })
         } else {
-            quote! { () }
+            (quote! { () }, quote! {})
         };
+        let ident = &method.ident;
         let server_method = if let Some(namespace) = namespace {
             format!("{}.{}", namespace.value(), method.ident)
         } else {
@@ -64,22 +66,32 @@ pub fn many_client(attr: TokenStream, input: TokenStream) -> TokenStream {
                 minicbor::decode(&response).map_err(many_error::ManyError::deserialization_error)
             }
         };
-        Ok(q.into_token_stream())
-    }).try_fold(vec![], |mut acc, curr: syn::Result<TokenStream2>| {
+        let blocking = quote! {
+            pub #blocking_method {
+                crate::client::blocking::block_on(self.0.#ident(#args_pass))
+            }
+        };
+        Ok((q.into_token_stream(), blocking))
+    }).try_fold((vec![], vec![]), |mut acc, curr: syn::Result<(TokenStream2, TokenStream2)>| {
         match curr {
-            Ok(c) => acc.push(c),
+            Ok((c, b)) => {
+                acc.0.push(c);
+                acc.1.push(b);
+            }
             Err(e) => return Err(e)
         }
         Ok(acc)
     });
-    let methods_vec = match methods_vec {
+    let (methods_vec, blocking_vec) = match methods_vec {
         Ok(v) => v,
         Err(e) => return e.to_compile_error().into(),
     };
-    let methods_iter = methods_vec.into_iter();
 
-    let methods = TokenStream2::from_iter(methods_iter);
+    let methods = TokenStream2::from_iter(methods_vec);
+    let blocking_methods = TokenStream2::from_iter(blocking_vec);
 
+    // The blocking client of the same name wraps this one; it is declared in
+    // the `blocking` module.
     let q = quote! {
         impl<I: many_identity::Identity> #r#type<I> {
             #methods
@@ -88,6 +100,14 @@ pub fn many_client(attr: TokenStream, input: TokenStream) -> TokenStream {
                 Self(client)
             }
         }
+
+        impl<I: many_identity::Identity> crate::client::blocking::#r#type<I> {
+            #blocking_methods
+
+            pub fn new(client: crate::client::blocking::ManyClient<I>) -> Self {
+                Self(#r#type::new(client.into_async()))
+            }
+        }
     };
     q.into()
 }
@@ -1,22 +1,30 @@
 pub mod base;
 pub mod blockchain;
 pub mod blocking;
+pub mod events;
 pub mod ledger;
+pub mod transport;
 
 pub use ledger::LedgerClient;
 
-use coset::{CoseSign1, TaggedCborSerializable};
+use coset::CoseSign1;
 use many_error::ManyError;
 use many_identity::verifiers::AnonymousVerifier;
 use many_identity::{verifiers, Address, Identity};
 use many_identity_dsa::CoseKeyVerifier;
 use many_modules::base::Status;
+use many_modules::r#async::{AsyncToken, StatusArgs, StatusReturn};
 use many_protocol::{
-    encode_cose_sign1_from_request, RequestMessage, RequestMessageBuilder, ResponseMessage,
+    encode_cose_sign1_from_request, negotiate_version, RequestMessage, RequestMessageBuilder,
+    ResponseMessage, PROTOCOL_VERSION, SUPPORTED_VERSIONS,
 };
-use minicbor::Encode;
+use many_types::attributes::{Attribute, AttributeId};
+use many_types::{DelegationChain, NetworkId, Warning};
+use minicbor::{Decode, Encode};
 use reqwest::{IntoUrl, Url};
 use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+use transport::{send_envelope_with, Transport};
 
 #[derive(Clone)]
 pub struct ManyClient<I: Identity> {
@@ -24,6 +32,11 @@ pub struct ManyClient<I: Identity> {
     to: Option<Address>,
     url: Url,
     verifier: (AnonymousVerifier, CoseKeyVerifier),
+    status: Option<Status>,
+    version: u8,
+    network_id: Option<NetworkId>,
+    delegation: Option<(Address, Attribute)>,
+    transport: Arc<dyn Transport>,
 }
 
 impl<I: Identity + Debug> Debug for ManyClient<I> {
@@ -32,47 +45,16 @@ impl<I: Identity + Debug> Debug for ManyClient<I> {
             .field("id", &self.identity)
             .field("to", &self.to)
             .field("url", &self.url)
+            .field("transport", &self.transport)
             .finish()
     }
 }
 
 pub async fn send_envelope<S: IntoUrl>(url: S, message: CoseSign1) -> Result<CoseSign1, ManyError> {
-    let bytes = message
-        .to_tagged_vec()
-        .map_err(|_| ManyError::internal_server_error())?;
-    let len = bytes.len();
-    tracing::debug!("Message length in bytes: {}", len);
-
-    let client = reqwest::Client::new();
-    tracing::debug!("request {}", hex::encode(&bytes));
-    let response = client
-        .post(url)
-        .body(bytes)
-        .send()
-        .await
+    let url = url
+        .into_url()
         .map_err(|e| ManyError::unexpected_transport_error(e.to_string()))?;
-    // The HTTP request handler can return errors if the request invalid.
-    match response.status().as_u16() {
-        413 => {
-            return Err(ManyError::unexpected_transport_error(format!(
-                "413: Content Too Large : {len} bytes"
-            )))
-        }
-        500 => {
-            return Err(ManyError::unexpected_transport_error(
-                "500: Internal Server Error".to_string(),
-            ))
-        }
-        _ => {}
-    }
-    let body = response
-        .bytes()
-        .await
-        .map_err(|e| ManyError::unexpected_transport_error(e.to_string()))?;
-    let bytes = body.to_vec();
-    tracing::debug!("Response body length: {}", bytes.len());
-    CoseSign1::from_tagged_slice(&bytes)
-        .map_err(|e| ManyError::deserialization_error(e.to_string()))
+    send_envelope_with(transport::default_transport().as_ref(), &url, message).await
 }
 
 impl<I: Identity> ManyClient<I> {
@@ -84,15 +66,94 @@ impl<I: Identity> ManyClient<I> {
             to: Some(to),
             url: url.into_url().map_err(|e| e.to_string())?,
             verifier,
+            status: None,
+            version: PROTOCOL_VERSION,
+            network_id: None,
+            delegation: None,
+            transport: transport::default_transport(),
         })
     }
 
+    /// Send the requests of this client with another transport, e.g. to use
+    /// another HTTP client or runtime.
+    pub fn with_transport(mut self, transport: impl Transport + 'static) -> Self {
+        self.transport = Arc::new(transport);
+        self
+    }
+
+    /// Bind the requests of this client to a network, so they are refused by
+    /// servers of other networks.
+    pub fn with_network_id(mut self, id: NetworkId) -> Self {
+        self.network_id = Some(id);
+        self
+    }
+
+    /// Send the requests of this client on behalf of the delegator of a
+    /// delegation chain, which must delegate to the identity of the client.
+    pub fn with_delegation(mut self, chain: DelegationChain) -> Result<Self, ManyError> {
+        self.delegation = Some((chain.delegator()?, chain.to_attribute()?));
+        Ok(self)
+    }
+
+    /// Create a client and verify the server before sending any message: the
+    /// identity reported by the server's status must be `to` (unless `to` is
+    /// anonymous), and the server must support all the required attributes.
+    /// The status is cached and available from [ManyClient::cached_status].
+    /// If the server advertises a network ID, requests are bound to it.
+    pub async fn new_verified<S: IntoUrl>(
+        url: S,
+        to: Address,
+        identity: I,
+        required_attributes: impl IntoIterator<Item = AttributeId>,
+    ) -> Result<Self, ManyError> {
+        let mut client = Self::new(url, to, identity).map_err(ManyError::unknown)?;
+        let status = client.status().await?;
+
+        if !to.is_anonymous() && status.identity != to {
+            return Err(ManyError::unknown_destination(
+                to.to_string(),
+                status.identity.to_string(),
+            ));
+        }
+        if let Some(id) = required_attributes
+            .into_iter()
+            .find(|id| !status.attributes.has_id(*id))
+        {
+            return Err(ManyError::attribute_not_found(id));
+        }
+
+        client.network_id = status.network_id.clone();
+        client.status = Some(status);
+        Ok(client)
+    }
+
+    /// Use the highest protocol version supported by both the client and the
+    /// server for the following requests, and return it.
+    pub async fn negotiate_version(&mut self) -> Result<u8, ManyError> {
+        let status = self.status().await?;
+        let theirs = status
+            .supported_versions
+            .unwrap_or_else(|| vec![status.version]);
+
+        self.version = negotiate_version(SUPPORTED_VERSIONS, &theirs).ok_or_else(|| {
+            ManyError::unknown(format!(
+                "No protocol version in common with the server. Server supports {theirs:?}, client supports {SUPPORTED_VERSIONS:?}."
+            ))
+        })?;
+        Ok(self.version)
+    }
+
+    /// The status of the server, if it was fetched when creating the client.
+    pub fn cached_status(&self) -> Option<&Status> {
+        self.status.as_ref()
+    }
+
     pub async fn send_message(
         &self,
         message: RequestMessage,
     ) -> Result<ResponseMessage, ManyError> {
         let cose = encode_cose_sign1_from_request(message, &self.identity).unwrap();
-        let cose_sign1 = send_envelope(self.url.clone(), cose).await?;
+        let cose_sign1 = send_envelope_with(self.transport.as_ref(), &self.url, cose).await?;
 
         ResponseMessage::decode_and_verify(&cose_sign1, &self.verifier)
     }
@@ -102,6 +163,20 @@ impl<I: Identity> ManyClient<I> {
         method: M,
         argument: &[u8],
     ) -> Result<ResponseMessage, ManyError>
+    where
+        M: Into<String>,
+    {
+        self.call_raw_with_id(method, argument, None).await
+    }
+
+    /// Send a request with a correlation ID chosen by the caller. The server
+    /// returns the same ID in the response and logs it alongside the request.
+    pub async fn call_raw_with_id<M>(
+        &self,
+        method: M,
+        argument: &[u8],
+        id: Option<u64>,
+    ) -> Result<ResponseMessage, ManyError>
     where
         M: Into<String>,
     {
@@ -111,19 +186,32 @@ impl<I: Identity> ManyClient<I> {
         let mut builder = RequestMessageBuilder::default();
 
         builder
-            .version(1)
-            .from(self.identity.address())
+            .version(self.version)
+            .from(
+                self.delegation
+                    .as_ref()
+                    .map_or_else(|| self.identity.address(), |(from, _)| *from),
+            )
             .method(method.into())
             .data(argument.to_vec())
             .nonce(nonce.to_vec());
+        if let Some(id) = id {
+            builder.id(id);
+        }
 
-        let message: RequestMessage = if let Some(to) = self.to {
+        let mut message: RequestMessage = if let Some(to) = self.to {
             builder.to(to)
         } else {
             &mut builder
         }
         .build()
         .map_err(|_| ManyError::internal_server_error())?;
+        if let Some(network_id) = &self.network_id {
+            message = message.with_attribute(network_id.clone().into());
+        }
+        if let Some((_, attribute)) = &self.delegation {
+            message = message.with_attribute(attribute.clone());
+        }
 
         self.send_message(message).await
     }
@@ -139,6 +227,23 @@ impl<I: Identity> ManyClient<I> {
         self.call_raw(method, bytes.as_slice()).await
     }
 
+    pub async fn call_with_id<M, A>(
+        &self,
+        method: M,
+        argument: A,
+        id: u64,
+    ) -> Result<ResponseMessage, ManyError>
+    where
+        M: Into<String>,
+        A: Encode<()>,
+    {
+        let bytes: Vec<u8> = minicbor::to_vec(argument)
+            .map_err(|e| ManyError::serialization_error(e.to_string()))?;
+
+        self.call_raw_with_id(method, bytes.as_slice(), Some(id))
+            .await
+    }
+
     pub async fn call_<M, A>(&self, method: M, argument: A) -> Result<Vec<u8>, ManyError>
     where
         M: Into<String>,
@@ -147,6 +252,43 @@ impl<I: Identity> ManyClient<I> {
         self.call(method, argument).await?.data
     }
 
+    /// Like [ManyClient::call_], but also returns the warnings the server
+    /// attached to a successful response.
+    pub async fn call_with_warnings<M, A>(
+        &self,
+        method: M,
+        argument: A,
+    ) -> Result<(Vec<u8>, Vec<Warning>), ManyError>
+    where
+        M: Into<String>,
+        A: Encode<()>,
+    {
+        let response = self.call(method, argument).await?;
+        let warnings = response.warnings()?;
+        Ok((response.data?, warnings))
+    }
+
+    /// Like [ManyClient::call_], but also decodes the result.
+    pub async fn call_typed<M, A, R>(&self, method: M, argument: A) -> Result<R, ManyError>
+    where
+        M: Into<String>,
+        A: Encode<()>,
+        R: for<'b> Decode<'b, ()>,
+    {
+        let response = self.call_(method, argument).await?;
+        minicbor::decode(&response).map_err(ManyError::deserialization_error)
+    }
+
+    /// The status of a request which returned an async token.
+    pub async fn async_status(&self, token: AsyncToken) -> Result<StatusReturn, ManyError> {
+        self.call_typed("async.status", StatusArgs { token }).await
+    }
+
+    /// Decode and verify the response of a completed async request.
+    pub fn async_response(&self, response: &CoseSign1) -> Result<ResponseMessage, ManyError> {
+        ResponseMessage::decode_and_verify(response, &self.verifier)
+    }
+
     pub async fn status(&self) -> Result<Status, ManyError> {
         let response = self.call_("status", ()).await?;
 
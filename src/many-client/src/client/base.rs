@@ -2,7 +2,7 @@ use many_client_macros::many_client;
 use many_error::ManyError;
 pub use many_identity::Identity;
 use many_modules::base::HeartbeatReturn;
-pub use many_modules::base::{Endpoints, Status};
+pub use many_modules::base::{DescribeReturn, Endpoints, Status};
 
 use crate::ManyClient;
 
@@ -11,6 +11,7 @@ trait BaseClientTrait {
     fn status(&self) -> Result<Status, ManyError>;
     fn heartbeat(&self) -> Result<HeartbeatReturn, ManyError>;
     fn endpoints(&self) -> Result<Endpoints, ManyError>;
+    fn describe(&self) -> Result<DescribeReturn, ManyError>;
 }
 
 #[derive(Debug, Clone)]
@@ -1,10 +1,15 @@
 use many_error::ManyError;
 use many_identity::{Address, Identity};
 use many_modules::base::Status;
+use many_modules::r#async::{AsyncToken, StatusReturn};
 use many_protocol::{RequestMessage, ResponseMessage};
-use minicbor::Encode;
+use many_types::attributes::AttributeId;
+use many_types::{DelegationChain, NetworkId, Warning};
+use minicbor::{Decode, Encode};
 use reqwest::IntoUrl;
+use std::time::{Duration, Instant};
 
+use crate::client::transport::Transport;
 use crate::ManyClient as AsyncClient;
 
 #[derive(Debug, Clone)]
@@ -12,6 +17,28 @@ pub struct ManyClient<I: Identity> {
     client: AsyncClient<I>,
 }
 
+/// Blocking version of [crate::client::base::BaseClient].
+#[derive(Debug, Clone)]
+pub struct BaseClient<I: Identity>(pub(crate) crate::client::base::BaseClient<I>);
+
+/// Blocking version of [crate::client::blockchain::BlockchainClient].
+#[derive(Debug, Clone)]
+pub struct BlockchainClient<I: Identity>(pub(crate) crate::client::blockchain::BlockchainClient<I>);
+
+/// Blocking version of [crate::client::events::EventsClient].
+#[derive(Debug, Clone)]
+pub struct EventsClient<I: Identity>(pub(crate) crate::client::events::EventsClient<I>);
+
+/// Blocking version of [crate::LedgerClient].
+#[derive(Debug, Clone)]
+pub struct LedgerClient<I: Identity>(pub(crate) crate::client::ledger::LedgerClient<I>);
+
+/// How often [ManyClient::wait_async] polls the server.
+const ASYNC_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Run a future to completion. With the `tokio` feature, this uses the
+/// current tokio runtime, or a new one.
+#[cfg(feature = "tokio")]
 pub fn block_on<F>(future: F) -> F::Output
 where
     F: std::future::Future,
@@ -25,12 +52,72 @@ where
     }
 }
 
+/// Run a future to completion on the current thread. Without the `tokio`
+/// feature, clients use a blocking transport, whose futures don't need a
+/// runtime.
+#[cfg(not(feature = "tokio"))]
+pub fn block_on<F>(future: F) -> F::Output
+where
+    F: std::future::Future,
+{
+    futures_lite::future::block_on(future)
+}
+
 impl<I: Identity> ManyClient<I> {
     pub fn new<S: IntoUrl>(url: S, to: Address, identity: I) -> Result<Self, String> {
         let client = AsyncClient::new(url, to, identity)?;
         Ok(Self { client })
     }
 
+    pub fn new_verified<S: IntoUrl>(
+        url: S,
+        to: Address,
+        identity: I,
+        required_attributes: impl IntoIterator<Item = AttributeId>,
+    ) -> Result<Self, ManyError> {
+        let client = block_on(AsyncClient::new_verified(
+            url,
+            to,
+            identity,
+            required_attributes,
+        ))?;
+        Ok(Self { client })
+    }
+
+    /// The async client this wraps.
+    pub fn into_async(self) -> AsyncClient<I> {
+        self.client
+    }
+
+    /// See [AsyncClient::with_transport].
+    pub fn with_transport(self, transport: impl Transport + 'static) -> Self {
+        Self {
+            client: self.client.with_transport(transport),
+        }
+    }
+
+    /// See [AsyncClient::with_network_id].
+    pub fn with_network_id(self, id: NetworkId) -> Self {
+        Self {
+            client: self.client.with_network_id(id),
+        }
+    }
+
+    /// See [AsyncClient::with_delegation].
+    pub fn with_delegation(self, chain: DelegationChain) -> Result<Self, ManyError> {
+        Ok(Self {
+            client: self.client.with_delegation(chain)?,
+        })
+    }
+
+    pub fn negotiate_version(&mut self) -> Result<u8, ManyError> {
+        block_on(self.client.negotiate_version())
+    }
+
+    pub fn cached_status(&self) -> Option<&Status> {
+        self.client.cached_status()
+    }
+
     pub fn send_message(&self, message: RequestMessage) -> Result<ResponseMessage, ManyError> {
         block_on(self.client.send_message(message))
     }
@@ -42,6 +129,18 @@ impl<I: Identity> ManyClient<I> {
         block_on(self.client.call_raw(method, argument))
     }
 
+    pub fn call_raw_with_id<M>(
+        &self,
+        method: M,
+        argument: &[u8],
+        id: Option<u64>,
+    ) -> Result<ResponseMessage, ManyError>
+    where
+        M: Into<String>,
+    {
+        block_on(self.client.call_raw_with_id(method, argument, id))
+    }
+
     pub fn call<M, A>(&self, method: M, argument: A) -> Result<ResponseMessage, ManyError>
     where
         M: Into<String>,
@@ -50,6 +149,19 @@ impl<I: Identity> ManyClient<I> {
         block_on(self.client.call(method, argument))
     }
 
+    pub fn call_with_id<M, A>(
+        &self,
+        method: M,
+        argument: A,
+        id: u64,
+    ) -> Result<ResponseMessage, ManyError>
+    where
+        M: Into<String>,
+        A: Encode<()>,
+    {
+        block_on(self.client.call_with_id(method, argument, id))
+    }
+
     pub fn call_<M, A>(&self, method: M, argument: A) -> Result<Vec<u8>, ManyError>
     where
         M: Into<String>,
@@ -58,6 +170,56 @@ impl<I: Identity> ManyClient<I> {
         block_on(self.client.call_(method, argument))
     }
 
+    pub fn call_with_warnings<M, A>(
+        &self,
+        method: M,
+        argument: A,
+    ) -> Result<(Vec<u8>, Vec<Warning>), ManyError>
+    where
+        M: Into<String>,
+        A: Encode<()>,
+    {
+        block_on(self.client.call_with_warnings(method, argument))
+    }
+
+    pub fn call_typed<M, A, R>(&self, method: M, argument: A) -> Result<R, ManyError>
+    where
+        M: Into<String>,
+        A: Encode<()>,
+        R: for<'b> Decode<'b, ()>,
+    {
+        block_on(self.client.call_typed(method, argument))
+    }
+
+    pub fn async_status(&self, token: AsyncToken) -> Result<StatusReturn, ManyError> {
+        block_on(self.client.async_status(token))
+    }
+
+    /// Poll the status of an async token until its response is available, or
+    /// `timeout` passed. Returns `None` if the token expired first.
+    pub fn wait_async(
+        &self,
+        token: AsyncToken,
+        timeout: Duration,
+    ) -> Result<Option<ResponseMessage>, ManyError> {
+        let start = Instant::now();
+        loop {
+            match self.async_status(token.clone())? {
+                StatusReturn::Done { response } => {
+                    return self.client.async_response(&response).map(Some)
+                }
+                StatusReturn::Expired => return Ok(None),
+                _ => {}
+            }
+            if start.elapsed() + ASYNC_POLL_INTERVAL > timeout {
+                return Err(ManyError::unexpected_transport_error(
+                    "Timed out waiting for the async response.",
+                ));
+            }
+            std::thread::sleep(ASYNC_POLL_INTERVAL);
+        }
+    }
+
     pub fn status(&self) -> Result<Status, ManyError> {
         block_on(self.client.status())
     }
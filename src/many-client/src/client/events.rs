@@ -0,0 +1,72 @@
+use futures_lite::{stream, Stream, StreamExt};
+use many_client_macros::many_client;
+use many_error::ManyError;
+pub use many_identity::Identity;
+pub use many_modules::events::{EventLog, InfoArgs, InfoReturn, ListArgs, ListReturns};
+
+use crate::ManyClient;
+
+#[many_client(EventsClient, "events")]
+trait EventsClientTrait {
+    fn info(&self, args: InfoArgs) -> Result<InfoReturn, ManyError>;
+    fn list(&self, args: ListArgs) -> Result<ListReturns, ManyError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct EventsClient<I: Identity>(ManyClient<I>);
+
+impl<I: Identity> EventsClient<I> {
+    /// List all the events matching the arguments, calling `events.list`
+    /// again with the cursor of each page. `count` is the size of the pages.
+    /// The stream ends after the first error.
+    pub fn list_stream(
+        &self,
+        args: ListArgs,
+    ) -> impl Stream<Item = Result<EventLog, ManyError>> + '_ {
+        stream::unfold(Some(args), move |args| async move {
+            let args = args?;
+            Some(match self.list(args.clone()).await {
+                Ok(ListReturns {
+                    events,
+                    next_cursor,
+                    ..
+                }) => {
+                    let next = next_cursor.map(|cursor| ListArgs {
+                        cursor: Some(cursor),
+                        ..args
+                    });
+                    (events.into_iter().map(Ok).collect::<Vec<_>>(), next)
+                }
+                Err(e) => (vec![Err(e)], None),
+            })
+        })
+        .flat_map(stream::iter)
+    }
+}
+
+impl<I: Identity> crate::client::blocking::EventsClient<I> {
+    /// Blocking version of [EventsClient::list_stream].
+    pub fn list_iter(
+        &self,
+        args: ListArgs,
+    ) -> impl Iterator<Item = Result<EventLog, ManyError>> + '_ {
+        let mut args = Some(args);
+        let mut page = Vec::new().into_iter();
+        std::iter::from_fn(move || loop {
+            if let Some(event) = page.next() {
+                return Some(Ok(event));
+            }
+            let current = args.take()?;
+            match self.list(current.clone()) {
+                Ok(returns) => {
+                    args = returns.next_cursor.map(|cursor| ListArgs {
+                        cursor: Some(cursor),
+                        ..current
+                    });
+                    page = returns.events.into_iter();
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        })
+    }
+}
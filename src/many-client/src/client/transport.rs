@@ -0,0 +1,120 @@
+//! How a client sends its envelopes. The client itself doesn't depend on an
+//! async runtime; only the transports do.
+use async_trait::async_trait;
+use coset::{CoseSign1, TaggedCborSerializable};
+use many_error::ManyError;
+use reqwest::Url;
+use std::fmt::Debug;
+
+#[async_trait]
+pub trait Transport: Debug + Send + Sync {
+    /// Send the bytes of an envelope to a server, and return the bytes of its
+    /// response.
+    async fn send(&self, url: &Url, bytes: Vec<u8>) -> Result<Vec<u8>, ManyError>;
+}
+
+/// The HTTP handler of a server returns these codes for requests it can't
+/// decode.
+fn check_status(status: u16, len: usize) -> Result<(), ManyError> {
+    match status {
+        413 => Err(ManyError::unexpected_transport_error(format!(
+            "413: Content Too Large : {len} bytes"
+        ))),
+        500 => Err(ManyError::unexpected_transport_error(
+            "500: Internal Server Error".to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// HTTP requests made by an async reqwest client, which must run in a tokio
+/// runtime.
+#[cfg(feature = "tokio")]
+#[derive(Clone, Debug, Default)]
+pub struct HttpTransport {
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "tokio")]
+#[async_trait]
+impl Transport for HttpTransport {
+    async fn send(&self, url: &Url, bytes: Vec<u8>) -> Result<Vec<u8>, ManyError> {
+        let len = bytes.len();
+        let response = self
+            .client
+            .post(url.clone())
+            .body(bytes)
+            .send()
+            .await
+            .map_err(|e| ManyError::unexpected_transport_error(e.to_string()))?;
+        check_status(response.status().as_u16(), len)?;
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| ManyError::unexpected_transport_error(e.to_string()))?;
+        Ok(body.to_vec())
+    }
+}
+
+/// HTTP requests made by a blocking reqwest client. Its futures complete
+/// without yielding, so they can be polled by any executor, but they block
+/// the thread meanwhile. This is meant for scripts and tools without an
+/// async runtime; it must not be used from within a tokio runtime.
+#[cfg(feature = "blocking")]
+#[derive(Clone, Debug, Default)]
+pub struct BlockingHttpTransport {
+    client: reqwest::blocking::Client,
+}
+
+#[cfg(feature = "blocking")]
+#[async_trait]
+impl Transport for BlockingHttpTransport {
+    async fn send(&self, url: &Url, bytes: Vec<u8>) -> Result<Vec<u8>, ManyError> {
+        let len = bytes.len();
+        let response = self
+            .client
+            .post(url.clone())
+            .body(bytes)
+            .send()
+            .map_err(|e| ManyError::unexpected_transport_error(e.to_string()))?;
+        check_status(response.status().as_u16(), len)?;
+        let body = response
+            .bytes()
+            .map_err(|e| ManyError::unexpected_transport_error(e.to_string()))?;
+        Ok(body.to_vec())
+    }
+}
+
+/// The transport of new clients: [HttpTransport] with the `tokio` feature,
+/// [BlockingHttpTransport] otherwise.
+pub fn default_transport() -> std::sync::Arc<dyn Transport> {
+    #[cfg(feature = "tokio")]
+    {
+        std::sync::Arc::new(HttpTransport::default())
+    }
+    #[cfg(all(feature = "blocking", not(feature = "tokio")))]
+    {
+        std::sync::Arc::new(BlockingHttpTransport::default())
+    }
+}
+
+#[cfg(not(any(feature = "tokio", feature = "blocking")))]
+compile_error!("many-client needs a transport: enable the `tokio` or `blocking` feature.");
+
+/// Send an envelope with a transport and decode the envelope of the response.
+pub async fn send_envelope_with(
+    transport: &dyn Transport,
+    url: &Url,
+    message: CoseSign1,
+) -> Result<CoseSign1, ManyError> {
+    let bytes = message
+        .to_tagged_vec()
+        .map_err(|_| ManyError::internal_server_error())?;
+    tracing::debug!("Message length in bytes: {}", bytes.len());
+    tracing::debug!("request {}", hex::encode(&bytes));
+
+    let bytes = transport.send(url, bytes).await?;
+    tracing::debug!("Response body length: {}", bytes.len());
+    CoseSign1::from_tagged_slice(&bytes)
+        .map_err(|e| ManyError::deserialization_error(e.to_string()))
+}
@@ -2,10 +2,9 @@
 
 use crate::allow_addrs::AllowAddrsModule;
 use clap::Parser;
-use many_identity::verifiers::AnonymousVerifier;
+use many_cli_helpers::verifiers::VerifierFlags;
 use many_identity::Address;
-use many_identity_dsa::{CoseKeyIdentity, CoseKeyVerifier};
-use many_identity_webauthn::WebAuthnVerifier;
+use many_identity_dsa::CoseKeyIdentity;
 use many_modules::compute;
 use many_protocol::ManyUrl;
 use many_server::transport::http::HttpServer;
@@ -60,6 +59,9 @@ struct Opts {
     #[clap(long)]
     allow_origin: Option<Vec<ManyUrl>>,
 
+    #[clap(flatten)]
+    verifier_flags: VerifierFlags,
+
     /// Path to a JSON file containing an array of MANY addresses
     /// Only addresses from this array will be able to execute commands, e.g., send, put, ...
     /// Any addresses will be able to execute queries, e.g., balance, get, ...
@@ -79,6 +81,7 @@ fn main() {
         persistent,
         clean,
         allow_origin,
+        verifier_flags,
         allow_addrs,
         akash_opt,
         ..
@@ -89,6 +92,9 @@ fn main() {
     let abci = false;
 
     common_flags.init_logging().unwrap();
+    let verifier_config = verifier_flags
+        .config()
+        .expect("Could not load the verifier config");
 
     debug!("{:?}", Opts::parse());
     info!(
@@ -135,11 +141,7 @@ fn main() {
     let many = ManyServer::simple(
         "many-compute",
         key,
-        (
-            AnonymousVerifier,
-            CoseKeyVerifier,
-            WebAuthnVerifier::new(allow_origin),
-        ),
+        verifier_config.build(allow_origin),
         Some(env!("CARGO_PKG_VERSION").to_string()),
     );
 
@@ -15,7 +15,7 @@ use many_types::compute::{
     Bids, ComputeListFilter, ComputeStatus, DeploymentInfo, DeploymentMeta, LeaseStatus,
     LeasesResponse, ProviderInfo, ServiceProtocol, ServiceStatus, TxLog,
 };
-use many_types::Timestamp;
+use many_types::BlockTime;
 use std::cmp::Ordering;
 use std::collections::{BTreeMap, HashMap};
 use std::io::Write;
@@ -654,8 +654,7 @@ impl ManyAbciModuleBackend for ComputeModuleImpl {
         );
 
         if let Some(time) = time {
-            let time = Timestamp::new(time)?;
-            self.storage.set_time(time);
+            self.storage.set_time(BlockTime::from_secs(time));
         }
 
         Ok(BeginBlockReturn {})
@@ -672,6 +671,7 @@ impl ManyAbciModuleBackend for ComputeModuleImpl {
         Ok(AbciInfo {
             height: storage.get_height(),
             hash: storage.hash().into(),
+            events_digest: None,
         })
     }
 
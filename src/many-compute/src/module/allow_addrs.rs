@@ -1,8 +1,9 @@
-use coset::CoseSign1;
+use coset::{CoseSign, CoseSign1};
 use many_error::ManyError;
 use many_identity::Address;
 use many_modules::{compute, ManyModule, ManyModuleInfo};
 use many_protocol::{RequestMessage, ResponseMessage};
+use many_types::BlockTime;
 use std::collections::BTreeSet;
 use std::fmt::{Debug, Formatter};
 
@@ -27,11 +28,34 @@ impl<T: compute::ComputeModuleBackend> ManyModule for AllowAddrsModule<T> {
         self.inner.validate(message, envelope)
     }
 
+    fn validate_multi(
+        &self,
+        message: &RequestMessage,
+        envelope: &CoseSign,
+        signers: &BTreeSet<Address>,
+    ) -> Result<(), ManyError> {
+        self.inner.validate_multi(message, envelope, signers)
+    }
+
     async fn execute(&self, message: RequestMessage) -> Result<ResponseMessage, ManyError> {
+        self.execute_with_block_time(message, None).await
+    }
+
+    async fn execute_with_block_time(
+        &self,
+        message: RequestMessage,
+        block_time: Option<BlockTime>,
+    ) -> Result<ResponseMessage, ManyError> {
         if !self.allow_addrs.contains(&message.from()) {
             return Err(ManyError::invalid_from_identity());
         }
 
-        self.inner.execute(message).await
+        self.inner
+            .execute_with_block_time(message, block_time)
+            .await
+    }
+
+    fn heartbeat(&self) -> Result<(), ManyError> {
+        self.inner.heartbeat()
     }
 }
@@ -5,7 +5,7 @@ use many_identity::Address;
 use many_modules::abci_backend::AbciCommitInfo;
 use many_modules::events::EventId;
 use many_types::compute::{ComputeStatus, DeploymentMeta};
-use many_types::{SortOrder, Timestamp};
+use many_types::{BlockTime, SortOrder};
 use merk::{BatchEntry, Op};
 use std::path::Path;
 
@@ -20,7 +20,7 @@ pub struct ComputeStorage {
     blockchain: bool,
 
     latest_event_id: EventId,
-    current_time: Option<Timestamp>,
+    current_time: Option<BlockTime>,
     current_hash: Option<Vec<u8>>,
     #[allow(dead_code)]
     next_subresource: u32,
@@ -36,7 +36,7 @@ impl std::fmt::Debug for ComputeStorage {
 
 impl ComputeStorage {
     #[inline]
-    pub fn set_time(&mut self, time: Timestamp) {
+    pub fn set_time(&mut self, time: BlockTime) {
         self.current_time = Some(time);
     }
 
@@ -155,6 +155,7 @@ impl ComputeStorage {
         AbciCommitInfo {
             retain_height,
             hash: hash.into(),
+            events_digest: None,
         }
     }
 
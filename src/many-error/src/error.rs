@@ -108,6 +108,7 @@ many_error! {
       -10: InvalidAttributeArguments as invalid_attribute_arguments()
             => "Attribute does not have the right arguments.",
       -11: AttributeNotFound as attribute_not_found(id) => "Expected attribute {id} not found.",
+      -12: InvalidProof as invalid_proof(details) => "Invalid proof: {details}.",
 
      -100: InvalidIdentity as invalid_identity()
             => "Identity is invalid (does not follow the protocol).",
@@ -163,10 +164,38 @@ many_error! {
             => "Non-WebAuthn request denied for endpoint '{endpoint}'.",
     -1009: DuplicatedMessage as duplicated_message()
             => "This message was already processed.",
+    -1010: InvalidArgument as invalid_argument(field, details)
+            => "Invalid argument '{field}': {details}.",
+    -1011: QuotaExceeded as quota_exceeded(resource, retry)
+            => "Quota exceeded for {resource}. Retry in {retry} seconds.",
+    -1012: UnsupportedProtocolVersion as unsupported_protocol_version(version)
+            => "Protocol version {version} is not supported.",
+    -1013: SimulationNotSupported as simulation_not_supported()
+            => "This server cannot simulate the execution of a request.",
+    -1014: EndpointSunset as endpoint_sunset(method, height)
+            => "Endpoint '{method}' was removed at height {height}.",
+    -1015: NetworkIdMismatch as network_id_mismatch(actual, expected)
+            => "Request is for network '{actual}', but this server is on network '{expected}'.",
+    -1016: NetworkIdMissing as network_id_missing(expected)
+            => "Requests must carry the ID of the network '{expected}'.",
+    -1017: NotEnoughSigners as not_enough_signers(count, threshold)
+            => "Request needs {threshold} signatures from the signer set of its sender, got {count}.",
+    -1018: InvalidDelegation as invalid_delegation(reason)
+            => "Invalid delegation: {reason}.",
+    -1019: MultiSignerNotSupported as multi_signer_not_supported(method)
+            => "Endpoint '{method}' does not accept multi-signer envelopes.",
 
     // -2000 - -2999 is for server errors.
     -2000: InternalServerError as internal_server_error()
             => "An internal server error happened.",
+    -2001: ModulePanicked as module_panicked(method)
+            => "The server failed unexpectedly while executing '{method}'.",
+    -2002: ModulesUnhealthy as modules_unhealthy(modules)
+            => "Some modules are unhealthy: {modules}.",
+    -2003: UnknownAsyncToken as unknown_async_token(token)
+            => "Unknown async token {token}.",
+    -2004: InvalidAsyncTransition as invalid_async_transition(from, to)
+            => "An async request cannot go from {from} to {to}.",
 
     // Negative 10000+ are reserved for attribute specified codes and are defined separately.
     // The method to use these is ATTRIBUTE_ID * -10000.
@@ -176,11 +205,21 @@ many_error! {
 }
 
 /// Easily define ManyError for specific attributes.
+///
+/// Next to each constructor function, a module of the same name holds the
+/// stable `CODE` of the error, so callers can branch on it, e.g.
+/// `err.code() == insufficient_funds::CODE`.
 #[macro_export]
 macro_rules! define_attribute_many_error {
     ( $( attribute $module_id: literal => { $( $id: literal : $vis: vis fn $name: ident ($( $var_name: ident ),*) => $message: literal ),* $(,)? } );* ) => {
         $(
         $(
+            #[allow(dead_code)]
+            $vis mod $name {
+                pub const CODE: $crate::ManyErrorCode =
+                    $crate::ManyErrorCode::AttributeSpecific(($module_id as i32) * -10000i32 - ($id as i32));
+            }
+
             $vis fn $name( $($var_name: impl ToString),* ) -> $crate::ManyError {
                 $crate::ManyError::attribute_specific(
                     ($module_id as i32) * -10000i32 - ($id as i32),
@@ -194,12 +233,19 @@ macro_rules! define_attribute_many_error {
         )*
     }
 }
-/// Easily define ManyError for specific application.
+/// Easily define ManyError for specific application. Like attribute errors,
+/// each constructor function has a module holding its `CODE`.
 #[macro_export]
 macro_rules! define_application_many_error {
     ( $( { $( $id: literal : $vis: vis fn $name: ident ($( $var_name: ident ),*) => $message: literal ),* $(,)? } );* ) => {
         $(
         $(
+            #[allow(dead_code)]
+            $vis mod $name {
+                pub const CODE: $crate::ManyErrorCode =
+                    $crate::ManyErrorCode::ApplicationSpecific($id as u32);
+            }
+
             $vis fn $name ( $($var_name: impl ToString),* ) -> $crate::ManyError {
                 $crate::ManyError::application_specific(
                     $id as u32,
@@ -398,4 +444,25 @@ mod tests {
 
         assert_eq!(e.to_string(), "/{}{ZERO}{}}{TWO.");
     }
+
+    #[test]
+    fn defined_error_codes() {
+        define_attribute_many_error!(
+            attribute 3 => {
+                4: fn attribute_error(name) => "Attribute error {name}.",
+            }
+        );
+        define_application_many_error!(
+            {
+                5: fn application_error() => "Application error.",
+            }
+        );
+
+        let e = attribute_error("foo");
+        assert_eq!(e.code(), attribute_error::CODE);
+        assert_eq!(i64::from(attribute_error::CODE), -30004);
+        assert_eq!(e.to_string(), "Attribute error foo.");
+        assert_eq!(application_error().code(), application_error::CODE);
+        assert_eq!(application_error::CODE, ErrorCode::ApplicationSpecific(5));
+    }
 }
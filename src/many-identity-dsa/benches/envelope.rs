@@ -0,0 +1,40 @@
+use coset::CborSerializable;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use many_identity::{Identity, Verifier};
+use many_identity_dsa::ed25519::generate_random_ed25519_identity;
+use many_identity_dsa::CoseKeyVerifier;
+use many_protocol::{
+    decode_request_from_cose_sign1, encode_cose_sign1_from_request, RequestMessage,
+};
+
+fn request(from: many_identity::Address) -> RequestMessage {
+    RequestMessage::default()
+        .with_method("ledger.send".to_string())
+        .with_from(from)
+        .with_data(vec![0xAB; 256])
+}
+
+fn envelope(c: &mut Criterion) {
+    let identity = generate_random_ed25519_identity();
+    let message = request(identity.address());
+    let envelope = encode_cose_sign1_from_request(message.clone(), &identity).unwrap();
+    let bytes = envelope.clone().to_vec().unwrap();
+
+    let mut group = c.benchmark_group("envelope");
+    group.bench_function("encode", |b| {
+        b.iter(|| encode_cose_sign1_from_request(black_box(message.clone()), &identity).unwrap())
+    });
+    group.bench_function("decode", |b| {
+        b.iter(|| {
+            let envelope = coset::CoseSign1::from_slice(black_box(&bytes)).unwrap();
+            decode_request_from_cose_sign1(&envelope, &CoseKeyVerifier).unwrap()
+        })
+    });
+    group.bench_function("verify", |b| {
+        b.iter(|| CoseKeyVerifier.verify_1(black_box(&envelope)).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, envelope);
+criterion_main!(benches);
@@ -9,6 +9,9 @@ pub mod ed25519;
 #[cfg(feature = "ecdsa")]
 pub mod ecdsa;
 
+#[cfg(feature = "bls")]
+pub mod bls;
+
 /// Assert a COSE key as valid.
 fn check_key(
     cose_key: &CoseKey,
@@ -0,0 +1,525 @@
+use blst::min_pk::{AggregateSignature, PublicKey, SecretKey, Signature};
+use blst::BLST_ERROR;
+use coset::cbor::value::{Integer, Value};
+use coset::iana::{EnumI64, OkpKeyParameter};
+use coset::{CoseKey, CoseSign, CoseSign1, CoseSign1Builder, Label};
+use many_error::ManyError;
+use many_identity::{cose, Address, Identity, Verifier};
+use std::collections::BTreeSet;
+use std::fmt::{Debug, Formatter};
+
+/// COSE has no registered algorithm for BLS signatures. This private use
+/// value identifies the BLS12-381 ciphersuite with public keys in G1 and
+/// signatures in G2, using proofs of possession.
+pub const BLS_ALGORITHM: i64 = -65_601;
+
+/// The `Bls12381G1` curve of the COSE Elliptic Curves registry.
+pub const BLS12381G1_CURVE: i64 = 13;
+
+/// Domain separation tag of signatures.
+const SIGNATURE_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+/// Domain separation tag of proofs of possession.
+const POP_DST: &[u8] = b"BLS_POP_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+fn algorithm() -> coset::Algorithm {
+    coset::Algorithm::PrivateUse(BLS_ALGORITHM)
+}
+
+fn check(result: BLST_ERROR) -> Result<(), ManyError> {
+    match result {
+        BLST_ERROR::BLST_SUCCESS => Ok(()),
+        e => Err(ManyError::could_not_verify_signature(format!("{e:?}"))),
+    }
+}
+
+/// Build a BLS CoseKey
+///
+/// # Arguments
+///
+/// * `x` - Public key, a compressed G1 point
+/// * `d` - Private key
+pub fn bls_cose_key(x: Vec<u8>, d: Option<Vec<u8>>) -> CoseKey {
+    let mut params: Vec<(Label, Value)> = Vec::from([
+        (
+            Label::Int(OkpKeyParameter::Crv.to_i64()),
+            Value::from(BLS12381G1_CURVE),
+        ),
+        (Label::Int(OkpKeyParameter::X.to_i64()), Value::Bytes(x)),
+    ]);
+
+    let mut key_ops: BTreeSet<coset::KeyOperation> =
+        BTreeSet::from([coset::KeyOperation::Assigned(
+            coset::iana::KeyOperation::Verify,
+        )]);
+
+    if let Some(d) = d {
+        params.push((Label::Int(OkpKeyParameter::D.to_i64()), Value::Bytes(d)));
+        key_ops.insert(coset::KeyOperation::Assigned(
+            coset::iana::KeyOperation::Sign,
+        ));
+    }
+
+    CoseKey {
+        kty: coset::KeyType::Assigned(coset::iana::KeyType::OKP),
+        alg: Some(algorithm()),
+        key_ops,
+        params,
+        ..Default::default()
+    }
+}
+
+/// Derive a BLS CoseKey, with its private key, from at least 32 bytes of
+/// secret keying material.
+pub fn seed_cose_key(ikm: &[u8]) -> Result<CoseKey, ManyError> {
+    let secret_key = SecretKey::key_gen(ikm, &[])
+        .map_err(|e| ManyError::unknown(format!("Could not generate BLS key: {e:?}")))?;
+    Ok(bls_cose_key(
+        secret_key.sk_to_pk().compress().to_vec(),
+        Some(secret_key.to_bytes().to_vec()),
+    ))
+}
+
+fn param(key: &CoseKey, param: OkpKeyParameter) -> Option<&Value> {
+    key.params
+        .iter()
+        .find(|(l, _)| l == &Label::Int(param.to_i64()))
+        .map(|(_, v)| v)
+}
+
+fn param_bytes<'a>(
+    key: &'a CoseKey,
+    param_: OkpKeyParameter,
+    name: &str,
+) -> Result<&'a [u8], ManyError> {
+    Ok(param(key, param_)
+        .ok_or_else(|| ManyError::unknown(format!("Could not find the {name} parameter in key")))?
+        .as_bytes()
+        .ok_or_else(|| {
+            ManyError::unknown(format!("Could not convert the {name} parameter to bytes"))
+        })?
+        .as_slice())
+}
+
+pub fn public_key(key: &CoseKey) -> Result<Option<CoseKey>, ManyError> {
+    if key.alg != Some(algorithm()) {
+        return Ok(None);
+    }
+    let x = param_bytes(key, OkpKeyParameter::X, "X")?;
+    Ok(Some(bls_cose_key(x.to_vec(), None)))
+}
+
+/// Assert a COSE key as a valid BLS key. This mirrors `check_key`, which only
+/// supports registered algorithms and curves.
+fn check_key(cose_key: &CoseKey, sign: bool, verify: bool) -> Result<(), ManyError> {
+    let op = |op| coset::KeyOperation::Assigned(op);
+    if sign
+        && !cose_key
+            .key_ops
+            .contains(&op(coset::iana::KeyOperation::Sign))
+    {
+        return Err(ManyError::unknown("Key cannot sign"));
+    }
+    if verify
+        && !cose_key
+            .key_ops
+            .contains(&op(coset::iana::KeyOperation::Verify))
+    {
+        return Err(ManyError::unknown("Key cannot verify"));
+    }
+    if cose_key.kty != coset::KeyType::Assigned(coset::iana::KeyType::OKP) {
+        return Err(ManyError::unknown(format!(
+            "Wrong key type: {:?}",
+            cose_key.kty
+        )));
+    }
+    if cose_key.alg != Some(algorithm()) {
+        return Err(ManyError::unknown(format!(
+            "Wrong key algorihm: {:?}",
+            cose_key.alg
+        )));
+    }
+    if param(cose_key, OkpKeyParameter::Crv)
+        .and_then(Value::as_integer)
+        .ok_or_else(|| ManyError::unknown("Crv parameter not found."))?
+        != Integer::from(BLS12381G1_CURVE)
+    {
+        return Err(ManyError::unknown("Curve unsupported. Expected Bls12381G1"));
+    }
+    Ok(())
+}
+
+/// Aggregate signatures into a single signature of the same size. The result
+/// is verified with [verify_aggregate] or [verify_aggregate_distinct].
+pub fn aggregate_signatures(signatures: &[&[u8]]) -> Result<Vec<u8>, ManyError> {
+    let signatures = signatures
+        .iter()
+        .map(|s| Signature::sig_validate(s, true))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| ManyError::unknown(format!("Invalid BLS signature: {e:?}")))?;
+    let signatures: Vec<&Signature> = signatures.iter().collect();
+
+    AggregateSignature::aggregate(&signatures, false)
+        .map(|s| s.to_signature().compress().to_vec())
+        .map_err(|e| ManyError::unknown(format!("Could not aggregate signatures: {e:?}")))
+}
+
+/// Verify an aggregate signature of the same data by all the verifiers, e.g.
+/// the members of a validator set signing a block.
+///
+/// The keys of the verifiers must have been checked with
+/// [BlsVerifier::verify_proof_of_possession] beforehand, otherwise a signer
+/// can forge an aggregate signature with a key derived from the others.
+pub fn verify_aggregate(
+    verifiers: &[&BlsVerifier],
+    signature: &[u8],
+    data: &[u8],
+) -> Result<(), ManyError> {
+    let signature = Signature::sig_validate(signature, true)
+        .map_err(|e| ManyError::could_not_verify_signature(format!("{e:?}")))?;
+    let public_keys: Vec<&PublicKey> = verifiers.iter().map(|v| &v.public_key).collect();
+    check(signature.fast_aggregate_verify(true, data, SIGNATURE_DST, &public_keys))
+}
+
+/// Verify an aggregate signature where each verifier signed its own data,
+/// e.g. the envelopes of different signers.
+pub fn verify_aggregate_distinct(
+    signed: &[(&BlsVerifier, &[u8])],
+    signature: &[u8],
+) -> Result<(), ManyError> {
+    let signature = Signature::sig_validate(signature, true)
+        .map_err(|e| ManyError::could_not_verify_signature(format!("{e:?}")))?;
+    let (public_keys, data): (Vec<&PublicKey>, Vec<&[u8]>) =
+        signed.iter().map(|(v, d)| (&v.public_key, *d)).unzip();
+    check(signature.aggregate_verify(true, &data, SIGNATURE_DST, &public_keys, false))
+}
+
+#[derive(Clone)]
+struct BlsIdentityInner {
+    address: Address,
+    public_key: CoseKey,
+    secret_key: SecretKey,
+}
+
+impl BlsIdentityInner {
+    pub fn from_key(cose_key: &CoseKey) -> Result<Self, ManyError> {
+        check_key(cose_key, true, false)?;
+        let public_key = public_key(cose_key)?.ok_or_else(|| ManyError::unknown("Invalid key."))?;
+        let d = param_bytes(cose_key, OkpKeyParameter::D, "D")?;
+        let secret_key = SecretKey::from_bytes(d)
+            .map_err(|e| ManyError::unknown(format!("Invalid BLS secret key: {e:?}")))?;
+
+        if param_bytes(&public_key, OkpKeyParameter::X, "X")? != secret_key.sk_to_pk().compress() {
+            return Err(ManyError::unknown(
+                "The BLS public key does not match the secret key",
+            ));
+        }
+        let address = unsafe { cose::address_unchecked(&public_key) }?;
+
+        Ok(Self {
+            address,
+            public_key,
+            secret_key,
+        })
+    }
+
+    fn sign(&self, bytes: &[u8]) -> Vec<u8> {
+        self.secret_key
+            .sign(bytes, SIGNATURE_DST, &[])
+            .compress()
+            .to_vec()
+    }
+}
+
+impl Identity for BlsIdentityInner {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn public_key(&self) -> Option<CoseKey> {
+        Some(self.public_key.clone())
+    }
+
+    fn sign_1(&self, mut envelope: CoseSign1) -> Result<CoseSign1, ManyError> {
+        // Add the algorithm and key id.
+        envelope.protected.header.alg = Some(algorithm());
+        envelope.protected.header.key_id = self.address.to_vec();
+
+        let builder = CoseSign1Builder::new()
+            .protected(envelope.protected.header)
+            .unprotected(envelope.unprotected);
+
+        let builder = if let Some(payload) = envelope.payload {
+            builder.payload(payload)
+        } else {
+            builder
+        };
+
+        Ok(builder
+            .create_signature(&[], |bytes| self.sign(bytes))
+            .build())
+    }
+}
+
+/// A BLS12-381 identity that sign messages and include the public key in the
+/// protected headers. Its signatures can be aggregated with the signatures of
+/// other BLS identities.
+#[derive(Clone)]
+pub struct BlsIdentity(BlsIdentityInner);
+
+impl Debug for BlsIdentity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("BlsIdentity").field(&self.0.address).finish()
+    }
+}
+
+impl BlsIdentity {
+    pub fn from_key(key: &CoseKey) -> Result<Self, ManyError> {
+        BlsIdentityInner::from_key(key).map(Self)
+    }
+
+    /// Derive a key from at least 32 bytes of secret keying material.
+    pub fn from_seed(ikm: &[u8]) -> Result<Self, ManyError> {
+        Self::from_key(&seed_cose_key(ikm)?)
+    }
+
+    /// Sign arbitrary data, e.g. to aggregate the signature with others.
+    pub fn sign(&self, data: &[u8]) -> Vec<u8> {
+        self.0.sign(data)
+    }
+
+    /// A signature of the public key, which proves that this identity owns
+    /// the secret key.
+    pub fn proof_of_possession(&self) -> Vec<u8> {
+        let public_key = self.0.secret_key.sk_to_pk().compress();
+        self.0
+            .secret_key
+            .sign(&public_key, POP_DST, &[])
+            .compress()
+            .to_vec()
+    }
+}
+
+impl Identity for BlsIdentity {
+    fn address(&self) -> Address {
+        self.0.address
+    }
+
+    fn public_key(&self) -> Option<CoseKey> {
+        self.0.public_key()
+    }
+
+    fn sign_1(&self, envelope: CoseSign1) -> Result<CoseSign1, ManyError> {
+        self.0.sign_1(cose::add_keyset_header(envelope, self)?)
+    }
+
+    fn sign_multi(&self, envelope: CoseSign) -> Result<CoseSign, ManyError> {
+        cose::add_signature(envelope, self, algorithm(), |bytes| Ok(self.0.sign(bytes)))
+    }
+}
+
+#[derive(Clone)]
+pub struct BlsVerifier {
+    address: Address,
+    public_key: PublicKey,
+}
+
+impl Debug for BlsVerifier {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("BlsVerifier").field(&self.address).finish()
+    }
+}
+
+impl BlsVerifier {
+    pub fn from_key(cose_key: &CoseKey) -> Result<Self, ManyError> {
+        let public_key = public_key(cose_key)?.ok_or_else(|| ManyError::unknown("Key not BLS."))?;
+        check_key(&public_key, false, true)?;
+        let address = unsafe { cose::address_unchecked(&public_key) }?;
+
+        let x = param_bytes(&public_key, OkpKeyParameter::X, "X")?;
+        let public_key = PublicKey::key_validate(x)
+            .map_err(|e| ManyError::unknown(format!("Invalid BLS public key: {e:?}")))?;
+
+        Ok(Self {
+            address,
+            public_key,
+        })
+    }
+
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    pub fn verify_signature(&self, signature: &[u8], data: &[u8]) -> Result<(), ManyError> {
+        let signature = Signature::sig_validate(signature, true)
+            .map_err(|e| ManyError::could_not_verify_signature(format!("{e:?}")))?;
+        check(signature.verify(false, data, SIGNATURE_DST, &[], &self.public_key, false))
+    }
+
+    pub fn verify_proof_of_possession(&self, proof: &[u8]) -> Result<(), ManyError> {
+        let proof = Signature::sig_validate(proof, true)
+            .map_err(|e| ManyError::could_not_verify_signature(format!("{e:?}")))?;
+        check(proof.verify(
+            false,
+            &self.public_key.compress(),
+            POP_DST,
+            &[],
+            &self.public_key,
+            false,
+        ))
+    }
+}
+
+impl Verifier for BlsVerifier {
+    fn verify_1(&self, envelope: &CoseSign1) -> Result<Address, ManyError> {
+        let address = Address::from_bytes(&envelope.protected.header.key_id)?;
+        if self.address.matches(&address) {
+            envelope
+                .verify_signature(&[], |signature, msg| self.verify_signature(signature, msg))?;
+            Ok(address)
+        } else {
+            Err(ManyError::unknown(format!(
+                "Address in envelope does not match expected address. Expected: {}, Actual: {address}",
+                self.address
+            )))
+        }
+    }
+
+    fn verify_multi(&self, envelope: &CoseSign, index: usize) -> Result<Address, ManyError> {
+        cose::verify_signature(envelope, index, &self.address, |signature, msg| {
+            self.verify_signature(signature, msg)
+        })
+    }
+}
+
+#[cfg(feature = "testing")]
+pub fn generate_random_bls_identity() -> BlsIdentity {
+    use rand::RngCore;
+
+    let mut ikm = [0u8; 32];
+    rand::rngs::OsRng.fill_bytes(&mut ikm);
+    BlsIdentity::from_seed(&ikm).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use coset::TaggedCborSerializable;
+
+    const MSG: &[u8] = b"FOOBAR";
+
+    #[test]
+    fn sign_verify() {
+        let id = generate_random_bls_identity();
+        let verifier = BlsVerifier::from_key(&id.public_key().unwrap()).unwrap();
+        verifier.verify_signature(&id.sign(MSG), MSG).unwrap();
+        assert!(verifier.verify_signature(&id.sign(MSG), b"other").is_err());
+    }
+
+    #[test]
+    fn deterministic_seed() {
+        let a = BlsIdentity::from_seed(&[1; 32]).unwrap();
+        let b = BlsIdentity::from_seed(&[1; 32]).unwrap();
+        assert_eq!(a.address(), b.address());
+        assert!(BlsIdentity::from_seed(&[1; 16]).is_err());
+    }
+
+    #[test]
+    fn mismatched_public_key() {
+        let a = generate_random_bls_identity();
+        let b = generate_random_bls_identity();
+        let mut key = a.0.public_key.clone();
+        key.params.push((
+            Label::Int(OkpKeyParameter::D.to_i64()),
+            Value::Bytes(b.0.secret_key.to_bytes().to_vec()),
+        ));
+        key.key_ops.insert(coset::KeyOperation::Assigned(
+            coset::iana::KeyOperation::Sign,
+        ));
+        assert!(BlsIdentity::from_key(&key).is_err());
+    }
+
+    #[test]
+    fn proof_of_possession() {
+        let a = generate_random_bls_identity();
+        let b = generate_random_bls_identity();
+        let verifier = BlsVerifier::from_key(&a.public_key().unwrap()).unwrap();
+        verifier
+            .verify_proof_of_possession(&a.proof_of_possession())
+            .unwrap();
+        assert!(verifier
+            .verify_proof_of_possession(&b.proof_of_possession())
+            .is_err());
+        // A signature of the public key isn't a proof of possession.
+        assert!(verifier
+            .verify_proof_of_possession(&a.sign(&a.0.secret_key.sk_to_pk().compress()))
+            .is_err());
+    }
+
+    #[test]
+    fn aggregate_same_message() {
+        let ids: Vec<BlsIdentity> = (0..4).map(|_| generate_random_bls_identity()).collect();
+        let verifiers: Vec<BlsVerifier> = ids
+            .iter()
+            .map(|id| BlsVerifier::from_key(&id.public_key().unwrap()).unwrap())
+            .collect();
+        let verifiers: Vec<&BlsVerifier> = verifiers.iter().collect();
+
+        let signatures: Vec<Vec<u8>> = ids.iter().map(|id| id.sign(MSG)).collect();
+        let signatures: Vec<&[u8]> = signatures.iter().map(Vec::as_slice).collect();
+        let aggregate = aggregate_signatures(&signatures).unwrap();
+        assert_eq!(aggregate.len(), signatures[0].len());
+
+        verify_aggregate(&verifiers, &aggregate, MSG).unwrap();
+        assert!(verify_aggregate(&verifiers, &aggregate, b"other").is_err());
+        assert!(verify_aggregate(&verifiers[1..], &aggregate, MSG).is_err());
+
+        let partial = aggregate_signatures(&signatures[1..]).unwrap();
+        assert!(verify_aggregate(&verifiers, &partial, MSG).is_err());
+    }
+
+    #[test]
+    fn aggregate_envelopes() {
+        let ids: Vec<BlsIdentity> = (0..3).map(|_| generate_random_bls_identity()).collect();
+        let envelopes: Vec<CoseSign1> = ids
+            .iter()
+            .map(|id| {
+                id.sign_1(CoseSign1Builder::new().payload(MSG.to_vec()).build())
+                    .unwrap()
+            })
+            .collect();
+        let verifiers: Vec<BlsVerifier> = ids
+            .iter()
+            .map(|id| BlsVerifier::from_key(&id.public_key().unwrap()).unwrap())
+            .collect();
+
+        for (envelope, verifier) in envelopes.iter().zip(&verifiers) {
+            assert_eq!(verifier.verify_1(envelope).unwrap(), verifier.address());
+        }
+
+        let signatures: Vec<&[u8]> = envelopes.iter().map(|e| e.signature.as_slice()).collect();
+        let aggregate = aggregate_signatures(&signatures).unwrap();
+        let data: Vec<Vec<u8>> = envelopes.iter().map(|e| e.tbs_data(&[])).collect();
+        let signed: Vec<(&BlsVerifier, &[u8])> = verifiers
+            .iter()
+            .zip(data.iter().map(Vec::as_slice))
+            .collect();
+        verify_aggregate_distinct(&signed, &aggregate).unwrap();
+        assert!(verify_aggregate_distinct(&signed[1..], &aggregate).is_err());
+    }
+
+    #[test]
+    fn envelope_roundtrip() {
+        let id = generate_random_bls_identity();
+        let envelope = id
+            .sign_1(CoseSign1Builder::new().payload(MSG.to_vec()).build())
+            .unwrap();
+        let envelope = CoseSign1::from_tagged_slice(&envelope.to_tagged_vec().unwrap()).unwrap();
+        let verifier = BlsVerifier::from_key(&id.public_key().unwrap()).unwrap();
+        assert_eq!(verifier.verify_1(&envelope).unwrap(), id.address());
+
+        let other =
+            BlsVerifier::from_key(&generate_random_bls_identity().public_key().unwrap()).unwrap();
+        assert!(other.verify_1(&envelope).is_err());
+    }
+}
@@ -1,8 +1,9 @@
 use crate::impls::check_key;
 use coset::cbor::value::Value;
 use coset::iana::{EnumI64, OkpKeyParameter};
-use coset::{CoseKey, CoseSign1, CoseSign1Builder, Label};
-use ed25519::pkcs8::DecodePrivateKey;
+use coset::{CoseKey, CoseSign, CoseSign1, CoseSign1Builder, Label};
+use ed25519::pkcs8::spki::der::pem::LineEnding;
+use ed25519::pkcs8::{DecodePrivateKey, EncodePrivateKey};
 use ed25519_dalek::{Signer, SigningKey, Verifier as _};
 use many_error::ManyError;
 use many_identity::{cose, Address, Identity, Verifier};
@@ -45,6 +46,16 @@ fn eddsa_cose_key(x: Vec<u8>, d: Option<Vec<u8>>) -> CoseKey {
     }
 }
 
+/// Build the CoseKey of the 32 bytes of an Ed25519 secret key, with its
+/// public key.
+pub fn secret_cose_key(secret_key: &[u8; 32]) -> CoseKey {
+    let signing_key = SigningKey::from_bytes(secret_key);
+    eddsa_cose_key(
+        signing_key.verifying_key().to_bytes().to_vec(),
+        Some(signing_key.to_bytes().to_vec()),
+    )
+}
+
 pub fn public_key(key: &CoseKey) -> Result<Option<CoseKey>, ManyError> {
     match key.alg {
         Some(coset::Algorithm::Assigned(coset::iana::Algorithm::EdDSA)) => {
@@ -192,6 +203,21 @@ impl Ed25519Identity {
         Self::from_key(&cose_key)
     }
 
+    /// Create an identity from the 32 bytes of an Ed25519 secret key.
+    pub fn from_secret_key(secret_key: &[u8; 32]) -> Result<Self, ManyError> {
+        Self::from_key(&secret_cose_key(secret_key))
+    }
+
+    /// Encode the secret key as a PKCS#8 PEM, which can be read back with
+    /// [Ed25519Identity::from_pem].
+    pub fn to_pem(&self) -> Result<String, ManyError> {
+        self.0
+            .key_pair
+            .to_pkcs8_pem(LineEnding::LF)
+            .map(|pem| pem.to_string())
+            .map_err(ManyError::unknown)
+    }
+
     pub fn public_key(&self) -> CoseKey {
         self.0.public_key.clone()
     }
@@ -214,6 +240,15 @@ impl Identity for Ed25519Identity {
     fn sign_1(&self, envelope: CoseSign1) -> Result<CoseSign1, ManyError> {
         self.0.sign_1(cose::add_keyset_header(envelope, self)?)
     }
+
+    fn sign_multi(&self, envelope: CoseSign) -> Result<CoseSign, ManyError> {
+        cose::add_signature(
+            envelope,
+            self,
+            coset::Algorithm::Assigned(coset::iana::Algorithm::EdDSA),
+            |bytes| self.0.try_sign(bytes),
+        )
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -290,6 +325,12 @@ impl Verifier for Ed25519Verifier {
             )))
         }
     }
+
+    fn verify_multi(&self, envelope: &CoseSign, index: usize) -> Result<Address, ManyError> {
+        cose::verify_signature(envelope, index, &self.address, |signature, msg| {
+            self.verify_signature(signature, msg)
+        })
+    }
 }
 
 #[cfg(feature = "testing")]
@@ -322,6 +363,24 @@ pub mod tests {
         Ed25519Identity::from_pem(pem).unwrap()
     }
 
+    #[test]
+    fn pem_roundtrip() {
+        let id = eddsa_identity();
+        let pem = id.to_pem().unwrap();
+        assert_eq!(
+            Ed25519Identity::from_pem(&pem).unwrap().address(),
+            id.address()
+        );
+
+        let secret_key = id.0.key_pair.to_bytes();
+        assert_eq!(
+            Ed25519Identity::from_secret_key(&secret_key)
+                .unwrap()
+                .address(),
+            id.address()
+        );
+    }
+
     #[test]
     fn eddsa_256_sign_verify() {
         let id = eddsa_identity();
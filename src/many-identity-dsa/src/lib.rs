@@ -1,4 +1,4 @@
-use coset::{CoseKey, CoseSign1};
+use coset::{CoseKey, CoseSign, CoseSign1};
 use many_error::ManyError;
 use many_identity::{Address, Identity, Verifier};
 use std::fmt::{Debug, Formatter};
@@ -6,12 +6,18 @@ use tracing::trace;
 
 mod impls;
 
+#[cfg(feature = "testing")]
+pub mod testing;
+
 #[cfg(feature = "ed25519")]
 pub use impls::ed25519;
 
 #[cfg(feature = "ecdsa")]
 pub use impls::ecdsa;
-use many_identity::cose::keyset_from_cose_sign1;
+
+#[cfg(feature = "bls")]
+pub use impls::bls;
+use many_identity::cose::{keyset_from_cose_sign1, signer_key};
 
 #[non_exhaustive]
 #[derive(Clone)]
@@ -22,6 +28,9 @@ enum CoseKeyImpl {
     #[cfg(feature = "ecdsa")]
     EcDsa(ecdsa::EcDsaIdentity),
 
+    #[cfg(feature = "bls")]
+    Bls(bls::BlsIdentity),
+
     /// This should never be constructed, but in some cases the other enum
     /// values might not exist and an empty enum is illegal.
     #[allow(unused)]
@@ -40,6 +49,11 @@ impl CoseKeyImpl {
             return Some(Self::EcDsa(i));
         }
 
+        #[cfg(feature = "bls")]
+        if let Ok(i) = bls::BlsIdentity::from_key(key) {
+            return Some(Self::Bls(i));
+        }
+
         None
     }
 
@@ -65,6 +79,9 @@ impl CoseKeyImpl {
             #[cfg(feature = "ecdsa")]
             CoseKeyImpl::EcDsa(i) => i.address(),
 
+            #[cfg(feature = "bls")]
+            CoseKeyImpl::Bls(i) => i.address(),
+
             CoseKeyImpl::Illegal_ => unreachable!(),
         }
     }
@@ -77,6 +94,9 @@ impl CoseKeyImpl {
             #[cfg(feature = "ecdsa")]
             CoseKeyImpl::EcDsa(i) => Identity::public_key(i),
 
+            #[cfg(feature = "bls")]
+            CoseKeyImpl::Bls(i) => Identity::public_key(i),
+
             CoseKeyImpl::Illegal_ => unreachable!(),
         }
     }
@@ -89,6 +109,24 @@ impl CoseKeyImpl {
             #[cfg(feature = "ecdsa")]
             CoseKeyImpl::EcDsa(i) => i.sign_1(envelope),
 
+            #[cfg(feature = "bls")]
+            CoseKeyImpl::Bls(i) => i.sign_1(envelope),
+
+            CoseKeyImpl::Illegal_ => unreachable!(),
+        }
+    }
+
+    pub fn sign_multi(&self, envelope: CoseSign) -> Result<CoseSign, ManyError> {
+        match self {
+            #[cfg(feature = "ed25519")]
+            CoseKeyImpl::Ed25519(i) => i.sign_multi(envelope),
+
+            #[cfg(feature = "ecdsa")]
+            CoseKeyImpl::EcDsa(i) => i.sign_multi(envelope),
+
+            #[cfg(feature = "bls")]
+            CoseKeyImpl::Bls(i) => i.sign_multi(envelope),
+
             CoseKeyImpl::Illegal_ => unreachable!(),
         }
     }
@@ -135,13 +173,17 @@ impl Identity for CoseKeyIdentity {
     fn sign_1(&self, envelope: CoseSign1) -> Result<CoseSign1, ManyError> {
         self.inner.sign_1(envelope)
     }
+
+    fn sign_multi(&self, envelope: CoseSign) -> Result<CoseSign, ManyError> {
+        self.inner.sign_multi(envelope)
+    }
 }
 
 macro_rules! try_verify {
-    ($init: expr, $envelope: ident, $name: literal) => {
+    ($init: expr, $name: literal, $method: ident ( $( $arg: expr ),* )) => {
         match $init {
             Ok(v) => {
-                return v.verify_1($envelope);
+                return v.$method( $( $arg ),* );
             }
             Err(err) => {
                 trace!("Initialization error ({}): {}", $name, err)
@@ -169,16 +211,54 @@ impl Verifier for CoseKeyVerifier {
 
         let address = (|| {
             #[cfg(feature = "ed25519")]
-            try_verify!(ed25519::Ed25519Verifier::from_key(key), envelope, "ed25519");
+            try_verify!(
+                ed25519::Ed25519Verifier::from_key(key),
+                "ed25519",
+                verify_1(envelope)
+            );
 
             #[cfg(feature = "ecdsa")]
-            try_verify!(ecdsa::EcDsaVerifier::from_key(key), envelope, "ecdsa");
+            try_verify!(
+                ecdsa::EcDsaVerifier::from_key(key),
+                "ecdsa",
+                verify_1(envelope)
+            );
+
+            #[cfg(feature = "bls")]
+            try_verify!(bls::BlsVerifier::from_key(key), "bls", verify_1(envelope));
 
             Err(ManyError::unknown("Algorithm unsupported."))
         })()?;
 
         Ok(address)
     }
+
+    fn verify_multi(&self, envelope: &CoseSign, index: usize) -> Result<Address, ManyError> {
+        let key = &signer_key(envelope, index)?;
+
+        #[cfg(feature = "ed25519")]
+        try_verify!(
+            ed25519::Ed25519Verifier::from_key(key),
+            "ed25519",
+            verify_multi(envelope, index)
+        );
+
+        #[cfg(feature = "ecdsa")]
+        try_verify!(
+            ecdsa::EcDsaVerifier::from_key(key),
+            "ecdsa",
+            verify_multi(envelope, index)
+        );
+
+        #[cfg(feature = "bls")]
+        try_verify!(
+            bls::BlsVerifier::from_key(key),
+            "bls",
+            verify_multi(envelope, index)
+        );
+
+        Err(ManyError::unknown("Algorithm unsupported."))
+    }
 }
 
 impl Debug for CoseKeyVerifier {
@@ -191,6 +271,9 @@ impl Debug for CoseKeyVerifier {
         #[cfg(feature = "ed25519")]
         x.field(&"ed25519");
 
+        #[cfg(feature = "bls")]
+        x.field(&"bls");
+
         x.finish()
     }
 }
@@ -228,3 +311,34 @@ fn sign_and_verify_response() {
 
     many_protocol::decode_response_from_cose_sign1(&envelope, None, &CoseKeyVerifier).unwrap();
 }
+
+#[test]
+fn sign_and_verify_multi_signer_request() {
+    let ed25519 = ed25519::generate_random_ed25519_identity();
+    let ecdsa = ecdsa::generate_random_ecdsa_identity();
+    let bls = bls::generate_random_bls_identity();
+    let request = many_protocol::RequestMessageBuilder::default()
+        .from(ed25519.address())
+        .method("req".to_string())
+        .build()
+        .unwrap();
+
+    let envelope =
+        many_protocol::encode_cose_sign_from_request(request.clone(), &[&ed25519, &ecdsa, &bls])
+            .unwrap();
+    let (_, signers) =
+        many_protocol::decode_request_from_cose_sign(&envelope, &CoseKeyVerifier).unwrap();
+    assert_eq!(
+        signers,
+        std::collections::BTreeSet::from([ed25519.address(), ecdsa.address(), bls.address()])
+    );
+
+    // Every signature must be valid.
+    let mut tampered = envelope.clone();
+    tampered.signatures[1].signature[0] ^= 1;
+    assert!(many_protocol::decode_request_from_cose_sign(&tampered, &CoseKeyVerifier).is_err());
+
+    // The sender must be one of the signers.
+    let envelope = many_protocol::encode_cose_sign_from_request(request, &[&ecdsa, &bls]).unwrap();
+    assert!(many_protocol::decode_request_from_cose_sign(&envelope, &CoseKeyVerifier).is_err());
+}
@@ -0,0 +1,125 @@
+//! Deterministic identities with keys, for reproducible fixtures. Addresses
+//! without keys and identities with invalid signatures are in
+//! `many_identity::testing`.
+use crate::CoseKeyIdentity;
+use coset::CoseKey;
+use sha3::{Digest, Sha3_256};
+
+#[cfg(feature = "ecdsa")]
+use crate::ecdsa::EcDsaCurve;
+
+/// The algorithm of the key of a test identity.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum KeyAlgorithm {
+    #[cfg(feature = "ed25519")]
+    Ed25519,
+
+    #[cfg(feature = "ecdsa")]
+    EcDsa(EcDsaCurve),
+
+    #[cfg(feature = "bls")]
+    Bls,
+}
+
+/// The secret keying material of a seed.
+#[allow(unused)]
+fn secret(seed: u32) -> [u8; 32] {
+    Sha3_256::new()
+        .chain_update(b"many-identity-dsa testing")
+        .chain_update(seed.to_be_bytes())
+        .finalize()
+        .into()
+}
+
+/// The key of [identity], with its private key.
+pub fn cose_key(seed: u32, algorithm: KeyAlgorithm) -> CoseKey {
+    match algorithm {
+        #[cfg(feature = "ed25519")]
+        KeyAlgorithm::Ed25519 => crate::ed25519::secret_cose_key(&secret(seed)),
+
+        #[cfg(feature = "ecdsa")]
+        KeyAlgorithm::EcDsa(curve) => curve
+            .secret_cose_key(&secret(seed))
+            .expect("Invalid EcDSA test key"),
+
+        #[cfg(feature = "bls")]
+        KeyAlgorithm::Bls => {
+            crate::bls::seed_cose_key(&secret(seed)).expect("Invalid BLS test key")
+        }
+    }
+}
+
+/// An identity with a key derived from a seed, which is the same for every
+/// run. Different algorithms give different addresses for the same seed.
+pub fn identity(seed: u32, algorithm: KeyAlgorithm) -> CoseKeyIdentity {
+    CoseKeyIdentity::from_key(&cose_key(seed, algorithm)).expect("Invalid test key")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CoseKeyVerifier;
+    use coset::CoseSign1Builder;
+    use many_identity::testing::{Tamper, TamperedIdentity};
+    use many_identity::{Identity, Verifier};
+
+    const ALGORITHMS: [KeyAlgorithm; 5] = [
+        KeyAlgorithm::Ed25519,
+        KeyAlgorithm::EcDsa(EcDsaCurve::P256),
+        KeyAlgorithm::EcDsa(EcDsaCurve::P384),
+        KeyAlgorithm::EcDsa(EcDsaCurve::P521),
+        KeyAlgorithm::Bls,
+    ];
+
+    fn envelope() -> coset::CoseSign1 {
+        CoseSign1Builder::new().payload(b"FOOBAR".to_vec()).build()
+    }
+
+    #[test]
+    fn deterministic() {
+        for algorithm in ALGORITHMS {
+            assert_eq!(
+                identity(1, algorithm).address(),
+                identity(1, algorithm).address()
+            );
+            assert_ne!(
+                identity(1, algorithm).address(),
+                identity(2, algorithm).address()
+            );
+        }
+        assert_eq!(
+            identity(0, KeyAlgorithm::Ed25519).address(),
+            "mag35k3q732wg6pybehxog3zwl5croivp64w75hamdkgxakizx"
+        );
+    }
+
+    #[test]
+    fn sign_verify() {
+        for algorithm in ALGORITHMS {
+            let id = identity(3, algorithm);
+            let envelope = id.sign_1(envelope()).unwrap();
+            assert_eq!(CoseKeyVerifier.verify_1(&envelope).unwrap(), id.address());
+        }
+    }
+
+    #[test]
+    fn tampered() {
+        let other = identity(5, KeyAlgorithm::Ed25519).address();
+        for algorithm in ALGORITHMS {
+            for tamper in [
+                Tamper::Signature,
+                Tamper::Payload,
+                Tamper::KeyId(other),
+                Tamper::Unsigned,
+            ] {
+                let id = TamperedIdentity::new(identity(4, algorithm), tamper);
+                let envelope = id.sign_1(envelope()).unwrap();
+                assert!(
+                    CoseKeyVerifier.verify_1(&envelope).is_err(),
+                    "{algorithm:?} {tamper:?}"
+                );
+            }
+        }
+    }
+}
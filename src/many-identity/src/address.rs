@@ -92,6 +92,9 @@ impl From<SubresourceId> for u32 {
 
 /// An identity address in the ManyVerse. This could be a server, network, user, DAO,
 /// automated process, etc.
+/// The URI scheme of addresses in QR codes.
+const QR_URI_SCHEME: &str = "many";
+
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
 #[must_use]
 pub struct Address(InnerAddress);
@@ -194,6 +197,23 @@ impl Address {
         self.0.to_byte_array()
     }
 
+    /// The textual format of this address in a `MANY:` URI, all in uppercase
+    /// so QR codes can encode it in their compact alphanumeric mode.
+    pub fn to_qr_string(&self) -> String {
+        format!("{QR_URI_SCHEME}:{self}").to_ascii_uppercase()
+    }
+
+    /// Parse an address scanned from a QR code, with or without its `MANY:`
+    /// scheme, in any case. The checksum of the address is verified.
+    pub fn from_qr(value: &str) -> Result<Self, ManyError> {
+        let value = value.trim();
+        let address = match value.split_once(':') {
+            Some((scheme, address)) if scheme.eq_ignore_ascii_case(QR_URI_SCHEME) => address,
+            _ => value,
+        };
+        Self::from_str(&address.to_ascii_lowercase())
+    }
+
     /// Check that another identity matches this one, ignoring any subresouce IDs.
     #[inline]
     pub fn matches(&self, other: &Address) -> bool {
@@ -784,6 +804,29 @@ pub mod tests {
         assert_tokens(&id.compact(), &[Token::Bytes(&[DISCRIMINANT_ANONYMOUS])]);
     }
 
+    #[test]
+    fn qr_string() {
+        let a =
+            Address::from_str("mqbfbahksdwaqeenayy2gxke32hgb7aq4ao4wt745lsfs6wiaaaaqnz").unwrap();
+        let qr = a.to_qr_string();
+        assert_eq!(
+            qr,
+            "MANY:MQBFBAHKSDWAQEENAYY2GXKE32HGB7AQ4AO4WT745LSFS6WIAAAAQNZ"
+        );
+
+        assert_eq!(Address::from_qr(&qr).unwrap(), a);
+        assert_eq!(Address::from_qr(&qr.to_lowercase()).unwrap(), a);
+        assert_eq!(Address::from_qr(&qr[5..]).unwrap(), a);
+        assert_eq!(Address::from_qr("MAA").unwrap(), Address::anonymous());
+
+        // Bad checksum.
+        assert!(
+            Address::from_qr("MANY:MQBFBAHKSDWAQEENAYY2GXKE32HGB7AQ4AO4WT745LSFS6WIAAAAQNA")
+                .is_err()
+        );
+        assert!(Address::from_qr("OTHER:MAA").is_err());
+    }
+
     #[test]
     fn from_str_overflow() {
         assert!(Address::from_str("m").is_err());
@@ -1,6 +1,9 @@
 use crate::{Address, Identity};
 use coset::cbor::value::Value;
-use coset::{AsCborValue, CborSerializable, CoseKey, CoseKeySet, CoseSign1, Label};
+use coset::{
+    AsCborValue, CborSerializable, CoseKey, CoseKeySet, CoseSign, CoseSign1, CoseSignature, Header,
+    Label, ProtectedHeader,
+};
 use many_error::ManyError;
 use sha3::{Digest, Sha3_224};
 
@@ -26,12 +29,19 @@ pub fn add_keyset_header(
     mut envelope: CoseSign1,
     key: &impl Identity,
 ) -> Result<CoseSign1, ManyError> {
+    add_keyset_to_header(&mut envelope.protected.header, key)?;
+    Ok(envelope)
+}
+
+/// Add the public key of an identity to the keyset of a header, creating the
+/// keyset if it was not present.
+pub fn add_keyset_to_header(header: &mut Header, key: &impl Identity) -> Result<(), ManyError> {
     let mut cose_key = key
         .public_key()
         .ok_or_else(|| ManyError::unknown("Invalid Public Key"))?;
     cose_key.key_id = key.address().to_vec();
 
-    let headers = &mut envelope.protected.header.rest;
+    let headers = &mut header.rest;
 
     if let Some(index) = headers
         .iter()
@@ -42,7 +52,7 @@ pub fn add_keyset_header(
             keyset.0.push(cose_key);
             headers.get_mut(index).unwrap().1 =
                 Value::Bytes(keyset.to_vec().map_err(ManyError::unknown)?);
-            return Ok(envelope);
+            return Ok(());
         } else {
             headers.remove(index);
         }
@@ -51,19 +61,22 @@ pub fn add_keyset_header(
     let mut keyset = CoseKeySet::default();
     keyset.0.push(cose_key);
 
-    envelope.protected.header.rest.push((
+    headers.push((
         Label::Text("keyset".to_string()),
         Value::Bytes(keyset.to_vec().map_err(ManyError::unknown)?),
     ));
 
-    Ok(envelope)
+    Ok(())
 }
 
 /// Extract the keyset parameter from the envelope.
 pub fn keyset_from_cose_sign1(envelope: &CoseSign1) -> Option<CoseKeySet> {
-    let keyset = &envelope
-        .protected
-        .header
+    keyset_from_header(&envelope.protected.header)
+}
+
+/// Extract the keyset parameter from a header.
+pub fn keyset_from_header(header: &Header) -> Option<CoseKeySet> {
+    let keyset = &header
         .rest
         .iter()
         .find(|(k, _)| k == &coset::Label::Text("keyset".to_string()))?
@@ -72,3 +85,70 @@ pub fn keyset_from_cose_sign1(envelope: &CoseSign1) -> Option<CoseKeySet> {
     let bytes = keyset.as_bytes()?;
     CoseKeySet::from_slice(bytes).ok()
 }
+
+/// The signature at an index of a multi-signer envelope.
+pub fn signature_at(envelope: &CoseSign, index: usize) -> Result<&CoseSignature, ManyError> {
+    envelope
+        .signatures
+        .get(index)
+        .ok_or_else(|| ManyError::could_not_verify_signature(format!("No signature {index}")))
+}
+
+/// The public key of the signer at an index of a multi-signer envelope, from
+/// the keyset of its signature headers.
+pub fn signer_key(envelope: &CoseSign, index: usize) -> Result<CoseKey, ManyError> {
+    let header = &signature_at(envelope, index)?.protected.header;
+    keyset_from_header(header)
+        .ok_or_else(|| ManyError::unknown("Could not find keyset in headers."))?
+        .0
+        .into_iter()
+        .find(|key| key.key_id.eq(&header.key_id))
+        .ok_or_else(|| ManyError::unknown("Could not find the key in keyset."))
+}
+
+/// Add a signature to a multi-signer envelope. The protected headers of the
+/// signature hold the algorithm, the address of the identity as key id and
+/// a keyset with its public key. `sign` signs the bytes to be signed.
+pub fn add_signature(
+    mut envelope: CoseSign,
+    identity: &impl Identity,
+    alg: coset::Algorithm,
+    sign: impl FnOnce(&[u8]) -> Result<Vec<u8>, ManyError>,
+) -> Result<CoseSign, ManyError> {
+    let mut header = Header {
+        alg: Some(alg),
+        key_id: identity.address().to_vec(),
+        ..Default::default()
+    };
+    add_keyset_to_header(&mut header, identity)?;
+
+    let mut signature = CoseSignature {
+        protected: ProtectedHeader {
+            original_data: None,
+            header,
+        },
+        ..Default::default()
+    };
+    signature.signature = sign(&envelope.tbs_data(&[], &signature))?;
+    envelope.signatures.push(signature);
+    Ok(envelope)
+}
+
+/// Verify the signature at an index of a multi-signer envelope, made by the
+/// expected address. `verify` checks a signature against the signed bytes.
+pub fn verify_signature(
+    envelope: &CoseSign,
+    index: usize,
+    expected: &Address,
+    verify: impl FnOnce(&[u8], &[u8]) -> Result<(), ManyError>,
+) -> Result<Address, ManyError> {
+    let address = Address::from_bytes(&signature_at(envelope, index)?.protected.header.key_id)?;
+    if expected.matches(&address) {
+        envelope.verify_signature(index, &[], verify)?;
+        Ok(address)
+    } else {
+        Err(ManyError::unknown(format!(
+            "Address in envelope does not match expected address. Expected: {expected}, Actual: {address}"
+        )))
+    }
+}
@@ -1,6 +1,6 @@
 //! An Identity is a signer that also has an address on the MANY protocol.
 use crate::Address;
-use coset::{CoseKey, CoseSign1};
+use coset::{CoseKey, CoseSign, CoseSign1};
 use many_error::ManyError;
 
 /// An Identity is anything that is a unique address and can sign messages.
@@ -13,6 +13,14 @@ pub trait Identity: Send + Sync {
 
     /// Signs an envelope with this identity.
     fn sign_1(&self, envelope: CoseSign1) -> Result<CoseSign1, ManyError>;
+
+    /// Adds the signature of this identity to a multi-signer envelope. The
+    /// signatures already in the envelope are kept.
+    fn sign_multi(&self, _envelope: CoseSign) -> Result<CoseSign, ManyError> {
+        Err(ManyError::unknown(
+            "This identity cannot sign multi-signer envelopes.",
+        ))
+    }
 }
 
 /// A Verifier is the other side of the signature. It verifies that an envelope
@@ -21,6 +29,14 @@ pub trait Identity: Send + Sync {
 /// the envelope, and returns it.
 pub trait Verifier: Send {
     fn verify_1(&self, envelope: &CoseSign1) -> Result<Address, ManyError>;
+
+    /// Verifies the signature at this index of a multi-signer envelope, and
+    /// returns the address of its signer.
+    fn verify_multi(&self, _envelope: &CoseSign, _index: usize) -> Result<Address, ManyError> {
+        Err(ManyError::could_not_verify_signature(
+            "Multi-signer envelopes are not supported",
+        ))
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -39,6 +55,11 @@ impl Identity for AnonymousIdentity {
         // An anonymous envelope has no signature, or special header.
         Ok(envelope)
     }
+
+    fn sign_multi(&self, envelope: CoseSign) -> Result<CoseSign, ManyError> {
+        // Anonymous does not add a signer.
+        Ok(envelope)
+    }
 }
 
 #[cfg(feature = "testing")]
@@ -59,6 +80,16 @@ mod testing {
                 Address::from_bytes(kid)
             }
         }
+
+        fn verify_multi(
+            &self,
+            envelope: &coset::CoseSign,
+            index: usize,
+        ) -> Result<Address, many_error::ManyError> {
+            // Does not verify the signature either.
+            let signature = crate::cose::signature_at(envelope, index)?;
+            Address::from_bytes(&signature.protected.header.key_id)
+        }
     }
 }
 
@@ -93,6 +124,7 @@ macro_rules! decl_identity_impl {
                 fn address(&self) -> Address,
                 fn public_key(&self) -> Option<CoseKey>,
                 fn sign_1(&self, envelope: CoseSign1) -> Result<CoseSign1, ManyError>,
+                fn sign_multi(&self, envelope: CoseSign) -> Result<CoseSign, ManyError>,
             );
         }
         )+
@@ -111,6 +143,7 @@ macro_rules! decl_verifier_impl {
         impl $(< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? Verifier for $ty {
             decl_redirection!(
                 fn verify_1(&self, envelope: &CoseSign1) -> Result<Address, ManyError>,
+                fn verify_multi(&self, envelope: &CoseSign, index: usize) -> Result<Address, ManyError>,
             );
         }
         )+
@@ -136,6 +169,11 @@ macro_rules! declare_tuple_verifiers {
             fn verify_1(&self, envelope: &CoseSign1) -> Result<Address, ManyError> {
                 self.0.verify_1(envelope)
             }
+
+            #[inline]
+            fn verify_multi(&self, envelope: &CoseSign, index: usize) -> Result<Address, ManyError> {
+                self.0.verify_multi(envelope, index)
+            }
         }
     };
 
@@ -153,6 +191,19 @@ macro_rules! declare_tuple_verifiers {
 
                 Err(ManyError::could_not_verify_signature(errs.join(", ")))
             }
+
+            #[inline]
+            fn verify_multi(&self, envelope: &CoseSign, index: usize) -> Result<Address, ManyError> {
+                let mut errs = Vec::new();
+                $(
+                    match self. $index . verify_multi(envelope, index) {
+                        Ok(a) => return Ok(a),
+                        Err(e) => errs.push(e.to_string()),
+                    }
+                )*
+
+                Err(ManyError::could_not_verify_signature(errs.join(", ")))
+            }
         }
     };
 }
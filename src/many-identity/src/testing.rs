@@ -0,0 +1,100 @@
+//! Fixtures for tests. Identities with actual keys are in the
+//! `many-identity-dsa` crate, under its `testing` feature.
+use crate::{Address, Identity};
+use coset::{CoseKey, CoseSign1};
+use many_error::ManyError;
+
+/// A deterministic public key address, which has no key behind it.
+pub fn identity(seed: u32) -> Address {
+    #[rustfmt::skip]
+        let bytes = [
+        1u8,
+        0, 0, 0, 0,
+        0, 0, 0, 0,
+        0, 0, 0, 0,
+        0, 0, 0, 0,
+        0, 0, 0, 0,
+        0, 0, 0, 0,
+        (seed >> 24) as u8, (seed >> 16) as u8, (seed >> 8) as u8, (seed & 0xFF) as u8
+    ];
+    Address::from_bytes(&bytes).unwrap()
+}
+
+/// A subresource of [identity].
+pub fn subresource(seed: u32, subid: u32) -> Address {
+    identity(seed)
+        .with_subresource_id(subid)
+        .expect("Invalid subresource id")
+}
+
+/// How a [TamperedIdentity] breaks the envelopes it signs.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Tamper {
+    /// Flip a bit of the signature.
+    Signature,
+
+    /// Change the payload after signing.
+    Payload,
+
+    /// Claim another address in the key id, after signing.
+    KeyId(Address),
+
+    /// Remove the signature.
+    Unsigned,
+}
+
+/// An identity whose envelopes fail verification, to test the rejection of
+/// invalid requests. The envelopes are otherwise signed by the inner
+/// identity.
+#[derive(Clone, Debug)]
+pub struct TamperedIdentity<I> {
+    inner: I,
+    tamper: Tamper,
+}
+
+impl<I: Identity> TamperedIdentity<I> {
+    pub fn new(inner: I, tamper: Tamper) -> Self {
+        Self { inner, tamper }
+    }
+}
+
+impl<I: Identity> Identity for TamperedIdentity<I> {
+    fn address(&self) -> Address {
+        self.inner.address()
+    }
+
+    fn public_key(&self) -> Option<CoseKey> {
+        self.inner.public_key()
+    }
+
+    fn sign_1(&self, envelope: CoseSign1) -> Result<CoseSign1, ManyError> {
+        let mut envelope = self.inner.sign_1(envelope)?;
+        match self.tamper {
+            Tamper::Signature => match envelope.signature.last_mut() {
+                Some(byte) => *byte ^= 1,
+                None => envelope.signature.push(0),
+            },
+            Tamper::Payload => envelope.payload.get_or_insert_with(Vec::new).push(0),
+            Tamper::KeyId(address) => {
+                envelope.protected.original_data = None;
+                envelope.protected.header.key_id = address.to_vec();
+            }
+            Tamper::Unsigned => envelope.signature.clear(),
+        }
+        Ok(envelope)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subresources() {
+        let address = subresource(1, 2);
+        assert!(address.matches(&identity(1)));
+        assert_eq!(address.subresource_id(), Some(2));
+        assert_ne!(address, subresource(1, 3));
+        assert_ne!(address, subresource(2, 2));
+    }
+}
@@ -0,0 +1,48 @@
+use crate::storage::KvStoreStorage;
+use many_error::ManyError;
+use many_modules::admin;
+use std::path::{Path, PathBuf};
+
+#[derive(clap::Args, Debug)]
+pub struct CompactOpts {
+    /// Path to the persistent store database (rocksdb) to compact.
+    /// The store cannot be used by a running server at the same time.
+    #[clap(long)]
+    pub persistent: PathBuf,
+}
+
+pub fn run(opts: CompactOpts) -> Result<(), ManyError> {
+    let CompactOpts { persistent } = opts;
+    compact(&persistent)
+}
+
+fn compact(persistent: &Path) -> Result<(), ManyError> {
+    admin::compact_offline(
+        || KvStoreStorage::stats(persistent),
+        || KvStoreStorage::compact(persistent),
+    )
+}
+
+/// The file scheduling a compaction of the store, next to it.
+fn schedule_path(persistent: &Path) -> PathBuf {
+    let mut path = persistent.as_os_str().to_owned();
+    path.push(".compact");
+    path.into()
+}
+
+/// Schedule a compaction of the store at the next start of the server. A
+/// compaction needs exclusive access to the store, so a running server
+/// cannot compact its own store.
+pub fn schedule(persistent: &Path) -> Result<(), ManyError> {
+    std::fs::write(schedule_path(persistent), []).map_err(ManyError::unknown)
+}
+
+/// Run the compaction scheduled by [schedule], if any.
+pub fn run_scheduled(persistent: &Path) -> Result<(), ManyError> {
+    let path = schedule_path(persistent);
+    if !path.exists() {
+        return Ok(());
+    }
+    compact(persistent)?;
+    std::fs::remove_file(path).map_err(ManyError::unknown)
+}
@@ -10,12 +10,17 @@ define_attribute_many_error!(
         5: pub fn subres_alt_unsupported() => "Subresource alternative owner unsupported.",
         6: pub fn key_not_found() => "The key was not found.",
         7: pub fn cannot_disable_empty_key() => "Unable to disable an empty key.",
+        8: pub fn disable_until_in_past() => "Unable to disable a key until a time in the past.",
+        9: pub fn key_not_disabled() => "The key is not disabled.",
+        10: pub fn key_without_owner() => "A key must have at least one owner.",
     }
 );
 
 define_application_many_error!(
     {
         1: pub fn storage_apply_failed(desc) => "Unable to apply change to persistent storage: {desc}.",
-        2: pub fn storage_get_failed(desc) => "Unable to get data from persistent storage: {desc}."
+        2: pub fn storage_get_failed(desc) => "Unable to get data from persistent storage: {desc}.",
+        3: pub fn storage_open_failed(desc) => "Unable to open persistent storage: {desc}.",
+        4: pub fn storage_commit_failed(desc) => "Unable to commit data to persistent storage: {desc}.",
     }
 );
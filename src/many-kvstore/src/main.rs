@@ -1,35 +1,43 @@
 use crate::module::account::AccountFeatureModule;
 use clap::Parser;
-use many_identity::verifiers::AnonymousVerifier;
+use many_cli_helpers::verifiers::VerifierFlags;
+use many_error::ManyError;
 use many_identity::Address;
-use many_identity_dsa::{CoseKeyIdentity, CoseKeyVerifier};
-use many_identity_webauthn::WebAuthnVerifier;
+use many_identity_dsa::CoseKeyIdentity;
 use many_modules::account::features::Feature;
-use many_modules::{abci_backend, account, events, kvstore};
+use many_modules::{abci_backend, account, admin, events, kvstore};
 use many_protocol::ManyUrl;
+use many_server::admin::AdminModuleImpl;
+use many_server::panic::PanicPolicy;
+use many_server::registry::ModuleRegistry;
 use many_server::transport::http::HttpServer;
 use many_server::ManyServer;
 use many_server_cache::{RequestCacheValidator, RocksDbCacheBackend};
 use std::collections::BTreeSet;
 use std::net::SocketAddr;
 use std::path::PathBuf;
+use std::sync::atomic::Ordering;
 use std::sync::{Arc, Mutex};
 use tracing::{debug, info};
 
+mod compact;
 mod error;
 mod module;
 mod storage;
 
 use module::*;
+use storage::KvStoreStorage;
 
 #[derive(Debug, Parser)]
+#[clap(args_override_self(true), subcommand_negates_reqs(true))]
 struct Opts {
     #[clap(flatten)]
     common_flags: many_cli_helpers::CommonCliFlags,
 
     /// The location of a PEM file for the identity of this server.
-    #[clap(long)]
-    pem: PathBuf,
+    // The field needs to be an Option for the clap derive to work properly.
+    #[clap(long, required = true)]
+    pem: Option<PathBuf>,
 
     /// The address and port to bind to for the MANY Http server.
     #[clap(long, short, default_value = "127.0.0.1:8000")]
@@ -44,8 +52,9 @@ struct Opts {
     state: Option<PathBuf>,
 
     /// Path to a persistent store database (rocksdb).
-    #[clap(long)]
-    persistent: PathBuf,
+    // The field needs to be an Option for the clap derive to work properly.
+    #[clap(long, required = true)]
+    persistent: Option<PathBuf>,
 
     /// Delete the persistent storage to start from a clean state.
     /// If this is not specified the initial state will not be used.
@@ -58,22 +67,56 @@ struct Opts {
     #[clap(long)]
     allow_addrs: Option<PathBuf>,
 
+    /// The address allowed to call the admin endpoints, e.g. to change the
+    /// log level or shut down the server. The admin module is disabled if
+    /// unspecified.
+    #[clap(long)]
+    admin: Option<Address>,
+
+    /// Path to a JSON file containing an array of the attribute IDs of the
+    /// optional modules (kvstore transfer) to serve at startup. The admin
+    /// module can then enable and disable them while the server runs. All of
+    /// them are served if unspecified. Not available with `--abci`, as the
+    /// nodes would serve different modules.
+    #[clap(long, conflicts_with = "abci")]
+    modules: Option<PathBuf>,
+
     /// Application absolute URLs allowed to communicate with this server. Any
     /// application will be able to communicate with this server if left empty.
     /// Multiple occurences of this argument can be given.
     #[clap(long)]
     allow_origin: Option<Vec<ManyUrl>>,
 
+    #[clap(flatten)]
+    verifier_flags: VerifierFlags,
+
     /// Database path to the request cache to validate duplicate messages.
     /// If unspecified, the server will not verify transactions for duplicate
     /// messages.
     #[clap(long)]
     cache_db: Option<PathBuf>,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Compact a persistent store to reclaim the space used by stale data,
+    /// and report its size before and after.
+    Compact(compact::CompactOpts),
 }
 
 fn main() {
+    let opts = Opts::parse();
+    let log_level = opts.common_flags.init_logging().unwrap();
+    debug!("{opts:?}");
+    let admin = opts
+        .admin
+        .map(|admin| AdminModuleImpl::new(admin).with_config(&opts));
+
     let Opts {
-        common_flags,
+        common_flags: _,
         pem,
         addr,
         abci,
@@ -81,24 +124,40 @@ fn main() {
         persistent,
         clean,
         allow_addrs,
+        admin: _,
+        modules,
         allow_origin,
+        verifier_flags,
         cache_db,
-    } = Opts::parse();
+        command,
+    } = opts;
 
-    common_flags.init_logging().unwrap();
+    let verifier_config = verifier_flags
+        .config()
+        .expect("Could not load the verifier config");
 
-    debug!("{:?}", Opts::parse());
     info!(
         version = env!("CARGO_PKG_VERSION"),
         git_sha = env!("VERGEN_GIT_SHA")
     );
 
+    if let Some(Command::Compact(opts)) = command {
+        compact::run(opts).expect("Could not compact the store.");
+        return;
+    }
+
+    // Safe unwrap.
+    // At this point the Options should contain a value.
+    let pem = pem.unwrap();
+    let persistent = persistent.unwrap();
+
     if clean {
         // Delete the persistent storage.
         let _ = std::fs::remove_dir_all(persistent.as_path());
     } else if persistent.exists() {
         // Initial state is ignored.
         state = None;
+        compact::run_scheduled(&persistent).expect("Could not compact the store.");
     }
 
     let key = CoseKeyIdentity::from_pem(std::fs::read_to_string(pem).unwrap()).unwrap();
@@ -108,6 +167,7 @@ fn main() {
         json5::from_str(&content).unwrap()
     });
 
+    let persistent_path = persistent.clone();
     let module = if persistent.exists() {
         if state.is_some() {
             tracing::warn!(
@@ -131,16 +191,21 @@ fn main() {
     let many = ManyServer::simple(
         "many-kvstore",
         key,
-        (
-            AnonymousVerifier,
-            CoseKeyVerifier,
-            WebAuthnVerifier::new(allow_origin),
-        ),
+        verifier_config.build(allow_origin),
         Some(env!("CARGO_PKG_VERSION").to_string()),
     );
 
+    let mut registry = ModuleRegistry::new(&many);
+    registry
+        .register(kvstore::KvStoreTransferModule::new(module.clone()))
+        .expect("Could not register the optional modules");
+
     {
         let mut s = many.lock().unwrap();
+        s.set_build_info(
+            env!("VERGEN_GIT_SHA"),
+            cfg!(feature = "fault_testing").then_some("fault_testing"),
+        );
         s.add_module(kvstore::KvStoreModule::new(module.clone()));
         let kvstore_command_module = kvstore::KvStoreCommandsModule::new(module.clone());
         if let Some(path) = allow_addrs {
@@ -153,15 +218,18 @@ fn main() {
         } else {
             s.add_module(kvstore_command_module);
         }
-        s.add_module(kvstore::KvStoreTransferModule::new(module.clone()));
         s.add_module(events::EventsModule::new(module.clone()));
 
         s.add_module(AccountFeatureModule::new(
             account::AccountModule::new(module.clone()),
             [Feature::with_id(2)],
         ));
+        s.set_simulator(module.clone());
         if abci {
             s.set_timeout(u64::MAX);
+            // A panic can leave the state of this node diverging from the
+            // other nodes.
+            s.set_panic_policy(PanicPolicy::Abort);
             s.add_module(abci_backend::AbciModule::new(module));
         }
 
@@ -169,7 +237,40 @@ fn main() {
             s.add_validator(RequestCacheValidator::new(RocksDbCacheBackend::new(p)));
         }
     }
-    let mut many_server = HttpServer::new(many);
+    let enabled_modules: BTreeSet<u32> = match &modules {
+        Some(path) => json5::from_str(&std::fs::read_to_string(path).unwrap()).unwrap(),
+        None => registry.attributes().collect(),
+    };
+    for attribute in enabled_modules {
+        registry
+            .enable(attribute)
+            .expect("Could not enable an optional module");
+    }
+
+    let mut many_server = HttpServer::new(many.clone());
+
+    if let Some(admin) = admin {
+        let term_signal = many_server.term_signal();
+        let compact_path = persistent_path.clone();
+        let admin = admin
+            .with_log_level(move |level| log_level.set(level).map_err(ManyError::unknown))
+            .with_storage_stats(move || KvStoreStorage::stats(&persistent_path))
+            .with_compaction(move || {
+                // Stop, and compact the store before starting again.
+                compact::schedule(&compact_path)?;
+                term_signal.store(true, Ordering::Relaxed);
+                Ok(())
+            })
+            .with_term_signal(many_server.term_signal());
+        let admin = if modules.is_some() {
+            admin.with_module_registry(registry)
+        } else {
+            admin
+        };
+        many.lock()
+            .unwrap()
+            .add_module(admin::AdminModule::new(Arc::new(Mutex::new(admin))));
+    }
 
     signal_hook::flag::register(signal_hook::consts::SIGTERM, many_server.term_signal())
         .expect("Could not register signal handler");
@@ -11,12 +11,17 @@ use many_modules::abci_backend::{
 use many_modules::account::Role;
 use many_modules::kvstore::list::{ListArgs, ListReturns};
 use many_modules::kvstore::{
-    DisableArgs, DisableReturn, GetArgs, GetReturns, InfoArg, InfoReturns,
-    KvStoreCommandsModuleBackend, KvStoreModuleBackend, KvStoreTransferModuleBackend, PutArgs,
-    PutReturn, QueryArgs, QueryReturns, TransferArgs, TransferReturn,
+    DisableArgs, DisableReturn, EnableArgs, EnableReturn, GetArgs, GetReturns, InfoArg,
+    InfoReturns, KeyRole, KeyRoles, KvStoreCommandsModuleBackend, KvStoreModuleBackend,
+    KvStoreTransferModuleBackend, ProveArgs, ProveReturns, PutArgs, PutReturn, QueryArgs,
+    QueryReturns, TransferArgs, TransferReturn,
 };
-use many_types::{Either, Timestamp};
-use std::collections::BTreeMap;
+use many_protocol::context::Context;
+use many_server::simulation::Simulator;
+use many_server::transaction::StorageTransaction;
+use many_types::cbor::CborAny;
+use many_types::{BlockTime, Either, Timestamp};
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::Debug;
 use std::path::Path;
 use tracing::info;
@@ -39,27 +44,149 @@ pub struct KvStoreModuleImpl {
     storage: KvStoreStorage,
 }
 
-/// The KvStoreMetadata mimics the QueryReturns structure but adds serde capabilities
-#[derive(Clone, Debug, minicbor::Encode, minicbor::Decode, serde::Deserialize)]
-#[serde(remote = "QueryReturns")]
-#[cbor(map)]
+/// The metadata of a key, with the roles of the addresses on it. A key
+/// always has an owner.
+#[derive(Clone, Debug)]
 pub struct KvStoreMetadata {
+    pub roles: KeyRoles,
+    pub disabled: Option<Either<bool, Reason<u64>>>,
+    pub previous_owner: Option<Address>,
+    pub disabled_until: Option<Timestamp>,
+}
+
+/// The metadata of a key as stored. Metadata stored before role maps has an
+/// owner, and the roles of other addresses if any. It is migrated to a role
+/// map when decoded, and stored as one the next time the key is written.
+#[derive(minicbor::Encode, minicbor::Decode)]
+#[cbor(map)]
+struct StoredMetadata {
     #[n(0)]
-    pub owner: Address,
+    owner: Option<Address>,
 
     #[n(1)]
-    #[serde(skip_deserializing)]
-    pub disabled: Option<Either<bool, Reason<u64>>>,
+    disabled: Option<Either<bool, Reason<u64>>>,
 
     #[n(2)]
-    pub previous_owner: Option<Address>,
+    previous_owner: Option<Address>,
+
+    #[n(3)]
+    disabled_until: Option<Timestamp>,
+
+    #[n(4)]
+    roles: Option<KeyRoles>,
+}
+
+impl<C> minicbor::Encode<C> for KvStoreMetadata {
+    fn encode<W: minicbor::encode::Write>(
+        &self,
+        e: &mut minicbor::Encoder<W>,
+        ctx: &mut C,
+    ) -> Result<(), minicbor::encode::Error<W::Error>> {
+        StoredMetadata {
+            owner: None,
+            disabled: self.disabled.clone(),
+            previous_owner: self.previous_owner,
+            disabled_until: self.disabled_until,
+            roles: Some(self.roles.clone()),
+        }
+        .encode(e, ctx)
+    }
+}
+
+impl<'b, C> minicbor::Decode<'b, C> for KvStoreMetadata {
+    fn decode(d: &mut minicbor::Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
+        let stored = StoredMetadata::decode(d, ctx)?;
+        let mut roles = stored.roles.unwrap_or_default();
+        if let Some(owner) = stored.owner {
+            roles.insert(owner, KeyRole::Owner);
+        }
+        Ok(Self {
+            roles,
+            disabled: stored.disabled,
+            previous_owner: stored.previous_owner,
+            disabled_until: stored.disabled_until,
+        })
+    }
+}
+
+impl From<KvStoreMetadata> for QueryReturns {
+    fn from(meta: KvStoreMetadata) -> Self {
+        Self {
+            owner: meta.owners().next().unwrap_or_default(),
+            disabled: meta.disabled,
+            previous_owner: meta.previous_owner,
+            disabled_until: meta.disabled_until,
+            roles: Some(meta.roles),
+        }
+    }
+}
+
+impl KvStoreMetadata {
+    /// The metadata of a new key, owned by `owner`.
+    pub fn new(owner: Address) -> Self {
+        Self {
+            roles: KeyRoles::from([(owner, KeyRole::Owner)]),
+            disabled: Some(Either::Left(false)),
+            previous_owner: None,
+            disabled_until: None,
+        }
+    }
+
+    /// Whether the key is disabled at the given time. A key disabled until a
+    /// timestamp is enabled again once that timestamp is reached.
+    pub fn is_disabled(&self, now: Timestamp) -> bool {
+        let disabled = matches!(
+            self.disabled,
+            Some(Either::Left(true)) | Some(Either::Right(_))
+        );
+        disabled && self.disabled_until.map_or(true, |until| now < until)
+    }
+
+    /// The addresses with the owner role. There is at least one.
+    pub fn owners(&self) -> impl Iterator<Item = Address> + '_ {
+        self.roles
+            .iter()
+            .filter(|(_, role)| **role == KeyRole::Owner)
+            .map(|(address, _)| *address)
+    }
+
+    /// Whether an address has a role including `role` on the key.
+    pub fn allows(&self, address: &Address, role: KeyRole) -> bool {
+        self.roles.get(address).map_or(false, |r| r.includes(role))
+    }
+
+    /// Whether the key has readers, in which case only addresses with a role
+    /// can read it.
+    pub fn is_read_restricted(&self) -> bool {
+        self.roles.values().any(|r| *r == KeyRole::Reader)
+    }
+}
+
+/// The metadata of keys in the initial state, as in [QueryReturns]. It is
+/// stored as is, and migrated like any metadata stored before role maps.
+#[allow(dead_code)]
+#[derive(serde::Deserialize)]
+#[serde(remote = "QueryReturns")]
+struct QueryReturnsDef {
+    owner: Address,
+
+    #[serde(skip_deserializing)]
+    disabled: Option<Either<bool, Reason<u64>>>,
+
+    previous_owner: Option<Address>,
+
+    #[serde(skip_deserializing)]
+    disabled_until: Option<Timestamp>,
+
+    #[serde(skip_deserializing)]
+    roles: Option<KeyRoles>,
 }
 
 #[derive(Debug, serde::Deserialize, minicbor::Encode, minicbor::Decode)]
 #[serde(transparent)]
 #[cbor(transparent)]
 pub struct KvStoreMetadataWrapper(
-    #[serde(with = "KvStoreMetadata")]
+    #[serde(with = "QueryReturnsDef")]
     #[n(0)]
     QueryReturns,
 );
@@ -105,6 +232,36 @@ impl KvStoreModuleImpl {
     }
 }
 
+impl StorageTransaction for KvStoreModuleImpl {
+    fn begin(&mut self) -> Result<(), ManyError> {
+        self.storage.begin_transaction()
+    }
+
+    fn commit(&mut self) -> Result<(), ManyError> {
+        self.storage.commit_transaction()
+    }
+
+    fn rollback(&mut self) -> Result<(), ManyError> {
+        self.storage.rollback_transaction()
+    }
+}
+
+impl Simulator for KvStoreModuleImpl {
+    fn begin(&mut self) -> Result<(), ManyError> {
+        self.storage.begin_transaction()
+    }
+
+    fn rollback(&mut self) -> Result<Vec<CborAny>, ManyError> {
+        // Roll back even if the events cannot be read.
+        let events = self.storage.transaction_events();
+        self.storage.rollback_transaction()?;
+        events?
+            .iter()
+            .map(|event| minicbor::decode(event).map_err(ManyError::deserialization_error))
+            .collect()
+    }
+}
+
 // This module is always supported, but will only be added when created using an ABCI
 // flag.
 impl ManyAbciModuleBackend for KvStoreModuleImpl {
@@ -117,8 +274,10 @@ impl ManyAbciModuleBackend for KvStoreModuleImpl {
                 ("kvstore.query".to_string(), EndpointInfo { is_command: false }),
                 ("kvstore.put".to_string(), EndpointInfo { is_command: true }),
                 ("kvstore.disable".to_string(), EndpointInfo { is_command: true }),
+                ("kvstore.enable".to_string(), EndpointInfo { is_command: true }),
                 ("kvstore.transfer".to_string(), EndpointInfo { is_command: true }),
                 ("kvstore.list".to_string(), EndpointInfo { is_command: false }),
+                ("kvstore.prove".to_string(), EndpointInfo { is_command: false }),
 
                 // Accounts
                 ("account.create".to_string(), EndpointInfo { is_command: true }),
@@ -130,6 +289,7 @@ impl ManyAbciModuleBackend for KvStoreModuleImpl {
                 ("account.info".to_string(), EndpointInfo { is_command: false }),
                 ("account.disable".to_string(), EndpointInfo { is_command: true }),
                 ("account.addFeatures".to_string(), EndpointInfo { is_command: true }),
+                ("account.listSubresources".to_string(), EndpointInfo { is_command: false }),
 
                 // Events
                 ("events.info".to_string(), EndpointInfo { is_command: false }),
@@ -152,8 +312,7 @@ impl ManyAbciModuleBackend for KvStoreModuleImpl {
         );
 
         if let Some(time) = time {
-            let time = Timestamp::new(time)?;
-            self.storage.set_time(time);
+            self.storage.set_time(BlockTime::from_secs(time));
         }
 
         Ok(BeginBlockReturn {})
@@ -170,11 +329,12 @@ impl ManyAbciModuleBackend for KvStoreModuleImpl {
         Ok(AbciInfo {
             height: storage.get_height(),
             hash: storage.hash().into(),
+            events_digest: storage.events_digest().map(Into::into),
         })
     }
 
     fn commit(&mut self) -> Result<AbciCommitInfo, ManyError> {
-        let result = self.storage.commit();
+        let result = self.storage.commit()?;
 
         info!(
             "abci.commit(): retain_height={} hash={}",
@@ -193,30 +353,59 @@ impl KvStoreModuleBackend for KvStoreModuleImpl {
         Ok(InfoReturns { hash: hash.into() })
     }
 
-    fn get(&self, _sender: &Address, args: GetArgs) -> Result<GetReturns, ManyError> {
+    fn get(
+        &self,
+        sender: &Address,
+        args: GetArgs,
+        context: Context,
+    ) -> Result<GetReturns, ManyError> {
+        self.verify_read(sender, &args.key)?;
         let value = self.storage.get(&args.key)?;
+        self.storage
+            .prove_keys_state(context, [args.key.as_slice()])?;
         Ok(GetReturns {
             value: value.map(|x| x.into()),
         })
     }
 
-    fn query(&self, _sender: &Address, args: QueryArgs) -> Result<QueryReturns, ManyError> {
-        minicbor::decode(
-            &self
-                .storage
-                .get_metadata(&args.key)?
-                .ok_or_else(error::key_not_found)?,
-        )
-        .map_err(|e| ManyError::deserialization_error(e.to_string()))
+    fn query(
+        &self,
+        sender: &Address,
+        args: QueryArgs,
+        context: Context,
+    ) -> Result<QueryReturns, ManyError> {
+        let metadata = self.metadata(&args.key)?.ok_or_else(error::key_not_found)?;
+        // Anyone can query the metadata, but the proof includes the value.
+        context.prove(|| {
+            self.verify_read(sender, &args.key)?;
+            self.storage.prove_keys([args.key.as_slice()])
+        })?;
+        Ok(metadata.into())
+    }
+
+    fn list(&self, sender: &Address, args: ListArgs) -> Result<ListReturns, ManyError> {
+        let mut keys = Vec::new();
+        for item in self
+            .storage
+            .list(args.order.unwrap_or_default(), args.filter)
+        {
+            let key = item.into_iter().skip(1).collect::<Vec<_>>(); // Skip the delimiter
+            if self.can_read(sender, &key)? {
+                keys.push(key.into());
+            }
+        }
+        Ok(ListReturns { keys })
     }
 
-    fn list(&self, _sender: &Address, args: ListArgs) -> Result<ListReturns, ManyError> {
-        Ok(ListReturns {
-            keys: self
-                .storage
-                .list(args.order.unwrap_or_default(), args.filter)
-                .map(|item| item.into_iter().skip(1).collect::<Vec<_>>().into()) // Skip the delimiter
-                .collect(),
+    fn prove(&self, sender: &Address, args: ProveArgs) -> Result<ProveReturns, ManyError> {
+        for key in &args.keys {
+            self.verify_read(sender, key)?;
+        }
+        let keys = BTreeSet::from_iter(args.keys.iter().map(|key| key.as_slice()));
+        let proof = self.storage.prove_keys(keys)?;
+        Ok(ProveReturns {
+            proof: proof.into(),
+            hash: self.storage.hash().into(),
         })
     }
 }
@@ -227,6 +416,7 @@ impl KvStoreCommandsModuleBackend for KvStoreModuleImpl {
             key,
             value,
             alternative_owner,
+            roles,
         } = args;
         let owner = if let Some(alternative_owner) = alternative_owner {
             self.validate_alternative_owner(
@@ -239,14 +429,41 @@ impl KvStoreCommandsModuleBackend for KvStoreModuleImpl {
             *sender
         };
 
-        self.verify_acl(&owner, &key)?;
+        let required = if roles.is_some() {
+            KeyRole::Owner
+        } else {
+            KeyRole::Writer
+        };
+        let previous = self.verify_acl(&owner, &key, required)?;
 
-        let meta = KvStoreMetadata {
-            owner,
-            disabled: Some(Either::Left(false)),
-            previous_owner: None,
+        // Putting to a disabled key enables it again, which only owners can do.
+        if let Some(previous) = &previous {
+            if previous.is_disabled(self.storage.now()) && !previous.allows(&owner, KeyRole::Owner)
+            {
+                return Err(error::key_disabled());
+            }
+        }
+
+        // Writers and owners put values without becoming the only owner.
+        let meta = match (previous, roles) {
+            (Some(previous), roles) => KvStoreMetadata {
+                roles: roles.unwrap_or(previous.roles),
+                disabled: Some(Either::Left(false)),
+                previous_owner: previous.previous_owner,
+                disabled_until: None,
+            },
+            (None, roles) => {
+                let mut meta = KvStoreMetadata::new(owner);
+                for (address, role) in roles.unwrap_or_default() {
+                    meta.roles.entry(address).or_insert(role);
+                }
+                meta
+            }
         };
-        self.storage.put(&meta, &key, value.into())?;
+        if meta.owners().next().is_none() {
+            return Err(error::key_without_owner());
+        }
+        self.storage.put(&meta, &key, value.into(), owner)?;
         Ok(PutReturn {})
     }
 
@@ -255,10 +472,14 @@ impl KvStoreCommandsModuleBackend for KvStoreModuleImpl {
             key,
             alternative_owner,
             reason,
+            until,
         } = args;
         if self.storage.get(&key)?.is_none() {
             return Err(error::cannot_disable_empty_key());
         }
+        if matches!(until, Some(until) if until <= self.storage.now()) {
+            return Err(error::disable_until_in_past());
+        }
         let owner = if let Some(ref alternative_owner) = alternative_owner {
             self.validate_alternative_owner(
                 sender,
@@ -270,7 +491,7 @@ impl KvStoreCommandsModuleBackend for KvStoreModuleImpl {
             sender
         };
 
-        self.verify_acl(owner, &key)?;
+        let previous = self.verify_acl(owner, &key, KeyRole::Owner)?;
 
         let maybe_reason = if let Some(reason) = reason {
             Either::Right(reason)
@@ -279,14 +500,48 @@ impl KvStoreCommandsModuleBackend for KvStoreModuleImpl {
         };
 
         let meta = KvStoreMetadata {
-            owner: *owner,
             disabled: Some(maybe_reason),
             previous_owner: None,
+            disabled_until: until,
+            ..previous.unwrap_or_else(|| KvStoreMetadata::new(*owner))
         };
 
         self.storage.disable(&meta, &key)?;
         Ok(DisableReturn {})
     }
+
+    fn enable(&mut self, sender: &Address, args: EnableArgs) -> Result<EnableReturn, ManyError> {
+        let EnableArgs {
+            key,
+            alternative_owner,
+        } = args;
+        let metadata = self.metadata(&key)?.ok_or_else(error::key_not_found)?;
+        if !metadata.is_disabled(self.storage.now()) {
+            return Err(error::key_not_disabled());
+        }
+
+        let owner = if let Some(ref alternative_owner) = alternative_owner {
+            self.validate_alternative_owner(
+                sender,
+                alternative_owner,
+                [Role::CanKvStoreDisable, Role::Owner],
+            )?;
+            alternative_owner
+        } else {
+            sender
+        };
+
+        self.verify_acl(owner, &key, KeyRole::Owner)?;
+
+        let meta = KvStoreMetadata {
+            disabled: Some(Either::Left(false)),
+            disabled_until: None,
+            ..metadata
+        };
+
+        self.storage.enable(&meta, &key, owner)?;
+        Ok(EnableReturn {})
+    }
 }
 
 impl KvStoreTransferModuleBackend for KvStoreModuleImpl {
@@ -303,13 +558,7 @@ impl KvStoreTransferModuleBackend for KvStoreModuleImpl {
         }
 
         let key: Vec<u8> = args.key.into();
-        let metadata: KvStoreMetadata = minicbor::decode(
-            &self
-                .storage
-                .get_metadata(&key)?
-                .ok_or_else(error::key_not_found)?,
-        )
-        .map_err(|e| ManyError::deserialization_error(e.to_string()))?;
+        let metadata = self.metadata(&key)?.ok_or_else(error::key_not_found)?;
 
         let owner = if let Some(ref alternative_owner) = args.alternative_owner {
             self.validate_alternative_owner(
@@ -322,15 +571,19 @@ impl KvStoreTransferModuleBackend for KvStoreModuleImpl {
             sender
         };
 
-        self.verify_acl(owner, &key)?;
+        self.verify_acl(owner, &key, KeyRole::Owner)?;
 
+        // The owner transferring the key gives its role to the new owner.
         // We allow transferring a disabled key, and keep the same reason.
+        let mut roles = metadata.roles;
+        roles.remove(owner);
+        roles.insert(args.new_owner, KeyRole::Owner);
         let meta = KvStoreMetadata {
-            owner: args.new_owner,
-            disabled: metadata.disabled,
-            previous_owner: Some(metadata.owner),
+            roles,
+            previous_owner: Some(*owner),
+            ..metadata
         };
-        self.storage.transfer(&key, *owner, meta)?;
+        self.storage.transfer(&key, *owner, args.new_owner, meta)?;
 
         Ok(TransferReturn {})
     }
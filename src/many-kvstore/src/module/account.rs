@@ -1,5 +1,5 @@
-use super::{error, KvStoreMetadata, KvStoreModuleImpl};
-use coset::CoseSign1;
+use super::{error, KeyRole, KvStoreMetadata, KvStoreModuleImpl};
+use coset::{CoseSign, CoseSign1};
 use many_error::{ManyError, ManyErrorCode};
 use many_identity::Address;
 use many_modules::account::features::{FeatureInfo, TryCreateFeature};
@@ -7,9 +7,12 @@ use many_modules::account::{AccountModuleBackend, Role};
 use many_modules::{account, EmptyReturn, ManyModule, ManyModuleInfo};
 use many_protocol::{context::Context, RequestMessage, ResponseMessage};
 use many_types::cbor::CborAny;
+use many_types::BlockTime;
 use std::collections::BTreeSet;
 use std::fmt::{Debug, Formatter};
 
+const MAXIMUM_SUBRESOURCE_COUNT: usize = 100;
+
 pub(crate) fn validate_account(account: &account::Account) -> Result<(), ManyError> {
     // Verify that we support all features.
     validate_features_for_account(account)?;
@@ -111,8 +114,31 @@ impl<T: AccountModuleBackend> ManyModule for AccountFeatureModule<T> {
         self.inner.validate(message, envelope)
     }
 
+    fn validate_multi(
+        &self,
+        message: &RequestMessage,
+        envelope: &CoseSign,
+        signers: &BTreeSet<Address>,
+    ) -> Result<(), ManyError> {
+        self.inner.validate_multi(message, envelope, signers)
+    }
+
     async fn execute(&self, message: RequestMessage) -> Result<ResponseMessage, ManyError> {
-        self.inner.execute(message).await
+        self.execute_with_block_time(message, None).await
+    }
+
+    async fn execute_with_block_time(
+        &self,
+        message: RequestMessage,
+        block_time: Option<BlockTime>,
+    ) -> Result<ResponseMessage, ManyError> {
+        self.inner
+            .execute_with_block_time(message, block_time)
+            .await
+    }
+
+    fn heartbeat(&self) -> Result<(), ManyError> {
+        self.inner.heartbeat()
     }
 }
 
@@ -150,6 +176,24 @@ impl AccountModuleBackend for KvStoreModuleImpl {
             .map(|_| EmptyReturn)
     }
 
+    fn set_metadata(
+        &mut self,
+        sender: &Address,
+        args: account::SetMetadataArgs,
+    ) -> Result<EmptyReturn, ManyError> {
+        let (account, _) = self.storage.get_account(&args.account);
+        let account = account.ok_or_else(|| account::errors::unknown_account(args.account))?;
+
+        if !account.has_role(sender, account::Role::Owner) {
+            return Err(account::errors::user_needs_role("owner"));
+        }
+        account::validate_metadata(&args.metadata)?;
+
+        self.storage
+            .set_metadata(account, args)
+            .map(|_| EmptyReturn)
+    }
+
     fn list_roles(
         &self,
         _: &Address,
@@ -237,6 +281,7 @@ impl AccountModuleBackend for KvStoreModuleImpl {
                      roles,
                      features,
                      disabled,
+                     metadata,
                  }| {
                     self.storage
                         .prove_state(context, vec![account_key])
@@ -245,6 +290,7 @@ impl AccountModuleBackend for KvStoreModuleImpl {
                             roles,
                             features,
                             disabled,
+                            metadata,
                         })
                 },
             )
@@ -285,6 +331,38 @@ impl AccountModuleBackend for KvStoreModuleImpl {
             })
         }
     }
+
+    fn list_subresources(
+        &self,
+        _: &Address,
+        args: account::ListSubresourcesArgs,
+        context: Context,
+    ) -> Result<account::ListSubresourcesReturn, ManyError> {
+        let count = args.count.map_or(MAXIMUM_SUBRESOURCE_COUNT, |c| {
+            std::cmp::min(c as usize, MAXIMUM_SUBRESOURCE_COUNT)
+        });
+        let (subresources, next_subresource, keys) =
+            self.storage
+                .list_subresources(&args.account, args.start.unwrap_or_default(), count)?;
+
+        self.storage
+            .prove_state(context, keys)
+            .map(|_| account::ListSubresourcesReturn {
+                subresources,
+                next_subresource,
+            })
+    }
+
+    /// The kvstore does not index its accounts by the identities holding
+    /// roles in them.
+    fn list_for_identity(
+        &self,
+        _: &Address,
+        _: account::ListForIdentityArgs,
+        _: Context,
+    ) -> Result<account::ListForIdentityReturn, ManyError> {
+        Err(ManyError::invalid_method_name("account.listForIdentity"))
+    }
 }
 
 impl KvStoreModuleImpl {
@@ -309,20 +387,47 @@ impl KvStoreModuleImpl {
         }
     }
 
-    /// Verify if user is permitted to access the value at the given key
-    pub(crate) fn verify_acl(&self, sender: &Address, key: &[u8]) -> Result<(), ManyError> {
-        // Get ACL, if it exists
-        if let Some(meta_cbor) = self.storage.get_metadata(key)? {
-            // Decode ACL
-            let meta: KvStoreMetadata = minicbor::decode(&meta_cbor)
-                .map_err(|e| ManyError::deserialization_error(e.to_string()))?;
+    /// The metadata of a key, if it exists.
+    pub(crate) fn metadata(&self, key: &[u8]) -> Result<Option<KvStoreMetadata>, ManyError> {
+        self.storage
+            .get_metadata(key)?
+            .map(|meta_cbor| {
+                minicbor::decode(&meta_cbor)
+                    .map_err(|e| ManyError::deserialization_error(e.to_string()))
+            })
+            .transpose()
+    }
 
-            if &meta.owner == sender {
-                return Ok(());
-            }
+    /// Verify if user has a role on the key including `role`, and return its
+    /// current metadata. Anyone can create a key which doesn't exist.
+    pub(crate) fn verify_acl(
+        &self,
+        sender: &Address,
+        key: &[u8],
+        role: KeyRole,
+    ) -> Result<Option<KvStoreMetadata>, ManyError> {
+        match self.metadata(key)? {
+            Some(meta) if !meta.allows(sender, role) => Err(error::permission_denied()),
+            meta => Ok(meta),
+        }
+    }
+
+    /// Whether the user can read the value at the given key, list it or get
+    /// its proofs. Keys with readers can only be read by the addresses with a
+    /// role.
+    pub(crate) fn can_read(&self, sender: &Address, key: &[u8]) -> Result<bool, ManyError> {
+        Ok(match self.metadata(key)? {
+            Some(meta) => !meta.is_read_restricted() || meta.allows(sender, KeyRole::Reader),
+            None => true,
+        })
+    }
 
-            return Err(error::permission_denied());
+    /// Verify if user can read the value at the given key, or its proofs.
+    pub(crate) fn verify_read(&self, sender: &Address, key: &[u8]) -> Result<(), ManyError> {
+        if self.can_read(sender, key)? {
+            Ok(())
+        } else {
+            Err(error::permission_denied())
         }
-        Ok(())
     }
 }
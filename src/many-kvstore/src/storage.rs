@@ -2,28 +2,27 @@ use crate::module::{KvStoreMetadata, KvStoreMetadataWrapper};
 use many_error::ManyError;
 use many_identity::Address;
 use many_modules::abci_backend::AbciCommitInfo;
+use many_modules::account::Subresource;
 use many_modules::events::EventInfo;
-use many_types::{Either, ProofOperation, SortOrder, Timestamp};
-use merk::{
-    proofs::{
-        Decoder,
-        Node::{Hash, KVHash, KV},
-        Query,
-    },
-    BatchEntry, Op,
-};
+use many_types::{BlockTime, Either, ProofOperation, SortOrder, Timestamp};
+use merk::{proofs::Query, BatchEntry, Op};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::path::Path;
 
 mod account;
 mod event;
+#[cfg(feature = "fault_testing")]
+pub mod fault;
 pub mod iterator;
+mod maintenance;
+mod transaction;
 
 use crate::error;
 use crate::storage::iterator::KvStoreIterator;
+use crate::storage::transaction::{JournaledStore, TransactionState};
 use event::EventId;
-use many_modules::kvstore::KeyFilterType;
+use many_modules::kvstore::{KeyFilterType, KeyRole};
 
 const KVSTORE_ROOT: &[u8] = b"s";
 const KVSTORE_ACL_ROOT: &[u8] = b"a";
@@ -38,7 +37,7 @@ pub struct Key {
 pub type AclMap = BTreeMap<Key, KvStoreMetadataWrapper>;
 
 pub struct KvStoreStorage {
-    persistent_store: merk::Merk,
+    persistent_store: JournaledStore,
 
     /// When this is true, we do not commit every transactions as they come,
     /// but wait for a `commit` call before committing the batch to the
@@ -46,10 +45,19 @@ pub struct KvStoreStorage {
     blockchain: bool,
 
     latest_event_id: EventId,
-    current_time: Option<Timestamp>,
+
+    /// The latest event ID of the last committed block, and the digest of the
+    /// events of that block. The digest is only known for blocks committed
+    /// since the storage was opened.
+    block_start_event_id: EventId,
+    events_digest: Option<Vec<u8>>,
+
+    current_time: Option<BlockTime>,
     current_hash: Option<Vec<u8>>,
     next_subresource: u32,
     root_identity: Address,
+
+    transaction: Option<TransactionState>,
 }
 
 impl std::fmt::Debug for KvStoreStorage {
@@ -58,31 +66,39 @@ impl std::fmt::Debug for KvStoreStorage {
     }
 }
 
-fn filter_key(filter: &KeyFilterType, _key: &[u8], meta: &KvStoreMetadata) -> bool {
+fn filter_key(filter: &KeyFilterType, _key: &[u8], meta: &KvStoreMetadata, now: Timestamp) -> bool {
     match filter {
-        KeyFilterType::Owner(address) => &meta.owner == address,
+        KeyFilterType::Owner(address) => meta.allows(address, KeyRole::Owner),
         KeyFilterType::PreviousOwner(address) => &meta.previous_owner == address,
-        KeyFilterType::Disabled(disabled) => {
-            if *disabled {
-                matches!(
-                    meta.disabled,
-                    Some(Either::Left(true)) | Some(Either::Right(_))
-                )
-            } else {
-                matches!(meta.disabled, None | Some(Either::Left(false)))
-            }
-        }
+        KeyFilterType::Disabled(disabled) => meta.is_disabled(now) == *disabled,
     }
 }
 
 impl KvStoreStorage {
     #[inline]
-    pub fn set_time(&mut self, time: Timestamp) {
+    pub fn set_time(&mut self, time: BlockTime) {
         self.current_time = Some(time);
     }
     #[inline]
     pub fn now(&self) -> Timestamp {
-        self.current_time.unwrap_or_else(Timestamp::now)
+        self.current_time.map_or_else(Timestamp::now, Into::into)
+    }
+
+    /// Commit the writes to disk outside of blockchain mode. The writes of a
+    /// transaction are committed when it ends, so the writes it rolls back
+    /// never reach the disk.
+    fn maybe_commit(&mut self) -> Result<(), ManyError> {
+        if !self.blockchain && self.transaction.is_none() {
+            self.commit_storage()
+        } else {
+            Ok(())
+        }
+    }
+
+    fn commit_storage(&mut self) -> Result<(), ManyError> {
+        self.persistent_store
+            .commit(&[])
+            .map_err(error::storage_commit_failed)
     }
 
     pub fn new_subresource_id(&mut self) -> Result<(Address, Vec<u8>), ManyError> {
@@ -101,6 +117,36 @@ impl KvStoreStorage {
             .map(|address| (address, key))
     }
 
+    /// List up to `count` subresources allocated under an address, starting
+    /// at the subresource ID `start`, and the next subresource ID to be
+    /// allocated, and the keys to prove. Only the root identity allocates
+    /// subresources. Creation heights are not recorded by the kvstore.
+    pub fn list_subresources(
+        &self,
+        address: &Address,
+        start: u32,
+        count: usize,
+    ) -> Result<(Vec<Subresource>, u32, Vec<Vec<u8>>), ManyError> {
+        if address != &self.root_identity {
+            return Ok((vec![], 0, vec![]));
+        }
+
+        let subresources = (start..self.next_subresource)
+            .take(count)
+            .map(|id| {
+                address.with_subresource_id(id).map(|address| Subresource {
+                    address,
+                    height: None,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok((
+            subresources,
+            self.next_subresource,
+            vec![b"/config/subresource_id".to_vec()],
+        ))
+    }
+
     pub fn load<P: AsRef<Path>>(persistent_path: P, blockchain: bool) -> Result<Self, String> {
         let persistent_store = merk::Merk::open(persistent_path).map_err(|e| e.to_string())?;
 
@@ -130,13 +176,16 @@ impl KvStoreStorage {
         .map_err(|e| e.to_string())?;
 
         Ok(Self {
-            persistent_store,
+            persistent_store: JournaledStore::new(persistent_store),
             blockchain,
             current_time: None,
             current_hash: None,
+            block_start_event_id: latest_event_id.clone(),
+            events_digest: None,
             latest_event_id,
             next_subresource,
             root_identity,
+            transaction: None,
         })
     }
 
@@ -175,25 +224,28 @@ impl KvStoreStorage {
         persistent_store.commit(&[]).map_err(|e| e.to_string())?;
 
         Ok(Self {
-            persistent_store,
+            persistent_store: JournaledStore::new(persistent_store),
             blockchain,
             current_time: None,
             current_hash: None,
+            block_start_event_id: latest_event_id.clone(),
+            events_digest: None,
             latest_event_id,
             next_subresource: 0,
             root_identity: identity,
+            transaction: None,
         })
     }
 
-    fn inc_height(&mut self) -> u64 {
+    fn inc_height(&mut self) -> Result<u64, ManyError> {
         let current_height = self.get_height();
         self.persistent_store
             .apply(&[(
                 b"/height".to_vec(),
                 Op::Put((current_height + 1).to_be_bytes().to_vec()),
             )])
-            .unwrap();
-        current_height
+            .map_err(error::storage_apply_failed)?;
+        Ok(current_height)
     }
 
     pub fn get_height(&self) -> u64 {
@@ -207,26 +259,55 @@ impl KvStoreStorage {
             })
     }
 
-    pub fn commit(&mut self) -> AbciCommitInfo {
-        let _ = self.inc_height();
-        self.persistent_store
-            .apply(&[(
-                b"/latest_event_id".to_vec(),
-                Op::Put(
-                    minicbor::to_vec(&self.latest_event_id).expect("Unable to encode event id"),
-                ),
-            )])
-            .unwrap();
-        self.persistent_store.commit(&[]).unwrap();
+    /// Write the height and the latest event ID of the block. On error the
+    /// state of the block is left as it was.
+    fn write_block(&mut self) -> Result<(), ManyError> {
+        self.begin_transaction()?;
+        let result = (|| {
+            self.inc_height()?;
+            self.persistent_store
+                .apply(&[(
+                    b"/latest_event_id".to_vec(),
+                    Op::Put(
+                        minicbor::to_vec(&self.latest_event_id).expect("Unable to encode event id"),
+                    ),
+                )])
+                .map_err(error::storage_apply_failed)?;
+            self.commit_storage()
+        })();
+
+        match result {
+            Ok(()) => self.commit_transaction(),
+            Err(e) => {
+                self.rollback_transaction()?;
+                Err(e)
+            }
+        }
+    }
+
+    pub fn commit(&mut self) -> Result<AbciCommitInfo, ManyError> {
+        self.write_block()?;
+
+        // Events are only visible to iterators once committed.
+        let events_digest = self.block_events_digest();
+        self.events_digest = Some(events_digest.clone());
+        self.block_start_event_id = self.latest_event_id.clone();
 
         let retain_height = 0;
         let hash = self.persistent_store.root_hash().to_vec();
         self.current_hash = Some(hash.clone());
 
-        AbciCommitInfo {
+        Ok(AbciCommitInfo {
             retain_height,
             hash: hash.into(),
-        }
+            events_digest: Some(events_digest.into()),
+        })
+    }
+
+    /// The digest of the events emitted during the last committed block, if
+    /// it was committed since the storage was opened.
+    pub fn events_digest(&self) -> Option<Vec<u8>> {
+        self.events_digest.clone()
     }
 
     pub fn hash(&self) -> Vec<u8> {
@@ -246,11 +327,8 @@ impl KvStoreStorage {
             let meta: KvStoreMetadata = minicbor::decode(&cbor)
                 .map_err(|e| ManyError::deserialization_error(e.to_string()))?;
 
-            if let Some(either) = meta.disabled {
-                match either {
-                    Either::Left(false) => {}
-                    _ => return Err(error::key_disabled()),
-                }
+            if meta.is_disabled(self.now()) {
+                return Err(error::key_disabled());
             }
         }
         self._get(key, KVSTORE_ROOT)
@@ -261,12 +339,13 @@ impl KvStoreStorage {
         order: SortOrder,
         filter: Option<Vec<KeyFilterType>>,
     ) -> impl Iterator<Item = Vec<u8>> + '_ {
+        let now = self.now();
         KvStoreIterator::all_keys(&self.persistent_store, order).filter_map(move |item| {
             let (k, v) = item.ok()?;
             if let Some(filters) = &filter {
                 if !filters.is_empty() {
                     let meta: KvStoreMetadata = minicbor::decode(&v).ok()?;
-                    if filters.iter().all(|f| filter_key(f, &k, &meta)) {
+                    if filters.iter().all(|f| filter_key(f, &k, &meta, now)) {
                         return Some(k.into_vec());
                     } else {
                         return None;
@@ -281,11 +360,14 @@ impl KvStoreStorage {
         self._get(key, KVSTORE_ACL_ROOT)
     }
 
+    /// Put a value, written by `owner`. The values of keys with readers are
+    /// left out of the event, which anyone can list.
     pub fn put(
         &mut self,
         meta: &KvStoreMetadata,
         key: &[u8],
         value: Vec<u8>,
+        owner: Address,
     ) -> Result<(), ManyError> {
         self.persistent_store
             .apply(&[
@@ -303,16 +385,18 @@ impl KvStoreStorage {
             ])
             .map_err(|e| ManyError::unknown(e.to_string()))?;
 
+        let value = if meta.is_read_restricted() {
+            vec![]
+        } else {
+            value
+        };
         self.log_event(EventInfo::KvStorePut {
             key: key.to_vec().into(),
             value: value.into(),
-            owner: meta.owner,
-        });
+            owner,
+        })?;
 
-        if !self.blockchain {
-            self.persistent_store.commit(&[]).unwrap();
-        }
-        Ok(())
+        self.maybe_commit()
     }
 
     pub fn disable(&mut self, meta: &KvStoreMetadata, key: &[u8]) -> Result<(), ManyError> {
@@ -338,21 +422,43 @@ impl KvStoreStorage {
         self.log_event(EventInfo::KvStoreDisable {
             key: key.to_vec().into(),
             reason: reason.cloned(),
-        });
+            until: meta.disabled_until,
+        })?;
 
-        if !self.blockchain {
-            self.persistent_store.commit(&[]).unwrap();
-        }
-        Ok(())
+        self.maybe_commit()
+    }
+
+    pub fn enable(
+        &mut self,
+        meta: &KvStoreMetadata,
+        key: &[u8],
+        owner: &Address,
+    ) -> Result<(), ManyError> {
+        self.persistent_store
+            .apply(&[(
+                [KVSTORE_ACL_ROOT.to_vec(), key.to_vec()].concat(),
+                Op::Put(
+                    minicbor::to_vec(meta)
+                        .map_err(|e| ManyError::serialization_error(e.to_string()))?,
+                ),
+            )])
+            .map_err(|e| ManyError::unknown(e.to_string()))?;
+
+        self.log_event(EventInfo::KvStoreEnable {
+            key: key.to_vec().into(),
+            owner: *owner,
+        })?;
+
+        self.maybe_commit()
     }
 
     pub fn transfer(
         &mut self,
         key: &[u8],
         previous_owner: Address,
+        new_owner: Address,
         meta: KvStoreMetadata,
     ) -> Result<(), ManyError> {
-        let new_owner = meta.owner;
         self.persistent_store
             .apply(&[(
                 [KVSTORE_ACL_ROOT.to_vec(), key.to_vec()].concat(),
@@ -367,12 +473,33 @@ impl KvStoreStorage {
             key: key.to_vec().into(),
             owner: previous_owner,
             new_owner,
-        });
+        })?;
 
-        if !self.blockchain {
-            self.persistent_store.commit(&[]).unwrap();
-        }
-        Ok(())
+        self.maybe_commit()
+    }
+
+    pub fn prove(
+        &self,
+        keys: impl IntoIterator<Item = Vec<u8>>,
+    ) -> Result<Vec<ProofOperation>, ManyError> {
+        many_proof::merk_prove!(self.persistent_store, {
+            let mut query = Query::new();
+            keys.into_iter().for_each(|key| query.insert_key(key));
+            query
+        })
+    }
+
+    /// Prove the values and metadata of user keys.
+    pub fn prove_keys<'a>(
+        &self,
+        keys: impl IntoIterator<Item = &'a [u8]>,
+    ) -> Result<Vec<ProofOperation>, ManyError> {
+        self.prove(keys.into_iter().flat_map(|key| {
+            [
+                [KVSTORE_ROOT, key].concat(),
+                [KVSTORE_ACL_ROOT, key].concat(),
+            ]
+        }))
     }
 
     pub fn prove_state(
@@ -380,32 +507,16 @@ impl KvStoreStorage {
         context: impl AsRef<many_protocol::context::Context>,
         keys: impl IntoIterator<Item = Vec<u8>>,
     ) -> Result<(), ManyError> {
-        use merk::proofs::Op;
-        context.as_ref().prove(|| {
-            self.persistent_store
-                .prove({
-                    let mut query = Query::new();
-                    keys.into_iter().for_each(|key| query.insert_key(key));
-                    query
-                })
-                .and_then(|proof| {
-                    Decoder::new(proof.as_slice())
-                        .map(|fallible_operation| {
-                            fallible_operation.map(|operation| match operation {
-                                Op::Child => ProofOperation::Child,
-                                Op::Parent => ProofOperation::Parent,
-                                Op::Push(Hash(hash)) => ProofOperation::NodeHash(hash.to_vec()),
-                                Op::Push(KV(key, value)) => {
-                                    ProofOperation::KeyValuePair(key.into(), value.into())
-                                }
-                                Op::Push(KVHash(hash)) => {
-                                    ProofOperation::KeyValueHash(hash.to_vec())
-                                }
-                            })
-                        })
-                        .collect::<Result<Vec<_>, _>>()
-                })
-                .map_err(|error| ManyError::unknown(error.to_string()))
-        })
+        context.as_ref().prove(|| self.prove(keys))
+    }
+
+    /// Send a proof of the values and metadata of user keys, if the request
+    /// asked for one.
+    pub fn prove_keys_state<'a>(
+        &self,
+        context: impl AsRef<many_protocol::context::Context>,
+        keys: impl IntoIterator<Item = &'a [u8]>,
+    ) -> Result<(), ManyError> {
+        context.as_ref().prove(|| self.prove_keys(keys))
     }
 }
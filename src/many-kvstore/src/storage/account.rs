@@ -27,7 +27,7 @@ impl KvStoreStorage {
                 description: account.clone().description,
                 roles: account.clone().roles,
                 features: account.clone().features,
-            });
+            })?;
         }
 
         self.commit_account(&id, account)
@@ -80,7 +80,20 @@ impl KvStoreStorage {
         self.log_event(events::EventInfo::AccountSetDescription {
             account: args.account,
             description: args.description,
-        });
+        })?;
+        self.commit_account(&args.account, account)
+    }
+
+    pub fn set_metadata(
+        &mut self,
+        mut account: account::Account,
+        args: account::SetMetadataArgs,
+    ) -> Result<Vec<u8>, ManyError> {
+        account.set_metadata(args.metadata.clone());
+        self.log_event(events::EventInfo::AccountSetMetadata {
+            account: args.account,
+            metadata: args.metadata,
+        })?;
         self.commit_account(&args.account, account)
     }
 
@@ -98,7 +111,7 @@ impl KvStoreStorage {
         self.log_event(events::EventInfo::AccountAddRoles {
             account: args.account,
             roles: args.clone().roles,
-        });
+        })?;
         self.commit_account(&args.account, account)
     }
 
@@ -127,7 +140,7 @@ impl KvStoreStorage {
         self.log_event(events::EventInfo::AccountRemoveRoles {
             account: args.account,
             roles: args.clone().roles,
-        });
+        })?;
         self.commit_account(&args.account, account)
     }
 
@@ -155,7 +168,7 @@ impl KvStoreStorage {
             account: args.account,
             roles: args.clone().roles.unwrap_or_default(), // TODO: Verify this
             features: args.clone().features,
-        });
+        })?;
         self.commit_account(&args.account, account)
     }
 
@@ -176,11 +189,7 @@ impl KvStoreStorage {
             )])
             .map_err(|e| ManyError::unknown(e.to_string()))?;
 
-        if !self.blockchain {
-            self.persistent_store
-                .commit(&[])
-                .expect("Could not commit to store.");
-        }
+        self.maybe_commit()?;
         Ok(key)
     }
 
@@ -194,13 +203,9 @@ impl KvStoreStorage {
         if account.disabled.is_none() || account.disabled == Some(Either::Left(false)) {
             account.disabled = Some(Either::Left(true));
             let commit_key = self.commit_account(id, account)?;
-            self.log_event(events::EventInfo::AccountDisable { account: *id });
+            self.log_event(events::EventInfo::AccountDisable { account: *id })?;
 
-            if !self.blockchain {
-                self.persistent_store
-                    .commit(&[])
-                    .map_err(ManyError::unknown)?;
-            }
+            self.maybe_commit()?;
 
             Ok(vec![account_key, commit_key])
         } else {
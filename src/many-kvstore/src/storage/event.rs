@@ -1,4 +1,6 @@
 use super::KvStoreStorage;
+use crate::error;
+use many_error::ManyError;
 use many_modules::events;
 use many_types::{CborRange, SortOrder};
 use merk::tree::Tree;
@@ -16,7 +18,7 @@ pub type EventId = events::EventId;
 const EVENT_ID_KEY_SIZE_IN_BYTES: usize = 32;
 
 /// Returns the storage key for an event in the kv-store.
-fn key_for_event(id: events::EventId) -> Vec<u8> {
+pub(super) fn key_for_event(id: events::EventId) -> Vec<u8> {
     let id = id.as_ref();
     let id = if id.len() > EVENT_ID_KEY_SIZE_IN_BYTES {
         &id[0..EVENT_ID_KEY_SIZE_IN_BYTES]
@@ -46,7 +48,7 @@ impl KvStoreStorage {
             })
     }
 
-    pub(crate) fn log_event(&mut self, content: events::EventInfo) {
+    pub(crate) fn log_event(&mut self, content: events::EventInfo) -> Result<(), ManyError> {
         let current_nb_events = self.nb_events();
         let event = events::EventLog {
             id: self.new_event_id(),
@@ -65,16 +67,30 @@ impl KvStoreStorage {
                     Op::Put((current_nb_events + 1).to_be_bytes().to_vec()),
                 ),
             ])
-            .unwrap();
+            .map_err(error::storage_apply_failed)?;
 
-        if !self.blockchain {
-            self.persistent_store.commit(&[]).unwrap();
-        }
+        self.maybe_commit()
     }
 
     pub fn iter(&self, range: CborRange<events::EventId>, order: SortOrder) -> KvStoreIterator {
         KvStoreIterator::scoped_by_id(&self.persistent_store, range, order)
     }
+
+    /// Returns the encoded events emitted since the last committed block, in
+    /// order.
+    pub fn block_events(&self) -> Vec<Vec<u8>> {
+        let range = CborRange {
+            start: Bound::Excluded(self.block_start_event_id.clone()),
+            end: Bound::Included(self.latest_event_id.clone()),
+        };
+        self.iter(range, SortOrder::Ascending)
+            .map(|item| item.map(|(_k, v)| v).unwrap())
+            .collect()
+    }
+
+    pub(super) fn block_events_digest(&self) -> Vec<u8> {
+        events::digest::events_digest(&self.block_events()).to_vec()
+    }
 }
 
 pub struct KvStoreIterator<'a> {
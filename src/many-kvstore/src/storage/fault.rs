@@ -0,0 +1,11 @@
+//! Failures injected in the persistent store, to test that the key-value store
+//! recovers or fails cleanly when the store fails.
+use crate::storage::KvStoreStorage;
+
+pub use many_server::transaction::Faults;
+
+impl KvStoreStorage {
+    pub fn faults(&mut self) -> &mut Faults {
+        self.persistent_store.faults()
+    }
+}
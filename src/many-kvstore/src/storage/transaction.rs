@@ -0,0 +1,102 @@
+use crate::error;
+use crate::storage::event::{key_for_event, EventId};
+use crate::storage::KvStoreStorage;
+use many_error::ManyError;
+use many_server::transaction::{StoreBackend, StoreError};
+use merk::{BatchEntry, Op};
+
+/// The merk store of the key-value store.
+pub struct MerkBackend;
+
+impl StoreBackend for MerkBackend {
+    type Store = merk::Merk;
+    type Op = Op;
+
+    fn get(store: &merk::Merk, key: &[u8]) -> Result<Option<Vec<u8>>, StoreError> {
+        store.get(key).map_err(StoreError::new)
+    }
+
+    fn apply(store: &mut merk::Merk, batch: &[BatchEntry]) -> Result<(), StoreError> {
+        store.apply(batch).map_err(StoreError::new)
+    }
+
+    fn commit(store: &mut merk::Merk, aux: &[BatchEntry]) -> Result<(), StoreError> {
+        store.commit(aux).map_err(StoreError::new)
+    }
+
+    fn restore(previous: Option<Vec<u8>>) -> Op {
+        previous.map_or(Op::Delete, Op::Put)
+    }
+}
+
+/// The persistent store of the key-value store.
+pub type JournaledStore = many_server::transaction::JournaledStore<MerkBackend>;
+
+/// The state of a transaction which is not in the store.
+pub(super) struct TransactionState {
+    latest_event_id: EventId,
+    next_subresource: u32,
+}
+
+impl KvStoreStorage {
+    pub fn begin_transaction(&mut self) -> Result<(), ManyError> {
+        if self.transaction.is_some() {
+            return Err(ManyError::unknown("A transaction is already open."));
+        }
+        self.persistent_store.begin();
+        self.transaction = Some(TransactionState {
+            latest_event_id: self.latest_event_id.clone(),
+            next_subresource: self.next_subresource,
+        });
+        Ok(())
+    }
+
+    pub fn commit_transaction(&mut self) -> Result<(), ManyError> {
+        self.transaction
+            .take()
+            .ok_or_else(|| ManyError::unknown("No open transaction."))?;
+        self.persistent_store.end();
+        // The writes of the transaction are only committed once it ends.
+        self.maybe_commit()
+    }
+
+    /// The encoded events logged since the start of the transaction, in
+    /// order. They are read by ID, as iterators only see committed events.
+    pub fn transaction_events(&self) -> Result<Vec<Vec<u8>>, ManyError> {
+        let TransactionState {
+            latest_event_id, ..
+        } = self
+            .transaction
+            .as_ref()
+            .ok_or_else(|| ManyError::unknown("No open transaction."))?;
+        let mut id = latest_event_id.clone();
+        let mut events = Vec::new();
+        while id < self.latest_event_id {
+            id += 1;
+            let event = self
+                .persistent_store
+                .get(&key_for_event(id.clone()))
+                .map_err(error::storage_get_failed)?;
+            events.extend(event);
+        }
+        Ok(events)
+    }
+
+    /// Restore the keys written since the start of the transaction, and the
+    /// counters of events and subresources.
+    pub fn rollback_transaction(&mut self) -> Result<(), ManyError> {
+        let TransactionState {
+            latest_event_id,
+            next_subresource,
+        } = self
+            .transaction
+            .take()
+            .ok_or_else(|| ManyError::unknown("No open transaction."))?;
+        self.persistent_store
+            .undo()
+            .map_err(error::storage_apply_failed)?;
+        self.latest_event_id = latest_event_id;
+        self.next_subresource = next_subresource;
+        self.maybe_commit()
+    }
+}
@@ -522,3 +522,48 @@ fn empty_feature_add_features() {
     assert!(result.is_err());
     assert_many_err(result, account::errors::empty_feature());
 }
+
+#[test]
+/// Verify we can list the accounts allocated by the server
+fn list_subresources() {
+    let setup = setup_with_args(AccountType::KvStore);
+    let id = setup.id();
+    let a0 = setup
+        .module_impl_mut()
+        .create(&id, setup.args.clone())
+        .unwrap()
+        .id;
+    let a1 = setup
+        .module_impl_mut()
+        .create(&id, setup.args.clone())
+        .unwrap()
+        .id;
+
+    let list = |account: Address, start: Option<u32>| {
+        setup
+            .module_impl()
+            .list_subresources(
+                &id,
+                account::ListSubresourcesArgs {
+                    account,
+                    start,
+                    count: None,
+                },
+                Context::new(RequestMessage::default(), unbounded().0),
+            )
+            .unwrap()
+    };
+
+    let server = a0.public_key().unwrap();
+    let result = list(server, None);
+    assert_eq!(result.next_subresource, a1.subresource_id().unwrap() + 1);
+    assert_eq!(result.subresources.len(), result.next_subresource as usize);
+    assert!(result.subresources.iter().any(|s| s.address == a0));
+    assert!(result.subresources.iter().all(|s| s.height.is_none()));
+
+    let result = list(server, a1.subresource_id());
+    assert_eq!(result.subresources.len(), 1);
+    assert_eq!(result.subresources[0].address, a1);
+
+    assert!(list(identity(1), None).subresources.is_empty());
+}
@@ -1,3 +1,4 @@
+use async_channel::unbounded;
 use many_error::{ManyError, Reason};
 use many_identity::testing::identity;
 use many_identity::{Address, Identity};
@@ -9,10 +10,11 @@ use many_modules::account::features::FeatureInfo;
 use many_modules::account::{AccountModuleBackend, Role};
 use many_modules::kvstore::list::{ListArgs, ListReturns};
 use many_modules::kvstore::{
-    DisableArgs, DisableReturn, GetArgs, GetReturns, KeyFilterType, KvStoreCommandsModuleBackend,
-    KvStoreModuleBackend, PutArgs, QueryArgs, QueryReturns,
+    DisableArgs, DisableReturn, EnableArgs, EnableReturn, GetArgs, GetReturns, KeyFilterType,
+    KvStoreCommandsModuleBackend, KvStoreModuleBackend, PutArgs, QueryArgs, QueryReturns,
 };
-use many_types::SortOrder;
+use many_protocol::{context::Context, RequestMessage};
+use many_types::{SortOrder, Timestamp};
 use once_cell::sync::Lazy;
 use std::cell::{Ref, RefCell, RefMut};
 use std::collections::BTreeMap;
@@ -89,13 +91,18 @@ impl Setup {
                 key: key.into(),
                 value: value.into(),
                 alternative_owner: alt_owner,
+                roles: None,
             },
         )?;
         Ok(())
     }
 
     pub fn get(&self, sender: &Address, key: Vec<u8>) -> Result<GetReturns, ManyError> {
-        self.module_impl.get(sender, GetArgs { key: key.into() })
+        self.module_impl.get(
+            sender,
+            GetArgs { key: key.into() },
+            Context::new(RequestMessage::default(), unbounded().0),
+        )
     }
 
     pub fn list(
@@ -128,13 +135,51 @@ impl Setup {
                 key: key.into(),
                 alternative_owner: alt_owner,
                 reason,
+                until: None,
+            },
+        )
+    }
+
+    pub fn disable_until(
+        &mut self,
+        sender: &Address,
+        key: Vec<u8>,
+        until: u64,
+    ) -> Result<DisableReturn, ManyError> {
+        KvStoreCommandsModuleBackend::disable(
+            &mut self.module_impl,
+            sender,
+            DisableArgs {
+                key: key.into(),
+                alternative_owner: None,
+                reason: None,
+                until: Some(Timestamp::new(until)?),
+            },
+        )
+    }
+
+    pub fn enable(
+        &mut self,
+        sender: &Address,
+        key: Vec<u8>,
+        alt_owner: Option<Address>,
+    ) -> Result<EnableReturn, ManyError> {
+        KvStoreCommandsModuleBackend::enable(
+            &mut self.module_impl,
+            sender,
+            EnableArgs {
+                key: key.into(),
+                alternative_owner: alt_owner,
             },
         )
     }
 
     pub fn query(&self, sender: &Address, key: Vec<u8>) -> Result<QueryReturns, ManyError> {
-        self.module_impl
-            .query(sender, QueryArgs { key: key.into() })
+        self.module_impl.query(
+            sender,
+            QueryArgs { key: key.into() },
+            Context::new(RequestMessage::default(), unbounded().0),
+        )
     }
 }
 
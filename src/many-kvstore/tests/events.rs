@@ -33,6 +33,7 @@ fn list() {
         count: None,
         order: None,
         filter: None,
+        cursor: None,
     });
     assert!(result.is_ok());
     let list_return = result.unwrap();
@@ -62,6 +63,7 @@ fn list_filter_account() {
             account: Some(vec![account_id].into()),
             ..events::EventFilter::default()
         }),
+        cursor: None,
     });
     assert!(result.is_ok());
     let list_return = result.unwrap();
@@ -94,6 +96,7 @@ fn list_filter_kind() {
             kind: Some(vec![events::EventKind::KvStorePut].into()),
             ..events::EventFilter::default()
         }),
+        cursor: None,
     });
     assert!(result.is_ok());
     let list_return = result.unwrap();
@@ -124,6 +127,7 @@ fn list_filter_date() {
             }),
             ..events::EventFilter::default()
         }),
+        cursor: None,
     });
     assert!(result.is_ok());
     let list_return = result.unwrap();
@@ -145,6 +149,7 @@ fn list_filter_date() {
             }),
             ..events::EventFilter::default()
         }),
+        cursor: None,
     });
     assert!(result.is_ok());
     let list_return = result.unwrap();
@@ -1,14 +1,21 @@
 pub mod common;
 
 use crate::common::{setup, Setup};
+use async_channel::unbounded;
 use many_error::Reason;
 use many_identity::testing::identity;
 use many_identity::Address;
 use many_kvstore::error;
+use many_kvstore::module::KvStoreMetadata;
+use many_modules::events::{EventInfo, EventLog};
 use many_modules::kvstore::{
-    InfoArg, KeyFilterType, KvStoreModuleBackend, KvStoreTransferModuleBackend, TransferArgs,
+    GetArgs, InfoArg, KeyFilterType, KeyRole, KvStoreCommandsModuleBackend, KvStoreModuleBackend,
+    KvStoreTransferModuleBackend, ProveArgs, PutArgs, QueryArgs, QueryReturns, TransferArgs,
 };
-use many_types::{Either, SortOrder};
+use many_protocol::context::{Context, ProofResult};
+use many_protocol::RequestMessage;
+use many_server::simulation::Simulator;
+use many_types::{Either, ProofOperation, SortOrder, Timestamp, PROOF};
 use minicbor::bytes::ByteVec;
 use std::collections::BTreeMap;
 
@@ -135,6 +142,125 @@ fn put_disable_put() {
     assert_eq!(ByteVec::from(vec![3]), get_value);
 }
 
+#[test]
+fn put_roles() {
+    let mut setup = setup();
+    let id = setup.id;
+    let roles = BTreeMap::from([
+        (identity(1), KeyRole::Writer),
+        (identity(2), KeyRole::Reader),
+    ]);
+    let put_with_roles = |setup: &mut Setup, sender: &Address| {
+        setup.module_impl.put(
+            sender,
+            PutArgs {
+                key: vec![1].into(),
+                value: vec![2].into(),
+                alternative_owner: None,
+                roles: Some(roles.clone()),
+            },
+        )
+    };
+    assert!(put_with_roles(&mut setup, &id).is_ok());
+
+    // Writers can put, but not change the roles.
+    let put = setup.put(&identity(1), vec![1], vec![3], None);
+    assert!(put.is_ok());
+    let put = put_with_roles(&mut setup, &identity(1));
+    assert_eq!(put.unwrap_err().code(), error::permission_denied().code());
+
+    let query = setup.query(&id, vec![1]).unwrap();
+    assert_eq!(query.owner, id);
+    let mut expected = roles.clone();
+    expected.insert(id, KeyRole::Owner);
+    assert_eq!(query.roles, Some(expected));
+
+    // Only addresses with a role can read a key with readers.
+    for sender in [id, identity(1), identity(2)] {
+        let get_value = setup.get(&sender, vec![1]).unwrap().value.unwrap();
+        assert_eq!(ByteVec::from(vec![3]), get_value);
+    }
+    let get_value = setup.get(&identity(3), vec![1]);
+    assert_eq!(
+        get_value.unwrap_err().code(),
+        error::permission_denied().code()
+    );
+
+    // Readers can't write, and writers can't disable.
+    let put = setup.put(&identity(2), vec![1], vec![4], None);
+    assert_eq!(put.unwrap_err().code(), error::permission_denied().code());
+    let disable = setup.disable(&identity(1), vec![1], None, None);
+    assert_eq!(
+        disable.unwrap_err().code(),
+        error::permission_denied().code()
+    );
+}
+
+#[test]
+fn put_disabled_key() {
+    let mut setup = setup();
+    let id = setup.id;
+    setup
+        .module_impl
+        .put(
+            &id,
+            PutArgs {
+                key: vec![1].into(),
+                value: vec![2].into(),
+                alternative_owner: None,
+                roles: Some(BTreeMap::from([(identity(1), KeyRole::Writer)])),
+            },
+        )
+        .unwrap();
+    setup.disable(&id, vec![1], None, None).unwrap();
+
+    // Writers can't enable the key again by putting to it.
+    let put = setup.put(&identity(1), vec![1], vec![3], None);
+    assert_eq!(put.unwrap_err().code(), error::key_disabled().code());
+    assert_eq!(
+        setup.query(&id, vec![1]).unwrap().disabled,
+        Some(Either::Left(true))
+    );
+
+    // Owners can.
+    setup.put(&id, vec![1], vec![3], None).unwrap();
+    let get_value = setup.get(&identity(1), vec![1]).unwrap().value.unwrap();
+    assert_eq!(ByteVec::from(vec![3]), get_value);
+}
+
+#[test]
+fn list_read_restricted_keys() {
+    let mut setup = setup();
+    let id = setup.id;
+    setup
+        .module_impl
+        .put(
+            &id,
+            PutArgs {
+                key: vec![1].into(),
+                value: vec![2].into(),
+                alternative_owner: None,
+                roles: Some(BTreeMap::from([(identity(1), KeyRole::Reader)])),
+            },
+        )
+        .unwrap();
+    setup.put(&id, vec![2], vec![3], None).unwrap();
+
+    let list = |sender: &Address| {
+        setup
+            .list(sender, SortOrder::Ascending, None)
+            .unwrap()
+            .keys
+            .into_iter()
+            .map(Into::into)
+            .collect::<Vec<Vec<u8>>>()
+    };
+    assert_eq!(list(&id), vec![vec![1], vec![2]]);
+    assert_eq!(list(&identity(1)), vec![vec![1], vec![2]]);
+    // Keys with readers are only listed to the addresses with a role.
+    assert_eq!(list(&identity(2)), vec![vec![2]]);
+}
+
 #[test]
 fn query() {
     let mut setup = setup();
@@ -488,3 +614,251 @@ fn list_filter_previous_owner() {
         vec![keys[0].clone()]
     );
 }
+
+#[test]
+fn get_and_query_prove_key() {
+    let mut setup = setup();
+    let id = setup.id;
+    setup.put(&id, vec![1], vec![2], None).unwrap();
+
+    let (transmitter, receiver) = unbounded();
+    let context = Context::new(RequestMessage::default().with_attribute(PROOF), transmitter);
+    setup
+        .module_impl
+        .get(
+            &id,
+            GetArgs {
+                key: vec![1].into(),
+            },
+            context.clone(),
+        )
+        .unwrap();
+    setup
+        .module_impl
+        .query(
+            &id,
+            QueryArgs {
+                key: vec![1].into(),
+            },
+            context,
+        )
+        .unwrap();
+
+    for _ in 0..2 {
+        let ProofResult::Proof(proof) = receiver.try_recv().unwrap() else {
+            panic!("Expected a proof");
+        };
+        assert!(proof.contains(&ProofOperation::KeyValuePair(
+            b"s\x01".to_vec().into(),
+            vec![2].into(),
+        )));
+    }
+}
+
+#[test]
+fn prove_read_restricted_key() {
+    let mut setup = setup();
+    let id = setup.id;
+    setup
+        .module_impl
+        .put(
+            &id,
+            PutArgs {
+                key: vec![1].into(),
+                value: vec![2].into(),
+                alternative_owner: None,
+                roles: Some(BTreeMap::from([(identity(1), KeyRole::Reader)])),
+            },
+        )
+        .unwrap();
+    let prove = |setup: &Setup, sender: &Address| {
+        setup.module_impl.prove(
+            sender,
+            ProveArgs {
+                keys: vec![vec![1].into()],
+            },
+        )
+    };
+    assert!(prove(&setup, &id).is_ok());
+    assert!(prove(&setup, &identity(1)).is_ok());
+    assert_eq!(
+        prove(&setup, &identity(2)).unwrap_err().code(),
+        error::permission_denied().code()
+    );
+
+    // Anyone can query the metadata, but only readers get a proof of the value.
+    let (transmitter, receiver) = unbounded();
+    let context = Context::new(RequestMessage::default().with_attribute(PROOF), transmitter);
+    let query = setup.module_impl.query(
+        &identity(2),
+        QueryArgs {
+            key: vec![1].into(),
+        },
+        context,
+    );
+    assert!(query.is_ok());
+    let ProofResult::Error(e) = receiver.try_recv().unwrap() else {
+        panic!("Expected an error");
+    };
+    assert_eq!(e.code(), error::permission_denied().code());
+}
+
+#[test]
+fn keys_keep_an_owner() {
+    let mut setup = setup();
+    let id = setup.id;
+    setup.put(&id, vec![1], vec![2], None).unwrap();
+
+    let put = setup.module_impl.put(
+        &id,
+        PutArgs {
+            key: vec![1].into(),
+            value: vec![3].into(),
+            alternative_owner: None,
+            roles: Some(BTreeMap::from([(identity(1), KeyRole::Writer)])),
+        },
+    );
+    assert_eq!(put.unwrap_err().code(), error::key_without_owner().code());
+
+    // A key can have several owners, and transferring it keeps the others.
+    let roles = BTreeMap::from([(id, KeyRole::Owner), (identity(1), KeyRole::Owner)]);
+    setup
+        .module_impl
+        .put(
+            &id,
+            PutArgs {
+                key: vec![1].into(),
+                value: vec![3].into(),
+                alternative_owner: None,
+                roles: Some(roles),
+            },
+        )
+        .unwrap();
+    setup
+        .module_impl
+        .transfer(
+            &id,
+            TransferArgs {
+                key: vec![1].into(),
+                alternative_owner: None,
+                new_owner: identity(2),
+            },
+        )
+        .unwrap();
+    let query = setup.query(&identity(1), vec![1]).unwrap();
+    assert_eq!(
+        query.roles,
+        Some(BTreeMap::from([
+            (identity(1), KeyRole::Owner),
+            (identity(2), KeyRole::Owner),
+        ]))
+    );
+    assert_eq!(query.previous_owner, Some(id));
+}
+
+#[test]
+fn legacy_metadata() {
+    // Metadata used to be stored with a separate owner.
+    let legacy = minicbor::to_vec(QueryReturns {
+        owner: identity(1),
+        disabled: None,
+        previous_owner: None,
+        disabled_until: None,
+        roles: Some(BTreeMap::from([(identity(2), KeyRole::Writer)])),
+    })
+    .unwrap();
+    let metadata: KvStoreMetadata = minicbor::decode(&legacy).unwrap();
+    assert_eq!(
+        metadata.roles,
+        BTreeMap::from([
+            (identity(1), KeyRole::Owner),
+            (identity(2), KeyRole::Writer),
+        ])
+    );
+
+    let query: QueryReturns = metadata.into();
+    assert_eq!(query.owner, identity(1));
+}
+
+#[test]
+fn simulate() {
+    let mut setup = Setup::new(true);
+    let id = setup.id;
+    setup.block(|setup| {
+        Simulator::begin(&mut setup.module_impl).unwrap();
+        setup.put(&id, vec![1], vec![2], None).unwrap();
+        let events = Simulator::rollback(&mut setup.module_impl).unwrap();
+        assert_eq!(events.len(), 1);
+        let event: EventLog = minicbor::decode(&minicbor::to_vec(&events[0]).unwrap()).unwrap();
+        assert!(matches!(
+            event.content,
+            EventInfo::KvStorePut { key, .. } if key.as_slice() == [1]
+        ));
+    });
+
+    assert_eq!(setup.get(&id, vec![1]).unwrap().value, None);
+}
+
+#[test]
+fn disable_until_expires() {
+    let mut setup = Setup::new(true);
+    let id = setup.id;
+    setup.block(|setup| setup.put(&id, vec![1], vec![2], None).unwrap());
+
+    // Blocks advance the time by one second.
+    let (_, disable) = setup.block(|setup| setup.disable_until(&id, vec![1], 1_000_004));
+    assert!(disable.is_ok());
+    assert_eq!(
+        setup.query(&id, vec![1]).unwrap().disabled_until,
+        Some(Timestamp::new(1_000_004).unwrap())
+    );
+
+    setup.block(|_| {});
+    assert_eq!(
+        setup.get(&id, vec![1]).unwrap_err().code(),
+        error::key_disabled().code()
+    );
+
+    setup.block(|_| {});
+    let get_value = setup.get(&id, vec![1]).unwrap().value.unwrap();
+    assert_eq!(ByteVec::from(vec![2]), get_value);
+}
+
+#[test]
+fn disable_until_in_past() {
+    let mut setup = Setup::new(true);
+    let id = setup.id;
+    setup.block(|setup| setup.put(&id, vec![1], vec![2], None).unwrap());
+
+    let (_, disable) = setup.block(|setup| setup.disable_until(&id, vec![1], 1_000_002));
+    assert_eq!(
+        disable.unwrap_err().code(),
+        error::disable_until_in_past().code()
+    );
+}
+
+#[test]
+fn disable_enable() {
+    let mut setup = setup();
+    let id = setup.id;
+    setup.put(&id, vec![1], vec![2], None).unwrap();
+
+    let enable = setup.enable(&id, vec![1], None);
+    assert_eq!(enable.unwrap_err().code(), error::key_not_disabled().code());
+
+    setup.disable(&id, vec![1], None, None).unwrap();
+
+    let enable = setup.enable(&identity(1), vec![1], None);
+    assert_eq!(
+        enable.unwrap_err().code(),
+        error::permission_denied().code()
+    );
+
+    assert!(setup.enable(&id, vec![1], None).is_ok());
+    let get_value = setup.get(&id, vec![1]).unwrap().value.unwrap();
+    assert_eq!(ByteVec::from(vec![2]), get_value);
+
+    let query_value = setup.query(&id, vec![1]).unwrap();
+    assert_eq!(query_value.disabled, Some(Either::Left(false)));
+    assert_eq!(query_value.disabled_until, None);
+}
@@ -1,7 +1,13 @@
+use async_channel::unbounded;
 use many_identity::testing::identity;
 use many_kvstore::error;
-use many_kvstore::module::KvStoreModuleImpl;
-use many_modules::kvstore::{GetArgs, KvStoreCommandsModuleBackend, KvStoreModuleBackend, PutArgs};
+use many_kvstore::module::{KvStoreMetadata, KvStoreModuleImpl};
+use many_kvstore::storage::KvStoreStorage;
+use many_modules::kvstore::{
+    GetArgs, InfoArg, KvStoreCommandsModuleBackend, KvStoreModuleBackend, PutArgs,
+};
+use many_protocol::{context::Context, RequestMessage};
+use many_server::simulation::Simulator;
 
 /// Verify persistent storage can be re-loaded
 #[test]
@@ -32,6 +38,7 @@ fn load() {
                     key: vec![2, 3, 4].into(),
                     value: vec![0, 1, 2, 3].into(),
                     alternative_owner: None,
+                    roles: None,
                 },
             )
             .expect("Unable to put new data in DB");
@@ -46,6 +53,7 @@ fn load() {
             GetArgs {
                 key: vec![2, 3, 4].into(),
             },
+            Context::new(RequestMessage::default(), unbounded().0),
         )
         .unwrap()
         .value
@@ -60,6 +68,7 @@ fn load() {
             key: vec![1, 2, 3].into(),
             value: vec![0].into(),
             alternative_owner: None,
+            roles: None,
         },
     );
     assert!(p.is_err());
@@ -73,6 +82,7 @@ fn load() {
             key: vec![1, 2, 3].into(),
             value: vec![0].into(),
             alternative_owner: None,
+            roles: None,
         },
     );
     assert!(p.is_ok());
@@ -84,9 +94,167 @@ fn load() {
             GetArgs {
                 key: vec![1, 2, 3].into(),
             },
+            Context::new(RequestMessage::default(), unbounded().0),
         )
         .unwrap()
         .value
         .unwrap();
     assert_eq!(v, vec![0].into());
 }
+
+/// Verify the statistics of a store can be read while it is open, and that
+/// the store can be compacted and re-loaded once closed.
+#[test]
+fn stats_and_compact() {
+    let path = tempfile::tempdir().unwrap().into_path();
+    {
+        let init = r#"{
+            identity: "mahukzwuwgt3porn6q4vq4xu3mwy5gyskhouryzbscq7wb2iow",
+            acl: {}
+        }"#;
+        let state = json5::from_str(init).unwrap();
+        let mut module_impl = KvStoreModuleImpl::new(state, path.clone(), false).unwrap();
+        for i in 0..100u8 {
+            module_impl
+                .put(
+                    &identity(1),
+                    PutArgs {
+                        key: vec![i].into(),
+                        value: vec![i].into(),
+                        alternative_owner: None,
+                        roles: None,
+                    },
+                )
+                .unwrap();
+        }
+
+        let stats = KvStoreStorage::stats(&path).unwrap();
+        assert!(stats.size_on_disk > 0);
+    }
+
+    let stats = KvStoreStorage::compact(&path).unwrap();
+    assert!(stats.size_on_disk > 0);
+    assert!(stats.node_count > 0);
+
+    let module_impl = KvStoreModuleImpl::load(path, false).unwrap();
+    let v = module_impl
+        .get(
+            &identity(1),
+            GetArgs {
+                key: vec![42].into(),
+            },
+            Context::new(RequestMessage::default(), unbounded().0),
+        )
+        .unwrap()
+        .value
+        .unwrap();
+    assert_eq!(v, vec![42].into());
+}
+
+/// A block which fails to be written can be committed again.
+#[test]
+fn commit_failure() {
+    let path = tempfile::tempdir().unwrap().into_path();
+    let mut storage = KvStoreStorage::new(Default::default(), identity(0), path, true).unwrap();
+    storage.commit().unwrap();
+    let height = storage.get_height();
+    let hash = storage.hash();
+
+    for inject in [
+        |storage: &mut KvStoreStorage| {
+            storage.faults().fail_apply(1);
+        },
+        |storage: &mut KvStoreStorage| {
+            storage.faults().fail_commit(0);
+        },
+    ] {
+        inject(&mut storage);
+        assert!(storage.commit().is_err());
+        assert_eq!(storage.get_height(), height);
+        assert_eq!(storage.hash(), hash);
+    }
+
+    storage.faults().clear();
+    storage.commit().unwrap();
+    assert_eq!(storage.get_height(), height + 1);
+}
+
+/// Outside of blockchain mode, the writes of a transaction only reach the
+/// disk once it ends.
+#[test]
+fn transaction_committed_when_it_ends() {
+    let path = tempfile::tempdir().unwrap().into_path();
+    let meta = KvStoreMetadata::new(identity(1));
+    {
+        let mut storage =
+            KvStoreStorage::new(Default::default(), identity(0), path.clone(), false).unwrap();
+        storage.begin_transaction().unwrap();
+        storage.put(&meta, &[1], vec![2], identity(1)).unwrap();
+        // The storage is dropped with the transaction open, as in a crash.
+    }
+
+    let mut storage = KvStoreStorage::load(&path, false).unwrap();
+    assert_eq!(storage.get(&[1]).unwrap(), None);
+    storage.begin_transaction().unwrap();
+    storage.put(&meta, &[1], vec![2], identity(1)).unwrap();
+    storage.commit_transaction().unwrap();
+    drop(storage);
+
+    let storage = KvStoreStorage::load(&path, false).unwrap();
+    assert_eq!(storage.get(&[1]).unwrap(), Some(vec![2]));
+}
+
+/// Simulated requests leave nothing on disk, outside of blockchain mode too,
+/// even if the server stops during the simulation.
+#[test]
+fn simulate_not_persisted() {
+    let path = tempfile::tempdir().unwrap().into_path();
+    let hash = {
+        let state = json5::from_str(
+            r#"{ identity: "mahukzwuwgt3porn6q4vq4xu3mwy5gyskhouryzbscq7wb2iow", acl: {} }"#,
+        )
+        .unwrap();
+        let mut module_impl = KvStoreModuleImpl::new(state, path.clone(), false).unwrap();
+        let hash = module_impl.info(&identity(1), InfoArg {}).unwrap().hash;
+
+        let put = |module_impl: &mut KvStoreModuleImpl| {
+            module_impl
+                .put(
+                    &identity(1),
+                    PutArgs {
+                        key: vec![1].into(),
+                        value: vec![2].into(),
+                        alternative_owner: None,
+                        roles: None,
+                    },
+                )
+                .unwrap();
+        };
+
+        Simulator::begin(&mut module_impl).unwrap();
+        put(&mut module_impl);
+        assert_eq!(Simulator::rollback(&mut module_impl).unwrap().len(), 1);
+
+        // The module is dropped during a simulation, as in a crash.
+        Simulator::begin(&mut module_impl).unwrap();
+        put(&mut module_impl);
+        hash
+    };
+
+    let module_impl = KvStoreModuleImpl::load(path, false).unwrap();
+    assert_eq!(
+        module_impl.info(&identity(1), InfoArg {}).unwrap().hash,
+        hash
+    );
+    let value = module_impl
+        .get(
+            &identity(1),
+            GetArgs {
+                key: vec![1].into(),
+            },
+            Context::new(RequestMessage::default(), unbounded().0),
+        )
+        .unwrap()
+        .value;
+    assert_eq!(value, None);
+}
@@ -0,0 +1,77 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use many_identity::testing::identity;
+use many_ledger_test_utils::*;
+use many_modules::events::{self, EventFilter, EventsModuleBackend};
+use many_types::SortOrder;
+
+const EVENT_COUNT: u64 = 1_000_000;
+const SENDS_PER_BLOCK: u64 = 10_000;
+
+/// `cargo test --all-targets` runs each benchmark once to check it works,
+/// without the `--bench` flag. Filling the ledger is not worth it then.
+fn event_count() -> u64 {
+    if std::env::args().any(|arg| arg == "--bench") {
+        EVENT_COUNT
+    } else {
+        SENDS_PER_BLOCK
+    }
+}
+
+fn send(c: &mut Criterion) {
+    let mut harness = Setup::new(false);
+    let id = harness.id;
+    harness.set_balance(id, u64::MAX, *MFX_SYMBOL);
+
+    c.bench_function("ledger send", |b| {
+        b.iter(|| harness.send_(id, black_box(identity(1)), 1u64))
+    });
+}
+
+/// List events out of a ledger with a million of them. The filtered list has
+/// to go through every event, since none of them match.
+fn events_list(c: &mut Criterion) {
+    let mut harness = Setup::new(true);
+    let id = harness.id;
+    harness.set_balance(id, u64::MAX, *MFX_SYMBOL);
+    for _ in 0..event_count() / SENDS_PER_BLOCK {
+        harness.block(|h| {
+            for i in 0..SENDS_PER_BLOCK {
+                h.send_(id, identity(1 + (i % 1_000) as u32), 1u64);
+            }
+        });
+    }
+
+    let mut group = c.benchmark_group("events.list");
+    group.sample_size(10);
+    group.bench_function("latest", |b| {
+        b.iter_batched(
+            || events::ListArgs {
+                count: Some(100),
+                order: Some(SortOrder::Descending),
+                filter: None,
+                cursor: None,
+            },
+            |args| harness.module_impl.list(args).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("filtered", |b| {
+        b.iter_batched(
+            || events::ListArgs {
+                count: Some(100),
+                order: None,
+                filter: Some(EventFilter {
+                    account: Some(vec![identity(5_000)].into()),
+                    ..Default::default()
+                }),
+                cursor: None,
+            },
+            |args| harness.module_impl.list(args).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, send, events_list);
+criterion_main!(benches);
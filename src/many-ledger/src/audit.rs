@@ -0,0 +1,351 @@
+use crate::json::InitialStateJson;
+use crate::storage::LedgerStorage;
+use many_error::ManyError;
+use many_identity::Address;
+use many_migration::MigrationConfig;
+use many_modules::events::{EventInfo, EventLog};
+use many_types::ledger::{Symbol, TokenAmount};
+use many_types::{CborRange, SortOrder};
+use num_bigint::{BigInt, BigUint};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+
+#[derive(clap::Args, Debug)]
+pub struct AuditOpts {
+    /// Path to the persistent store database (rocksdb) to audit.
+    /// The store cannot be used by a running server at the same time.
+    #[clap(long)]
+    pub persistent: PathBuf,
+
+    /// Path of the state file used to create the store. Its initial balances
+    /// are not part of the event log; without it, the replay starts with no
+    /// balances.
+    #[clap(long)]
+    pub state: Option<PathBuf>,
+
+    /// Path to a JSON file containing the configurations for the migrations.
+    #[clap(long, short)]
+    pub migrations_config: Option<PathBuf>,
+}
+
+/// A balance of the state that does not match the balance replayed from the
+/// event log.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Discrepancy {
+    pub address: Address,
+    pub symbol: Symbol,
+    pub replayed: BigInt,
+    pub actual: TokenAmount,
+}
+
+impl Display for Discrepancy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}: replayed {}, state {}",
+            self.address, self.symbol, self.replayed, self.actual
+        )
+    }
+}
+
+/// Balances replayed from the event log. Replayed balances are signed, so a
+/// balance going negative (e.g. a burn of funds that were never minted) is
+/// reported instead of hiding the following events.
+#[derive(Clone, Debug, Default)]
+pub struct Replay {
+    balances: BTreeMap<(Address, Symbol), BigInt>,
+}
+
+impl Replay {
+    pub fn new(initial: &BTreeMap<Address, BTreeMap<Symbol, TokenAmount>>) -> Self {
+        let mut replay = Self::default();
+        for (address, balances) in initial {
+            for (symbol, amount) in balances {
+                replay.credit(address, symbol, amount);
+            }
+        }
+        replay
+    }
+
+    fn amount(amount: &TokenAmount) -> BigInt {
+        BigInt::from(AsRef::<BigUint>::as_ref(amount).clone())
+    }
+
+    fn credit(&mut self, address: &Address, symbol: &Symbol, amount: &TokenAmount) {
+        *self.balances.entry((*address, *symbol)).or_default() += Self::amount(amount);
+    }
+
+    fn debit(&mut self, address: &Address, symbol: &Symbol, amount: &TokenAmount) {
+        *self.balances.entry((*address, *symbol)).or_default() -= Self::amount(amount);
+    }
+
+    /// The replayed balances of an address, per symbol.
+    pub fn balances_of<'a>(
+        &'a self,
+        address: &'a Address,
+    ) -> impl Iterator<Item = (&'a Symbol, &'a BigInt)> + 'a {
+        self.balances
+            .iter()
+            .filter(move |((a, _), _)| a == address)
+            .map(|((_, symbol), amount)| (symbol, amount))
+    }
+
+    /// Apply the balance changes of an event. Events that do not move funds
+    /// are ignored.
+    pub fn apply(&mut self, event: &EventLog) {
+        self.update(event, false)
+    }
+
+    /// Undo the balance changes of an event, to go back from the balances
+    /// after it to the balances before it.
+    pub fn revert(&mut self, event: &EventLog) {
+        self.update(event, true)
+    }
+
+    fn update(&mut self, event: &EventLog, revert: bool) {
+        type Change = fn(&mut Replay, &Address, &Symbol, &TokenAmount);
+
+        // Reverting an event swaps its credits and debits.
+        let (credit, debit): (Change, Change) = if revert {
+            (Self::debit, Self::credit)
+        } else {
+            (Self::credit, Self::debit)
+        };
+
+        match &event.content {
+            EventInfo::Send {
+                from,
+                to,
+                symbol,
+                amount,
+                ..
+            } => {
+                debit(self, from, symbol, amount);
+                credit(self, to, symbol, amount);
+            }
+            EventInfo::TokenMint {
+                symbol,
+                distribution,
+                ..
+            }
+            | EventInfo::TokenCreate {
+                symbol,
+                initial_distribution: Some(distribution),
+                ..
+            } => {
+                for (address, amount) in distribution {
+                    credit(self, address, symbol, amount);
+                }
+            }
+            EventInfo::TokenBurn {
+                symbol,
+                distribution,
+                ..
+            } => {
+                for (address, amount) in distribution {
+                    debit(self, address, symbol, amount);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Compare the replayed balances with the balances of the state. Missing
+    /// balances on either side are zero.
+    pub fn compare(
+        &self,
+        actual: &BTreeMap<Address, BTreeMap<Symbol, TokenAmount>>,
+    ) -> Vec<Discrepancy> {
+        let mut keys: Vec<(Address, Symbol)> = self.balances.keys().cloned().collect();
+        keys.extend(
+            actual
+                .iter()
+                .flat_map(|(a, b)| b.keys().map(move |s| (*a, *s))),
+        );
+        keys.sort();
+        keys.dedup();
+
+        keys.into_iter()
+            .filter_map(|(address, symbol)| {
+                let replayed = self
+                    .balances
+                    .get(&(address, symbol))
+                    .cloned()
+                    .unwrap_or_default();
+                let actual = actual
+                    .get(&address)
+                    .and_then(|b| b.get(&symbol))
+                    .cloned()
+                    .unwrap_or_else(TokenAmount::zero);
+                (replayed != Self::amount(&actual)).then_some(Discrepancy {
+                    address,
+                    symbol,
+                    replayed,
+                    actual,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Replay the event log of a store on top of the initial balances, and
+/// return the balances of the state that do not match.
+pub fn audit(
+    storage: &LedgerStorage,
+    initial: &BTreeMap<Address, BTreeMap<Symbol, TokenAmount>>,
+) -> Result<Vec<Discrepancy>, ManyError> {
+    let mut replay = Replay::new(initial);
+    for item in storage.iter_events(CborRange::default(), SortOrder::Ascending) {
+        let (_k, v) = item.map_err(ManyError::unknown)?;
+        let event =
+            minicbor::decode::<EventLog>(v.as_slice()).map_err(ManyError::deserialization_error)?;
+        replay.apply(&event);
+    }
+
+    Ok(replay.compare(&storage.get_all_account_balances()?))
+}
+
+/// Run the audit and print the discrepancies. Returns whether the state
+/// matches the event log.
+pub fn run(opts: AuditOpts) -> Result<bool, ManyError> {
+    let AuditOpts {
+        persistent,
+        state,
+        migrations_config,
+    } = opts;
+
+    let initial = state
+        .map(|p| {
+            InitialStateJson::read(p)
+                .map_err(|e| ManyError::unknown(e.to_string()))?
+                .balances()
+        })
+        .transpose()?
+        .unwrap_or_default();
+    let migrations = migrations_config
+        .map(|file| {
+            let content = std::fs::read_to_string(file).map_err(ManyError::unknown)?;
+            serde_json::from_str::<MigrationConfig>(&content)
+                .map(MigrationConfig::strict)
+                .map_err(ManyError::deserialization_error)
+        })
+        .transpose()?;
+    let storage = LedgerStorage::load(persistent, false, migrations)?;
+
+    let discrepancies = audit(&storage, &initial)?;
+    for d in &discrepancies {
+        println!("{d}");
+    }
+    println!("{} discrepancies found.", discrepancies.len());
+
+    Ok(discrepancies.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use many_identity::testing::identity;
+    use many_types::Timestamp;
+
+    fn event(content: EventInfo) -> EventLog {
+        EventLog {
+            id: 1u64.into(),
+            time: Timestamp::new(1).unwrap(),
+            content,
+        }
+    }
+
+    #[test]
+    fn replay() {
+        let symbol = identity(100);
+        let initial = BTreeMap::from([(
+            identity(1),
+            BTreeMap::from([(symbol, TokenAmount::from(1000u64))]),
+        )]);
+        let mut replay = Replay::new(&initial);
+        replay.apply(&event(EventInfo::Send {
+            from: identity(1),
+            to: identity(2),
+            symbol,
+            amount: TokenAmount::from(300u64),
+            memo: None,
+            reference: None,
+        }));
+        replay.apply(&event(EventInfo::TokenMint {
+            symbol,
+            distribution: BTreeMap::from([(identity(3), TokenAmount::from(50u64))]),
+            memo: None,
+        }));
+        replay.apply(&event(EventInfo::TokenBurn {
+            symbol,
+            distribution: BTreeMap::from([(identity(1), TokenAmount::from(100u64))]),
+            memo: None,
+        }));
+
+        let mut actual = BTreeMap::from([
+            (
+                identity(1),
+                BTreeMap::from([(symbol, TokenAmount::from(600u64))]),
+            ),
+            (
+                identity(2),
+                BTreeMap::from([(symbol, TokenAmount::from(300u64))]),
+            ),
+            (
+                identity(3),
+                BTreeMap::from([(symbol, TokenAmount::from(50u64))]),
+            ),
+        ]);
+        assert_eq!(replay.compare(&actual), vec![]);
+
+        // A balance changed outside of the event log.
+        actual
+            .get_mut(&identity(2))
+            .unwrap()
+            .insert(symbol, TokenAmount::from(350u64));
+        // A balance that was never funded.
+        actual.insert(
+            identity(4),
+            BTreeMap::from([(symbol, TokenAmount::from(1u64))]),
+        );
+        assert_eq!(
+            replay.compare(&actual),
+            vec![
+                Discrepancy {
+                    address: identity(2),
+                    symbol,
+                    replayed: BigInt::from(300),
+                    actual: TokenAmount::from(350u64),
+                },
+                Discrepancy {
+                    address: identity(4),
+                    symbol,
+                    replayed: BigInt::from(0),
+                    actual: TokenAmount::from(1u64),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn negative() {
+        let symbol = identity(100);
+        let mut replay = Replay::default();
+        replay.apply(&event(EventInfo::TokenBurn {
+            symbol,
+            distribution: BTreeMap::from([(identity(1), TokenAmount::from(10u64))]),
+            memo: None,
+        }));
+
+        assert_eq!(
+            replay.compare(&BTreeMap::new()),
+            vec![Discrepancy {
+                address: identity(1),
+                symbol,
+                replayed: BigInt::from(-10),
+                actual: TokenAmount::zero(),
+            }]
+        );
+    }
+}
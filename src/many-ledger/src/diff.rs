@@ -0,0 +1,201 @@
+use crate::storage::LedgerStorage;
+use many_error::ManyError;
+use sha3::{Digest, Sha3_256};
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+
+#[derive(clap::Args, Debug)]
+pub struct StateDiffOpts {
+    /// Path to the persistent store database (rocksdb) with the older state,
+    /// e.g. a backup taken at a given height.
+    #[clap(long)]
+    pub before: PathBuf,
+
+    /// Path to the persistent store database (rocksdb) with the newer state,
+    /// or the state of another validator at the same height.
+    #[clap(long)]
+    pub after: PathBuf,
+}
+
+/// A key whose value differs between two states, with the SHA3-256 hash of
+/// its value in each of them (`None` if it is missing).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct KeyDiff {
+    pub key: Vec<u8>,
+    pub before: Option<[u8; 32]>,
+    pub after: Option<[u8; 32]>,
+}
+
+fn hash(value: &[u8]) -> [u8; 32] {
+    Sha3_256::digest(value).into()
+}
+
+impl Display for KeyDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let show = |h: &Option<[u8; 32]>| h.map_or_else(|| "-".to_string(), hex::encode);
+        let kind = match (self.before, self.after) {
+            (None, _) => '+',
+            (_, None) => '-',
+            _ => '~',
+        };
+        write!(
+            f,
+            "{kind} {} {} {}",
+            self.key.escape_ascii(),
+            show(&self.before),
+            show(&self.after)
+        )
+    }
+}
+
+/// Compare two lists of key/value pairs sorted by key. The result is sorted
+/// by key as well, so two runs on the same states always give the same diff.
+pub fn diff<K: AsRef<[u8]>, E>(
+    mut before: impl Iterator<Item = Result<(K, Vec<u8>), E>>,
+    mut after: impl Iterator<Item = Result<(K, Vec<u8>), E>>,
+) -> Result<Vec<KeyDiff>, E> {
+    let mut diffs = Vec::new();
+    let mut b = before.next().transpose()?;
+    let mut a = after.next().transpose()?;
+
+    loop {
+        match (b.take(), a.take()) {
+            (None, None) => break,
+            (Some((k, v)), None) => {
+                diffs.push(KeyDiff {
+                    key: k.as_ref().to_vec(),
+                    before: Some(hash(&v)),
+                    after: None,
+                });
+                b = before.next().transpose()?;
+            }
+            (None, Some((k, v))) => {
+                diffs.push(KeyDiff {
+                    key: k.as_ref().to_vec(),
+                    before: None,
+                    after: Some(hash(&v)),
+                });
+                a = after.next().transpose()?;
+            }
+            (Some(x), Some(y)) => match x.0.as_ref().cmp(y.0.as_ref()) {
+                Ordering::Less => {
+                    diffs.push(KeyDiff {
+                        key: x.0.as_ref().to_vec(),
+                        before: Some(hash(&x.1)),
+                        after: None,
+                    });
+                    b = before.next().transpose()?;
+                    a = Some(y);
+                }
+                Ordering::Greater => {
+                    diffs.push(KeyDiff {
+                        key: y.0.as_ref().to_vec(),
+                        before: None,
+                        after: Some(hash(&y.1)),
+                    });
+                    b = Some(x);
+                    a = after.next().transpose()?;
+                }
+                Ordering::Equal => {
+                    if x.1 != y.1 {
+                        diffs.push(KeyDiff {
+                            key: x.0.as_ref().to_vec(),
+                            before: Some(hash(&x.1)),
+                            after: Some(hash(&y.1)),
+                        });
+                    }
+                    b = before.next().transpose()?;
+                    a = after.next().transpose()?;
+                }
+            },
+        }
+    }
+
+    Ok(diffs)
+}
+
+/// Print the heights and root hashes of both stores, and every key that
+/// differs between them.
+pub fn run(opts: StateDiffOpts) -> Result<(), ManyError> {
+    let StateDiffOpts { before, after } = opts;
+    for path in [&before, &after] {
+        if !path.exists() {
+            return Err(ManyError::unknown(format!(
+                "Store {} does not exist.",
+                path.display()
+            )));
+        }
+    }
+
+    let before = LedgerStorage::load(before, false, None)?;
+    let after = LedgerStorage::load(after, false, None)?;
+
+    let diffs = diff(before.iter_all(), after.iter_all()).map_err(ManyError::unknown)?;
+    for d in &diffs {
+        println!("{d}");
+    }
+    println!(
+        "Height: {} -> {}",
+        before.get_height()?,
+        after.get_height()?
+    );
+    println!("Hash before: {}", hex::encode(before.hash()));
+    println!("Hash after:  {}", hex::encode(after.hash()));
+    println!("{} keys differ.", diffs.len());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+
+    fn pairs(p: &[(&str, &str)]) -> impl Iterator<Item = Result<(Vec<u8>, Vec<u8>), Infallible>> {
+        p.iter()
+            .map(|(k, v)| Ok((k.as_bytes().to_vec(), v.as_bytes().to_vec())))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn hashes() {
+        let before = pairs(&[("/a", "1"), ("/b", "2"), ("/c", "3")]);
+        let after = pairs(&[("/b", "2"), ("/c", "4"), ("/d", "5")]);
+
+        assert_eq!(
+            diff(before, after).unwrap(),
+            vec![
+                KeyDiff {
+                    key: b"/a".to_vec(),
+                    before: Some(hash(b"1")),
+                    after: None,
+                },
+                KeyDiff {
+                    key: b"/c".to_vec(),
+                    before: Some(hash(b"3")),
+                    after: Some(hash(b"4")),
+                },
+                KeyDiff {
+                    key: b"/d".to_vec(),
+                    before: None,
+                    after: Some(hash(b"5")),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn display() {
+        let d = KeyDiff {
+            key: b"/balances/\x01".to_vec(),
+            before: None,
+            after: Some([0xab; 32]),
+        };
+        assert_eq!(
+            d.to_string(),
+            format!("+ /balances/\\x01 - {}", "ab".repeat(32))
+        );
+    }
+}
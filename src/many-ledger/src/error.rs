@@ -1,4 +1,4 @@
-use many_error::{define_application_many_error, define_attribute_many_error};
+use many_error::{define_application_many_error, define_attribute_many_error, ManyError};
 
 define_attribute_many_error!(
     attribute 2 => {
@@ -17,6 +17,16 @@ define_attribute_many_error!(
         9: pub fn amount_is_zero()
             => "Unable to send zero (0) token.",
         10: pub fn storage_key_not_found(key) => "Key not found in storage: {key:?}.",
+        11: pub fn reference_too_long(max) => "The reference of a send is longer than {max} bytes.",
+        12: pub fn storage_iteration_failed(desc) => "Unable to iterate over persistent storage: {desc}.",
+        13: pub fn transaction_already_open() => "A transaction is already open.",
+        14: pub fn no_open_transaction() => "No open transaction.",
+        15: pub fn height_not_reached(height, current) => "Height {height} is after the current height {current}.",
+        16: pub fn inconsistent_balance_history(address, symbol)
+            => "The event log does not explain the balance of {address} in {symbol}.",
+        17: pub fn height_too_old(height, oldest) => "Height {height} is before the oldest queryable height {oldest}.",
+        18: pub fn too_many_events_since_height(height, max)
+            => "More than {max} events were logged since height {height}, balances at this height cannot be computed.",
     }
 );
 
@@ -28,6 +38,7 @@ define_attribute_many_error!(
         4: pub fn ticker_exists(ticker) => "Token ticker already exists on this network: {ticker}.",
         5: pub fn subresource_exhausted(key) => "Subresources are exhausted for: {key}.",
         6: pub fn invalid_ticker_length(ticker) => "Token ticker length is invalid (<3 or >5): {ticker}.",
+        7: pub fn too_many_decimals(decimals, max) => "Token decimals must be at most {max}, not {decimals}.",
     }
 );
 
@@ -49,5 +60,97 @@ define_application_many_error!(
         3: pub fn storage_commit_failed(desc) => "Unable to commit data to persistent storage: {desc}.",
         4: pub fn storage_open_failed(desc) => "Unable to open persistent storage: {desc}.",
         5: pub fn unable_to_load_migrations(desc) => "Unable to load migrations: {desc}.",
+        6: pub fn hotfix_failed(name, desc) => "Hotfix {name} failed: {desc}.",
     }
 );
+
+/// An error of the persistent storage. It converts to a [ManyError] with a
+/// stable code, and back from one so clients can branch on it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum StorageError {
+    Open(String),
+    Get(String),
+    Apply(String),
+    Commit(String),
+    Iteration(String),
+    LoadMigrations(String),
+    TransactionAlreadyOpen,
+    NoOpenTransaction,
+}
+
+impl From<merk::rocksdb::Error> for StorageError {
+    fn from(value: merk::rocksdb::Error) -> Self {
+        Self::Iteration(value.to_string())
+    }
+}
+
+impl From<StorageError> for ManyError {
+    fn from(value: StorageError) -> Self {
+        match value {
+            StorageError::Open(desc) => storage_open_failed(desc),
+            StorageError::Get(desc) => storage_get_failed(desc),
+            StorageError::Apply(desc) => storage_apply_failed(desc),
+            StorageError::Commit(desc) => storage_commit_failed(desc),
+            StorageError::Iteration(desc) => storage_iteration_failed(desc),
+            StorageError::LoadMigrations(desc) => unable_to_load_migrations(desc),
+            StorageError::TransactionAlreadyOpen => transaction_already_open(),
+            StorageError::NoOpenTransaction => no_open_transaction(),
+        }
+    }
+}
+
+/// Gives back the error if it is not a storage error.
+impl TryFrom<ManyError> for StorageError {
+    type Error = ManyError;
+
+    fn try_from(value: ManyError) -> Result<Self, Self::Error> {
+        let desc = || value.argument("desc").unwrap_or_default().to_string();
+        let code = value.code();
+        Ok(if code == storage_open_failed::CODE {
+            Self::Open(desc())
+        } else if code == storage_get_failed::CODE {
+            Self::Get(desc())
+        } else if code == storage_apply_failed::CODE {
+            Self::Apply(desc())
+        } else if code == storage_commit_failed::CODE {
+            Self::Commit(desc())
+        } else if code == storage_iteration_failed::CODE {
+            Self::Iteration(desc())
+        } else if code == unable_to_load_migrations::CODE {
+            Self::LoadMigrations(desc())
+        } else if code == transaction_already_open::CODE {
+            Self::TransactionAlreadyOpen
+        } else if code == no_open_transaction::CODE {
+            Self::NoOpenTransaction
+        } else {
+            return Err(value);
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn storage_error_round_trip() {
+        for error in [
+            StorageError::Open("open".to_string()),
+            StorageError::Get("get".to_string()),
+            StorageError::Apply("apply".to_string()),
+            StorageError::Commit("commit".to_string()),
+            StorageError::Iteration("iteration".to_string()),
+            StorageError::LoadMigrations("migrations".to_string()),
+            StorageError::TransactionAlreadyOpen,
+            StorageError::NoOpenTransaction,
+        ] {
+            let many_error = ManyError::from(error.clone());
+            assert_ne!(many_error.code(), ManyError::unknown("").code());
+            assert_eq!(StorageError::try_from(many_error), Ok(error));
+        }
+        assert_eq!(
+            StorageError::try_from(insufficient_funds()),
+            Err(insufficient_funds())
+        );
+    }
+}
@@ -0,0 +1,309 @@
+use crate::migration::tokens::TOKEN_MIGRATION;
+use crate::storage::LedgerStorage;
+use many_error::ManyError;
+use many_identity::Address;
+use many_migration::MigrationConfig;
+use many_modules::events::{EventInfo, EventLog};
+use many_types::ledger::{Symbol, TokenAmount};
+use many_types::{CborRange, SortOrder, Timestamp};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::PathBuf;
+
+#[derive(clap::ArgEnum, Clone, Copy, Debug)]
+pub enum ExportFormat {
+    Jsonl,
+    Csv,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ExportEventsOpts {
+    /// Path to the persistent store database (rocksdb) to export the events of.
+    /// The store cannot be used by a running server at the same time.
+    #[clap(long)]
+    pub persistent: PathBuf,
+
+    /// Path to a JSON file containing the configurations for the migrations.
+    /// Needed to render amounts with their token decimals when the token
+    /// migration is active.
+    #[clap(long, short)]
+    pub migrations_config: Option<PathBuf>,
+
+    /// Only export events that happened at or after this time, e.g.
+    /// "2023-01-01T00:00:00Z".
+    #[clap(long)]
+    pub from: Option<humantime::Timestamp>,
+
+    /// Only export events that happened at or before this time.
+    #[clap(long)]
+    pub to: Option<humantime::Timestamp>,
+
+    /// Output format. One line per transfer for ledger events (a mint or burn
+    /// to several addresses has several lines), and one line for other events.
+    #[clap(long, arg_enum, default_value_t = ExportFormat::Jsonl)]
+    pub format: ExportFormat,
+}
+
+/// A flat representation of an event, used for the exports.
+#[derive(Clone, Debug, Default, Eq, PartialEq, serde::Serialize)]
+pub struct EventRow {
+    pub id: String,
+    pub time: String,
+    pub kind: String,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub symbol: Option<String>,
+    pub ticker: Option<String>,
+    pub amount: Option<String>,
+}
+
+impl EventRow {
+    const CSV_HEADER: &'static str = "id,time,kind,from,to,symbol,ticker,amount";
+
+    fn to_csv(&self) -> String {
+        [
+            Some(&self.id),
+            Some(&self.time),
+            Some(&self.kind),
+            self.from.as_ref(),
+            self.to.as_ref(),
+            self.symbol.as_ref(),
+            self.ticker.as_ref(),
+            self.amount.as_ref(),
+        ]
+        .into_iter()
+        .map(|field| csv_field(field.map_or("", String::as_str)))
+        .collect::<Vec<_>>()
+        .join(",")
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// The ticker and decimals of the tokens of the ledger.
+#[derive(Clone, Debug, Default)]
+pub struct TokenTable(BTreeMap<Symbol, (String, Option<u64>)>);
+
+impl TokenTable {
+    pub fn load(storage: &LedgerStorage) -> Result<Self, ManyError> {
+        Ok(Self(if storage.migrations().is_active(&TOKEN_MIGRATION) {
+            storage
+                .get_token_info_summary()?
+                .into_iter()
+                .map(|(symbol, summary)| (symbol, (summary.ticker, Some(summary.decimals))))
+                .collect()
+        } else {
+            storage
+                .get_symbols_and_tickers()?
+                .into_iter()
+                .map(|(symbol, ticker)| (symbol, (ticker, None)))
+                .collect()
+        }))
+    }
+
+    fn row(
+        &self,
+        base: &EventRow,
+        from: Option<&Address>,
+        to: Option<&Address>,
+        symbol: &Symbol,
+        amount: &TokenAmount,
+    ) -> EventRow {
+        let (ticker, decimals) = self
+            .0
+            .get(symbol)
+            .map_or((None, None), |(t, d)| (Some(t.clone()), *d));
+        let amount = match decimals {
+            Some(d) => amount
+                .to_decimal_string(d)
+                .unwrap_or_else(|_| amount.to_string()),
+            None => amount.to_string(),
+        };
+        EventRow {
+            from: from.map(Address::to_string),
+            to: to.map(Address::to_string),
+            symbol: Some(symbol.to_string()),
+            ticker,
+            amount: Some(amount),
+            ..base.clone()
+        }
+    }
+
+    /// The rows of an event.
+    pub fn rows(&self, event: &EventLog) -> Vec<EventRow> {
+        let time = event.time.as_system_time().map_or_else(
+            |_| event.time.secs().to_string(),
+            |t| humantime::format_rfc3339_seconds(t).to_string(),
+        );
+        let base = EventRow {
+            id: hex::encode(&event.id),
+            time,
+            kind: event.kind().to_string(),
+            ..Default::default()
+        };
+
+        match &event.content {
+            EventInfo::Send {
+                from,
+                to,
+                symbol,
+                amount,
+                ..
+            } => vec![self.row(&base, Some(from), Some(to), symbol, amount)],
+            EventInfo::TokenMint {
+                symbol,
+                distribution,
+                ..
+            }
+            | EventInfo::TokenCreate {
+                symbol,
+                initial_distribution: Some(distribution),
+                ..
+            } => distribution
+                .iter()
+                .map(|(to, amount)| self.row(&base, None, Some(to), symbol, amount))
+                .collect(),
+            EventInfo::TokenBurn {
+                symbol,
+                distribution,
+                ..
+            } => distribution
+                .iter()
+                .map(|(from, amount)| self.row(&base, Some(from), None, symbol, amount))
+                .collect(),
+            _ => vec![base],
+        }
+    }
+}
+
+/// Write the events of the store between two times to `out`.
+pub fn export_events(
+    storage: &LedgerStorage,
+    from: Option<Timestamp>,
+    to: Option<Timestamp>,
+    format: ExportFormat,
+    mut out: impl Write,
+) -> Result<(), ManyError> {
+    let tokens = TokenTable::load(storage)?;
+
+    if let ExportFormat::Csv = format {
+        writeln!(out, "{}", EventRow::CSV_HEADER).map_err(ManyError::unknown)?;
+    }
+
+    // Events are ordered by ID, and their time never decreases.
+    let iter = storage
+        .iter_events(CborRange::default(), SortOrder::Ascending)
+        .map(|item| {
+            let (_k, v) = item.map_err(ManyError::unknown)?;
+            minicbor::decode::<EventLog>(v.as_slice()).map_err(ManyError::deserialization_error)
+        })
+        .skip_while(|e| matches!((e, from), (Ok(e), Some(from)) if e.time < from))
+        .take_while(|e| !matches!((e, to), (Ok(e), Some(to)) if e.time > to));
+
+    for event in iter {
+        for row in tokens.rows(&event?) {
+            match format {
+                ExportFormat::Jsonl => writeln!(
+                    out,
+                    "{}",
+                    serde_json::to_string(&row).map_err(ManyError::serialization_error)?
+                ),
+                ExportFormat::Csv => writeln!(out, "{}", row.to_csv()),
+            }
+            .map_err(ManyError::unknown)?;
+        }
+    }
+
+    out.flush().map_err(ManyError::unknown)
+}
+
+pub fn run(opts: ExportEventsOpts) -> Result<(), ManyError> {
+    let ExportEventsOpts {
+        persistent,
+        migrations_config,
+        from,
+        to,
+        format,
+    } = opts;
+
+    let migrations = migrations_config
+        .map(|file| {
+            let content = std::fs::read_to_string(file).map_err(ManyError::unknown)?;
+            serde_json::from_str::<MigrationConfig>(&content)
+                .map(MigrationConfig::strict)
+                .map_err(ManyError::deserialization_error)
+        })
+        .transpose()?;
+    let storage = LedgerStorage::load(persistent, false, migrations)?;
+
+    let from = from
+        .map(|t| Timestamp::from_system_time(t.into()))
+        .transpose()?;
+    let to = to
+        .map(|t| Timestamp::from_system_time(t.into()))
+        .transpose()?;
+
+    let stdout = std::io::stdout();
+    export_events(
+        &storage,
+        from,
+        to,
+        format,
+        std::io::BufWriter::new(stdout.lock()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use many_identity::testing::identity;
+
+    #[test]
+    fn csv() {
+        let row = EventRow {
+            id: "01".to_string(),
+            time: "2023-01-01T00:00:00Z".to_string(),
+            kind: "send".to_string(),
+            ticker: Some("A,\"B\"".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            row.to_csv(),
+            "01,2023-01-01T00:00:00Z,send,,,,\"A,\"\"B\"\"\","
+        );
+    }
+
+    #[test]
+    fn rows() {
+        let symbol = identity(100);
+        let tokens = TokenTable(BTreeMap::from([(symbol, ("MFX".to_string(), Some(2)))]));
+        let event = EventLog {
+            id: 1u64.into(),
+            time: Timestamp::new(1_672_531_200).unwrap(),
+            content: EventInfo::TokenBurn {
+                symbol,
+                distribution: BTreeMap::from([
+                    (identity(1), TokenAmount::from(150u64)),
+                    (identity(2), TokenAmount::from(5u64)),
+                ]),
+                memo: None,
+            },
+        };
+
+        let rows = tokens.rows(&event);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].kind, "token-burn");
+        assert_eq!(rows[0].time, "2023-01-01T00:00:00Z");
+        assert_eq!(rows[0].from, Some(identity(1).to_string()));
+        assert_eq!(rows[0].to, None);
+        assert_eq!(rows[0].ticker.as_deref(), Some("MFX"));
+        assert_eq!(rows[0].amount.as_deref(), Some("1.5"));
+        assert_eq!(rows[1].amount.as_deref(), Some("0.05"));
+    }
+}
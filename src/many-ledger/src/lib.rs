@@ -2,8 +2,14 @@
 
 extern crate core;
 
+pub mod audit;
+pub mod compact;
+pub mod diff;
 pub mod error;
+pub mod export;
 pub mod json;
 pub mod migration;
 pub mod module;
+pub mod namespaces;
+pub mod simulate;
 pub mod storage;
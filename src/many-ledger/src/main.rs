@@ -1,22 +1,33 @@
 #![feature(used_with_arg)]
 
 use clap::Parser;
+use many_cli_helpers::verifiers::VerifierFlags;
 use many_cli_helpers::CommonCliFlags;
-use many_identity::verifiers::AnonymousVerifier;
+use many_error::ManyError;
 use many_identity::{Address, Identity};
-use many_identity_dsa::{CoseKeyIdentity, CoseKeyVerifier};
-use many_identity_webauthn::WebAuthnVerifier;
+use many_identity_dsa::CoseKeyIdentity;
 use many_migration::MigrationConfig;
 use many_modules::account::features::Feature;
-use many_modules::{abci_backend, account, data, events, idstore, ledger};
+use many_modules::{
+    abci_backend, account, admin, attest, compliance, data, events, idstore, ledger, names, random,
+    relay, store,
+};
 use many_protocol::ManyUrl;
-use many_server::transport::http::HttpServer;
+use many_server::admin::AdminModuleImpl;
+use many_server::panic::PanicPolicy;
+use many_server::quota::{Quota, QuotaValidator};
+use many_server::registry::ModuleRegistry;
+use many_server::request_log::RequestSampler;
+use many_server::transport::http::{EnvelopeTagging, HttpServer};
 use many_server::ManyServer;
 use many_server_cache::{RequestCacheValidator, RocksDbCacheBackend};
+use many_types::NetworkId;
 use std::collections::BTreeSet;
 use std::net::SocketAddr;
 use std::path::PathBuf;
+use std::sync::atomic::Ordering;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tracing::{debug, info, warn};
 
 use crate::allow_addrs::AllowAddrsModule;
@@ -26,23 +37,30 @@ use crate::idstore_webauthn::IdStoreWebAuthnModule;
 use crate::json::InitialStateJson;
 use crate::migration::MIGRATIONS;
 use crate::module::account::AccountFeatureModule;
+use crate::storage::LedgerStorage;
 use module::*;
 
+mod audit;
+mod compact;
+mod diff;
 mod error;
+mod export;
 mod json;
 mod migration;
 mod module;
+mod namespaces;
+mod simulate;
 mod storage;
 
 #[derive(Parser, Debug)]
-#[clap(args_override_self(true))]
+#[clap(args_override_self(true), subcommand_negates_reqs(true))]
 struct Opts {
     #[clap(flatten)]
     common_flags: CommonCliFlags,
 
     /// The location of a PEM file for the identity of this server.
     // The field needs to be an Option for the clap derive to work properly.
-    #[clap(long, required = true)]
+    #[clap(long, required_unless_present = "validate-migrations")]
     pem: Option<PathBuf>,
 
     /// The address and port to bind to for the MANY Http server.
@@ -59,7 +77,7 @@ struct Opts {
 
     /// Path to a persistent store database (rocksdb).
     // The field needs to be an Option for the clap derive to work properly.
-    #[clap(long, required = true)]
+    #[clap(long, required_unless_present = "validate-migrations")]
     persistent: Option<PathBuf>,
 
     /// Delete the persistent storage to start from a clean state.
@@ -73,6 +91,9 @@ struct Opts {
     #[clap(long)]
     allow_origin: Option<Vec<ManyUrl>>,
 
+    #[clap(flatten)]
+    verifier_flags: VerifierFlags,
+
     /// A list of initial balances. This will be in addition to the genesis
     /// state file in --state and should only be used for testing.
     /// Each transaction MUST be of the format:
@@ -100,22 +121,134 @@ struct Opts {
     #[clap(long, exclusive = true)]
     list_migrations: bool,
 
+    /// Check the migrations configuration against the built-in migrations
+    /// and the height of the persistent store, if it exists, then exit. The
+    /// exit status is non-zero if the configuration cannot be loaded.
+    #[clap(long, requires = "migrations-config")]
+    validate_migrations: bool,
+
     /// Path to a JSON file containing an array of MANY addresses
     /// Only addresses from this array will be able to execute commands, e.g., send, put, ...
     /// Any addresses will be able to execute queries, e.g., balance, get, ...
     #[clap(long)]
     allow_addrs: Option<PathBuf>,
 
+    /// The address allowed to call the admin endpoints, e.g. to change the
+    /// log level or shut down the server. The admin module is disabled if
+    /// unspecified.
+    #[clap(long)]
+    admin: Option<Address>,
+
+    /// Path to a JSON file containing an array of the attribute IDs of the
+    /// optional modules (data, random, names, attest, relay, store and
+    /// compliance) to serve at startup. The admin module can then enable and
+    /// disable them while the server runs. All of them are served if
+    /// unspecified. Not available with `--abci`, as the nodes would serve
+    /// different modules.
+    #[clap(long, conflicts_with = "abci")]
+    modules: Option<PathBuf>,
+
     /// Database path to the request cache to validate duplicate messages.
     /// If unspecified, the server will not verify transactions for duplicate
     /// messages.
     #[clap(long)]
     cache_db: Option<PathBuf>,
+
+    /// Validate the arguments of requests against the CDDL of their endpoint
+    /// before executing them, refusing unknown or malformed fields.
+    #[clap(long)]
+    validate_arguments: bool,
+
+    /// Refuse request envelopes without the COSE Sign1 CBOR tag. By default,
+    /// untagged envelopes are accepted too.
+    #[clap(long)]
+    strict_envelope_tagging: bool,
+
+    /// Log the method, sender and argument of this percentage of requests,
+    /// with sensitive fields redacted.
+    #[clap(long, value_parser = clap::value_parser!(u8).range(0..=100))]
+    log_requests: Option<u8>,
+
+    /// The network of this ledger, advertised in its status. Requests signed
+    /// for another network are refused.
+    #[clap(long)]
+    network_id: Option<String>,
+
+    /// Also refuse requests without a network ID.
+    #[clap(long, requires("network-id"))]
+    require_network_id: bool,
+
+    /// Abort the process when a module panics, instead of responding with
+    /// an error. Always on with `--abci`, as a panic can leave the state of
+    /// the node diverging from the other nodes.
+    #[clap(long)]
+    abort_on_panic: bool,
+
+    /// Run at most this many requests at once, serving the waiting ones
+    /// fairly across senders.
+    #[clap(long, value_parser = clap::value_parser!(u64).range(1..))]
+    execution_slots: Option<u64>,
+
+    /// Deny the requests of a sender that went over `--quota-max-bytes` or
+    /// `--quota-max-execution-ms` within a window of this many seconds.
+    /// Not available with `--abci`, as the nodes would deny different
+    /// requests.
+    #[clap(long, conflicts_with = "abci", value_parser = clap::value_parser!(u64).range(1..))]
+    quota_window: Option<u64>,
+
+    /// The bytes of request arguments a sender can send per quota window.
+    #[clap(long, requires("quota-window"))]
+    quota_max_bytes: Option<u64>,
+
+    /// The milliseconds of execution a sender can use per quota window.
+    #[clap(long, requires("quota-window"))]
+    quota_max_execution_ms: Option<u64>,
+
+    /// A sender that is never denied by the quota, e.g. a node operator.
+    /// Can be repeated.
+    #[clap(long, requires("quota-window"))]
+    quota_exempt: Vec<Address>,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Export the event log of a persistent store as JSON lines or CSV.
+    ExportEvents(export::ExportEventsOpts),
+
+    /// Replay the event log of a persistent store and report the balances
+    /// that do not match its state.
+    Audit(audit::AuditOpts),
+
+    /// Run the pending migrations on a copy of a persistent store and report
+    /// the changes to its hash and data.
+    SimulateMigration(simulate::SimulateMigrationOpts),
+
+    /// Compact a persistent store to reclaim the space used by stale data,
+    /// and report its size before and after.
+    Compact(compact::CompactOpts),
+
+    /// Compare two persistent stores, e.g. backups at two heights or the
+    /// states of two validators, and list every key that differs with the
+    /// hashes of its values.
+    StateDiff(diff::StateDiffOpts),
+
+    /// Count the keys and bytes of each namespace of a persistent store, and
+    /// list the keys by namespace.
+    Namespaces(namespaces::NamespacesOpts),
 }
 
 fn main() {
+    let opts = Opts::parse();
+    let log_level = opts.common_flags.init_logging().unwrap();
+    debug!("{opts:?}");
+    let admin = opts
+        .admin
+        .map(|admin| AdminModuleImpl::new(admin).with_config(&opts));
+
     let Opts {
-        common_flags,
         pem,
         addr,
         abci,
@@ -124,15 +257,31 @@ fn main() {
         clean,
         migrations_config,
         allow_origin,
+        verifier_flags,
         allow_addrs,
+        modules,
         list_migrations,
+        validate_migrations,
         cache_db,
+        validate_arguments,
+        strict_envelope_tagging,
+        log_requests,
+        network_id,
+        require_network_id,
+        abort_on_panic,
+        execution_slots,
+        quota_window,
+        quota_max_bytes,
+        quota_max_execution_ms,
+        quota_exempt,
+        command,
         ..
-    } = Opts::parse();
+    } = opts;
 
-    common_flags.init_logging().unwrap();
+    let verifier_config = verifier_flags
+        .config()
+        .expect("Could not load the verifier config");
 
-    debug!("{:?}", Opts::parse());
     info!(
         version = env!("CARGO_PKG_VERSION"),
         git_sha = env!("VERGEN_GIT_SHA")
@@ -146,6 +295,46 @@ fn main() {
         return;
     }
 
+    if validate_migrations {
+        let report = simulate::validate(migrations_config.unwrap(), persistent)
+            .expect("Could not validate the migrations.");
+        println!("{report}");
+        if !report.is_ok() {
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    match command {
+        Some(Command::ExportEvents(opts)) => {
+            export::run(opts).expect("Could not export events.");
+            return;
+        }
+        Some(Command::Audit(opts)) => {
+            if !audit::run(opts).expect("Could not audit the store.") {
+                std::process::exit(1);
+            }
+            return;
+        }
+        Some(Command::SimulateMigration(opts)) => {
+            simulate::run(opts).expect("Could not simulate the migrations.");
+            return;
+        }
+        Some(Command::Compact(opts)) => {
+            compact::run(opts).expect("Could not compact the store.");
+            return;
+        }
+        Some(Command::StateDiff(opts)) => {
+            diff::run(opts).expect("Could not compare the stores.");
+            return;
+        }
+        Some(Command::Namespaces(opts)) => {
+            namespaces::run(opts).expect("Could not list the namespaces.");
+            return;
+        }
+        None => {}
+    }
+
     // Safe unwrap.
     // At this point the Options should contain a value.
     let pem = pem.unwrap();
@@ -164,6 +353,7 @@ fn main() {
     } else if persistent.exists() {
         // Initial state is ignored.
         state = None;
+        compact::run_scheduled(&persistent).expect("Could not compact the store.");
     }
 
     let pem = std::fs::read_to_string(pem).expect("Could not read PEM file.");
@@ -181,7 +371,8 @@ fn main() {
         config.strict()
     });
 
-    let module_impl = if persistent.exists() {
+    let persistent_path = persistent.clone();
+    let mut module_impl = if persistent.exists() {
         if state.is_some() {
             warn!(
                 r#"
@@ -241,21 +432,40 @@ fn main() {
     } else {
         panic!("Persistent store or staging file not found.")
     };
+    module_impl.set_cosign_verifier(verifier_config.build(allow_origin.clone()));
     let module_impl = Arc::new(Mutex::new(module_impl));
 
     let many = ManyServer::simple(
         "many-ledger",
         key,
-        (
-            AnonymousVerifier,
-            CoseKeyVerifier,
-            WebAuthnVerifier::new(allow_origin),
-        ),
+        verifier_config.build(allow_origin),
         Some(env!("CARGO_PKG_VERSION").to_string()),
     );
 
+    let mut registry = ModuleRegistry::new(&many);
+    registry
+        .register(data::DataModule::new(module_impl.clone()))
+        .and_then(|r| r.register(random::RandomModule::new(module_impl.clone())))
+        .and_then(|r| r.register(names::NamesModule::new(module_impl.clone())))
+        .and_then(|r| r.register(attest::AttestModule::new(module_impl.clone())))
+        .and_then(|r| r.register(relay::RelayModule::new(module_impl.clone(), many.clone())))
+        .and_then(|r| r.register(store::StoreModule::new(module_impl.clone())))
+        .and_then(|r| r.register(compliance::ComplianceModule::new(module_impl.clone())))
+        .expect("Could not register the optional modules");
+
     {
         let mut s = many.lock().unwrap();
+        s.set_build_info(
+            env!("VERGEN_GIT_SHA"),
+            [
+                ("balance_testing", cfg!(feature = "balance_testing")),
+                ("fault_testing", cfg!(feature = "fault_testing")),
+                ("migration_testing", cfg!(feature = "migration_testing")),
+                ("webauthn_testing", cfg!(feature = "webauthn_testing")),
+            ]
+            .into_iter()
+            .filter_map(|(name, enabled)| enabled.then_some(name)),
+        );
         s.add_module(ledger::LedgerModule::new(module_impl.clone()));
         let ledger_command_module = ledger::LedgerCommandsModule::new(module_impl.clone());
         if let Some(path) = allow_addrs {
@@ -294,12 +504,27 @@ fn main() {
 
         s.add_module(AccountFeatureModule::new(
             account::AccountModule::new(module_impl.clone()),
-            [Feature::with_id(0), Feature::with_id(1)],
+            [
+                Feature::with_id(0),
+                Feature::with_id(1),
+                Feature::with_id(4),
+            ],
         ));
         s.add_module(account::features::multisig::AccountMultisigModule::new(
             module_impl.clone(),
         ));
-        s.add_module(data::DataModule::new(module_impl.clone()));
+        s.add_module(account::features::cosign::AccountCosignModule::new(
+            module_impl.clone(),
+        ));
+        s.set_simulator(module_impl.clone());
+        {
+            let module_impl = module_impl.clone();
+            s.set_height_fn(move || module_impl.lock().unwrap().height());
+        }
+        {
+            let module_impl = module_impl.clone();
+            s.set_block_time_fn(move || module_impl.lock().unwrap().block_time());
+        }
         if abci {
             s.set_timeout(u64::MAX);
             s.add_module(abci_backend::AbciModule::new(module_impl));
@@ -308,9 +533,70 @@ fn main() {
         if let Some(p) = cache_db {
             s.add_validator(RequestCacheValidator::new(RocksDbCacheBackend::new(p)));
         }
+        s.set_validate_arguments(validate_arguments);
+        if let Some(percent) = log_requests {
+            s.set_request_sampler(RequestSampler::new(percent));
+        }
+        if let Some(id) = network_id {
+            s.set_network_id(NetworkId::new(id), require_network_id);
+        }
+        if abort_on_panic || abci {
+            s.set_panic_policy(PanicPolicy::Abort);
+        }
+        if let Some(slots) = execution_slots {
+            s.set_execution_slots(slots as usize);
+        }
+        if let Some(window) = quota_window {
+            s.add_validator(
+                QuotaValidator::new(Quota {
+                    window: Duration::from_secs(window),
+                    max_bytes: quota_max_bytes,
+                    max_execution_time: quota_max_execution_ms.map(Duration::from_millis),
+                })
+                .with_exempt(quota_exempt),
+            );
+        }
+    }
+
+    let enabled_modules: BTreeSet<u32> = match &modules {
+        Some(path) => json5::from_str(&std::fs::read_to_string(path).unwrap()).unwrap(),
+        None => registry.attributes().collect(),
+    };
+    for attribute in enabled_modules {
+        registry
+            .enable(attribute)
+            .expect("Could not enable an optional module");
     }
 
-    let mut many_server = HttpServer::new(many);
+    let mut many_server =
+        HttpServer::new(many.clone()).with_envelope_tagging(if strict_envelope_tagging {
+            EnvelopeTagging::Strict
+        } else {
+            EnvelopeTagging::Lenient
+        });
+
+    if let Some(admin) = admin {
+        let term_signal = many_server.term_signal();
+        let compact_path = persistent_path.clone();
+        let admin = admin
+            .with_log_level(move |level| log_level.set(level).map_err(ManyError::unknown))
+            .with_storage_stats(move || LedgerStorage::stats(&persistent_path))
+            .with_compaction(move || {
+                // Stop, and compact the store before starting again.
+                compact::schedule(&compact_path)?;
+                term_signal.store(true, Ordering::Relaxed);
+                Ok(())
+            })
+            .with_term_signal(many_server.term_signal());
+        let admin = if modules.is_some() {
+            admin.with_module_registry(registry)
+        } else {
+            admin
+        };
+        many.lock()
+            .unwrap()
+            .add_module(admin::AdminModule::new(Arc::new(Mutex::new(admin))));
+    }
 
     signal_hook::flag::register(signal_hook::consts::SIGTERM, many_server.term_signal())
         .expect("Could not register signal handler");
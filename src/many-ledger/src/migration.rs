@@ -3,13 +3,25 @@ use linkme::distributed_slice;
 use many_error::ManyError;
 use many_migration::{InnerMigration, MigrationSet};
 
+pub mod account_gc;
+pub mod account_index;
+pub mod attest;
 pub mod block_9400;
+pub mod compliance;
+pub mod cosign;
 pub mod data;
 pub mod disable_token_create;
 pub mod disable_token_mint;
+pub mod event_redaction;
 pub mod legacy_remove_roles;
 pub mod memo;
+pub mod names;
+pub mod relay;
+pub mod store;
+pub mod subresource_history;
+pub mod supply_history;
 pub mod token_create;
+pub mod token_history;
 pub mod tokens;
 
 #[cfg(feature = "migration_testing")]
@@ -0,0 +1,69 @@
+use crate::error;
+use crate::migration::MIGRATIONS;
+use crate::storage::account::ACCOUNTS_ROOT;
+use crate::storage::account_gc::{
+    is_disabled, key_for_disabled_account, AccountGcConfig, ACCOUNT_GC_CONFIG_KEY,
+};
+use crate::storage::iterator::LedgerIterator;
+use crate::storage::InnerStorage;
+use linkme::distributed_slice;
+use many_error::ManyError;
+use many_identity::Address;
+use many_migration::InnerMigration;
+use many_modules::account::Account;
+use many_types::Timestamp;
+use merk::Op;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Store the retention period, and queue the accounts disabled before the
+/// migration. Their disable time is unknown, so their retention period starts
+/// with the first block after the migration.
+fn initialize(storage: &mut InnerStorage, extra: &HashMap<String, Value>) -> Result<(), ManyError> {
+    let retention_secs: u64 = extra
+        .get("retention_secs")
+        .map(|value| serde_json::from_value(value.clone()))
+        .transpose()
+        .map_err(ManyError::deserialization_error)?
+        .ok_or_else(|| {
+            ManyError::unknown("Missing extra parameter 'retention_secs' for Account GC Migration")
+        })?;
+
+    let mut batch = vec![(
+        ACCOUNT_GC_CONFIG_KEY.to_vec(),
+        Op::Put(
+            minicbor::to_vec(AccountGcConfig { retention_secs })
+                .map_err(ManyError::serialization_error)?,
+        ),
+    )];
+    for item in LedgerIterator::all_with_prefix(storage, ACCOUNTS_ROOT.as_bytes()) {
+        let (key, value) = item.map_err(ManyError::unknown)?;
+        let account: Account =
+            minicbor::decode(&value).map_err(ManyError::deserialization_error)?;
+        if is_disabled(&account) {
+            let id = std::str::from_utf8(&key[ACCOUNTS_ROOT.len()..])
+                .map_err(ManyError::deserialization_error)
+                .and_then(Address::from_str)?;
+            batch.push((
+                key_for_disabled_account(&id),
+                Op::Put(
+                    minicbor::to_vec(None::<Timestamp>).map_err(ManyError::serialization_error)?,
+                ),
+            ));
+        }
+    }
+
+    batch.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
+    storage
+        .apply(batch.as_slice())
+        .map_err(error::storage_apply_failed)
+}
+
+#[distributed_slice(MIGRATIONS)]
+pub static ACCOUNT_GC_MIGRATION: InnerMigration<InnerStorage, ManyError> =
+    InnerMigration::new_initialize(
+        initialize,
+        "Account GC Migration",
+        "Prune the accounts disabled for longer than a retention period, keeping a tombstone hash",
+    );
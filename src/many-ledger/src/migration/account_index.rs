@@ -0,0 +1,46 @@
+use crate::error;
+use crate::migration::MIGRATIONS;
+use crate::storage::account::{account_index_entries, ACCOUNTS_ROOT};
+use crate::storage::iterator::LedgerIterator;
+use crate::storage::InnerStorage;
+use linkme::distributed_slice;
+use many_error::ManyError;
+use many_identity::Address;
+use many_migration::InnerMigration;
+use many_modules::account::Account;
+use merk::Op;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Index the roles of the accounts created before the migration.
+fn initialize(storage: &mut InnerStorage, _: &HashMap<String, Value>) -> Result<(), ManyError> {
+    let mut batch = Vec::new();
+    for item in LedgerIterator::all_with_prefix(storage, ACCOUNTS_ROOT.as_bytes()) {
+        let (key, value) = item.map_err(ManyError::unknown)?;
+        let id = std::str::from_utf8(&key[ACCOUNTS_ROOT.len()..])
+            .map_err(ManyError::deserialization_error)
+            .and_then(Address::from_str)?;
+        let account: Account =
+            minicbor::decode(&value).map_err(ManyError::deserialization_error)?;
+        for (key, roles) in account_index_entries(&id, &account) {
+            batch.push((
+                key,
+                Op::Put(minicbor::to_vec(roles).map_err(ManyError::serialization_error)?),
+            ));
+        }
+    }
+
+    batch.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
+    storage
+        .apply(batch.as_slice())
+        .map_err(error::storage_apply_failed)
+}
+
+#[distributed_slice(MIGRATIONS)]
+pub static ACCOUNT_INDEX_MIGRATION: InnerMigration<InnerStorage, ManyError> =
+    InnerMigration::new_initialize(
+        initialize,
+        "Account Index Migration",
+        "Index the accounts by the identities holding roles in them",
+    );
@@ -0,0 +1,44 @@
+use crate::error;
+use crate::migration::MIGRATIONS;
+use crate::storage::attest::ATTESTORS_KEY;
+use crate::storage::InnerStorage;
+use linkme::distributed_slice;
+use many_error::ManyError;
+use many_identity::Address;
+use many_migration::InnerMigration;
+use merk::Op;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::str::FromStr;
+
+/// Store the trusted attestors, given as a list of addresses.
+fn initialize(storage: &mut InnerStorage, extra: &HashMap<String, Value>) -> Result<(), ManyError> {
+    let attestors: Vec<String> = extra
+        .get("attestors")
+        .map(|value| serde_json::from_value(value.clone()))
+        .transpose()
+        .map_err(ManyError::deserialization_error)?
+        .ok_or_else(|| {
+            ManyError::unknown("Missing extra parameter 'attestors' for Attest Migration")
+        })?;
+    let attestors = attestors
+        .iter()
+        .map(|attestor| Address::from_str(attestor))
+        .collect::<Result<BTreeSet<_>, _>>()?;
+
+    storage
+        .apply(&[(
+            ATTESTORS_KEY.to_vec(),
+            Op::Put(minicbor::to_vec(attestors).map_err(ManyError::serialization_error)?),
+        )])
+        .map_err(error::storage_apply_failed)?;
+    Ok(())
+}
+
+#[distributed_slice(MIGRATIONS)]
+pub static ATTEST_MIGRATION: InnerMigration<InnerStorage, ManyError> =
+    InnerMigration::new_initialize(
+        initialize,
+        "Attest Migration",
+        "Enables the attestation module and stores its trusted attestors",
+    );
@@ -0,0 +1,55 @@
+use crate::error;
+use crate::migration::MIGRATIONS;
+use crate::storage::compliance::{ComplianceConfig, COMPLIANCE_CONFIG_KEY};
+use crate::storage::InnerStorage;
+use linkme::distributed_slice;
+use many_error::ManyError;
+use many_identity::Address;
+use many_migration::InnerMigration;
+use many_modules::compliance::ListMode;
+use merk::Op;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Store the compliance administrator and the mode of the list, either
+/// `"deny"` (the default) or `"allow"`.
+fn initialize(storage: &mut InnerStorage, extra: &HashMap<String, Value>) -> Result<(), ManyError> {
+    let admin: String = extra
+        .get("admin")
+        .map(|value| serde_json::from_value(value.clone()))
+        .transpose()
+        .map_err(ManyError::deserialization_error)?
+        .ok_or_else(|| {
+            ManyError::unknown("Missing extra parameter 'admin' for Compliance Migration")
+        })?;
+    let admin = Address::from_str(&admin)?;
+    let mode = match extra.get("mode").and_then(Value::as_str) {
+        None | Some("deny") => ListMode::Deny,
+        Some("allow") => ListMode::Allow,
+        Some(mode) => {
+            return Err(ManyError::unknown(format!(
+                "Invalid mode '{mode}' for Compliance Migration"
+            )))
+        }
+    };
+
+    storage
+        .apply(&[(
+            COMPLIANCE_CONFIG_KEY.to_vec(),
+            Op::Put(
+                minicbor::to_vec(ComplianceConfig { admin, mode })
+                    .map_err(ManyError::serialization_error)?,
+            ),
+        )])
+        .map_err(error::storage_apply_failed)?;
+    Ok(())
+}
+
+#[distributed_slice(MIGRATIONS)]
+pub static COMPLIANCE_MIGRATION: InnerMigration<InnerStorage, ManyError> =
+    InnerMigration::new_initialize(
+        initialize,
+        "Compliance Migration",
+        "Enables the compliance list, which restricts the addresses that can send and receive tokens",
+    );
@@ -0,0 +1,11 @@
+use crate::migration::MIGRATIONS;
+use linkme::distributed_slice;
+use many_error::ManyError;
+use many_migration::InnerMigration;
+
+#[distributed_slice(MIGRATIONS)]
+pub static COSIGN_MIGRATION: InnerMigration<merk::Merk, ManyError> = InnerMigration::new_trigger(
+    false,
+    "Cosign Migration",
+    "Enables executing multisig transactions approved by co-signatures",
+);
@@ -15,6 +15,9 @@ use std::collections::{BTreeMap, HashMap};
 pub static ACCOUNT_TOTAL_COUNT_INDEX: DataIndex = DataIndex::new(0).with_index(2).with_index(0);
 pub static NON_ZERO_ACCOUNT_TOTAL_COUNT_INDEX: DataIndex =
     DataIndex::new(0).with_index(2).with_index(1);
+/// The total supply gauges are `0.3.n`, for the n-th token in symbol order.
+/// They are computed from the token info once the token migration is active.
+pub static TOTAL_SUPPLY_INDEX: DataIndex = DataIndex::new(0).with_index(3);
 
 const BALANCES_ROOT_BYTES: &[u8] = b"/balances";
 
@@ -0,0 +1,192 @@
+//! Redact the memos and references of historical events, e.g. to comply
+//! with a legal takedown request.
+//!
+//! The memo of each listed event (and for multisig submissions, the legacy
+//! memo and data fields and the memo of the submitted send) and the reference
+//! of sends are replaced by `redacted:` followed by the hex SHA3-256 hash of
+//! a salt and the CBOR encoding of the removed content. Whoever holds the
+//! original content and the salt can still prove it was part of the event,
+//! but the ledger does not serve it anymore. Without the salt, short contents
+//! cannot be recovered by hashing guesses, so the salt should only be shared
+//! with the validators and not published with the configuration.
+//!
+//! Events are part of the state, so the migration changes the state hash at
+//! its block height like any other migration. Every node runs it at the same
+//! height and agrees on the new hash; blocks before that height are left as
+//! they were committed. The events digests of the redacted blocks computed
+//! after the migration differ from the ones returned when the blocks were
+//! committed.
+//!
+//! The events to redact are given by their hex encoded IDs, with a hex
+//! encoded salt of at least 16 bytes. The migration is repeatable: each
+//! takedown is another entry of the configuration, at its own height.
+//!
+//! ```json
+//! [
+//!   {
+//!     "name": "Event Redaction Migration",
+//!     "block_height": 123456,
+//!     "salt": "8c1f6e0d2a9b4c7e5f3a1d0b9e8c7f6a",
+//!     "events": ["0200000001", "0200000002"]
+//!   },
+//!   {
+//!     "name": "Event Redaction Migration",
+//!     "block_height": 234567,
+//!     "salt": "0f7e1c2b3a4d5e6f708192a3b4c5d6e7",
+//!     "events": ["0300000005"]
+//!   }
+//! ]
+//! ```
+use crate::error;
+use crate::migration::MIGRATIONS;
+use crate::storage::event::key_for_event;
+use crate::storage::InnerStorage;
+use linkme::distributed_slice;
+use many_error::ManyError;
+use many_migration::InnerMigration;
+use many_modules::events::{AccountMultisigTransaction, EventId, EventInfo, EventLog};
+use many_types::Memo;
+use merk::Op;
+use serde_json::Value;
+use sha3::{Digest, Sha3_256};
+use std::collections::HashMap;
+
+pub const REDACTED_PREFIX: &str = "redacted:";
+
+/// The shortest salt accepted by the migration, in bytes.
+pub const MIN_SALT_LENGTH: usize = 16;
+
+/// The hash replacing redacted content, `redacted:` followed by the hex
+/// SHA3-256 hash of the salt and the CBOR encoding of the content.
+pub fn redacted(salt: &[u8], content: &impl minicbor::Encode<()>) -> Result<String, ManyError> {
+    let bytes = minicbor::to_vec(content).map_err(ManyError::serialization_error)?;
+    let hash = Sha3_256::new().chain_update(salt).chain_update(bytes);
+    Ok(format!("{REDACTED_PREFIX}{}", hex::encode(hash.finalize())))
+}
+
+/// Replace the memo, data and reference of an event by their hashes. Returns
+/// whether there was anything to redact.
+pub fn redact_event(event: &mut EventInfo, salt: &[u8]) -> Result<bool, ManyError> {
+    let mut hashes = Vec::new();
+    if let Some(memo) = event.memo_mut().and_then(Option::take) {
+        hashes.push(redacted(salt, &memo)?);
+    }
+    if let EventInfo::Send { reference, .. } = event {
+        if let Some(reference) = reference.take() {
+            hashes.push(redacted(salt, &reference)?);
+        }
+    }
+    if let EventInfo::AccountMultisigSubmit {
+        memo_,
+        data_,
+        transaction,
+        ..
+    } = event
+    {
+        if let Some(memo) = memo_.take() {
+            hashes.push(redacted(salt, &memo)?);
+        }
+        if let Some(data) = data_.take() {
+            hashes.push(redacted(salt, &data)?);
+        }
+        if let AccountMultisigTransaction::Send(args) = transaction.as_mut() {
+            if let Some(memo) = args.memo.take() {
+                hashes.push(redacted(salt, &memo)?);
+            }
+            if let Some(reference) = args.reference.take() {
+                hashes.push(redacted(salt, &reference)?);
+            }
+        }
+    }
+
+    let mut hashes = hashes.into_iter();
+    let Some(first) = hashes.next() else {
+        return Ok(false);
+    };
+    let mut memo = Memo::try_from(first)?;
+    for hash in hashes {
+        memo.push_str(hash)?;
+    }
+    if let Some(field) = event.memo_mut() {
+        *field = Some(memo);
+    }
+    Ok(true)
+}
+
+/// Redact the events with these IDs in the storage. Events which do not
+/// exist or have nothing to redact are skipped with a warning, so a mistake
+/// in the list does not stop the chain.
+pub fn redact_events(
+    storage: &mut InnerStorage,
+    ids: impl IntoIterator<Item = EventId>,
+    salt: &[u8],
+) -> Result<(), ManyError> {
+    let mut batch = Vec::new();
+    for id in ids {
+        let key = key_for_event(id.clone());
+        let Some(value) = storage.get(&key).map_err(error::storage_get_failed)? else {
+            tracing::warn!("Event {} not found, skipping redaction", hex::encode(&id));
+            continue;
+        };
+
+        let mut event: EventLog =
+            minicbor::decode(&value).map_err(ManyError::deserialization_error)?;
+        if !redact_event(&mut event.content, salt)? {
+            tracing::warn!("Event {} has nothing to redact", hex::encode(&id));
+            continue;
+        }
+        batch.push((
+            key,
+            Op::Put(minicbor::to_vec(event).map_err(ManyError::serialization_error)?),
+        ));
+    }
+
+    batch.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
+    batch.dedup_by(|(k1, _), (k2, _)| k1 == k2);
+    storage
+        .apply(batch.as_slice())
+        .map_err(error::storage_apply_failed)
+}
+
+fn extra_param<T: serde::de::DeserializeOwned>(
+    extra: &HashMap<String, Value>,
+    name: &str,
+) -> Result<T, ManyError> {
+    extra
+        .get(name)
+        .map(|value| serde_json::from_value(value.clone()))
+        .transpose()
+        .map_err(ManyError::deserialization_error)?
+        .ok_or_else(|| {
+            ManyError::unknown(format!(
+                "Missing extra parameter '{name}' for Event Redaction Migration"
+            ))
+        })
+}
+
+fn initialize(storage: &mut InnerStorage, extra: &HashMap<String, Value>) -> Result<(), ManyError> {
+    let events: Vec<String> = extra_param(extra, "events")?;
+    let salt: String = extra_param(extra, "salt")?;
+    let salt = hex::decode(salt).map_err(ManyError::deserialization_error)?;
+    if salt.len() < MIN_SALT_LENGTH {
+        return Err(ManyError::unknown(format!(
+            "The salt of Event Redaction Migration must be at least {MIN_SALT_LENGTH} bytes"
+        )));
+    }
+    let ids = events
+        .iter()
+        .map(|id| hex::decode(id).map(EventId::from))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(ManyError::deserialization_error)?;
+
+    redact_events(storage, ids, &salt)
+}
+
+#[distributed_slice(MIGRATIONS)]
+pub static EVENT_REDACTION_MIGRATION: InnerMigration<InnerStorage, ManyError> =
+    InnerMigration::new_initialize(
+        initialize,
+        "Event Redaction Migration",
+        "Replace the memos and references of a list of events by their salted hashes",
+    )
+    .repeatable();
@@ -58,6 +58,7 @@ fn update_multisig_submit_events(storage: &mut InnerStorage) -> Result<(), ManyE
             execute_automatically,
             data_,
             memo,
+            attachment,
         } = content
         {
             if memo.is_some() {
@@ -89,6 +90,7 @@ fn update_multisig_submit_events(storage: &mut InnerStorage) -> Result<(), ManyE
                         execute_automatically,
                         data_: None,
                         memo: Some(memo),
+                        attachment,
                     },
                 };
                 batch.push((
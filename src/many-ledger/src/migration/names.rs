@@ -0,0 +1,71 @@
+use crate::error;
+use crate::migration::MIGRATIONS;
+use crate::storage::names::{NamesConfig, NAMES_CONFIG_KEY};
+use crate::storage::InnerStorage;
+use linkme::distributed_slice;
+use many_error::ManyError;
+use many_migration::InnerMigration;
+use many_modules::names::NameFee;
+use many_types::ledger::{Symbol, TokenAmount};
+use merk::Op;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+fn param<T: DeserializeOwned>(
+    extra: &HashMap<String, Value>,
+    name: &str,
+) -> Result<Option<T>, ManyError> {
+    extra
+        .get(name)
+        .map(|value| serde_json::from_value(value.clone()))
+        .transpose()
+        .map_err(ManyError::deserialization_error)
+}
+
+/// Store the configuration of the name service. The fee is optional, but all
+/// of its parameters must be given together.
+fn initialize(storage: &mut InnerStorage, extra: &HashMap<String, Value>) -> Result<(), ManyError> {
+    let duration_in_secs: u64 = param(extra, "duration_in_secs")?.ok_or_else(|| {
+        ManyError::unknown("Missing extra parameter 'duration_in_secs' for Names Migration")
+    })?;
+
+    let fee = match (
+        param::<String>(extra, "fee_symbol")?,
+        param::<u64>(extra, "fee_amount")?,
+        param::<String>(extra, "fee_collector")?,
+    ) {
+        (Some(symbol), Some(amount), Some(collector)) => Some(NameFee {
+            symbol: Symbol::from_str(&symbol)?,
+            amount: TokenAmount::from(amount),
+            collector: FromStr::from_str(&collector)?,
+        }),
+        (None, None, None) => None,
+        _ => {
+            return Err(ManyError::unknown(
+                "Extra parameters 'fee_symbol', 'fee_amount' and 'fee_collector' of Names Migration must be given together",
+            ))
+        }
+    };
+
+    let config = NamesConfig {
+        fee,
+        duration_in_secs,
+    };
+    storage
+        .apply(&[(
+            NAMES_CONFIG_KEY.to_vec(),
+            Op::Put(minicbor::to_vec(config).map_err(ManyError::serialization_error)?),
+        )])
+        .map_err(error::storage_apply_failed)?;
+    Ok(())
+}
+
+#[distributed_slice(MIGRATIONS)]
+pub static NAMES_MIGRATION: InnerMigration<InnerStorage, ManyError> =
+    InnerMigration::new_initialize(
+        initialize,
+        "Names Migration",
+        "Enables the name service and stores its fee and registration duration",
+    );
@@ -0,0 +1,71 @@
+use crate::error;
+use crate::migration::MIGRATIONS;
+use crate::storage::relay::{RelayConfig, RELAY_CONFIG_KEY};
+use crate::storage::InnerStorage;
+use linkme::distributed_slice;
+use many_error::ManyError;
+use many_migration::InnerMigration;
+use many_modules::relay::RelayFee;
+use many_types::ledger::{Symbol, TokenAmount};
+use merk::Op;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+fn param<T: DeserializeOwned>(
+    extra: &HashMap<String, Value>,
+    name: &str,
+) -> Result<Option<T>, ManyError> {
+    extra
+        .get(name)
+        .map(|value| serde_json::from_value(value.clone()))
+        .transpose()
+        .map_err(ManyError::deserialization_error)
+}
+
+/// Store the configuration of relayed requests. The fee is optional, but all
+/// of its parameters must be given together.
+fn initialize(storage: &mut InnerStorage, extra: &HashMap<String, Value>) -> Result<(), ManyError> {
+    let timeout_in_secs: u64 = param(extra, "timeout_in_secs")?.ok_or_else(|| {
+        ManyError::unknown("Missing extra parameter 'timeout_in_secs' for Relay Migration")
+    })?;
+
+    let fee = match (
+        param::<String>(extra, "fee_symbol")?,
+        param::<u64>(extra, "fee_amount")?,
+        param::<String>(extra, "fee_collector")?,
+    ) {
+        (Some(symbol), Some(amount), Some(collector)) => Some(RelayFee {
+            symbol: Symbol::from_str(&symbol)?,
+            amount: TokenAmount::from(amount),
+            collector: FromStr::from_str(&collector)?,
+        }),
+        (None, None, None) => None,
+        _ => {
+            return Err(ManyError::unknown(
+                "Extra parameters 'fee_symbol', 'fee_amount' and 'fee_collector' of Relay Migration must be given together",
+            ))
+        }
+    };
+
+    let config = RelayConfig {
+        fee,
+        timeout_in_secs,
+    };
+    storage
+        .apply(&[(
+            RELAY_CONFIG_KEY.to_vec(),
+            Op::Put(minicbor::to_vec(config).map_err(ManyError::serialization_error)?),
+        )])
+        .map_err(error::storage_apply_failed)?;
+    Ok(())
+}
+
+#[distributed_slice(MIGRATIONS)]
+pub static RELAY_MIGRATION: InnerMigration<InnerStorage, ManyError> =
+    InnerMigration::new_initialize(
+        initialize,
+        "Relay Migration",
+        "Enables relaying pre-signed requests and stores the relay fee",
+    );
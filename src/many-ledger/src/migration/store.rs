@@ -0,0 +1,41 @@
+use crate::error;
+use crate::migration::MIGRATIONS;
+use crate::storage::store::{StoreConfig, STORE_CONFIG_KEY};
+use crate::storage::InnerStorage;
+use linkme::distributed_slice;
+use many_error::ManyError;
+use many_migration::InnerMigration;
+use merk::Op;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Store the maximum size of objects, in bytes.
+fn initialize(storage: &mut InnerStorage, extra: &HashMap<String, Value>) -> Result<(), ManyError> {
+    let max_size: u64 = extra
+        .get("max_size")
+        .map(|value| serde_json::from_value(value.clone()))
+        .transpose()
+        .map_err(ManyError::deserialization_error)?
+        .ok_or_else(|| {
+            ManyError::unknown("Missing extra parameter 'max_size' for Store Migration")
+        })?;
+
+    storage
+        .apply(&[(
+            STORE_CONFIG_KEY.to_vec(),
+            Op::Put(
+                minicbor::to_vec(StoreConfig { max_size })
+                    .map_err(ManyError::serialization_error)?,
+            ),
+        )])
+        .map_err(error::storage_apply_failed)?;
+    Ok(())
+}
+
+#[distributed_slice(MIGRATIONS)]
+pub static STORE_MIGRATION: InnerMigration<InnerStorage, ManyError> =
+    InnerMigration::new_initialize(
+        initialize,
+        "Store Migration",
+        "Enables the object store module and stores its maximum object size",
+    );
@@ -0,0 +1,96 @@
+use crate::error;
+use crate::migration::MIGRATIONS;
+use crate::storage::iterator::LedgerIterator;
+use crate::storage::ledger_tokens::SYMBOLS_ROOT_DASH;
+use crate::storage::supply_history::{key_for_supply_history, SupplyDelta};
+use crate::storage::InnerStorage;
+use linkme::distributed_slice;
+use many_error::ManyError;
+use many_migration::InnerMigration;
+use many_modules::events::{EventInfo, EventLog};
+use many_types::ledger::{LedgerTokensAddressMap, Symbol, TokenAmount, TokenInfo};
+use merk::Op;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+
+fn total(distribution: &LedgerTokensAddressMap) -> TokenAmount {
+    distribution
+        .values()
+        .fold(TokenAmount::zero(), |total, amount| total + amount.clone())
+}
+
+/// Build the supply history of every token from the token events logged
+/// before the migration. Supply which is not explained by the events (e.g.
+/// set at genesis) is recorded at height 0.
+fn initialize(storage: &mut InnerStorage, _: &HashMap<String, Value>) -> Result<(), ManyError> {
+    let mut deltas: BTreeMap<(Symbol, u64), SupplyDelta> = BTreeMap::new();
+    for item in LedgerIterator::all_events(storage) {
+        let (_, value) = item.map_err(ManyError::unknown)?;
+        let event: EventLog = minicbor::decode(&value).map_err(ManyError::deserialization_error)?;
+        let height = event.id.height();
+        match event.content {
+            EventInfo::TokenCreate {
+                symbol,
+                initial_distribution: Some(distribution),
+                ..
+            }
+            | EventInfo::TokenMint {
+                symbol,
+                distribution,
+                ..
+            } => {
+                deltas.entry((symbol, height)).or_default().minted += total(&distribution);
+            }
+            EventInfo::TokenBurn {
+                symbol,
+                distribution,
+                ..
+            } => {
+                deltas.entry((symbol, height)).or_default().burned += total(&distribution);
+            }
+            _ => {}
+        }
+    }
+
+    for item in LedgerIterator::all_symbols(storage, Default::default()) {
+        let (key, value) = item.map_err(ManyError::unknown)?;
+        let symbol = std::str::from_utf8(&key[SYMBOLS_ROOT_DASH.len()..])
+            .map_err(ManyError::deserialization_error)
+            .and_then(Symbol::from_str)?;
+        let info: TokenInfo = minicbor::decode(&value).map_err(ManyError::deserialization_error)?;
+
+        let (minted, burned) = deltas.iter().filter(|((s, _), _)| s == &symbol).fold(
+            (TokenAmount::zero(), TokenAmount::zero()),
+            |(m, b), (_, d)| (m + d.minted.clone(), b + d.burned.clone()),
+        );
+        let expected = info.supply.circulating + burned;
+        if expected > minted {
+            deltas.entry((symbol, 0)).or_default().minted += expected - minted;
+        } else if expected < minted {
+            deltas.entry((symbol, 0)).or_default().burned += minted - expected;
+        }
+    }
+
+    let mut batch = deltas
+        .into_iter()
+        .map(|((symbol, height), delta)| {
+            Ok((
+                key_for_supply_history(&symbol, height),
+                Op::Put(minicbor::to_vec(delta).map_err(ManyError::serialization_error)?),
+            ))
+        })
+        .collect::<Result<Vec<_>, ManyError>>()?;
+    batch.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
+    storage
+        .apply(batch.as_slice())
+        .map_err(error::storage_apply_failed)
+}
+
+#[distributed_slice(MIGRATIONS)]
+pub static SUPPLY_HISTORY_MIGRATION: InnerMigration<InnerStorage, ManyError> =
+    InnerMigration::new_initialize(
+        initialize,
+        "Supply History Migration",
+        "Record the tokens minted and burned at each height",
+    );
@@ -2,14 +2,22 @@ use crate::error;
 use crate::json::InitialStateJson;
 use crate::storage::LedgerStorage;
 use many_error::ManyError;
+use many_identity::Verifier;
+use many_identity_dsa::CoseKeyVerifier;
 use many_migration::MigrationConfig;
-use std::fmt::Debug;
+use many_server::simulation::Simulator;
+use many_server::transaction::StorageTransaction;
+use many_types::cbor::CborAny;
+use many_types::BlockTime;
+use std::fmt::{Debug, Formatter};
 use std::path::Path;
 use tracing::info;
 
 mod abci;
 pub mod account;
 pub mod allow_addrs;
+mod attest;
+mod compliance;
 mod data;
 mod event;
 mod idstore;
@@ -19,11 +27,25 @@ mod ledger_commands;
 mod ledger_mintburn;
 mod ledger_tokens;
 mod multisig;
+mod names;
+mod random;
+mod relay;
+mod store;
 
 /// A simple ledger that keeps transactions in memory.
-#[derive(Debug)]
 pub struct LedgerModuleImpl {
     storage: LedgerStorage,
+
+    /// Verifies the co-signatures of `account.cosignExecute`.
+    cosign_verifier: Box<dyn Verifier>,
+}
+
+impl Debug for LedgerModuleImpl {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LedgerModuleImpl")
+            .field("storage", &self.storage)
+            .finish()
+    }
 }
 
 impl LedgerModuleImpl {
@@ -71,7 +93,10 @@ impl LedgerModuleImpl {
 
         tracing::debug!("Final migrations: {:?}", storage.migrations());
 
-        Ok(Self { storage })
+        Ok(Self {
+            storage,
+            cosign_verifier: Box::new(CoseKeyVerifier),
+        })
     }
 
     pub fn load<P: AsRef<Path>>(
@@ -83,7 +108,26 @@ impl LedgerModuleImpl {
 
         tracing::debug!("Final migrations: {:?}", storage.migrations());
 
-        Ok(Self { storage })
+        Ok(Self {
+            storage,
+            cosign_verifier: Box::new(CoseKeyVerifier),
+        })
+    }
+
+    /// Verify co-signatures with the verifiers of the server instead of only
+    /// accepting COSE keys, e.g. so WebAuthn signers can co-sign.
+    pub fn set_cosign_verifier(&mut self, verifier: impl Verifier + 'static) {
+        self.cosign_verifier = Box::new(verifier);
+    }
+
+    /// The height of the last committed block.
+    pub fn height(&self) -> Result<u64, ManyError> {
+        self.storage.get_height()
+    }
+
+    /// The time of the current block, if running behind a blockchain.
+    pub fn block_time(&self) -> Option<BlockTime> {
+        self.storage.block_time()
     }
 
     #[cfg(feature = "balance_testing")]
@@ -97,3 +141,33 @@ impl LedgerModuleImpl {
             .set_balance_only_for_testing(account, balance, symbol)
     }
 }
+
+impl StorageTransaction for LedgerModuleImpl {
+    fn begin(&mut self) -> Result<(), ManyError> {
+        self.storage.begin_transaction()
+    }
+
+    fn commit(&mut self) -> Result<(), ManyError> {
+        self.storage.commit_transaction()
+    }
+
+    fn rollback(&mut self) -> Result<(), ManyError> {
+        self.storage.rollback_transaction()
+    }
+}
+
+impl Simulator for LedgerModuleImpl {
+    fn begin(&mut self) -> Result<(), ManyError> {
+        self.storage.begin_transaction()
+    }
+
+    fn rollback(&mut self) -> Result<Vec<CborAny>, ManyError> {
+        // Roll back even if the events cannot be read.
+        let events = self.storage.transaction_events();
+        self.storage.rollback_transaction()?;
+        events?
+            .iter()
+            .map(|event| minicbor::decode(event).map_err(ManyError::deserialization_error))
+            .collect()
+    }
+}
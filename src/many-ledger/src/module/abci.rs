@@ -4,7 +4,7 @@ use many_modules::abci_backend::{
     AbciBlock, AbciCommitInfo, AbciInfo, AbciInit, BeginBlockReturn, EndpointInfo, InitChainReturn,
     ManyAbciModuleBackend,
 };
-use many_types::Timestamp;
+use many_types::BlockTime;
 use std::collections::BTreeMap;
 use tracing::info;
 
@@ -17,6 +17,7 @@ impl ManyAbciModuleBackend for LedgerModuleImpl {
             endpoints: BTreeMap::from([
                 ("ledger.info".to_string(), EndpointInfo { is_command: false }),
                 ("ledger.balance".to_string(), EndpointInfo { is_command: false }),
+                ("ledger.prove".to_string(), EndpointInfo { is_command: false }),
                 ("ledger.send".to_string(), EndpointInfo { is_command: true }),
 
                 // Events
@@ -38,6 +39,8 @@ impl ManyAbciModuleBackend for LedgerModuleImpl {
                 ("account.info".to_string(), EndpointInfo { is_command: false }),
                 ("account.disable".to_string(), EndpointInfo { is_command: true }),
                 ("account.addFeatures".to_string(), EndpointInfo { is_command: true }),
+                ("account.listSubresources".to_string(), EndpointInfo { is_command: false }),
+                ("account.listForIdentity".to_string(), EndpointInfo { is_command: false }),
 
                 // Account Features - Multisig
                 ("account.multisigSetDefaults".to_string(), EndpointInfo { is_command: true }),
@@ -47,16 +50,44 @@ impl ManyAbciModuleBackend for LedgerModuleImpl {
                 ("account.multisigRevoke".to_string(), EndpointInfo { is_command: true }),
                 ("account.multisigExecute".to_string(), EndpointInfo { is_command: true }),
                 ("account.multisigWithdraw".to_string(), EndpointInfo { is_command: true }),
+                ("account.cosignExecute".to_string(), EndpointInfo { is_command: true }),
 
                 // Data Attributes
                 ("data.info".to_string(), EndpointInfo { is_command: false }),
                 ("data.getInfo".to_string(), EndpointInfo { is_command: false }),
                 ("data.query".to_string(), EndpointInfo { is_command: false }),
 
+                // Randomness
+                ("random.get".to_string(), EndpointInfo { is_command: false }),
+
+                // Names
+                ("names.info".to_string(), EndpointInfo { is_command: false }),
+                ("names.resolve".to_string(), EndpointInfo { is_command: false }),
+                ("names.register".to_string(), EndpointInfo { is_command: true }),
+                ("names.transfer".to_string(), EndpointInfo { is_command: true }),
+
+                // Attestations
+                ("attest.info".to_string(), EndpointInfo { is_command: false }),
+                ("attest.get".to_string(), EndpointInfo { is_command: false }),
+                ("attest.publish".to_string(), EndpointInfo { is_command: true }),
+                ("attest.revoke".to_string(), EndpointInfo { is_command: true }),
+
+                // Relayed requests
+                ("relay.info".to_string(), EndpointInfo { is_command: false }),
+                ("relay.execute".to_string(), EndpointInfo { is_command: true }),
+
+                // Object store
+                ("store.info".to_string(), EndpointInfo { is_command: false }),
+                ("store.get".to_string(), EndpointInfo { is_command: false }),
+                ("store.put".to_string(), EndpointInfo { is_command: true }),
+                ("store.pin".to_string(), EndpointInfo { is_command: true }),
+                ("store.unpin".to_string(), EndpointInfo { is_command: true }),
+
                 // Token attribute
                 ("tokens.create".to_string(), EndpointInfo { is_command : true }),
                 ("tokens.update".to_string(), EndpointInfo { is_command : true }),
                 ("tokens.info".to_string(), EndpointInfo { is_command : false }),
+                ("tokens.supplyHistory".to_string(), EndpointInfo { is_command : false }),
                 ("tokens.addExtendedInfo".to_string(), EndpointInfo { is_command : true }),
                 ("tokens.removeExtendedInfo".to_string(), EndpointInfo { is_command : true }),
                 ("tokens.mint".to_string(), EndpointInfo { is_command : true }),
@@ -79,8 +110,7 @@ impl ManyAbciModuleBackend for LedgerModuleImpl {
         );
 
         if let Some(time) = time {
-            let time = Timestamp::new(time)?;
-            self.storage.set_time(time);
+            self.storage.set_time(BlockTime::from_secs(time));
         }
 
         Ok(BeginBlockReturn {})
@@ -95,14 +125,21 @@ impl ManyAbciModuleBackend for LedgerModuleImpl {
             height,
             hex::encode(storage.hash()).as_str()
         );
+        // The events of the last committed block were emitted while the
+        // height was one less than the current one.
+        let events_digest = height
+            .checked_sub(1)
+            .map(|h| storage.block_events_digest(h))
+            .transpose()?;
         Ok(AbciInfo {
             height,
             hash: storage.hash().into(),
+            events_digest: events_digest.map(Into::into),
         })
     }
 
     fn commit(&mut self) -> Result<AbciCommitInfo, ManyError> {
-        let result = self.storage.commit();
+        let result = self.storage.commit()?;
 
         info!(
             "abci.commit(): retain_height={} hash={}",
@@ -1,15 +1,19 @@
+use crate::migration::account_index::ACCOUNT_INDEX_MIGRATION;
 use crate::module::LedgerModuleImpl;
-use coset::CoseSign1;
+use coset::{CoseSign, CoseSign1};
 use many_error::{ManyError, ManyErrorCode};
 use many_identity::Address;
-use many_modules::account::features::{multisig, FeatureId, FeatureInfo, TryCreateFeature};
+use many_modules::account::features::{cosign, multisig, FeatureId, FeatureInfo, TryCreateFeature};
 use many_modules::account::{Account, AccountModuleBackend, Role};
 use many_modules::{account, EmptyReturn, ManyModule, ManyModuleInfo};
 use many_protocol::{context::Context, RequestMessage, ResponseMessage};
 use many_types::cbor::CborAny;
+use many_types::BlockTime;
 use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::{Debug, Formatter};
 
+const MAXIMUM_SUBRESOURCE_COUNT: usize = 100;
+
 fn get_roles_for_account(account: &account::Account) -> BTreeSet<account::Role> {
     let features = account.features();
 
@@ -19,6 +23,9 @@ fn get_roles_for_account(account: &account::Account) -> BTreeSet<account::Role>
     if features.has_id(multisig::MultisigAccountFeature::ID) {
         roles.append(&mut multisig::MultisigAccountFeature::roles());
     }
+    if features.has_id(cosign::CosignAccountFeature::ID) {
+        roles.append(&mut cosign::CosignAccountFeature::roles());
+    }
     if features.has_id(account::features::ledger::AccountLedger::ID) {
         roles.append(&mut account::features::ledger::AccountLedger::roles());
     }
@@ -38,6 +45,11 @@ pub(crate) fn validate_features_for_account(account: &account::Account) -> Resul
             return Err(e);
         }
     }
+    if let Err(e) = features.get::<cosign::CosignAccountFeature>() {
+        if e.code() != ManyErrorCode::AttributeNotFound {
+            return Err(e);
+        }
+    }
     if let Err(e) = features.get::<account::features::ledger::AccountLedger>() {
         if e.code() != ManyErrorCode::AttributeNotFound {
             return Err(e);
@@ -66,6 +78,9 @@ pub(crate) fn validate_roles_for_account(account: &account::Account) -> Result<(
     if features.get::<multisig::MultisigAccountFeature>().is_ok() {
         allowed_roles.append(&mut multisig::MultisigAccountFeature::roles());
     }
+    if features.get::<cosign::CosignAccountFeature>().is_ok() {
+        allowed_roles.append(&mut cosign::CosignAccountFeature::roles());
+    }
     if features
         .get::<account::features::ledger::AccountLedger>()
         .is_ok()
@@ -148,6 +163,23 @@ impl AccountModuleBackend for LedgerModuleImpl {
             .map(|_| EmptyReturn)
     }
 
+    fn set_metadata(
+        &mut self,
+        sender: &Address,
+        args: account::SetMetadataArgs,
+    ) -> Result<EmptyReturn, ManyError> {
+        let (account, _) = self.storage.get_account(&args.account)?;
+
+        if !account.has_role(sender, account::Role::Owner) {
+            return Err(account::errors::user_needs_role("owner"));
+        }
+        account::validate_metadata(&args.metadata)?;
+
+        self.storage
+            .set_metadata(account, args)
+            .map(|_| EmptyReturn)
+    }
+
     fn list_roles(
         &self,
         _: &Address,
@@ -231,6 +263,7 @@ impl AccountModuleBackend for LedgerModuleImpl {
                         roles,
                         features,
                         disabled,
+                        metadata,
                     },
                     keys,
                 )| {
@@ -241,6 +274,7 @@ impl AccountModuleBackend for LedgerModuleImpl {
                             roles,
                             features,
                             disabled,
+                            metadata,
                         })
                 },
             )
@@ -278,6 +312,57 @@ impl AccountModuleBackend for LedgerModuleImpl {
                 .map(|_| EmptyReturn)
         }
     }
+
+    fn list_subresources(
+        &self,
+        _: &Address,
+        args: account::ListSubresourcesArgs,
+        context: Context,
+    ) -> Result<account::ListSubresourcesReturn, ManyError> {
+        let count = args.count.map_or(MAXIMUM_SUBRESOURCE_COUNT, |c| {
+            std::cmp::min(c as usize, MAXIMUM_SUBRESOURCE_COUNT)
+        });
+        let (subresources, next_subresource, keys) =
+            self.storage
+                .list_subresources(&args.account, args.start.unwrap_or_default(), count)?;
+
+        self.storage
+            .prove_state(context, keys)
+            .map(|_| account::ListSubresourcesReturn {
+                subresources,
+                next_subresource,
+            })
+    }
+
+    fn list_for_identity(
+        &self,
+        _: &Address,
+        args: account::ListForIdentityArgs,
+        context: Context,
+    ) -> Result<account::ListForIdentityReturn, ManyError> {
+        if !self
+            .storage
+            .migrations()
+            .is_active(&ACCOUNT_INDEX_MIGRATION)
+        {
+            return Err(ManyError::invalid_method_name("account.listForIdentity"));
+        }
+
+        let (accounts, keys) = self.storage.list_accounts_for_identity(&args.address)?;
+        let accounts = accounts
+            .into_iter()
+            .map(|(id, account)| account::AccountMembership {
+                account: id,
+                roles: account.get_roles(&args.address),
+                description: account.description,
+                features: account.features,
+            })
+            .collect();
+
+        self.storage
+            .prove_state(context, keys)
+            .map(|_| account::ListForIdentityReturn { accounts })
+    }
 }
 
 /// A module for returning the features by this account.
@@ -319,7 +404,30 @@ impl<T: AccountModuleBackend> ManyModule for AccountFeatureModule<T> {
         self.inner.validate(message, envelope)
     }
 
+    fn validate_multi(
+        &self,
+        message: &RequestMessage,
+        envelope: &CoseSign,
+        signers: &BTreeSet<Address>,
+    ) -> Result<(), ManyError> {
+        self.inner.validate_multi(message, envelope, signers)
+    }
+
     async fn execute(&self, message: RequestMessage) -> Result<ResponseMessage, ManyError> {
-        self.inner.execute(message).await
+        self.execute_with_block_time(message, None).await
+    }
+
+    async fn execute_with_block_time(
+        &self,
+        message: RequestMessage,
+        block_time: Option<BlockTime>,
+    ) -> Result<ResponseMessage, ManyError> {
+        self.inner
+            .execute_with_block_time(message, block_time)
+            .await
+    }
+
+    fn heartbeat(&self) -> Result<(), ManyError> {
+        self.inner.heartbeat()
     }
 }
@@ -0,0 +1,73 @@
+use crate::migration::attest::ATTEST_MIGRATION;
+use crate::module::LedgerModuleImpl;
+use crate::storage::attest::ATTESTORS_KEY;
+use many_error::ManyError;
+use many_identity::Address;
+use many_modules::attest;
+use many_protocol::context::Context;
+use tracing::info;
+
+impl LedgerModuleImpl {
+    fn check_attest_migration(&self, method: &str) -> Result<(), ManyError> {
+        if self.storage.migrations().is_active(&ATTEST_MIGRATION) {
+            Ok(())
+        } else {
+            Err(ManyError::invalid_method_name(method))
+        }
+    }
+}
+
+impl attest::AttestModuleBackend for LedgerModuleImpl {
+    fn info(
+        &self,
+        _sender: &Address,
+        _args: attest::InfoArgs,
+    ) -> Result<attest::InfoReturns, ManyError> {
+        self.check_attest_migration("attest.info")?;
+        Ok(attest::InfoReturns {
+            attestors: self.storage.get_attestors()?,
+        })
+    }
+
+    fn get(
+        &self,
+        _sender: &Address,
+        args: attest::GetArgs,
+        context: Context,
+    ) -> Result<attest::GetReturns, ManyError> {
+        self.check_attest_migration("attest.get")?;
+        let (claims, mut keys) = self
+            .storage
+            .get_claims(&args.subject, args.attestor.as_ref())?;
+
+        // Prove the attestors too so a light client can check that the claims
+        // come from trusted attestors.
+        keys.push(ATTESTORS_KEY.to_vec());
+        self.storage.prove_state(context, keys)?;
+        Ok(attest::GetReturns { claims })
+    }
+
+    fn publish(
+        &mut self,
+        sender: &Address,
+        args: attest::PublishArgs,
+    ) -> Result<attest::PublishReturns, ManyError> {
+        self.check_attest_migration("attest.publish")?;
+        info!("publish({}, {} => {})", sender, args.kind, args.subject);
+        self.storage
+            .publish_claim(sender, args.subject, args.kind, args.value, args.expiration)
+            .map(|_| attest::PublishReturns {})
+    }
+
+    fn revoke(
+        &mut self,
+        sender: &Address,
+        args: attest::RevokeArgs,
+    ) -> Result<attest::RevokeReturns, ManyError> {
+        self.check_attest_migration("attest.revoke")?;
+        info!("revoke({}, {} => {})", sender, args.kind, args.subject);
+        self.storage
+            .revoke_claim(sender, args.subject, args.kind)
+            .map(|_| attest::RevokeReturns {})
+    }
+}
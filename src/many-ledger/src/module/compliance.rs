@@ -0,0 +1,66 @@
+use crate::migration::compliance::COMPLIANCE_MIGRATION;
+use crate::module::LedgerModuleImpl;
+use many_error::ManyError;
+use many_identity::Address;
+use many_modules::compliance;
+use tracing::info;
+
+impl LedgerModuleImpl {
+    fn check_compliance_migration(&self, method: &str) -> Result<(), ManyError> {
+        if self.storage.migrations().is_active(&COMPLIANCE_MIGRATION) {
+            Ok(())
+        } else {
+            Err(ManyError::invalid_method_name(method))
+        }
+    }
+}
+
+impl compliance::ComplianceModuleBackend for LedgerModuleImpl {
+    fn info(
+        &self,
+        _sender: &Address,
+        _args: compliance::InfoArgs,
+    ) -> Result<compliance::InfoReturns, ManyError> {
+        self.check_compliance_migration("compliance.info")?;
+        let config = self.storage.get_compliance_config()?;
+        Ok(compliance::InfoReturns {
+            admin: config.admin,
+            mode: config.mode,
+        })
+    }
+
+    fn list(
+        &self,
+        _sender: &Address,
+        _args: compliance::ListArgs,
+    ) -> Result<compliance::ListReturns, ManyError> {
+        self.check_compliance_migration("compliance.list")?;
+        Ok(compliance::ListReturns {
+            addresses: self.storage.get_compliance_list()?,
+        })
+    }
+
+    fn add(
+        &mut self,
+        sender: &Address,
+        args: compliance::AddArgs,
+    ) -> Result<compliance::AddReturns, ManyError> {
+        self.check_compliance_migration("compliance.add")?;
+        info!("compliance.add({}, {})", sender, args.address);
+        self.storage
+            .add_to_compliance_list(sender, args.address)
+            .map(|_| compliance::AddReturns {})
+    }
+
+    fn remove(
+        &mut self,
+        sender: &Address,
+        args: compliance::RemoveArgs,
+    ) -> Result<compliance::RemoveReturns, ManyError> {
+        self.check_compliance_migration("compliance.remove")?;
+        info!("compliance.remove({}, {})", sender, args.address);
+        self.storage
+            .remove_from_compliance_list(sender, args.address)
+            .map(|_| compliance::RemoveReturns {})
+    }
+}
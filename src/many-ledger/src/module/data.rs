@@ -14,9 +14,10 @@ impl DataModuleBackend for LedgerModuleImpl {
         _: DataInfoArgs,
         context: Context,
     ) -> Result<DataInfoReturns, ManyError> {
+        let (_, supply, keys) = self.storage.total_supply_data()?;
         self.storage.prove_state(
             context,
-            vec![crate::storage::data::DATA_ATTRIBUTES_KEY.into()],
+            std::iter::once(crate::storage::data::DATA_ATTRIBUTES_KEY.into()).chain(keys),
         )?;
         Ok(DataInfoReturns {
             indices: self
@@ -24,6 +25,7 @@ impl DataModuleBackend for LedgerModuleImpl {
                 .data_attributes()?
                 .unwrap_or_default()
                 .into_keys()
+                .chain(supply.into_keys())
                 .collect(),
         })
     }
@@ -34,15 +36,20 @@ impl DataModuleBackend for LedgerModuleImpl {
         args: DataGetInfoArgs,
         context: Context,
     ) -> Result<DataGetInfoReturns, ManyError> {
+        let (supply, _, keys) = self.storage.total_supply_data()?;
         let filtered = self
             .storage
             .data_info()?
             .unwrap_or_default()
             .into_iter()
+            .chain(supply)
             .filter(|(k, _)| args.indices.0.contains(k))
             .collect();
         self.storage
-            .prove_state(context, vec![crate::storage::data::DATA_INFO_KEY.into()])
+            .prove_state(
+                context,
+                std::iter::once(crate::storage::data::DATA_INFO_KEY.into()).chain(keys),
+            )
             .map(|_| filtered)
     }
 
@@ -52,17 +59,19 @@ impl DataModuleBackend for LedgerModuleImpl {
         args: DataQueryArgs,
         context: Context,
     ) -> Result<DataQueryReturns, ManyError> {
+        let (_, supply, keys) = self.storage.total_supply_data()?;
         let filtered = self
             .storage
             .data_attributes()?
             .unwrap_or_default()
             .into_iter()
+            .chain(supply)
             .filter(|(k, _)| args.indices.0.contains(k))
             .collect();
         self.storage
             .prove_state(
                 context,
-                vec![crate::storage::data::DATA_ATTRIBUTES_KEY.into()],
+                std::iter::once(crate::storage::data::DATA_ATTRIBUTES_KEY.into()).chain(keys),
             )
             .map(|_| filtered)
     }
@@ -18,7 +18,6 @@ fn filter_account<'a>(
     account: Option<VecOrSingle<Address>>,
 ) -> Box<dyn Iterator<Item = EventLogResult> + 'a> {
     if let Some(account) = account {
-        let account: Vec<Address> = account.into();
         Box::new(it.filter(move |t| match t {
             // Propagate the errors.
             Err(_) => true,
@@ -34,7 +33,6 @@ fn filter_event_kind<'a>(
     event_kind: Option<VecOrSingle<events::EventKind>>,
 ) -> Box<dyn Iterator<Item = EventLogResult> + 'a> {
     if let Some(k) = event_kind {
-        let k: Vec<events::EventKind> = k.into();
         Box::new(it.filter(move |t| match t {
             Err(_) => true,
             Ok(t) => k.contains(&t.kind()),
@@ -108,10 +106,12 @@ impl events::EventsModuleBackend for LedgerModuleImpl {
     }
 
     fn list(&self, args: events::ListArgs) -> Result<events::ListReturns, ManyError> {
+        let id_range = args.id_range();
         let events::ListArgs {
             count,
             order,
             filter,
+            ..
         } = args;
         let filter = filter.unwrap_or_default();
 
@@ -121,10 +121,7 @@ impl events::EventsModuleBackend for LedgerModuleImpl {
 
         let storage = &self.storage;
         let nb_events = storage.nb_events()?;
-        let iter = storage.iter_events(
-            filter.id_range.unwrap_or_default(),
-            order.unwrap_or_default(),
-        );
+        let iter = storage.iter_events(id_range, order.unwrap_or_default());
 
         let iter = Box::new(iter.map(|item| {
             let (_k, v) = item.map_err(ManyError::unknown)?;
@@ -137,8 +134,6 @@ impl events::EventsModuleBackend for LedgerModuleImpl {
         let iter = filter_date(iter, filter.date_range.unwrap_or_default());
         let iter = filter_attribute_specific(iter, &filter.events_filter_attribute_specific);
 
-        let events: Vec<events::EventLog> = iter.take(count).collect::<Result<_, _>>()?;
-
-        Ok(events::ListReturns { nb_events, events })
+        events::ListReturns::page(nb_events, iter, count)
     }
 }
@@ -1,7 +1,10 @@
-use coset::CoseSign1;
+use coset::{CoseSign, CoseSign1};
 use many_error::ManyError;
+use many_identity::Address;
 use many_modules::{idstore, ManyModule, ManyModuleInfo};
 use many_protocol::{RequestMessage, ResponseMessage};
+use many_types::BlockTime;
+use std::collections::BTreeSet;
 use std::fmt::{Debug, Formatter};
 
 pub struct IdStoreWebAuthnModule<T: idstore::IdStoreModuleBackend> {
@@ -15,14 +18,8 @@ impl<T: idstore::IdStoreModuleBackend> Debug for IdStoreWebAuthnModule<T> {
     }
 }
 
-#[async_trait::async_trait]
-impl<T: idstore::IdStoreModuleBackend> ManyModule for IdStoreWebAuthnModule<T> {
-    fn info(&self) -> &ManyModuleInfo {
-        self.inner.info()
-    }
-
-    fn validate(&self, message: &RequestMessage, envelope: &CoseSign1) -> Result<(), ManyError> {
-        let result: Result<(), ManyError> = self.inner.validate(message, envelope);
+impl<T: idstore::IdStoreModuleBackend> IdStoreWebAuthnModule<T> {
+    fn allow_non_webauthn(&self, result: Result<(), ManyError>) -> Result<(), ManyError> {
         if let Err(e) = result {
             if e.code() == ManyError::non_webauthn_request_denied("").code() && !self.check_webauthn
             {
@@ -33,8 +30,42 @@ impl<T: idstore::IdStoreModuleBackend> ManyModule for IdStoreWebAuthnModule<T> {
         };
         Ok(())
     }
+}
+
+#[async_trait::async_trait]
+impl<T: idstore::IdStoreModuleBackend> ManyModule for IdStoreWebAuthnModule<T> {
+    fn info(&self) -> &ManyModuleInfo {
+        self.inner.info()
+    }
+
+    fn validate(&self, message: &RequestMessage, envelope: &CoseSign1) -> Result<(), ManyError> {
+        self.allow_non_webauthn(self.inner.validate(message, envelope))
+    }
+
+    fn validate_multi(
+        &self,
+        message: &RequestMessage,
+        envelope: &CoseSign,
+        signers: &BTreeSet<Address>,
+    ) -> Result<(), ManyError> {
+        self.allow_non_webauthn(self.inner.validate_multi(message, envelope, signers))
+    }
 
     async fn execute(&self, message: RequestMessage) -> Result<ResponseMessage, ManyError> {
-        self.inner.execute(message).await
+        self.execute_with_block_time(message, None).await
+    }
+
+    async fn execute_with_block_time(
+        &self,
+        message: RequestMessage,
+        block_time: Option<BlockTime>,
+    ) -> Result<ResponseMessage, ManyError> {
+        self.inner
+            .execute_with_block_time(message, block_time)
+            .await
+    }
+
+    fn heartbeat(&self) -> Result<(), ManyError> {
+        self.inner.heartbeat()
     }
 }
@@ -41,18 +41,45 @@ impl ledger::LedgerModuleBackend for LedgerModuleImpl {
     fn balance(
         &self,
         sender: &Address,
-        ledger::BalanceArgs { account, symbols }: ledger::BalanceArgs,
+        ledger::BalanceArgs {
+            account,
+            symbols,
+            height,
+        }: ledger::BalanceArgs,
         context: Context,
     ) -> Result<ledger::BalanceReturns, ManyError> {
         let identity = account.as_ref().unwrap_or(sender);
 
         let storage = &self.storage;
         let symbols = symbols.unwrap_or_default().0;
+        let symbol_set = BTreeSet::from_iter(symbols.clone().into_iter());
 
-        let (balances, keys) = storage
-            .get_multiple_balances(identity, &BTreeSet::from_iter(symbols.clone().into_iter()))?;
-        storage.prove_state(context, keys)?;
+        let balances = match height {
+            // Only the current state can be proven.
+            Some(height) => storage.get_multiple_balances_at(identity, &symbol_set, height)?,
+            None => {
+                let (balances, keys) = storage.get_multiple_balances(identity, &symbol_set)?;
+                storage.prove_state(context, keys)?;
+                balances
+            }
+        };
         info!("balance({}, {:?}): {:?}", identity, &symbols, &balances);
         Ok(ledger::BalanceReturns { balances })
     }
+
+    fn prove(
+        &self,
+        _sender: &Address,
+        ledger::ProveArgs { keys }: ledger::ProveArgs,
+        _context: Context,
+    ) -> Result<ledger::ProveReturns, ManyError> {
+        let storage = &self.storage;
+
+        let keys = BTreeSet::from_iter(keys.into_iter().map(Vec::from));
+        let proof = storage.prove(keys)?;
+        Ok(ledger::ProveReturns {
+            proof: proof.into(),
+            hash: storage.hash().into(),
+        })
+    }
 }
@@ -15,6 +15,7 @@ impl ledger::LedgerCommandsModuleBackend for LedgerModuleImpl {
             amount,
             symbol,
             memo,
+            reference,
         } = args;
 
         let from = from.as_ref().unwrap_or(sender);
@@ -40,7 +41,7 @@ impl ledger::LedgerCommandsModuleBackend for LedgerModuleImpl {
         }
 
         self.storage
-            .send(from, &to, &symbol, amount, memo)
+            .send(from, &to, &symbol, amount, memo, reference)
             .map(|_| EmptyReturn)
     }
 }
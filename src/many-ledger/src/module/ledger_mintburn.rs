@@ -5,7 +5,6 @@ use crate::module::LedgerModuleImpl;
 use crate::storage::ledger_tokens::verify_tokens_sender;
 use many_error::ManyError;
 use many_identity::Address;
-use many_modules::events::EventInfo;
 use many_modules::ledger;
 use many_modules::ledger::{TokenBurnArgs, TokenBurnReturns, TokenMintArgs, TokenMintReturns};
 use many_types::ledger::Symbol;
@@ -51,15 +50,8 @@ impl ledger::LedgerMintBurnModuleBackend for LedgerModuleImpl {
         check_symbol_exists(&symbol, self.storage.get_symbols()?)?;
 
         // Mint into storage
-        let _ = self.storage.mint_token(symbol, &distribution)?;
-
-        // Log event
         self.storage
-            .log_event(EventInfo::TokenMint {
-                symbol,
-                distribution,
-                memo,
-            })
+            .mint_token(symbol, &distribution, memo)
             .map(|_| TokenMintReturns {})
     }
 
@@ -91,15 +83,8 @@ impl ledger::LedgerMintBurnModuleBackend for LedgerModuleImpl {
         }
 
         // Burn from storage
-        let _ = self.storage.burn_token(symbol, &distribution)?;
-
-        // Log event
         self.storage
-            .log_event(EventInfo::TokenBurn {
-                symbol,
-                distribution: distribution.clone(),
-                memo,
-            })
+            .burn_token(symbol, &distribution, memo)
             .map(|_| TokenBurnReturns { distribution })
     }
 }
@@ -1,6 +1,8 @@
 use crate::error;
 use crate::migration::disable_token_create::DISABLE_TOKEN_CREATE_MIGRATION;
+use crate::migration::supply_history::SUPPLY_HISTORY_MIGRATION;
 use crate::migration::token_create::TOKEN_CREATE_MIGRATION;
+use crate::migration::token_history::TOKEN_HISTORY_MIGRATION;
 use crate::migration::tokens::TOKEN_MIGRATION;
 use crate::module::LedgerModuleImpl;
 use crate::storage::account::verify_acl;
@@ -11,10 +13,13 @@ use many_modules::account::features::TryCreateFeature;
 use many_modules::account::Role;
 use many_modules::ledger::{
     LedgerTokensModuleBackend, TokenAddExtendedInfoArgs, TokenAddExtendedInfoReturns,
-    TokenCreateArgs, TokenCreateReturns, TokenInfoArgs, TokenInfoReturns,
-    TokenRemoveExtendedInfoArgs, TokenRemoveExtendedInfoReturns, TokenUpdateArgs,
-    TokenUpdateReturns,
+    TokenCreateArgs, TokenCreateReturns, TokenHistoryArgs, TokenHistoryReturns, TokenInfoArgs,
+    TokenInfoReturns, TokenListArgs, TokenListReturns, TokenRemoveExtendedInfoArgs,
+    TokenRemoveExtendedInfoReturns, TokenSupplyHistoryArgs, TokenSupplyHistoryReturns,
+    TokenUpdateArgs, TokenUpdateReturns,
 };
+use many_protocol::context::Context;
+use many_types::ledger::TokenAmount;
 use many_types::Either;
 
 fn check_ticker_length(ticker: &String) -> Result<(), ManyError> {
@@ -24,6 +29,16 @@ fn check_ticker_length(ticker: &String) -> Result<(), ManyError> {
     Ok(())
 }
 
+fn check_decimals(decimals: u64) -> Result<(), ManyError> {
+    if decimals > TokenAmount::MAXIMUM_DECIMALS {
+        return Err(error::too_many_decimals(
+            decimals,
+            TokenAmount::MAXIMUM_DECIMALS,
+        ));
+    }
+    Ok(())
+}
+
 impl LedgerTokensModuleBackend for LedgerModuleImpl {
     fn create(
         &mut self,
@@ -66,6 +81,7 @@ impl LedgerTokensModuleBackend for LedgerModuleImpl {
 
         let ticker = &args.summary.ticker;
         check_ticker_length(ticker)?;
+        check_decimals(args.summary.decimals)?;
 
         if self
             .storage
@@ -96,6 +112,59 @@ impl LedgerTokensModuleBackend for LedgerModuleImpl {
         self.storage.info_token(args)
     }
 
+    fn list(
+        &self,
+        _sender: &Address,
+        args: TokenListArgs,
+        context: Context,
+    ) -> Result<TokenListReturns, ManyError> {
+        if !self.storage.migrations().is_active(&TOKEN_MIGRATION) {
+            return Err(ManyError::invalid_method_name("tokens.list"));
+        }
+
+        let (result, keys) = self.storage.list_tokens(args)?;
+        self.storage.prove_state(context, keys)?;
+        Ok(result)
+    }
+
+    fn history(
+        &self,
+        _sender: &Address,
+        args: TokenHistoryArgs,
+        context: Context,
+    ) -> Result<TokenHistoryReturns, ManyError> {
+        if !self
+            .storage
+            .migrations()
+            .is_active(&TOKEN_HISTORY_MIGRATION)
+        {
+            return Err(ManyError::invalid_method_name("tokens.history"));
+        }
+
+        let (result, keys) = self.storage.history_token(args)?;
+        self.storage.prove_state(context, keys)?;
+        Ok(result)
+    }
+
+    fn supply_history(
+        &self,
+        _sender: &Address,
+        args: TokenSupplyHistoryArgs,
+        context: Context,
+    ) -> Result<TokenSupplyHistoryReturns, ManyError> {
+        if !self
+            .storage
+            .migrations()
+            .is_active(&SUPPLY_HISTORY_MIGRATION)
+        {
+            return Err(ManyError::invalid_method_name("tokens.supplyHistory"));
+        }
+
+        let (result, keys) = self.storage.supply_history(args)?;
+        self.storage.prove_state(context, keys)?;
+        Ok(result)
+    }
+
     fn update(
         &mut self,
         sender: &Address,
@@ -135,6 +204,9 @@ impl LedgerTokensModuleBackend for LedgerModuleImpl {
         if let Some(ticker) = &args.ticker {
             check_ticker_length(ticker)?;
         }
+        if let Some(decimals) = args.decimals {
+            check_decimals(decimals)?;
+        }
 
         let (result, _) = self.storage.update_token(sender, args)?;
         Ok(result)
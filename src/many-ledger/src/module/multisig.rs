@@ -1,10 +1,13 @@
+use crate::migration::cosign::COSIGN_MIGRATION;
 use crate::module::LedgerModuleImpl;
 use many_error::ManyError;
 use many_identity::Address;
+use many_modules::account::features::cosign::{self, CosignedTransaction};
 use many_modules::account::features::multisig;
 use many_modules::EmptyReturn;
 use many_protocol::ResponseMessage;
 use minicbor::bytes::ByteVec;
+use std::collections::BTreeSet;
 
 impl multisig::AccountMultisigModuleBackend for LedgerModuleImpl {
     fn multisig_submit_transaction(
@@ -75,3 +78,29 @@ impl multisig::AccountMultisigModuleBackend for LedgerModuleImpl {
             .map(|_| EmptyReturn)
     }
 }
+
+impl cosign::AccountCosignModuleBackend for LedgerModuleImpl {
+    fn cosign_execute(
+        &mut self,
+        sender: &Address,
+        args: cosign::ExecuteArgs,
+    ) -> Result<ResponseMessage, ManyError> {
+        if !self.storage.migrations().is_active(&COSIGN_MIGRATION) {
+            return Err(ManyError::invalid_method_name("account.cosignExecute"));
+        }
+
+        let signers = args
+            .signatures
+            .iter()
+            .map(|signature| {
+                CosignedTransaction::verify(
+                    self.cosign_verifier.as_ref(),
+                    signature,
+                    &args.transaction,
+                )
+            })
+            .collect::<Result<BTreeSet<_>, _>>()?;
+        self.storage
+            .execute_cosigned_multisig(sender, &args.transaction, signers)
+    }
+}
@@ -0,0 +1,70 @@
+use crate::migration::names::NAMES_MIGRATION;
+use crate::module::LedgerModuleImpl;
+use many_error::ManyError;
+use many_identity::Address;
+use many_modules::names;
+use tracing::info;
+
+impl LedgerModuleImpl {
+    fn check_names_migration(&self, method: &str) -> Result<(), ManyError> {
+        if self.storage.migrations().is_active(&NAMES_MIGRATION) {
+            Ok(())
+        } else {
+            Err(ManyError::invalid_method_name(method))
+        }
+    }
+}
+
+impl names::NamesModuleBackend for LedgerModuleImpl {
+    fn info(
+        &self,
+        _sender: &Address,
+        _args: names::InfoArgs,
+    ) -> Result<names::InfoReturns, ManyError> {
+        self.check_names_migration("names.info")?;
+        let config = self.storage.get_names_config()?;
+        Ok(names::InfoReturns {
+            fee: config.fee,
+            duration: config.duration_in_secs,
+        })
+    }
+
+    fn resolve(
+        &self,
+        _sender: &Address,
+        names::ResolveArgs { name }: names::ResolveArgs,
+    ) -> Result<names::ResolveReturns, ManyError> {
+        self.check_names_migration("names.resolve")?;
+        let record = self
+            .storage
+            .get_name(&name)?
+            .ok_or_else(|| names::name_not_found(name))?;
+        Ok(names::ResolveReturns {
+            owner: record.owner,
+            expiration: record.expiration,
+        })
+    }
+
+    fn register(
+        &mut self,
+        sender: &Address,
+        names::RegisterArgs { name }: names::RegisterArgs,
+    ) -> Result<names::RegisterReturns, ManyError> {
+        self.check_names_migration("names.register")?;
+        info!("register({}, {})", sender, name);
+        let expiration = self.storage.register_name(sender, name)?;
+        Ok(names::RegisterReturns { expiration })
+    }
+
+    fn transfer(
+        &mut self,
+        sender: &Address,
+        names::TransferArgs { name, new_owner }: names::TransferArgs,
+    ) -> Result<names::TransferReturns, ManyError> {
+        self.check_names_migration("names.transfer")?;
+        info!("transfer({}, {} => {})", name, sender, new_owner);
+        self.storage
+            .transfer_name(sender, name, new_owner)
+            .map(|_| names::TransferReturns {})
+    }
+}
@@ -0,0 +1,28 @@
+use crate::module::LedgerModuleImpl;
+use many_error::ManyError;
+use many_identity::Address;
+use many_modules::random;
+use tracing::info;
+
+impl random::RandomModuleBackend for LedgerModuleImpl {
+    fn get(
+        &self,
+        _sender: &Address,
+        random::GetArgs { domain }: random::GetArgs,
+    ) -> Result<random::GetReturns, ManyError> {
+        let storage = &self.storage;
+
+        // The ledger has no validator contributions, the seed only depends
+        // on the committed state.
+        let height = storage.get_height()?;
+        let hash = storage.hash();
+        let seed = random::seed::<&[u8]>(height, &hash, &[], domain.as_deref().map(Vec::as_slice));
+
+        info!("random.get(): height={} seed={}", height, hex::encode(seed));
+        Ok(random::GetReturns {
+            height,
+            hash: hash.into(),
+            seed: seed.to_vec().into(),
+        })
+    }
+}
@@ -0,0 +1,38 @@
+use crate::migration::relay::RELAY_MIGRATION;
+use crate::module::LedgerModuleImpl;
+use many_error::ManyError;
+use many_identity::Address;
+use many_modules::relay;
+use many_protocol::RequestMessage;
+use tracing::info;
+
+impl LedgerModuleImpl {
+    fn check_relay_migration(&self, method: &str) -> Result<(), ManyError> {
+        if self.storage.migrations().is_active(&RELAY_MIGRATION) {
+            Ok(())
+        } else {
+            Err(ManyError::invalid_method_name(method))
+        }
+    }
+}
+
+impl relay::RelayModuleBackend for LedgerModuleImpl {
+    fn info(
+        &self,
+        _sender: &Address,
+        _args: relay::InfoArgs,
+    ) -> Result<relay::InfoReturns, ManyError> {
+        self.check_relay_migration("relay.info")?;
+        let config = self.storage.get_relay_config()?;
+        Ok(relay::InfoReturns {
+            fee: config.fee,
+            timeout_in_secs: config.timeout_in_secs,
+        })
+    }
+
+    fn relay(&mut self, relayer: &Address, request: &RequestMessage) -> Result<(), ManyError> {
+        self.check_relay_migration("relay.execute")?;
+        info!("relay({}, {}, {})", relayer, request.from(), request.method);
+        self.storage.relay_request(relayer, request)
+    }
+}
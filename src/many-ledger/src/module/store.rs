@@ -0,0 +1,77 @@
+use crate::migration::store::STORE_MIGRATION;
+use crate::module::LedgerModuleImpl;
+use many_error::ManyError;
+use many_identity::Address;
+use many_modules::store;
+use many_protocol::context::Context;
+use tracing::info;
+
+impl LedgerModuleImpl {
+    fn check_store_migration(&self, method: &str) -> Result<(), ManyError> {
+        if self.storage.migrations().is_active(&STORE_MIGRATION) {
+            Ok(())
+        } else {
+            Err(ManyError::invalid_method_name(method))
+        }
+    }
+}
+
+impl store::StoreModuleBackend for LedgerModuleImpl {
+    fn info(
+        &self,
+        _sender: &Address,
+        _args: store::InfoArgs,
+    ) -> Result<store::InfoReturns, ManyError> {
+        self.check_store_migration("store.info")?;
+        Ok(store::InfoReturns {
+            max_size: self.storage.get_store_config()?.max_size,
+        })
+    }
+
+    fn get(
+        &self,
+        _sender: &Address,
+        args: store::GetArgs,
+        context: Context,
+    ) -> Result<store::GetReturns, ManyError> {
+        self.check_store_migration("store.get")?;
+        let (data, info, keys) = self.storage.get_object(&args.hash)?;
+        self.storage.prove_state(context, keys)?;
+        Ok(store::GetReturns { data, info })
+    }
+
+    fn put(
+        &mut self,
+        sender: &Address,
+        args: store::PutArgs,
+    ) -> Result<store::PutReturns, ManyError> {
+        self.check_store_migration("store.put")?;
+        info!("put({}, {} bytes)", sender, args.data.len());
+        let hash = self.storage.put_object(sender, args.data)?;
+        Ok(store::PutReturns { hash })
+    }
+
+    fn pin(
+        &mut self,
+        sender: &Address,
+        args: store::PinArgs,
+    ) -> Result<store::PinReturns, ManyError> {
+        self.check_store_migration("store.pin")?;
+        info!("pin({}, {})", sender, hex::encode(&args.hash));
+        self.storage
+            .pin_object(sender, args.hash)
+            .map(|_| store::PinReturns {})
+    }
+
+    fn unpin(
+        &mut self,
+        sender: &Address,
+        args: store::UnpinArgs,
+    ) -> Result<store::UnpinReturns, ManyError> {
+        self.check_store_migration("store.unpin")?;
+        info!("unpin({}, {})", sender, hex::encode(&args.hash));
+        self.storage
+            .unpin_object(sender, args.hash)
+            .map(|_| store::UnpinReturns {})
+    }
+}
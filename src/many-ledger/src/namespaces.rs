@@ -0,0 +1,106 @@
+use crate::storage::namespace::Namespace;
+use crate::storage::LedgerStorage;
+use many_error::ManyError;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+#[derive(clap::Args, Debug)]
+pub struct NamespacesOpts {
+    /// Path to the persistent store database (rocksdb) to inspect.
+    /// The store cannot be used by a running server at the same time.
+    #[clap(long)]
+    pub persistent: PathBuf,
+
+    /// Also print every key of the store, with its namespace.
+    #[clap(long)]
+    pub keys: bool,
+
+    /// Only print the keys of this namespace, or `none` for the keys outside
+    /// of any namespace. Implies `--keys`.
+    #[clap(long)]
+    pub namespace: Option<String>,
+}
+
+/// The number of keys of a namespace, and the size of its keys and values.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Usage {
+    pub keys: u64,
+    pub bytes: u64,
+}
+
+fn name_of(key: &[u8]) -> &'static str {
+    Namespace::of(key).map_or("none", |namespace| namespace.name)
+}
+
+/// Sum the usage of each namespace, by name. The keys outside of any
+/// namespace are counted as `none`.
+pub fn tally<K: AsRef<[u8]>, E>(
+    entries: impl Iterator<Item = Result<(K, Vec<u8>), E>>,
+) -> Result<BTreeMap<&'static str, Usage>, E> {
+    let mut usage = BTreeMap::<_, Usage>::new();
+    for entry in entries {
+        let (key, value) = entry?;
+        let u = usage.entry(name_of(key.as_ref())).or_default();
+        u.keys += 1;
+        u.bytes += (key.as_ref().len() + value.len()) as u64;
+    }
+    Ok(usage)
+}
+
+/// Print the usage of every namespace of the store, and its keys if asked.
+pub fn run(opts: NamespacesOpts) -> Result<(), ManyError> {
+    let NamespacesOpts {
+        persistent,
+        keys,
+        namespace,
+    } = opts;
+    if !persistent.exists() {
+        return Err(ManyError::unknown(format!(
+            "Store {} does not exist.",
+            persistent.display()
+        )));
+    }
+
+    let storage = LedgerStorage::load(persistent, false, None)?;
+
+    if keys || namespace.is_some() {
+        for entry in storage.iter_all() {
+            let (key, _) = entry.map_err(ManyError::unknown)?;
+            let name = name_of(&key);
+            if namespace.as_ref().map_or(true, |n| n == name) {
+                println!("{name} {}", key.escape_ascii());
+            }
+        }
+    }
+
+    for (name, usage) in tally(storage.iter_all()).map_err(ManyError::unknown)? {
+        println!("{name}: {} keys, {} bytes", usage.keys, usage.bytes);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+
+    #[test]
+    fn tally_by_namespace() {
+        let entries = [
+            ("/balances/a", "1"),
+            ("/balances/b", "22"),
+            ("/config/symbols", "3"),
+            ("/height", "4"),
+        ]
+        .map(|(k, v)| Ok::<_, Infallible>((k.as_bytes().to_vec(), v.as_bytes().to_vec())));
+
+        assert_eq!(
+            tally(entries.into_iter()).unwrap(),
+            BTreeMap::from([
+                ("balances", Usage { keys: 2, bytes: 25 }),
+                ("config", Usage { keys: 1, bytes: 16 }),
+                ("none", Usage { keys: 1, bytes: 8 }),
+            ])
+        );
+    }
+}
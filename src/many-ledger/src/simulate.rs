@@ -0,0 +1,216 @@
+use crate::diff::KeyDiff;
+use crate::migration::{LedgerMigrations, MIGRATIONS};
+use crate::storage::LedgerStorage;
+use many_error::ManyError;
+use many_migration::{MigrationConfig, MigrationReport};
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+#[derive(clap::Args, Debug)]
+pub struct SimulateMigrationOpts {
+    /// Path to the persistent store database (rocksdb) to simulate the
+    /// migrations on. It is copied and left unchanged.
+    #[clap(long)]
+    pub snapshot: PathBuf,
+
+    /// Run the migrations of all blocks after the height of the snapshot, up
+    /// to and including this height.
+    #[clap(long)]
+    pub height: u64,
+
+    /// Path to a JSON file containing the configurations for the migrations.
+    #[clap(long, short)]
+    pub migrations_config: PathBuf,
+
+    /// Path where to keep the migrated copy of the snapshot. By default, the
+    /// copy is made in a temporary directory and removed afterwards.
+    #[clap(long)]
+    pub output: Option<PathBuf>,
+}
+
+/// A key of the store that was changed by the migrations.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Change {
+    Added(Vec<u8>),
+    Removed(Vec<u8>),
+    Changed(Vec<u8>),
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Change::Added(k) => write!(f, "+ {}", k.escape_ascii()),
+            Change::Removed(k) => write!(f, "- {}", k.escape_ascii()),
+            Change::Changed(k) => write!(f, "~ {}", k.escape_ascii()),
+        }
+    }
+}
+
+impl From<KeyDiff> for Change {
+    fn from(d: KeyDiff) -> Self {
+        match (d.before, d.after) {
+            (None, _) => Change::Added(d.key),
+            (_, None) => Change::Removed(d.key),
+            _ => Change::Changed(d.key),
+        }
+    }
+}
+
+/// Compare two lists of key/value pairs sorted by key.
+pub fn diff<K: AsRef<[u8]>, E>(
+    before: impl Iterator<Item = Result<(K, Vec<u8>), E>>,
+    after: impl Iterator<Item = Result<(K, Vec<u8>), E>>,
+) -> Result<Vec<Change>, E> {
+    Ok(crate::diff::diff(before, after)?
+        .into_iter()
+        .map(Change::from)
+        .collect())
+}
+
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let path = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &path)?;
+        } else {
+            std::fs::copy(entry.path(), path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Run the migrations on a copy of the snapshot, and print the root hashes
+/// before and after, and the keys that changed.
+pub fn run(opts: SimulateMigrationOpts) -> Result<(), ManyError> {
+    let SimulateMigrationOpts {
+        snapshot,
+        height,
+        migrations_config,
+        output,
+    } = opts;
+
+    let content = std::fs::read_to_string(migrations_config).map_err(ManyError::unknown)?;
+    let migrations = serde_json::from_str::<MigrationConfig>(&content)
+        .map(MigrationConfig::strict)
+        .map_err(ManyError::deserialization_error)?;
+
+    let (copy, temporary) = match output {
+        Some(path) => (path, false),
+        None => (
+            std::env::temp_dir().join(format!("many-ledger-simulation-{}", std::process::id())),
+            true,
+        ),
+    };
+    if copy.exists() {
+        return Err(ManyError::unknown(format!(
+            "Output {} already exists.",
+            copy.display()
+        )));
+    }
+    copy_dir(&snapshot, &copy).map_err(ManyError::unknown)?;
+
+    let result = simulate(&snapshot, &copy, height, migrations);
+    if temporary {
+        let _ = std::fs::remove_dir_all(&copy);
+    }
+    result
+}
+
+/// Validate a migrations configuration at the height of a persistent store,
+/// or at height 0 if the store does not exist.
+pub fn validate(
+    migrations_config: PathBuf,
+    persistent: Option<PathBuf>,
+) -> Result<MigrationReport, ManyError> {
+    let content = std::fs::read_to_string(migrations_config).map_err(ManyError::unknown)?;
+    let migrations = serde_json::from_str::<MigrationConfig>(&content)
+        .map(MigrationConfig::strict)
+        .map_err(ManyError::deserialization_error)?;
+
+    let height = match persistent {
+        Some(path) if path.exists() => LedgerStorage::load(path, false, None)?.get_height()?,
+        _ => 0,
+    };
+    Ok(LedgerMigrations::validate(&MIGRATIONS, &migrations, height))
+}
+
+fn simulate(
+    snapshot: &Path,
+    copy: &Path,
+    height: u64,
+    migrations: MigrationConfig,
+) -> Result<(), ManyError> {
+    let before = LedgerStorage::load(snapshot, false, None)?;
+    let mut after = LedgerStorage::load(copy, false, Some(migrations))?;
+
+    let current = after.get_height()?;
+    if height <= current {
+        return Err(ManyError::unknown(format!(
+            "Height {height} is not after the snapshot height {current}."
+        )));
+    }
+    after.simulate_migrations(height)?;
+
+    let changes = diff(before.iter_all(), after.iter_all()).map_err(ManyError::unknown)?;
+    for change in &changes {
+        println!("{change}");
+    }
+    println!("Height: {current} -> {height}");
+    println!("Hash before: {}", hex::encode(before.hash()));
+    println!("Hash after:  {}", hex::encode(after.hash()));
+    println!("{} keys changed.", changes.len());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+
+    fn pairs(p: &[(&str, &str)]) -> impl Iterator<Item = Result<(Vec<u8>, Vec<u8>), Infallible>> {
+        p.iter()
+            .map(|(k, v)| Ok((k.as_bytes().to_vec(), v.as_bytes().to_vec())))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn changes() {
+        let before = pairs(&[("/a", "1"), ("/b", "2"), ("/c", "3"), ("/e", "5")]);
+        let after = pairs(&[
+            ("/b", "2"),
+            ("/c", "4"),
+            ("/d", "4"),
+            ("/e", "5"),
+            ("/f", ""),
+        ]);
+
+        assert_eq!(
+            diff(before, after).unwrap(),
+            vec![
+                Change::Removed(b"/a".to_vec()),
+                Change::Changed(b"/c".to_vec()),
+                Change::Added(b"/d".to_vec()),
+                Change::Added(b"/f".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn same() {
+        let p = [("/a", "1"), ("/b", "2")];
+        assert!(diff(pairs(&p), pairs(&p)).unwrap().is_empty());
+        assert!(diff(pairs(&[]), pairs(&[])).unwrap().is_empty());
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            Change::Added(b"/balances/\x01".to_vec()).to_string(),
+            "+ /balances/\\x01"
+        );
+    }
+}
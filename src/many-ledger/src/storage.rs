@@ -2,36 +2,53 @@ use crate::error;
 use crate::migration::tokens::TOKEN_MIGRATION;
 use crate::migration::{LedgerMigrations, MIGRATIONS};
 use crate::storage::account::ACCOUNT_SUBRESOURCE_ID_ROOT;
-use crate::storage::event::HEIGHT_EVENTID_SHIFT;
+use crate::storage::iterator::LedgerIterator;
+use crate::storage::transaction::{JournaledStore, TransactionState};
 use many_error::ManyError;
 use many_identity::{Address, MAX_SUBRESOURCE_ID};
 use many_migration::{MigrationConfig, MigrationSet};
 use many_modules::events::EventId;
 use many_types::ledger::Symbol;
-use many_types::Timestamp;
+use many_types::{BlockTime, Timestamp};
 use merk::Op;
 use std::collections::{BTreeMap, BTreeSet};
 use std::path::Path;
 
 mod abci;
 pub mod account;
+pub mod account_gc;
+pub mod attest;
+pub mod compliance;
 pub mod data;
 pub mod event;
+pub(crate) mod expiry;
+#[cfg(feature = "fault_testing")]
+pub mod fault;
+pub mod hooks;
 pub(crate) mod idstore;
 pub mod iterator;
 mod ledger;
 mod ledger_commands;
 pub mod ledger_mintburn;
 pub mod ledger_tokens;
+mod maintenance;
 mod migrations;
 pub mod multisig;
+pub mod names;
+pub mod namespace;
+pub mod relay;
+pub mod store;
+pub mod subresource;
+pub mod supply_history;
+pub mod transaction;
 
 pub const SYMBOLS_ROOT: &str = "/config/symbols";
 pub const IDENTITY_ROOT: &str = "/config/identity";
 pub const HEIGHT_ROOT: &str = "/height";
+pub const BALANCES_ROOT: &str = "/balances/";
 
 pub(super) fn key_for_account_balance(id: &Address, symbol: &Symbol) -> Vec<u8> {
-    format!("/balances/{id}/{symbol}").into_bytes()
+    format!("{BALANCES_ROOT}{id}/{symbol}").into_bytes()
 }
 
 pub(super) fn key_for_subresource_counter(id: &Address, token_migration_active: bool) -> Vec<u8> {
@@ -46,7 +63,7 @@ pub(super) fn key_for_subresource_counter(id: &Address, token_migration_active:
 pub type InnerStorage = merk::Merk;
 
 pub struct LedgerStorage {
-    persistent_store: InnerStorage,
+    persistent_store: JournaledStore,
 
     /// When this is true, we do not commit every transactions as they come,
     /// but wait for a `commit` call before committing the batch to the
@@ -55,10 +72,18 @@ pub struct LedgerStorage {
 
     latest_tid: EventId,
 
-    current_time: Option<Timestamp>,
+    current_time: Option<BlockTime>,
     current_hash: Option<Vec<u8>>,
 
     migrations: LedgerMigrations,
+
+    /// The number of multisig transactions currently executing, as executing
+    /// a transaction can execute another one.
+    multisig_depth: usize,
+
+    transfer_hooks: hooks::TransferHooks,
+
+    transaction: Option<TransactionState>,
 }
 
 impl LedgerStorage {
@@ -98,21 +123,28 @@ impl std::fmt::Debug for LedgerStorage {
 
 impl LedgerStorage {
     #[inline]
-    pub fn set_time(&mut self, time: Timestamp) {
+    pub fn set_time(&mut self, time: BlockTime) {
         self.current_time = Some(time);
     }
     #[inline]
+    pub fn block_time(&self) -> Option<BlockTime> {
+        self.current_time
+    }
+    #[inline]
     pub fn now(&self) -> Timestamp {
-        self.current_time.unwrap_or_else(Timestamp::now)
+        self.current_time.map_or_else(Timestamp::now, Into::into)
     }
 
     pub fn migrations(&self) -> &LedgerMigrations {
         &self.migrations
     }
 
+    /// Commit the writes to disk outside of blockchain mode. The writes of a
+    /// transaction are committed when it ends, so the writes it rolls back
+    /// never reach the disk.
     #[inline]
     fn maybe_commit(&mut self) -> Result<(), ManyError> {
-        if !self.blockchain {
+        if !self.blockchain && self.transaction.is_none() {
             self.commit_storage()
         } else {
             Ok(())
@@ -143,7 +175,7 @@ impl LedgerStorage {
                 u64::from_be_bytes(bytes)
             });
 
-        // The call to `saturating_sub()` is required to fix
+        // Starting from the IDs of the current height, not the next one, is required to fix
         // https://github.com/liftedinit/many-framework/issues/289
         //
         // The `commit()` function computes the `latest_tid` using the previous height while
@@ -151,7 +183,7 @@ impl LedgerStorage {
         //
         // The discrepancy will lead to an application hash mismatch if the block following the `load()` contains
         // a transaction.
-        let latest_tid = EventId::from(height.saturating_sub(1) << HEIGHT_EVENTID_SHIFT);
+        let latest_tid = EventId::first_of_height(height);
         let migrations = migration_config
             .map_or_else(MigrationSet::empty, |config| {
                 LedgerMigrations::load(&MIGRATIONS, config, height)
@@ -159,25 +191,32 @@ impl LedgerStorage {
             .map_err(error::unable_to_load_migrations)?;
 
         Ok(Self {
-            persistent_store,
+            persistent_store: JournaledStore::new(persistent_store),
             blockchain,
             latest_tid,
             current_time: None,
             current_hash: None,
             migrations,
+            multisig_depth: 0,
+            transfer_hooks: hooks::TransferHooks::builtin(),
+            transaction: None,
         })
     }
 
     pub fn new<P: AsRef<Path>>(persistent_path: P, blockchain: bool) -> Result<Self, ManyError> {
-        let persistent_store = InnerStorage::open(persistent_path).map_err(ManyError::unknown)?; // TODO: Custom error
+        let persistent_store =
+            InnerStorage::open(persistent_path).map_err(error::storage_open_failed)?;
 
         Ok(Self {
-            persistent_store,
+            persistent_store: JournaledStore::new(persistent_store),
             blockchain,
             latest_tid: EventId::from(vec![0]),
             current_time: None,
             current_hash: None,
-            migrations: MigrationSet::empty().map_err(ManyError::unknown)?, // TODO: Custom error
+            migrations: MigrationSet::empty().map_err(error::unable_to_load_migrations)?,
+            multisig_depth: 0,
+            transfer_hooks: hooks::TransferHooks::builtin(),
+            transaction: None,
         })
     }
 
@@ -242,6 +281,11 @@ impl LedgerStorage {
             .map_or_else(|| self.persistent_store.root_hash().to_vec(), |x| x.clone())
     }
 
+    /// Iterate over all the key/value pairs of the store, in key order.
+    pub fn iter_all(&self) -> LedgerIterator {
+        LedgerIterator::all_with_prefix(&self.persistent_store, &[])
+    }
+
     /// Get the identity stored at a given DB key
     pub fn get_identity(&self, identity_root: &str) -> Result<Address, ManyError> {
         Address::from_bytes(
@@ -297,7 +341,8 @@ impl LedgerStorage {
             .map_err(error::storage_apply_failed)?;
         let mut keys = vec![key_for_subresource];
 
-        self.persistent_store
+        let address = self
+            .persistent_store
             .get((*identity_root).as_bytes())
             .map_err(error::storage_get_failed)?
             .map_or(
@@ -310,8 +355,12 @@ impl LedgerStorage {
                     Address::from_bytes(&bytes)
                 },
             )?
-            .with_subresource_id(current_id)
-            .map(|address| (address, keys))
+            .with_subresource_id(current_id)?;
+
+        if let Some(key) = self.record_subresource_height(&address)? {
+            keys.push(key);
+        }
+        Ok((address, keys))
     }
 
     /// Get the subresource counter from the given DB key.
@@ -1,37 +1,68 @@
-use crate::storage::event::HEIGHT_EVENTID_SHIFT;
 use crate::storage::LedgerStorage;
+use many_error::ManyError;
 use many_modules::abci_backend::AbciCommitInfo;
 use many_modules::events::EventId;
 
 impl LedgerStorage {
-    pub fn commit(&mut self) -> AbciCommitInfo {
-        // First check if there's any need to clean up multisig transactions. Ignore
-        // errors.
-        let _ = self.check_timed_out_multisig_transactions();
+    /// Write the block to the persistent store. On error, the changes made
+    /// here are undone so the block can be committed again.
+    fn write_block(&mut self) -> Result<u64, ManyError> {
+        self.begin_transaction()?;
+        let result = (|| {
+            // First check if there's any need to clean up multisig transactions,
+            // disabled accounts or expired keys. Ignore errors.
+            let _ = self.check_timed_out_multisig_transactions();
+            let _ = self.prune_disabled_accounts();
+            let _ = self.prune_expired_keys();
 
-        let height = self.inc_height().expect("Unable to increment height.");
+            let height = self.inc_height()?;
+
+            // Committing before the migration so that the migration has
+            // the actual state of the database when setting its
+            // attributes.
+            self.commit_storage()?;
+            Ok(height)
+        })();
+
+        match result {
+            Ok(height) => self.commit_transaction().map(|_| height),
+            Err(e) => {
+                self.rollback_transaction()?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Commit the current block. An error writing the block leaves the state
+    /// of the block as it was. Once it is written, the migrations of the next
+    /// height must run, and failing to do so is fatal.
+    pub fn commit(&mut self) -> Result<AbciCommitInfo, ManyError> {
+        let height = self.write_block()?;
         let retain_height = 0;
 
-        // Committing before the migration so that the migration has
-        // the actual state of the database when setting its
-        // attributes.
-        self.commit_storage().expect("Unable to commit to storage.");
+        // Events are only visible to iterators once committed.
+        let events_digest = self
+            .block_events_digest(height)
+            .expect("Unable to compute the events digest.");
 
         // Initialize/update migrations at current height, if any
         self.migrations
             .update_at_height(&mut self.persistent_store, height + 1)
             .expect("Unable to run migrations");
+        self.apply_key_value_hotfixes(height + 1)
+            .expect("Unable to run hotfixes");
 
         self.commit_storage().expect("Unable to commit to storage.");
 
         let hash = self.persistent_store.root_hash().to_vec();
         self.current_hash = Some(hash.clone());
 
-        self.latest_tid = EventId::from(height << HEIGHT_EVENTID_SHIFT);
+        self.latest_tid = EventId::first_of_height(height + 1);
 
-        AbciCommitInfo {
+        Ok(AbciCommitInfo {
             retain_height,
             hash: hash.into(),
-        }
+            events_digest: Some(events_digest.into()),
+        })
     }
 }
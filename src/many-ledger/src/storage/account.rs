@@ -1,7 +1,10 @@
 use crate::error;
+use crate::migration::account_index::ACCOUNT_INDEX_MIGRATION;
 use crate::migration::legacy_remove_roles::LEGACY_REMOVE_ROLES_TRIGGER;
 use crate::migration::tokens::TOKEN_MIGRATION;
 use crate::module::account::{validate_account, verify_account_role};
+use crate::storage::account_gc::is_disabled;
+use crate::storage::iterator::LedgerIterator;
 use crate::storage::multisig::{
     MULTISIG_DEFAULT_EXECUTE_AUTOMATICALLY, MULTISIG_DEFAULT_TIMEOUT_IN_SECS,
     MULTISIG_MAXIMUM_TIMEOUT_IN_SECS,
@@ -15,9 +18,15 @@ use many_modules::{account, events};
 use many_types::Either;
 use merk::Op;
 use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
 
 pub const ACCOUNT_IDENTITY_ROOT: &str = "/config/account_identity";
 pub const ACCOUNT_SUBRESOURCE_ID_ROOT: &str = "/config/account_id";
+pub const ACCOUNTS_ROOT: &str = "/accounts/";
+
+/// The reverse index of the accounts, by the identities holding roles in
+/// them. Values are the roles of the identity in the account.
+pub const ACCOUNT_INDEX_ROOT: &str = "/account_index/";
 
 /// Internal representation of Account metadata
 #[derive(Clone, Debug)]
@@ -30,7 +39,28 @@ pub struct AccountMeta {
 }
 
 pub(super) fn key_for_account(id: &Address) -> Vec<u8> {
-    format!("/accounts/{id}").into_bytes()
+    format!("{ACCOUNTS_ROOT}{id}").into_bytes()
+}
+
+fn prefix_for_account_index(identity: &Address) -> Vec<u8> {
+    format!("{ACCOUNT_INDEX_ROOT}{identity}/").into_bytes()
+}
+
+fn key_for_account_index(identity: &Address, account: &Address) -> Vec<u8> {
+    format!("{ACCOUNT_INDEX_ROOT}{identity}/{account}").into_bytes()
+}
+
+/// The index keys of an account, with the roles of each identity. Identities
+/// with an empty set of roles are not members of the account.
+pub(crate) fn account_index_entries<'a>(
+    id: &'a Address,
+    account: &'a account::Account,
+) -> impl Iterator<Item = (Vec<u8>, &'a BTreeSet<Role>)> + 'a {
+    account
+        .roles
+        .iter()
+        .filter(|(_, roles)| !roles.is_empty())
+        .map(move |(identity, roles)| (key_for_account_index(identity, id), roles))
 }
 
 pub fn verify_acl(
@@ -75,6 +105,7 @@ impl LedgerStorage {
                         roles: account.roles,
                         features: account.features,
                         disabled: None,
+                        metadata: None,
                     },
                     false,
                 )?;
@@ -176,10 +207,11 @@ impl LedgerStorage {
         let (mut account, keys) = self.get_account_even_disabled(id)?;
         let mut keys = keys.into_iter().collect::<Vec<_>>();
 
-        if account.disabled.is_none() || account.disabled == Some(Either::Left(false)) {
+        if !is_disabled(&account) {
             account.disabled = Some(Either::Left(true));
             let key = self.commit_account(id, account)?;
             keys.push(key);
+            self.mark_account_disabled(id)?;
             self.log_event(events::EventInfo::AccountDisable { account: *id })?;
 
             self.maybe_commit().map(|_| keys)
@@ -201,6 +233,19 @@ impl LedgerStorage {
         .and_then(|_| self.commit_account(&args.account, account))
     }
 
+    pub fn set_metadata(
+        &mut self,
+        mut account: account::Account,
+        args: account::SetMetadataArgs,
+    ) -> Result<Vec<u8>, ManyError> {
+        account.set_metadata(args.metadata.clone());
+        self.log_event(events::EventInfo::AccountSetMetadata {
+            account: args.account,
+            metadata: args.metadata,
+        })
+        .and_then(|_| self.commit_account(&args.account, account))
+    }
+
     pub fn add_roles(
         &mut self,
         mut account: account::Account,
@@ -290,7 +335,7 @@ impl LedgerStorage {
         id: &Address,
     ) -> Result<(account::Account, impl IntoIterator<Item = Vec<u8>>), ManyError> {
         let (account, keys) = self.get_account_even_disabled(id)?;
-        if account.disabled.is_none() || account.disabled == Some(Either::Left(false)) {
+        if !is_disabled(&account) {
             Ok((account, keys))
         } else {
             Err(account::errors::unknown_account(id))
@@ -322,13 +367,81 @@ impl LedgerStorage {
         tracing::debug!("commit({:?})", account);
         let key = key_for_account(id);
 
+        if self.migrations.is_active(&ACCOUNT_INDEX_MIGRATION) {
+            self.update_account_index(id, &account)?;
+        }
+
         self.persistent_store
             .apply(&[(
                 key.clone(),
                 Op::Put(minicbor::to_vec(account).map_err(ManyError::serialization_error)?),
             )])
-            .map_err(|e| ManyError::unknown(e.to_string()))?;
+            .map_err(error::storage_apply_failed)?;
 
         self.maybe_commit().map(|_| key)
     }
+
+    /// Update the index entries of an account which changed, before it is
+    /// committed.
+    fn update_account_index(
+        &mut self,
+        id: &Address,
+        account: &account::Account,
+    ) -> Result<(), ManyError> {
+        let old = self
+            .get_account_even_disabled(id)
+            .map(|(account, _)| account)
+            .ok();
+        let old_entries: BTreeMap<_, _> = old
+            .as_ref()
+            .map(|old| account_index_entries(id, old).collect())
+            .unwrap_or_default();
+        let new_entries: BTreeMap<_, _> = account_index_entries(id, account).collect();
+
+        let mut batch = Vec::new();
+        for key in old_entries.keys() {
+            if !new_entries.contains_key(key) {
+                batch.push((key.clone(), Op::Delete));
+            }
+        }
+        for (key, roles) in &new_entries {
+            if old_entries.get(key) != Some(roles) {
+                batch.push((
+                    key.clone(),
+                    Op::Put(minicbor::to_vec(roles).map_err(ManyError::serialization_error)?),
+                ));
+            }
+        }
+        if batch.is_empty() {
+            return Ok(());
+        }
+        batch.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
+
+        self.persistent_store
+            .apply(&batch)
+            .map_err(error::storage_apply_failed)
+    }
+
+    /// The accounts where an identity holds roles, and the keys to prove.
+    /// Disabled accounts are skipped.
+    pub fn list_accounts_for_identity(
+        &self,
+        identity: &Address,
+    ) -> Result<(Vec<(Address, account::Account)>, Vec<Vec<u8>>), ManyError> {
+        let prefix = prefix_for_account_index(identity);
+        let mut accounts = Vec::new();
+        let mut keys = Vec::new();
+        for item in LedgerIterator::all_with_prefix(&self.persistent_store, &prefix) {
+            let (key, _) = item.map_err(error::StorageError::from)?;
+            let id = std::str::from_utf8(&key[prefix.len()..])
+                .map_err(ManyError::deserialization_error)
+                .and_then(Address::from_str)?;
+            keys.push(key.to_vec());
+            if let Ok((account, account_keys)) = self.get_account(&id) {
+                keys.extend(account_keys);
+                accounts.push((id, account));
+            }
+        }
+        Ok((accounts, keys))
+    }
 }
@@ -0,0 +1,168 @@
+//! Pruning of the accounts disabled for longer than a retention period. A
+//! pruned account is replaced by a tombstone holding the hash of its last
+//! state, so its content can still be proven from an archive.
+use crate::error;
+use crate::migration::account_gc::ACCOUNT_GC_MIGRATION;
+use crate::migration::account_index::ACCOUNT_INDEX_MIGRATION;
+use crate::storage::account::{account_index_entries, key_for_account};
+use crate::storage::iterator::LedgerIterator;
+use crate::storage::LedgerStorage;
+use many_error::ManyError;
+use many_identity::Address;
+use many_modules::{account, events};
+use many_types::{Either, Timestamp};
+use merk::Op;
+use minicbor::bytes::ByteVec;
+use minicbor::{Decode, Encode};
+use sha3::{Digest, Sha3_256};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+pub const ACCOUNT_GC_CONFIG_KEY: &[u8] = b"/config/account_gc";
+
+/// The disabled accounts waiting to be pruned. Values are the time the
+/// account was disabled, or null for accounts disabled before the migration,
+/// whose retention period starts at the first block after it.
+pub const ACCOUNT_DISABLED_ROOT: &str = "/account_disabled/";
+
+/// The pruned accounts. Values are the hash of the last state of the account.
+pub const ACCOUNT_TOMBSTONES_ROOT: &str = "/account_tombstones/";
+
+/// The retention policy of disabled accounts, set by the account GC
+/// migration.
+#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct AccountGcConfig {
+    #[n(0)]
+    pub retention_secs: u64,
+}
+
+pub(crate) fn key_for_disabled_account(id: &Address) -> Vec<u8> {
+    format!("{ACCOUNT_DISABLED_ROOT}{id}").into_bytes()
+}
+
+fn key_for_tombstone(id: &Address) -> Vec<u8> {
+    format!("{ACCOUNT_TOMBSTONES_ROOT}{id}").into_bytes()
+}
+
+pub(crate) fn is_disabled(account: &account::Account) -> bool {
+    !(account.disabled.is_none() || account.disabled == Some(Either::Left(false)))
+}
+
+/// The hash of the encoded state of an account, kept when it is pruned.
+pub fn tombstone_hash(account_bytes: &[u8]) -> ByteVec {
+    Sha3_256::digest(account_bytes).to_vec().into()
+}
+
+impl LedgerStorage {
+    pub fn get_account_gc_config(&self) -> Result<AccountGcConfig, ManyError> {
+        let config = self
+            .persistent_store
+            .get(ACCOUNT_GC_CONFIG_KEY)
+            .map_err(error::storage_get_failed)?
+            .ok_or_else(|| error::storage_key_not_found("/config/account_gc"))?;
+        minicbor::decode(&config).map_err(ManyError::deserialization_error)
+    }
+
+    /// The tombstone hash of a pruned account, or `None` if the account was
+    /// never pruned.
+    pub fn get_account_tombstone(&self, id: &Address) -> Result<Option<ByteVec>, ManyError> {
+        Ok(self
+            .persistent_store
+            .get(&key_for_tombstone(id))
+            .map_err(error::storage_get_failed)?
+            .map(ByteVec::from))
+    }
+
+    /// Start the retention period of an account which was just disabled.
+    pub(crate) fn mark_account_disabled(&mut self, id: &Address) -> Result<(), ManyError> {
+        if !self.migrations.is_active(&ACCOUNT_GC_MIGRATION) {
+            return Ok(());
+        }
+        self.persistent_store
+            .apply(&[(
+                key_for_disabled_account(id),
+                Op::Put(
+                    minicbor::to_vec(Some(self.now())).map_err(ManyError::serialization_error)?,
+                ),
+            )])
+            .map_err(error::storage_apply_failed)
+    }
+
+    /// Prune the accounts disabled for longer than the retention period, with
+    /// their index entries, and log an event for each of them. Only accounts
+    /// disabled before the last commit are considered.
+    pub fn prune_disabled_accounts(&mut self) -> Result<Vec<Address>, ManyError> {
+        if !self.migrations.is_active(&ACCOUNT_GC_MIGRATION) {
+            return Ok(vec![]);
+        }
+        let retention_secs = self.get_account_gc_config()?.retention_secs;
+        let now = self.now();
+        let with_index = self.migrations.is_active(&ACCOUNT_INDEX_MIGRATION);
+
+        let mut batch = BTreeMap::new();
+        let mut pruned = Vec::new();
+        let prefix = ACCOUNT_DISABLED_ROOT.as_bytes();
+        for item in LedgerIterator::all_with_prefix(&self.persistent_store, prefix) {
+            let (key, value) = item.map_err(error::StorageError::from)?;
+            let id = std::str::from_utf8(&key[prefix.len()..])
+                .map_err(ManyError::deserialization_error)
+                .and_then(Address::from_str)?;
+            let disabled_at: Option<Timestamp> =
+                minicbor::decode(&value).map_err(ManyError::deserialization_error)?;
+
+            let expired = match disabled_at {
+                None => {
+                    batch.insert(
+                        key.to_vec(),
+                        Op::Put(
+                            minicbor::to_vec(Some(now)).map_err(ManyError::serialization_error)?,
+                        ),
+                    );
+                    false
+                }
+                Some(disabled_at) => now
+                    .duration_since(disabled_at)
+                    .map_or(false, |d| d.as_secs() >= retention_secs),
+            };
+            if !expired {
+                continue;
+            }
+
+            let account_key = key_for_account(&id);
+            if let Some(bytes) = self
+                .persistent_store
+                .get(&account_key)
+                .map_err(error::storage_get_failed)?
+            {
+                let account: account::Account =
+                    minicbor::decode(&bytes).map_err(ManyError::deserialization_error)?;
+                if with_index {
+                    for (index_key, _) in account_index_entries(&id, &account) {
+                        batch.insert(index_key, Op::Delete);
+                    }
+                }
+                let hash = tombstone_hash(&bytes);
+                batch.insert(account_key, Op::Delete);
+                batch.insert(key_for_tombstone(&id), Op::Put(hash.to_vec()));
+                pruned.push((id, hash));
+            }
+            batch.insert(key.to_vec(), Op::Delete);
+        }
+
+        if !batch.is_empty() {
+            self.persistent_store
+                .apply(&batch.into_iter().collect::<Vec<_>>())
+                .map_err(error::storage_apply_failed)?;
+        }
+        for (account, hash) in &pruned {
+            self.log_event(events::EventInfo::AccountPrune {
+                account: *account,
+                hash: hash.clone(),
+            })?;
+        }
+
+        self.maybe_commit()?;
+        Ok(pruned.into_iter().map(|(id, _)| id).collect())
+    }
+}
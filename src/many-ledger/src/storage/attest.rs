@@ -0,0 +1,131 @@
+use crate::error;
+use crate::storage::iterator::LedgerIterator;
+use crate::storage::LedgerStorage;
+use many_error::ManyError;
+use many_identity::Address;
+use many_modules::attest::{self, Claim};
+use many_modules::events::EventInfo;
+use many_types::cbor::CborAny;
+use many_types::Timestamp;
+use merk::Op;
+use std::collections::BTreeSet;
+
+pub const ATTEST_ROOT: &str = "/attest/";
+pub const ATTESTORS_KEY: &[u8] = b"/config/attestors";
+
+/// Claims are stored by subject first so all claims about an address can be
+/// listed with a single prefix.
+fn prefix_for_claims(subject: &Address, attestor: Option<&Address>) -> Vec<u8> {
+    match attestor {
+        Some(attestor) => format!("{ATTEST_ROOT}{subject}/{attestor}/"),
+        None => format!("{ATTEST_ROOT}{subject}/"),
+    }
+    .into_bytes()
+}
+
+pub(crate) fn key_for_claim(subject: &Address, attestor: &Address, kind: &str) -> Vec<u8> {
+    let mut key = prefix_for_claims(subject, Some(attestor));
+    key.extend_from_slice(kind.as_bytes());
+    key
+}
+
+impl LedgerStorage {
+    pub fn get_attestors(&self) -> Result<BTreeSet<Address>, ManyError> {
+        self.persistent_store
+            .get(ATTESTORS_KEY)
+            .map_err(error::storage_get_failed)?
+            .map_or(Ok(BTreeSet::new()), |bytes| {
+                minicbor::decode(&bytes).map_err(ManyError::deserialization_error)
+            })
+    }
+
+    /// The claims about a subject that have not expired, with the keys they
+    /// are stored at.
+    pub fn get_claims(
+        &self,
+        subject: &Address,
+        attestor: Option<&Address>,
+    ) -> Result<(Vec<Claim>, Vec<Vec<u8>>), ManyError> {
+        let now = self.now();
+        let mut claims = Vec::new();
+        let mut keys = Vec::new();
+        let prefix = prefix_for_claims(subject, attestor);
+        for item in LedgerIterator::all_with_prefix(&self.persistent_store, &prefix) {
+            let (key, value) = item.map_err(error::StorageError::from)?;
+            let claim: Claim =
+                minicbor::decode(&value).map_err(ManyError::deserialization_error)?;
+            if claim.expiration.map_or(true, |expiration| expiration > now) {
+                claims.push(claim);
+                keys.push(key.to_vec());
+            }
+        }
+        Ok((claims, keys))
+    }
+
+    pub fn publish_claim(
+        &mut self,
+        attestor: &Address,
+        subject: Address,
+        kind: String,
+        value: CborAny,
+        expiration: Option<Timestamp>,
+    ) -> Result<(), ManyError> {
+        if !self.get_attestors()?.contains(attestor) {
+            return Err(attest::not_an_attestor(attestor.to_string()));
+        }
+        attest::validate_kind(&kind)?;
+
+        let claim = Claim {
+            attestor: *attestor,
+            subject,
+            kind: kind.clone(),
+            value,
+            issued: self.now(),
+            expiration,
+        };
+        self.persistent_store
+            .apply(&[(
+                key_for_claim(&subject, attestor, &kind),
+                Op::Put(minicbor::to_vec(claim).map_err(ManyError::serialization_error)?),
+            )])
+            .map_err(error::storage_apply_failed)?;
+
+        self.log_event(EventInfo::AttestPublish {
+            attestor: *attestor,
+            subject,
+            kind,
+            expiration,
+        })?;
+        self.maybe_commit()
+    }
+
+    /// Remove a claim. Claims are keyed by their attestor, so only the
+    /// attestor of a claim can revoke it.
+    pub fn revoke_claim(
+        &mut self,
+        attestor: &Address,
+        subject: Address,
+        kind: String,
+    ) -> Result<(), ManyError> {
+        let key = key_for_claim(&subject, attestor, &kind);
+        if self
+            .persistent_store
+            .get(&key)
+            .map_err(error::storage_get_failed)?
+            .is_none()
+        {
+            return Err(attest::claim_not_found(kind));
+        }
+
+        self.persistent_store
+            .apply(&[(key, Op::Delete)])
+            .map_err(error::storage_apply_failed)?;
+
+        self.log_event(EventInfo::AttestRevoke {
+            attestor: *attestor,
+            subject,
+            kind,
+        })?;
+        self.maybe_commit()
+    }
+}
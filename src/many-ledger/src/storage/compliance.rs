@@ -0,0 +1,123 @@
+use crate::error;
+use crate::migration::compliance::COMPLIANCE_MIGRATION;
+use crate::storage::namespace::Namespace;
+use crate::storage::LedgerStorage;
+use many_error::ManyError;
+use many_identity::Address;
+use many_modules::compliance::{self, ListMode};
+use many_modules::events::EventInfo;
+use minicbor::{Decode, Encode};
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+pub const COMPLIANCE_ROOT: &str = "/compliance/";
+pub const COMPLIANCE_CONFIG_KEY: &[u8] = b"/config/compliance";
+
+/// The listed addresses, as keys relative to the namespace.
+pub const COMPLIANCE_NAMESPACE: Namespace =
+    Namespace::new("compliance", COMPLIANCE_ROOT.as_bytes());
+
+/// The administrator and mode of the compliance list, set by the compliance
+/// migration.
+#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct ComplianceConfig {
+    #[n(0)]
+    pub admin: Address,
+
+    #[n(1)]
+    pub mode: ListMode,
+}
+
+/// A transfer hook rejecting the sends the compliance list does not permit.
+/// Mints and burns are left to the token owners.
+pub(crate) fn check_send(storage: &LedgerStorage, event: &EventInfo) -> Result<(), ManyError> {
+    if !storage.migrations().is_active(&COMPLIANCE_MIGRATION) {
+        return Ok(());
+    }
+    if let EventInfo::Send { from, to, .. } = event {
+        let mode = storage.get_compliance_config()?.mode;
+        for address in [from, to] {
+            if !mode.permits(storage.is_listed(address)?) {
+                return Err(compliance::address_blocked(address.to_string()));
+            }
+        }
+    }
+    Ok(())
+}
+
+impl LedgerStorage {
+    pub fn get_compliance_config(&self) -> Result<ComplianceConfig, ManyError> {
+        let config = self
+            .persistent_store
+            .get(COMPLIANCE_CONFIG_KEY)
+            .map_err(error::storage_get_failed)?
+            .ok_or_else(|| error::storage_key_not_found("/config/compliance"))?;
+        minicbor::decode(&config).map_err(ManyError::deserialization_error)
+    }
+
+    pub fn is_listed(&self, address: &Address) -> Result<bool, ManyError> {
+        Ok(self
+            .namespace(COMPLIANCE_NAMESPACE)
+            .get(address.to_string())?
+            .is_some())
+    }
+
+    pub fn get_compliance_list(&self) -> Result<BTreeSet<Address>, ManyError> {
+        let mut addresses = BTreeSet::new();
+        for item in self.namespace(COMPLIANCE_NAMESPACE).iter() {
+            let (key, _) = item?;
+            let address = std::str::from_utf8(&key).map_err(ManyError::deserialization_error)?;
+            addresses.insert(Address::from_str(address)?);
+        }
+        Ok(addresses)
+    }
+
+    fn check_compliance_admin(&self, sender: &Address) -> Result<(), ManyError> {
+        if self.get_compliance_config()?.admin == *sender {
+            Ok(())
+        } else {
+            Err(compliance::not_compliance_admin(sender.to_string()))
+        }
+    }
+
+    pub fn add_to_compliance_list(
+        &mut self,
+        sender: &Address,
+        address: Address,
+    ) -> Result<(), ManyError> {
+        self.check_compliance_admin(sender)?;
+        if self.is_listed(&address)? {
+            return Err(compliance::already_listed(address.to_string()));
+        }
+
+        self.namespace_mut(COMPLIANCE_NAMESPACE)
+            .put(address.to_string(), vec![])?;
+
+        self.log_event(EventInfo::ComplianceListAdd {
+            admin: *sender,
+            address,
+        })?;
+        self.maybe_commit()
+    }
+
+    pub fn remove_from_compliance_list(
+        &mut self,
+        sender: &Address,
+        address: Address,
+    ) -> Result<(), ManyError> {
+        self.check_compliance_admin(sender)?;
+        if !self.is_listed(&address)? {
+            return Err(compliance::not_listed(address.to_string()));
+        }
+
+        self.namespace_mut(COMPLIANCE_NAMESPACE)
+            .delete(address.to_string())?;
+
+        self.log_event(EventInfo::ComplianceListRemove {
+            admin: *sender,
+            address,
+        })?;
+        self.maybe_commit()
+    }
+}
@@ -1,17 +1,113 @@
 use crate::error;
-use crate::migration::data::{ACCOUNT_TOTAL_COUNT_INDEX, NON_ZERO_ACCOUNT_TOTAL_COUNT_INDEX};
+use crate::migration::data::{
+    ACCOUNT_TOTAL_COUNT_INDEX, NON_ZERO_ACCOUNT_TOTAL_COUNT_INDEX, TOTAL_SUPPLY_INDEX,
+};
+use crate::migration::tokens::TOKEN_MIGRATION;
+use crate::storage::iterator::LedgerIterator;
 use crate::storage::{key_for_account_balance, LedgerStorage};
 use many_error::ManyError;
 use many_identity::Address;
-use many_modules::data::{DataIndex, DataInfo, DataValue};
-use many_types::ledger::TokenAmount;
+use many_modules::data::{DataIndex, DataInfo, DataType, DataValue, DataValueTypeGauge, Decimal};
+use many_types::ledger::{Symbol, TokenAmount, TokenInfo};
+use many_types::SortOrder;
 use merk::Op;
 use std::collections::BTreeMap;
 
 pub const DATA_ATTRIBUTES_KEY: &[u8] = b"/data/attributes";
 pub const DATA_INFO_KEY: &[u8] = b"/data/info";
+/// The index of the total supply gauge of every token. It is assigned when
+/// the token is created, so creating a token does not renumber the others.
+pub const TOTAL_SUPPLY_INDICES_KEY: &[u8] = b"/data/total_supply_indices";
+
+/// The total supply gauges of every token, with their info, and the keys to
+/// prove them. They are computed from the token info, not stored.
+pub(crate) type TotalSupplyData = (
+    BTreeMap<DataIndex, DataInfo>,
+    BTreeMap<DataIndex, DataValue>,
+    Vec<Vec<u8>>,
+);
 
 impl LedgerStorage {
+    /// The index of the total supply gauge of every token. Until a token is
+    /// created, they are numbered in the order of their symbols.
+    pub(crate) fn total_supply_indices(&self) -> Result<BTreeMap<Symbol, u32>, ManyError> {
+        if let Some(enc) = self
+            .persistent_store
+            .get(TOTAL_SUPPLY_INDICES_KEY)
+            .map_err(error::storage_get_failed)?
+        {
+            return minicbor::decode(&enc).map_err(ManyError::deserialization_error);
+        }
+
+        let mut indices = BTreeMap::new();
+        let it = LedgerIterator::all_symbols(&self.persistent_store, SortOrder::Ascending);
+        for (i, item) in (0u32..).zip(it) {
+            let (_, value) = item.map_err(error::StorageError::from)?;
+            let info: TokenInfo =
+                minicbor::decode(&value).map_err(ManyError::deserialization_error)?;
+            indices.insert(info.symbol, i);
+        }
+        Ok(indices)
+    }
+
+    /// Assign the next total supply gauge index to a new token. Returns the
+    /// batch entry storing the indices.
+    pub(crate) fn add_total_supply_index(
+        &self,
+        symbol: Symbol,
+    ) -> Result<(Vec<u8>, Op), ManyError> {
+        let mut indices = self.total_supply_indices()?;
+        let next = indices.values().max().map_or(0, |i| i + 1);
+        indices.insert(symbol, next);
+        Ok((
+            TOTAL_SUPPLY_INDICES_KEY.to_vec(),
+            Op::Put(minicbor::to_vec(&indices).map_err(ManyError::serialization_error)?),
+        ))
+    }
+
+    /// The total supply of each token as an exact decimal gauge, e.g. `1.5`
+    /// for a supply of 1500 with 3 decimals. Empty until the token migration
+    /// is active. Tokens with more than [`TokenAmount::MAXIMUM_DECIMALS`]
+    /// have no gauge.
+    pub(crate) fn total_supply_data(&self) -> Result<TotalSupplyData, ManyError> {
+        let mut data = TotalSupplyData::default();
+        if !self.migrations.is_active(&TOKEN_MIGRATION) {
+            return Ok(data);
+        }
+
+        let indices = self.total_supply_indices()?;
+        let it = LedgerIterator::all_symbols(&self.persistent_store, SortOrder::Ascending);
+        for item in it {
+            let (key, value) = item.map_err(error::StorageError::from)?;
+            let info: TokenInfo =
+                minicbor::decode(&value).map_err(ManyError::deserialization_error)?;
+            let Some(i) = indices.get(&info.symbol) else {
+                continue;
+            };
+            if info.summary.decimals > TokenAmount::MAXIMUM_DECIMALS {
+                continue;
+            }
+            let index = TOTAL_SUPPLY_INDEX.with_index(*i);
+            let exponent = info.summary.decimals as i64;
+            data.0.insert(
+                index,
+                DataInfo {
+                    r#type: DataType::Gauge,
+                    shortname: format!("{}TotalSupply", info.summary.ticker),
+                },
+            );
+            data.1.insert(
+                index,
+                DataValue::Gauge(DataValueTypeGauge::Decimal(Decimal::new(
+                    info.supply.total.as_ref().clone(),
+                    -exponent,
+                ))),
+            );
+            data.2.push(key.to_vec());
+        }
+        Ok(data)
+    }
+
     pub(crate) fn data_info(&self) -> Result<Option<BTreeMap<DataIndex, DataInfo>>, ManyError> {
         Ok(self
             .persistent_store
@@ -10,16 +10,13 @@ use merk::Op;
 pub(crate) const EVENTS_ROOT: &[u8] = b"/events/";
 pub(crate) const EVENT_COUNT_ROOT: &[u8] = b"/events_count";
 
-// Left-shift the height by this amount of bits
-pub(crate) const HEIGHT_EVENTID_SHIFT: u64 = 32;
-
 /// Number of bytes in an event ID when serialized. Keys smaller than this
 /// will have `\0` prepended, and keys larger will be cut to this number of
 /// bytes.
 pub(crate) const EVENT_ID_KEY_SIZE_IN_BYTES: usize = 32;
 
 /// Returns the storage key for an event in the kv-store.
-pub(super) fn key_for_event(id: events::EventId) -> Vec<u8> {
+pub(crate) fn key_for_event(id: events::EventId) -> Vec<u8> {
     let id = id.as_ref();
     let id = if id.len() > EVENT_ID_KEY_SIZE_IN_BYTES {
         &id[0..EVENT_ID_KEY_SIZE_IN_BYTES]
@@ -80,6 +77,24 @@ impl LedgerStorage {
     pub fn iter_events(&self, range: CborRange<EventId>, order: SortOrder) -> LedgerIterator {
         LedgerIterator::events_scoped_by_id(&self.persistent_store, range, order)
     }
+
+    /// Returns the encoded events emitted during the block committed at
+    /// `height`, in order. Event IDs of the first two blocks share the same
+    /// range, see `LedgerStorage::load()`.
+    pub fn block_events(&self, height: u64) -> Result<Vec<Vec<u8>>, ManyError> {
+        self.iter_events(EventId::range_of_height(height), SortOrder::Ascending)
+            .map(|item| {
+                item.map(|(_k, v)| v)
+                    .map_err(error::storage_iteration_failed)
+            })
+            .collect()
+    }
+
+    /// Returns the digest of the events emitted during the block committed at
+    /// `height`.
+    pub fn block_events_digest(&self, height: u64) -> Result<Vec<u8>, ManyError> {
+        Ok(events::digest::events_digest(&self.block_events(height)?).to_vec())
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,105 @@
+use crate::error;
+use crate::storage::iterator::LedgerIterator;
+use crate::storage::LedgerStorage;
+use many_error::ManyError;
+use merk::Op;
+use std::collections::BTreeMap;
+
+/// An index of the keys which can be deleted after a deadline, ordered by
+/// deadline. Its keys are the deadline in seconds (big endian) followed by
+/// the key to delete.
+pub(crate) const EXPIRY_ROOT: &[u8] = b"/expiry/";
+
+fn key_for_expiry(expires_at: u64, key: &[u8]) -> Vec<u8> {
+    [EXPIRY_ROOT, &expires_at.to_be_bytes(), key].concat()
+}
+
+impl LedgerStorage {
+    /// Put a key which is deleted by [`Self::prune_expired_keys`] once the
+    /// block time reaches `expires_at`, in seconds.
+    pub(crate) fn put_expiring(
+        &mut self,
+        key: Vec<u8>,
+        value: Vec<u8>,
+        expires_at: u64,
+    ) -> Result<(), ManyError> {
+        let mut batch = vec![
+            (key_for_expiry(expires_at, &key), Op::Put(vec![])),
+            (key, Op::Put(value)),
+        ];
+        batch.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
+        self.persistent_store
+            .apply(&batch)
+            .map_err(error::storage_apply_failed)
+    }
+
+    /// Delete the keys which expired, with their index entries. Only keys
+    /// put before the last commit are considered.
+    pub fn prune_expired_keys(&mut self) -> Result<(), ManyError> {
+        let now = self.now().secs();
+        let mut batch = BTreeMap::new();
+        for item in LedgerIterator::all_with_prefix(&self.persistent_store, EXPIRY_ROOT) {
+            let (index_key, _) = item.map_err(error::StorageError::from)?;
+            let rest = &index_key[EXPIRY_ROOT.len()..];
+            let deadline = rest
+                .get(..8)
+                .and_then(|d| <[u8; 8]>::try_from(d).ok())
+                .ok_or_else(|| ManyError::deserialization_error("Invalid expiry key"))?;
+            // The index is ordered by deadline.
+            if u64::from_be_bytes(deadline) > now {
+                break;
+            }
+            batch.insert(index_key.to_vec(), Op::Delete);
+            batch.insert(rest[8..].to_vec(), Op::Delete);
+        }
+
+        if !batch.is_empty() {
+            self.persistent_store
+                .apply(&batch.into_iter().collect::<Vec<_>>())
+                .map_err(error::storage_apply_failed)?;
+        }
+        self.maybe_commit()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use many_types::BlockTime;
+
+    #[test]
+    fn prune_expired_keys() {
+        let path = tempfile::tempdir().unwrap().into_path();
+        let mut storage = LedgerStorage::new(path, false).unwrap().build().unwrap();
+        storage.set_time(BlockTime::from_secs(100));
+        storage
+            .put_expiring(b"/a/1".to_vec(), b"1".to_vec(), 150)
+            .unwrap();
+        storage
+            .put_expiring(b"/a/2".to_vec(), b"2".to_vec(), 200)
+            .unwrap();
+        storage.maybe_commit().unwrap();
+
+        storage.prune_expired_keys().unwrap();
+        assert!(storage.persistent_store.get(b"/a/1").unwrap().is_some());
+
+        storage.set_time(BlockTime::from_secs(150));
+        storage.prune_expired_keys().unwrap();
+        assert_eq!(storage.persistent_store.get(b"/a/1").unwrap(), None);
+        assert_eq!(
+            storage.persistent_store.get(b"/a/2").unwrap(),
+            Some(b"2".to_vec())
+        );
+        assert_eq!(
+            storage
+                .persistent_store
+                .get(&key_for_expiry(150, b"/a/1"))
+                .unwrap(),
+            None
+        );
+
+        storage.set_time(BlockTime::from_secs(1_000));
+        storage.prune_expired_keys().unwrap();
+        assert_eq!(storage.persistent_store.get(b"/a/2").unwrap(), None);
+    }
+}
@@ -0,0 +1,11 @@
+//! Failures injected in the persistent store, to test that the ledger
+//! recovers or fails cleanly when the store fails.
+use crate::storage::LedgerStorage;
+
+pub use many_server::transaction::Faults;
+
+impl LedgerStorage {
+    pub fn faults(&mut self) -> &mut Faults {
+        self.persistent_store.faults()
+    }
+}
@@ -0,0 +1,65 @@
+//! Callbacks of other modules on the balance changes of the ledger, e.g. a
+//! fees or a compliance module, which can reject a change without changing
+//! the code of the send path.
+use crate::storage::{compliance, LedgerStorage};
+use many_error::ManyError;
+use many_modules::events::EventInfo;
+use many_types::ledger::Symbol;
+use std::collections::BTreeMap;
+
+/// A callback on a balance change. It receives the storage before the change
+/// is applied and the event which will be logged for it. Returning an error
+/// cancels the change, and the error is returned to the sender.
+pub type TransferHook =
+    Box<dyn Fn(&LedgerStorage, &EventInfo) -> Result<(), ManyError> + Send + 'static>;
+
+#[derive(Default)]
+pub(crate) struct TransferHooks {
+    all: Vec<TransferHook>,
+    by_symbol: BTreeMap<Symbol, Vec<TransferHook>>,
+}
+
+impl TransferHooks {
+    /// The hooks of the ledger's own modules. They check whether their
+    /// migration is active themselves.
+    pub(crate) fn builtin() -> Self {
+        let mut hooks = Self::default();
+        hooks.all.push(Box::new(compliance::check_send));
+        hooks
+    }
+
+    fn get<'a>(&'a self, symbol: &Symbol) -> impl Iterator<Item = &'a TransferHook> {
+        self.all
+            .iter()
+            .chain(self.by_symbol.get(symbol).into_iter().flatten())
+    }
+}
+
+impl LedgerStorage {
+    /// Register a hook on the sends, mints and burns of a symbol, or of all
+    /// symbols if `symbol` is `None`. Hooks on all symbols run first, then
+    /// hooks run in the order they were added, after the built-in hooks. The
+    /// first error stops the change.
+    pub fn add_transfer_hook(
+        &mut self,
+        symbol: Option<Symbol>,
+        hook: impl Fn(&LedgerStorage, &EventInfo) -> Result<(), ManyError> + Send + 'static,
+    ) {
+        let hooks = match symbol {
+            Some(symbol) => self.transfer_hooks.by_symbol.entry(symbol).or_default(),
+            None => &mut self.transfer_hooks.all,
+        };
+        hooks.push(Box::new(hook));
+    }
+
+    /// Run the hooks of `symbol` on an event, before applying its changes.
+    pub(crate) fn run_transfer_hooks(
+        &self,
+        symbol: &Symbol,
+        event: &EventInfo,
+    ) -> Result<(), ManyError> {
+        self.transfer_hooks
+            .get(symbol)
+            .try_for_each(|hook| hook(self, event))
+    }
+}
@@ -32,10 +32,47 @@ impl<'a> LedgerIterator<'a> {
     }
 
     pub fn all_symbols(merk: &'a InnerStorage, order: SortOrder) -> Self {
+        Self::symbols_after(merk, None, order)
+    }
+
+    /// The symbols following the symbol key `after` (excluded) in the order
+    /// of the iteration, or all of them. The iteration starts at the key.
+    pub fn symbols_after(merk: &'a InnerStorage, after: Option<&[u8]>, order: SortOrder) -> Self {
         use crate::storage::ledger_tokens::SYMBOLS_ROOT_DASH;
 
+        let mut lower = SYMBOLS_ROOT_DASH.as_bytes().to_vec();
+        let mut upper = lower.clone();
+        *upper.last_mut().unwrap() += 1;
+
+        let it_mode = match order {
+            SortOrder::Indeterminate | SortOrder::Ascending => {
+                if let Some(after) = after {
+                    lower = lower.max([after, &[0]].concat());
+                }
+                IteratorMode::Start
+            }
+            SortOrder::Descending => {
+                if let Some(after) = after {
+                    upper = upper.min(after.to_vec());
+                }
+                IteratorMode::End
+            }
+        };
+
+        let mut options = ReadOptions::default();
+        options.set_iterate_lower_bound(lower);
+        options.set_iterate_upper_bound(upper);
+
+        let inner = merk.iter_opt(it_mode, options);
+
+        Self { inner }
+    }
+
+    pub fn all_balances(merk: &'a InnerStorage, order: SortOrder) -> Self {
+        use crate::storage::BALANCES_ROOT;
+
         let mut options = ReadOptions::default();
-        options.set_iterate_range(rocksdb::PrefixRange(SYMBOLS_ROOT_DASH.as_bytes()));
+        options.set_iterate_range(rocksdb::PrefixRange(BALANCES_ROOT.as_bytes()));
 
         let it_mode = match order {
             SortOrder::Indeterminate | SortOrder::Ascending => IteratorMode::Start,
@@ -47,6 +84,15 @@ impl<'a> LedgerIterator<'a> {
         Self { inner }
     }
 
+    pub fn all_with_prefix(merk: &'a InnerStorage, prefix: &[u8]) -> Self {
+        let mut options = ReadOptions::default();
+        options.set_iterate_range(rocksdb::PrefixRange(prefix));
+
+        let inner = merk.iter_opt(IteratorMode::Start, options);
+
+        Self { inner }
+    }
+
     pub fn all_events(merk: &'a InnerStorage) -> Self {
         Self::events_scoped_by_id(merk, CborRange::default(), SortOrder::Indeterminate)
     }
@@ -1,22 +1,29 @@
+use crate::audit::Replay;
 use crate::error;
-use crate::storage::{key_for_account_balance, LedgerStorage, IDENTITY_ROOT, SYMBOLS_ROOT};
+use crate::storage::iterator::LedgerIterator;
+use crate::storage::{
+    key_for_account_balance, LedgerStorage, BALANCES_ROOT, IDENTITY_ROOT, SYMBOLS_ROOT,
+};
 use many_error::ManyError;
 use many_identity::Address;
+use many_modules::events::{EventId, EventLog};
 use many_protocol::context::Context;
 use many_types::{
     ledger::{Symbol, TokenAmount},
-    ProofOperation,
-};
-use merk::{
-    proofs::{
-        query::QueryItem,
-        Decoder,
-        Node::{Hash, KVHash, KV},
-        Op::{Child, Parent, Push},
-    },
-    BatchEntry, Op,
+    ProofOperation, SortOrder,
 };
+use merk::{proofs::query::QueryItem, BatchEntry, Op};
 use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+
+/// How many blocks `get_multiple_balances_at` can look back. Each query
+/// replays the events of every block since the height, so the lookback is
+/// capped to keep queries cheap.
+pub const MAX_BALANCE_LOOKBACK: u64 = 10_000;
+
+/// How many events `get_multiple_balances_at` can replay, as blocks can hold
+/// any number of events.
+pub const MAX_BALANCE_EVENTS: usize = 10_000;
 
 impl LedgerStorage {
     pub fn with_balances(
@@ -91,6 +98,26 @@ impl LedgerStorage {
         })
     }
 
+    /// Every balance stored in the state, per address and symbol.
+    pub fn get_all_account_balances(
+        &self,
+    ) -> Result<BTreeMap<Address, BTreeMap<Symbol, TokenAmount>>, ManyError> {
+        let mut result: BTreeMap<Address, BTreeMap<Symbol, TokenAmount>> = BTreeMap::new();
+        for item in LedgerIterator::all_balances(&self.persistent_store, SortOrder::Indeterminate) {
+            let (k, v) = item.map_err(error::StorageError::from)?;
+            let key = std::str::from_utf8(&k[BALANCES_ROOT.len()..])
+                .map_err(ManyError::deserialization_error)?;
+            let (id, symbol) = key.split_once('/').ok_or_else(|| {
+                ManyError::deserialization_error(format!("Invalid balance key {key:?}"))
+            })?;
+            result
+                .entry(Address::from_str(id)?)
+                .or_default()
+                .insert(Symbol::from_str(symbol)?, TokenAmount::from(v));
+        }
+        Ok(result)
+    }
+
     pub fn get_multiple_balances(
         &self,
         identity: &Address,
@@ -117,35 +144,132 @@ impl LedgerStorage {
         })
     }
 
+    /// The balances of an identity after the block at `height`, at most
+    /// `MAX_BALANCE_LOOKBACK` blocks and `MAX_BALANCE_EVENTS` events ago. They
+    /// are computed from the current balances by reverting the transfers
+    /// logged since then, so balance changes made without an event, i.e. by
+    /// migrations or hotfixes, are not taken into account.
+    pub fn get_multiple_balances_at(
+        &self,
+        identity: &Address,
+        symbols: &BTreeSet<Symbol>,
+        height: u64,
+    ) -> Result<BTreeMap<Symbol, TokenAmount>, ManyError> {
+        self.balances_at(identity, symbols, height, MAX_BALANCE_EVENTS)
+    }
+
+    fn balances_at(
+        &self,
+        identity: &Address,
+        symbols: &BTreeSet<Symbol>,
+        height: u64,
+        max_events: usize,
+    ) -> Result<BTreeMap<Symbol, TokenAmount>, ManyError> {
+        let current = self.get_height()?;
+        if height > current {
+            return Err(error::height_not_reached(height, current));
+        }
+        let oldest = current.saturating_sub(MAX_BALANCE_LOOKBACK);
+        if height < oldest {
+            return Err(error::height_too_old(height, oldest));
+        }
+
+        let (balances, _) = self.get_multiple_balances(identity, symbols)?;
+        let mut replay = Replay::new(&BTreeMap::from([(*identity, balances)]));
+        for (i, item) in self
+            .iter_events(
+                EventId::range_of_heights(height.saturating_add(1)..),
+                SortOrder::Descending,
+            )
+            .enumerate()
+        {
+            if i == max_events {
+                return Err(error::too_many_events_since_height(height, max_events));
+            }
+            let (_, value) = item.map_err(error::storage_iteration_failed)?;
+            let event: EventLog =
+                minicbor::decode(&value).map_err(ManyError::deserialization_error)?;
+            replay.revert(&event);
+        }
+
+        replay
+            .balances_of(identity)
+            .filter(|(symbol, _)| symbols.is_empty() || symbols.contains(*symbol))
+            .map(|(symbol, amount)| {
+                amount
+                    .to_biguint()
+                    .map(|amount| (*symbol, TokenAmount::from(amount)))
+                    .ok_or_else(|| error::inconsistent_balance_history(identity, symbol))
+            })
+            .collect()
+    }
+
+    pub fn prove(
+        &self,
+        keys: impl IntoIterator<Item = Vec<u8>>,
+    ) -> Result<Vec<ProofOperation>, ManyError> {
+        many_proof::merk_prove!(
+            self.persistent_store,
+            keys.into_iter()
+                .map(QueryItem::Key)
+                .collect::<Vec<_>>()
+                .into()
+        )
+    }
+
     pub fn prove_state(
         &self,
         context: impl AsRef<Context>,
         keys: impl IntoIterator<Item = Vec<u8>>,
     ) -> Result<(), ManyError> {
-        context.as_ref().prove(|| {
-            self.persistent_store
-                .prove(
-                    keys.into_iter()
-                        .map(QueryItem::Key)
-                        .collect::<Vec<_>>()
-                        .into(),
+        context.as_ref().prove(|| self.prove(keys))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use many_identity::testing::identity;
+
+    #[test]
+    fn balances_at_event_budget() {
+        let path = tempfile::tempdir().unwrap().into_path();
+        let symbol = identity(1000);
+        let mut storage = LedgerStorage::new(path, true)
+            .unwrap()
+            .build()
+            .unwrap()
+            .with_balances(
+                &identity(0),
+                &BTreeMap::from([(symbol, "MFX".to_string())]),
+                &BTreeMap::from([(identity(1), BTreeMap::from([(symbol, 100u16.into())]))]),
+            )
+            .unwrap();
+        storage.commit().unwrap();
+        for _ in 0..3 {
+            storage
+                .send(
+                    &identity(1),
+                    &identity(2),
+                    &symbol,
+                    10u16.into(),
+                    None,
+                    None,
                 )
-                .and_then(|proof| {
-                    Decoder::new(proof.as_slice())
-                        .map(|fallible_operation| {
-                            fallible_operation.map(|operation| match operation {
-                                Child => ProofOperation::Child,
-                                Parent => ProofOperation::Parent,
-                                Push(Hash(hash)) => ProofOperation::NodeHash(hash.to_vec()),
-                                Push(KV(key, value)) => {
-                                    ProofOperation::KeyValuePair(key.into(), value.into())
-                                }
-                                Push(KVHash(hash)) => ProofOperation::KeyValueHash(hash.to_vec()),
-                            })
-                        })
-                        .collect::<Result<Vec<_>, _>>()
-                })
-                .map_err(|error| ManyError::unknown(error.to_string()))
-        })
+                .unwrap();
+        }
+        storage.commit().unwrap();
+
+        let symbols = BTreeSet::from([symbol]);
+        let balances = storage.balances_at(&identity(1), &symbols, 0, 3).unwrap();
+        assert_eq!(balances.get(&symbol), Some(&100u16.into()));
+
+        let err = storage
+            .balances_at(&identity(1), &symbols, 0, 2)
+            .unwrap_err();
+        assert_eq!(
+            err.code(),
+            error::too_many_events_since_height("", "").code()
+        );
     }
 }
@@ -3,6 +3,7 @@ use crate::storage::{key_for_account_balance, LedgerStorage};
 use many_error::ManyError;
 use many_identity::Address;
 use many_modules::events::EventInfo;
+use many_modules::ledger::MAX_REFERENCE_LENGTH;
 use many_types::ledger::{Symbol, TokenAmount};
 use many_types::Memo;
 use merk::{BatchEntry, Op};
@@ -39,6 +40,7 @@ impl LedgerStorage {
         symbol: &Symbol,
         amount: TokenAmount,
         memo: Option<Memo>,
+        reference: Option<String>,
     ) -> Result<impl IntoIterator<Item = Vec<u8>>, ManyError> {
         if from == to {
             return Err(error::destination_is_source());
@@ -52,6 +54,13 @@ impl LedgerStorage {
             return Err(error::anonymous_cannot_hold_funds());
         }
 
+        if reference
+            .as_ref()
+            .map_or(false, |r| r.len() > MAX_REFERENCE_LENGTH)
+        {
+            return Err(error::reference_too_long(MAX_REFERENCE_LENGTH));
+        }
+
         let mut amount_from = self.get_balance(from, symbol)?;
         if amount > amount_from {
             return Err(error::insufficient_funds());
@@ -78,19 +87,23 @@ impl LedgerStorage {
             ],
         };
 
-        self.update_account_count(from, to, amount.clone(), symbol)?;
+        let event = EventInfo::Send {
+            from: *from,
+            to: *to,
+            symbol: *symbol,
+            amount: amount.clone(),
+            memo,
+            reference,
+        };
+        self.run_transfer_hooks(symbol, &event)?;
+
+        self.update_account_count(from, to, amount, symbol)?;
 
         self.persistent_store
             .apply(&batch)
             .map_err(error::storage_apply_failed)?;
 
-        self.log_event(EventInfo::Send {
-            from: *from,
-            to: *to,
-            symbol: *symbol,
-            amount,
-            memo,
-        })?;
+        self.log_event(event)?;
 
         self.maybe_commit().map(|_| vec![key_from, key_to])
     }
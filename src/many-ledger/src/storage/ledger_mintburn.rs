@@ -2,8 +2,10 @@ use crate::error;
 use crate::storage::ledger_tokens::key_for_symbol;
 use crate::storage::{key_for_account_balance, LedgerStorage};
 use many_error::ManyError;
+use many_modules::events::EventInfo;
 use many_modules::ledger::TokenInfoArgs;
 use many_types::ledger::{LedgerTokensAddressMap, Symbol, TokenAmount, TokenInfoSupply};
+use many_types::Memo;
 use merk::{BatchEntry, Op};
 use std::collections::BTreeSet;
 
@@ -22,7 +24,15 @@ impl LedgerStorage {
         &mut self,
         symbol: Symbol,
         distribution: &LedgerTokensAddressMap,
+        memo: Option<Memo>,
     ) -> Result<impl IntoIterator<Item = Vec<u8>>, ManyError> {
+        let event = EventInfo::TokenMint {
+            symbol,
+            distribution: distribution.clone(),
+            memo,
+        };
+        self.run_transfer_hooks(&symbol, &event)?;
+
         let mut batch: Vec<BatchEntry> = Vec::new();
         let mut circulating = TokenAmount::zero();
         let current_supply = self.get_token_supply(&symbol)?;
@@ -61,7 +71,7 @@ impl LedgerStorage {
             })?
             .info;
         info.supply.circulating += &circulating;
-        info.supply.total += circulating;
+        info.supply.total += &circulating;
         let symbol_key = key_for_symbol(&symbol);
         keys.push(symbol_key.clone().into_bytes());
         batch.push((
@@ -77,6 +87,9 @@ impl LedgerStorage {
             .apply(batch.as_slice())
             .map_err(error::storage_apply_failed)?;
 
+        keys.extend(self.record_supply_change(&symbol, &circulating, &TokenAmount::zero())?);
+        self.log_event(event)?;
+
         self.maybe_commit().map(|_| keys)
     }
 
@@ -84,7 +97,15 @@ impl LedgerStorage {
         &mut self,
         symbol: Symbol,
         distribution: &LedgerTokensAddressMap,
+        memo: Option<Memo>,
     ) -> Result<impl IntoIterator<Item = Vec<u8>>, ManyError> {
+        let event = EventInfo::TokenBurn {
+            symbol,
+            distribution: distribution.clone(),
+            memo,
+        };
+        self.run_transfer_hooks(&symbol, &event)?;
+
         let mut batch: Vec<BatchEntry> = Vec::new();
         let mut circulating = TokenAmount::zero();
         let mut keys: Vec<Vec<u8>> = Vec::new();
@@ -120,7 +141,7 @@ impl LedgerStorage {
             })?
             .info;
         info.supply.circulating -= &circulating;
-        info.supply.total -= circulating;
+        info.supply.total -= &circulating;
 
         let symbol_key = key_for_symbol(&symbol);
         keys.push(symbol_key.clone().into_bytes());
@@ -138,6 +159,9 @@ impl LedgerStorage {
             .apply(batch.as_slice())
             .map_err(error::storage_apply_failed)?;
 
+        keys.extend(self.record_supply_change(&symbol, &TokenAmount::zero(), &circulating)?);
+        self.log_event(event)?;
+
         self.maybe_commit().map(|_| keys)
     }
 }
@@ -1,4 +1,5 @@
 use crate::error;
+use crate::migration::token_history::TOKEN_HISTORY_MIGRATION;
 use crate::migration::tokens::TOKEN_MIGRATION;
 use crate::storage::iterator::LedgerIterator;
 use crate::storage::{
@@ -12,7 +13,8 @@ use many_modules::events::EventInfo;
 use many_modules::ledger::extended_info::{ExtendedInfoKey, TokenExtendedInfo};
 use many_modules::ledger::{
     TokenAddExtendedInfoArgs, TokenAddExtendedInfoReturns, TokenCreateArgs, TokenCreateReturns,
-    TokenInfoArgs, TokenInfoReturns, TokenRemoveExtendedInfoArgs, TokenRemoveExtendedInfoReturns,
+    TokenHistoryArgs, TokenHistoryEntry, TokenHistoryReturns, TokenInfoArgs, TokenInfoReturns,
+    TokenListArgs, TokenListReturns, TokenRemoveExtendedInfoArgs, TokenRemoveExtendedInfoReturns,
     TokenUpdateArgs, TokenUpdateReturns,
 };
 use many_types::ledger::{Symbol, TokenAmount, TokenInfo, TokenInfoSummary, TokenInfoSupply};
@@ -24,6 +26,8 @@ use std::str::FromStr;
 pub const SYMBOLS_ROOT_DASH: &str = const_format::concatcp!(SYMBOLS_ROOT, "/");
 pub const TOKEN_IDENTITY_ROOT: &str = "/config/token_identity";
 
+const MAXIMUM_TOKEN_COUNT: usize = 100;
+
 pub fn key_for_symbol(symbol: &Symbol) -> String {
     format!("/config/symbols/{symbol}")
 }
@@ -32,6 +36,10 @@ pub fn key_for_ext_info(symbol: &Symbol) -> Vec<u8> {
     format!("/config/ext_info/{symbol}").into_bytes()
 }
 
+pub fn key_for_token_history(symbol: &Symbol) -> Vec<u8> {
+    format!("/config/token_history/{symbol}").into_bytes()
+}
+
 pub struct SymbolMeta {
     pub name: String,
     pub decimals: u64,
@@ -183,7 +191,7 @@ impl LedgerStorage {
         let mut symbols = BTreeSet::new();
         let it = LedgerIterator::all_symbols(&self.persistent_store, SortOrder::Indeterminate);
         for item in it {
-            let (k, _) = item.map_err(ManyError::unknown)?;
+            let (k, _) = item.map_err(error::StorageError::from)?;
             symbols.insert(Symbol::from_str(
                 std::str::from_utf8(&k.as_ref()[SYMBOLS_ROOT_DASH.len()..])
                     .map_err(ManyError::deserialization_error)?, // TODO: We could safely use from_utf8_unchecked() if performance is an issue
@@ -197,7 +205,7 @@ impl LedgerStorage {
         if self.migrations.is_active(&TOKEN_MIGRATION) {
             let it = LedgerIterator::all_symbols(&self.persistent_store, SortOrder::Indeterminate);
             for item in it {
-                let (k, v) = item.map_err(ManyError::unknown)?;
+                let (k, v) = item.map_err(error::StorageError::from)?;
                 let info: TokenInfo =
                     minicbor::decode(&v).map_err(ManyError::deserialization_error)?;
                 info_summary.insert(
@@ -268,6 +276,9 @@ impl LedgerStorage {
             TokenAmount::zero()
         };
 
+        if !total_supply.is_zero() {
+            keys.extend(self.record_supply_change(&symbol, &total_supply, &TokenAmount::zero())?);
+        }
         let supply = TokenInfoSupply {
             total: total_supply.clone(),
             circulating: total_supply,
@@ -299,6 +310,10 @@ impl LedgerStorage {
             Op::Put(minicbor::to_vec(&ext_info).map_err(ManyError::serialization_error)?),
         ));
 
+        let (indices_key, indices_op) = self.add_total_supply_index(symbol)?;
+        keys.push(indices_key.clone());
+        batch.push((indices_key, indices_op));
+
         self.log_event(EventInfo::TokenCreate {
             summary,
             symbol,
@@ -331,7 +346,7 @@ impl LedgerStorage {
         let token_info_enc = self
             .persistent_store
             .get(key_for_symbol(&symbol).as_bytes())
-            .map_err(ManyError::unknown)?
+            .map_err(error::storage_get_failed)?
             .ok_or_else(|| error::token_info_not_found(symbol))?;
 
         let ext_info_enc = self
@@ -359,6 +374,37 @@ impl LedgerStorage {
         })
     }
 
+    /// List the token info of all symbols, paginated using the `after` symbol.
+    /// Also returns the keys of the listed symbols so they can be proven.
+    pub fn list_tokens(
+        &self,
+        args: TokenListArgs,
+    ) -> Result<(TokenListReturns, Vec<Vec<u8>>), ManyError> {
+        let TokenListArgs {
+            count,
+            order,
+            after,
+        } = args;
+
+        let count = count.map_or(MAXIMUM_TOKEN_COUNT, |c| {
+            std::cmp::min(c as usize, MAXIMUM_TOKEN_COUNT)
+        });
+        let order = order.unwrap_or(SortOrder::Indeterminate);
+        let after = after.map(|symbol| key_for_symbol(&symbol).into_bytes());
+
+        let mut tokens = Vec::new();
+        let mut keys = Vec::new();
+        let it = LedgerIterator::symbols_after(&self.persistent_store, after.as_deref(), order);
+        for item in it.take(count) {
+            let (k, v) = item.map_err(error::StorageError::from)?;
+            let info: TokenInfo = minicbor::decode(&v).map_err(ManyError::deserialization_error)?;
+            tokens.push(info);
+            keys.push(k.to_vec());
+        }
+
+        Ok((TokenListReturns { tokens }, keys))
+    }
+
     pub fn update_token(
         &mut self,
         _sender: &Address,
@@ -379,19 +425,25 @@ impl LedgerStorage {
         if let Some(enc) = self
             .persistent_store
             .get(symbol_key.as_bytes())
-            .map_err(ManyError::unknown)?
+            .map_err(error::storage_get_failed)?
         {
             keys.push(symbol_key.clone().into());
             let mut info: TokenInfo = minicbor::decode(&enc).unwrap();
+            let previous = info.summary.clone();
+            let with_history = self.migrations.is_active(&TOKEN_HISTORY_MIGRATION);
 
             if let Some(name) = name.as_ref() {
                 info.summary.name = name.clone();
             }
             if let Some(ticker) = ticker.as_ref() {
-                if self.get_symbols_and_tickers()?.values().contains(ticker) {
-                    return Err(error::ticker_exists(ticker));
-                };
-                keys.extend(self.update_symbols(symbol, ticker.clone())?);
+                // Setting a token's ticker to its current value is a no-op once
+                // the history is kept.
+                if !(with_history && ticker == &previous.ticker) {
+                    if self.get_symbols_and_tickers()?.values().contains(ticker) {
+                        return Err(error::ticker_exists(ticker));
+                    };
+                    keys.extend(self.update_symbols(symbol, ticker.clone())?);
+                }
                 info.summary.ticker = ticker.clone();
             }
             if let Some(decimals) = decimals {
@@ -412,6 +464,12 @@ impl LedgerStorage {
                 )])
                 .map_err(error::storage_apply_failed)?;
 
+            let renamed =
+                info.summary.name != previous.name || info.summary.ticker != previous.ticker;
+            if with_history && renamed {
+                keys.push(self.push_token_history(&symbol, &previous)?);
+            }
+
             self.log_event(EventInfo::TokenUpdate {
                 symbol,
                 name,
@@ -421,6 +479,16 @@ impl LedgerStorage {
                 memo,
             })?;
 
+            if with_history && renamed {
+                self.log_event(EventInfo::TokenRename {
+                    symbol,
+                    previous_name: previous.name,
+                    previous_ticker: previous.ticker,
+                    name: info.summary.name,
+                    ticker: info.summary.ticker,
+                })?;
+            }
+
             self.maybe_commit().map(|_| (TokenUpdateReturns {}, keys))
         } else {
             Err(ManyError::unknown(format!(
@@ -429,6 +497,59 @@ impl LedgerStorage {
         }
     }
 
+    fn get_token_history(&self, symbol: &Symbol) -> Result<Vec<TokenHistoryEntry>, ManyError> {
+        self.persistent_store
+            .get(&key_for_token_history(symbol))
+            .map_err(error::storage_get_failed)?
+            .map_or(Ok(vec![]), |enc| {
+                minicbor::decode(&enc).map_err(ManyError::deserialization_error)
+            })
+    }
+
+    /// Append the previous name and ticker of a token to its history.
+    fn push_token_history(
+        &mut self,
+        symbol: &Symbol,
+        previous: &TokenInfoSummary,
+    ) -> Result<Vec<u8>, ManyError> {
+        let mut history = self.get_token_history(symbol)?;
+        history.push(TokenHistoryEntry {
+            name: previous.name.clone(),
+            ticker: previous.ticker.clone(),
+            until: self.now(),
+        });
+
+        let key = key_for_token_history(symbol);
+        self.persistent_store
+            .apply(&[(
+                key.clone(),
+                Op::Put(minicbor::to_vec(&history).map_err(ManyError::serialization_error)?),
+            )])
+            .map_err(error::storage_apply_failed)?;
+        Ok(key)
+    }
+
+    pub fn history_token(
+        &self,
+        args: TokenHistoryArgs,
+    ) -> Result<(TokenHistoryReturns, Vec<Vec<u8>>), ManyError> {
+        let TokenHistoryArgs { symbol } = args;
+        if self
+            .persistent_store
+            .get(key_for_symbol(&symbol).as_bytes())
+            .map_err(error::storage_get_failed)?
+            .is_none()
+        {
+            return Err(error::token_info_not_found(symbol));
+        }
+
+        let history = self.get_token_history(&symbol)?;
+        Ok((
+            TokenHistoryReturns { history },
+            vec![key_for_token_history(&symbol)],
+        ))
+    }
+
     pub fn add_extended_info(
         &mut self,
         args: TokenAddExtendedInfoArgs,
@@ -0,0 +1,82 @@
+use crate::error;
+use crate::storage::LedgerStorage;
+use many_error::ManyError;
+use many_modules::admin::StorageStats;
+use merk::rocksdb::{self, Options, DB};
+use std::path::Path;
+
+/// The total size of the files in a directory and its subdirectories.
+fn dir_size(path: &Path) -> std::io::Result<u64> {
+    let mut size = 0;
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        size += if metadata.is_dir() {
+            dir_size(&entry.path())?
+        } else {
+            metadata.len()
+        };
+    }
+    Ok(size)
+}
+
+/// Sum an integer property of RocksDB over all the column families.
+fn property_sum(db: &DB, column_families: &[String], name: &str) -> Result<u64, rocksdb::Error> {
+    let mut sum = 0;
+    for cf in column_families.iter().filter_map(|name| db.cf_handle(name)) {
+        sum += db.property_int_value_cf(cf, name)?.unwrap_or_default();
+    }
+    Ok(sum)
+}
+
+fn stats_of(
+    db: &DB,
+    column_families: &[String],
+    persistent_path: &Path,
+) -> Result<StorageStats, ManyError> {
+    // Merk stores the nodes of the tree in the default column family, and its
+    // metadata in the others.
+    let node_count = db
+        .property_int_value("rocksdb.estimate-num-keys")
+        .map_err(error::storage_get_failed)?
+        .unwrap_or_default();
+    let sst_size = property_sum(db, column_families, "rocksdb.total-sst-files-size")
+        .map_err(error::storage_get_failed)?;
+    let live_size = property_sum(db, column_families, "rocksdb.estimate-live-data-size")
+        .map_err(error::storage_get_failed)?;
+
+    Ok(StorageStats {
+        size_on_disk: dir_size(persistent_path).map_err(error::storage_get_failed)?,
+        node_count,
+        stale_size: sst_size.saturating_sub(live_size),
+    })
+}
+
+impl LedgerStorage {
+    /// Read the statistics of a persistent store. The store is opened
+    /// read-only, so it can be used by a running server at the same time.
+    pub fn stats<P: AsRef<Path>>(persistent_path: P) -> Result<StorageStats, ManyError> {
+        let path = persistent_path.as_ref();
+        let options = Options::default();
+        let column_families = DB::list_cf(&options, path).map_err(error::storage_open_failed)?;
+        let db = DB::open_cf_for_read_only(&options, path, &column_families, false)
+            .map_err(error::storage_open_failed)?;
+        stats_of(&db, &column_families, path)
+    }
+
+    /// Compact a persistent store to reclaim the space used by stale versions
+    /// of its nodes, and return its statistics after the compaction. The store
+    /// cannot be used by a running server at the same time.
+    pub fn compact<P: AsRef<Path>>(persistent_path: P) -> Result<StorageStats, ManyError> {
+        let path = persistent_path.as_ref();
+        let options = Options::default();
+        let column_families = DB::list_cf(&options, path).map_err(error::storage_open_failed)?;
+        let db =
+            DB::open_cf(&options, path, &column_families).map_err(error::storage_open_failed)?;
+
+        for cf in column_families.iter().filter_map(|name| db.cf_handle(name)) {
+            db.compact_range_cf(cf, None::<&[u8]>, None::<&[u8]>);
+        }
+        stats_of(&db, &column_families, path)
+    }
+}
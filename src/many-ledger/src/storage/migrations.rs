@@ -1,7 +1,10 @@
+use crate::error;
 use crate::migration::{LedgerMigrations, MIGRATIONS};
+use crate::storage::iterator::LedgerIterator;
 use crate::storage::LedgerStorage;
 use many_error::ManyError;
-use many_migration::{MigrationConfig, MigrationSet};
+use many_migration::{FnMultiKey, KeyValueBatch, KeyValueVisitor, MigrationConfig, MigrationSet};
+use merk::Op;
 
 impl LedgerStorage {
     pub fn with_migrations(
@@ -14,8 +17,71 @@ impl LedgerStorage {
             .map_or_else(MigrationSet::empty, |config| {
                 LedgerMigrations::load(&MIGRATIONS, config, 0)
             })
-            .map_err(ManyError::unknown)?; // TODO: Custom error
+            .map_err(error::unable_to_load_migrations)?;
 
         Ok(self)
     }
+
+    /// Run the key/value hotfixes of a block height on the persistent store,
+    /// then its multi-key hotfixes. The changes of a hotfix are applied before
+    /// the next one reads the store.
+    pub(crate) fn apply_key_value_hotfixes(&mut self, block_height: u64) -> Result<(), ManyError> {
+        let visitors: Vec<&dyn KeyValueVisitor> =
+            self.migrations.key_value_hotfixes(block_height).collect();
+
+        for visitor in visitors {
+            let pairs = LedgerIterator::all_with_prefix(&self.persistent_store, visitor.prefix())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(error::storage_get_failed)?;
+
+            let batch: Vec<(Vec<u8>, Op)> = visitor
+                .rewrite(pairs)
+                .into_iter()
+                .map(|(k, v)| (k, Op::Put(v)))
+                .collect();
+            if !batch.is_empty() {
+                self.persistent_store
+                    .apply(&batch)
+                    .map_err(error::storage_apply_failed)?;
+            }
+        }
+
+        let hotfixes: Vec<(String, FnMultiKey)> = self
+            .migrations
+            .multi_key_hotfixes(block_height)
+            .map(|(name, hotfix_fn)| (name.to_string(), hotfix_fn))
+            .collect();
+
+        for (name, hotfix_fn) in hotfixes {
+            let store = &self.persistent_store;
+            let changes =
+                KeyValueBatch::new(|key: &[u8]| store.get(key).map_err(|e| e.to_string()))
+                    .run(hotfix_fn)
+                    .map_err(|e| error::hotfix_failed(name, e))?;
+
+            let batch: Vec<(Vec<u8>, Op)> = changes
+                .into_iter()
+                .map(|(k, v)| (k, v.map_or(Op::Delete, Op::Put)))
+                .collect();
+            if !batch.is_empty() {
+                self.persistent_store
+                    .apply(&batch)
+                    .map_err(error::storage_apply_failed)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Run the migrations of the blocks following the current height, up to
+    /// and including `block_height`, the same way `commit` would. The height
+    /// of the store is not changed.
+    pub fn simulate_migrations(&mut self, block_height: u64) -> Result<(), ManyError> {
+        for height in self.get_height()? + 1..=block_height {
+            self.migrations
+                .update_at_height(&mut self.persistent_store, height)?;
+            self.apply_key_value_hotfixes(height)?;
+            self.commit_storage()?;
+        }
+        Ok(())
+    }
 }
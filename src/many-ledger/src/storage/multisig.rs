@@ -6,15 +6,19 @@ use crate::storage::event::EVENT_ID_KEY_SIZE_IN_BYTES;
 use crate::storage::LedgerStorage;
 use many_error::ManyError;
 use many_identity::Address;
+use many_modules::account::features::cosign::{self, CosignAccountFeature, CosignedTransaction};
+use many_modules::account::features::multisig::MULTISIG_TRANSACTION_MAX_DEPTH;
 use many_modules::account::features::FeatureInfo;
-use many_modules::{account, events, EmptyReturn};
+use many_modules::{account, events, store, EmptyReturn};
 use many_protocol::ResponseMessage;
 use many_types::{SortOrder, Timestamp};
 use merk::Op;
-use std::collections::BTreeMap;
+use sha3::{Digest, Sha3_256};
+use std::collections::{BTreeMap, BTreeSet};
 use tracing::debug;
 
 pub(crate) const MULTISIG_TRANSACTIONS_ROOT: &[u8] = b"/multisig/";
+pub(crate) const COSIGNED_TRANSACTIONS_ROOT: &[u8] = b"/multisig_cosigned/";
 
 /// Returns the storage key for a multisig pending transaction.
 pub(super) fn key_for_multisig_transaction(token: &[u8]) -> Vec<u8> {
@@ -32,19 +36,50 @@ pub(super) fn key_for_multisig_transaction(token: &[u8]) -> Vec<u8> {
         .to_vec()
 }
 
+/// Returns the storage key marking a co-signed transaction as executed.
+fn key_for_cosigned_transaction(transaction: &[u8]) -> Vec<u8> {
+    [
+        COSIGNED_TRANSACTIONS_ROOT,
+        Sha3_256::digest(transaction).as_slice(),
+    ]
+    .concat()
+}
+
+/// Execute a transaction on behalf of an account. Executing a transaction can
+/// execute other pending transactions, so the depth of these executions is
+/// bounded.
 fn _execute_multisig_tx(
     ledger: &mut LedgerStorage,
-    _tx_id: &[u8],
-    storage: &MultisigTransactionStorage,
+    sender: &Address,
+    transaction: &events::AccountMultisigTransaction,
+) -> Result<Vec<u8>, ManyError> {
+    if ledger.multisig_depth >= MULTISIG_TRANSACTION_MAX_DEPTH {
+        return Err(
+            account::features::multisig::errors::transaction_nested_too_deeply(
+                MULTISIG_TRANSACTION_MAX_DEPTH.to_string(),
+            ),
+        );
+    }
+
+    ledger.multisig_depth += 1;
+    let result = _execute_multisig_tx_inner(ledger, sender, transaction);
+    ledger.multisig_depth -= 1;
+    result
+}
+
+fn _execute_multisig_tx_inner(
+    ledger: &mut LedgerStorage,
+    sender: &Address,
+    transaction: &events::AccountMultisigTransaction,
 ) -> Result<Vec<u8>, ManyError> {
-    let sender = &storage.account;
-    match &storage.info.transaction {
+    match transaction {
         events::AccountMultisigTransaction::Send(many_modules::ledger::SendArgs {
             from,
             to,
             symbol,
             amount,
             memo,
+            reference,
         }) => {
             // Use the `from` field to resolve the account sending the funds
             let from = from.ok_or_else(ManyError::invalid_from_identity)?;
@@ -56,7 +91,14 @@ fn _execute_multisig_tx(
                 [account::Role::CanLedgerTransact, account::Role::Owner],
             )?;
 
-            ledger.send(&from, to, symbol, amount.clone(), memo.clone())?;
+            ledger.send(
+                &from,
+                to,
+                symbol,
+                amount.clone(),
+                memo.clone(),
+                reference.clone(),
+            )?;
             minicbor::to_vec(EmptyReturn)
         }
 
@@ -84,6 +126,15 @@ fn _execute_multisig_tx(
             minicbor::to_vec(EmptyReturn)
         }
 
+        events::AccountMultisigTransaction::AccountSetMetadata(args) => {
+            let (account, _) = ledger.get_account(&args.account)?;
+
+            account.needs_role(sender, [account::Role::Owner])?;
+            account::validate_metadata(&args.metadata)?;
+            ledger.set_metadata(account, args.clone())?;
+            minicbor::to_vec(EmptyReturn)
+        }
+
         events::AccountMultisigTransaction::AccountAddRoles(args) => {
             let (account, _) = ledger.get_account(&args.account)?;
             account.needs_role(sender, [account::Role::Owner])?;
@@ -183,7 +234,7 @@ impl LedgerStorage {
         let mut batch = vec![];
 
         for item in it {
-            let (k, v) = item.map_err(ManyError::unknown)?;
+            let (k, v) = item.map_err(error::StorageError::from)?;
 
             let mut storage: MultisigTransactionStorage =
                 minicbor::decode(v.as_slice()).map_err(ManyError::deserialization_error)?;
@@ -197,7 +248,10 @@ impl LedgerStorage {
                         batch.push((k.to_vec(), Op::Put(v)));
                     }
                 }
-            } else if let Ok(d) = now.as_system_time()?.duration_since(storage.creation) {
+            } else if let Some(d) = Timestamp::from_system_time(storage.creation)
+                .ok()
+                .and_then(|creation| now.duration_since(creation))
+            {
                 // Since the DB is ordered by event ID (keys), at this point we don't need
                 // to continue since we know that the rest is all timed out anyway.
                 if d.as_secs() > MULTISIG_MAXIMUM_TIMEOUT_IN_SECS {
@@ -279,6 +333,18 @@ impl LedgerStorage {
         sender: &Address,
         arg: account::features::multisig::SubmitTransactionArgs,
     ) -> Result<Vec<u8>, ManyError> {
+        if arg.transaction.depth() > MULTISIG_TRANSACTION_MAX_DEPTH {
+            return Err(
+                account::features::multisig::errors::transaction_nested_too_deeply(
+                    MULTISIG_TRANSACTION_MAX_DEPTH.to_string(),
+                ),
+            );
+        }
+
+        if let Some(attachment) = &arg.attachment {
+            store::validate_hash(&attachment.hash)?;
+        }
+
         let event_id = self.new_event_id();
         let account_id = arg.account;
 
@@ -327,11 +393,9 @@ impl LedgerStorage {
             account::features::multisig::ApproverInfo { approved: true },
         )]);
 
-        let timeout = Timestamp::from_system_time(
-            time.as_system_time()?
-                .checked_add(std::time::Duration::from_secs(timeout_in_secs))
-                .ok_or_else(|| ManyError::unknown("Invalid time.".to_string()))?,
-        )?;
+        let timeout = time
+            .checked_add(std::time::Duration::from_secs(timeout_in_secs))
+            .ok_or_else(|| ManyError::unknown("Invalid time.".to_string()))?;
 
         // If the migration hasn't been applied yet, use the old fields and skip
         // the new memo field. If it has, ignore the old fields and only use the
@@ -355,8 +419,9 @@ impl LedgerStorage {
                 timeout,
                 data_: data_.clone(),
                 state: account::features::multisig::MultisigTransactionState::Pending,
+                attachment: arg.attachment.clone(),
             },
-            creation: self.now().as_system_time()?,
+            creation: time.as_system_time()?,
             disabled: false,
         };
 
@@ -372,6 +437,7 @@ impl LedgerStorage {
             execute_automatically,
             data_,
             memo,
+            attachment: arg.attachment,
         })?;
 
         Ok(event_id.into())
@@ -535,13 +601,35 @@ impl LedgerStorage {
         self.maybe_commit()
     }
 
+    /// Attachments without a URI must be in the object store when the
+    /// transaction is executed, so approvers could read them. The content of
+    /// external attachments is checked by clients.
+    fn check_multisig_attachment(
+        &self,
+        attachment: Option<&account::features::multisig::Attachment>,
+    ) -> Result<(), ManyError> {
+        match attachment {
+            Some(attachment) if attachment.uri.is_none() => {
+                if self.get_object_info(&attachment.hash)?.is_none() {
+                    return Err(account::features::multisig::errors::attachment_not_found(
+                        hex::encode(&attachment.hash),
+                    ));
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
     fn execute_multisig_transaction_internal(
         &mut self,
         tx_id: &[u8],
         storage: &MultisigTransactionStorage,
         automatic: bool,
     ) -> Result<ResponseMessage, ManyError> {
-        let result = _execute_multisig_tx(self, tx_id, storage);
+        let result = self
+            .check_multisig_attachment(storage.info.attachment.as_ref())
+            .and_then(|_| _execute_multisig_tx(self, &storage.account, &storage.info.transaction));
 
         self.disable_multisig_transaction(
             tx_id,
@@ -573,4 +661,87 @@ impl LedgerStorage {
 
         Ok(response)
     }
+
+    /// Execute a co-signed transaction. The signatures must already be
+    /// verified; `signers` are the addresses that signed `transaction`.
+    pub fn execute_cosigned_multisig(
+        &mut self,
+        submitter: &Address,
+        transaction: &[u8],
+        mut signers: BTreeSet<Address>,
+    ) -> Result<ResponseMessage, ManyError> {
+        let cosigned: CosignedTransaction =
+            minicbor::decode(transaction).map_err(ManyError::deserialization_error)?;
+
+        let now = self.now();
+        let max_timeout = now
+            .checked_add(std::time::Duration::from_secs(
+                MULTISIG_MAXIMUM_TIMEOUT_IN_SECS,
+            ))
+            .ok_or_else(|| ManyError::unknown("Invalid time.".to_string()))?;
+        if cosigned.timeout <= now || cosigned.timeout > max_timeout {
+            return Err(cosign::errors::cosigned_transaction_expired());
+        }
+
+        let key = key_for_cosigned_transaction(transaction);
+        if self
+            .persistent_store
+            .get(&key)
+            .map_err(error::storage_get_failed)?
+            .is_some()
+        {
+            return Err(cosign::errors::cosigned_transaction_already_executed());
+        }
+
+        let (account, _) = self.get_account(&cosigned.account)?;
+        account.needs_role(
+            submitter,
+            [account::Role::CanMultisigSubmit, account::Role::Owner],
+        )?;
+        let feature = account.features.get::<CosignAccountFeature>()?;
+
+        for signer in &signers {
+            if !account.has_role(signer, account::Role::CanMultisigApprove)
+                && !account.has_role(signer, account::Role::CanMultisigSubmit)
+                && !account.has_role(signer, account::Role::Owner)
+            {
+                return Err(account::features::multisig::errors::user_cannot_approve_transaction());
+            }
+        }
+        signers.insert(*submitter);
+
+        let threshold = feature.arg.threshold.unwrap_or(MULTISIG_DEFAULT_THRESHOLD);
+        if (signers.len() as u64) < threshold {
+            return Err(cosign::errors::not_enough_cosignatures(
+                signers.len().to_string(),
+                threshold.to_string(),
+            ));
+        }
+
+        // Remember the transaction until it times out so it cannot be replayed.
+        self.put_expiring(
+            key,
+            cosigned.timeout.secs().to_be_bytes().to_vec(),
+            cosigned.timeout.secs(),
+        )?;
+
+        let result = _execute_multisig_tx(self, &cosigned.account, &cosigned.transaction);
+        let response = ResponseMessage {
+            from: cosigned.account,
+            to: None,
+            data: result,
+            timestamp: Some(now),
+            ..Default::default()
+        };
+
+        self.log_event(events::EventInfo::AccountMultisigCosignedExecute {
+            account: cosigned.account,
+            submitter: *submitter,
+            signers,
+            transaction: cosigned.transaction,
+            response: response.clone(),
+        })?;
+        self.maybe_commit()?;
+        Ok(response)
+    }
 }
@@ -0,0 +1,148 @@
+use crate::error;
+use crate::storage::LedgerStorage;
+use many_error::ManyError;
+use many_identity::Address;
+use many_modules::events::EventInfo;
+use many_modules::names::{self, NameFee};
+use many_types::Timestamp;
+use merk::Op;
+use minicbor::{Decode, Encode};
+use std::time::Duration;
+
+pub const NAMES_ROOT: &str = "/names/";
+pub const NAMES_CONFIG_KEY: &[u8] = b"/config/names";
+
+pub(crate) fn key_for_name(name: &str) -> Vec<u8> {
+    format!("{NAMES_ROOT}{name}").into_bytes()
+}
+
+/// The fee and duration of registrations, set by the names migration.
+#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct NamesConfig {
+    #[n(0)]
+    pub fee: Option<NameFee>,
+
+    #[n(1)]
+    pub duration_in_secs: u64,
+}
+
+#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct NameRecord {
+    #[n(0)]
+    pub owner: Address,
+
+    #[n(1)]
+    pub expiration: Timestamp,
+}
+
+impl LedgerStorage {
+    pub fn get_names_config(&self) -> Result<NamesConfig, ManyError> {
+        let config = self
+            .persistent_store
+            .get(NAMES_CONFIG_KEY)
+            .map_err(error::storage_get_failed)?
+            .ok_or_else(|| error::storage_key_not_found("/config/names"))?;
+        minicbor::decode(&config).map_err(ManyError::deserialization_error)
+    }
+
+    /// The record of a name, or `None` if it was never registered or has
+    /// expired.
+    pub fn get_name(&self, name: &str) -> Result<Option<NameRecord>, ManyError> {
+        let now = self.now();
+        self.persistent_store
+            .get(&key_for_name(name))
+            .map_err(error::storage_get_failed)?
+            .map(|record| minicbor::decode::<NameRecord>(&record))
+            .transpose()
+            .map_err(ManyError::deserialization_error)
+            .map(|record| record.filter(|r| r.expiration > now))
+    }
+
+    fn put_name(&mut self, name: &str, record: &NameRecord) -> Result<(), ManyError> {
+        self.persistent_store
+            .apply(&[(
+                key_for_name(name),
+                Op::Put(minicbor::to_vec(record).map_err(ManyError::serialization_error)?),
+            )])
+            .map_err(error::storage_apply_failed)
+    }
+
+    /// Register or renew a name, charging the registration fee to the sender.
+    /// Returns the new expiration of the name.
+    pub fn register_name(
+        &mut self,
+        sender: &Address,
+        name: String,
+    ) -> Result<Timestamp, ManyError> {
+        names::validate_name(&name)?;
+        let config = self.get_names_config()?;
+
+        // Renewing extends the current registration.
+        let start = match self.get_name(&name)? {
+            Some(record) if &record.owner != sender => return Err(names::name_taken(name)),
+            Some(record) => record.expiration,
+            None => self.now(),
+        };
+        let expiration = start
+            .checked_add(Duration::from_secs(config.duration_in_secs))
+            .ok_or_else(|| ManyError::unknown("Name expiration is out of range."))?;
+
+        if let Some(NameFee {
+            symbol,
+            amount,
+            collector,
+        }) = config.fee
+        {
+            if !amount.is_zero() && &collector != sender {
+                self.send(sender, &collector, &symbol, amount, None, None)?;
+            }
+        }
+
+        self.put_name(
+            &name,
+            &NameRecord {
+                owner: *sender,
+                expiration,
+            },
+        )?;
+        self.log_event(EventInfo::NameRegister {
+            name,
+            owner: *sender,
+            expiration,
+        })?;
+        self.maybe_commit().map(|_| expiration)
+    }
+
+    pub fn transfer_name(
+        &mut self,
+        sender: &Address,
+        name: String,
+        new_owner: Address,
+    ) -> Result<(), ManyError> {
+        let record = self
+            .get_name(&name)?
+            .ok_or_else(|| names::name_not_found(name.clone()))?;
+        if &record.owner != sender {
+            return Err(names::not_name_owner(name));
+        }
+        if new_owner.is_anonymous() {
+            return Err(error::anonymous_cannot_hold_funds());
+        }
+
+        self.put_name(
+            &name,
+            &NameRecord {
+                owner: new_owner,
+                expiration: record.expiration,
+            },
+        )?;
+        self.log_event(EventInfo::NameTransfer {
+            name,
+            owner: *sender,
+            new_owner,
+        })?;
+        self.maybe_commit()
+    }
+}
@@ -0,0 +1,238 @@
+use crate::error;
+use crate::storage::account::{
+    ACCOUNTS_ROOT, ACCOUNT_DISABLED_ROOT, ACCOUNT_INDEX_ROOT, ACCOUNT_TOMBSTONES_ROOT,
+};
+use crate::storage::attest::ATTEST_ROOT;
+use crate::storage::compliance::COMPLIANCE_NAMESPACE;
+use crate::storage::event::EVENTS_ROOT;
+use crate::storage::expiry::EXPIRY_ROOT;
+use crate::storage::idstore::IDSTORE_ROOT;
+use crate::storage::iterator::LedgerIterator;
+use crate::storage::multisig::{COSIGNED_TRANSACTIONS_ROOT, MULTISIG_TRANSACTIONS_ROOT};
+use crate::storage::names::NAMES_ROOT;
+use crate::storage::relay::RELAYED_REQUESTS_ROOT;
+use crate::storage::subresource::SUBRESOURCE_HEIGHT_ROOT;
+use crate::storage::supply_history::SUPPLY_HISTORY_ROOT;
+use crate::storage::transaction::JournaledStore;
+use crate::storage::{LedgerStorage, BALANCES_ROOT};
+use many_error::ManyError;
+use merk::Op;
+use std::borrow::{Borrow, BorrowMut};
+
+/// The keys of the persistent store owned by a module, which all start with
+/// the prefix of the namespace.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Namespace {
+    pub name: &'static str,
+    pub prefix: &'static [u8],
+}
+
+impl Namespace {
+    /// Panics unless the prefix starts and ends with a `/`, so the prefix of a
+    /// namespace cannot start the prefix of another one by accident, like
+    /// `/multisig` would start `/multisig_cosigned`.
+    pub const fn new(name: &'static str, prefix: &'static [u8]) -> Self {
+        assert!(
+            prefix.len() > 1 && prefix[0] == b'/' && prefix[prefix.len() - 1] == b'/',
+            "Namespace prefixes must start and end with '/'."
+        );
+        Self { name, prefix }
+    }
+
+    /// The key of the store for a key relative to the namespace.
+    pub fn key(&self, key: impl AsRef<[u8]>) -> Vec<u8> {
+        [self.prefix, key.as_ref()].concat()
+    }
+
+    pub fn contains(&self, key: &[u8]) -> bool {
+        key.starts_with(self.prefix)
+    }
+
+    /// Whether a key of the store can be in both namespaces.
+    pub fn overlaps(&self, other: &Namespace) -> bool {
+        self.prefix.starts_with(other.prefix) || other.prefix.starts_with(self.prefix)
+    }
+
+    /// The namespace of a key of the store, or `None` for the keys outside of
+    /// any namespace, e.g. the height.
+    pub fn of(key: &[u8]) -> Option<&'static Namespace> {
+        NAMESPACES.iter().find(|namespace| namespace.contains(key))
+    }
+}
+
+/// All the namespaces of the store. They must not overlap.
+///
+/// The `/config/` namespace predates namespaces and is shared between modules;
+/// new modules should keep their configuration in their own namespace.
+pub const NAMESPACES: &[Namespace] = &[
+    Namespace::new("accounts", ACCOUNTS_ROOT.as_bytes()),
+    Namespace::new("account_disabled", ACCOUNT_DISABLED_ROOT.as_bytes()),
+    Namespace::new("account_index", ACCOUNT_INDEX_ROOT.as_bytes()),
+    Namespace::new("account_tombstones", ACCOUNT_TOMBSTONES_ROOT.as_bytes()),
+    Namespace::new("attest", ATTEST_ROOT.as_bytes()),
+    Namespace::new("balances", BALANCES_ROOT.as_bytes()),
+    COMPLIANCE_NAMESPACE,
+    Namespace::new("config", b"/config/"),
+    Namespace::new("data", b"/data/"),
+    Namespace::new("events", EVENTS_ROOT),
+    Namespace::new("expiry", EXPIRY_ROOT),
+    Namespace::new("idstore", IDSTORE_ROOT),
+    Namespace::new("multisig", MULTISIG_TRANSACTIONS_ROOT),
+    Namespace::new("multisig_cosigned", COSIGNED_TRANSACTIONS_ROOT),
+    Namespace::new("names", NAMES_ROOT.as_bytes()),
+    Namespace::new("relay", RELAYED_REQUESTS_ROOT),
+    Namespace::new("store", b"/store/"),
+    Namespace::new("subresources", SUBRESOURCE_HEIGHT_ROOT.as_bytes()),
+    Namespace::new("supply_history", SUPPLY_HISTORY_ROOT.as_bytes()),
+];
+
+/// A view of the persistent store restricted to a namespace. Its keys are
+/// relative to the namespace, so a module using it cannot read or write the
+/// keys of another module.
+pub struct NamespacedStorage<S> {
+    store: S,
+    namespace: Namespace,
+}
+
+impl<S: Borrow<JournaledStore>> NamespacedStorage<S> {
+    pub fn new(store: S, namespace: Namespace) -> Self {
+        Self { store, namespace }
+    }
+
+    pub fn namespace(&self) -> &Namespace {
+        &self.namespace
+    }
+
+    pub fn get(&self, key: impl AsRef<[u8]>) -> Result<Option<Vec<u8>>, ManyError> {
+        self.store
+            .borrow()
+            .get(&self.namespace.key(key))
+            .map_err(error::storage_get_failed)
+    }
+
+    /// Iterate over the key/value pairs of the namespace in key order, with
+    /// the keys relative to the namespace.
+    pub fn iter(&self) -> impl Iterator<Item = Result<(Vec<u8>, Vec<u8>), ManyError>> + '_ {
+        let len = self.namespace.prefix.len();
+        LedgerIterator::all_with_prefix(self.store.borrow(), self.namespace.prefix).map(
+            move |item| {
+                item.map(|(key, value)| (key[len..].to_vec(), value))
+                    .map_err(|e| error::StorageError::from(e).into())
+            },
+        )
+    }
+}
+
+impl<S: BorrowMut<JournaledStore>> NamespacedStorage<S> {
+    /// Apply a batch of operations to keys relative to the namespace. As for
+    /// the store, the keys must be sorted.
+    pub fn apply<K: AsRef<[u8]>>(
+        &mut self,
+        batch: impl IntoIterator<Item = (K, Op)>,
+    ) -> Result<(), ManyError> {
+        let batch: Vec<_> = batch
+            .into_iter()
+            .map(|(key, op)| (self.namespace.key(key), op))
+            .collect();
+        self.store
+            .borrow_mut()
+            .apply(&batch)
+            .map_err(error::storage_apply_failed)
+    }
+
+    pub fn put(&mut self, key: impl AsRef<[u8]>, value: Vec<u8>) -> Result<(), ManyError> {
+        self.apply([(key, Op::Put(value))])
+    }
+
+    pub fn delete(&mut self, key: impl AsRef<[u8]>) -> Result<(), ManyError> {
+        self.apply([(key, Op::Delete)])
+    }
+}
+
+impl LedgerStorage {
+    pub fn namespace(&self, namespace: Namespace) -> NamespacedStorage<&JournaledStore> {
+        NamespacedStorage::new(&self.persistent_store, namespace)
+    }
+
+    /// Writes to the namespace are not committed, as for any other write of
+    /// the store.
+    pub fn namespace_mut(
+        &mut self,
+        namespace: Namespace,
+    ) -> NamespacedStorage<&mut JournaledStore> {
+        NamespacedStorage::new(&mut self.persistent_store, namespace)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn namespaces_do_not_overlap() {
+        for (i, a) in NAMESPACES.iter().enumerate() {
+            for b in &NAMESPACES[i + 1..] {
+                assert!(!a.overlaps(b), "{} overlaps {}", a.name, b.name);
+                assert_ne!(a.name, b.name);
+            }
+        }
+    }
+
+    #[test]
+    fn of() {
+        assert_eq!(Namespace::of(b"/balances/abc").unwrap().name, "balances");
+        assert_eq!(
+            Namespace::of(b"/multisig_cosigned/1").unwrap().name,
+            "multisig_cosigned"
+        );
+        assert_eq!(Namespace::of(b"/config/symbols").unwrap().name, "config");
+        assert_eq!(Namespace::of(b"/height"), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn prefix_must_end_with_slash() {
+        Namespace::new("multisig", b"/multisig");
+    }
+
+    #[test]
+    fn prefixes_keys() {
+        let path = tempfile::tempdir().unwrap().into_path();
+        let mut storage = LedgerStorage::new(path, false).unwrap().build().unwrap();
+        let a = Namespace::new("a", b"/a/");
+        let b = Namespace::new("b", b"/b/");
+
+        storage.namespace_mut(a).put("key", b"1".to_vec()).unwrap();
+        storage
+            .namespace_mut(b)
+            .apply([("key", Op::Put(b"2".to_vec())), ("other", Op::Put(vec![]))])
+            .unwrap();
+        storage.maybe_commit().unwrap();
+
+        assert_eq!(
+            storage.namespace(a).get("key").unwrap(),
+            Some(b"1".to_vec())
+        );
+        assert_eq!(
+            storage.namespace(b).get("key").unwrap(),
+            Some(b"2".to_vec())
+        );
+        assert_eq!(
+            storage.persistent_store.get(b"/a/key").unwrap(),
+            Some(b"1".to_vec())
+        );
+        assert_eq!(
+            storage
+                .namespace(b)
+                .iter()
+                .map(|item| item.unwrap().0)
+                .collect::<Vec<_>>(),
+            vec![b"key".to_vec(), b"other".to_vec()]
+        );
+
+        storage.namespace_mut(a).delete("key").unwrap();
+        storage.maybe_commit().unwrap();
+        assert_eq!(storage.namespace(a).get("key").unwrap(), None);
+        assert_eq!(storage.namespace(a).iter().count(), 0);
+    }
+}
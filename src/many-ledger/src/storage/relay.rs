@@ -0,0 +1,87 @@
+use crate::error;
+use crate::storage::LedgerStorage;
+use many_error::ManyError;
+use many_identity::Address;
+use many_modules::events::EventInfo;
+use many_modules::relay::{self, RelayFee};
+use many_protocol::RequestMessage;
+use minicbor::{Decode, Encode};
+use sha3::{Digest, Sha3_256};
+
+pub const RELAYED_REQUESTS_ROOT: &[u8] = b"/relay/";
+pub const RELAY_CONFIG_KEY: &[u8] = b"/config/relay";
+
+/// Relayed requests are keyed by the hash of the request itself rather than
+/// its envelope, so re-signing a request does not allow replaying it. The
+/// keys are deleted once the timestamp of the request is too old to relay it.
+fn key_for_relayed_request(request: &RequestMessage) -> Result<Vec<u8>, ManyError> {
+    let bytes = request.to_bytes().map_err(ManyError::serialization_error)?;
+    Ok([RELAYED_REQUESTS_ROOT, Sha3_256::digest(bytes).as_slice()].concat())
+}
+
+/// The fee and timeout of relayed requests, set by the relay migration.
+#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct RelayConfig {
+    #[n(0)]
+    pub fee: Option<RelayFee>,
+
+    #[n(1)]
+    pub timeout_in_secs: u64,
+}
+
+impl LedgerStorage {
+    pub fn get_relay_config(&self) -> Result<RelayConfig, ManyError> {
+        let config = self
+            .persistent_store
+            .get(RELAY_CONFIG_KEY)
+            .map_err(error::storage_get_failed)?
+            .ok_or_else(|| error::storage_key_not_found("/config/relay"))?;
+        minicbor::decode(&config).map_err(ManyError::deserialization_error)
+    }
+
+    /// Check that a request was not relayed before and that its timestamp is
+    /// recent, then charge the relay fee to the relayer.
+    pub fn relay_request(
+        &mut self,
+        relayer: &Address,
+        request: &RequestMessage,
+    ) -> Result<(), ManyError> {
+        let config = self.get_relay_config()?;
+        request.validate_time(self.now().as_system_time()?, config.timeout_in_secs)?;
+
+        let key = key_for_relayed_request(request)?;
+        if self
+            .persistent_store
+            .get(&key)
+            .map_err(error::storage_get_failed)?
+            .is_some()
+        {
+            return Err(relay::relayed_request_already_executed());
+        }
+
+        if let Some(RelayFee {
+            symbol,
+            amount,
+            collector,
+        }) = config.fee
+        {
+            if !amount.is_zero() && &collector != relayer {
+                self.send(relayer, &collector, &symbol, amount, None, None)?;
+            }
+        }
+
+        // `validate_time` succeeded, so the request has a timestamp.
+        let expires_at = request
+            .timestamp
+            .map_or(0, |t| t.secs())
+            .saturating_add(config.timeout_in_secs);
+        self.put_expiring(key, vec![], expires_at)?;
+        self.log_event(EventInfo::RelayExecute {
+            relayer: *relayer,
+            sender: request.from(),
+            method: request.method.clone(),
+        })?;
+        self.maybe_commit()
+    }
+}
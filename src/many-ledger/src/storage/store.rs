@@ -0,0 +1,161 @@
+use crate::error;
+use crate::storage::LedgerStorage;
+use many_error::ManyError;
+use many_identity::Address;
+use many_modules::events::EventInfo;
+use many_modules::store::{self, ObjectInfo};
+use merk::Op;
+use minicbor::bytes::ByteVec;
+use minicbor::{Decode, Encode};
+use std::collections::BTreeSet;
+
+/// Objects are stored in the state tree, so the root hash commits to their
+/// content and `store.get` can prove it.
+pub const STORE_OBJECTS_ROOT: &str = "/store/objects/";
+pub const STORE_INFO_ROOT: &str = "/store/info/";
+pub const STORE_CONFIG_KEY: &[u8] = b"/config/store";
+
+pub(crate) fn key_for_object(hash: &[u8]) -> Vec<u8> {
+    format!("{STORE_OBJECTS_ROOT}{}", hex::encode(hash)).into_bytes()
+}
+
+pub(crate) fn key_for_object_info(hash: &[u8]) -> Vec<u8> {
+    format!("{STORE_INFO_ROOT}{}", hex::encode(hash)).into_bytes()
+}
+
+/// The limits of the object store, set by the store migration.
+#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct StoreConfig {
+    #[n(0)]
+    pub max_size: u64,
+}
+
+impl LedgerStorage {
+    pub fn get_store_config(&self) -> Result<StoreConfig, ManyError> {
+        let config = self
+            .persistent_store
+            .get(STORE_CONFIG_KEY)
+            .map_err(error::storage_get_failed)?
+            .ok_or_else(|| error::storage_key_not_found("/config/store"))?;
+        minicbor::decode(&config).map_err(ManyError::deserialization_error)
+    }
+
+    pub fn get_object_info(&self, hash: &[u8]) -> Result<Option<ObjectInfo>, ManyError> {
+        store::validate_hash(hash)?;
+        self.persistent_store
+            .get(&key_for_object_info(hash))
+            .map_err(error::storage_get_failed)?
+            .map(|info| minicbor::decode(&info))
+            .transpose()
+            .map_err(ManyError::deserialization_error)
+    }
+
+    /// The content of an object and its information, with the keys they are
+    /// stored at.
+    pub fn get_object(
+        &self,
+        hash: &[u8],
+    ) -> Result<(ByteVec, ObjectInfo, Vec<Vec<u8>>), ManyError> {
+        let info = self
+            .get_object_info(hash)?
+            .ok_or_else(|| store::object_not_found(hex::encode(hash)))?;
+        let key = key_for_object(hash);
+        let data = self
+            .persistent_store
+            .get(&key)
+            .map_err(error::storage_get_failed)?
+            .ok_or_else(|| store::object_not_found(hex::encode(hash)))?;
+        Ok((data.into(), info, vec![key, key_for_object_info(hash)]))
+    }
+
+    fn put_object_info(&mut self, info: &ObjectInfo) -> Result<(), ManyError> {
+        self.persistent_store
+            .apply(&[(
+                key_for_object_info(&info.hash),
+                Op::Put(minicbor::to_vec(info).map_err(ManyError::serialization_error)?),
+            )])
+            .map_err(error::storage_apply_failed)
+    }
+
+    /// Store an object pinned by the sender, and return its hash.
+    pub fn put_object(&mut self, sender: &Address, data: ByteVec) -> Result<ByteVec, ManyError> {
+        let size = data.len() as u64;
+        let max_size = self.get_store_config()?.max_size;
+        if size > max_size {
+            return Err(store::object_too_large(size, max_size));
+        }
+
+        let hash: ByteVec = store::object_hash(&data).to_vec().into();
+        let info = match self.get_object_info(&hash)? {
+            Some(mut info) => {
+                info.pins.insert(*sender);
+                info
+            }
+            None => {
+                self.persistent_store
+                    .apply(&[(key_for_object(&hash), Op::Put(data.into()))])
+                    .map_err(error::storage_apply_failed)?;
+                ObjectInfo {
+                    hash: hash.clone(),
+                    size,
+                    created: self.now(),
+                    pins: BTreeSet::from([*sender]),
+                }
+            }
+        };
+        self.put_object_info(&info)?;
+
+        self.log_event(EventInfo::StorePin {
+            owner: *sender,
+            hash: hash.clone(),
+            size,
+        })?;
+        self.maybe_commit()?;
+        Ok(hash)
+    }
+
+    pub fn pin_object(&mut self, sender: &Address, hash: ByteVec) -> Result<(), ManyError> {
+        let mut info = self
+            .get_object_info(&hash)?
+            .ok_or_else(|| store::object_not_found(hex::encode(&hash)))?;
+        info.pins.insert(*sender);
+        self.put_object_info(&info)?;
+
+        self.log_event(EventInfo::StorePin {
+            owner: *sender,
+            hash,
+            size: info.size,
+        })?;
+        self.maybe_commit()
+    }
+
+    /// Remove the pin of the sender, and the object if no pins are left.
+    pub fn unpin_object(&mut self, sender: &Address, hash: ByteVec) -> Result<(), ManyError> {
+        let mut info = self
+            .get_object_info(&hash)?
+            .ok_or_else(|| store::object_not_found(hex::encode(&hash)))?;
+        if !info.pins.remove(sender) {
+            return Err(store::not_pinned(hex::encode(&hash)));
+        }
+
+        let removed = info.pins.is_empty();
+        if removed {
+            self.persistent_store
+                .apply(&[
+                    (key_for_object_info(&hash), Op::Delete),
+                    (key_for_object(&hash), Op::Delete),
+                ])
+                .map_err(error::storage_apply_failed)?;
+        } else {
+            self.put_object_info(&info)?;
+        }
+
+        self.log_event(EventInfo::StoreUnpin {
+            owner: *sender,
+            hash,
+            removed,
+        })?;
+        self.maybe_commit()
+    }
+}
@@ -0,0 +1,103 @@
+use crate::error;
+use crate::migration::subresource_history::SUBRESOURCE_HISTORY_MIGRATION;
+use crate::migration::tokens::TOKEN_MIGRATION;
+use crate::storage::account::ACCOUNT_IDENTITY_ROOT;
+use crate::storage::ledger_tokens::TOKEN_IDENTITY_ROOT;
+use crate::storage::{LedgerStorage, IDENTITY_ROOT};
+use many_error::ManyError;
+use many_identity::Address;
+use many_modules::account::Subresource;
+use merk::Op;
+
+pub const SUBRESOURCE_HEIGHT_ROOT: &str = "/subresources/";
+
+pub(super) fn key_for_subresource_height(address: &Address) -> Vec<u8> {
+    format!("{SUBRESOURCE_HEIGHT_ROOT}{address}").into_bytes()
+}
+
+impl LedgerStorage {
+    /// Record the height of the block where a subresource is allocated, if
+    /// the subresource history migration is active. Returns the key written.
+    pub(super) fn record_subresource_height(
+        &mut self,
+        address: &Address,
+    ) -> Result<Option<Vec<u8>>, ManyError> {
+        if !self.migrations.is_active(&SUBRESOURCE_HISTORY_MIGRATION) {
+            return Ok(None);
+        }
+
+        // The block being executed is committed at the next height.
+        let height = self.get_height()? + 1;
+        let key = key_for_subresource_height(address);
+        self.persistent_store
+            .apply(&[(key.clone(), Op::Put(height.to_be_bytes().to_vec()))])
+            .map_err(error::storage_apply_failed)?;
+        Ok(Some(key))
+    }
+
+    /// Returns whether subresources are allocated under this address. Before
+    /// the token migration, only accounts are allocated.
+    fn allocates_subresources(&self, address: &Address) -> Result<bool, ManyError> {
+        let roots = if self.migrations.is_active(&TOKEN_MIGRATION) {
+            vec![ACCOUNT_IDENTITY_ROOT, TOKEN_IDENTITY_ROOT]
+        } else {
+            vec![ACCOUNT_IDENTITY_ROOT]
+        };
+
+        for root in roots {
+            let identity = self
+                .persistent_store
+                .get(root.as_bytes())
+                .map_err(error::storage_get_failed)?
+                .map_or(self.get_identity(IDENTITY_ROOT), |bytes| {
+                    Address::from_bytes(&bytes)
+                })?;
+            if &identity == address {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// List up to `count` subresources allocated under an address, starting
+    /// at the subresource ID `start`. Also returns the next subresource ID to
+    /// be allocated, and the keys to prove.
+    pub fn list_subresources(
+        &self,
+        address: &Address,
+        start: u32,
+        count: usize,
+    ) -> Result<(Vec<Subresource>, u32, Vec<Vec<u8>>), ManyError> {
+        if !self.allocates_subresources(address)? {
+            return Ok((vec![], 0, vec![]));
+        }
+
+        let next_subresource = self.get_subresource_counter(address)?;
+        let mut keys = vec![super::key_for_subresource_counter(
+            address,
+            self.migrations.is_active(&TOKEN_MIGRATION),
+        )];
+        let subresources = (start..next_subresource)
+            .take(count)
+            .map(|id| {
+                let address = address.with_subresource_id(id)?;
+                let key = key_for_subresource_height(&address);
+                let height = self
+                    .persistent_store
+                    .get(&key)
+                    .map_err(error::storage_get_failed)?
+                    .map(|bytes| {
+                        let mut height = [0u8; 8];
+                        height.copy_from_slice(bytes.as_slice());
+                        u64::from_be_bytes(height)
+                    });
+                if height.is_some() {
+                    keys.push(key);
+                }
+                Ok(Subresource { address, height })
+            })
+            .collect::<Result<Vec<_>, ManyError>>()?;
+
+        Ok((subresources, next_subresource, keys))
+    }
+}
@@ -0,0 +1,121 @@
+//! The tokens minted and burned at each height, per symbol, so the evolution
+//! of the circulating supply of a token can be audited against its maximum.
+use crate::error;
+use crate::migration::supply_history::SUPPLY_HISTORY_MIGRATION;
+use crate::storage::iterator::LedgerIterator;
+use crate::storage::{InnerStorage, LedgerStorage};
+use many_error::ManyError;
+use many_modules::ledger::{
+    TokenInfoArgs, TokenSupplyHistoryArgs, TokenSupplyHistoryEntry, TokenSupplyHistoryReturns,
+};
+use many_types::ledger::{Symbol, TokenAmount};
+use merk::Op;
+use minicbor::{Decode, Encode};
+
+pub const SUPPLY_HISTORY_ROOT: &str = "/supply_history/";
+
+/// The supply changes of a token during a block.
+#[derive(Clone, Debug, Default, Decode, Encode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct SupplyDelta {
+    #[n(0)]
+    pub minted: TokenAmount,
+
+    #[n(1)]
+    pub burned: TokenAmount,
+}
+
+fn prefix_for_supply_history(symbol: &Symbol) -> Vec<u8> {
+    format!("{SUPPLY_HISTORY_ROOT}{symbol}/").into_bytes()
+}
+
+pub(crate) fn key_for_supply_history(symbol: &Symbol, height: u64) -> Vec<u8> {
+    [
+        prefix_for_supply_history(symbol),
+        height.to_be_bytes().to_vec(),
+    ]
+    .concat()
+}
+
+pub(crate) fn get_supply_delta(
+    storage: &InnerStorage,
+    symbol: &Symbol,
+    height: u64,
+) -> Result<SupplyDelta, ManyError> {
+    storage
+        .get(&key_for_supply_history(symbol, height))
+        .map_err(error::storage_get_failed)?
+        .map_or(Ok(SupplyDelta::default()), |enc| {
+            minicbor::decode(&enc).map_err(ManyError::deserialization_error)
+        })
+}
+
+impl LedgerStorage {
+    /// Add tokens minted and burned to the supply history of the current
+    /// height, if the history is kept. Returns the key updated.
+    pub(crate) fn record_supply_change(
+        &mut self,
+        symbol: &Symbol,
+        minted: &TokenAmount,
+        burned: &TokenAmount,
+    ) -> Result<Option<Vec<u8>>, ManyError> {
+        if !self.migrations.is_active(&SUPPLY_HISTORY_MIGRATION) {
+            return Ok(None);
+        }
+
+        let height = self.latest_tid.height();
+        let mut delta = get_supply_delta(&self.persistent_store, symbol, height)?;
+        delta.minted += minted;
+        delta.burned += burned;
+
+        let key = key_for_supply_history(symbol, height);
+        self.persistent_store
+            .apply(&[(
+                key.clone(),
+                Op::Put(minicbor::to_vec(&delta).map_err(ManyError::serialization_error)?),
+            )])
+            .map_err(error::storage_apply_failed)?;
+        Ok(Some(key))
+    }
+
+    pub fn supply_history(
+        &self,
+        args: TokenSupplyHistoryArgs,
+    ) -> Result<(TokenSupplyHistoryReturns, Vec<Vec<u8>>), ManyError> {
+        let TokenSupplyHistoryArgs { symbol } = args;
+        let maximum = self
+            .info_token(TokenInfoArgs {
+                symbol,
+                extended_info: None,
+            })?
+            .info
+            .supply
+            .maximum;
+
+        let prefix = prefix_for_supply_history(&symbol);
+        let mut keys = Vec::new();
+        let mut history = Vec::new();
+        let mut circulating = TokenAmount::zero();
+        for item in LedgerIterator::all_with_prefix(&self.persistent_store, &prefix) {
+            let (key, value) = item.map_err(error::StorageError::from)?;
+            let height = key[prefix.len()..]
+                .try_into()
+                .map(u64::from_be_bytes)
+                .map_err(ManyError::deserialization_error)?;
+            let SupplyDelta { minted, burned } =
+                minicbor::decode(&value).map_err(ManyError::deserialization_error)?;
+
+            circulating += &minted;
+            circulating -= &burned;
+            history.push(TokenSupplyHistoryEntry {
+                height,
+                minted,
+                burned,
+                circulating: circulating.clone(),
+            });
+            keys.push(key.to_vec());
+        }
+
+        Ok((TokenSupplyHistoryReturns { history, maximum }, keys))
+    }
+}
@@ -0,0 +1,96 @@
+use crate::error;
+use crate::storage::event::key_for_event;
+use crate::storage::{InnerStorage, LedgerStorage};
+use many_error::ManyError;
+use many_modules::events::EventId;
+use many_server::transaction::{StoreBackend, StoreError};
+use merk::{BatchEntry, Op};
+
+/// The merk store of the ledger.
+pub struct MerkBackend;
+
+impl StoreBackend for MerkBackend {
+    type Store = InnerStorage;
+    type Op = Op;
+
+    fn get(store: &InnerStorage, key: &[u8]) -> Result<Option<Vec<u8>>, StoreError> {
+        store.get(key).map_err(StoreError::new)
+    }
+
+    fn apply(store: &mut InnerStorage, batch: &[BatchEntry]) -> Result<(), StoreError> {
+        store.apply(batch).map_err(StoreError::new)
+    }
+
+    fn commit(store: &mut InnerStorage, aux: &[BatchEntry]) -> Result<(), StoreError> {
+        store.commit(aux).map_err(StoreError::new)
+    }
+
+    fn restore(previous: Option<Vec<u8>>) -> Op {
+        previous.map_or(Op::Delete, Op::Put)
+    }
+}
+
+/// The persistent store of the ledger. Migrations write to the inner store
+/// directly, so their writes are not undone by a rollback.
+pub type JournaledStore = many_server::transaction::JournaledStore<MerkBackend>;
+
+/// The state of a transaction which is not in the store.
+pub(crate) struct TransactionState {
+    latest_tid: EventId,
+}
+
+impl LedgerStorage {
+    pub fn begin_transaction(&mut self) -> Result<(), ManyError> {
+        if self.transaction.is_some() {
+            return Err(error::transaction_already_open());
+        }
+        self.persistent_store.begin();
+        self.transaction = Some(TransactionState {
+            latest_tid: self.latest_tid.clone(),
+        });
+        Ok(())
+    }
+
+    pub fn commit_transaction(&mut self) -> Result<(), ManyError> {
+        self.transaction
+            .take()
+            .ok_or_else(error::no_open_transaction)?;
+        self.persistent_store.end();
+        // The writes of the transaction are only committed once it ends.
+        self.maybe_commit()
+    }
+
+    /// The encoded events logged since the start of the transaction, in
+    /// order. They are read by ID, as iterators only see committed events.
+    pub fn transaction_events(&self) -> Result<Vec<Vec<u8>>, ManyError> {
+        let TransactionState { latest_tid } = self
+            .transaction
+            .as_ref()
+            .ok_or_else(error::no_open_transaction)?;
+        let mut id = latest_tid.clone();
+        let mut events = Vec::new();
+        while id < self.latest_tid {
+            id += 1;
+            let event = self
+                .persistent_store
+                .get(&key_for_event(id.clone()))
+                .map_err(error::storage_get_failed)?;
+            events.extend(event);
+        }
+        Ok(events)
+    }
+
+    /// Restore the keys written since the start of the transaction, and the
+    /// ID of the next event.
+    pub fn rollback_transaction(&mut self) -> Result<(), ManyError> {
+        let TransactionState { latest_tid } = self
+            .transaction
+            .take()
+            .ok_or_else(error::no_open_transaction)?;
+        self.persistent_store
+            .undo()
+            .map_err(error::storage_apply_failed)?;
+        self.latest_tid = latest_tid;
+        self.maybe_commit()
+    }
+}
@@ -1,4 +1,5 @@
 pub mod cucumber;
+pub mod scenario;
 
 use async_channel::unbounded;
 use coset::CborSerializable;
@@ -71,18 +72,32 @@ pub struct MigrationHarness {
     inner: &'static InnerMigration<merk::Merk, ManyError>,
     block_height: u64,
     enabled: bool,
+    extra: serde_json::Map<String, serde_json::Value>,
 }
 
 impl MigrationHarness {
+    /// Add extra parameters to the migration configuration.
+    pub fn with_extra(mut self, extra: serde_json::Value) -> Self {
+        if let serde_json::Value::Object(extra) = extra {
+            self.extra.extend(extra);
+        }
+        self
+    }
+
     pub fn to_json_str(&self) -> String {
         let maybe_enabled = if !self.enabled {
             r#", "disabled": true"#
         } else {
             ""
         };
+        let extra: String = self
+            .extra
+            .iter()
+            .map(|(k, v)| format!(r#", "{k}": {v}"#))
+            .collect();
 
         format!(
-            r#"{{ "name": "{}", "block_height": {}, "issue": "" {maybe_enabled} }}"#,
+            r#"{{ "name": "{}", "block_height": {}, "issue": "" {maybe_enabled}{extra} }}"#,
             self.inner.name(),
             self.block_height
         )
@@ -95,6 +110,7 @@ impl From<(u64, &'static InnerMigration<merk::Merk, ManyError>)> for MigrationHa
             inner,
             block_height,
             enabled: true,
+            extra: Default::default(),
         }
     }
 }
@@ -107,10 +123,31 @@ impl From<(u64, &'static InnerMigration<merk::Merk, ManyError>, bool)> for Migra
             inner,
             block_height,
             enabled,
+            extra: Default::default(),
         }
     }
 }
 
+/// A harness on a blockchain where a single migration, configured with
+/// `extra`, activates at height 1.
+pub fn setup_with_migration(
+    migration: &'static InnerMigration<Merk, ManyError>,
+    extra: serde_json::Value,
+) -> Setup {
+    let migration = MigrationHarness::from((1, migration)).with_extra(extra);
+    Setup::new_with_migrations(true, [migration], true)
+}
+
+/// Assert that an endpoint gated by a migration is unknown when the
+/// migration is not configured.
+pub fn assert_disabled<T>(call: impl FnOnce(&mut Setup) -> Result<T, ManyError>) {
+    let mut harness = Setup::new(false);
+    match call(&mut harness) {
+        Ok(_) => panic!("The endpoint is enabled without its migration."),
+        Err(err) => assert_eq!(err.code(), ManyError::invalid_method_name("").code()),
+    }
+}
+
 pub static MFX_SYMBOL: Lazy<Address> = Lazy::new(|| {
     Address::from_str("mqbfbahksdwaqeenayy2gxke32hgb7aq4ao4wt745lsfs6wiaaaaqnz").unwrap()
 });
@@ -249,6 +286,7 @@ impl Setup {
                 BalanceArgs {
                     account: None,
                     symbols: Some(vec![symbol].into()),
+                    height: None,
                 },
                 Context::new(RequestMessage::default(), unbounded().0),
             )?
@@ -289,6 +327,7 @@ impl Setup {
                     amount: amount.into(),
                     symbol,
                     memo: None,
+                    reference: None,
                 },
             )
             .map(|_| ())
@@ -397,6 +436,7 @@ impl Setup {
                     execute_automatically: None,
                     data_: None,
                     memo_: None,
+                    attachment: None,
                 },
             )
             .map(|x| x.token)
@@ -426,6 +466,7 @@ impl Setup {
                 symbol,
                 amount: amount.into(),
                 memo: None,
+                reference: None,
             }),
         )
     }
@@ -560,6 +601,7 @@ fn event_from_kind(
         symbol: *MFX_SYMBOL,
         amount: TokenAmount::from(10u16),
         memo: None,
+        reference: None,
     });
 
     match event {
@@ -619,6 +661,7 @@ fn event_from_kind(
                     execute_automatically: Some(false),
                     data_: None,
                     memo_: None,
+                    attachment: None,
                 },
             )
         }
@@ -635,6 +678,7 @@ fn event_from_kind(
                         execute_automatically: Some(false),
                         data_: None,
                         memo_: None,
+                        attachment: None,
                     },
                 )
                 .unwrap()
@@ -656,6 +700,7 @@ fn event_from_kind(
                         execute_automatically: Some(false),
                         data_: None,
                         memo_: None,
+                        attachment: None,
                     },
                 )
                 .unwrap()
@@ -678,6 +723,7 @@ fn event_from_kind(
                         execute_automatically: Some(false),
                         data_: None,
                         memo_: None,
+                        attachment: None,
                     },
                 )
                 .unwrap()
@@ -706,6 +752,7 @@ fn event_from_kind(
                         execute_automatically: Some(false),
                         data_: None,
                         memo_: None,
+                        attachment: None,
                     },
                 )
                 .unwrap()
@@ -758,6 +805,7 @@ pub fn verify_balance(
         BalanceArgs {
             account: Some(id),
             symbols: Some(vec![symbol].into()),
+            height: None,
         },
         Context::new(RequestMessage::default(), unbounded().0),
     );
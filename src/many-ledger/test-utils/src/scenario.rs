@@ -0,0 +1,315 @@
+//! A builder for ledger test scenarios, creating tokens, accounts and balances
+//! in a few chained calls.
+//!
+//! ```ignore
+//! let s = Scenario::new()
+//!     .with_balance(identity(1), 1000)
+//!     .with_multisig("ms", [identity(2), identity(3)], 2)
+//!     .with_balance("ms", 500)
+//!     .with_token("FOO", [(identity(1), 100)])
+//!     .build();
+//! assert_eq!(s.balance("ms", "MFX"), TokenAmount::from(500u16));
+//! ```
+use crate::{create_account_args, AccountType, MigrationHarness, Setup, MFX_SYMBOL};
+use many_identity::Address;
+use many_ledger::migration::token_create::TOKEN_CREATE_MIGRATION;
+use many_ledger::migration::tokens::TOKEN_MIGRATION;
+use many_modules::account;
+use many_modules::account::features::multisig::MultisigAccountFeature;
+use many_modules::account::features::FeatureInfo;
+use many_modules::account::AccountModuleBackend;
+use many_modules::ledger::{LedgerTokensModuleBackend, TokenCreateArgs};
+use many_types::ledger::{LedgerTokensAddressMap, Symbol, TokenAmount, TokenInfoSummary};
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::{Deref, DerefMut};
+
+/// An identity or an account of a scenario, given by the name it was created
+/// with.
+#[derive(Clone, Debug)]
+pub enum Party {
+    Address(Address),
+    Account(String),
+}
+
+impl From<Address> for Party {
+    fn from(address: Address) -> Self {
+        Self::Address(address)
+    }
+}
+
+impl From<&str> for Party {
+    fn from(name: &str) -> Self {
+        Self::Account(name.to_string())
+    }
+}
+
+enum Step {
+    Account {
+        name: String,
+        account_type: AccountType,
+    },
+    Multisig {
+        name: String,
+        approvers: Vec<Party>,
+        threshold: u64,
+    },
+    Token {
+        ticker: String,
+        distribution: Vec<(Party, TokenAmount)>,
+    },
+    Balance {
+        party: Party,
+        amount: u64,
+        ticker: String,
+    },
+}
+
+/// A ledger test scenario. The steps run in order on [Scenario::build].
+#[derive(Default)]
+pub struct Scenario {
+    blockchain: bool,
+    migrations: Vec<MigrationHarness>,
+    steps: Vec<Step>,
+}
+
+impl Scenario {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run the ledger in blockchain mode.
+    pub fn blockchain(mut self) -> Self {
+        self.blockchain = true;
+        self
+    }
+
+    pub fn with_migration(mut self, migration: impl Into<MigrationHarness>) -> Self {
+        self.migrations.push(migration.into());
+        self
+    }
+
+    /// Create an account owned by the setup identity, with the roles and
+    /// features of [create_account_args].
+    pub fn with_account(mut self, name: &str, account_type: AccountType) -> Self {
+        self.steps.push(Step::Account {
+            name: name.to_string(),
+            account_type,
+        });
+        self
+    }
+
+    /// Create a multisig account owned by the setup identity, where the
+    /// approvers can submit and approve transactions.
+    pub fn with_multisig(
+        mut self,
+        name: &str,
+        approvers: impl IntoIterator<Item = impl Into<Party>>,
+        threshold: u64,
+    ) -> Self {
+        self.steps.push(Step::Multisig {
+            name: name.to_string(),
+            approvers: approvers.into_iter().map(Into::into).collect(),
+            threshold,
+        });
+        self
+    }
+
+    /// Create a token with this ticker and initial distribution. The token
+    /// migrations are enabled at height 0.
+    pub fn with_token(
+        mut self,
+        ticker: &str,
+        distribution: impl IntoIterator<Item = (impl Into<Party>, impl Into<TokenAmount>)>,
+    ) -> Self {
+        self.steps.push(Step::Token {
+            ticker: ticker.to_string(),
+            distribution: distribution
+                .into_iter()
+                .map(|(party, amount)| (party.into(), amount.into()))
+                .collect(),
+        });
+        self
+    }
+
+    /// Set the MFX balance of a party.
+    pub fn with_balance(self, party: impl Into<Party>, amount: u64) -> Self {
+        self.with_token_balance(party, amount, "MFX")
+    }
+
+    /// Set the balance of a party in a token created earlier. Balances must be
+    /// set before the first block.
+    pub fn with_token_balance(
+        mut self,
+        party: impl Into<Party>,
+        amount: u64,
+        ticker: &str,
+    ) -> Self {
+        self.steps.push(Step::Balance {
+            party: party.into(),
+            amount,
+            ticker: ticker.to_string(),
+        });
+        self
+    }
+
+    pub fn build(mut self) -> ScenarioSetup {
+        let creates_tokens = self.steps.iter().any(|s| matches!(s, Step::Token { .. }));
+        if creates_tokens {
+            for migration in [&TOKEN_MIGRATION, &TOKEN_CREATE_MIGRATION] {
+                if !self
+                    .migrations
+                    .iter()
+                    .any(|m| m.inner.name() == migration.name())
+                {
+                    self.migrations.push((0, migration).into());
+                }
+            }
+        }
+
+        let setup = if self.migrations.is_empty() {
+            Setup::new(self.blockchain)
+        } else {
+            // The token migrations change the hash of the initial state.
+            Setup::new_with_migrations(self.blockchain, self.migrations, true)
+        };
+        let mut scenario = ScenarioSetup {
+            setup,
+            accounts: BTreeMap::new(),
+            symbols: BTreeMap::from([("MFX".to_string(), *MFX_SYMBOL)]),
+        };
+
+        for step in self.steps {
+            scenario.run(step);
+        }
+        scenario
+    }
+}
+
+/// A [Setup] built from a [Scenario], with the accounts and tokens it created
+/// by name.
+#[derive(Debug)]
+pub struct ScenarioSetup {
+    pub setup: Setup,
+    accounts: BTreeMap<String, Address>,
+    symbols: BTreeMap<String, Symbol>,
+}
+
+impl ScenarioSetup {
+    pub fn account(&self, name: &str) -> Address {
+        *self
+            .accounts
+            .get(name)
+            .unwrap_or_else(|| panic!("Unknown account {name}"))
+    }
+
+    pub fn symbol(&self, ticker: &str) -> Symbol {
+        *self
+            .symbols
+            .get(ticker)
+            .unwrap_or_else(|| panic!("Unknown token {ticker}"))
+    }
+
+    pub fn address(&self, party: impl Into<Party>) -> Address {
+        match party.into() {
+            Party::Address(address) => address,
+            Party::Account(name) => self.account(&name),
+        }
+    }
+
+    pub fn balance(&self, party: impl Into<Party>, ticker: &str) -> TokenAmount {
+        self.setup
+            .balance(self.address(party), self.symbol(ticker))
+            .expect("Could not get balance")
+    }
+
+    fn run(&mut self, step: Step) {
+        match step {
+            Step::Account { name, account_type } => {
+                let id = self.setup.create_account_(account_type);
+                self.accounts.insert(name, id);
+            }
+            Step::Multisig {
+                name,
+                approvers,
+                threshold,
+            } => {
+                let roles = approvers
+                    .into_iter()
+                    .map(|p| {
+                        (
+                            self.address(p),
+                            BTreeSet::from([
+                                account::Role::CanMultisigSubmit,
+                                account::Role::CanMultisigApprove,
+                            ]),
+                        )
+                    })
+                    .collect();
+                let args = account::CreateArgs {
+                    roles: Some(roles),
+                    features: account::features::FeatureSet::from_iter([
+                        MultisigAccountFeature::create(Some(threshold), None, None).as_feature(),
+                    ]),
+                    ..create_account_args(AccountType::Multisig)
+                };
+                let id =
+                    AccountModuleBackend::create(&mut self.setup.module_impl, &self.setup.id, args)
+                        .expect("Could not create multisig account")
+                        .id;
+                self.accounts.insert(name, id);
+            }
+            Step::Token {
+                ticker,
+                distribution,
+            } => {
+                let initial_distribution = distribution
+                    .into_iter()
+                    .map(|(p, amount)| (self.address(p), amount))
+                    .collect::<LedgerTokensAddressMap>();
+                let args = TokenCreateArgs {
+                    summary: TokenInfoSummary {
+                        name: ticker.clone(),
+                        ticker: ticker.clone(),
+                        decimals: 9,
+                    },
+                    owner: None,
+                    initial_distribution: Some(initial_distribution),
+                    maximum_supply: None,
+                    extended_info: None,
+                    memo: None,
+                };
+                let symbol = LedgerTokensModuleBackend::create(
+                    &mut self.setup.module_impl,
+                    &self.setup.id,
+                    args,
+                )
+                .expect("Could not create token")
+                .info
+                .symbol;
+                self.symbols.insert(ticker, symbol);
+            }
+            Step::Balance {
+                party,
+                amount,
+                ticker,
+            } => {
+                let (id, symbol) = (self.address(party), self.symbol(&ticker));
+                self.setup.set_balance(id, amount, symbol);
+            }
+        }
+    }
+}
+
+impl Deref for ScenarioSetup {
+    type Target = Setup;
+
+    fn deref(&self) -> &Self::Target {
+        &self.setup
+    }
+}
+
+impl DerefMut for ScenarioSetup {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.setup
+    }
+}
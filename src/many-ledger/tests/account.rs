@@ -1,13 +1,18 @@
 use async_channel::unbounded;
 use many_identity::testing::identity;
 use many_identity::Address;
+use many_ledger::migration::account_gc::ACCOUNT_GC_MIGRATION;
+use many_ledger::migration::account_index::ACCOUNT_INDEX_MIGRATION;
+use many_ledger::migration::subresource_history::SUBRESOURCE_HISTORY_MIGRATION;
 use many_ledger::module::LedgerModuleImpl;
 use many_ledger_test_utils::*;
 use many_modules::account;
 use many_modules::account::features::{FeatureInfo, TryCreateFeature};
 use many_modules::account::AccountModuleBackend;
+use many_modules::events;
 use many_protocol::{context::Context, RequestMessage};
 use many_types::{Either, VecOrSingle};
+use serde_json::json;
 use std::collections::{BTreeMap, BTreeSet};
 
 fn account_info(
@@ -115,6 +120,70 @@ fn set_description_non_owner() {
     );
 }
 
+#[test]
+/// Verify owners can set the account metadata, within its limits
+fn set_metadata() {
+    let SetupWithAccount {
+        mut module_impl,
+        id,
+        account_id,
+    } = setup_with_account(AccountType::Multisig);
+    let metadata = BTreeMap::from([
+        ("cost-center".to_string(), "1234".to_string()),
+        ("external-id".to_string(), "abc".to_string()),
+    ]);
+    let result = module_impl.set_metadata(
+        &id,
+        account::SetMetadataArgs {
+            account: account_id,
+            metadata: metadata.clone(),
+        },
+    );
+    assert!(result.is_ok());
+    assert_eq!(
+        account_info(&module_impl, &id, &account_id).metadata,
+        Some(metadata)
+    );
+
+    let result = module_impl.set_metadata(
+        &identity(1),
+        account::SetMetadataArgs {
+            account: account_id,
+            metadata: BTreeMap::new(),
+        },
+    );
+    assert_eq!(
+        result.unwrap_err().code(),
+        account::errors::user_needs_role("owner").code()
+    );
+
+    let result = module_impl.set_metadata(
+        &id,
+        account::SetMetadataArgs {
+            account: account_id,
+            metadata: BTreeMap::from([(
+                "key".to_string(),
+                "v".repeat(account::METADATA_VALUE_MAX_LENGTH + 1),
+            )]),
+        },
+    );
+    assert_eq!(
+        result.unwrap_err().code(),
+        account::errors::invalid_metadata("").code()
+    );
+
+    // Empty metadata removes it.
+    let result = module_impl.set_metadata(
+        &id,
+        account::SetMetadataArgs {
+            account: account_id,
+            metadata: BTreeMap::new(),
+        },
+    );
+    assert!(result.is_ok());
+    assert_eq!(account_info(&module_impl, &id, &account_id).metadata, None);
+}
+
 #[test]
 /// Verify we can list account roles
 fn list_roles() {
@@ -576,3 +645,208 @@ fn empty_feature_add_features() {
     assert!(result.is_err());
     assert_many_err(result, account::errors::empty_feature());
 }
+
+fn list_subresources(
+    module_impl: &LedgerModuleImpl,
+    account: Address,
+    start: Option<u32>,
+    count: Option<u64>,
+) -> account::ListSubresourcesReturn {
+    module_impl
+        .list_subresources(
+            &Address::anonymous(),
+            account::ListSubresourcesArgs {
+                account,
+                start,
+                count,
+            },
+            Context::new(RequestMessage::default(), unbounded().0),
+        )
+        .unwrap()
+}
+
+#[test]
+/// Verify we can list the accounts allocated by the server, with their
+/// creation height once the migration is active
+fn subresources() {
+    let mut harness = Setup::new_with_migrations(true, [(2, &SUBRESOURCE_HISTORY_MIGRATION)], true);
+    let (_, a0) = harness.block(|h| h.create_account_(AccountType::Multisig));
+    harness.block(|_| {});
+    harness.block(|_| {});
+    let (h2, a2) = harness.block(|h| h.create_account_(AccountType::Multisig));
+    let server = a0.public_key().unwrap();
+
+    let list = list_subresources(&harness.module_impl, server, None, None);
+    let next = a2.subresource_id().unwrap() + 1;
+    assert_eq!(list.next_subresource, next);
+    assert_eq!(list.subresources.len(), next as usize);
+
+    let last = &list.subresources[list.subresources.len() - 2..];
+    assert_eq!(last[0].address, a0);
+    assert_eq!(last[0].height, None);
+    assert_eq!(last[1].address, a2);
+    assert_eq!(last[1].height, Some(h2));
+
+    let list = list_subresources(&harness.module_impl, server, a2.subresource_id(), Some(10));
+    assert_eq!(list.subresources.len(), 1);
+    assert_eq!(list.subresources[0].address, a2);
+
+    // Other addresses do not allocate subresources.
+    let list = list_subresources(&harness.module_impl, identity(1), None, None);
+    assert!(list.subresources.is_empty());
+    assert_eq!(list.next_subresource, 0);
+}
+
+fn list_for_identity(module_impl: &LedgerModuleImpl, address: Address) -> Vec<Address> {
+    module_impl
+        .list_for_identity(
+            &Address::anonymous(),
+            account::ListForIdentityArgs { address },
+            Context::new(RequestMessage::default(), unbounded().0),
+        )
+        .unwrap()
+        .accounts
+        .into_iter()
+        .map(|membership| membership.account)
+        .collect()
+}
+
+#[test]
+/// Verify we can list the accounts where an identity holds roles, including
+/// the accounts created before the index migration
+fn accounts_for_identity() {
+    let mut harness = Setup::new_with_migrations(true, [(3, &ACCOUNT_INDEX_MIGRATION)], true);
+    let (_, a0) = harness.block(|h| h.create_account_(AccountType::Multisig));
+    harness.block(|_| {});
+    harness.block(|_| {});
+    let (_, a1) = harness.block(|h| h.create_account_(AccountType::Ledger));
+
+    assert_eq!(list_for_identity(&harness.module_impl, identity(2)), {
+        let mut accounts = vec![a0, a1];
+        accounts.sort_by_key(|a| a.to_string());
+        accounts
+    });
+    assert_eq!(
+        list_for_identity(&harness.module_impl, identity(3)),
+        vec![a0]
+    );
+    assert_eq!(list_for_identity(&harness.module_impl, a1), vec![a1]);
+    assert!(list_for_identity(&harness.module_impl, identity(4)).is_empty());
+
+    let membership = harness
+        .module_impl
+        .list_for_identity(
+            &Address::anonymous(),
+            account::ListForIdentityArgs {
+                address: identity(3),
+            },
+            Context::new(RequestMessage::default(), unbounded().0),
+        )
+        .unwrap()
+        .accounts
+        .remove(0);
+    assert_eq!(
+        membership.roles,
+        BTreeSet::from([account::Role::CanMultisigSubmit])
+    );
+    assert!(membership
+        .features
+        .has_id(account::features::multisig::MultisigAccountFeature::ID));
+
+    // Removing the roles of an identity, or disabling the account, removes it
+    // from the list.
+    let id = harness.id;
+    harness.block(|h| {
+        h.module_impl
+            .remove_roles(
+                &id,
+                account::RemoveRolesArgs {
+                    account: a1,
+                    roles: BTreeMap::from([(
+                        identity(2),
+                        BTreeSet::from([account::Role::CanLedgerTransact]),
+                    )]),
+                },
+            )
+            .unwrap();
+        h.module_impl
+            .disable(&id, account::DisableArgs { account: a0 })
+            .unwrap();
+    });
+    assert!(list_for_identity(&harness.module_impl, identity(2)).is_empty());
+    assert!(list_for_identity(&harness.module_impl, identity(3)).is_empty());
+}
+
+fn account_exists(module_impl: &LedgerModuleImpl, account_id: Address) -> bool {
+    account::AccountModuleBackend::info(
+        module_impl,
+        &Address::anonymous(),
+        account::InfoArgs {
+            account: account_id,
+        },
+        Context::new(RequestMessage::default(), unbounded().0),
+    )
+    .is_ok()
+}
+
+#[test]
+/// Verify disabled accounts are pruned after the retention period, including
+/// the accounts disabled before the GC migration
+fn prune_disabled() {
+    let mut harness = Setup::new_with_migrations(
+        true,
+        [
+            MigrationHarness::from((1, &ACCOUNT_INDEX_MIGRATION)),
+            MigrationHarness::from((3, &ACCOUNT_GC_MIGRATION))
+                .with_extra(json!({ "retention_secs": 10 })),
+        ],
+        true,
+    );
+    harness.inc_time(1_000);
+    let id = harness.id;
+    let disable = |h: &mut Setup, account| {
+        h.module_impl
+            .disable(&id, account::DisableArgs { account })
+            .unwrap();
+    };
+
+    let (_, a0) = harness.block(|h| h.create_account_(AccountType::Multisig));
+    harness.block(|h| disable(h, a0));
+    harness.block(|_| {});
+    let (_, a1) = harness.block(|h| h.create_account_(AccountType::Multisig));
+    let (_, a2) = harness.block(|h| h.create_account_(AccountType::Multisig));
+    harness.block(|h| disable(h, a1));
+
+    harness.block(|_| {});
+    assert!(account_exists(&harness.module_impl, a0));
+    assert!(account_exists(&harness.module_impl, a1));
+
+    harness.inc_time(20);
+    harness.block(|_| {});
+    assert!(!account_exists(&harness.module_impl, a0));
+    assert!(!account_exists(&harness.module_impl, a1));
+    assert!(account_exists(&harness.module_impl, a2));
+    assert_eq!(
+        list_for_identity(&harness.module_impl, identity(3)),
+        vec![a2]
+    );
+
+    let pruned: BTreeSet<Address> = events::EventsModuleBackend::list(
+        &harness.module_impl,
+        events::ListArgs {
+            count: None,
+            order: None,
+            filter: None,
+            cursor: None,
+        },
+    )
+    .unwrap()
+    .events
+    .into_iter()
+    .filter_map(|event| match event.content {
+        events::EventInfo::AccountPrune { account, .. } => Some(account),
+        _ => None,
+    })
+    .collect();
+    assert_eq!(pruned, BTreeSet::from([a0, a1]));
+}
@@ -0,0 +1,131 @@
+use async_channel::unbounded;
+use many_error::ManyError;
+use many_identity::testing::identity;
+use many_identity::Address;
+use many_ledger::migration::attest::ATTEST_MIGRATION;
+use many_ledger_test_utils::*;
+use many_modules::attest::{self, AttestModuleBackend};
+use many_protocol::context::Context;
+use many_protocol::RequestMessage;
+use many_types::cbor::CborAny;
+use many_types::Timestamp;
+use serde_json::json;
+use std::collections::BTreeSet;
+
+fn setup() -> Setup {
+    setup_with_migration(
+        &ATTEST_MIGRATION,
+        json!({ "attestors": [identity(1).to_string(), identity(2).to_string()] }),
+    )
+}
+
+fn publish(
+    h: &mut Setup,
+    attestor: Address,
+    kind: &str,
+    expiration: Option<Timestamp>,
+) -> Result<attest::PublishReturns, ManyError> {
+    h.module_impl.publish(
+        &attestor,
+        attest::PublishArgs {
+            subject: identity(10),
+            kind: kind.to_string(),
+            value: CborAny::Int(2),
+            expiration,
+        },
+    )
+}
+
+fn get(h: &Setup, attestor: Option<Address>) -> Vec<attest::Claim> {
+    h.module_impl
+        .get(
+            &Address::anonymous(),
+            attest::GetArgs {
+                subject: identity(10),
+                attestor,
+            },
+            Context::new(RequestMessage::default(), unbounded().0),
+        )
+        .unwrap()
+        .claims
+}
+
+#[test]
+fn info() {
+    let mut harness = setup();
+    harness.block(|_| {});
+    let info = harness
+        .module_impl
+        .info(&Address::anonymous(), attest::InfoArgs)
+        .unwrap();
+    assert_eq!(info.attestors, BTreeSet::from([identity(1), identity(2)]));
+}
+
+#[test]
+fn publish_and_get() {
+    let mut harness = setup();
+    harness.block(|h| {
+        publish(h, identity(1), "kyc", None).unwrap();
+        publish(h, identity(2), "member.of", None).unwrap();
+    });
+
+    let claims = get(&harness, None);
+    assert_eq!(claims.len(), 2);
+    assert_eq!(claims[0].attestor, identity(1));
+    assert_eq!(claims[0].kind, "kyc");
+    assert_eq!(claims[0].value, CborAny::Int(2));
+
+    let claims = get(&harness, Some(identity(2)));
+    assert_eq!(claims.len(), 1);
+    assert_eq!(claims[0].kind, "member.of");
+}
+
+#[test]
+fn publish_untrusted() {
+    let mut harness = setup();
+    let (_, result) = harness.block(|h| publish(h, identity(3), "kyc", None));
+    assert_eq!(
+        result.unwrap_err().code(),
+        attest::not_an_attestor("").code()
+    );
+    assert!(get(&harness, None).is_empty());
+}
+
+#[test]
+fn expired() {
+    let mut harness = setup();
+    let expiration = Timestamp::new(1_000_100).unwrap();
+    harness.block(|h| publish(h, identity(1), "kyc", Some(expiration)).unwrap());
+    assert_eq!(get(&harness, None).len(), 1);
+
+    harness.inc_time(200);
+    harness.block(|_| {});
+    assert!(get(&harness, None).is_empty());
+}
+
+#[test]
+fn revoke() {
+    let mut harness = setup();
+    harness.block(|h| publish(h, identity(1), "kyc", None).unwrap());
+
+    let revoke = |h: &mut Setup, attestor: Address| {
+        h.module_impl.revoke(
+            &attestor,
+            attest::RevokeArgs {
+                subject: identity(10),
+                kind: "kyc".to_string(),
+            },
+        )
+    };
+
+    // Only the attestor of a claim can revoke it.
+    let (_, result) = harness.block(|h| revoke(h, identity(2)));
+    assert_eq!(
+        result.unwrap_err().code(),
+        attest::claim_not_found("").code()
+    );
+
+    let (_, result) = harness.block(|h| revoke(h, identity(1)));
+    assert!(result.is_ok());
+    assert!(get(&harness, None).is_empty());
+}
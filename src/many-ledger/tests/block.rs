@@ -1,6 +1,9 @@
 //! Tests regarding blockchain behaviour.
 use many_identity::testing::identity;
+use many_identity::Address;
 use many_ledger_test_utils::*;
+use many_modules::abci_backend::ManyAbciModuleBackend;
+use many_modules::events::{self, digest, EventsModuleBackend};
 
 /// Test that out of order keys at commit in a blockchain don't cause a problem.
 #[test]
@@ -56,3 +59,67 @@ fn query_in_flight() {
 
     assert_eq!(harness.balance_(harness.id), 996u32);
 }
+
+/// The events digest of the last block covers the events of that block only.
+#[test]
+fn events_digest() {
+    let mut harness = Setup::new(true);
+    harness.set_balance(harness.id, 1_000, *MFX_SYMBOL);
+
+    // Event IDs of the first blocks overlap, skip them.
+    harness.block(|_| {});
+    harness.block(|_| {});
+    harness.block(|harness| harness.send_(harness.id, identity(2), 1u32));
+    harness.block(|harness| {
+        harness.send_(harness.id, identity(3), 1u32);
+        harness.send_(harness.id, identity(4), 1u32);
+        harness.send_(harness.id, identity(5), 1u32);
+    });
+
+    let info = ManyAbciModuleBackend::info(&harness.module_impl).unwrap();
+    let events_digest = info.events_digest.unwrap();
+
+    let list = harness
+        .module_impl
+        .list(
+            &Address::anonymous(),
+            events::ListArgs {
+                count: None,
+                order: Some(many_types::SortOrder::Ascending),
+                filter: None,
+                cursor: None,
+            },
+        )
+        .unwrap();
+    let encoded: Vec<Vec<u8>> = list
+        .events
+        .iter()
+        .map(|e| minicbor::to_vec(e).unwrap())
+        .collect();
+    assert_eq!(encoded.len(), 4);
+
+    let (previous, block_events) = encoded.split_at(1);
+    assert_eq!(
+        digest::events_digest(block_events).as_slice(),
+        events_digest.as_slice()
+    );
+    for (i, event) in block_events.iter().enumerate() {
+        let proof = digest::inclusion_proof(block_events, i).unwrap();
+        assert!(digest::verify_inclusion(&events_digest, event, &proof));
+    }
+
+    let proof = digest::inclusion_proof(&encoded, 0).unwrap();
+    assert!(!digest::verify_inclusion(
+        &events_digest,
+        &previous[0],
+        &proof
+    ));
+
+    // A block without events has the empty digest.
+    harness.block(|_| {});
+    let info = ManyAbciModuleBackend::info(&harness.module_impl).unwrap();
+    assert_eq!(
+        info.events_digest.unwrap().as_slice(),
+        digest::empty_digest().as_slice()
+    );
+}
@@ -0,0 +1,98 @@
+use many_error::ManyError;
+use many_identity::testing::identity;
+use many_identity::Address;
+use many_ledger::migration::compliance::COMPLIANCE_MIGRATION;
+use many_ledger_test_utils::*;
+use many_modules::compliance::{self, ComplianceModuleBackend, ListMode};
+use many_modules::EmptyArg;
+use serde_json::json;
+use std::collections::BTreeSet;
+
+fn setup(mode: &str) -> Setup {
+    let mut harness = setup_with_migration(
+        &COMPLIANCE_MIGRATION,
+        json!({ "admin": identity(9).to_string(), "mode": mode }),
+    );
+    harness.set_balance(identity(1), 1000, *MFX_SYMBOL);
+    harness
+}
+
+fn add(h: &mut Setup, sender: Address, address: Address) -> Result<(), ManyError> {
+    h.module_impl
+        .add(&sender, compliance::AddArgs { address })
+        .map(|_| ())
+}
+
+fn remove(h: &mut Setup, sender: Address, address: Address) -> Result<(), ManyError> {
+    h.module_impl
+        .remove(&sender, compliance::RemoveArgs { address })
+        .map(|_| ())
+}
+
+#[test]
+fn deny() {
+    let mut harness = setup("deny");
+    harness.block(|h| add(h, identity(9), identity(2)).unwrap());
+
+    let (_, result) = harness.block(|h| h.send(identity(1), identity(2), 10u16, *MFX_SYMBOL));
+    assert_eq!(
+        result.unwrap_err().code(),
+        compliance::address_blocked("").code()
+    );
+    let (_, result) = harness.block(|h| h.send(identity(1), identity(3), 10u16, *MFX_SYMBOL));
+    assert!(result.is_ok());
+
+    harness.block(|h| remove(h, identity(9), identity(2)).unwrap());
+    let (_, result) = harness.block(|h| h.send(identity(1), identity(2), 10u16, *MFX_SYMBOL));
+    assert!(result.is_ok());
+}
+
+#[test]
+fn allow() {
+    let mut harness = setup("allow");
+    harness.block(|h| add(h, identity(9), identity(1)).unwrap());
+
+    let (_, result) = harness.block(|h| h.send(identity(1), identity(2), 10u16, *MFX_SYMBOL));
+    assert_eq!(
+        result.unwrap_err().code(),
+        compliance::address_blocked("").code()
+    );
+
+    harness.block(|h| add(h, identity(9), identity(2)).unwrap());
+    let (_, result) = harness.block(|h| h.send(identity(1), identity(2), 10u16, *MFX_SYMBOL));
+    assert!(result.is_ok());
+
+    let info = harness
+        .module_impl
+        .info(&Address::anonymous(), EmptyArg)
+        .unwrap();
+    assert_eq!(info.mode, ListMode::Allow);
+    assert_eq!(info.admin, identity(9));
+    let list = harness
+        .module_impl
+        .list(&Address::anonymous(), EmptyArg)
+        .unwrap();
+    assert_eq!(list.addresses, BTreeSet::from([identity(1), identity(2)]));
+}
+
+#[test]
+fn admin_only() {
+    let mut harness = setup("deny");
+    let (_, result) = harness.block(|h| add(h, identity(1), identity(2)));
+    assert_eq!(
+        result.unwrap_err().code(),
+        compliance::not_compliance_admin("").code()
+    );
+
+    harness.block(|h| add(h, identity(9), identity(2)).unwrap());
+    let (_, result) = harness.block(|h| add(h, identity(9), identity(2)));
+    assert_eq!(
+        result.unwrap_err().code(),
+        compliance::already_listed("").code()
+    );
+    let (_, result) = harness.block(|h| remove(h, identity(9), identity(3)));
+    assert_eq!(
+        result.unwrap_err().code(),
+        compliance::not_listed("").code()
+    );
+}
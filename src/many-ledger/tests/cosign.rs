@@ -0,0 +1,236 @@
+use coset::CoseSign1;
+use many_error::ManyError;
+use many_identity::testing::identity;
+use many_identity::{Address, Identity, Verifier};
+use many_identity_dsa::ed25519::{generate_random_ed25519_identity, Ed25519Identity};
+use many_identity_dsa::CoseKeyVerifier;
+use many_ledger::migration::cosign::COSIGN_MIGRATION;
+use many_ledger_test_utils::*;
+use many_modules::account::features::cosign::{
+    self, AccountCosignModuleBackend, CosignAccountFeature, CosignedTransaction,
+};
+use many_modules::account::features::multisig;
+use many_modules::account::features::{FeatureInfo, FeatureSet};
+use many_modules::account::{self, AccountModuleBackend};
+use many_modules::events::AccountMultisigTransaction;
+use many_modules::ledger::SendArgs;
+use many_protocol::ResponseMessage;
+use many_types::Timestamp;
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+
+struct CosignSetup {
+    harness: Setup,
+    account: Address,
+    signers: [Ed25519Identity; 2],
+}
+
+/// An account where the owner and two signers must agree on a transaction.
+fn setup() -> CosignSetup {
+    let mut harness = Setup::new_with_migrations(false, [(0, &COSIGN_MIGRATION)], true);
+    let signers = [
+        generate_random_ed25519_identity(),
+        generate_random_ed25519_identity(),
+    ];
+    let roles = signers
+        .iter()
+        .map(|s| {
+            (
+                s.address(),
+                BTreeSet::from([account::Role::CanMultisigApprove]),
+            )
+        })
+        .collect::<BTreeMap<_, _>>();
+    let account = AccountModuleBackend::create(
+        &mut harness.module_impl,
+        &harness.id,
+        account::CreateArgs {
+            description: None,
+            roles: Some(roles),
+            features: FeatureSet::from_iter([
+                account::features::ledger::AccountLedger.as_feature(),
+                CosignAccountFeature::create(Some(3)).as_feature(),
+            ]),
+        },
+    )
+    .unwrap()
+    .id;
+    harness.set_balance(account, 1_000, *MFX_SYMBOL);
+
+    CosignSetup {
+        harness,
+        account,
+        signers,
+    }
+}
+
+fn transaction(account: Address, timeout: Timestamp) -> Vec<u8> {
+    minicbor::to_vec(CosignedTransaction {
+        account,
+        transaction: Box::new(AccountMultisigTransaction::Send(SendArgs {
+            from: Some(account),
+            to: identity(5),
+            symbol: *MFX_SYMBOL,
+            amount: 100u64.into(),
+            memo: None,
+            reference: None,
+        })),
+        timeout,
+    })
+    .unwrap()
+}
+
+fn in_an_hour() -> Timestamp {
+    Timestamp::now()
+        .checked_add(Duration::from_secs(60 * 60))
+        .unwrap()
+}
+
+fn execute(
+    setup: &mut CosignSetup,
+    transaction: &[u8],
+    signers: &[&Ed25519Identity],
+) -> Result<ResponseMessage, ManyError> {
+    let signatures = signers
+        .iter()
+        .map(|s| CosignedTransaction::cosign(*s, transaction).unwrap())
+        .collect();
+    let id = setup.harness.id;
+    setup.harness.module_impl.cosign_execute(
+        &id,
+        cosign::ExecuteArgs {
+            transaction: transaction.to_vec().into(),
+            signatures,
+        },
+    )
+}
+
+#[test]
+fn execute_with_enough_signatures() {
+    let mut setup = setup();
+    let [a, b] = setup.signers.clone();
+    let transaction = transaction(setup.account, in_an_hour());
+
+    let response = execute(&mut setup, &transaction, &[&a, &b]).unwrap();
+    assert_eq!(response.from, setup.account);
+    assert!(response.data.is_ok());
+    assert_eq!(setup.harness.balance_(identity(5)), 100u64);
+    assert_eq!(setup.harness.balance_(setup.account), 900u64);
+}
+
+#[test]
+fn not_enough_signatures() {
+    let mut setup = setup();
+    let [a, _] = setup.signers.clone();
+    let transaction = transaction(setup.account, in_an_hour());
+
+    let err = execute(&mut setup, &transaction, &[&a]).unwrap_err();
+    assert_eq!(
+        err.code(),
+        cosign::errors::not_enough_cosignatures("", "").code()
+    );
+    assert_eq!(setup.harness.balance_(identity(5)), 0u64);
+}
+
+#[test]
+fn replay() {
+    let mut setup = setup();
+    let [a, b] = setup.signers.clone();
+    let transaction = transaction(setup.account, in_an_hour());
+
+    execute(&mut setup, &transaction, &[&a, &b]).unwrap();
+    let err = execute(&mut setup, &transaction, &[&a, &b]).unwrap_err();
+    assert_eq!(
+        err.code(),
+        cosign::errors::cosigned_transaction_already_executed().code()
+    );
+    assert_eq!(setup.harness.balance_(identity(5)), 100u64);
+}
+
+#[test]
+fn expired() {
+    let mut setup = setup();
+    let [a, b] = setup.signers.clone();
+    let transaction = transaction(setup.account, Timestamp::new(1).unwrap());
+
+    let err = execute(&mut setup, &transaction, &[&a, &b]).unwrap_err();
+    assert_eq!(
+        err.code(),
+        cosign::errors::cosigned_transaction_expired().code()
+    );
+}
+
+#[test]
+fn unauthorized_signer() {
+    let mut setup = setup();
+    let [a, _] = setup.signers.clone();
+    let stranger = generate_random_ed25519_identity();
+    let transaction = transaction(setup.account, in_an_hour());
+
+    let err = execute(&mut setup, &transaction, &[&a, &stranger]).unwrap_err();
+    assert_eq!(
+        err.code(),
+        multisig::errors::user_cannot_approve_transaction().code()
+    );
+}
+
+#[test]
+fn signature_of_another_transaction() {
+    let mut setup = setup();
+    let [a, b] = setup.signers.clone();
+    let transaction = transaction(setup.account, in_an_hour());
+    let signatures = vec![
+        CosignedTransaction::cosign(&a, &transaction).unwrap(),
+        CosignedTransaction::cosign(&b, b"another transaction").unwrap(),
+    ];
+
+    let id = setup.harness.id;
+    let err = setup
+        .harness
+        .module_impl
+        .cosign_execute(
+            &id,
+            cosign::ExecuteArgs {
+                transaction: transaction.into(),
+                signatures,
+            },
+        )
+        .unwrap_err();
+    assert_eq!(err.code(), cosign::errors::invalid_cosignature("").code());
+}
+
+/// Verifies COSE keys, but attributes the signatures of one address to
+/// another, like a verifier of the server could for a WebAuthn credential.
+struct Alias {
+    from: Address,
+    to: Address,
+}
+
+impl Verifier for Alias {
+    fn verify_1(&self, envelope: &CoseSign1) -> Result<Address, ManyError> {
+        let address = CoseKeyVerifier.verify_1(envelope)?;
+        Ok(if address == self.from {
+            self.to
+        } else {
+            address
+        })
+    }
+}
+
+#[test]
+fn configured_verifier() {
+    let mut setup = setup();
+    let [a, b] = setup.signers.clone();
+    let stranger = generate_random_ed25519_identity();
+    let transaction = transaction(setup.account, in_an_hour());
+
+    assert!(execute(&mut setup, &transaction, &[&a, &stranger]).is_err());
+
+    setup.harness.module_impl.set_cosign_verifier(Alias {
+        from: stranger.address(),
+        to: b.address(),
+    });
+    let response = execute(&mut setup, &transaction, &[&a, &stranger]).unwrap();
+    assert!(response.data.is_ok());
+    assert_eq!(setup.harness.balance_(identity(5)), 100u64);
+}
@@ -0,0 +1,139 @@
+use many_identity::testing::identity;
+use many_identity::Address;
+use many_ledger::migration::event_redaction::{
+    redact_event, redacted, EVENT_REDACTION_MIGRATION, REDACTED_PREFIX,
+};
+use many_ledger_test_utils::*;
+use many_modules::events::{self, EventId, EventInfo, EventsModuleBackend};
+use many_modules::ledger::{self, LedgerCommandsModuleBackend};
+use many_types::{Memo, SortOrder};
+use serde_json::json;
+
+const SALT: [u8; 16] = [7; 16];
+
+fn send_with_memo(h: &mut Setup, to: Address, memo: &str) {
+    send(h, to, memo, None)
+}
+
+fn send(h: &mut Setup, to: Address, memo: &str, reference: Option<&str>) {
+    let id = h.id;
+    h.module_impl
+        .send(
+            &id,
+            ledger::SendArgs {
+                from: None,
+                to,
+                amount: 10u16.into(),
+                symbol: *MFX_SYMBOL,
+                memo: Some(Memo::try_from(memo.to_string()).unwrap()),
+                reference: reference.map(str::to_string),
+            },
+        )
+        .unwrap();
+}
+
+fn events(h: &Setup) -> Vec<EventInfo> {
+    h.module_impl
+        .list(events::ListArgs {
+            count: None,
+            order: Some(SortOrder::Ascending),
+            filter: None,
+            cursor: None,
+        })
+        .unwrap()
+        .events
+        .into_iter()
+        .map(|event| event.content)
+        .collect()
+}
+
+fn memos(h: &Setup) -> Vec<Option<Memo>> {
+    events(h)
+        .iter()
+        .map(|event| event.memo().cloned())
+        .collect()
+}
+
+#[test]
+fn redact() {
+    // The first event of the first block.
+    let first = EventId::first_of_height(1) + 1u32;
+    let migration = MigrationHarness::from((4, &EVENT_REDACTION_MIGRATION))
+        .with_extra(json!({ "events": [hex::encode(first)], "salt": hex::encode(SALT) }));
+    let mut harness = Setup::new_with_migrations(true, [migration], true);
+    let id = harness.id;
+    harness.set_balance(id, 1000, *MFX_SYMBOL);
+
+    harness.block(|h| send_with_memo(h, identity(1), "secret"));
+    harness.block(|h| send_with_memo(h, identity(2), "kept"));
+    harness.block(|_| {});
+    let secret = Memo::try_from("secret".to_string()).unwrap();
+    let kept = Memo::try_from("kept".to_string()).unwrap();
+    assert_eq!(memos(&harness), [Some(secret.clone()), Some(kept.clone())]);
+
+    harness.block(|_| {});
+
+    let redacted_memo = Memo::try_from(redacted(&SALT, &secret).unwrap()).unwrap();
+    assert_eq!(memos(&harness), [Some(redacted_memo), Some(kept)]);
+}
+
+#[test]
+fn salted() {
+    let memo = Memo::try_from("secret".to_string()).unwrap();
+    let hash = redacted(&SALT, &memo).unwrap();
+    assert!(hash.starts_with(REDACTED_PREFIX));
+    assert_ne!(hash, redacted(&[8; 16], &memo).unwrap());
+}
+
+#[test]
+fn redact_repeatedly() {
+    let first = EventId::first_of_height(1) + 1u32;
+    let second = EventId::first_of_height(2) + 1u32;
+    let migrations = [
+        MigrationHarness::from((4, &EVENT_REDACTION_MIGRATION))
+            .with_extra(json!({ "events": [hex::encode(first)], "salt": hex::encode(SALT) })),
+        MigrationHarness::from((5, &EVENT_REDACTION_MIGRATION))
+            .with_extra(json!({ "events": [hex::encode(second)], "salt": hex::encode(SALT) })),
+    ];
+    let mut harness = Setup::new_with_migrations(true, migrations, true);
+    let id = harness.id;
+    harness.set_balance(id, 1000, *MFX_SYMBOL);
+
+    harness.block(|h| send_with_memo(h, identity(1), "first"));
+    harness.block(|h| send(h, identity(2), "second", Some("invoice 42")));
+    harness.block(|_| {});
+    harness.block(|_| {});
+
+    // Only the first takedown ran.
+    let first_memo = Memo::try_from("first".to_string()).unwrap();
+    let first_memo = Memo::try_from(redacted(&SALT, &first_memo).unwrap()).unwrap();
+    assert_eq!(memos(&harness)[0], Some(first_memo.clone()));
+    assert!(matches!(
+        &events(&harness)[1],
+        EventInfo::Send { reference: Some(r), .. } if r == "invoice 42"
+    ));
+
+    harness.block(|_| {});
+
+    let second_memo = Memo::try_from("second".to_string()).unwrap();
+    let mut expected = Memo::try_from(redacted(&SALT, &second_memo).unwrap()).unwrap();
+    expected
+        .push_str(redacted(&SALT, &"invoice 42".to_string()).unwrap())
+        .unwrap();
+    assert_eq!(memos(&harness), [Some(first_memo), Some(expected)]);
+    assert!(matches!(
+        &events(&harness)[1],
+        EventInfo::Send {
+            reference: None,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn nothing_to_redact() {
+    let mut event = EventInfo::AccountDisable {
+        account: identity(1),
+    };
+    assert!(!redact_event(&mut event, &SALT).unwrap());
+}
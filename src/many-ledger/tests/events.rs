@@ -10,7 +10,7 @@ use many_modules::events::{
 };
 use many_modules::ledger;
 use many_modules::ledger::LedgerCommandsModuleBackend;
-use many_types::{CborRange, Memo, Timestamp};
+use many_types::{CborRange, Memo, SortOrder, Timestamp};
 use proptest::prelude::*;
 use proptest::test_runner::Config;
 use std::collections::BTreeMap;
@@ -32,6 +32,7 @@ fn send_(module_impl: &mut LedgerModuleImpl, from: Address, to: Address) {
             amount: 10u16.into(),
             symbol: *MFX_SYMBOL,
             memo: None,
+            reference: None,
         },
     );
     assert!(result.is_ok());
@@ -65,6 +66,7 @@ fn list() {
         count: None,
         order: None,
         filter: None,
+        cursor: None,
     });
     assert!(result.is_ok());
     let list_return = result.unwrap();
@@ -85,6 +87,7 @@ fn list_many() {
         count: None,
         order: None,
         filter: None,
+        cursor: None,
     });
     assert!(result.is_ok());
     let list_return = result.unwrap();
@@ -97,6 +100,7 @@ fn list_many() {
             count: None,
             order: None,
             filter: None,
+            cursor: None,
         })
         .unwrap();
     assert_eq!(list_return.nb_events, 2);
@@ -108,6 +112,7 @@ fn list_many() {
             count: None,
             order: None,
             filter: None,
+            cursor: None,
         })
         .unwrap();
     assert_eq!(list_return.nb_events, 3);
@@ -118,12 +123,56 @@ fn list_many() {
             count: Some(2),
             order: None,
             filter: None,
+            cursor: None,
         })
         .unwrap();
     assert_eq!(list_return.nb_events, 3);
     assert_eq!(list_return.events.len(), 2);
 }
 
+#[test]
+fn list_pages() {
+    let Setup {
+        mut module_impl,
+        id,
+        ..
+    } = setup();
+    for i in 1..=5 {
+        send(&mut module_impl, id, identity(i));
+    }
+
+    for order in [SortOrder::Ascending, SortOrder::Descending] {
+        let all = module_impl
+            .list(events::ListArgs {
+                order: Some(order.clone()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(all.events.len(), 5);
+        assert_eq!(all.next_cursor, None);
+
+        let mut cursor = None;
+        let mut pages = vec![];
+        loop {
+            let page = module_impl
+                .list(events::ListArgs {
+                    count: Some(2),
+                    order: Some(order.clone()),
+                    filter: None,
+                    cursor,
+                })
+                .unwrap();
+            pages.extend(page.events.into_iter().map(|e| e.id));
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        let ids: Vec<_> = all.events.into_iter().map(|e| e.id).collect();
+        assert_eq!(pages, ids);
+    }
+}
+
 #[test]
 fn list_blockchain() {
     let mut setup = Setup::new(true);
@@ -135,6 +184,7 @@ fn list_blockchain() {
         count: None,
         order: None,
         filter: None,
+        cursor: None,
     });
     assert!(result.is_ok());
     let list_return = result.unwrap();
@@ -151,6 +201,7 @@ fn list_blockchain() {
                 count: None,
                 order: None,
                 filter: None,
+                cursor: None,
             })
             .unwrap();
         assert_eq!(list_return.nb_events, i);
@@ -163,6 +214,7 @@ fn list_blockchain() {
             count: Some(2),
             order: None,
             filter: None,
+            cursor: None,
         })
         .unwrap();
     assert_eq!(list_return.nb_events, 3);
@@ -185,6 +237,7 @@ fn list_filter_account() {
             account: Some(vec![account_id].into()),
             ..events::EventFilter::default()
         }),
+        cursor: None,
     });
     assert!(result.is_ok());
     let list_return = result.unwrap();
@@ -218,6 +271,7 @@ fn list_filter_kind() {
             kind: Some(vec![events::EventKind::Send].into()),
             ..events::EventFilter::default()
         }),
+        cursor: None,
     });
     assert!(result.is_ok());
     let list_return = result.unwrap();
@@ -251,6 +305,7 @@ fn list_filter_date() {
             }),
             ..events::EventFilter::default()
         }),
+        cursor: None,
     });
     assert!(result.is_ok());
     let list_return = result.unwrap();
@@ -274,6 +329,7 @@ fn list_filter_date() {
             }),
             ..events::EventFilter::default()
         }),
+        cursor: None,
     });
     assert!(result.is_ok());
     let list_return = result.unwrap();
@@ -294,6 +350,7 @@ fn submit_args(
         execute_automatically,
         data_: None,
         memo_: None,
+        attachment: None,
     }
 }
 
@@ -323,6 +380,7 @@ proptest! {
                 ]),
                 ..events::EventFilter::default()
             })
+            cursor: None,
         }).expect("List should return a value");
 
         assert!(!result.events.is_empty());
@@ -337,6 +395,7 @@ proptest! {
                 ]),
                 ..events::EventFilter::default()
             })
+            cursor: None,
         }).expect("List should return a value");
         assert!(result.events.is_empty());
     }
@@ -0,0 +1,66 @@
+use many_identity::testing::identity;
+use many_ledger::storage::fault::Faults;
+use many_ledger::storage::LedgerStorage;
+use many_types::ledger::TokenAmount;
+use std::collections::BTreeMap;
+
+fn setup() -> LedgerStorage {
+    let symbol = identity(100);
+    let symbols = BTreeMap::from([(symbol, "MFX".to_string())]);
+    let balances = BTreeMap::from([(
+        identity(0),
+        BTreeMap::from([(symbol, TokenAmount::from(1000u16))]),
+    )]);
+    let persistent_path = tempfile::tempdir().unwrap();
+
+    LedgerStorage::new(persistent_path, true)
+        .unwrap()
+        .with_balances(&identity(2), &symbols, &balances)
+        .unwrap()
+        .build()
+        .unwrap()
+}
+
+/// A block which fails to be written can be committed again, without the
+/// changes made by the failed commit.
+fn assert_recovers(inject: impl FnOnce(&mut Faults)) {
+    let mut storage = setup();
+    let symbol = identity(100);
+    storage
+        .send(
+            &identity(0),
+            &identity(1),
+            &symbol,
+            TokenAmount::from(100u16),
+            None,
+            None,
+        )
+        .unwrap();
+    let height = storage.get_height().unwrap();
+
+    inject(storage.faults());
+    assert!(storage.commit().is_err());
+    assert_eq!(storage.get_height().unwrap(), height);
+
+    storage.faults().clear();
+    storage.commit().unwrap();
+    assert_eq!(storage.get_height().unwrap(), height + 1);
+    assert_eq!(
+        storage.get_balance(&identity(1), &symbol).unwrap(),
+        TokenAmount::from(100u16)
+    );
+}
+
+#[test]
+fn apply_failure() {
+    assert_recovers(|faults| {
+        faults.fail_apply(0);
+    });
+}
+
+#[test]
+fn commit_failure() {
+    assert_recovers(|faults| {
+        faults.fail_commit(0);
+    });
+}
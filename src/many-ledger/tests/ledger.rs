@@ -1,10 +1,25 @@
 use async_channel::unbounded;
+use many_identity::testing::identity;
 use many_identity::Address;
+use many_ledger::migration::data::TOTAL_SUPPLY_INDEX;
+use many_ledger::migration::supply_history::SUPPLY_HISTORY_MIGRATION;
+use many_ledger::migration::token_create::TOKEN_CREATE_MIGRATION;
+use many_ledger::migration::token_history::TOKEN_HISTORY_MIGRATION;
+use many_ledger::migration::tokens::TOKEN_MIGRATION;
+use many_ledger::module::LedgerModuleImpl;
 use many_ledger_test_utils::*;
+use many_modules::data::{
+    DataGetInfoArgs, DataModuleBackend, DataQueryArgs, DataValue, DataValueTypeGauge, Decimal,
+};
 use many_modules::ledger;
-use many_modules::ledger::{LedgerCommandsModuleBackend, LedgerModuleBackend, SendArgs};
+use many_modules::ledger::{
+    LedgerCommandsModuleBackend, LedgerMintBurnModuleBackend, LedgerModuleBackend, SendArgs,
+    TokenBurnArgs, TokenHistoryArgs, TokenListArgs, TokenMintArgs, TokenSupplyHistoryArgs,
+    TokenUpdateArgs,
+};
 use many_protocol::{context::Context, RequestMessage};
-use many_types::ledger::TokenAmount;
+use many_types::ledger::{LedgerTokensAddressMap, TokenAmount};
+use many_types::{SortOrder, VecOrSingle};
 use proptest::prelude::*;
 
 #[test]
@@ -20,6 +35,281 @@ fn info() {
     assert!(result.is_ok());
 }
 
+#[test]
+fn tokens_list() {
+    let Setup {
+        mut module_impl,
+        id,
+        ..
+    } = Setup::new_with_migrations(
+        false,
+        [(0, &TOKEN_MIGRATION), (0, &TOKEN_CREATE_MIGRATION)],
+        true,
+    );
+    for ticker in ["ABC", "DEF"] {
+        let mut args = default_token_create_args(None, None);
+        args.summary.ticker = ticker.to_string();
+        ledger::LedgerTokensModuleBackend::create(&mut module_impl, &id, args).unwrap();
+    }
+
+    let list = |args: TokenListArgs| {
+        ledger::LedgerTokensModuleBackend::list(
+            &module_impl,
+            &id,
+            args,
+            Context::new(RequestMessage::default(), unbounded().0),
+        )
+        .unwrap()
+        .tokens
+    };
+
+    // MFX and the two new tokens.
+    let all = list(TokenListArgs {
+        count: None,
+        order: Some(SortOrder::Ascending),
+        after: None,
+    });
+    assert_eq!(all.len(), 3);
+
+    let page = list(TokenListArgs {
+        count: Some(1),
+        order: Some(SortOrder::Ascending),
+        after: None,
+    });
+    assert_eq!(page, all[..1]);
+
+    let page = list(TokenListArgs {
+        count: Some(10),
+        order: Some(SortOrder::Ascending),
+        after: Some(all[0].symbol),
+    });
+    assert_eq!(page, all[1..]);
+
+    let page = list(TokenListArgs {
+        count: None,
+        order: Some(SortOrder::Descending),
+        after: Some(all[2].symbol),
+    });
+    assert_eq!(page, all[..2].iter().rev().cloned().collect::<Vec<_>>());
+}
+
+#[test]
+fn tokens_total_supply_data() {
+    let Setup {
+        mut module_impl,
+        id,
+        ..
+    } = Setup::new_with_migrations(
+        false,
+        [(0, &TOKEN_MIGRATION), (0, &TOKEN_CREATE_MIGRATION)],
+        true,
+    );
+    let gauges = |module_impl: &LedgerModuleImpl, count: u32| {
+        let indices: Vec<_> = (0..count)
+            .map(|i| TOTAL_SUPPLY_INDEX.with_index(i))
+            .collect();
+        let info = DataModuleBackend::get_info(
+            module_impl,
+            &id,
+            DataGetInfoArgs {
+                indices: VecOrSingle(indices.clone()),
+            },
+            Context::new(RequestMessage::default(), unbounded().0),
+        )
+        .unwrap();
+        let query = DataModuleBackend::query(
+            module_impl,
+            &id,
+            DataQueryArgs {
+                indices: VecOrSingle(indices.clone()),
+            },
+            Context::new(RequestMessage::default(), unbounded().0),
+        )
+        .unwrap();
+        assert_eq!(query.len(), count as usize);
+        indices
+            .into_iter()
+            .map(|index| (info[&index].shortname.clone(), query[&index].clone()))
+            .collect::<Vec<_>>()
+    };
+
+    // MFX.
+    let before = gauges(&module_impl, 1);
+    assert_eq!(before[0].0, "MFXTotalSupply");
+
+    // New tokens get the next indices, whatever their symbols.
+    let mut created = vec![];
+    for ticker in ["ABC", "DEF"] {
+        let mut args = default_token_create_args(None, None);
+        args.summary.ticker = ticker.to_string();
+        created.push(
+            ledger::LedgerTokensModuleBackend::create(&mut module_impl, &id, args)
+                .unwrap()
+                .info,
+        );
+    }
+
+    let after = gauges(&module_impl, 3);
+    assert_eq!(after[0], before[0]);
+    for (token, (shortname, value)) in created.iter().zip(&after[1..]) {
+        assert_eq!(*shortname, format!("{}TotalSupply", token.summary.ticker));
+        match value {
+            DataValue::Gauge(DataValueTypeGauge::Decimal(d)) => assert_eq!(
+                *d,
+                Decimal::new(
+                    token.supply.total.as_ref().clone(),
+                    -(token.summary.decimals as i64)
+                )
+            ),
+            _ => panic!("Expected a decimal gauge"),
+        }
+    }
+}
+
+#[test]
+fn tokens_too_many_decimals() {
+    let Setup {
+        mut module_impl,
+        id,
+        ..
+    } = Setup::new_with_migrations(
+        false,
+        [(0, &TOKEN_MIGRATION), (0, &TOKEN_CREATE_MIGRATION)],
+        true,
+    );
+    let mut args = default_token_create_args(None, None);
+    args.summary.decimals = TokenAmount::MAXIMUM_DECIMALS + 1;
+    let err = ledger::LedgerTokensModuleBackend::create(&mut module_impl, &id, args).unwrap_err();
+    assert_eq!(
+        err.code(),
+        many_ledger::error::too_many_decimals("", "").code()
+    );
+}
+
+#[test]
+fn tokens_history() {
+    let Setup {
+        mut module_impl,
+        id,
+        ..
+    } = Setup::new_with_migrations(
+        false,
+        [
+            (0, &TOKEN_MIGRATION),
+            (0, &TOKEN_CREATE_MIGRATION),
+            (0, &TOKEN_HISTORY_MIGRATION),
+        ],
+        true,
+    );
+    let symbol = ledger::LedgerTokensModuleBackend::create(
+        &mut module_impl,
+        &id,
+        default_token_create_args(None, None),
+    )
+    .unwrap()
+    .info
+    .symbol;
+
+    let update = |module_impl: &mut LedgerModuleImpl, ticker: &str| {
+        ledger::LedgerTokensModuleBackend::update(
+            module_impl,
+            &id,
+            TokenUpdateArgs {
+                symbol,
+                name: None,
+                ticker: Some(ticker.to_string()),
+                decimals: None,
+                owner: None,
+                memo: None,
+            },
+        )
+        .unwrap();
+    };
+    update(&mut module_impl, "ABC");
+    // Updating to the current ticker is a no-op.
+    update(&mut module_impl, "ABC");
+    update(&mut module_impl, "DEF");
+
+    let history = ledger::LedgerTokensModuleBackend::history(
+        &module_impl,
+        &id,
+        TokenHistoryArgs { symbol },
+        Context::new(RequestMessage::default(), unbounded().0),
+    )
+    .unwrap()
+    .history;
+    let tickers: Vec<_> = history.iter().map(|e| e.ticker.as_str()).collect();
+    assert_eq!(tickers, ["TTT", "ABC"]);
+    assert!(history.iter().all(|e| e.name == "Test Token"));
+}
+
+#[test]
+fn tokens_supply_history() {
+    let mut harness = Setup::new_with_migrations(
+        true,
+        [
+            (0, &TOKEN_MIGRATION),
+            (0, &TOKEN_CREATE_MIGRATION),
+            (0, &SUPPLY_HISTORY_MIGRATION),
+        ],
+        true,
+    );
+    let id = harness.id;
+    let (_, symbol) = harness.block(|h| {
+        ledger::LedgerTokensModuleBackend::create(
+            &mut h.module_impl,
+            &id,
+            default_token_create_args(None, Some(TokenAmount::from(10_000u64))),
+        )
+        .unwrap()
+        .info
+        .symbol
+    });
+    harness.block(|h| {
+        let distribution =
+            |amount: u64| LedgerTokensAddressMap::from([(identity(1), TokenAmount::from(amount))]);
+        h.module_impl
+            .mint(
+                &id,
+                TokenMintArgs {
+                    symbol,
+                    distribution: distribution(100),
+                    memo: None,
+                },
+            )
+            .unwrap();
+        h.module_impl
+            .burn(
+                &id,
+                TokenBurnArgs {
+                    symbol,
+                    distribution: distribution(23),
+                    memo: None,
+                    error_on_under_burn: None,
+                },
+            )
+            .unwrap();
+    });
+
+    let result = ledger::LedgerTokensModuleBackend::supply_history(
+        &harness.module_impl,
+        &id,
+        TokenSupplyHistoryArgs { symbol },
+        Context::new(RequestMessage::default(), unbounded().0),
+    )
+    .unwrap();
+    assert_eq!(result.maximum, Some(TokenAmount::from(10_000u64)));
+
+    let history = result.history;
+    assert_eq!(history.len(), 2);
+    assert!(history[0].height < history[1].height);
+    assert_eq!(history[0].minted, TokenAmount::from(1368u64));
+    assert_eq!(history[0].circulating, TokenAmount::from(1368u64));
+    assert_eq!(history[1].minted, TokenAmount::from(100u64));
+    assert_eq!(history[1].burned, TokenAmount::from(23u64));
+    assert_eq!(history[1].circulating, TokenAmount::from(1445u64));
+}
+
 proptest! {
     #[test]
     fn balance(amount in any::<u64>()) {
@@ -53,6 +343,7 @@ fn illegal_address() {
                 amount: TokenAmount::from(1_000u32),
                 symbol: *MFX_SYMBOL,
                 memo: None,
+                reference: None,
             },
         )
         .unwrap();
@@ -75,6 +366,7 @@ fn illegal_address() {
                 amount: TokenAmount::from(100u32),
                 symbol: *MFX_SYMBOL,
                 memo: None,
+                reference: None,
             },
         )
         .is_err());
@@ -87,3 +379,49 @@ fn illegal_address() {
         1_000u32.into(),
     );
 }
+
+#[test]
+fn balance_at_height() {
+    let mut harness = Setup::new(true);
+    harness.set_balance(identity(1), 1000, *MFX_SYMBOL);
+    let (h1, _) = harness.block(|h| h.send_(identity(1), identity(2), 100u16));
+    let (h2, _) = harness.block(|h| h.send_(identity(2), identity(3), 40u16));
+    let (h3, _) = harness.block(|h| h.send_(identity(1), identity(3), 10u16));
+
+    let balance_at = |h: &Setup, account: Address, height: u64| {
+        h.module_impl
+            .balance(
+                &account,
+                ledger::BalanceArgs {
+                    account: None,
+                    symbols: Some(vec![*MFX_SYMBOL].into()),
+                    height: Some(height),
+                },
+                Context::new(RequestMessage::default(), unbounded().0),
+            )
+            .map(|returns| {
+                returns
+                    .balances
+                    .get(&*MFX_SYMBOL)
+                    .cloned()
+                    .unwrap_or_default()
+            })
+    };
+
+    assert_eq!(balance_at(&harness, identity(1), h1).unwrap(), 900u16);
+    assert_eq!(balance_at(&harness, identity(2), h1).unwrap(), 100u16);
+    assert_eq!(balance_at(&harness, identity(3), h1).unwrap(), 0u16);
+    assert_eq!(balance_at(&harness, identity(2), h2).unwrap(), 60u16);
+    assert_eq!(balance_at(&harness, identity(3), h2).unwrap(), 40u16);
+    assert_eq!(balance_at(&harness, identity(1), h3).unwrap(), 890u16);
+    assert_eq!(balance_at(&harness, identity(3), h3).unwrap(), 50u16);
+
+    // Balances set before the first block are the starting point.
+    assert_eq!(balance_at(&harness, identity(1), 0).unwrap(), 1000u16);
+
+    let err = balance_at(&harness, identity(1), h3 + 1).unwrap_err();
+    assert_eq!(
+        err.code(),
+        many_ledger::error::height_not_reached("", "").code()
+    );
+}
@@ -1,6 +1,7 @@
 use {
     many_identity::testing::identity, many_ledger::error, many_ledger_test_utils::*,
-    many_modules::ledger, many_modules::ledger::LedgerCommandsModuleBackend, proptest::prelude::*,
+    many_modules::events, many_modules::ledger, many_modules::ledger::LedgerCommandsModuleBackend,
+    proptest::prelude::*,
 };
 
 proptest! {
@@ -19,6 +20,7 @@ proptest! {
             amount: half.into(),
             symbol: *MFX_SYMBOL,
             memo: None,
+            reference: None,
         });
         assert!(result.is_ok());
         verify_balance(&module_impl, id, *MFX_SYMBOL, (amount - half).into());
@@ -40,6 +42,7 @@ proptest! {
             amount: half.into(),
             symbol: *MFX_SYMBOL,
             memo: None,
+            reference: None,
         });
         assert!(result.is_ok());
         verify_balance(&module_impl, account_id, *MFX_SYMBOL, (amount - half).into());
@@ -62,6 +65,7 @@ fn send_account_missing_feature() {
             amount: 10u16.into(),
             symbol: *MFX_SYMBOL,
             memo: None,
+            reference: None,
         },
     );
     assert!(result.is_err());
@@ -83,8 +87,52 @@ fn send_invalid_account() {
             amount: 10u16.into(),
             symbol: *MFX_SYMBOL,
             memo: None,
+            reference: None,
         },
     );
     assert!(result.is_err());
     assert_eq!(result.unwrap_err().code(), error::unauthorized().code());
 }
+
+#[test]
+fn send_reference() {
+    let Setup {
+        mut module_impl,
+        id,
+        ..
+    } = setup();
+    module_impl
+        .set_balance_only_for_testing(id, 1000, *MFX_SYMBOL)
+        .expect("Unable to set balance for testing.");
+    let args = |reference: String| ledger::SendArgs {
+        from: Some(id),
+        to: identity(1),
+        amount: 10u16.into(),
+        symbol: *MFX_SYMBOL,
+        memo: None,
+        reference: Some(reference),
+    };
+
+    let result = module_impl.send(&id, args("x".repeat(ledger::MAX_REFERENCE_LENGTH + 1)));
+    assert_eq!(
+        result.unwrap_err().code(),
+        error::reference_too_long(0).code()
+    );
+
+    module_impl.send(&id, args("INV-0042".to_string())).unwrap();
+    let list = events::EventsModuleBackend::list(
+        &module_impl,
+        events::ListArgs {
+            count: None,
+            order: None,
+            filter: None,
+            cursor: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(list.events.len(), 1);
+    assert!(matches!(
+        &list.events[0].content,
+        events::EventInfo::Send { reference: Some(r), .. } if r == "INV-0042"
+    ));
+}
@@ -2,13 +2,15 @@ pub mod migration_;
 
 use async_channel::unbounded;
 use many_identity::testing::identity;
+use many_identity::Address;
 use many_ledger::migration::data::{
     ACCOUNT_COUNT_DATA_ATTRIBUTE, ACCOUNT_TOTAL_COUNT_INDEX, NON_ZERO_ACCOUNT_TOTAL_COUNT_INDEX,
 };
 use many_ledger_test_utils::*;
 use many_modules::{
+    attest, compliance,
     data::{DataGetInfoArgs, DataModuleBackend, DataQueryArgs},
-    EmptyArg,
+    names, relay, store, EmptyArg,
 };
 use many_protocol::{context::Context, RequestMessage};
 use many_types::{ledger::TokenAmount, VecOrSingle};
@@ -71,6 +73,56 @@ fn assert_metrics(harness: &Setup, expected_total: u32, expected_non_zero: u32)
     assert_eq!(non_zero, BigInt::from(expected_non_zero));
 }
 
+/// Modules activated by a migration are unknown until then.
+#[test]
+fn disabled_modules() {
+    assert_disabled(|h| {
+        attest::AttestModuleBackend::publish(
+            &mut h.module_impl,
+            &identity(1),
+            attest::PublishArgs {
+                subject: identity(10),
+                kind: "kyc".to_string(),
+                value: many_types::cbor::CborAny::Int(2),
+                expiration: None,
+            },
+        )
+    });
+    assert_disabled(|h| {
+        names::NamesModuleBackend::resolve(
+            &h.module_impl,
+            &Address::anonymous(),
+            names::ResolveArgs {
+                name: "alice".to_string(),
+            },
+        )
+    });
+    assert_disabled(|h| {
+        let request = RequestMessage::default()
+            .with_method("ledger.send".to_string())
+            .with_from(identity(1));
+        relay::RelayModuleBackend::relay(&mut h.module_impl, &identity(2), &request)
+    });
+    assert_disabled(|h| {
+        store::StoreModuleBackend::put(
+            &mut h.module_impl,
+            &identity(1),
+            store::PutArgs {
+                data: b"hello".to_vec().into(),
+            },
+        )
+    });
+    assert_disabled(|h| {
+        compliance::ComplianceModuleBackend::add(
+            &mut h.module_impl,
+            &identity(9),
+            compliance::AddArgs {
+                address: identity(2),
+            },
+        )
+    });
+}
+
 #[test]
 fn migration() {
     // Setup starts with 2 accounts because of staging/ledger_state.json5
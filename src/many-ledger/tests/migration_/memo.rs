@@ -25,6 +25,7 @@ fn memo_migration_works() {
             symbol: *MFX_SYMBOL,
             amount: TokenAmount::from(10_000u16),
             memo: None,
+            reference: None,
         });
 
         let memo = match (memo_str, memo_data) {
@@ -46,6 +47,7 @@ fn memo_migration_works() {
             timeout_in_secs: None,
             execute_automatically: None,
             data_: legacy_data.map(|x| x.as_bytes().to_vec().try_into().unwrap()),
+            attachment: None,
             // This should be ignored as it would be backward incompatible
             // before the migration is active.
             memo,
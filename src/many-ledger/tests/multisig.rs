@@ -3,15 +3,18 @@ use {
     many_error::ManyError,
     many_identity::testing::identity,
     many_identity::Address,
+    many_ledger::migration::store::STORE_MIGRATION,
     many_ledger::module::LedgerModuleImpl,
     many_ledger_test_utils::*,
     many_modules::account::features::multisig::AccountMultisigModuleBackend,
     many_modules::account::features::{multisig, TryCreateFeature},
+    many_modules::store::{self, StoreModuleBackend},
     many_modules::{account, events, ledger},
     many_protocol::{context::Context, RequestMessage},
     many_types::ledger::TokenAmount,
     proptest::prelude::*,
     proptest::test_runner::Config,
+    serde_json::json,
     std::collections::{BTreeMap, BTreeSet},
 };
 
@@ -60,6 +63,7 @@ fn submit_args(
         execute_automatically,
         data_: None,
         memo_: None,
+        attachment: None,
     }
 }
 
@@ -556,6 +560,7 @@ fn multisig_send_from_another_identity_owner() {
         symbol: *MFX_SYMBOL,
         amount: TokenAmount::from(10u16),
         memo: None,
+        reference: None,
     });
 
     // Create a multisig tx on acc1 which sends funds from acc2 to some Address
@@ -617,6 +622,7 @@ fn multisig_send_from_another_identity_with_perm() {
         symbol: *MFX_SYMBOL,
         amount: TokenAmount::from(10u16),
         memo: None,
+        reference: None,
     });
 
     // Create a multisig tx on acc1 which sends funds from acc2 to some Address
@@ -673,6 +679,7 @@ fn recursive_multisig() {
         symbol: *MFX_SYMBOL,
         amount: TokenAmount::from(10u16),
         memo: None,
+        reference: None,
     });
 
     let multisig_tx = events::AccountMultisigTransaction::AccountMultisigSubmit(
@@ -685,6 +692,7 @@ fn recursive_multisig() {
             execute_automatically: Some(false),
             data_: None,
             memo_: None,
+            attachment: None,
         },
     );
 
@@ -734,6 +742,7 @@ fn recursive_multisig() {
         symbol: *MFX_SYMBOL,
         amount: TokenAmount::from(10u16),
         memo: None,
+        reference: None,
     });
 
     let multisig_tx = events::AccountMultisigTransaction::AccountMultisigSubmit(
@@ -746,6 +755,7 @@ fn recursive_multisig() {
             execute_automatically: None,
             data_: None,
             memo_: None,
+            attachment: None,
         },
     );
 
@@ -781,6 +791,31 @@ fn recursive_multisig() {
     assert_eq!(setup.balance_(acc2), 999_990u32);
 }
 
+#[test]
+fn nested_too_deeply() {
+    let mut setup = Setup::new(false);
+    let acc1 = setup.create_account_as_(setup.id, AccountType::Multisig);
+
+    let send_tx = events::AccountMultisigTransaction::Send(ledger::SendArgs {
+        from: Some(acc1),
+        to: identity(1234),
+        symbol: *MFX_SYMBOL,
+        amount: TokenAmount::from(10u16),
+        memo: None,
+        reference: None,
+    });
+    let nested = (0..multisig::MULTISIG_TRANSACTION_MAX_DEPTH).fold(send_tx, |tx, _| {
+        events::AccountMultisigTransaction::AccountMultisigSubmit(submit_args(acc1, tx, None))
+    });
+
+    assert_many_err(
+        setup.create_multisig_as(acc1, acc1, nested),
+        multisig::errors::transaction_nested_too_deeply(
+            multisig::MULTISIG_TRANSACTION_MAX_DEPTH.to_string(),
+        ),
+    );
+}
+
 #[test]
 // Issue #179
 fn approve_executed_tx() {
@@ -808,3 +843,67 @@ fn approve_executed_tx() {
     let result = setup.multisig_approve(identity(6), &token);
     assert_many_err(result, multisig::errors::transaction_expired_or_withdrawn());
 }
+
+#[test]
+fn attachment() {
+    let migration =
+        MigrationHarness::from((1, &STORE_MIGRATION)).with_extra(json!({ "max_size": 1024 }));
+    let mut setup = Setup::new_with_migrations(true, [migration], true);
+    let (_, acc1) = setup.block(|setup| setup.create_account_(AccountType::Multisig));
+    setup.set_balance(acc1, 1_000_000, *MFX_SYMBOL);
+
+    let send_tx = events::AccountMultisigTransaction::Send(ledger::SendArgs {
+        from: Some(acc1),
+        to: identity(1234),
+        symbol: *MFX_SYMBOL,
+        amount: TokenAmount::from(10u16),
+        memo: None,
+        reference: None,
+    });
+    let submit_and_execute = |setup: &mut Setup, hash: Vec<u8>| {
+        let id = setup.id;
+        let args = multisig::SubmitTransactionArgs {
+            threshold: Some(1),
+            attachment: Some(multisig::Attachment {
+                hash: hash.into(),
+                uri: None,
+            }),
+            ..submit_args(acc1, send_tx.clone(), None)
+        };
+        setup.block(|setup| {
+            let token = setup
+                .module_impl
+                .multisig_submit_transaction(&id, args)?
+                .token;
+            setup.multisig_execute(&token)
+        })
+    };
+
+    let (_, result) = submit_and_execute(&mut setup, vec![1, 2, 3]);
+    assert_many_err(result, store::invalid_hash("010203"));
+
+    // The document must be in the object store when the transaction executes.
+    let hash = store::object_hash(b"invoice").to_vec();
+    let (_, response) = submit_and_execute(&mut setup, hash.clone());
+    assert_eq!(
+        response.unwrap().data.unwrap_err().code(),
+        multisig::errors::attachment_not_found("").code()
+    );
+    assert_eq!(setup.balance_(identity(1234)), 0u16);
+
+    setup.block(|setup| {
+        let id = setup.id;
+        setup
+            .module_impl
+            .put(
+                &id,
+                store::PutArgs {
+                    data: b"invoice".to_vec().into(),
+                },
+            )
+            .unwrap()
+    });
+    let (_, response) = submit_and_execute(&mut setup, hash);
+    assert!(response.unwrap().data.is_ok());
+    assert_eq!(setup.balance_(identity(1234)), 10u16);
+}
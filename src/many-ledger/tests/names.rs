@@ -0,0 +1,139 @@
+use many_error::ManyError;
+use many_identity::testing::identity;
+use many_identity::Address;
+use many_ledger::migration::names::NAMES_MIGRATION;
+use many_ledger_test_utils::*;
+use many_modules::names::{self, NamesModuleBackend};
+use many_types::ledger::TokenAmount;
+use serde_json::json;
+
+fn setup() -> Setup {
+    let mut harness = setup_with_migration(
+        &NAMES_MIGRATION,
+        json!({
+            "duration_in_secs": 100,
+            "fee_symbol": MFX_SYMBOL.to_string(),
+            "fee_amount": 10,
+            "fee_collector": identity(100).to_string(),
+        }),
+    );
+    harness.set_balance(harness.id, 1_000, *MFX_SYMBOL);
+    harness
+}
+
+fn register(
+    h: &mut Setup,
+    sender: Address,
+    name: &str,
+) -> Result<names::RegisterReturns, ManyError> {
+    h.module_impl.register(
+        &sender,
+        names::RegisterArgs {
+            name: name.to_string(),
+        },
+    )
+}
+
+fn resolve(h: &Setup, name: &str) -> Result<names::ResolveReturns, ManyError> {
+    h.module_impl.resolve(
+        &Address::anonymous(),
+        names::ResolveArgs {
+            name: name.to_string(),
+        },
+    )
+}
+
+#[test]
+fn register_and_resolve() {
+    let mut harness = setup();
+    let id = harness.id;
+    let (_, result) = harness.block(|h| register(h, id, "alice"));
+    let expiration = result.unwrap().expiration;
+
+    let record = resolve(&harness, "alice").unwrap();
+    assert_eq!(record.owner, id);
+    assert_eq!(record.expiration, expiration);
+
+    // The fee was paid to the collector.
+    assert_eq!(harness.balance_(id), TokenAmount::from(990u64));
+    assert_eq!(
+        harness.balance(identity(100), *MFX_SYMBOL).unwrap(),
+        TokenAmount::from(10u64)
+    );
+
+    let err = resolve(&harness, "bob").unwrap_err();
+    assert_eq!(err.code(), names::name_not_found("").code());
+}
+
+#[test]
+fn register_taken() {
+    let mut harness = setup();
+    let id = harness.id;
+    harness.set_balance(identity(1), 1_000, *MFX_SYMBOL);
+    harness.block(|h| register(h, id, "alice").unwrap());
+
+    let (_, result) = harness.block(|h| register(h, identity(1), "alice"));
+    assert_eq!(result.unwrap_err().code(), names::name_taken("").code());
+    assert_eq!(resolve(&harness, "alice").unwrap().owner, id);
+}
+
+#[test]
+fn register_invalid() {
+    let mut harness = setup();
+    let id = harness.id;
+    let (_, result) = harness.block(|h| register(h, id, "Alice"));
+    assert_eq!(result.unwrap_err().code(), names::invalid_name("").code());
+    assert_eq!(harness.balance_(id), TokenAmount::from(1_000u64));
+}
+
+#[test]
+fn renew() {
+    let mut harness = setup();
+    let id = harness.id;
+    let (_, first) = harness.block(|h| register(h, id, "alice").unwrap());
+    let (_, second) = harness.block(|h| register(h, id, "alice").unwrap());
+
+    assert_eq!(second.expiration.secs(), first.expiration.secs() + 100);
+    assert_eq!(harness.balance_(id), TokenAmount::from(980u64));
+}
+
+#[test]
+fn expired() {
+    let mut harness = setup();
+    let id = harness.id;
+    harness.set_balance(identity(1), 1_000, *MFX_SYMBOL);
+    harness.block(|h| register(h, id, "alice").unwrap());
+
+    harness.inc_time(200);
+    harness.block(|_| {});
+    let err = resolve(&harness, "alice").unwrap_err();
+    assert_eq!(err.code(), names::name_not_found("").code());
+
+    // Anyone can register an expired name.
+    harness.block(|h| register(h, identity(1), "alice").unwrap());
+    assert_eq!(resolve(&harness, "alice").unwrap().owner, identity(1));
+}
+
+#[test]
+fn transfer() {
+    let mut harness = setup();
+    let id = harness.id;
+    harness.block(|h| register(h, id, "alice").unwrap());
+
+    let transfer = |h: &mut Setup, sender: &Address| {
+        h.module_impl.transfer(
+            sender,
+            names::TransferArgs {
+                name: "alice".to_string(),
+                new_owner: identity(1),
+            },
+        )
+    };
+
+    let (_, result) = harness.block(|h| transfer(h, &identity(2)));
+    assert_eq!(result.unwrap_err().code(), names::not_name_owner("").code());
+
+    let (_, result) = harness.block(|h| transfer(h, &id));
+    assert!(result.is_ok());
+    assert_eq!(resolve(&harness, "alice").unwrap().owner, identity(1));
+}
@@ -0,0 +1,99 @@
+use many_error::ManyError;
+use many_identity::testing::identity;
+use many_identity::Address;
+use many_ledger::migration::relay::RELAY_MIGRATION;
+use many_ledger_test_utils::*;
+use many_modules::relay::{self, RelayModuleBackend};
+use many_protocol::RequestMessage;
+use many_types::ledger::TokenAmount;
+use many_types::Timestamp;
+use serde_json::json;
+
+fn setup() -> Setup {
+    let mut harness = setup_with_migration(
+        &RELAY_MIGRATION,
+        json!({
+            "timeout_in_secs": 300,
+            "fee_symbol": MFX_SYMBOL.to_string(),
+            "fee_amount": 10,
+            "fee_collector": identity(100).to_string(),
+        }),
+    );
+    harness.set_balance(harness.id, 1_000, *MFX_SYMBOL);
+    harness
+}
+
+fn request(timestamp: u64) -> RequestMessage {
+    RequestMessage {
+        timestamp: Some(Timestamp::new(timestamp).unwrap()),
+        ..RequestMessage::default()
+            .with_method("ledger.send".to_string())
+            .with_from(identity(1))
+    }
+}
+
+fn relay(h: &mut Setup, relayer: Address, request: &RequestMessage) -> Result<(), ManyError> {
+    h.module_impl.relay(&relayer, request)
+}
+
+#[test]
+fn info() {
+    let mut harness = setup();
+    harness.block(|_| {});
+    let info = harness
+        .module_impl
+        .info(&Address::anonymous(), relay::InfoArgs)
+        .unwrap();
+    assert_eq!(info.timeout_in_secs, 300);
+    assert_eq!(info.fee.unwrap().amount, TokenAmount::from(10u64));
+}
+
+#[test]
+fn charges_relayer() {
+    let mut harness = setup();
+    let id = harness.id;
+    let (_, result) = harness.block(|h| relay(h, id, &request(1_000_000)));
+    assert!(result.is_ok());
+
+    assert_eq!(harness.balance_(id), TokenAmount::from(990u64));
+    assert_eq!(
+        harness.balance(identity(100), *MFX_SYMBOL).unwrap(),
+        TokenAmount::from(10u64)
+    );
+    // The sender of the relayed request pays nothing.
+    assert_eq!(harness.balance_(identity(1)), TokenAmount::zero());
+}
+
+#[test]
+fn relayer_without_funds() {
+    let mut harness = setup();
+    let (_, result) = harness.block(|h| relay(h, identity(2), &request(1_000_000)));
+    assert!(result.is_err());
+}
+
+#[test]
+fn replay() {
+    let mut harness = setup();
+    let id = harness.id;
+    let request = request(1_000_000);
+    harness.block(|h| relay(h, id, &request).unwrap());
+
+    let (_, result) = harness.block(|h| relay(h, id, &request));
+    assert_eq!(
+        result.unwrap_err().code(),
+        relay::relayed_request_already_executed().code()
+    );
+    assert_eq!(harness.balance_(id), TokenAmount::from(990u64));
+}
+
+#[test]
+fn expired() {
+    let mut harness = setup();
+    let id = harness.id;
+    let (_, result) = harness.block(|h| relay(h, id, &request(1_000)));
+    assert_eq!(
+        result.unwrap_err().code(),
+        ManyError::timestamp_out_of_range().code()
+    );
+    assert_eq!(harness.balance_(id), TokenAmount::from(1_000u64));
+}
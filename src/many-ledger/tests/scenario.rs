@@ -0,0 +1,48 @@
+use many_identity::testing::identity;
+use many_ledger_test_utils::scenario::{Party, Scenario};
+use many_ledger_test_utils::AccountType;
+use many_modules::account::features::multisig;
+use many_types::ledger::TokenAmount;
+
+#[test]
+fn balances_and_tokens() {
+    let s = Scenario::new()
+        .with_balance(identity(1), 1000)
+        .with_account("ledger", AccountType::Ledger)
+        .with_balance("ledger", 200)
+        .with_token(
+            "FOO",
+            [
+                (Party::from(identity(1)), 100u64),
+                (Party::from("ledger"), 50u64),
+            ],
+        )
+        .build();
+
+    assert_eq!(s.balance(identity(1), "MFX"), TokenAmount::from(1000u16));
+    assert_eq!(s.balance("ledger", "MFX"), TokenAmount::from(200u16));
+    assert_eq!(s.balance(identity(1), "FOO"), TokenAmount::from(100u16));
+    assert_eq!(s.balance("ledger", "FOO"), TokenAmount::from(50u16));
+    assert_ne!(s.symbol("FOO"), s.symbol("MFX"));
+}
+
+#[test]
+fn multisig() {
+    let mut s = Scenario::new()
+        .with_multisig("ms", [identity(2), identity(3)], 3)
+        .with_balance("ms", 500)
+        .build();
+    let ms = s.account("ms");
+
+    let token = s.multisig_send_(ms, identity(4), 100u32);
+    s.multisig_approve_(identity(2), &token);
+    assert_eq!(
+        s.multisig_execute(&token).unwrap_err(),
+        multisig::errors::cannot_execute_transaction(),
+    );
+
+    s.multisig_approve_(identity(3), &token);
+    assert!(s.multisig_execute_(&token).data.is_ok());
+    assert_eq!(s.balance("ms", "MFX"), TokenAmount::from(400u16));
+    assert_eq!(s.balance(identity(4), "MFX"), TokenAmount::from(100u16));
+}
@@ -58,6 +58,7 @@ fn load() {
             ledger::BalanceArgs {
                 account: Some(identity(5)),
                 symbols: Some(vec![identity(1000)].into()),
+                height: None,
             },
             Context::new(RequestMessage::default(), unbounded().0),
         )
@@ -154,6 +155,7 @@ fn load_symbol_meta() {
             ledger::BalanceArgs {
                 account: Some(identity(5)),
                 symbols: Some(vec![identity(1000)].into()),
+                height: None,
             },
             Context::new(RequestMessage::default(), unbounded().0),
         )
@@ -194,3 +196,47 @@ fn load_symbol_meta() {
     assert_eq!(info.summary.ticker, "MF0".to_string());
     assert_eq!(info.summary.decimals, 9);
 }
+
+/// Verify the statistics of a store can be read while it is open, and that
+/// the store can be compacted and re-loaded once closed.
+#[test]
+fn stats_and_compact() {
+    let path = tempfile::tempdir().unwrap().into_path();
+    {
+        let symbols = BTreeMap::from([(identity(1000), "MF0".to_string())]);
+        let balances = BTreeMap::from([(
+            identity(5),
+            BTreeMap::from([(identity(1000), 10000000u64.into())]),
+        )]);
+        let _storage = LedgerStorage::new(path.clone(), false)
+            .unwrap()
+            .with_balances(&identity(666), &symbols, &balances)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let stats = LedgerStorage::stats(&path).unwrap();
+        assert!(stats.size_on_disk > 0);
+    }
+
+    let stats = LedgerStorage::compact(&path).unwrap();
+    assert!(stats.size_on_disk > 0);
+    assert!(stats.node_count > 0);
+
+    let module_impl = LedgerModuleImpl::load(None, path, false).unwrap();
+    let balance = module_impl
+        .balance(
+            &identity(5),
+            ledger::BalanceArgs {
+                account: Some(identity(5)),
+                symbols: Some(vec![identity(1000)].into()),
+                height: None,
+            },
+            Context::new(RequestMessage::default(), unbounded().0),
+        )
+        .unwrap();
+    assert_eq!(
+        balance.balances,
+        BTreeMap::from([(identity(1000), 10000000u64.into())])
+    );
+}
@@ -0,0 +1,105 @@
+use async_channel::unbounded;
+use many_error::ManyError;
+use many_identity::testing::identity;
+use many_identity::Address;
+use many_ledger::migration::store::STORE_MIGRATION;
+use many_ledger_test_utils::*;
+use many_modules::store::{self, StoreModuleBackend};
+use many_protocol::context::Context;
+use many_protocol::RequestMessage;
+use minicbor::bytes::ByteVec;
+use serde_json::json;
+use std::collections::BTreeSet;
+
+fn setup() -> Setup {
+    setup_with_migration(&STORE_MIGRATION, json!({ "max_size": 16 }))
+}
+
+fn put(h: &mut Setup, sender: Address, data: &[u8]) -> Result<ByteVec, ManyError> {
+    h.module_impl
+        .put(
+            &sender,
+            store::PutArgs {
+                data: data.to_vec().into(),
+            },
+        )
+        .map(|returns| returns.hash)
+}
+
+fn get(h: &Setup, hash: ByteVec) -> Result<store::GetReturns, ManyError> {
+    h.module_impl.get(
+        &Address::anonymous(),
+        store::GetArgs { hash },
+        Context::new(RequestMessage::default(), unbounded().0),
+    )
+}
+
+fn unpin(h: &mut Setup, sender: Address, hash: ByteVec) -> Result<(), ManyError> {
+    h.module_impl
+        .unpin(&sender, store::UnpinArgs { hash })
+        .map(|_| ())
+}
+
+#[test]
+fn put_and_get() {
+    let mut harness = setup();
+    let (_, hash) = harness.block(|h| put(h, identity(1), b"hello").unwrap());
+    assert_eq!(hash, ByteVec::from(store::object_hash(b"hello").to_vec()));
+
+    let returns = get(&harness, hash.clone()).unwrap();
+    assert_eq!(returns.data, ByteVec::from(b"hello".to_vec()));
+    assert_eq!(returns.info.hash, hash);
+    assert_eq!(returns.info.size, 5);
+    assert_eq!(returns.info.pins, BTreeSet::from([identity(1)]));
+
+    // The same content has the same hash, and is pinned by both senders.
+    let (_, same) = harness.block(|h| put(h, identity(2), b"hello").unwrap());
+    assert_eq!(same, hash);
+    let returns = get(&harness, hash).unwrap();
+    assert_eq!(
+        returns.info.pins,
+        BTreeSet::from([identity(1), identity(2)])
+    );
+}
+
+#[test]
+fn too_large() {
+    let mut harness = setup();
+    let (_, result) = harness.block(|h| put(h, identity(1), &[0; 17]));
+    assert_eq!(
+        result.unwrap_err().code(),
+        store::object_too_large("", "").code()
+    );
+}
+
+#[test]
+fn pin_unpin() {
+    let mut harness = setup();
+    let (_, hash) = harness.block(|h| put(h, identity(1), b"hello").unwrap());
+    harness.block(|h| {
+        h.module_impl
+            .pin(&identity(2), store::PinArgs { hash: hash.clone() })
+            .unwrap()
+    });
+
+    let (_, result) = harness.block(|h| unpin(h, identity(3), hash.clone()));
+    assert_eq!(result.unwrap_err().code(), store::not_pinned("").code());
+
+    // The object is kept until its last pin is removed.
+    harness.block(|h| unpin(h, identity(1), hash.clone()).unwrap());
+    assert!(get(&harness, hash.clone()).is_ok());
+
+    harness.block(|h| unpin(h, identity(2), hash.clone()).unwrap());
+    assert_eq!(
+        get(&harness, hash).unwrap_err().code(),
+        store::object_not_found("").code()
+    );
+}
+
+#[test]
+fn invalid_hash() {
+    let mut harness = setup();
+    harness.block(|_| {});
+    let err = get(&harness, ByteVec::from(vec![1, 2, 3])).unwrap_err();
+    assert_eq!(err.code(), store::invalid_hash("").code());
+}
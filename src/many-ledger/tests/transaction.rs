@@ -0,0 +1,144 @@
+use many_identity::testing::identity;
+use many_identity::Address;
+use many_ledger::error;
+use many_ledger::storage::LedgerStorage;
+use many_modules::events::{EventInfo, EventLog};
+use many_types::ledger::TokenAmount;
+use std::collections::BTreeMap;
+
+fn setup(blockchain: bool) -> (LedgerStorage, Address) {
+    let symbol = identity(100);
+    let symbols = BTreeMap::from([(symbol, "MFX".to_string())]);
+    let balances = BTreeMap::from([(
+        identity(0),
+        BTreeMap::from([(symbol, TokenAmount::from(1000u16))]),
+    )]);
+    let persistent_path = tempfile::tempdir().unwrap();
+
+    let storage = LedgerStorage::new(persistent_path, blockchain)
+        .unwrap()
+        .with_balances(&identity(2), &symbols, &balances)
+        .unwrap()
+        .build()
+        .unwrap();
+    (storage, symbol)
+}
+
+#[test]
+fn rollback() {
+    let (mut storage, symbol) = setup(false);
+    let (id0, id1) = (identity(0), identity(1));
+
+    storage.begin_transaction().unwrap();
+    assert_eq!(
+        storage.begin_transaction().unwrap_err().code(),
+        error::transaction_already_open::CODE
+    );
+    for _ in 0..2 {
+        storage
+            .send(&id0, &id1, &symbol, TokenAmount::from(100u16), None, None)
+            .unwrap();
+    }
+    assert_eq!(
+        storage.get_balance(&id1, &symbol).unwrap(),
+        TokenAmount::from(200u16)
+    );
+    storage.rollback_transaction().unwrap();
+
+    assert_eq!(
+        storage.get_balance(&id0, &symbol).unwrap(),
+        TokenAmount::from(1000u16)
+    );
+    assert_eq!(
+        storage.get_balance(&id1, &symbol).unwrap(),
+        TokenAmount::zero()
+    );
+    assert_eq!(storage.nb_events().unwrap(), 0);
+    assert!(storage.rollback_transaction().is_err());
+}
+
+#[test]
+fn commit() {
+    let (mut storage, symbol) = setup(false);
+    let (id0, id1) = (identity(0), identity(1));
+
+    storage.begin_transaction().unwrap();
+    storage
+        .send(&id0, &id1, &symbol, TokenAmount::from(100u16), None, None)
+        .unwrap();
+    storage.commit_transaction().unwrap();
+    assert_eq!(
+        storage.rollback_transaction().unwrap_err().code(),
+        error::no_open_transaction::CODE
+    );
+
+    assert_eq!(
+        storage.get_balance(&id1, &symbol).unwrap(),
+        TokenAmount::from(100u16)
+    );
+    assert_eq!(storage.nb_events().unwrap(), 1);
+}
+
+#[test]
+fn transaction_events() {
+    // The events of a block are only committed with the block.
+    let (mut storage, symbol) = setup(true);
+    let (id0, id1) = (identity(0), identity(1));
+    assert_eq!(
+        storage.transaction_events().unwrap_err().code(),
+        error::no_open_transaction::CODE
+    );
+
+    storage.begin_transaction().unwrap();
+    storage
+        .send(&id0, &id1, &symbol, TokenAmount::from(100u16), None, None)
+        .unwrap();
+    let events = storage.transaction_events().unwrap();
+    assert_eq!(events.len(), 1);
+    let event: EventLog = minicbor::decode(&events[0]).unwrap();
+    assert!(matches!(
+        event.content,
+        EventInfo::Send { to, .. } if to == id1
+    ));
+
+    storage.rollback_transaction().unwrap();
+    assert_eq!(storage.nb_events().unwrap(), 0);
+}
+
+/// Outside of blockchain mode, the writes of a transaction only reach the disk
+/// once it is committed, so simulated requests leave nothing behind even if
+/// the server stops before rolling them back.
+#[test]
+fn transaction_not_persisted() {
+    let path = tempfile::tempdir().unwrap().into_path();
+    let symbol = identity(100);
+    let (id0, id1) = (identity(0), identity(1));
+    let hash = {
+        let mut storage = LedgerStorage::new(&path, false)
+            .unwrap()
+            .with_balances(
+                &identity(2),
+                &BTreeMap::from([(symbol, "MFX".to_string())]),
+                &BTreeMap::from([(id0, BTreeMap::from([(symbol, TokenAmount::from(1000u16))]))]),
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+        let hash = storage.hash();
+
+        storage.begin_transaction().unwrap();
+        storage
+            .send(&id0, &id1, &symbol, TokenAmount::from(100u16), None, None)
+            .unwrap();
+        // The storage is dropped with the transaction open, as in a crash.
+        hash
+    };
+
+    let storage = LedgerStorage::load(&path, false, None).unwrap();
+    assert_eq!(storage.hash(), hash);
+    assert_eq!(
+        storage.get_balance(&id1, &symbol).unwrap(),
+        TokenAmount::zero()
+    );
+    assert_eq!(storage.nb_events().unwrap(), 0);
+}
@@ -0,0 +1,100 @@
+use many_error::ManyError;
+use many_identity::testing::identity;
+use many_identity::Address;
+use many_ledger::storage::LedgerStorage;
+use many_modules::events::EventInfo;
+use many_types::ledger::TokenAmount;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+fn setup() -> (LedgerStorage, Address, Address) {
+    let symbol0 = identity(100);
+    let symbol1 = identity(101);
+    let symbols = BTreeMap::from([(symbol0, "MFX".to_string()), (symbol1, "FBT".to_string())]);
+    let balances = BTreeMap::from([(
+        identity(0),
+        BTreeMap::from([
+            (symbol0, TokenAmount::from(1000u16)),
+            (symbol1, TokenAmount::from(1000u16)),
+        ]),
+    )]);
+    let persistent_path = tempfile::tempdir().unwrap();
+
+    let storage = LedgerStorage::new(persistent_path, false)
+        .unwrap()
+        .with_balances(&identity(2), &symbols, &balances)
+        .unwrap()
+        .build()
+        .unwrap();
+    (storage, symbol0, symbol1)
+}
+
+#[test]
+fn veto() {
+    let (mut storage, symbol0, symbol1) = setup();
+    let (id0, id1) = (identity(0), identity(1));
+    storage.add_transfer_hook(Some(symbol0), |_, event| match event {
+        EventInfo::Send { amount, .. } if amount > &TokenAmount::from(100u16) => {
+            Err(ManyError::unknown("Amount over limit"))
+        }
+        _ => Ok(()),
+    });
+
+    assert!(storage
+        .send(&id0, &id1, &symbol0, TokenAmount::from(500u16), None, None)
+        .is_err());
+    assert_eq!(
+        storage.get_balance(&id0, &symbol0).unwrap(),
+        TokenAmount::from(1000u16)
+    );
+    assert_eq!(storage.nb_events().unwrap(), 0);
+
+    // Under the limit, or on another symbol.
+    storage
+        .send(&id0, &id1, &symbol0, TokenAmount::from(100u16), None, None)
+        .unwrap();
+    storage
+        .send(&id0, &id1, &symbol1, TokenAmount::from(500u16), None, None)
+        .unwrap();
+    assert_eq!(
+        storage.get_balance(&id1, &symbol0).unwrap(),
+        TokenAmount::from(100u16)
+    );
+    assert_eq!(
+        storage.get_balance(&id1, &symbol1).unwrap(),
+        TokenAmount::from(500u16)
+    );
+}
+
+#[test]
+fn all_symbols() {
+    let (mut storage, symbol0, symbol1) = setup();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let c = calls.clone();
+    storage.add_transfer_hook(None, move |storage, event| {
+        // Hooks see the balances before the change.
+        if let EventInfo::Send { from, symbol, .. } = event {
+            assert_eq!(
+                storage.get_balance(from, symbol).unwrap(),
+                TokenAmount::from(1000u16)
+            );
+        }
+        c.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    });
+
+    for symbol in [symbol0, symbol1] {
+        storage
+            .send(
+                &identity(0),
+                &identity(1),
+                &symbol,
+                TokenAmount::from(1u16),
+                None,
+                None,
+            )
+            .unwrap();
+    }
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
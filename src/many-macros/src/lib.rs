@@ -3,7 +3,7 @@ use proc_macro2::{Ident, Span, TokenStream};
 use quote::{quote, quote_spanned};
 use serde::Deserialize;
 use serde_tokenstream::from_tokenstream;
-use syn::parse::ParseStream;
+use syn::parse::{Parse, ParseStream};
 use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
 use syn::{FnArg, Pat, PatType, ReturnType, Token, TraitItem, TraitItemFn, Type, TypePath};
@@ -14,12 +14,123 @@ struct ManyModuleAttributes {
     pub name: Option<String>,
     pub namespace: Option<String>,
     pub many_modules_crate: Option<String>,
+
+    /// Also emit a `{name}MockClient`, calling the endpoints of a backend in
+    /// memory through the module.
+    pub mock_client: Option<bool>,
+}
+
+/// The arguments of `#[many(deprecated(replacement = "...", sunset = 123))]`,
+/// both optional.
+#[derive(Debug)]
+struct DeprecatedAttribute {
+    keyword: Ident,
+    replacement: Option<syn::LitStr>,
+    sunset: Option<syn::LitInt>,
+}
+
+impl quote::ToTokens for DeprecatedAttribute {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.keyword.to_tokens(tokens)
+    }
+}
+
+impl DeprecatedAttribute {
+    fn parse_args(keyword: Ident, input: ParseStream) -> syn::Result<Self> {
+        let mut result = Self {
+            keyword,
+            replacement: None,
+            sunset: None,
+        };
+        if !input.peek(syn::token::Paren) {
+            return Ok(result);
+        }
+
+        let content;
+        syn::parenthesized!(content in input);
+        let args = content.parse_terminated(syn::MetaNameValue::parse, Token![,])?;
+        for arg in args {
+            let lit = match &arg.value {
+                syn::Expr::Lit(syn::ExprLit { lit, .. }) => Some(lit),
+                _ => None,
+            };
+            match (arg.path.get_ident(), lit) {
+                (Some(name), Some(syn::Lit::Str(s))) if name == "replacement" => {
+                    result.replacement = Some(s.clone())
+                }
+                (Some(name), Some(syn::Lit::Int(i))) if name == "sunset" => {
+                    result.sunset = Some(i.clone())
+                }
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        arg,
+                        "expected `replacement = \"<endpoint>\"` or `sunset = <height>`",
+                    ))
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    fn descriptor(&self, many_modules: &Ident) -> TokenStream {
+        let replacement = match &self.replacement {
+            Some(r) => quote! { Some(#r .to_string()) },
+            None => quote! { None },
+        };
+        let sunset = match &self.sunset {
+            Some(s) => quote! { Some(#s) },
+            None => quote! { None },
+        };
+        quote! {
+            Some(#many_modules ::base::Deprecation {
+                replacement: #replacement,
+                sunset: #sunset,
+            })
+        }
+    }
+}
+
+/// The arguments of `#[many(redact(0, 2))]`: the keys of the argument map
+/// which are not logged. Without keys, the whole argument is redacted.
+#[derive(Debug)]
+struct RedactAttribute {
+    keyword: Ident,
+    keys: Vec<syn::LitInt>,
+}
+
+impl quote::ToTokens for RedactAttribute {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.keyword.to_tokens(tokens)
+    }
+}
+
+impl RedactAttribute {
+    fn parse_args(keyword: Ident, input: ParseStream) -> syn::Result<Self> {
+        let keys = if input.peek(syn::token::Paren) {
+            let content;
+            syn::parenthesized!(content in input);
+            content
+                .parse_terminated(syn::LitInt::parse, Token![,])?
+                .into_iter()
+                .collect()
+        } else {
+            vec![]
+        };
+        Ok(Self { keyword, keys })
+    }
+
+    fn descriptor(&self) -> TokenStream {
+        let keys = &self.keys;
+        quote! { Some(vec![ #(#keys),* ]) }
+    }
 }
 
-#[derive(Debug, Default, Deserialize)]
+#[derive(Debug, Default)]
 struct EndpointManyAttribute {
     deny_anonymous: Option<bool>,
     check_webauthn: Option<bool>,
+    deprecated: Option<DeprecatedAttribute>,
+    redact: Option<RedactAttribute>,
 }
 
 impl EndpointManyAttribute {
@@ -47,6 +158,8 @@ impl EndpointManyAttribute {
         Ok(Self {
             deny_anonymous: either(self.deny_anonymous, other.deny_anonymous)?,
             check_webauthn: either(self.check_webauthn, other.check_webauthn)?,
+            deprecated: either(self.deprecated, other.deprecated)?,
+            redact: either(self.redact, other.redact)?,
         })
     }
 }
@@ -58,12 +171,22 @@ impl syn::parse::Parse for EndpointManyAttribute {
         if arg_name == "deny_anonymous" {
             Ok(Self {
                 deny_anonymous: Some(true),
-                check_webauthn: None,
+                ..Default::default()
             })
         } else if arg_name == "check_webauthn" {
             Ok(Self {
-                deny_anonymous: None,
                 check_webauthn: Some(true),
+                ..Default::default()
+            })
+        } else if arg_name == "deprecated" {
+            Ok(Self {
+                deprecated: Some(DeprecatedAttribute::parse_args(arg_name, input)?),
+                ..Default::default()
+            })
+        } else if arg_name == "redact" {
+            Ok(Self {
+                redact: Some(RedactAttribute::parse_args(arg_name, input)?),
+                ..Default::default()
             })
         } else {
             Err(syn::Error::new_spanned(arg_name, "unsupported attribute"))
@@ -284,7 +407,64 @@ impl Endpoint {
         }
     }
 
-    pub fn validate_endpoint_pat(&self, namespace: &Option<String>) -> TokenStream {
+    /// Returns the type of the successful result of this endpoint, i.e. `T` in
+    /// `Result<T, E>`.
+    fn ok_type(&self) -> &Type {
+        if let Type::Path(TypePath { path, .. }) = self.ret_type.as_ref() {
+            if let Some(syn::PathSegment {
+                arguments: syn::PathArguments::AngleBracketed(args),
+                ..
+            }) = path.segments.last()
+            {
+                if let Some(syn::GenericArgument::Type(ty)) = args.args.first() {
+                    return ty;
+                }
+            }
+        }
+        &self.ret_type
+    }
+
+    pub fn descriptor(&self, namespace: &Option<String>, many_modules: &Ident) -> TokenStream {
+        fn type_string(ty: &Type) -> String {
+            quote! { #ty }.to_string().replace(' ', "")
+        }
+
+        let name = self.name.as_str().to_camel_case();
+        let ep = match namespace {
+            Some(ref namespace) => format!("{namespace}.{name}"),
+            None => name,
+        };
+        let argument = if let Some((_, ty)) = &self.arg {
+            let ty = type_string(ty);
+            quote! { Some(#ty .to_string()) }
+        } else {
+            quote! { None }
+        };
+        let returns = type_string(self.ok_type());
+        let deprecated = match &self.metadata.deprecated {
+            Some(deprecated) => deprecated.descriptor(many_modules),
+            None => quote! { None },
+        };
+        let redacted = match &self.metadata.redact {
+            Some(redact) => redact.descriptor(),
+            None => quote! { None },
+        };
+
+        quote! {
+            #many_modules ::base::EndpointDescriptor {
+                name: #ep .to_string(),
+                argument: #argument,
+                returns: #returns .to_string(),
+                deprecated: #deprecated,
+                redacted: #redacted,
+            }
+        }
+    }
+
+    /// The validation of the endpoint, for a message in a single signer
+    /// envelope, or in a multi-signer one if `multi` is set. The WebAuthn
+    /// header is only set in single signer envelopes.
+    pub fn validate_endpoint_pat(&self, namespace: &Option<String>, multi: bool) -> TokenStream {
         let span = self.span;
         let name = self.name.as_str().to_camel_case();
         let ep = match namespace {
@@ -302,6 +482,12 @@ impl Endpoint {
             quote! { {} }
         };
 
+        if self.metadata.check_webauthn() && multi {
+            return quote_spanned! { span =>
+                #ep => return Err(many_error::ManyError::non_webauthn_request_denied(method)),
+            };
+        }
+
         let check_webauthn = if self.metadata.check_webauthn() {
             quote_spanned! { span => {
                 let protected = std::collections::BTreeMap::from_iter(envelope.protected.header.rest.clone().into_iter());
@@ -315,7 +501,8 @@ impl Endpoint {
 
         let check_ty = if let Some((_, ty)) = &self.arg {
             quote_spanned! { span =>
-                minicbor::decode::<'_, #ty>(data)
+                many_types::cbor::DecodeLimits::default()
+                    .decode::<'_, #ty>(data)
                     .map_err(|e| many_error::ManyError::deserialization_error(e.to_string()))?;
             }
         } else {
@@ -409,6 +596,29 @@ impl Endpoint {
             }
         }
     }
+
+    /// Returns the method of the mock client calling this endpoint.
+    pub fn mock_client_method(&self, namespace: &Option<String>) -> TokenStream {
+        let span = self.span;
+        let name = self.name.as_str().to_camel_case();
+        let ep = match namespace {
+            Some(ref namespace) => format!("{namespace}.{name}"),
+            None => name,
+        };
+        let func = &self.func;
+        let ok_type = self.ok_type();
+
+        let (arg, argument) = match &self.arg {
+            Some((_, ty)) => (quote! { args: #ty }, quote! { args }),
+            None => (quote! {}, quote! { () }),
+        };
+
+        quote_spanned! { span =>
+            pub async fn #func(&self, #arg) -> Result<#ok_type, many_error::ManyError> {
+                self.call_(#ep, #argument).await
+            }
+        }
+    }
 }
 
 impl quote::ToTokens for Endpoint {
@@ -491,9 +701,16 @@ fn many_module_impl(attr: &TokenStream, item: TokenStream) -> Result<TokenStream
         })
         .collect();
 
+    let endpoint_descriptors = endpoints
+        .iter()
+        .map(|e| e.descriptor(&namespace, &many_modules));
+
     let validate_endpoint_pat = endpoints
         .iter()
-        .map(|e| e.validate_endpoint_pat(&namespace));
+        .map(|e| e.validate_endpoint_pat(&namespace, false));
+    let validate_multi_endpoint_pat = endpoints
+        .iter()
+        .map(|e| e.validate_endpoint_pat(&namespace, true));
     let validate = quote! {
         fn validate(
             &self,
@@ -509,6 +726,22 @@ fn many_module_impl(attr: &TokenStream, item: TokenStream) -> Result<TokenStream
             };
             Ok(())
         }
+
+        fn validate_multi(
+            &self,
+            message: & many_protocol::RequestMessage,
+            _envelope: & coset::CoseSign,
+            _signers: & std::collections::BTreeSet<many_identity::Address>,
+        ) -> Result<(), many_error::ManyError> {
+            let method = message.method.as_str();
+            let data = message.data.as_slice();
+            match method {
+                #(#validate_multi_endpoint_pat)*
+
+                _ => return Err( many_error::ManyError::invalid_method_name(method.to_string())),
+            };
+            Ok(())
+        }
     };
 
     let execute_endpoint_pat = endpoints.iter().map(|e| e.execute_endpoint_pat(&namespace));
@@ -517,6 +750,14 @@ fn many_module_impl(attr: &TokenStream, item: TokenStream) -> Result<TokenStream
         async fn execute(
             &self,
             message: many_protocol::RequestMessage,
+        ) -> Result<many_protocol::ResponseMessage, many_error::ManyError> {
+            self.execute_with_block_time(message, None).await
+        }
+
+        async fn execute_with_block_time(
+            &self,
+            message: many_protocol::RequestMessage,
+            block_time: Option<many_types::BlockTime>,
         ) -> Result<many_protocol::ResponseMessage, many_error::ManyError> {
             use {
                 async_channel::unbounded,
@@ -525,7 +766,9 @@ fn many_module_impl(attr: &TokenStream, item: TokenStream) -> Result<TokenStream
                 many_types::PROOF
             };
             fn decode<'a, T: minicbor::Decode<'a, ()>>(data: &'a [u8]) -> Result<T, ManyError> {
-                minicbor::decode(data).map_err(|e| ManyError::deserialization_error(e.to_string()))
+                many_types::cbor::DecodeLimits::default()
+                    .decode(data)
+                    .map_err(|e| ManyError::deserialization_error(e.to_string()))
             }
             fn encode<T: minicbor::Encode<()>>(result: Result<T, ManyError>) -> Result<Vec<u8>, ManyError> {
                 minicbor::to_vec(result?).map_err(|e| ManyError::serialization_error(e.to_string()))
@@ -533,29 +776,82 @@ fn many_module_impl(attr: &TokenStream, item: TokenStream) -> Result<TokenStream
 
             let data = message.data.as_slice();
             let (transmitter, receiver) = unbounded();
-            let ctx = Context::new(message.clone(), transmitter);
+            let ctx = Context::new(message.clone(), transmitter).with_block_time(block_time);
+            let warnings = ctx.clone();
             let result = match message.method.as_str() {
                 #( #execute_endpoint_pat )*
 
                 _ => Err(ManyError::internal_server_error()),
             }?;
 
-            Ok(if message.attributes.contains(&PROOF) {
+            let response = if message.attributes.contains(&PROOF) {
                 many_protocol::ResponseMessage::from_request(
                     &message,
                     &message.to,
                     Ok(result),
-                ).with_attributes(receiver.recv().await.map_err(|e| ManyError::unknown(e.to_string()))?.into_iter().collect::<Result<Vec<_>, _>>()?)
+                ).with_attributes(ProofResult::combine(std::iter::from_fn(|| receiver.try_recv().ok())).into_iter().collect::<Result<Vec<_>, _>>()?)
             } else {
                 many_protocol::ResponseMessage::from_request(
                     &message,
                     &message.to,
                     Ok(result),
                 )
-            })
+            };
+            response.with_warnings(warnings.take_warnings())
         }
     };
 
+    let mock_client = if attrs.mock_client.unwrap_or(false) {
+        let mock_ident = Ident::new(&format!("{struct_name}MockClient"), struct_ident.span());
+        let methods = endpoints.iter().map(|e| e.mock_client_method(&namespace));
+        quote! {
+            /// A client calling the endpoints of a backend in memory, through
+            /// the module, for tests without a server.
+            #vis struct #mock_ident<T: #trait_ident> {
+                module: #struct_ident<T>,
+                sender: many_identity::Address,
+            }
+
+            impl<T: #trait_ident> #mock_ident<T> {
+                pub fn new(backend: std::sync::Arc<std::sync::Mutex<T>>) -> Self {
+                    Self {
+                        module: #struct_ident::new(backend),
+                        sender: many_identity::Address::anonymous(),
+                    }
+                }
+
+                /// Send the requests from this address instead of anonymous.
+                pub fn with_sender(mut self, sender: many_identity::Address) -> Self {
+                    self.sender = sender;
+                    self
+                }
+
+                pub async fn call_<A, R>(&self, method: &str, argument: A) -> Result<R, many_error::ManyError>
+                where
+                    A: minicbor::Encode<()>,
+                    R: for<'a> minicbor::Decode<'a, ()>,
+                {
+                    use #many_modules ::ManyModule;
+                    let data = minicbor::to_vec(argument)
+                        .map_err(|e| many_error::ManyError::serialization_error(e.to_string()))?;
+                    let message = many_protocol::RequestMessage::default()
+                        .with_method(method.to_string())
+                        .with_data(data)
+                        .with_from(self.sender);
+
+                    self.module.validate(&message, &coset::CoseSign1::default())?;
+                    let data = self.module.execute(message).await?.data?;
+                    minicbor::decode(&data)
+                        .map_err(|e| many_error::ManyError::deserialization_error(e.to_string()))
+                }
+
+                #( #methods )*
+            }
+        }
+    } else {
+        quote! {}
+    };
+
     let attribute = if attrs.id.is_some() {
         quote! { Some(#attr_ident) }
     } else {
@@ -579,6 +875,7 @@ fn many_module_impl(attr: &TokenStream, item: TokenStream) -> Result<TokenStream
                         name: #struct_name .to_string(),
                         attribute: #attribute,
                         endpoints: vec![ #( #endpoint_strings .to_string() ),* ],
+                        descriptors: vec![ #( #endpoint_descriptors ),* ],
                     })));
                     &*VALUE
                 }
@@ -614,6 +911,8 @@ fn many_module_impl(attr: &TokenStream, item: TokenStream) -> Result<TokenStream
 
             #execute
         }
+
+        #mock_client
     })
 }
 
@@ -2,13 +2,20 @@
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fmt;
 use std::fmt::Formatter;
 use std::ops::Index;
 use strum::Display;
 use tracing::trace;
 
+mod visitor;
+
+pub use visitor::{
+    FnKeyValue, FnMultiKey, KeyValueBatch, KeyValueStore, KeyValueVisitor, RewriteKey,
+    RewritePrefix, SetValue,
+};
+
 // Initialize and update functions receive the `metadata.extra` fields.
 // The `metadata.extra` field can be used to provide custom parameters to migrations.
 pub type FnPtr<T, E> = fn(&mut T, &HashMap<String, Value>) -> Result<(), E>;
@@ -78,7 +85,17 @@ pub struct RegularMigration<T, E> {
 
 #[derive(Copy, Clone)]
 pub struct HotfixMigration {
-    hotfix_fn: FnByte,
+    hotfix: Hotfix,
+}
+
+/// A hotfix either transforms a single value passed by the caller, visits
+/// the key/value pairs of the storage, or reads and writes any keys of the
+/// storage.
+#[derive(Copy, Clone)]
+enum Hotfix {
+    Bytes(FnByte),
+    KeyValue(&'static dyn KeyValueVisitor),
+    MultiKey(FnMultiKey),
 }
 
 /// A trigger migration is simply a migration that is active in a range of
@@ -95,6 +112,7 @@ pub struct InnerMigration<T, E> {
     r#type: MigrationType<T, E>,
     name: &'static str,
     description: &'static str,
+    repeatable: bool,
 }
 
 // The Debug derive requires that _all_ parametric types also implement Debug,
@@ -131,9 +149,47 @@ impl<T, E> InnerMigration<T, E> {
         description: &'static str,
     ) -> Self {
         Self {
-            r#type: MigrationType::Hotfix(HotfixMigration { hotfix_fn }),
+            r#type: MigrationType::Hotfix(HotfixMigration {
+                hotfix: Hotfix::Bytes(hotfix_fn),
+            }),
             name,
             description,
+            repeatable: false,
+        }
+    }
+
+    /// A hotfix that rewrites the key/value pairs of the storage visited by
+    /// `visitor`, at its block height.
+    pub const fn new_key_value_hotfix(
+        visitor: &'static dyn KeyValueVisitor,
+        name: &'static str,
+        description: &'static str,
+    ) -> Self {
+        Self {
+            r#type: MigrationType::Hotfix(HotfixMigration {
+                hotfix: Hotfix::KeyValue(visitor),
+            }),
+            name,
+            description,
+            repeatable: false,
+        }
+    }
+
+    /// A hotfix that reads and writes multiple keys of the storage, at its
+    /// block height. Its changes are applied together, and only if it
+    /// succeeds.
+    pub const fn new_multi_key_hotfix(
+        hotfix_fn: FnMultiKey,
+        name: &'static str,
+        description: &'static str,
+    ) -> Self {
+        Self {
+            r#type: MigrationType::Hotfix(HotfixMigration {
+                hotfix: Hotfix::MultiKey(hotfix_fn),
+            }),
+            name,
+            description,
+            repeatable: false,
         }
     }
 
@@ -150,6 +206,7 @@ impl<T, E> InnerMigration<T, E> {
             }),
             name,
             description,
+            repeatable: false,
         }
     }
 
@@ -165,6 +222,7 @@ impl<T, E> InnerMigration<T, E> {
             }),
             name,
             description,
+            repeatable: false,
         }
     }
 
@@ -180,6 +238,7 @@ impl<T, E> InnerMigration<T, E> {
             }),
             name,
             description,
+            repeatable: false,
         }
     }
 
@@ -192,6 +251,33 @@ impl<T, E> InnerMigration<T, E> {
             r#type: MigrationType::Trigger(TriggerMigration { active_by_default }),
             name,
             description,
+            repeatable: false,
+        }
+    }
+
+    /// Allow the migration to be listed more than once in a configuration,
+    /// at different block heights, each entry with its own metadata. Every
+    /// entry is initialized at its own height, but a repeatable migration is
+    /// never reported as enabled or active by name.
+    pub const fn repeatable(self) -> Self {
+        Self {
+            repeatable: true,
+            ..self
+        }
+    }
+
+    #[inline]
+    pub const fn is_repeatable(&self) -> bool {
+        self.repeatable
+    }
+
+    /// The key of a configuration entry of this migration. Repeatable
+    /// migrations have one entry per block height.
+    fn key(&self, metadata: &Metadata) -> String {
+        if self.repeatable {
+            format!("{}@{}", self.name, metadata.block_height)
+        } else {
+            self.name.to_string()
         }
     }
 
@@ -242,14 +328,36 @@ impl<T, E> InnerMigration<T, E> {
     /// This function gets executed when the storage block height == the migration block height
     fn hotfix<'b>(&'b self, b: &'b [u8]) -> Option<Vec<u8>> {
         match &self.r#type {
-            MigrationType::Hotfix(migration) => (migration.hotfix_fn)(b),
-            MigrationType::Regular(_) | MigrationType::Trigger(_) => None,
+            MigrationType::Hotfix(HotfixMigration {
+                hotfix: Hotfix::Bytes(hotfix_fn),
+            }) => hotfix_fn(b),
+            MigrationType::Hotfix(_) | MigrationType::Regular(_) | MigrationType::Trigger(_) => {
+                None
+            }
             x => {
                 trace!("Migration {} has unknown type {}", self.name(), x);
                 None
             }
         }
     }
+
+    fn key_value_visitor(&self) -> Option<&'static dyn KeyValueVisitor> {
+        match &self.r#type {
+            MigrationType::Hotfix(HotfixMigration {
+                hotfix: Hotfix::KeyValue(visitor),
+            }) => Some(*visitor),
+            _ => None,
+        }
+    }
+
+    fn multi_key_fn(&self) -> Option<FnMultiKey> {
+        match &self.r#type {
+            MigrationType::Hotfix(HotfixMigration {
+                hotfix: Hotfix::MultiKey(hotfix_fn),
+            }) => Some(*hotfix_fn),
+            _ => None,
+        }
+    }
 }
 
 pub enum Activated {
@@ -383,6 +491,27 @@ impl<'a, T, E> Migration<'a, T, E> {
         }
     }
 
+    /// The visitor of a key/value hotfix, if it should run at this block height.
+    #[inline]
+    pub fn key_value_hotfix(&self, block_height: u64) -> Option<&'static dyn KeyValueVisitor> {
+        if self.is_enabled() && self.metadata.block_height == block_height {
+            self.migration.key_value_visitor()
+        } else {
+            None
+        }
+    }
+
+    /// The function of a multi-key hotfix, if it should run at this block
+    /// height.
+    #[inline]
+    pub fn multi_key_hotfix(&self, block_height: u64) -> Option<FnMultiKey> {
+        if self.is_enabled() && self.metadata.block_height == block_height {
+            self.migration.multi_key_fn()
+        } else {
+            None
+        }
+    }
+
     #[inline]
     pub fn is_regular(&self) -> bool {
         matches!(self.migration.r#type, MigrationType::Regular(_))
@@ -498,6 +627,111 @@ impl<T: IntoIterator<Item = impl Into<SingleMigrationConfig>>> From<T> for Migra
     }
 }
 
+/// A problem found in a migration configuration by [MigrationSet::validate].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MigrationIssue {
+    /// The migration is not in the registry of this binary.
+    Unknown { name: String },
+
+    /// The migration is listed more than once, or more than once at the same
+    /// height if it is repeatable. Only the last entry is used.
+    Duplicate { name: String },
+
+    /// The migration is in the registry but not in a strict configuration.
+    Missing { name: String },
+
+    /// Multiple enabled migrations start at the same block height.
+    SameHeight {
+        block_height: u64,
+        names: Vec<String>,
+    },
+
+    /// The enabled migration starts at or before the current height, so it
+    /// is considered active already and will not be initialized.
+    PastHeight {
+        name: String,
+        block_height: u64,
+        height: u64,
+    },
+}
+
+impl MigrationIssue {
+    /// Whether loading the configuration would fail or silently drop a
+    /// migration. Other issues are warnings.
+    pub fn is_error(&self) -> bool {
+        matches!(
+            self,
+            Self::Unknown { .. } | Self::Duplicate { .. } | Self::Missing { .. }
+        )
+    }
+}
+
+impl fmt::Display for MigrationIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unknown { name } => write!(f, r#"Unsupported migration "{name}""#),
+            Self::Duplicate { name } => write!(f, r#"Migration "{name}" is listed more than once"#),
+            Self::Missing { name } => {
+                write!(f, r#"Migration Config is missing migration "{name}""#)
+            }
+            Self::SameHeight {
+                block_height,
+                names,
+            } => write!(
+                f,
+                "Migrations {names:?} are all enabled at height {block_height}"
+            ),
+            Self::PastHeight {
+                name,
+                block_height,
+                height,
+            } => write!(
+                f,
+                r#"Migration "{name}" is enabled at height {block_height}, which is not after the current height {height}"#
+            ),
+        }
+    }
+}
+
+/// The result of validating a migration configuration against a registry,
+/// at a storage height.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MigrationReport {
+    pub height: u64,
+    pub issues: Vec<MigrationIssue>,
+}
+
+impl MigrationReport {
+    pub fn errors(&self) -> impl Iterator<Item = &MigrationIssue> {
+        self.issues.iter().filter(|i| i.is_error())
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &MigrationIssue> {
+        self.issues.iter().filter(|i| !i.is_error())
+    }
+
+    /// Whether the configuration can be loaded. It may still have warnings.
+    pub fn is_ok(&self) -> bool {
+        self.errors().next().is_none()
+    }
+}
+
+impl fmt::Display for MigrationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Height: {}", self.height)?;
+        for issue in &self.issues {
+            let level = if issue.is_error() { "error" } else { "warning" };
+            writeln!(f, "{level}: {issue}")?;
+        }
+        write!(
+            f,
+            "{} error(s), {} warning(s).",
+            self.errors().count(),
+            self.warnings().count()
+        )
+    }
+}
+
 pub struct MigrationSet<'a, T: 'a, E: 'a = many_error::ManyError> {
     inner: BTreeMap<String, Migration<'a, T, E>>,
 }
@@ -518,7 +752,8 @@ impl<'a, T, E> MigrationSet<'a, T, E> {
     }
 
     pub fn insert(&mut self, migration: Migration<'a, T, E>) {
-        self.inner.insert(migration.name().to_string(), migration);
+        self.inner
+            .insert(migration.migration.key(&migration.metadata), migration);
     }
 
     pub fn load(
@@ -542,7 +777,7 @@ impl<'a, T, E> MigrationSet<'a, T, E> {
                     .get(config.name.as_str())
                     .ok_or_else(|| format!("Unsupported migration '{}'", config.name))?;
 
-                Ok((config.name, Migration::new(v, config.metadata)))
+                Ok((v.key(&config.metadata), Migration::new(v, config.metadata)))
             })
             .collect::<Result<BTreeMap<_, _>, String>>()?
             .into_iter()
@@ -552,7 +787,7 @@ impl<'a, T, E> MigrationSet<'a, T, E> {
         if is_strict {
             let maybe_missing = registry
                 .keys()
-                .filter(|name| !inner.contains_key(&name.to_string()))
+                .filter(|name| !inner.values().any(|m| m.name() == **name))
                 .collect::<Vec<_>>();
 
             match maybe_missing.as_slice() {
@@ -570,6 +805,69 @@ impl<'a, T, E> MigrationSet<'a, T, E> {
         Ok(Self { inner })
     }
 
+    /// Check a configuration against the registry without loading it, as if
+    /// the storage was at `height`.
+    pub fn validate(
+        registry: &[InnerMigration<T, E>],
+        config: &MigrationConfig,
+        height: u64,
+    ) -> MigrationReport {
+        let mut issues = Vec::new();
+        let mut seen = BTreeSet::new();
+        let mut listed = BTreeSet::new();
+        let mut heights: BTreeMap<u64, Vec<String>> = BTreeMap::new();
+
+        for SingleMigrationConfig { name, metadata } in &config.migrations {
+            let migration = registry.iter().find(|m| m.name == name.as_str());
+            if migration.is_none() {
+                issues.push(MigrationIssue::Unknown { name: name.clone() });
+            }
+            let key = migration.map_or_else(|| name.clone(), |m| m.key(metadata));
+            if !seen.insert(key) {
+                issues.push(MigrationIssue::Duplicate { name: name.clone() });
+            }
+            listed.insert(name.as_str());
+            if metadata.disabled {
+                continue;
+            }
+            heights
+                .entry(metadata.block_height)
+                .or_default()
+                .push(name.clone());
+            if metadata.block_height <= height {
+                issues.push(MigrationIssue::PastHeight {
+                    name: name.clone(),
+                    block_height: metadata.block_height,
+                    height,
+                });
+            }
+        }
+
+        if config.is_strict() {
+            let names = registry.iter().map(|m| m.name).collect::<BTreeSet<_>>();
+            issues.extend(
+                names
+                    .into_iter()
+                    .filter(|name| !listed.contains(name))
+                    .map(|name| MigrationIssue::Missing {
+                        name: name.to_string(),
+                    }),
+            );
+        }
+
+        issues.extend(
+            heights
+                .into_iter()
+                .filter(|(_, names)| names.len() > 1)
+                .map(|(block_height, names)| MigrationIssue::SameHeight {
+                    block_height,
+                    names,
+                }),
+        );
+
+        MigrationReport { height, issues }
+    }
+
     #[inline]
     pub fn update_at_height(&mut self, storage: &mut T, block_height: u64) -> Result<(), E> {
         for migration in self.inner.values_mut() {
@@ -598,6 +896,37 @@ impl<'a, T, E> MigrationSet<'a, T, E> {
         Ok(None)
     }
 
+    /// The visitors of the key/value hotfixes to run at this block height, in
+    /// the order of their names. The storage should apply the changes of a
+    /// visitor before visiting the next one.
+    pub fn key_value_hotfixes(
+        &self,
+        block_height: u64,
+    ) -> impl Iterator<Item = &'static dyn KeyValueVisitor> + '_ {
+        self.inner.values().filter_map(move |m| {
+            let visitor = m.key_value_hotfix(block_height);
+            if visitor.is_some() {
+                trace!("Key/value hotfix {} at height {block_height}", m.name());
+            }
+            visitor
+        })
+    }
+
+    /// The names and functions of the multi-key hotfixes to run at this block
+    /// height, in the order of their names. The storage should apply the
+    /// changes of a hotfix before running the next one, and run them after
+    /// the key/value hotfixes of the same height.
+    pub fn multi_key_hotfixes(
+        &self,
+        block_height: u64,
+    ) -> impl Iterator<Item = (&str, FnMultiKey)> + '_ {
+        self.inner.values().filter_map(move |m| {
+            let hotfix_fn = m.multi_key_hotfix(block_height)?;
+            trace!("Multi-key hotfix {} at height {block_height}", m.name());
+            Some((m.name(), hotfix_fn))
+        })
+    }
+
     #[inline]
     pub fn is_enabled(&self, name: impl AsRef<str>) -> bool {
         self.inner
@@ -0,0 +1,162 @@
+use crate::FnByte;
+use std::collections::BTreeMap;
+
+pub type FnKeyValue = fn(&[u8], &[u8]) -> Option<Vec<u8>>;
+pub type FnMultiKey = fn(&mut dyn KeyValueStore) -> Result<(), String>;
+
+/// Visits the key/value pairs of a storage during a hotfix, and returns the
+/// new values of the pairs to rewrite.
+pub trait KeyValueVisitor: Sync {
+    /// Only keys starting with this prefix are visited. Visiting the whole
+    /// storage can be slow, so this should be as specific as possible.
+    fn prefix(&self) -> &[u8];
+
+    /// Returns the new value of a pair, or [None] to leave it unchanged.
+    fn visit(&self, key: &[u8], value: &[u8]) -> Option<Vec<u8>>;
+}
+
+impl dyn KeyValueVisitor {
+    /// Visit a list of pairs, returning the pairs that changed with their new
+    /// value. Pairs outside of the prefix are skipped.
+    pub fn rewrite<K: AsRef<[u8]>, V: AsRef<[u8]>>(
+        &self,
+        pairs: impl IntoIterator<Item = (K, V)>,
+    ) -> Vec<(Vec<u8>, Vec<u8>)> {
+        pairs
+            .into_iter()
+            .filter(|(k, _)| k.as_ref().starts_with(self.prefix()))
+            .filter_map(|(k, v)| {
+                self.visit(k.as_ref(), v.as_ref())
+                    .map(|new| (k.as_ref().to_vec(), new))
+            })
+            .collect()
+    }
+}
+
+/// Rewrites the value of a single key.
+#[derive(Copy, Clone)]
+pub struct RewriteKey {
+    key: &'static [u8],
+    rewrite_fn: FnByte,
+}
+
+impl RewriteKey {
+    pub const fn new(key: &'static [u8], rewrite_fn: FnByte) -> Self {
+        Self { key, rewrite_fn }
+    }
+}
+
+impl KeyValueVisitor for RewriteKey {
+    fn prefix(&self) -> &[u8] {
+        self.key
+    }
+
+    fn visit(&self, key: &[u8], value: &[u8]) -> Option<Vec<u8>> {
+        if key == self.key {
+            (self.rewrite_fn)(value)
+        } else {
+            None
+        }
+    }
+}
+
+/// Rewrites the values of all keys starting with a prefix.
+#[derive(Copy, Clone)]
+pub struct RewritePrefix {
+    prefix: &'static [u8],
+    rewrite_fn: FnKeyValue,
+}
+
+impl RewritePrefix {
+    pub const fn new(prefix: &'static [u8], rewrite_fn: FnKeyValue) -> Self {
+        Self { prefix, rewrite_fn }
+    }
+}
+
+impl KeyValueVisitor for RewritePrefix {
+    fn prefix(&self) -> &[u8] {
+        self.prefix
+    }
+
+    fn visit(&self, key: &[u8], value: &[u8]) -> Option<Vec<u8>> {
+        (self.rewrite_fn)(key, value)
+    }
+}
+
+/// Replaces the value of an existing key. Keys that are not in the storage
+/// are not created.
+#[derive(Copy, Clone)]
+pub struct SetValue {
+    key: &'static [u8],
+    value: &'static [u8],
+}
+
+impl SetValue {
+    pub const fn new(key: &'static [u8], value: &'static [u8]) -> Self {
+        Self { key, value }
+    }
+}
+
+impl KeyValueVisitor for SetValue {
+    fn prefix(&self) -> &[u8] {
+        self.key
+    }
+
+    fn visit(&self, key: &[u8], value: &[u8]) -> Option<Vec<u8>> {
+        (key == self.key && value != self.value).then(|| self.value.to_vec())
+    }
+}
+
+/// Read and write access to the key/value pairs of a storage, given to the
+/// hotfixes that change multiple keys.
+pub trait KeyValueStore {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, String>;
+
+    fn put(&mut self, key: &[u8], value: Vec<u8>) -> Result<(), String>;
+
+    fn delete(&mut self, key: &[u8]) -> Result<(), String>;
+}
+
+/// A [KeyValueStore] recording the changes of a hotfix over a storage, so the
+/// storage can apply them all at once, or none if the hotfix fails. Reads see
+/// the changes recorded so far.
+pub struct KeyValueBatch<F> {
+    get_fn: F,
+    changes: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+}
+
+impl<F: Fn(&[u8]) -> Result<Option<Vec<u8>>, String>> KeyValueBatch<F> {
+    /// A batch reading the storage through `get_fn`.
+    pub fn new(get_fn: F) -> Self {
+        Self {
+            get_fn,
+            changes: BTreeMap::new(),
+        }
+    }
+
+    /// Run a hotfix and return its changes sorted by key, with [None] for
+    /// deleted keys.
+    pub fn run(mut self, hotfix_fn: FnMultiKey) -> Result<Vec<(Vec<u8>, Option<Vec<u8>>)>, String> {
+        hotfix_fn(&mut self)?;
+        Ok(self.changes.into_iter().collect())
+    }
+}
+
+impl<F: Fn(&[u8]) -> Result<Option<Vec<u8>>, String>> KeyValueStore for KeyValueBatch<F> {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, String> {
+        match self.changes.get(key) {
+            Some(change) => Ok(change.clone()),
+            None => (self.get_fn)(key),
+        }
+    }
+
+    fn put(&mut self, key: &[u8], value: Vec<u8>) -> Result<(), String> {
+        self.changes.insert(key.to_vec(), Some(value));
+        Ok(())
+    }
+
+    fn delete(&mut self, key: &[u8]) -> Result<(), String> {
+        self.changes.insert(key.to_vec(), None);
+        Ok(())
+    }
+}
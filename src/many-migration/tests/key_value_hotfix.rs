@@ -0,0 +1,194 @@
+#![feature(used_with_arg)] // Required to build the test with Bazel
+
+use linkme::distributed_slice;
+use many_migration::{
+    InnerMigration, KeyValueBatch, KeyValueStore, KeyValueVisitor, Metadata, MigrationSet,
+    RewriteKey, RewritePrefix, SetValue,
+};
+use std::collections::BTreeMap;
+
+type Storage = BTreeMap<Vec<u8>, Vec<u8>>;
+
+#[distributed_slice]
+static KEY_VALUE_MIGRATIONS: [InnerMigration<Storage, String>] = [..];
+
+/// Results stored with a legacy error code, which was renumbered.
+const LEGACY_CODE: i64 = -1;
+const NEW_CODE: i64 = -1000;
+
+fn _error_code(_key: &[u8], value: &[u8]) -> Option<Vec<u8>> {
+    let code = i64::from_be_bytes(value.try_into().ok()?);
+    (code == LEGACY_CODE).then(|| NEW_CODE.to_be_bytes().to_vec())
+}
+
+fn _double(value: &[u8]) -> Option<Vec<u8>> {
+    Some([value, value].concat())
+}
+
+static ERROR_CODE_VISITOR: RewritePrefix = RewritePrefix::new(b"/results/", _error_code);
+static DOUBLE_VISITOR: RewriteKey = RewriteKey::new(b"/config/a", _double);
+static SET_VISITOR: SetValue = SetValue::new(b"/config/b", b"fixed");
+
+#[distributed_slice(KEY_VALUE_MIGRATIONS)]
+static ERROR_CODE: InnerMigration<Storage, String> =
+    InnerMigration::new_key_value_hotfix(&ERROR_CODE_VISITOR, "ErrorCode", "Error code desc");
+
+#[distributed_slice(KEY_VALUE_MIGRATIONS)]
+static DOUBLE: InnerMigration<Storage, String> =
+    InnerMigration::new_key_value_hotfix(&DOUBLE_VISITOR, "Double", "Double desc");
+
+#[distributed_slice(KEY_VALUE_MIGRATIONS)]
+static SET: InnerMigration<Storage, String> =
+    InnerMigration::new_key_value_hotfix(&SET_VISITOR, "Set", "Set desc");
+
+/// Move the value of a key to another, and count the moves.
+fn _move(s: &mut dyn KeyValueStore) -> Result<(), String> {
+    let value = s.get(b"/config/a")?.ok_or("Nothing to move")?;
+    s.put(b"/moved/a", value)?;
+    s.delete(b"/config/a")?;
+
+    let count = s.get(b"/moved/count")?.map_or(0, |c| c[0]);
+    s.put(b"/moved/count", vec![count + 1])
+}
+
+#[distributed_slice(KEY_VALUE_MIGRATIONS)]
+static MOVE: InnerMigration<Storage, String> =
+    InnerMigration::new_multi_key_hotfix(_move, "Move", "Move desc");
+
+fn storage() -> Storage {
+    BTreeMap::from([
+        (b"/config/a".to_vec(), b"a".to_vec()),
+        (b"/config/ab".to_vec(), b"ab".to_vec()),
+        (b"/config/b".to_vec(), b"b".to_vec()),
+        (b"/results/1".to_vec(), LEGACY_CODE.to_be_bytes().to_vec()),
+        (b"/results/2".to_vec(), 5i64.to_be_bytes().to_vec()),
+        (b"/results/3".to_vec(), b"not a code".to_vec()),
+    ])
+}
+
+/// Apply the key/value hotfixes of a height the same way a storage would.
+fn apply(migrations: &MigrationSet<Storage, String>, storage: &mut Storage, height: u64) {
+    for visitor in migrations.key_value_hotfixes(height) {
+        let changes = visitor.rewrite(
+            storage
+                .range(visitor.prefix().to_vec()..)
+                .take_while(|(k, _)| k.starts_with(visitor.prefix())),
+        );
+        storage.extend(changes);
+    }
+    for (_, hotfix_fn) in migrations.multi_key_hotfixes(height) {
+        let changes = KeyValueBatch::new(|key: &[u8]| Ok(storage.get(key).cloned()))
+            .run(hotfix_fn)
+            .unwrap();
+        for (key, value) in changes {
+            match value {
+                Some(value) => storage.insert(key, value),
+                None => storage.remove(&key),
+            };
+        }
+    }
+}
+
+#[test]
+fn rewrite() {
+    let visitor: &dyn KeyValueVisitor = &ERROR_CODE_VISITOR;
+    assert_eq!(
+        visitor.rewrite(&storage()),
+        vec![(b"/results/1".to_vec(), NEW_CODE.to_be_bytes().to_vec())]
+    );
+
+    let visitor: &dyn KeyValueVisitor = &DOUBLE_VISITOR;
+    assert_eq!(
+        visitor.rewrite(&storage()),
+        vec![(b"/config/a".to_vec(), b"aa".to_vec())]
+    );
+
+    let visitor: &dyn KeyValueVisitor = &SET_VISITOR;
+    assert_eq!(
+        visitor.rewrite(&storage()),
+        vec![(b"/config/b".to_vec(), b"fixed".to_vec())]
+    );
+    assert!(visitor.rewrite([(b"/config/b", b"fixed")]).is_empty());
+}
+
+#[test]
+fn at_height() {
+    let migrations = MigrationSet::load(
+        &KEY_VALUE_MIGRATIONS,
+        [
+            (&ERROR_CODE, Metadata::enabled(2)),
+            (&DOUBLE, Metadata::enabled(3)),
+            (&SET, Metadata::disabled(2)),
+        ]
+        .into(),
+        0,
+    )
+    .unwrap();
+
+    let mut s = storage();
+    apply(&migrations, &mut s, 1);
+    assert_eq!(s, storage());
+
+    apply(&migrations, &mut s, 2);
+    assert_eq!(s[b"/results/1".as_slice()], NEW_CODE.to_be_bytes());
+    assert_eq!(s[b"/config/a".as_slice()], b"a");
+    assert_eq!(s[b"/config/b".as_slice()], b"b");
+
+    apply(&migrations, &mut s, 3);
+    assert_eq!(s[b"/config/a".as_slice()], b"aa");
+    assert_eq!(s[b"/config/ab".as_slice()], b"ab");
+
+    // Hotfixes only run at their height.
+    let before = s.clone();
+    apply(&migrations, &mut s, 4);
+    assert_eq!(s, before);
+}
+
+#[test]
+fn not_a_byte_hotfix() {
+    let migrations = MigrationSet::load(
+        &KEY_VALUE_MIGRATIONS,
+        [(&DOUBLE, Metadata::enabled(1))].into(),
+        0,
+    )
+    .unwrap();
+
+    assert!(migrations["Double"].is_hotfix());
+    assert_eq!(migrations.hotfix("Double", b"a", 1).unwrap(), None);
+}
+
+#[test]
+fn multi_key() {
+    let migrations = MigrationSet::load(
+        &KEY_VALUE_MIGRATIONS,
+        [
+            (&DOUBLE, Metadata::enabled(2)),
+            (&MOVE, Metadata::enabled(2)),
+        ]
+        .into(),
+        0,
+    )
+    .unwrap();
+
+    let mut s = storage();
+    apply(&migrations, &mut s, 2);
+    assert!(!s.contains_key(b"/config/a".as_slice()));
+    assert_eq!(s[b"/moved/a".as_slice()], b"aa");
+    assert_eq!(s[b"/moved/count".as_slice()], [1]);
+    assert_eq!(s[b"/config/ab".as_slice()], b"ab");
+}
+
+#[test]
+fn multi_key_failure() {
+    // A failed hotfix has no changes, even those made before it failed.
+    let s = Storage::from([(b"/moved/count".to_vec(), vec![1])]);
+    let result = KeyValueBatch::new(|key: &[u8]| Ok(s.get(key).cloned())).run(_move);
+    assert_eq!(result, Err("Nothing to move".to_string()));
+
+    // Reads see the changes of the hotfix.
+    let mut batch = KeyValueBatch::new(|key: &[u8]| Ok(s.get(key).cloned()));
+    batch.put(b"/config/a", b"a".to_vec()).unwrap();
+    assert_eq!(batch.get(b"/config/a").unwrap(), Some(b"a".to_vec()));
+    batch.delete(b"/moved/count").unwrap();
+    assert_eq!(batch.get(b"/moved/count").unwrap(), None);
+}
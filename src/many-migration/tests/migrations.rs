@@ -2,7 +2,8 @@
 
 use linkme::distributed_slice;
 use many_migration::{
-    InnerMigration, Metadata, Migration, MigrationConfig, MigrationSet, MigrationType,
+    InnerMigration, Metadata, Migration, MigrationConfig, MigrationIssue, MigrationSet,
+    MigrationType,
 };
 use serde_json::Value;
 use std::collections::{BTreeMap, HashMap};
@@ -505,3 +506,57 @@ fn strict_config_many() {
         r#"Migration Config is missing migrations ["C", "D", "E", "F"]"#.to_string()
     );
 }
+
+#[test]
+fn validate_config() {
+    let config: MigrationConfig = serde_json::from_str(
+        r#"{ "migrations": [
+            { "name": "A", "block_height": 5 },
+            { "name": "B", "block_height": 20 },
+            { "name": "C", "block_height": 20 },
+            { "name": "D", "block_height": 20, "disabled": true },
+            { "name": "B", "block_height": 30 },
+            { "name": "Z", "block_height": 40 }
+        ] }"#,
+    )
+    .unwrap();
+
+    let report = MigrationSet::validate(&SOME_MANY_RS_MIGRATIONS, &config.clone().strict(), 10);
+    assert_eq!(
+        report.issues,
+        vec![
+            MigrationIssue::PastHeight {
+                name: "A".to_string(),
+                block_height: 5,
+                height: 10,
+            },
+            MigrationIssue::Duplicate {
+                name: "B".to_string()
+            },
+            MigrationIssue::Unknown {
+                name: "Z".to_string()
+            },
+            MigrationIssue::Missing {
+                name: "E".to_string()
+            },
+            MigrationIssue::Missing {
+                name: "F".to_string()
+            },
+            MigrationIssue::SameHeight {
+                block_height: 20,
+                names: vec!["B".to_string(), "C".to_string()],
+            },
+        ]
+    );
+    assert!(!report.is_ok());
+    assert_eq!(report.errors().count(), 4);
+
+    // Warnings do not prevent loading the configuration.
+    let config = MigrationConfig::default()
+        .with_migration_opts(&A, Metadata::enabled(5))
+        .with_migration_opts(&B, Metadata::enabled(5));
+    let report = MigrationSet::validate(&SOME_MANY_RS_MIGRATIONS, &config, 0);
+    assert!(report.is_ok());
+    assert_eq!(report.warnings().count(), 1);
+    assert!(MigrationSet::load(&SOME_MANY_RS_MIGRATIONS, config, 0).is_ok());
+}
@@ -0,0 +1,68 @@
+#![feature(used_with_arg)] // Required to build the test with Bazel
+
+use linkme::distributed_slice;
+use many_migration::{InnerMigration, Metadata, MigrationConfig, MigrationIssue, MigrationSet};
+use serde_json::Value;
+use std::collections::HashMap;
+
+type Storage = Vec<u64>;
+
+#[distributed_slice]
+static REPEATABLE_MIGRATIONS: [InnerMigration<Storage, String>] = [..];
+
+fn _initialize(s: &mut Storage, extra: &HashMap<String, Value>) -> Result<(), String> {
+    s.push(extra.get("n").unwrap().as_u64().unwrap());
+    Ok(())
+}
+
+#[distributed_slice(REPEATABLE_MIGRATIONS)]
+static R: InnerMigration<Storage, String> =
+    InnerMigration::new_initialize(_initialize, "R", "R desc").repeatable();
+
+fn config() -> MigrationConfig {
+    serde_json::from_str(
+        r#"{ "migrations": [
+            { "name": "R", "block_height": 2, "n": 20 },
+            { "name": "R", "block_height": 3, "n": 30 }
+        ] }"#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn initialize_each_entry() {
+    assert!(R.is_repeatable());
+    let mut migrations = MigrationSet::load(&REPEATABLE_MIGRATIONS, config(), 0).unwrap();
+    assert_eq!(migrations.len(), 2);
+
+    let mut storage = Storage::new();
+    for height in 1..=4 {
+        migrations.update_at_height(&mut storage, height).unwrap();
+    }
+    assert_eq!(storage, [20, 30]);
+}
+
+#[test]
+fn validate() {
+    let report = MigrationSet::validate(&REPEATABLE_MIGRATIONS, &config().strict(), 0);
+    assert!(report.is_ok());
+    assert!(MigrationSet::load(&REPEATABLE_MIGRATIONS, config().strict(), 0).is_ok());
+
+    // Two entries at the same height would override each other.
+    let config = MigrationConfig::default()
+        .with_migration_opts(&R, Metadata::enabled(2))
+        .with_migration_opts(&R, Metadata::enabled(2));
+    let report = MigrationSet::validate(&REPEATABLE_MIGRATIONS, &config, 0);
+    assert_eq!(
+        report.issues,
+        vec![
+            MigrationIssue::Duplicate {
+                name: "R".to_string()
+            },
+            MigrationIssue::SameHeight {
+                block_height: 2,
+                names: vec!["R".to_string(), "R".to_string()],
+            },
+        ]
+    );
+}
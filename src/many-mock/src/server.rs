@@ -52,6 +52,8 @@ impl<I: Identity + Debug + Send + Sync> LowLevelManyRequestHandler for ManyMockS
             .ok_or_else(|| "No mock entry for that".to_string())?;
         let response = ResponseMessage {
             from: id.address(),
+            to: message.from,
+            id: message.id,
             data: Ok(response.clone()),
             ..Default::default()
         };
@@ -76,6 +78,8 @@ impl<I: Identity> base::BaseModuleBackend for ManyMockServer<I> {
             extras: Default::default(),
             server_version: None,
             timeout: None,
+            supported_versions: None,
+            network_id: None,
         })
     }
 }
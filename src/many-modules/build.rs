@@ -0,0 +1,696 @@
+//! Generates the CDDL rules exposed by `many_modules::cddl`.
+//!
+//! Every struct or enum deriving minicbor's `Encode` or `Decode` (or declared
+//! with `cbor_type_decl!`) in a module's files is turned into a rule named
+//! after the module namespace, e.g. `blockchain.ListArgs`. The shape of the
+//! rule comes from the minicbor attributes: `#[cbor(map)]`,
+//! `#[cbor(transparent)]`, `#[cbor(index_only)]`, the `#[n(..)]` indices and
+//! `with = "minicbor::bytes"`. Types with a hand-written encoding have no rule
+//! and are `any`.
+use proc_macro2::TokenTree;
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+use syn::parse::{Parse, ParseStream, Parser};
+use syn::punctuated::Punctuated;
+use syn::{
+    braced, parenthesized, Attribute, Fields, GenericArgument, Item, ItemMacro, LitInt, LitStr,
+    PathArguments, Token, Type, Visibility,
+};
+
+/// Rules that are defined by `src/cddl.rs` directly.
+const PRELUDE: &[(&str, &str)] = &[
+    ("Address", "address"),
+    ("SortOrder", "sort-order"),
+    ("Symbol", "address"),
+    ("Timestamp", "time"),
+    ("TokenAmount", "ledger-amount"),
+];
+
+#[derive(Clone, Debug)]
+enum Ty {
+    Any,
+    Bool,
+    Uint,
+    Int,
+    Float,
+    Text,
+    Bytes,
+    Nil,
+    Literal(u64),
+    Array(Box<Ty>),
+    Map(Box<Ty>, Box<Ty>),
+    Tuple(Vec<Ty>),
+    Choice(Vec<Ty>),
+    Named(String),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Encoding {
+    Map,
+    Array,
+    Transparent,
+}
+
+struct Field {
+    index: u64,
+    name: String,
+    ty: Ty,
+    optional: bool,
+}
+
+/// A variant of an enum, with the types of its fields in index order, or
+/// `None` if they are encoded as a map.
+struct Variant {
+    index: u64,
+    fields: Option<Vec<Ty>>,
+}
+
+enum Shape {
+    Struct(Encoding, Vec<Field>),
+    /// Enums are encoded as their variant index if they are
+    /// `#[cbor(index_only)]`, and as `[index, [fields]]` otherwise.
+    Enum {
+        index_only: bool,
+        variants: Vec<Variant>,
+    },
+}
+
+struct Rule {
+    namespace: String,
+    name: String,
+    shape: Shape,
+}
+
+/// The options of the minicbor attributes we care about.
+#[derive(Default)]
+struct CborAttrs {
+    index: Option<u64>,
+    map: bool,
+    transparent: bool,
+    index_only: bool,
+    bytes: bool,
+    custom: bool,
+}
+
+fn cbor_attrs(attrs: &[Attribute]) -> syn::Result<CborAttrs> {
+    let mut result = CborAttrs::default();
+    for attr in attrs {
+        let path = attr.path();
+        if path.is_ident("n") || path.is_ident("b") {
+            result.index = Some(attr.parse_args::<LitInt>()?.base10_parse()?);
+        } else if path.is_ident("cbor") {
+            attr.parse_nested_meta(|meta| {
+                let ident = meta.path.get_ident().map(ToString::to_string);
+                match ident.as_deref() {
+                    Some("n") | Some("b") => {
+                        let content;
+                        parenthesized!(content in meta.input);
+                        result.index = Some(content.parse::<LitInt>()?.base10_parse()?);
+                    }
+                    Some("map") => result.map = true,
+                    Some("transparent") => result.transparent = true,
+                    Some("index_only") => result.index_only = true,
+                    Some("with") => {
+                        let with = meta.value()?.parse::<LitStr>()?.value();
+                        if with == "minicbor::bytes" {
+                            result.bytes = true;
+                        } else {
+                            result.custom = true;
+                        }
+                    }
+                    Some("encode_with") | Some("decode_with") => {
+                        meta.value()?.parse::<LitStr>()?;
+                        result.custom = true;
+                    }
+                    _ => {
+                        // Skip over any argument of options we don't support.
+                        if meta.input.peek(Token![=]) {
+                            meta.value()?.parse::<syn::Expr>()?;
+                        } else if meta.input.peek(syn::token::Paren) {
+                            let content;
+                            parenthesized!(content in meta.input);
+                            content.parse::<proc_macro2::TokenStream>()?;
+                        }
+                    }
+                }
+                Ok(())
+            })?;
+        }
+    }
+    Ok(result)
+}
+
+fn derives_cbor(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path().is_ident("derive")
+            && attr
+                .parse_args_with(Punctuated::<syn::Path, Token![,]>::parse_terminated)
+                .map(|paths| {
+                    paths.iter().any(|p| {
+                        p.segments
+                            .last()
+                            .map_or(false, |s| s.ident == "Encode" || s.ident == "Decode")
+                    })
+                })
+                .unwrap_or(false)
+    })
+}
+
+fn type_args(args: &PathArguments) -> Vec<&Type> {
+    match args {
+        PathArguments::AngleBracketed(args) => args
+            .args
+            .iter()
+            .filter_map(|a| match a {
+                GenericArgument::Type(t) => Some(t),
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+fn is_u8(ty: &Type) -> bool {
+    matches!(ty, Type::Path(p) if p.path.is_ident("u8"))
+}
+
+fn option_inner(ty: &Type) -> Option<&Type> {
+    match ty {
+        Type::Path(p) => {
+            let last = p.path.segments.last()?;
+            if last.ident != "Option" {
+                return None;
+            }
+            type_args(&last.arguments).first().copied()
+        }
+        _ => None,
+    }
+}
+
+fn convert(ty: &Type, bytes: bool, generics: &BTreeSet<String>) -> Ty {
+    match ty {
+        Type::Path(p) => {
+            let Some(last) = p.path.segments.last() else {
+                return Ty::Any;
+            };
+            let name = last.ident.to_string();
+            let args = type_args(&last.arguments);
+            let arg = |i: usize| args.get(i).map_or(Ty::Any, |t| convert(t, bytes, generics));
+
+            if generics.contains(&name) {
+                return Ty::Any;
+            }
+            match name.as_str() {
+                "bool" => Ty::Bool,
+                "u8" | "u16" | "u32" | "u64" | "u128" | "usize" => Ty::Uint,
+                "i8" | "i16" | "i32" | "i64" | "i128" | "isize" => Ty::Int,
+                "f32" | "f64" => Ty::Float,
+                "String" | "str" => Ty::Text,
+                "ByteVec" | "Bytes" | "ByteArray" => Ty::Bytes,
+                "EmptyArg" | "EmptyReturn" => Ty::Nil,
+                "Vec" | "VecDeque" if bytes && args.first().map_or(false, |t| is_u8(t)) => {
+                    Ty::Bytes
+                }
+                "Vec" | "VecDeque" | "BTreeSet" | "HashSet" => Ty::Array(Box::new(arg(0))),
+                "BTreeMap" | "HashMap" => Ty::Map(Box::new(arg(0)), Box::new(arg(1))),
+                "Option" => Ty::Choice(vec![arg(0), Ty::Nil]),
+                "VecOrSingle" => Ty::Choice(vec![arg(0), Ty::Array(Box::new(arg(0)))]),
+                "Either" => Ty::Choice(vec![arg(0), arg(1)]),
+                "Box" | "Arc" | "Rc" | "Cow" => arg(0),
+                _ => Ty::Named(name),
+            }
+        }
+        Type::Reference(r) => convert(&r.elem, bytes, generics),
+        Type::Paren(p) => convert(&p.elem, bytes, generics),
+        Type::Group(g) => convert(&g.elem, bytes, generics),
+        Type::Array(a) if bytes && is_u8(&a.elem) => Ty::Bytes,
+        Type::Slice(s) if bytes && is_u8(&s.elem) => Ty::Bytes,
+        Type::Array(a) => Ty::Array(Box::new(convert(&a.elem, bytes, generics))),
+        Type::Slice(s) => Ty::Array(Box::new(convert(&s.elem, bytes, generics))),
+        Type::Tuple(t) if t.elems.is_empty() => Ty::Nil,
+        Type::Tuple(t) => Ty::Tuple(
+            t.elems
+                .iter()
+                .map(|t| convert(t, bytes, generics))
+                .collect(),
+        ),
+        _ => Ty::Any,
+    }
+}
+
+fn field(
+    index: u64,
+    name: String,
+    ty: &Type,
+    attrs: &CborAttrs,
+    encoding: Encoding,
+    generics: &BTreeSet<String>,
+) -> Field {
+    // Optional fields are skipped in maps, but are null in arrays.
+    let (ty, optional) = match option_inner(ty) {
+        Some(inner) if encoding == Encoding::Map => (inner, true),
+        _ => (ty, false),
+    };
+    let ty = if attrs.custom {
+        Ty::Any
+    } else {
+        convert(ty, attrs.bytes, generics)
+    };
+    Field {
+        index,
+        name,
+        ty,
+        optional,
+    }
+}
+
+fn item_struct(namespace: &str, item: &syn::ItemStruct) -> syn::Result<Option<Rule>> {
+    if !derives_cbor(&item.attrs) {
+        return Ok(None);
+    }
+    let attrs = cbor_attrs(&item.attrs)?;
+    let encoding = if attrs.transparent {
+        Encoding::Transparent
+    } else if attrs.map {
+        Encoding::Map
+    } else {
+        Encoding::Array
+    };
+    let generics = item
+        .generics
+        .type_params()
+        .map(|p| p.ident.to_string())
+        .collect();
+
+    let mut fields = Vec::new();
+    let members: Vec<_> = match &item.fields {
+        Fields::Named(f) => f.named.iter().collect(),
+        Fields::Unnamed(f) => f.unnamed.iter().collect(),
+        Fields::Unit => vec![],
+    };
+    for (i, f) in members.into_iter().enumerate() {
+        let field_attrs = cbor_attrs(&f.attrs)?;
+        // Fields without an index are skipped by minicbor.
+        let Some(index) = field_attrs.index else {
+            continue;
+        };
+        let name = f
+            .ident
+            .as_ref()
+            .map_or_else(|| i.to_string(), ToString::to_string);
+        fields.push(field(index, name, &f.ty, &field_attrs, encoding, &generics));
+    }
+
+    Ok(Some(Rule {
+        namespace: namespace.to_string(),
+        name: item.ident.to_string(),
+        shape: Shape::Struct(encoding, fields),
+    }))
+}
+
+fn item_enum(namespace: &str, item: &syn::ItemEnum) -> syn::Result<Option<Rule>> {
+    if !derives_cbor(&item.attrs) {
+        return Ok(None);
+    }
+    let attrs = cbor_attrs(&item.attrs)?;
+    let generics = item
+        .generics
+        .type_params()
+        .map(|p| p.ident.to_string())
+        .collect();
+
+    let mut variants = Vec::new();
+    for variant in &item.variants {
+        let variant_attrs = cbor_attrs(&variant.attrs)?;
+        let Some(index) = variant_attrs.index else {
+            continue;
+        };
+        let fields = if variant_attrs.map {
+            None
+        } else {
+            let mut fields = Vec::new();
+            for f in variant.fields.iter() {
+                let field_attrs = cbor_attrs(&f.attrs)?;
+                if let Some(index) = field_attrs.index {
+                    fields.push(field(
+                        index,
+                        String::new(),
+                        &f.ty,
+                        &field_attrs,
+                        Encoding::Array,
+                        &generics,
+                    ));
+                }
+            }
+            fields.sort_by_key(|f| f.index);
+            Some(fields.into_iter().map(|f| f.ty).collect())
+        };
+        variants.push(Variant { index, fields });
+    }
+
+    Ok(Some(Rule {
+        namespace: namespace.to_string(),
+        name: item.ident.to_string(),
+        shape: Shape::Enum {
+            index_only: attrs.index_only,
+            variants,
+        },
+    }))
+}
+
+/// A struct declared with `cbor_type_decl!`.
+struct DeclStruct {
+    name: syn::Ident,
+    fields: Vec<(u64, syn::Ident, Type)>,
+}
+
+impl Parse for DeclStruct {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        input.parse::<Visibility>()?;
+        input.parse::<Token![struct]>()?;
+        let name = input.parse()?;
+        let content;
+        braced!(content in input);
+
+        let mut fields = Vec::new();
+        while !content.is_empty() {
+            // Skip the tags before the index.
+            while content.peek(syn::Ident) {
+                content.parse::<syn::Ident>()?;
+            }
+            let index = content.parse::<LitInt>()?.base10_parse()?;
+            content.parse::<Token![=>]>()?;
+            let fname = content.parse()?;
+            content.parse::<Token![:]>()?;
+            let ty = content.parse()?;
+            fields.push((index, fname, ty));
+            if !content.is_empty() {
+                content.parse::<Token![,]>()?;
+            }
+        }
+        Ok(Self { name, fields })
+    }
+}
+
+fn item_macro(namespace: &str, item: &ItemMacro) -> syn::Result<Vec<Rule>> {
+    let is_decl = item
+        .mac
+        .path
+        .segments
+        .last()
+        .map_or(false, |s| s.ident == "cbor_type_decl");
+    if !is_decl {
+        return Ok(vec![]);
+    }
+
+    let parser = |input: ParseStream| {
+        let mut structs = Vec::new();
+        while !input.is_empty() {
+            structs.push(input.parse::<DeclStruct>()?);
+        }
+        Ok(structs)
+    };
+    let generics = BTreeSet::new();
+    let attrs = CborAttrs::default();
+    Ok(parser
+        .parse2(item.mac.tokens.clone())?
+        .into_iter()
+        .map(|decl| Rule {
+            namespace: namespace.to_string(),
+            name: decl.name.to_string(),
+            shape: Shape::Struct(
+                Encoding::Map,
+                decl.fields
+                    .into_iter()
+                    .map(|(index, name, ty)| {
+                        field(
+                            index,
+                            name.to_string(),
+                            &ty,
+                            &attrs,
+                            Encoding::Map,
+                            &generics,
+                        )
+                    })
+                    .collect(),
+            ),
+        })
+        .collect())
+}
+
+fn is_cfg_test(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path().is_ident("cfg")
+            && attr
+                .parse_args::<syn::Ident>()
+                .map_or(false, |i| i == "test")
+    })
+}
+
+fn items(namespace: &str, items: &[Item], out: &mut Vec<Rule>) -> syn::Result<()> {
+    for item in items {
+        match item {
+            Item::Struct(s) => out.extend(item_struct(namespace, s)?),
+            Item::Enum(e) => out.extend(item_enum(namespace, e)?),
+            Item::Macro(m) => out.extend(item_macro(namespace, m)?),
+            Item::Mod(m) if !is_cfg_test(&m.attrs) => {
+                if let Some((_, content)) = &m.content {
+                    self::items(namespace, content, out)?;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Find the namespace declared in the `#[many_module(...)]` attribute of a
+/// module file.
+fn namespace(file: &syn::File) -> Option<String> {
+    file.items.iter().find_map(|item| match item {
+        Item::Trait(t) => t.attrs.iter().find_map(|attr| {
+            if !attr.path().is_ident("many_module") {
+                return None;
+            }
+            let syn::Meta::List(list) = &attr.meta else {
+                return None;
+            };
+            // Namespaces can be keywords (e.g. `async`), so look at the raw
+            // tokens instead of parsing expressions.
+            let tokens = list.tokens.clone().into_iter().collect::<Vec<_>>();
+            tokens.windows(3).find_map(|w| match w {
+                [TokenTree::Ident(key), TokenTree::Punct(eq), TokenTree::Ident(value)]
+                    if key == "namespace" && eq.as_char() == '=' =>
+                {
+                    Some(value.to_string())
+                }
+                _ => None,
+            })
+        }),
+        _ => None,
+    })
+}
+
+fn rust_files(dir: &Path, out: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            rust_files(&path, out)?;
+        } else if path.extension().map_or(false, |e| e == "rs") {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn parse_file(path: &Path) -> Result<syn::File, Box<dyn Error>> {
+    println!("cargo:rerun-if-changed={}", path.display());
+    let content = fs::read_to_string(path)?;
+    syn::parse_file(&content).map_err(|e| format!("{}: {e}", path.display()).into())
+}
+
+/// Module files are named `_{id}_{name}.rs`, with their submodules in a
+/// directory of the same name.
+fn module_stem(path: &Path) -> Option<String> {
+    let stem = path.file_stem()?.to_str()?;
+    let rest = stem.strip_prefix('_')?;
+    let (id, _) = rest.split_once('_')?;
+    id.chars()
+        .all(|c| c.is_ascii_digit())
+        .then(|| stem.to_string())
+}
+
+fn collect(src: &Path) -> Result<Vec<Rule>, Box<dyn Error>> {
+    let mut modules = fs::read_dir(src)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .filter(|p| p.is_file())
+        .filter_map(|p| module_stem(&p).map(|stem| (stem, p)))
+        .collect::<Vec<_>>();
+    modules.sort_by_key(|(stem, _)| {
+        stem[1..]
+            .split('_')
+            .next()
+            .and_then(|id| id.parse::<u32>().ok())
+    });
+
+    let mut rules = Vec::new();
+    for (stem, path) in modules {
+        let file = parse_file(&path)?;
+        let namespace = namespace(&file).unwrap_or_else(|| "base".to_string());
+        items(&namespace, &file.items, &mut rules)?;
+
+        let mut files = Vec::new();
+        let dir = src.join(&stem);
+        if dir.is_dir() {
+            rust_files(&dir, &mut files)?;
+        }
+        for path in files {
+            items(&namespace, &parse_file(&path)?.items, &mut rules)?;
+        }
+    }
+    Ok(rules)
+}
+
+struct Resolver {
+    names: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl Resolver {
+    fn resolve(&self, namespace: &str, name: &str) -> Option<String> {
+        if let Some((_, rule)) = PRELUDE.iter().find(|(n, _)| *n == name) {
+            return Some(rule.to_string());
+        }
+        match self.names.get(name) {
+            Some(namespaces) if namespaces.contains(namespace) => {
+                Some(format!("{namespace}.{name}"))
+            }
+            Some(namespaces) if namespaces.len() == 1 => {
+                Some(format!("{}.{name}", namespaces.iter().next().unwrap()))
+            }
+            // Unknown or ambiguous.
+            _ => None,
+        }
+    }
+
+    fn render(&self, namespace: &str, ty: &Ty) -> String {
+        let list = |tys: &[Ty]| {
+            tys.iter()
+                .map(|t| self.render(namespace, t))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match ty {
+            Ty::Any => "CddlType::Any".to_string(),
+            Ty::Bool => "CddlType::Bool".to_string(),
+            Ty::Uint => "CddlType::Uint".to_string(),
+            Ty::Int => "CddlType::Int".to_string(),
+            Ty::Float => "CddlType::Float".to_string(),
+            Ty::Text => "CddlType::Text".to_string(),
+            Ty::Bytes => "CddlType::Bytes".to_string(),
+            Ty::Nil => "CddlType::Nil".to_string(),
+            Ty::Literal(n) => format!("CddlType::Literal({n})"),
+            Ty::Array(t) => format!("CddlType::Array(&{})", self.render(namespace, t)),
+            Ty::Map(k, v) => format!(
+                "CddlType::Map(&{}, &{})",
+                self.render(namespace, k),
+                self.render(namespace, v)
+            ),
+            Ty::Tuple(tys) => format!("CddlType::Tuple(&[{}])", list(tys)),
+            Ty::Choice(tys) => format!("CddlType::Choice(&[{}])", list(tys)),
+            // Never reference a rule which is not defined.
+            Ty::Named(name) => match self.resolve(namespace, name) {
+                Some(rule) => format!("CddlType::Rule({rule:?})"),
+                None => "CddlType::Any".to_string(),
+            },
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let src = Path::new(&env::var("CARGO_MANIFEST_DIR")?).join("src");
+    // Watch the directory too, so adding a module file reruns the script.
+    println!("cargo:rerun-if-changed={}", src.display());
+    let rules = collect(&src)?;
+
+    let mut names: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    let mut seen = BTreeSet::new();
+    for r in &rules {
+        names
+            .entry(r.name.clone())
+            .or_default()
+            .insert(r.namespace.clone());
+    }
+    let resolver = Resolver { names };
+
+    let mut out = String::from("&[\n");
+    for r in &rules {
+        // Keep the first declaration if a name is reused within a namespace.
+        if !seen.insert((r.namespace.as_str(), r.name.as_str())) {
+            continue;
+        }
+        let definition = match &r.shape {
+            Shape::Struct(encoding, fields) => {
+                let rendered = fields
+                    .iter()
+                    .map(|f| {
+                        format!(
+                            "CddlField {{ index: {}, name: {:?}, ty: {}, optional: {} }}",
+                            f.index,
+                            f.name,
+                            resolver.render(&r.namespace, &f.ty),
+                            f.optional,
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                match encoding {
+                    Encoding::Map => format!("CddlDefinition::Map(&[{}])", rendered.join(", ")),
+                    Encoding::Array => {
+                        format!("CddlDefinition::Array(&[{}])", rendered.join(", "))
+                    }
+                    Encoding::Transparent => format!(
+                        "CddlDefinition::Type({})",
+                        fields.first().map_or("CddlType::Any".to_string(), |f| {
+                            resolver.render(&r.namespace, &f.ty)
+                        })
+                    ),
+                }
+            }
+            Shape::Enum {
+                index_only,
+                variants,
+            } => {
+                let choices = variants
+                    .iter()
+                    .map(|v| match (*index_only, &v.fields) {
+                        (true, _) => Ty::Literal(v.index),
+                        (false, Some(fields)) => {
+                            Ty::Tuple(vec![Ty::Literal(v.index), Ty::Tuple(fields.clone())])
+                        }
+                        (false, None) => Ty::Tuple(vec![Ty::Literal(v.index), Ty::Any]),
+                    })
+                    .collect();
+                format!(
+                    "CddlDefinition::Type({})",
+                    resolver.render(&r.namespace, &Ty::Choice(choices))
+                )
+            }
+        };
+        writeln!(
+            out,
+            "    CddlRule {{ name: \"{}.{}\", definition: {definition} }},",
+            r.namespace, r.name
+        )?;
+    }
+    out.push(']');
+
+    fs::write(Path::new(&env::var("OUT_DIR")?).join("cddl.rs"), out)?;
+    Ok(())
+}
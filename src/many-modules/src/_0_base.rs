@@ -3,8 +3,10 @@ use coset::{CborSerializable, CoseKey};
 use derive_builder::Builder;
 use many_identity::Address;
 use many_macros::many_module;
-use many_types::attributes::AttributeSet;
+use many_types::attributes::{AttributeId, AttributeSet};
 use many_types::cbor::CborAny;
+use many_types::warning::Warning;
+use many_types::NetworkId;
 use minicbor::data::Type;
 use minicbor::encode::{Error, Write};
 use minicbor::{Decode, Decoder, Encode, Encoder};
@@ -21,6 +23,81 @@ pub struct Endpoints(#[n(0)] pub BTreeSet<String>);
 // TODO: Move this in it's own file, like other modules
 pub type HeartbeatReturn = EmptyReturn;
 
+/// Describes an endpoint, with the types of its argument (if any) and of its
+/// return value.
+#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct EndpointDescriptor {
+    #[n(0)]
+    pub name: String,
+
+    #[n(1)]
+    pub argument: Option<String>,
+
+    #[n(2)]
+    pub returns: String,
+
+    /// Set if the endpoint is deprecated.
+    #[n(3)]
+    pub deprecated: Option<Deprecation>,
+
+    /// The keys of the argument map which must not be logged, or all of the
+    /// argument if empty.
+    #[n(4)]
+    pub redacted: Option<Vec<u64>>,
+}
+
+/// How a deprecated endpoint is retired. Calls get a deprecation warning
+/// until the sunset height, and are rejected from it.
+#[derive(Clone, Debug, Default, Decode, Encode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct Deprecation {
+    /// The endpoint to call instead, if any.
+    #[n(0)]
+    pub replacement: Option<String>,
+
+    /// The block height from which calls are rejected, if any.
+    #[n(1)]
+    pub sunset: Option<u64>,
+}
+
+impl Deprecation {
+    /// Returns whether calls are rejected at this height.
+    pub fn is_sunset(&self, height: u64) -> bool {
+        self.sunset.map_or(false, |sunset| height >= sunset)
+    }
+
+    /// The warning added to the responses of a deprecated endpoint.
+    pub fn warning(&self, endpoint: &str) -> Warning {
+        let mut message = format!("Endpoint '{endpoint}' is deprecated.");
+        if let Some(replacement) = &self.replacement {
+            message.push_str(&format!(" Use '{replacement}' instead."));
+        }
+        if let Some(sunset) = self.sunset {
+            message.push_str(&format!(" It will be removed at height {sunset}."));
+        }
+        Warning::deprecated(message)
+    }
+}
+
+/// Describes a module, identified by its attribute, and all its endpoints.
+#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct ModuleDescriptor {
+    #[n(0)]
+    pub name: String,
+
+    #[n(1)]
+    pub attribute: Option<AttributeId>,
+
+    #[n(2)]
+    pub endpoints: Vec<EndpointDescriptor>,
+}
+
+#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
+#[cbor(transparent)]
+pub struct DescribeReturn(#[n(0)] pub Vec<ModuleDescriptor>);
+
 #[derive(Clone, Debug, Builder)]
 pub struct Status {
     pub version: u8,
@@ -35,6 +112,16 @@ pub struct Status {
     #[builder(setter(into, strip_option), default)]
     pub timeout: Option<u64>,
 
+    /// The protocol versions accepted by the server. Servers that do not
+    /// advertise them only accept `version`.
+    #[builder(setter(into, strip_option), default)]
+    pub supported_versions: Option<Vec<u8>>,
+
+    /// The network requests must be signed for, see
+    /// [`NETWORK_ID`](many_types::NETWORK_ID).
+    #[builder(setter(into, strip_option), default)]
+    pub network_id: Option<NetworkId>,
+
     #[builder(default)]
     pub extras: BTreeMap<String, CborAny>,
 }
@@ -47,6 +134,97 @@ impl Status {
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
         minicbor::decode(bytes).map_err(|e| e.to_string())
     }
+
+    /// The node information in the extras of this status.
+    pub fn node_info(&self) -> NodeInfo {
+        NodeInfo::from_extras(&self.extras)
+    }
+}
+
+/// The state of the node serving a status, stored in the status extras under
+/// standard keys so every server reports it the same way.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct NodeInfo {
+    /// The time the process started, in seconds since the Unix epoch.
+    pub start_time: Option<u64>,
+
+    /// The commit the server was built from.
+    pub git_sha: Option<String>,
+
+    /// The cargo features the server was built with.
+    pub features: Option<Vec<String>>,
+
+    /// The height of the storage of the server.
+    pub height: Option<u64>,
+
+    /// The number of module panics the server caught since it started.
+    pub module_panics: Option<u64>,
+}
+
+impl NodeInfo {
+    pub const START_TIME_KEY: &'static str = "startTime";
+    pub const GIT_SHA_KEY: &'static str = "gitSha";
+    pub const FEATURES_KEY: &'static str = "features";
+    pub const HEIGHT_KEY: &'static str = "height";
+    pub const MODULE_PANICS_KEY: &'static str = "modulePanics";
+
+    /// Add the fields which are set to the extras, replacing existing keys.
+    pub fn insert_into(&self, extras: &mut BTreeMap<String, CborAny>) {
+        if let Some(start_time) = self.start_time {
+            extras.insert(
+                Self::START_TIME_KEY.to_string(),
+                CborAny::Int(start_time as i64),
+            );
+        }
+        if let Some(git_sha) = &self.git_sha {
+            extras.insert(
+                Self::GIT_SHA_KEY.to_string(),
+                CborAny::String(git_sha.clone()),
+            );
+        }
+        if let Some(features) = &self.features {
+            extras.insert(
+                Self::FEATURES_KEY.to_string(),
+                CborAny::Array(features.iter().cloned().map(CborAny::String).collect()),
+            );
+        }
+        if let Some(height) = self.height {
+            extras.insert(Self::HEIGHT_KEY.to_string(), CborAny::Int(height as i64));
+        }
+        if let Some(module_panics) = self.module_panics {
+            extras.insert(
+                Self::MODULE_PANICS_KEY.to_string(),
+                CborAny::Int(module_panics as i64),
+            );
+        }
+    }
+
+    /// Read the fields from status extras. Keys of the wrong type are ignored.
+    pub fn from_extras(extras: &BTreeMap<String, CborAny>) -> Self {
+        let uint = |key: &str| match extras.get(key) {
+            Some(CborAny::Int(i)) => u64::try_from(*i).ok(),
+            _ => None,
+        };
+        Self {
+            start_time: uint(Self::START_TIME_KEY),
+            git_sha: match extras.get(Self::GIT_SHA_KEY) {
+                Some(CborAny::String(s)) => Some(s.clone()),
+                _ => None,
+            },
+            features: match extras.get(Self::FEATURES_KEY) {
+                Some(CborAny::Array(a)) => a
+                    .iter()
+                    .map(|f| match f {
+                        CborAny::String(s) => Some(s.clone()),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => None,
+            },
+            height: uint(Self::HEIGHT_KEY),
+            module_panics: uint(Self::MODULE_PANICS_KEY),
+        }
+    }
 }
 
 impl<C> Encode<C> for Status {
@@ -73,6 +251,14 @@ impl<C> Encode<C> for Status {
             e.u8(7)?.encode(timeout)?;
         }
 
+        if let Some(ref versions) = self.supported_versions {
+            e.u8(8)?.encode(versions)?;
+        }
+
+        if let Some(ref network_id) = self.network_id {
+            e.u8(9)?.str(network_id.as_str())?;
+        }
+
         for (k, v) in &self.extras {
             e.str(k.as_str())?.encode(v)?;
         }
@@ -111,6 +297,8 @@ impl<'b, C> Decode<'b, C> for Status {
                         4 => builder.attributes(d.decode()?),
                         5 => builder.server_version(d.decode::<String>()?),
                         7 => builder.timeout(d.decode::<u64>()?),
+                        8 => builder.supported_versions(d.decode::<Vec<u8>>()?),
+                        9 => builder.network_id(NetworkId::new(d.str()?)),
                         _ => &mut builder,
                     };
                 }
@@ -143,6 +331,9 @@ pub trait BaseModuleBackend: Send {
         Ok(HeartbeatReturn {})
     }
     fn status(&self) -> Result<Status, ManyError>;
+    fn describe(&self) -> Result<DescribeReturn, ManyError> {
+        Ok(DescribeReturn(vec![]))
+    }
 }
 
 #[cfg(test)]
@@ -171,6 +362,8 @@ mod tests {
             }]),
             server_version: Some("1.0.0".to_string()),
             timeout: Some(300),
+            supported_versions: Some(vec![1, 2]),
+            network_id: Some(NetworkId::from("testnet")),
             extras: BTreeMap::new(),
         };
         mock.expect_status()
@@ -187,6 +380,8 @@ mod tests {
         assert_eq!(status.attributes, results.attributes);
         assert_eq!(status.server_version, results.server_version);
         assert_eq!(status.timeout, results.timeout);
+        assert_eq!(status.supported_versions, results.supported_versions);
+        assert_eq!(status.network_id, results.network_id);
 
         let results = Status::from_bytes(&status.to_bytes().unwrap()).unwrap();
         assert_eq!(status.version, results.version);
@@ -196,6 +391,36 @@ mod tests {
         assert_eq!(status.attributes, results.attributes);
         assert_eq!(status.server_version, results.server_version);
         assert_eq!(status.timeout, results.timeout);
+        assert_eq!(status.supported_versions, results.supported_versions);
+        assert_eq!(status.network_id, results.network_id);
+    }
+
+    #[test]
+    fn node_info() {
+        let info = NodeInfo {
+            start_time: Some(1_650_000_000),
+            git_sha: Some("abcdef".to_string()),
+            features: Some(vec!["balance_testing".to_string()]),
+            height: Some(42),
+            module_panics: Some(1),
+        };
+        let mut extras = BTreeMap::from([("other".to_string(), CborAny::Bool(true))]);
+        info.insert_into(&mut extras);
+        assert_eq!(extras.len(), 6);
+        assert_eq!(NodeInfo::from_extras(&extras), info);
+
+        let id = generate_random_ed25519_identity();
+        let status = StatusBuilder::default()
+            .version(1)
+            .name("Foobar".to_string())
+            .identity(id.address())
+            .attributes(AttributeSet::default())
+            .extras(extras)
+            .build()
+            .unwrap();
+        let results = Status::from_bytes(&status.to_bytes().unwrap()).unwrap();
+        assert_eq!(results.node_info(), info);
+        assert_eq!(NodeInfo::from_extras(&BTreeMap::new()), NodeInfo::default());
     }
 
     #[test]
@@ -216,6 +441,31 @@ mod tests {
         assert_eq!(endpoints.0, results.0);
     }
 
+    #[test]
+    fn describe() {
+        let mut mock = MockBaseModuleBackend::new();
+        let describe = DescribeReturn(vec![ModuleDescriptor {
+            name: "BaseModule".to_string(),
+            attribute: Some(0),
+            endpoints: super::BaseModuleInfo.descriptors.clone(),
+        }]);
+        mock.expect_describe()
+            .times(1)
+            .return_const(Ok(describe.clone()));
+        let module = super::BaseModule::new(Arc::new(Mutex::new(mock)));
+        let results: DescribeReturn =
+            minicbor::decode(&call_module(1, &module, "describe", "null").unwrap()).unwrap();
+
+        assert_eq!(describe, results);
+        assert!(results.0[0].endpoints.contains(&EndpointDescriptor {
+            name: "status".to_string(),
+            argument: None,
+            returns: "Status".to_string(),
+            deprecated: None,
+            redacted: None,
+        }));
+    }
+
     #[test]
     fn heartbeat() {
         let mut mock = MockBaseModuleBackend::new();
@@ -36,6 +36,11 @@ pub struct AbciInfo {
 
     #[n(1)]
     pub hash: ByteVec,
+
+    /// The digest of the events emitted during the last committed block (see
+    /// `events::digest`), if the application keeps an event log.
+    #[n(2)]
+    pub events_digest: Option<ByteVec>,
 }
 
 #[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
@@ -53,6 +58,11 @@ pub struct AbciCommitInfo {
 
     #[n(1)]
     pub hash: ByteVec,
+
+    /// The digest of the events emitted during the last committed block (see
+    /// `events::digest`), if the application keeps an event log.
+    #[n(2)]
+    pub events_digest: Option<ByteVec>,
 }
 
 pub type InitChainReturn = EmptyReturn;
@@ -153,6 +163,7 @@ mod tests {
         let info = AbciInfo {
             height: 1,
             hash: vec![13u8; 8].into(),
+            events_digest: Some(vec![15u8; 32].into()),
         };
         let mut mock = MockManyAbciModuleBackend::new();
         mock.expect_info().times(1).return_const(Ok(info.clone()));
@@ -179,6 +190,7 @@ mod tests {
         let commit_info = AbciCommitInfo {
             retain_height: 1,
             hash: vec![14u8; 8].into(),
+            events_digest: None,
         };
         let mut mock = MockManyAbciModuleBackend::new();
         mock.expect_commit()
@@ -18,8 +18,9 @@ pub use types::*;
 #[many_module(name = IdStoreModule, id = 1002, namespace = idstore, many_modules_crate = crate)]
 #[cfg_attr(test, automock)]
 pub trait IdStoreModuleBackend: Send {
-    #[many(check_webauthn, deny_anonymous)]
+    #[many(check_webauthn, deny_anonymous, redact(1, 2))]
     fn store(&mut self, sender: &Address, args: StoreArgs) -> Result<StoreReturns, ManyError>;
+    #[many(redact)]
     fn get_from_recall_phrase(
         &self,
         args: GetFromRecallPhraseArgs,
@@ -31,6 +32,7 @@ pub trait IdStoreModuleBackend: Send {
 mod tests {
     use super::*;
     use crate::testutils::{call_module_cbor, call_module_envelope};
+    use crate::ManyModule;
     use coset::CborSerializable;
     use many_identity::testing::identity;
     use many_identity_dsa::ed25519::generate_random_ed25519_identity;
@@ -43,6 +45,19 @@ mod tests {
         sync::{Arc, Mutex},
     };
 
+    #[test]
+    fn redacted() {
+        let mock = MockIdStoreModuleBackend::new();
+        let module = super::IdStoreModule::new(Arc::new(Mutex::new(mock)));
+        let info = module.info();
+        assert_eq!(info.redacted("idstore.store"), Some([1, 2].as_slice()));
+        assert_eq!(
+            info.redacted("idstore.getFromRecallPhrase"),
+            Some([].as_slice())
+        );
+        assert_eq!(info.redacted("idstore.getFromAddress"), None);
+    }
+
     #[test]
     fn store() {
         let id = generate_random_ed25519_identity();
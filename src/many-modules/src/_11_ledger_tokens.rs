@@ -2,7 +2,8 @@ use crate::EmptyReturn;
 use many_error::ManyError;
 use many_identity::Address;
 use many_macros::many_module;
-use many_types::{cbor_type_decl, ledger, AttributeRelatedIndex, Memo};
+use many_protocol::context::Context;
+use many_types::{cbor_type_decl, ledger, AttributeRelatedIndex, Memo, SortOrder, Timestamp};
 use minicbor::{Decode, Encode};
 
 pub mod extended_info;
@@ -31,6 +32,34 @@ cbor_type_decl!(
         1 => extended_info: extended_info::TokenExtendedInfo,
     }
 
+    pub struct TokenListArgs {
+        0 => count: Option<u64>,
+        1 => order: Option<SortOrder>,
+        // Only list the symbols after this one (in the requested order).
+        2 => after: Option<ledger::Symbol>,
+    }
+
+    pub struct TokenListReturns {
+        0 => tokens: Vec<ledger::TokenInfo>,
+    }
+
+    pub struct TokenHistoryArgs {
+        0 => symbol: ledger::Symbol,
+    }
+
+    pub struct TokenHistoryReturns {
+        0 => history: Vec<TokenHistoryEntry>,
+    }
+
+    pub struct TokenSupplyHistoryArgs {
+        0 => symbol: ledger::Symbol,
+    }
+
+    pub struct TokenSupplyHistoryReturns {
+        0 => history: Vec<TokenSupplyHistoryEntry>,
+        1 => maximum: Option<ledger::TokenAmount>,
+    }
+
     pub struct TokenUpdateArgs {
         0 => symbol: ledger::Symbol,
         1 => name: Option<String>,
@@ -53,6 +82,39 @@ cbor_type_decl!(
     }
 );
 
+/// A previous name and ticker of a token, and the time at which they were
+/// replaced.
+#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct TokenHistoryEntry {
+    #[n(0)]
+    pub name: String,
+
+    #[n(1)]
+    pub ticker: String,
+
+    #[n(2)]
+    pub until: Timestamp,
+}
+
+/// The tokens minted and burned during a block, and the circulating supply
+/// at the end of the block.
+#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct TokenSupplyHistoryEntry {
+    #[n(0)]
+    pub height: u64,
+
+    #[n(1)]
+    pub minted: ledger::TokenAmount,
+
+    #[n(2)]
+    pub burned: ledger::TokenAmount,
+
+    #[n(3)]
+    pub circulating: ledger::TokenAmount,
+}
+
 pub type TokenUpdateReturns = EmptyReturn;
 pub type TokenAddExtendedInfoReturns = EmptyReturn;
 pub type TokenRemoveExtendedInfoReturns = EmptyReturn;
@@ -69,6 +131,27 @@ pub trait LedgerTokensModuleBackend: Send {
 
     fn info(&self, sender: &Address, args: TokenInfoArgs) -> Result<TokenInfoReturns, ManyError>;
 
+    fn list(
+        &self,
+        sender: &Address,
+        args: TokenListArgs,
+        context: Context,
+    ) -> Result<TokenListReturns, ManyError>;
+
+    fn history(
+        &self,
+        sender: &Address,
+        args: TokenHistoryArgs,
+        context: Context,
+    ) -> Result<TokenHistoryReturns, ManyError>;
+
+    fn supply_history(
+        &self,
+        sender: &Address,
+        args: TokenSupplyHistoryArgs,
+        context: Context,
+    ) -> Result<TokenSupplyHistoryReturns, ManyError>;
+
     #[many(deny_anonymous)]
     fn update(
         &mut self,
@@ -98,6 +181,7 @@ mod tests {
     use crate::testutils::call_module_cbor;
     use many_identity::testing::identity;
     use many_types::ledger::{TokenInfo, TokenInfoSummary, TokenInfoSupply};
+    use mockall::predicate;
     use mockall::predicate::eq;
     use std::sync::{Arc, Mutex};
 
@@ -142,6 +226,112 @@ mod tests {
         assert_eq!(create_returns.info, info);
     }
 
+    #[test]
+    fn list() {
+        let mut mock = MockLedgerTokensModuleBackend::new();
+        let data = TokenListArgs {
+            count: Some(10),
+            order: Some(SortOrder::Descending),
+            after: None,
+        };
+        let info = TokenInfo {
+            symbol: Default::default(),
+            summary: TokenInfoSummary {
+                name: "Foobar".to_string(),
+                ticker: "FBR".to_string(),
+                decimals: 9,
+            },
+            supply: TokenInfoSupply {
+                total: 1000u64.into(),
+                circulating: 1000u64.into(),
+                maximum: None,
+            },
+            owner: Some(identity(1)),
+        };
+        mock.expect_list()
+            .with(eq(identity(1)), eq(data.clone()), predicate::always())
+            .times(1)
+            .return_const(Ok(TokenListReturns {
+                tokens: vec![info.clone()],
+            }));
+        let module = super::LedgerTokensModule::new(Arc::new(Mutex::new(mock)));
+
+        let list_returns: TokenListReturns = minicbor::decode(
+            &call_module_cbor(1, &module, "tokens.list", minicbor::to_vec(data).unwrap()).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(list_returns.tokens, vec![info]);
+    }
+
+    #[test]
+    fn history() {
+        let mut mock = MockLedgerTokensModuleBackend::new();
+        let data = TokenHistoryArgs {
+            symbol: Default::default(),
+        };
+        let history = vec![TokenHistoryEntry {
+            name: "Foobar".to_string(),
+            ticker: "FBR".to_string(),
+            until: Timestamp::new(1_000_000).unwrap(),
+        }];
+        mock.expect_history()
+            .with(eq(identity(1)), eq(data.clone()), predicate::always())
+            .times(1)
+            .return_const(Ok(TokenHistoryReturns {
+                history: history.clone(),
+            }));
+        let module = super::LedgerTokensModule::new(Arc::new(Mutex::new(mock)));
+
+        let history_returns: TokenHistoryReturns = minicbor::decode(
+            &call_module_cbor(
+                1,
+                &module,
+                "tokens.history",
+                minicbor::to_vec(data).unwrap(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(history_returns.history, history);
+    }
+
+    #[test]
+    fn supply_history() {
+        let mut mock = MockLedgerTokensModuleBackend::new();
+        let data = TokenSupplyHistoryArgs {
+            symbol: Default::default(),
+        };
+        let returns = TokenSupplyHistoryReturns {
+            history: vec![TokenSupplyHistoryEntry {
+                height: 3,
+                minted: 100u64.into(),
+                burned: 10u64.into(),
+                circulating: 90u64.into(),
+            }],
+            maximum: Some(1000u64.into()),
+        };
+        mock.expect_supply_history()
+            .with(eq(identity(1)), eq(data.clone()), predicate::always())
+            .times(1)
+            .return_const(Ok(returns.clone()));
+        let module = super::LedgerTokensModule::new(Arc::new(Mutex::new(mock)));
+
+        let supply_history_returns: TokenSupplyHistoryReturns = minicbor::decode(
+            &call_module_cbor(
+                1,
+                &module,
+                "tokens.supplyHistory",
+                minicbor::to_vec(data).unwrap(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(supply_history_returns, returns);
+    }
+
     #[test]
     fn update() {
         let mut mock = MockLedgerTokensModuleBackend::new();
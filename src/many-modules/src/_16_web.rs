@@ -4,9 +4,11 @@ use many_macros::many_module;
 
 pub mod info;
 pub mod list;
+pub mod versions;
 
 pub use info::*;
 pub use list::*;
+pub use versions::*;
 
 #[cfg(test)]
 use mockall::{automock, predicate::*};
@@ -17,12 +19,20 @@ pub trait WebModuleBackend: Send {
     fn info(&self, sender: &Address, args: InfoArg) -> Result<InfoReturns, ManyError>;
 
     fn list(&self, sender: &Address, args: ListArgs) -> Result<ListReturns, ManyError>;
+
+    fn versions(&self, sender: &Address, args: VersionsArgs) -> Result<VersionsReturns, ManyError>;
 }
 
 #[cfg(test)]
 mod tests {
     use crate::testutils::call_module_cbor;
-    use crate::web::{InfoReturns, ListReturns, MockWebModuleBackend};
+    use crate::web::{
+        InfoReturns, ListReturns, MockWebModuleBackend, VersionsArgs, VersionsReturns,
+    };
+    use many_identity::testing::identity;
+    use many_types::web::WebDeploymentVersion;
+    use many_types::Timestamp;
+    use mockall::predicate;
     use std::sync::{Arc, Mutex};
 
     #[test]
@@ -77,4 +87,36 @@ mod tests {
         .unwrap();
         assert_eq!(list.deployments, vec![])
     }
+
+    #[test]
+    fn versions() {
+        let mut mock = MockWebModuleBackend::new();
+        let data = VersionsArgs {
+            owner: None,
+            site_name: "foobar".to_string(),
+        };
+        mock.expect_versions()
+            .with(predicate::eq(identity(1)), predicate::eq(data.clone()))
+            .times(1)
+            .returning(|_sender, _args| {
+                Ok(VersionsReturns {
+                    versions: vec![WebDeploymentVersion {
+                        version: 1,
+                        source_hash: "abcd".to_string(),
+                        timestamp: Timestamp::new(1_000_000).unwrap(),
+                        site_description: None,
+                        domain: None,
+                    }],
+                    current: 1,
+                })
+            });
+        let module = super::WebModule::new(Arc::new(Mutex::new(mock)));
+
+        let versions: VersionsReturns = minicbor::decode(
+            &call_module_cbor(1, &module, "web.versions", minicbor::to_vec(data).unwrap()).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(versions.current, 1);
+        assert_eq!(versions.versions[0].source_hash, "abcd");
+    }
 }
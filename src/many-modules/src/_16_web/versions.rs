@@ -0,0 +1,25 @@
+use many_identity::Address;
+use many_types::web::WebDeploymentVersion;
+use minicbor::{Decode, Encode};
+
+#[derive(Clone, Debug, Encode, Decode, PartialEq, Eq)]
+#[cbor(map)]
+pub struct VersionsArgs {
+    #[n(0)]
+    pub owner: Option<Address>,
+
+    #[n(1)]
+    pub site_name: String,
+}
+
+#[derive(Clone, Debug, Encode, Decode, PartialEq, Eq)]
+#[cbor(map)]
+pub struct VersionsReturns {
+    /// All the versions of the website, from the oldest to the newest.
+    #[n(0)]
+    pub versions: Vec<WebDeploymentVersion>,
+
+    /// The version currently served.
+    #[n(1)]
+    pub current: u64,
+}
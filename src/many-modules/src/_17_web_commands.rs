@@ -4,10 +4,12 @@ use many_macros::many_module;
 
 pub mod deploy;
 pub mod remove;
+pub mod rollback;
 pub mod update;
 
 pub use deploy::*;
 pub use remove::*;
+pub use rollback::*;
 pub use update::*;
 
 #[cfg(test)]
@@ -24,6 +26,14 @@ pub trait WebCommandsModuleBackend: Send {
 
     #[many(deny_anonymous)]
     fn update(&mut self, sender: &Address, args: UpdateArgs) -> Result<UpdateReturns, ManyError>;
+
+    /// Serve a previous version of a website again.
+    #[many(deny_anonymous)]
+    fn rollback(
+        &mut self,
+        sender: &Address,
+        args: RollbackArgs,
+    ) -> Result<RollbackReturns, ManyError>;
 }
 
 #[cfg(test)]
@@ -31,7 +41,7 @@ mod tests {
     use crate::testutils::call_module_cbor;
     use crate::web::{
         DeployArgs, DeployReturns, MockWebCommandsModuleBackend, RemoveArgs, RemoveReturns,
-        UpdateArgs, UpdateReturns,
+        RollbackArgs, RollbackReturns, UpdateArgs, UpdateReturns,
     };
     use many_identity::testing::identity;
     use many_types::web::{WebDeploymentInfo, WebDeploymentSource};
@@ -125,4 +135,36 @@ mod tests {
         .unwrap();
         assert_eq!(deploy.info.url, Some("foobar".to_string()));
     }
+
+    #[test]
+    fn rollback() {
+        let mut mock = MockWebCommandsModuleBackend::new();
+        let data = RollbackArgs {
+            owner: None,
+            site_name: "foobar".to_string(),
+            version: 1,
+            memo: None,
+        };
+        mock.expect_rollback()
+            .with(predicate::eq(identity(1)), predicate::eq(data.clone()))
+            .times(1)
+            .returning(|sender, args| {
+                Ok(RollbackReturns {
+                    info: WebDeploymentInfo {
+                        owner: *sender,
+                        site_name: args.site_name,
+                        site_description: None,
+                        url: Some("foobar".to_string()),
+                        domain: None,
+                    },
+                })
+            });
+        let module = super::WebCommandsModule::new(Arc::new(Mutex::new(mock)));
+
+        let rollback: RollbackReturns = minicbor::decode(
+            &call_module_cbor(1, &module, "web.rollback", minicbor::to_vec(data).unwrap()).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(rollback.info.site_name, "foobar");
+    }
 }
@@ -0,0 +1,27 @@
+use many_identity::Address;
+use many_types::web::WebDeploymentInfo;
+use many_types::Memo;
+use minicbor::{Decode, Encode};
+
+#[derive(Clone, Debug, Encode, Decode, PartialEq, Eq)]
+#[cbor(map)]
+pub struct RollbackArgs {
+    #[n(0)]
+    pub owner: Option<Address>,
+
+    #[n(1)]
+    pub site_name: String,
+
+    #[n(2)]
+    pub version: u64,
+
+    #[n(3)]
+    pub memo: Option<Memo>,
+}
+
+#[derive(Clone, Debug, Encode, Decode, PartialEq, Eq)]
+#[cbor(map)]
+pub struct RollbackReturns {
+    #[n(0)]
+    pub info: WebDeploymentInfo,
+}
@@ -0,0 +1,119 @@
+use many_error::ManyError;
+use many_identity::Address;
+use many_macros::many_module;
+use minicbor::bytes::ByteVec;
+use minicbor::{Decode, Encode};
+use sha3::{Digest, Sha3_256};
+
+#[cfg(test)]
+use mockall::{automock, predicate::*};
+
+/// The size of a seed, in bytes.
+pub const SEED_LENGTH: usize = 32;
+
+#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct GetArgs {
+    /// Applications using the beacon for different purposes should each use
+    /// their own domain, so their values are independent.
+    #[n(0)]
+    pub domain: Option<ByteVec>,
+}
+
+#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct GetReturns {
+    #[n(0)]
+    pub height: u64,
+
+    /// The hash the seed was derived from.
+    #[n(1)]
+    pub hash: ByteVec,
+
+    #[n(2)]
+    pub seed: ByteVec,
+}
+
+/// Derive the seed of a block from its height and hash, and the optional
+/// contributions of the validators. Every node derives the same seed, so
+/// anyone can check the value returned by a server.
+///
+/// The seed can be predicted by the proposer of the block, so it should not be
+/// used where the proposer could benefit from choosing the block content.
+pub fn seed<C: AsRef<[u8]>>(
+    height: u64,
+    hash: &[u8],
+    contributions: &[C],
+    domain: Option<&[u8]>,
+) -> [u8; SEED_LENGTH] {
+    // Contributions are sorted so their order does not change the seed.
+    let mut contributions: Vec<&[u8]> = contributions.iter().map(AsRef::as_ref).collect();
+    contributions.sort_unstable();
+
+    let mut hasher = Sha3_256::new();
+    hasher.update(b"many-random");
+    hasher.update(height.to_be_bytes());
+    for bytes in std::iter::once(hash).chain(contributions).chain(domain) {
+        hasher.update((bytes.len() as u64).to_be_bytes());
+        hasher.update(bytes);
+    }
+    hasher.finalize().into()
+}
+
+#[many_module(name = RandomModule, id = 18, namespace = random, many_modules_crate = crate)]
+#[cfg_attr(test, automock)]
+pub trait RandomModuleBackend: Send {
+    fn get(&self, sender: &Address, args: GetArgs) -> Result<GetReturns, ManyError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils::call_module_cbor;
+    use many_identity::testing::identity;
+    use mockall::predicate;
+    use std::sync::{Arc, Mutex};
+
+    const NONE: &[&[u8]] = &[];
+
+    #[test]
+    fn get() {
+        let data = GetArgs {
+            domain: Some(b"lottery".to_vec().into()),
+        };
+        let mut mock = MockRandomModuleBackend::new();
+        mock.expect_get()
+            .with(predicate::eq(identity(1)), predicate::eq(data.clone()))
+            .times(1)
+            .returning(|_, args| {
+                let domain = args.domain.as_ref().map(|d| d.as_slice());
+                Ok(GetReturns {
+                    height: 5,
+                    hash: vec![1u8; 8].into(),
+                    seed: seed(5, &[1u8; 8], NONE, domain).to_vec().into(),
+                })
+            });
+        let module = super::RandomModule::new(Arc::new(Mutex::new(mock)));
+
+        let get_returns: GetReturns = minicbor::decode(
+            &call_module_cbor(1, &module, "random.get", minicbor::to_vec(data).unwrap()).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(get_returns.height, 5);
+        assert_eq!(
+            get_returns.seed.as_slice(),
+            seed(5, &[1u8; 8], NONE, Some(b"lottery"))
+        );
+    }
+
+    #[test]
+    fn deterministic() {
+        let a = seed(1, b"hash", &[b"x", b"y"], None);
+        assert_eq!(a, seed(1, b"hash", &[b"y", b"x"], None));
+        assert_ne!(a, seed(2, b"hash", &[b"x", b"y"], None));
+        assert_ne!(a, seed(1, b"other", &[b"x", b"y"], None));
+        assert_ne!(a, seed(1, b"hash", &[b"x"], None));
+        assert_ne!(a, seed(1, b"hash", &[b"x", b"y"], Some(b"domain")));
+        assert_ne!(seed(1, b"ab", NONE, None), seed(1, b"a", &[b"b"], None));
+    }
+}
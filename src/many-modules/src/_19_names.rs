@@ -0,0 +1,187 @@
+use crate::{EmptyArg, EmptyReturn};
+use many_error::{define_attribute_many_error, ManyError};
+use many_identity::Address;
+use many_macros::many_module;
+use many_types::ledger::{Symbol, TokenAmount};
+use many_types::{cbor_type_decl, Timestamp};
+use minicbor::{Decode, Encode};
+
+#[cfg(test)]
+use mockall::{automock, predicate::*};
+
+define_attribute_many_error!(
+    attribute 19 => {
+        1: pub fn invalid_name(name) => "Invalid name: {name}. Names are 3 to 64 lowercase letters, digits or dashes.",
+        2: pub fn name_taken(name) => "Name is already registered: {name}.",
+        3: pub fn name_not_found(name) => "Name is not registered or has expired: {name}.",
+        4: pub fn not_name_owner(name) => "Only the owner can do this operation on the name: {name}.",
+    }
+);
+
+pub const NAME_MIN_LENGTH: usize = 3;
+pub const NAME_MAX_LENGTH: usize = 64;
+
+/// Check that a name can be registered. Names are restricted to lowercase
+/// ASCII so two names cannot look the same.
+pub fn validate_name(name: &str) -> Result<(), ManyError> {
+    let valid = (NAME_MIN_LENGTH..=NAME_MAX_LENGTH).contains(&name.len())
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+        && !name.starts_with('-')
+        && !name.ends_with('-');
+    if valid {
+        Ok(())
+    } else {
+        Err(invalid_name(name.to_string()))
+    }
+}
+
+/// The fee paid to register or renew a name.
+#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct NameFee {
+    #[n(0)]
+    pub symbol: Symbol,
+
+    #[n(1)]
+    pub amount: TokenAmount,
+
+    /// The account receiving the fees.
+    #[n(2)]
+    pub collector: Address,
+}
+
+pub type InfoArgs = EmptyArg;
+
+cbor_type_decl!(
+    pub struct InfoReturns {
+        0 => fee: Option<NameFee>,
+        // Number of seconds a registration lasts.
+        1 => duration: u64,
+    }
+
+    pub struct ResolveArgs {
+        0 => name: String,
+    }
+
+    pub struct ResolveReturns {
+        0 => owner: Address,
+        1 => expiration: Timestamp,
+    }
+
+    pub struct RegisterArgs {
+        0 => name: String,
+    }
+
+    pub struct RegisterReturns {
+        0 => expiration: Timestamp,
+    }
+
+    pub struct TransferArgs {
+        0 => name: String,
+        1 => new_owner: Address,
+    }
+);
+
+pub type TransferReturns = EmptyReturn;
+
+#[many_module(name = NamesModule, id = 19, namespace = names, many_modules_crate = crate)]
+#[cfg_attr(test, automock)]
+pub trait NamesModuleBackend: Send {
+    fn info(&self, sender: &Address, args: InfoArgs) -> Result<InfoReturns, ManyError>;
+
+    /// Resolve a name to the address owning it.
+    fn resolve(&self, sender: &Address, args: ResolveArgs) -> Result<ResolveReturns, ManyError>;
+
+    /// Register a name for the sender, or renew it if the sender already owns
+    /// it. Expired names can be registered by anyone.
+    #[many(deny_anonymous)]
+    fn register(
+        &mut self,
+        sender: &Address,
+        args: RegisterArgs,
+    ) -> Result<RegisterReturns, ManyError>;
+
+    #[many(deny_anonymous)]
+    fn transfer(
+        &mut self,
+        sender: &Address,
+        args: TransferArgs,
+    ) -> Result<TransferReturns, ManyError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils::call_module_cbor;
+    use many_identity::testing::identity;
+    use mockall::predicate;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn resolve() {
+        let data = ResolveArgs {
+            name: "alice".to_string(),
+        };
+        let mut mock = MockNamesModuleBackend::new();
+        mock.expect_resolve()
+            .with(predicate::eq(identity(1)), predicate::eq(data.clone()))
+            .times(1)
+            .return_const(Ok(ResolveReturns {
+                owner: identity(2),
+                expiration: Timestamp::new(1000).unwrap(),
+            }));
+        let module = super::NamesModule::new(Arc::new(Mutex::new(mock)));
+
+        let resolve_returns: ResolveReturns = minicbor::decode(
+            &call_module_cbor(1, &module, "names.resolve", minicbor::to_vec(data).unwrap())
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(resolve_returns.owner, identity(2));
+        assert_eq!(resolve_returns.expiration, Timestamp::new(1000).unwrap());
+    }
+
+    #[test]
+    fn register() {
+        let data = RegisterArgs {
+            name: "alice".to_string(),
+        };
+        let mut mock = MockNamesModuleBackend::new();
+        mock.expect_register()
+            .with(predicate::eq(identity(1)), predicate::eq(data.clone()))
+            .times(1)
+            .return_const(Ok(RegisterReturns {
+                expiration: Timestamp::new(1000).unwrap(),
+            }));
+        let module = super::NamesModule::new(Arc::new(Mutex::new(mock)));
+
+        let register_returns: RegisterReturns = minicbor::decode(
+            &call_module_cbor(
+                1,
+                &module,
+                "names.register",
+                minicbor::to_vec(data).unwrap(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(register_returns.expiration, Timestamp::new(1000).unwrap());
+    }
+
+    #[test]
+    fn names() {
+        for name in ["abc", "alice", "alice-1", "0xdeadbeef"] {
+            assert!(validate_name(name).is_ok(), "{name}");
+        }
+
+        let long = "a".repeat(NAME_MAX_LENGTH + 1);
+        for name in [
+            "", "ab", "Alice", "alice_1", "-alice", "alice-", "élise", &long,
+        ] {
+            let err = validate_name(name).unwrap_err();
+            assert_eq!(err.code(), invalid_name("").code(), "{name}");
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use crate::{EmptyArg, EmptyReturn};
+use many_error::{define_attribute_many_error, ManyError};
+use many_identity::Address;
+use many_macros::many_module;
+use many_protocol::context::Context;
+use many_types::cbor::CborAny;
+use many_types::{cbor_type_decl, Timestamp};
+use minicbor::{Decode, Encode};
+use std::collections::BTreeSet;
+
+#[cfg(test)]
+use mockall::{automock, predicate::*};
+
+define_attribute_many_error!(
+    attribute 20 => {
+        1: pub fn not_an_attestor(id) => "Address {id} is not a trusted attestor.",
+        2: pub fn claim_not_found(kind) => "Claim not found: {kind}.",
+        3: pub fn invalid_claim_kind(kind) => "Invalid claim kind: {kind}.",
+    }
+);
+
+pub const CLAIM_KIND_MAX_LENGTH: usize = 64;
+
+/// Check that a claim kind can be stored. Kinds are part of the storage key
+/// of claims so they cannot contain separators.
+pub fn validate_kind(kind: &str) -> Result<(), ManyError> {
+    let valid = !kind.is_empty()
+        && kind.len() <= CLAIM_KIND_MAX_LENGTH
+        && kind
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_' || b == b'.');
+    if valid {
+        Ok(())
+    } else {
+        Err(invalid_claim_kind(kind.to_string()))
+    }
+}
+
+/// A claim made by an attestor about a subject, e.g. a KYC level or the
+/// membership to an organization.
+#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct Claim {
+    #[n(0)]
+    pub attestor: Address,
+
+    #[n(1)]
+    pub subject: Address,
+
+    #[n(2)]
+    pub kind: String,
+
+    #[n(3)]
+    pub value: CborAny,
+
+    #[n(4)]
+    pub issued: Timestamp,
+
+    #[n(5)]
+    pub expiration: Option<Timestamp>,
+}
+
+pub type InfoArgs = EmptyArg;
+
+cbor_type_decl!(
+    pub struct InfoReturns {
+        0 => attestors: BTreeSet<Address>,
+    }
+
+    pub struct GetArgs {
+        0 => subject: Address,
+        // Only return the claims of this attestor.
+        1 => attestor: Option<Address>,
+    }
+
+    pub struct GetReturns {
+        0 => claims: Vec<Claim>,
+    }
+
+    pub struct PublishArgs {
+        0 => subject: Address,
+        1 => kind: String,
+        2 => value: CborAny,
+        3 => expiration: Option<Timestamp>,
+    }
+
+    pub struct RevokeArgs {
+        0 => subject: Address,
+        1 => kind: String,
+    }
+);
+
+pub type PublishReturns = EmptyReturn;
+pub type RevokeReturns = EmptyReturn;
+
+#[many_module(name = AttestModule, id = 20, namespace = attest, many_modules_crate = crate)]
+#[cfg_attr(test, automock)]
+pub trait AttestModuleBackend: Send {
+    fn info(&self, sender: &Address, args: InfoArgs) -> Result<InfoReturns, ManyError>;
+
+    /// The current claims about an address. Revoked and expired claims are
+    /// not returned.
+    fn get(
+        &self,
+        sender: &Address,
+        args: GetArgs,
+        context: Context,
+    ) -> Result<GetReturns, ManyError>;
+
+    /// Publish a claim as the sender, replacing any claim of the same kind
+    /// the sender made about the subject.
+    #[many(deny_anonymous)]
+    fn publish(&mut self, sender: &Address, args: PublishArgs)
+        -> Result<PublishReturns, ManyError>;
+
+    #[many(deny_anonymous)]
+    fn revoke(&mut self, sender: &Address, args: RevokeArgs) -> Result<RevokeReturns, ManyError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils::call_module_cbor;
+    use many_identity::testing::identity;
+    use mockall::predicate;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn get() {
+        let data = GetArgs {
+            subject: identity(2),
+            attestor: None,
+        };
+        let claim = Claim {
+            attestor: identity(1),
+            subject: identity(2),
+            kind: "kyc".to_string(),
+            value: CborAny::Int(2),
+            issued: Timestamp::new(1000).unwrap(),
+            expiration: None,
+        };
+        let mut mock = MockAttestModuleBackend::new();
+        mock.expect_get()
+            .with(
+                predicate::eq(identity(1)),
+                predicate::eq(data.clone()),
+                predicate::always(),
+            )
+            .times(1)
+            .return_const(Ok(GetReturns {
+                claims: vec![claim.clone()],
+            }));
+        let module = super::AttestModule::new(Arc::new(Mutex::new(mock)));
+
+        let get_returns: GetReturns = minicbor::decode(
+            &call_module_cbor(1, &module, "attest.get", minicbor::to_vec(data).unwrap()).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(get_returns.claims, vec![claim]);
+    }
+
+    #[test]
+    fn publish() {
+        let data = PublishArgs {
+            subject: identity(2),
+            kind: "member.of".to_string(),
+            value: CborAny::String("acme".to_string()),
+            expiration: None,
+        };
+        let mut mock = MockAttestModuleBackend::new();
+        mock.expect_publish()
+            .with(predicate::eq(identity(1)), predicate::eq(data.clone()))
+            .times(1)
+            .returning(|_, _| Ok(PublishReturns {}));
+        let module = super::AttestModule::new(Arc::new(Mutex::new(mock)));
+
+        let _: PublishReturns = minicbor::decode(
+            &call_module_cbor(
+                1,
+                &module,
+                "attest.publish",
+                minicbor::to_vec(data).unwrap(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn kinds() {
+        for kind in ["kyc", "kyc-level", "member.of", "KYC_2"] {
+            assert!(validate_kind(kind).is_ok(), "{kind}");
+        }
+
+        let long = "a".repeat(CLAIM_KIND_MAX_LENGTH + 1);
+        for kind in ["", "kyc/level", "a b", &long] {
+            let err = validate_kind(kind).unwrap_err();
+            assert_eq!(err.code(), invalid_claim_kind("").code(), "{kind}");
+        }
+    }
+}
@@ -0,0 +1,402 @@
+//! Relay of pre-signed requests (meta-transactions).
+//!
+//! A user signs a request offline and hands its envelope to a relayer, who
+//! submits it wrapped in a `relay.execute` request of its own. The inner
+//! request is executed as its signer, while the relay fee is charged to the
+//! relayer. The user never needs to hold funds or talk to the network.
+use crate::base::EndpointDescriptor;
+use crate::{EmptyArg, ManyModule, ManyModuleInfo};
+use coset::{CborSerializable, CoseSign, CoseSign1};
+use many_error::{define_attribute_many_error, ManyError};
+use many_identity::Address;
+use many_protocol::{RequestMessage, ResponseMessage};
+use many_types::attributes::Attribute;
+use many_types::cbor::DecodeLimits;
+use many_types::cbor_type_decl;
+use many_types::ledger::{Symbol, TokenAmount};
+use many_types::BlockTime;
+use minicbor::bytes::ByteVec;
+use minicbor::{Decode, Encode};
+use std::collections::BTreeSet;
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex};
+
+define_attribute_many_error!(
+    attribute 21 => {
+        1: pub fn invalid_relayed_request(reason) => "Invalid relayed request: {reason}.",
+        2: pub fn relayed_request_already_executed() => "This relayed request was already executed.",
+        3: pub fn method_cannot_be_relayed(method) => "Method {method} cannot be relayed.",
+    }
+);
+
+pub const RELAY_MODULE_ATTRIBUTE: Attribute = Attribute::id(21);
+
+/// The fee paid by the relayer for every relayed request.
+#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct RelayFee {
+    #[n(0)]
+    pub symbol: Symbol,
+
+    #[n(1)]
+    pub amount: TokenAmount,
+
+    #[n(2)]
+    pub collector: Address,
+}
+
+pub type InfoArgs = EmptyArg;
+
+cbor_type_decl!(
+    pub struct InfoReturns {
+        0 => fee: Option<RelayFee>,
+        // How far the timestamp of a relayed request can be from the current
+        // time.
+        1 => timeout_in_secs: u64,
+    }
+
+    pub struct ExecuteArgs {
+        // The encoded COSE_Sign1 envelope of the request, as signed by its
+        // sender.
+        0 => envelope: ByteVec,
+    }
+);
+
+/// Validates relayed requests and finds the module executing them. This is
+/// implemented by the server, so relayed requests are validated as if they
+/// were sent to it directly.
+pub trait RelayRouter: Send {
+    /// Verify and validate the envelope of a relayed request, and return the
+    /// request with the module that will execute it.
+    fn route(
+        &self,
+        envelope: &CoseSign1,
+    ) -> Result<(RequestMessage, Arc<dyn ManyModule + Send>), ManyError>;
+
+    /// Called once a relayed request was executed by its module, with its
+    /// result. Returns the response of the request.
+    fn executed(
+        &mut self,
+        envelope: &CoseSign1,
+        request: &RequestMessage,
+        module: &dyn ManyModule,
+        result: Result<ResponseMessage, ManyError>,
+    ) -> Result<ResponseMessage, ManyError>;
+}
+
+pub trait RelayModuleBackend: Send {
+    fn info(&self, sender: &Address, args: InfoArgs) -> Result<InfoReturns, ManyError>;
+
+    /// Check that a request can be relayed and charge its fee to the relayer.
+    /// This is called before the request is executed, and the fee is not
+    /// refunded if the request fails.
+    fn relay(&mut self, relayer: &Address, request: &RequestMessage) -> Result<(), ManyError>;
+}
+
+pub struct RelayModule<T: RelayModuleBackend, R: RelayRouter> {
+    backend: Arc<Mutex<T>>,
+    router: Arc<Mutex<R>>,
+    info: ManyModuleInfo,
+}
+
+impl<T: RelayModuleBackend, R: RelayRouter> RelayModule<T, R> {
+    /// Create a relay module. The router verifies the envelopes of relayed
+    /// requests, which cannot be anonymous.
+    pub fn new(backend: Arc<Mutex<T>>, router: Arc<Mutex<R>>) -> Self {
+        let info = ManyModuleInfo {
+            name: "RelayModule".to_string(),
+            attribute: Some(RELAY_MODULE_ATTRIBUTE),
+            endpoints: vec!["relay.info".to_string(), "relay.execute".to_string()],
+            descriptors: vec![
+                EndpointDescriptor {
+                    name: "relay.info".to_string(),
+                    argument: Some("InfoArgs".to_string()),
+                    returns: "InfoReturns".to_string(),
+                    deprecated: None,
+                    redacted: None,
+                },
+                EndpointDescriptor {
+                    name: "relay.execute".to_string(),
+                    argument: Some("ExecuteArgs".to_string()),
+                    returns: "ResponseMessage".to_string(),
+                    deprecated: None,
+                    redacted: None,
+                },
+            ],
+        };
+
+        Self {
+            backend,
+            router,
+            info,
+        }
+    }
+
+    /// Verify the envelope of a relayed request and return the request with
+    /// the module that will execute it.
+    fn route(
+        &self,
+        envelope: &CoseSign1,
+    ) -> Result<(RequestMessage, Arc<dyn ManyModule + Send>), ManyError> {
+        let (request, module) = self.router.lock().unwrap().route(envelope)?;
+        if request.from().is_anonymous() {
+            return Err(invalid_relayed_request("sender is anonymous"));
+        }
+        if self.info.endpoints.contains(&request.method) {
+            return Err(method_cannot_be_relayed(request.method));
+        }
+        Ok((request, module))
+    }
+
+    fn validate_message(&self, message: &RequestMessage) -> Result<(), ManyError> {
+        match message.method.as_str() {
+            "relay.info" => {}
+            "relay.execute" => {
+                if message.from().is_anonymous() {
+                    return Err(ManyError::invalid_identity());
+                }
+                DecodeLimits::default()
+                    .decode::<ExecuteArgs>(&message.data)
+                    .map_err(|e| ManyError::deserialization_error(e.to_string()))?;
+            }
+            method => return Err(ManyError::invalid_method_name(method.to_string())),
+        }
+        Ok(())
+    }
+}
+
+impl<T: RelayModuleBackend, R: RelayRouter> Debug for RelayModule<T, R> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("RelayModule")
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: RelayModuleBackend, R: RelayRouter> ManyModule for RelayModule<T, R> {
+    fn info(&self) -> &ManyModuleInfo {
+        &self.info
+    }
+
+    fn validate(&self, message: &RequestMessage, _envelope: &CoseSign1) -> Result<(), ManyError> {
+        self.validate_message(message)
+    }
+
+    // Relayers can sign with several keys, the relayed request is verified
+    // on its own.
+    fn validate_multi(
+        &self,
+        message: &RequestMessage,
+        _envelope: &CoseSign,
+        _signers: &BTreeSet<Address>,
+    ) -> Result<(), ManyError> {
+        self.validate_message(message)
+    }
+
+    async fn execute(&self, message: RequestMessage) -> Result<ResponseMessage, ManyError> {
+        self.execute_with_block_time(message, None).await
+    }
+
+    async fn execute_with_block_time(
+        &self,
+        message: RequestMessage,
+        block_time: Option<BlockTime>,
+    ) -> Result<ResponseMessage, ManyError> {
+        let from = message.from();
+        match message.method.as_str() {
+            "relay.info" => {
+                let args: InfoArgs = DecodeLimits::default()
+                    .decode(&message.data)
+                    .map_err(|e| ManyError::deserialization_error(e.to_string()))?;
+                let result = self.backend.lock().unwrap().info(&from, args)?;
+                let data = minicbor::to_vec(result)
+                    .map_err(|e| ManyError::serialization_error(e.to_string()))?;
+                Ok(ResponseMessage::from_request(
+                    &message,
+                    &message.to,
+                    Ok(data),
+                ))
+            }
+            "relay.execute" => {
+                let args: ExecuteArgs = DecodeLimits::default()
+                    .decode(&message.data)
+                    .map_err(|e| ManyError::deserialization_error(e.to_string()))?;
+                let envelope = CoseSign1::from_slice(&args.envelope)
+                    .map_err(|e| invalid_relayed_request(e.to_string()))?;
+                let (request, module) = self.route(&envelope)?;
+
+                // The backend lock must be released before executing, as the
+                // module of the relayed request likely shares the backend.
+                self.backend.lock().unwrap().relay(&from, &request)?;
+                let result = module
+                    .execute_with_block_time(request.clone(), block_time)
+                    .await;
+                self.router
+                    .lock()
+                    .unwrap()
+                    .executed(&envelope, &request, module.as_ref(), result)
+            }
+            _ => Err(ManyError::internal_server_error()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils::call_module_cbor;
+    use coset::CoseSign1Builder;
+    use many_identity::testing::identity;
+    use many_identity::Identity;
+    use many_identity_dsa::ed25519::generate_random_ed25519_identity;
+    use many_identity_dsa::CoseKeyVerifier;
+    use many_types::Timestamp;
+
+    /// A module echoing the sender of its requests.
+    #[derive(Debug)]
+    struct EchoModule(ManyModuleInfo);
+
+    #[async_trait::async_trait]
+    impl ManyModule for EchoModule {
+        fn info(&self) -> &ManyModuleInfo {
+            &self.0
+        }
+
+        async fn execute(&self, message: RequestMessage) -> Result<ResponseMessage, ManyError> {
+            let data = minicbor::to_vec(message.from()).unwrap();
+            Ok(ResponseMessage::from_request(
+                &message,
+                &message.to,
+                Ok(data),
+            ))
+        }
+    }
+
+    struct Router;
+
+    impl RelayRouter for Router {
+        fn route(
+            &self,
+            envelope: &CoseSign1,
+        ) -> Result<(RequestMessage, Arc<dyn ManyModule + Send>), ManyError> {
+            let request =
+                many_protocol::decode_request_from_cose_sign1(envelope, &CoseKeyVerifier)?;
+            let module: Arc<dyn ManyModule + Send> = match request.method.as_str() {
+                "echo" | "relay.execute" => Arc::new(EchoModule(ManyModuleInfo {
+                    name: "EchoModule".to_string(),
+                    attribute: None,
+                    endpoints: vec!["echo".to_string()],
+                    descriptors: vec![],
+                })),
+                _ => return Err(ManyError::could_not_route_message()),
+            };
+            Ok((request, module))
+        }
+
+        fn executed(
+            &mut self,
+            _envelope: &CoseSign1,
+            _request: &RequestMessage,
+            _module: &dyn ManyModule,
+            result: Result<ResponseMessage, ManyError>,
+        ) -> Result<ResponseMessage, ManyError> {
+            result
+        }
+    }
+
+    #[derive(Default)]
+    struct Backend {
+        relayed: Vec<(Address, Address)>,
+    }
+
+    impl RelayModuleBackend for Backend {
+        fn info(&self, _sender: &Address, _args: InfoArgs) -> Result<InfoReturns, ManyError> {
+            Ok(InfoReturns {
+                fee: None,
+                timeout_in_secs: 300,
+            })
+        }
+
+        fn relay(&mut self, relayer: &Address, request: &RequestMessage) -> Result<(), ManyError> {
+            self.relayed.push((*relayer, request.from()));
+            Ok(())
+        }
+    }
+
+    fn envelope(identity: &impl Identity, method: &str) -> ByteVec {
+        let request = RequestMessage::default()
+            .with_method(method.to_string())
+            .with_from(identity.address());
+        let request = RequestMessage {
+            timestamp: Some(Timestamp::now()),
+            ..request
+        };
+        let envelope = CoseSign1Builder::new()
+            .payload(request.to_bytes().unwrap())
+            .build();
+        identity.sign_1(envelope).unwrap().to_vec().unwrap().into()
+    }
+
+    fn execute(backend: &Arc<Mutex<Backend>>, envelope: ByteVec) -> Result<Vec<u8>, ManyError> {
+        let module = RelayModule::new(backend.clone(), Arc::new(Mutex::new(Router)));
+        call_module_cbor(
+            1,
+            &module,
+            "relay.execute",
+            minicbor::to_vec(ExecuteArgs { envelope }).unwrap(),
+        )
+    }
+
+    #[test]
+    fn execute_as_sender() {
+        let backend = Arc::new(Mutex::new(Backend::default()));
+        let id = generate_random_ed25519_identity();
+
+        let data = execute(&backend, envelope(&id, "echo")).unwrap();
+        let sender: Address = minicbor::decode(&data).unwrap();
+        assert_eq!(sender, id.address());
+        assert_eq!(
+            backend.lock().unwrap().relayed,
+            vec![(identity(1), id.address())]
+        );
+    }
+
+    #[test]
+    fn invalid_signature() {
+        let backend = Arc::new(Mutex::new(Backend::default()));
+        let id = generate_random_ed25519_identity();
+        let mut envelope = CoseSign1::from_slice(&envelope(&id, "echo")).unwrap();
+        envelope.signature[0] ^= 1;
+
+        assert!(execute(&backend, envelope.to_vec().unwrap().into()).is_err());
+        assert!(backend.lock().unwrap().relayed.is_empty());
+    }
+
+    #[test]
+    fn relay_cannot_be_relayed() {
+        let backend = Arc::new(Mutex::new(Backend::default()));
+        let id = generate_random_ed25519_identity();
+
+        let err = execute(&backend, envelope(&id, "relay.execute")).unwrap_err();
+        assert_eq!(err.code(), method_cannot_be_relayed("").code());
+        assert!(backend.lock().unwrap().relayed.is_empty());
+    }
+
+    #[test]
+    fn anonymous_relayer() {
+        let backend = Arc::new(Mutex::new(Backend::default()));
+        let module = RelayModule::new(backend, Arc::new(Mutex::new(Router)));
+        let id = generate_random_ed25519_identity();
+
+        let err = call_module_cbor(
+            0,
+            &module,
+            "relay.execute",
+            minicbor::to_vec(ExecuteArgs {
+                envelope: envelope(&id, "echo"),
+            })
+            .unwrap(),
+        )
+        .unwrap_err();
+        assert_eq!(err.code(), ManyError::invalid_identity().code());
+    }
+}
@@ -0,0 +1,247 @@
+//! Administration of a running server.
+//!
+//! These endpoints let the operator of a server change its log level, read its
+//! configuration, inspect or compact its storage, enable or disable its
+//! optional modules or shut it down without restarting the process. Only the administrator address configured by the server can call
+//! them.
+use crate::{EmptyArg, EmptyReturn};
+use many_error::{define_attribute_many_error, ManyError};
+use many_identity::Address;
+use many_macros::many_module;
+use many_types::cbor_type_decl;
+use minicbor::{Decode, Encode};
+
+#[cfg(test)]
+use mockall::{automock, predicate::*};
+
+define_attribute_many_error!(
+    attribute 22 => {
+        1: pub fn not_an_admin(id) => "Address {id} is not the administrator of this server.",
+        2: pub fn invalid_log_level(level) => "Invalid log level: {level}.",
+        3: pub fn unsupported_operation(op) => "This server does not support {op}.",
+        4: pub fn unknown_module(attribute) => "No module with attribute {attribute} is registered on this server.",
+        5: pub fn module_conflict(name) => "Module {name} conflicts with a module already running on this server.",
+    }
+);
+
+/// The log levels accepted by `admin.setLogLevel`, from the least to the most
+/// verbose.
+pub const LOG_LEVELS: [&str; 6] = ["off", "error", "warn", "info", "debug", "trace"];
+
+pub type ConfigArgs = EmptyArg;
+
+cbor_type_decl!(
+    pub struct ConfigReturns {
+        // The effective configuration of the server, in a human readable form.
+        0 => config: String,
+    }
+
+    pub struct SetLogLevelArgs {
+        // One of `LOG_LEVELS`.
+        0 => level: String,
+    }
+
+    pub struct SetLogLevelReturns {
+        0 => previous: String,
+    }
+
+    pub struct StorageStats {
+        // The size of all the files of the persistent storage, in bytes.
+        0 => size_on_disk: u64,
+        // An estimate of the number of nodes in the tree.
+        1 => node_count: u64,
+        // An estimate of the bytes used by stale versions of nodes, which a
+        // compaction would reclaim.
+        2 => stale_size: u64,
+    }
+
+    pub struct ModuleStatus {
+        0 => attribute: u32,
+        1 => name: String,
+        // Whether the module is currently serving its endpoints.
+        2 => enabled: bool,
+    }
+
+    pub struct ModulesReturns {
+        0 => modules: Vec<ModuleStatus>,
+    }
+
+    pub struct EnableModuleArgs {
+        0 => attribute: u32,
+    }
+
+    pub struct DisableModuleArgs {
+        0 => attribute: u32,
+    }
+);
+
+pub type StorageStatsArgs = EmptyArg;
+pub type StorageStatsReturns = StorageStats;
+
+pub type CompactArgs = EmptyArg;
+pub type CompactReturns = EmptyReturn;
+
+impl StorageStats {
+    /// Print the stats to the standard output, under a title.
+    pub fn print(&self, title: &str) {
+        println!("{title}:");
+        println!("  Size on disk: {} bytes", self.size_on_disk);
+        println!("  Nodes: {} (estimated)", self.node_count);
+        println!("  Stale versions: {} bytes (estimated)", self.stale_size);
+    }
+}
+
+/// Compact a persistent store offline, printing its stats before and after.
+/// Servers provide how to read the stats of their store and how to compact it.
+pub fn compact_offline(
+    stats: impl FnOnce() -> Result<StorageStats, ManyError>,
+    compact: impl FnOnce() -> Result<StorageStats, ManyError>,
+) -> Result<(), ManyError> {
+    stats()?.print("Before");
+    compact()?.print("After");
+    Ok(())
+}
+
+pub type ShutdownArgs = EmptyArg;
+pub type ShutdownReturns = EmptyReturn;
+
+pub type ModulesArgs = EmptyArg;
+pub type EnableModuleReturns = EmptyReturn;
+pub type DisableModuleReturns = EmptyReturn;
+
+#[many_module(name = AdminModule, id = 22, namespace = admin, many_modules_crate = crate)]
+#[cfg_attr(test, automock)]
+pub trait AdminModuleBackend: Send {
+    #[many(deny_anonymous)]
+    fn config(&self, sender: &Address, args: ConfigArgs) -> Result<ConfigReturns, ManyError>;
+
+    #[many(deny_anonymous)]
+    fn set_log_level(
+        &mut self,
+        sender: &Address,
+        args: SetLogLevelArgs,
+    ) -> Result<SetLogLevelReturns, ManyError>;
+
+    #[many(deny_anonymous)]
+    fn storage_stats(
+        &self,
+        sender: &Address,
+        args: StorageStatsArgs,
+    ) -> Result<StorageStatsReturns, ManyError>;
+
+    /// Compact the persistent storage of the server to reclaim disk space.
+    /// Servers which cannot compact their storage while running stop, and
+    /// compact it when they start again.
+    #[many(deny_anonymous)]
+    fn compact(&mut self, sender: &Address, args: CompactArgs)
+        -> Result<CompactReturns, ManyError>;
+
+    /// Stop the server once the response to this request is sent.
+    #[many(deny_anonymous)]
+    fn shutdown(
+        &mut self,
+        sender: &Address,
+        args: ShutdownArgs,
+    ) -> Result<ShutdownReturns, ManyError>;
+
+    /// List the optional modules registered on the server.
+    #[many(deny_anonymous)]
+    fn modules(&self, sender: &Address, args: ModulesArgs) -> Result<ModulesReturns, ManyError>;
+
+    /// Start serving the endpoints of a registered module.
+    #[many(deny_anonymous)]
+    fn enable_module(
+        &mut self,
+        sender: &Address,
+        args: EnableModuleArgs,
+    ) -> Result<EnableModuleReturns, ManyError>;
+
+    /// Stop serving the endpoints of a registered module.
+    #[many(deny_anonymous)]
+    fn disable_module(
+        &mut self,
+        sender: &Address,
+        args: DisableModuleArgs,
+    ) -> Result<DisableModuleReturns, ManyError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils::call_module_cbor;
+    use many_identity::testing::identity;
+    use mockall::predicate;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn set_log_level() {
+        let data = SetLogLevelArgs {
+            level: "debug".to_string(),
+        };
+        let mut mock = MockAdminModuleBackend::new();
+        mock.expect_set_log_level()
+            .with(predicate::eq(identity(1)), predicate::eq(data.clone()))
+            .times(1)
+            .return_const(Ok(SetLogLevelReturns {
+                previous: "info".to_string(),
+            }));
+        let module = super::AdminModule::new(Arc::new(Mutex::new(mock)));
+
+        let returns: SetLogLevelReturns = minicbor::decode(
+            &call_module_cbor(
+                1,
+                &module,
+                "admin.setLogLevel",
+                minicbor::to_vec(data).unwrap(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(returns.previous, "info");
+    }
+
+    #[test]
+    fn modules() {
+        let mut mock = MockAdminModuleBackend::new();
+        mock.expect_modules()
+            .with(predicate::eq(identity(1)), predicate::eq(EmptyArg))
+            .times(1)
+            .return_const(Ok(ModulesReturns {
+                modules: vec![ModuleStatus {
+                    attribute: 3,
+                    name: "KvStoreModule".to_string(),
+                    enabled: false,
+                }],
+            }));
+        let module = super::AdminModule::new(Arc::new(Mutex::new(mock)));
+
+        let returns: ModulesReturns = minicbor::decode(
+            &call_module_cbor(
+                1,
+                &module,
+                "admin.modules",
+                minicbor::to_vec(EmptyArg).unwrap(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(returns.modules.len(), 1);
+        assert_eq!(returns.modules[0].attribute, 3);
+        assert!(!returns.modules[0].enabled);
+    }
+
+    #[test]
+    fn shutdown_anonymous() {
+        let mock = MockAdminModuleBackend::new();
+        let module = super::AdminModule::new(Arc::new(Mutex::new(mock)));
+
+        let err = call_module_cbor(
+            0,
+            &module,
+            "admin.shutdown",
+            minicbor::to_vec(EmptyArg).unwrap(),
+        )
+        .unwrap_err();
+        assert_eq!(err.code(), ManyError::sender_cannot_be_anonymous().code());
+    }
+}
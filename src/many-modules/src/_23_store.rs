@@ -0,0 +1,182 @@
+use crate::{EmptyArg, EmptyReturn};
+use many_error::{define_attribute_many_error, ManyError};
+use many_identity::Address;
+use many_macros::many_module;
+use many_protocol::context::Context;
+use many_types::{cbor_type_decl, Timestamp};
+use minicbor::bytes::ByteVec;
+use minicbor::{Decode, Encode};
+use sha3::{Digest, Sha3_256};
+use std::collections::BTreeSet;
+
+#[cfg(test)]
+use mockall::{automock, predicate::*};
+
+define_attribute_many_error!(
+    attribute 23 => {
+        1: pub fn object_too_large(size, max) => "Object of {size} bytes is larger than the maximum of {max} bytes.",
+        2: pub fn object_not_found(hash) => "Object not found: {hash}.",
+        3: pub fn invalid_hash(hash) => "Invalid object hash: {hash}.",
+        4: pub fn not_pinned(hash) => "The sender did not pin the object: {hash}.",
+    }
+);
+
+/// The size of object hashes, in bytes.
+pub const HASH_LENGTH: usize = 32;
+
+/// The hash identifying an object, computed from its content only. Putting
+/// the same content twice returns the same hash.
+pub fn object_hash(data: &[u8]) -> [u8; HASH_LENGTH] {
+    Sha3_256::digest(data).into()
+}
+
+/// Check that a hash given by a client has the length of object hashes.
+pub fn validate_hash(hash: &[u8]) -> Result<(), ManyError> {
+    if hash.len() == HASH_LENGTH {
+        Ok(())
+    } else {
+        Err(invalid_hash(hex::encode(hash)))
+    }
+}
+
+pub type InfoArgs = EmptyArg;
+
+cbor_type_decl!(
+    pub struct InfoReturns {
+        // Objects larger than this many bytes are rejected.
+        0 => max_size: u64,
+    }
+
+    // An object is kept as long as at least one address pins it.
+    pub struct ObjectInfo {
+        0 => hash: ByteVec,
+        1 => size: u64,
+        2 => created: Timestamp,
+        3 => pins: BTreeSet<Address>,
+    }
+
+    pub struct GetArgs {
+        0 => hash: ByteVec,
+    }
+
+    pub struct GetReturns {
+        0 => data: ByteVec,
+        1 => info: ObjectInfo,
+    }
+
+    pub struct PutArgs {
+        0 => data: ByteVec,
+    }
+
+    pub struct PutReturns {
+        0 => hash: ByteVec,
+    }
+
+    pub struct PinArgs {
+        0 => hash: ByteVec,
+    }
+
+    pub struct UnpinArgs {
+        0 => hash: ByteVec,
+    }
+);
+
+pub type PinReturns = EmptyReturn;
+pub type UnpinReturns = EmptyReturn;
+
+#[many_module(name = StoreModule, id = 23, namespace = store, many_modules_crate = crate)]
+#[cfg_attr(test, automock)]
+pub trait StoreModuleBackend: Send {
+    fn info(&self, sender: &Address, args: InfoArgs) -> Result<InfoReturns, ManyError>;
+
+    /// The content of an object and its information.
+    fn get(
+        &self,
+        sender: &Address,
+        args: GetArgs,
+        context: Context,
+    ) -> Result<GetReturns, ManyError>;
+
+    /// Store an object and pin it for the sender. Putting an existing object
+    /// only adds the pin.
+    #[many(deny_anonymous)]
+    fn put(&mut self, sender: &Address, args: PutArgs) -> Result<PutReturns, ManyError>;
+
+    /// Pin an existing object for the sender, so it's kept when other
+    /// addresses unpin it.
+    #[many(deny_anonymous)]
+    fn pin(&mut self, sender: &Address, args: PinArgs) -> Result<PinReturns, ManyError>;
+
+    /// Remove the pin of the sender. Objects without pins are removed.
+    #[many(deny_anonymous)]
+    fn unpin(&mut self, sender: &Address, args: UnpinArgs) -> Result<UnpinReturns, ManyError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils::call_module_cbor;
+    use many_identity::testing::identity;
+    use mockall::predicate;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn put() {
+        let data = PutArgs {
+            data: b"hello".to_vec().into(),
+        };
+        let hash: ByteVec = object_hash(b"hello").to_vec().into();
+        let mut mock = MockStoreModuleBackend::new();
+        mock.expect_put()
+            .with(predicate::eq(identity(1)), predicate::eq(data.clone()))
+            .times(1)
+            .return_const(Ok(PutReturns { hash: hash.clone() }));
+        let module = super::StoreModule::new(Arc::new(Mutex::new(mock)));
+
+        let put_returns: PutReturns = minicbor::decode(
+            &call_module_cbor(1, &module, "store.put", minicbor::to_vec(data).unwrap()).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(put_returns.hash, hash);
+    }
+
+    #[test]
+    fn get() {
+        let hash: ByteVec = object_hash(b"hello").to_vec().into();
+        let data = GetArgs { hash: hash.clone() };
+        let returns = GetReturns {
+            data: b"hello".to_vec().into(),
+            info: ObjectInfo {
+                hash,
+                size: 5,
+                created: Timestamp::new(1000).unwrap(),
+                pins: BTreeSet::from([identity(1)]),
+            },
+        };
+        let mut mock = MockStoreModuleBackend::new();
+        mock.expect_get()
+            .with(
+                predicate::eq(identity(1)),
+                predicate::eq(data.clone()),
+                predicate::always(),
+            )
+            .times(1)
+            .return_const(Ok(returns.clone()));
+        let module = super::StoreModule::new(Arc::new(Mutex::new(mock)));
+
+        let get_returns: GetReturns = minicbor::decode(
+            &call_module_cbor(1, &module, "store.get", minicbor::to_vec(data).unwrap()).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(get_returns, returns);
+    }
+
+    #[test]
+    fn hashes() {
+        assert_eq!(object_hash(b"a"), object_hash(b"a"));
+        assert_ne!(object_hash(b"a"), object_hash(b"b"));
+        assert!(validate_hash(&object_hash(b"a")).is_ok());
+        let err = validate_hash(&[1, 2, 3]).unwrap_err();
+        assert_eq!(err.code(), invalid_hash("").code());
+    }
+}
@@ -0,0 +1,134 @@
+//! A list of addresses restricting the transfers of a ledger, for regulated
+//! token deployments.
+//!
+//! In deny mode, sends from or to a listed address are rejected. In allow
+//! mode, both parties of a send must be listed. Only the compliance
+//! administrator can change the list.
+use crate::{EmptyArg, EmptyReturn};
+use many_error::{define_attribute_many_error, ManyError};
+use many_identity::Address;
+use many_macros::many_module;
+use many_types::cbor_type_decl;
+use minicbor::{Decode, Encode};
+use std::collections::BTreeSet;
+
+#[cfg(test)]
+use mockall::{automock, predicate::*};
+
+define_attribute_many_error!(
+    attribute 24 => {
+        1: pub fn address_blocked(address) => "Address {address} is not allowed to transfer tokens by the compliance list.",
+        2: pub fn not_compliance_admin(id) => "Address {id} is not the compliance administrator.",
+        3: pub fn already_listed(address) => "Address {address} is already in the compliance list.",
+        4: pub fn not_listed(address) => "Address {address} is not in the compliance list.",
+    }
+);
+
+/// How the compliance list applies to sends.
+#[derive(Clone, Copy, Debug, Default, Decode, Encode, Eq, PartialEq)]
+#[cbor(index_only)]
+pub enum ListMode {
+    /// Listed addresses cannot send or receive tokens.
+    #[default]
+    #[n(0)]
+    Deny,
+
+    /// Only listed addresses can send or receive tokens.
+    #[n(1)]
+    Allow,
+}
+
+impl ListMode {
+    /// Whether an address can send or receive tokens, given whether it is in
+    /// the list.
+    pub fn permits(&self, listed: bool) -> bool {
+        match self {
+            ListMode::Deny => !listed,
+            ListMode::Allow => listed,
+        }
+    }
+}
+
+pub type InfoArgs = EmptyArg;
+pub type ListArgs = EmptyArg;
+
+cbor_type_decl!(
+    pub struct InfoReturns {
+        0 => admin: Address,
+        1 => mode: ListMode,
+    }
+
+    pub struct ListReturns {
+        0 => addresses: BTreeSet<Address>,
+    }
+
+    pub struct AddArgs {
+        0 => address: Address,
+    }
+
+    pub struct RemoveArgs {
+        0 => address: Address,
+    }
+);
+
+pub type AddReturns = EmptyReturn;
+pub type RemoveReturns = EmptyReturn;
+
+#[many_module(name = ComplianceModule, id = 24, namespace = compliance, many_modules_crate = crate)]
+#[cfg_attr(test, automock)]
+pub trait ComplianceModuleBackend: Send {
+    fn info(&self, sender: &Address, args: InfoArgs) -> Result<InfoReturns, ManyError>;
+
+    fn list(&self, sender: &Address, args: ListArgs) -> Result<ListReturns, ManyError>;
+
+    /// Add an address to the list. Only the compliance administrator can call
+    /// this.
+    #[many(deny_anonymous)]
+    fn add(&mut self, sender: &Address, args: AddArgs) -> Result<AddReturns, ManyError>;
+
+    /// Remove an address from the list. Only the compliance administrator can
+    /// call this.
+    #[many(deny_anonymous)]
+    fn remove(&mut self, sender: &Address, args: RemoveArgs) -> Result<RemoveReturns, ManyError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutils::call_module_cbor;
+    use many_identity::testing::identity;
+    use mockall::predicate;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn add() {
+        let data = AddArgs {
+            address: identity(2),
+        };
+        let mut mock = MockComplianceModuleBackend::new();
+        mock.expect_add()
+            .with(predicate::eq(identity(1)), predicate::eq(data.clone()))
+            .times(1)
+            .returning(|_, _| Ok(AddReturns {}));
+        let module = super::ComplianceModule::new(Arc::new(Mutex::new(mock)));
+
+        let _: AddReturns = minicbor::decode(
+            &call_module_cbor(
+                1,
+                &module,
+                "compliance.add",
+                minicbor::to_vec(data).unwrap(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn modes() {
+        assert!(ListMode::Deny.permits(false));
+        assert!(!ListMode::Deny.permits(true));
+        assert!(ListMode::Allow.permits(true));
+        assert!(!ListMode::Allow.permits(false));
+    }
+}
@@ -7,10 +7,12 @@ use mockall::{automock, predicate::*};
 
 mod balance;
 mod info;
+mod prove;
 
 pub use balance::*;
 pub use info::*;
 use many_identity::Address;
+pub use prove::*;
 
 define_attribute_many_error!(
     attribute 2 => {
@@ -23,7 +25,7 @@ define_attribute_many_error!(
     }
 );
 
-#[many_module(name = LedgerModule, id = 2, namespace = ledger, many_modules_crate = crate)]
+#[many_module(name = LedgerModule, id = 2, namespace = ledger, many_modules_crate = crate, mock_client = true)]
 #[cfg_attr(test, automock)]
 pub trait LedgerModuleBackend: Send {
     fn info(
@@ -38,6 +40,15 @@ pub trait LedgerModuleBackend: Send {
         args: BalanceArgs,
         context: Context,
     ) -> Result<BalanceReturns, ManyError>;
+
+    /// Prove storage keys without returning their values, for light clients
+    /// verifying against a known hash.
+    fn prove(
+        &self,
+        sender: &Address,
+        args: ProveArgs,
+        context: Context,
+    ) -> Result<ProveReturns, ManyError>;
 }
 
 #[cfg(test)]
@@ -96,6 +107,7 @@ mod tests {
         let data = BalanceArgs {
             account: None,
             symbols: Some(VecOrSingle::from(vec![*SYMBOL])),
+            height: None,
         };
         let mut mock = MockLedgerModuleBackend::new();
         mock.expect_balance()
@@ -130,4 +142,31 @@ mod tests {
             BTreeMap::from([(*SYMBOL, TokenAmount::from(123u16))])
         );
     }
+    #[test]
+    fn prove() {
+        let data = ProveArgs {
+            keys: vec![ByteVec::from(b"/balances/a".to_vec())],
+        };
+        let proof = vec![many_types::ProofOperation::NodeHash(vec![1u8; 32])];
+        let mut mock = MockLedgerModuleBackend::new();
+        mock.expect_prove()
+            .with(
+                predicate::eq(identity(1)),
+                predicate::eq(data.clone()),
+                predicate::always(),
+            )
+            .times(1)
+            .return_const(Ok(ProveReturns {
+                proof: proof.clone().into(),
+                hash: ByteVec::from(vec![10u8; 8]),
+            }));
+        let module = super::LedgerModule::new(Arc::new(Mutex::new(mock)));
+
+        let prove_returns: ProveReturns = minicbor::decode(
+            &call_module_cbor(1, &module, "ledger.prove", minicbor::to_vec(data).unwrap()).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(prove_returns.proof.operations, proof);
+        assert_eq!(prove_returns.hash, ByteVec::from(vec![10u8; 8]));
+    }
 }
@@ -11,6 +11,13 @@ pub struct BalanceArgs {
 
     #[n(1)]
     pub symbols: Option<VecOrSingle<ledger::Symbol>>,
+
+    /// Return the balances after the block at this height instead of the
+    /// current ones. Historical balances come without a proof, only go back a
+    /// limited number of blocks and events, and do not reflect changes made
+    /// by migrations or hotfixes.
+    #[n(2)]
+    pub height: Option<u64>,
 }
 
 #[derive(Clone, Encode, Decode)]
@@ -0,0 +1,22 @@
+use many_types::proof::Proof;
+use minicbor::bytes::ByteVec;
+use minicbor::{Decode, Encode};
+
+#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct ProveArgs {
+    /// The storage keys to prove.
+    #[n(0)]
+    pub keys: Vec<ByteVec>,
+}
+
+#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct ProveReturns {
+    #[n(0)]
+    pub proof: Proof,
+
+    /// The root hash of the storage the proof was made against.
+    #[n(1)]
+    pub hash: ByteVec,
+}
@@ -3,6 +3,7 @@ use crate::kvstore::list::ListReturns;
 use many_error::ManyError;
 use many_identity::Address;
 use many_macros::many_module;
+use many_protocol::context::Context;
 use minicbor::{decode, encode};
 
 #[cfg(test)]
@@ -11,18 +12,36 @@ use mockall::{automock, predicate::*};
 pub mod get;
 pub mod info;
 pub mod list;
+pub mod prove;
 pub mod query;
+pub mod role;
 pub use get::*;
 pub use info::*;
+pub use prove::*;
 pub use query::*;
+pub use role::*;
 
-#[many_module(name = KvStoreModule, id = 3, namespace = kvstore, many_modules_crate = crate)]
+#[many_module(name = KvStoreModule, id = 3, namespace = kvstore, many_modules_crate = crate, mock_client = true)]
 #[cfg_attr(test, automock)]
 pub trait KvStoreModuleBackend: Send {
     fn info(&self, sender: &Address, args: InfoArg) -> Result<InfoReturns, ManyError>;
-    fn get(&self, sender: &Address, args: GetArgs) -> Result<GetReturns, ManyError>;
-    fn query(&self, sender: &Address, args: QueryArgs) -> Result<QueryReturns, ManyError>;
+    fn get(
+        &self,
+        sender: &Address,
+        args: GetArgs,
+        context: Context,
+    ) -> Result<GetReturns, ManyError>;
+    fn query(
+        &self,
+        sender: &Address,
+        args: QueryArgs,
+        context: Context,
+    ) -> Result<QueryReturns, ManyError>;
     fn list(&self, sender: &Address, args: ListArgs) -> Result<ListReturns, ManyError>;
+
+    /// Prove keys without returning their values, for light clients verifying
+    /// against a known hash.
+    fn prove(&self, sender: &Address, args: ProveArgs) -> Result<ProveReturns, ManyError>;
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -135,9 +154,13 @@ mod tests {
         };
         let mut mock = MockKvStoreModuleBackend::new();
         mock.expect_get()
-            .with(predicate::eq(identity(1)), predicate::eq(data.clone()))
+            .with(
+                predicate::eq(identity(1)),
+                predicate::eq(data.clone()),
+                predicate::always(),
+            )
             .times(1)
-            .returning(|_id, _args| {
+            .returning(|_id, _args, _| {
                 Ok(GetReturns {
                     value: Some(ByteVec::from(vec![1, 2, 3, 4])),
                 })
@@ -159,13 +182,19 @@ mod tests {
         };
         let mut mock = MockKvStoreModuleBackend::new();
         mock.expect_query()
-            .with(predicate::eq(identity(1)), predicate::eq(data.clone()))
+            .with(
+                predicate::eq(identity(1)),
+                predicate::eq(data.clone()),
+                predicate::always(),
+            )
             .times(1)
-            .returning(|_id, _args| {
+            .returning(|_id, _args, _| {
                 Ok(QueryReturns {
                     owner: identity(666),
                     disabled: None,
                     previous_owner: None,
+                    disabled_until: None,
+                    roles: None,
                 })
             });
         let module = super::KvStoreModule::new(Arc::new(Mutex::new(mock)));
@@ -195,6 +224,32 @@ mod tests {
         assert_eq!(list_returns.keys, vec![vec![1].into(), vec![2].into()]);
     }
 
+    #[test]
+    fn prove() {
+        let data = ProveArgs {
+            keys: vec![ByteVec::from(vec![5, 6, 7])],
+        };
+        let proof = vec![many_types::ProofOperation::NodeHash(vec![1u8; 32])];
+        let mut mock = MockKvStoreModuleBackend::new();
+        mock.expect_prove()
+            .with(predicate::eq(identity(1)), predicate::eq(data.clone()))
+            .times(1)
+            .return_const(Ok(ProveReturns {
+                proof: proof.clone().into(),
+                hash: ByteVec::from(vec![9u8; 8]),
+            }));
+        let module = super::KvStoreModule::new(Arc::new(Mutex::new(mock)));
+
+        let prove_returns: ProveReturns = minicbor::decode(
+            &call_module_cbor(1, &module, "kvstore.prove", minicbor::to_vec(data).unwrap())
+                .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(prove_returns.proof.operations, proof);
+        assert_eq!(prove_returns.hash, ByteVec::from(vec![9u8; 8]));
+    }
+
     #[test]
     fn key_filter_type_from_str() {
         let key_filter_type = KeyFilterType::from_str("owner:maa").unwrap();
@@ -0,0 +1,23 @@
+use many_types::proof::Proof;
+use minicbor::bytes::ByteVec;
+use minicbor::{Decode, Encode};
+
+#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct ProveArgs {
+    /// The keys to prove. Both the value and the metadata of each key are
+    /// included in the proof.
+    #[n(0)]
+    pub keys: Vec<ByteVec>,
+}
+
+#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct ProveReturns {
+    #[n(0)]
+    pub proof: Proof,
+
+    /// The root hash of the store the proof was made against.
+    #[n(1)]
+    pub hash: ByteVec,
+}
@@ -1,6 +1,7 @@
+use crate::kvstore::KeyRoles;
 use many_error::Reason;
 use many_identity::Address;
-use many_types::Either;
+use many_types::{Either, Timestamp};
 use minicbor::bytes::ByteVec;
 use minicbor::{Decode, Encode};
 
@@ -14,6 +15,7 @@ pub struct QueryArgs {
 #[derive(Clone, Debug, Encode, Decode)]
 #[cbor(map)]
 pub struct QueryReturns {
+    /// An owner of the key, the first in address order if it has several.
     #[n(0)]
     pub owner: Address,
 
@@ -22,4 +24,12 @@ pub struct QueryReturns {
 
     #[n(2)]
     pub previous_owner: Option<Address>,
+
+    /// When the key is disabled until a given time, that time.
+    #[n(3)]
+    pub disabled_until: Option<Timestamp>,
+
+    /// The roles on the key, including its owners.
+    #[n(4)]
+    pub roles: Option<KeyRoles>,
 }
@@ -0,0 +1,33 @@
+use many_identity::Address;
+use minicbor::{Decode, Encode};
+use std::collections::BTreeMap;
+
+/// A role on a key, given by its owner. Each role includes the following
+/// ones.
+#[derive(Clone, Copy, Debug, Encode, Decode, Eq, PartialEq, Ord, PartialOrd)]
+#[cbor(index_only)]
+pub enum KeyRole {
+    /// Can put, disable, enable, transfer and change the roles of the key.
+    /// Every key has at least one owner.
+    #[n(0)]
+    Owner,
+
+    /// Can put new values, without becoming the owner.
+    #[n(1)]
+    Writer,
+
+    /// Can get the value. Keys with readers can only be read by their
+    /// readers, writers and owners. Keys without readers can be read by
+    /// anyone.
+    #[n(2)]
+    Reader,
+}
+
+impl KeyRole {
+    /// Whether this role grants the rights of `other`.
+    pub fn includes(&self, other: KeyRole) -> bool {
+        *self <= other
+    }
+}
+
+pub type KeyRoles = BTreeMap<Address, KeyRole>;
@@ -1,5 +1,7 @@
 use crate as module;
-use crate::account::features::multisig::MultisigTransactionState;
+use crate::account::features::multisig::{
+    Attachment, MultisigTransactionState, MULTISIG_TRANSACTION_MAX_DEPTH,
+};
 use crate::account::AddressRoleMap;
 use many_error::{ManyError, Reason};
 use many_identity::Address;
@@ -12,12 +14,16 @@ use many_types::{AttributeRelatedIndex, CborRange, Either, Memo, Timestamp, VecO
 use minicbor::bytes::ByteVec;
 use minicbor::{encode, Decode, Decoder, Encode, Encoder};
 use num_bigint::BigUint;
+use std::cell::Cell;
+use std::cmp::Ordering;
 use std::collections::{BTreeMap, BTreeSet};
+use std::ops::{Bound, RangeBounds};
 use std::sync::Arc;
 
 #[cfg(test)]
 use mockall::{automock, predicate::*};
 
+pub mod digest;
 mod info;
 mod list;
 
@@ -31,10 +37,83 @@ pub trait EventsModuleBackend: Send {
     fn list(&self, args: ListArgs) -> Result<ListReturns, ManyError>;
 }
 
-#[derive(Clone, Debug, Ord, PartialOrd, Eq, PartialEq)]
+/// Event IDs embed the height of their block in their upper bits, starting
+/// at this bit.
+pub const HEIGHT_EVENTID_SHIFT: u64 = 32;
+
+/// An event ID, as a big-endian unsigned integer of any length. IDs compare
+/// by their numeric value, regardless of leading zeroes.
+///
+/// The events of the block at height `h` have IDs in the range
+/// `[(h - 1) << HEIGHT_EVENTID_SHIFT, h << HEIGHT_EVENTID_SHIFT)`, so the
+/// events of heights 0 and 1 share the same range.
+#[derive(Clone, Debug)]
 #[repr(transparent)]
 pub struct EventId(ByteVec);
 
+impl EventId {
+    /// The lowest ID of the events of a height.
+    pub fn first_of_height(height: u64) -> Self {
+        EventId::from(height.saturating_sub(1) << HEIGHT_EVENTID_SHIFT)
+    }
+
+    /// The height of the block which emitted this event.
+    pub fn height(&self) -> u64 {
+        let height = BigUint::from_bytes_be(&self.0) >> HEIGHT_EVENTID_SHIFT;
+        u64::try_from(height).map_or(u64::MAX, |h| h.saturating_add(1))
+    }
+
+    /// The range of IDs of all events of a height.
+    pub fn range_of_height(height: u64) -> CborRange<EventId> {
+        Self::range_of_heights(height..=height)
+    }
+
+    /// The range of IDs of all events within a range of heights, e.g.
+    /// `EventId::range_of_heights(10..)`.
+    pub fn range_of_heights(heights: impl RangeBounds<u64>) -> CborRange<EventId> {
+        let start = match heights.start_bound() {
+            Bound::Included(h) => Bound::Included(Self::first_of_height(*h)),
+            Bound::Excluded(h) => Bound::Included(Self::first_of_height(h.saturating_add(1))),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        let end = match heights.end_bound() {
+            Bound::Included(h) => {
+                Bound::Excluded(Self::first_of_height(h.max(&1).saturating_add(1)))
+            }
+            Bound::Excluded(h) => Bound::Excluded(Self::first_of_height(*h)),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        CborRange { start, end }
+    }
+
+    /// The bytes of the ID without leading zeroes.
+    fn significant_bytes(&self) -> &[u8] {
+        let zeroes = self.0.iter().take_while(|b| **b == 0).count();
+        &self.0[zeroes..]
+    }
+}
+
+impl PartialEq for EventId {
+    fn eq(&self, other: &Self) -> bool {
+        self.significant_bytes() == other.significant_bytes()
+    }
+}
+
+impl Eq for EventId {}
+
+impl PartialOrd for EventId {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for EventId {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (a, b) = (self.significant_bytes(), other.significant_bytes());
+        a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+    }
+}
+
 impl From<ByteVec> for EventId {
     fn from(t: ByteVec) -> EventId {
         EventId(t)
@@ -331,6 +410,12 @@ impl<V> AddressContainer for BTreeMap<Address, V> {
     }
 }
 
+impl AddressContainer for BTreeSet<Address> {
+    fn addresses(&self) -> BTreeSet<Address> {
+        self.clone()
+    }
+}
+
 macro_rules! define_event_kind {
     ( $( [ $index: literal $(, $sub: literal )* ] $name: ident { $( $idx: literal | $fname: ident : $type: ty, )* }, )* ) => {
         #[derive(
@@ -402,6 +487,13 @@ macro_rules! define_event_info_memo {
     (@pick_memo $name_: ident $( $tag_: ident )*, $( $name: ident $( $tag: ident )*, )* ) => {
         define_event_info_memo!(@pick_memo $( $name $( $tag )*, )* )
     };
+    (@pick_memo_mut) => {};
+    (@pick_memo_mut $name: ident memo $(,)? $( $name_: ident $( $tag_: ident )*, )* ) => {
+        return Some($name)
+    };
+    (@pick_memo_mut $name_: ident $( $tag_: ident )*, $( $name: ident $( $tag: ident )*, )* ) => {
+        define_event_info_memo!(@pick_memo_mut $( $name $( $tag )*, )* )
+    };
 
     ( $( $name: ident { $( $fname: ident $( $tag: ident )* , )* } )* ) => {
         #[inline]
@@ -418,6 +510,23 @@ macro_rules! define_event_info_memo {
 
             None
         }
+
+        /// The memo field of the event, if its kind has one, e.g. to redact
+        /// it.
+        #[inline]
+        pub fn memo_mut(&mut self) -> Option<&mut Option<Memo>> {
+            match self {
+                $( EventInfo :: $name {
+                    $( $fname, )*
+                } => {
+                    // Remove warnings.
+                    $( let _ = $fname; )*
+                    define_event_info_memo!(@pick_memo_mut $( $fname $( $tag )*, )* );
+                } )*
+            }
+
+            None
+        }
     };
 }
 
@@ -497,6 +606,12 @@ macro_rules! event_info_count_field {
             None => 0u64,
         }
     };
+    (@single $name: ident [ optional $( $tag: ident )* ]) => {
+        match $name {
+            Some(_) => 1u64,
+            None => 0u64,
+        }
+    };
     (@single $name: ident [ $head: ident $( $tail: ident )* ]) => {
         event_info_count_field!(@single $name [ $( $tail )* ] )
     };
@@ -516,6 +631,13 @@ macro_rules! encode_event_info_field {
             $e.u8($idx)?.encode(field)?;
         }
     };
+    // Optional fields are left out of the map when empty, so adding one to an
+    // existing event does not change how the event is encoded.
+    (@inner $e: ident $idx: literal $name: ident [ optional $( $tail: ident )* ]) => {
+        if let Some(field) = $name {
+            $e.u8($idx)?.encode(field)?;
+        }
+    };
     (@inner $e: ident $idx: literal $name: ident [ $head: ident $( $tail: ident )* ]) => {
         encode_event_info_field!($e $idx $name [ $( $tail )* ])
     };
@@ -535,6 +657,12 @@ macro_rules! encode_event_info_unpack_decode {
             None => Ok(None),
         }
     };
+    (@inner $name: ident $idx: literal [optional $( $tail: ident )*]) => {
+        match $name {
+            Some(x) => Ok(x),
+            None => Ok(None),
+        }
+    };
     (@inner $name: ident $idx: literal [$head: ident $( $tail: ident )*]) => {
         encode_event_info_unpack_decode!( $name $idx [$( $tail )*] )
     };
@@ -604,6 +732,35 @@ macro_rules! encode_event_info {
     }
 }
 
+thread_local! {
+    static MULTISIG_TRANSACTION_DECODE_DEPTH: Cell<usize> = Cell::new(0);
+}
+
+/// Counts the multisig transactions being decoded on this thread, so a deeply
+/// nested payload is refused before it exhausts the stack.
+struct DecodeDepthGuard;
+
+impl DecodeDepthGuard {
+    fn enter() -> Result<Self, minicbor::decode::Error> {
+        MULTISIG_TRANSACTION_DECODE_DEPTH.with(|depth| {
+            if depth.get() >= MULTISIG_TRANSACTION_MAX_DEPTH {
+                Err(minicbor::decode::Error::message(
+                    "Transactions are nested too deeply.",
+                ))
+            } else {
+                depth.set(depth.get() + 1);
+                Ok(Self)
+            }
+        })
+    }
+}
+
+impl Drop for DecodeDepthGuard {
+    fn drop(&mut self) {
+        MULTISIG_TRANSACTION_DECODE_DEPTH.with(|depth| depth.set(depth.get() - 1));
+    }
+}
+
 macro_rules! define_multisig_event {
     (@addresses $arg: ident [ addresses $( $struct_tag: ident )* ]) => {
         $arg .addresses()
@@ -626,6 +783,16 @@ macro_rules! define_multisig_event {
             pub fn is_about(&self, id: Address) -> bool {
                 self.addresses().contains(&id)
             }
+
+            /// The number of transactions nested in this one, including itself.
+            pub fn depth(&self) -> usize {
+                match self {
+                    AccountMultisigTransaction::AccountMultisigSubmit(arg) => {
+                        1 + arg.transaction.depth()
+                    }
+                    _ => 1,
+                }
+            }
         }
 
         impl AddressContainer for AccountMultisigTransaction {
@@ -665,6 +832,7 @@ macro_rules! define_multisig_event {
 
         impl<'b, C> Decode<'b, C> for AccountMultisigTransaction {
             fn decode(d: &mut Decoder<'b>, _: &mut C) -> Result<Self, minicbor::decode::Error> {
+                let _depth = DecodeDepthGuard::enter()?;
                 let len = d.map()?.ok_or(minicbor::decode::Error::message(
                     "Invalid event type.",
                 ))?;
@@ -713,6 +881,7 @@ define_event! {
         3     | symbol:                 Symbol                                 [ id ],
         4     | amount:                 TokenAmount,
         5     | memo:                   Option<Memo>                           [ memo ],
+        6     | reference:              Option<String>,
     },
     [7, 0]      KvStorePut (crate::kvstore::PutArgs) {
         1     | key:                    ByteVec,
@@ -722,6 +891,11 @@ define_event! {
     [7, 1]      KvStoreDisable (crate::kvstore::DisableArgs) {
         1     | key:                    ByteVec,
         2     | reason:                 Option<Reason<u64>>,
+        3     | until:                  Option<Timestamp>                      [ optional ],
+    },
+    [7, 2]      KvStoreEnable (crate::kvstore::EnableArgs) {
+        1     | key:                    ByteVec,
+        2     | owner:                  Address                                [ id ],
     },
     [9, 0]      AccountCreate (crate::account::CreateArgs [ addresses ]) {
         1     | account:                Address                                [ id ],
@@ -749,6 +923,14 @@ define_event! {
         2     | roles:                  AddressRoleMap                         [ id ],
         3     | features:               crate::account::features::FeatureSet,
     },
+    [9, 6]      AccountPrune {
+        1     | account:                Address                                [ id ],
+        2     | hash:                   ByteVec,
+    },
+    [9, 7]      AccountSetMetadata (crate::account::SetMetadataArgs [ addresses ]) {
+        1     | account:                Address                                [ id ],
+        2     | metadata:               crate::account::AccountMetadata,
+    },
     [9, 1, 0]   AccountMultisigSubmit (crate::account::features::multisig::SubmitTransactionArgs [ addresses ]) {
         1     | submitter:              Address                                [ id ],
         2     | account:                Address                                [ id ],
@@ -760,6 +942,7 @@ define_event! {
         8     | execute_automatically:  bool,
         9     | data_:                  Option<DataLegacy>,
         10    | memo:                   Option<Memo>                           [ memo ],
+        11    | attachment:             Option<Attachment>,
     },
     [9, 1, 1]   AccountMultisigApprove (crate::account::features::multisig::ApproveArgs) {
         1     | account:                Address                                [ id ],
@@ -794,6 +977,13 @@ define_event! {
         2     | token:                  ByteVec,
         3     | time:                   Timestamp,
     },
+    [9, 1, 7]   AccountMultisigCosignedExecute {
+        1     | account:                Address                                [ id ],
+        2     | submitter:              Address                                [ id ],
+        3     | signers:                BTreeSet<Address>                      [ id ],
+        4     | transaction:            Box<AccountMultisigTransaction>        [ id ],
+        5     | response:               ResponseMessage,
+    },
     [11, 0]     TokenCreate (module::ledger::TokenCreateArgs) {
         1     | summary:                ledger::TokenInfoSummary,
         2     | symbol:                 Address                                [ id ],
@@ -821,6 +1011,13 @@ define_event! {
         2     | extended_info:          Vec<AttributeRelatedIndex>,
         3     | memo:                   Option<Memo>                           [ memo ],
     },
+    [11, 4]     TokenRename {
+        1     | symbol:                 Address                                [ id ],
+        2     | previous_name:          String,
+        3     | previous_ticker:        String,
+        4     | name:                   String,
+        5     | ticker:                 String,
+    },
     [12, 0]     TokenMint (module::ledger::TokenMintArgs) {
         1     | symbol:                 Address                                [ id ],
         2     | distribution:           ledger::LedgerTokensAddressMap         [ id ],
@@ -857,6 +1054,57 @@ define_event! {
         5     | memo:                   Option<Memo>                           [ memo ],
         6     | domain:                 Option<String>,
     },
+    [17, 3]     WebRollback (module::web::RollbackArgs) {
+        1     | owner:                  Address                                [ id ],
+        2     | site_name:              String,
+        3     | version:                u64,
+        4     | memo:                   Option<Memo>                           [ memo ],
+    },
+    [19, 0]     NameRegister {
+        1     | name:                   String,
+        2     | owner:                  Address                                [ id ],
+        3     | expiration:             Timestamp,
+    },
+    [19, 1]     NameTransfer {
+        1     | name:                   String,
+        2     | owner:                  Address                                [ id ],
+        3     | new_owner:              Address                                [ id ],
+    },
+    [20, 0]     AttestPublish {
+        1     | attestor:               Address                                [ id ],
+        2     | subject:                Address                                [ id ],
+        3     | kind:                   String,
+        4     | expiration:             Option<Timestamp>,
+    },
+    [20, 1]     AttestRevoke {
+        1     | attestor:               Address                                [ id ],
+        2     | subject:                Address                                [ id ],
+        3     | kind:                   String,
+    },
+    [21, 0]     RelayExecute {
+        1     | relayer:                Address                                [ id ],
+        2     | sender:                 Address                                [ id ],
+        3     | method:                 String,
+    },
+    [23, 0]     StorePin {
+        1     | owner:                  Address                                [ id ],
+        2     | hash:                   ByteVec,
+        3     | size:                   u64,
+    },
+    [23, 1]     StoreUnpin {
+        1     | owner:                  Address                                [ id ],
+        2     | hash:                   ByteVec,
+        // Whether the object was removed, without pins left.
+        3     | removed:                bool,
+    },
+    [24, 0]     ComplianceListAdd {
+        1     | admin:                  Address                                [ id ],
+        2     | address:                Address                                [ id ],
+    },
+    [24, 1]     ComplianceListRemove {
+        1     | admin:                  Address                                [ id ],
+        2     | address:                Address                                [ id ],
+    },
 }
 
 /// An Event that happened on the server and that is part of the log.
@@ -906,6 +1154,40 @@ mod test {
         assert_eq!(v.to_be_bytes(), Into::<Vec<u8>>::into(t).as_slice());
     }
 
+    #[test]
+    fn eventid_ordering() {
+        assert_eq!(EventId::from(vec![0, 0, 1]), EventId::from(vec![1]));
+        assert!(EventId::from(vec![2]) < EventId::from(vec![0, 1, 0]));
+        assert!(EventId::from(1u64 << 32) + 1 < EventId::from(2u64 << 32));
+        assert!(EventId::from(vec![1, 0]) < EventId::from(vec![1, 1]));
+    }
+
+    #[test]
+    fn eventid_height() {
+        for height in [2, 3, 1000] {
+            let first = EventId::first_of_height(height);
+            let range = EventId::range_of_height(height);
+            assert_eq!((first.clone() + 1).height(), height);
+            assert_eq!((EventId::first_of_height(height + 1) - 1).height(), height);
+            assert!(range.contains(&(first.clone() + 1)));
+            assert!(!range.contains(&(first - 1)));
+            assert!(!range.contains(&EventId::first_of_height(height + 1)));
+        }
+
+        // The first two heights share the same range.
+        assert_eq!(EventId::range_of_height(0), EventId::range_of_height(1));
+        assert_eq!(EventId::from(5u64).height(), 1);
+
+        let range = EventId::range_of_heights(3..5);
+        assert!(range.contains(&(EventId::first_of_height(3) + 1)));
+        assert!(range.contains(&(EventId::first_of_height(5) - 1)));
+        assert!(!range.contains(&EventId::first_of_height(5)));
+        assert_eq!(
+            EventId::range_of_heights(3..).start,
+            Bound::Included(EventId::first_of_height(3))
+        );
+    }
+
     #[test]
     fn eventid_from_u64() {
         let v = u64::MAX;
@@ -962,6 +1244,7 @@ mod test {
             symbol: Address::anonymous(),
             amount: Default::default(),
             memo: None,
+            reference: None,
         };
         assert_eq!(
             s0.addresses(),
@@ -986,6 +1269,7 @@ mod test {
                 amount: Default::default(),
                 symbol: Default::default(),
                 memo: None,
+                reference: None,
             })),
             token: None,
             threshold: 0,
@@ -993,6 +1277,7 @@ mod test {
             execute_automatically: false,
             data_: None,
             memo_: None,
+            attachment: None,
         };
         assert_eq!(s0.addresses(), BTreeSet::from_iter([i0, i01, i1, i11]));
     }
@@ -1014,12 +1299,14 @@ mod test {
                 amount: Default::default(),
                 symbol: Default::default(),
                 memo: None,
+                reference: None,
             })),
             threshold: None,
             timeout_in_secs: None,
             execute_automatically: None,
             data_: None,
             memo: None,
+            attachment: None,
         });
         let s1 = EventInfo::AccountMultisigSubmit {
             submitter: i1,
@@ -1032,10 +1319,56 @@ mod test {
             execute_automatically: false,
             data_: None,
             memo_: None,
+            attachment: None,
         };
         assert_eq!(s1.addresses(), BTreeSet::from_iter([i0, i01, i1, i11, i2]));
     }
 
+    #[test]
+    fn transaction_depth() {
+        fn nested(depth: usize) -> AccountMultisigTransaction {
+            (1..depth).fold(
+                AccountMultisigTransaction::Send(SendArgs {
+                    from: Some(identity(0)),
+                    to: identity(1),
+                    amount: Default::default(),
+                    symbol: Default::default(),
+                    memo: None,
+                    reference: None,
+                }),
+                |transaction, _| {
+                    AccountMultisigTransaction::AccountMultisigSubmit(SubmitTransactionArgs {
+                        account: identity(0),
+                        memo_: None,
+                        transaction: Box::new(transaction),
+                        threshold: None,
+                        timeout_in_secs: None,
+                        execute_automatically: None,
+                        data_: None,
+                        memo: None,
+                        attachment: None,
+                    })
+                },
+            )
+        }
+
+        let max = MULTISIG_TRANSACTION_MAX_DEPTH;
+        let transaction = nested(max);
+        assert_eq!(transaction.depth(), max);
+        let bytes = minicbor::to_vec(&transaction).unwrap();
+        assert_eq!(
+            minicbor::decode::<AccountMultisigTransaction>(&bytes).unwrap(),
+            transaction
+        );
+
+        let bytes = minicbor::to_vec(nested(max + 1)).unwrap();
+        assert!(minicbor::decode::<AccountMultisigTransaction>(&bytes).is_err());
+
+        // The depth is reset after a failure.
+        let bytes = minicbor::to_vec(nested(2)).unwrap();
+        assert!(minicbor::decode::<AccountMultisigTransaction>(&bytes).is_ok());
+    }
+
     #[test]
     fn addresses_1() {
         fn check(t: impl AddressContainer, expects: impl IntoIterator<Item = Address>) {
@@ -1054,6 +1387,7 @@ mod test {
                 symbol: i1,
                 amount: Default::default(),
                 memo: None,
+                reference: None,
             },
             [i0, i01, i1],
         );
@@ -1069,9 +1403,17 @@ mod test {
             EventInfo::KvStoreDisable {
                 key: vec![].into(),
                 reason: None,
+                until: None,
             },
             [],
         );
+        check(
+            EventInfo::KvStoreEnable {
+                key: vec![].into(),
+                owner: i0,
+            },
+            [i0],
+        );
         check(
             EventInfo::AccountCreate {
                 account: i0,
@@ -1108,6 +1450,16 @@ mod test {
             },
             [i0, i1],
         );
+        check(
+            EventInfo::TokenRename {
+                symbol: i0,
+                previous_name: "Foo".to_string(),
+                previous_ticker: "FOO".to_string(),
+                name: "Bar".to_string(),
+                ticker: "BAR".to_string(),
+            },
+            [i0],
+        );
         check(
             EventInfo::TokenMint {
                 symbol: i0,
@@ -1131,6 +1483,7 @@ mod test {
             symbol: Default::default(),
             amount: Default::default(),
             memo: None,
+            reference: None,
         };
         assert!(s0.is_about(i0));
         assert!(s0.is_about(i01));
@@ -1166,6 +1519,7 @@ mod test {
             symbol: i1,
             amount: Default::default(),
             memo: None,
+            reference: None,
         };
         assert_eq!(event.memo(), None);
 
@@ -1179,6 +1533,7 @@ mod test {
                 amount: Default::default(),
                 symbol: Default::default(),
                 memo: None,
+                reference: None,
             })),
             token: None,
             threshold: 0,
@@ -1186,6 +1541,7 @@ mod test {
             execute_automatically: false,
             data_: Some(DataLegacy::try_from(b"World".to_vec()).unwrap()),
             memo: Some(Memo::try_from("Foo").unwrap()),
+            attachment: None,
         };
         assert_eq!(event.memo().unwrap(), "Foo");
     }
@@ -1205,6 +1561,7 @@ mod test {
                 amount: Default::default(),
                 symbol: Default::default(),
                 memo: None,
+                reference: None,
             })),
             token: None,
             threshold: 0,
@@ -1212,6 +1569,7 @@ mod test {
             execute_automatically: false,
             data_: Some(DataLegacy::try_from(b"World".to_vec()).unwrap()),
             memo: None,
+            attachment: None,
         };
         assert_eq!(event.memo(), None);
     }
@@ -1237,6 +1595,7 @@ mod test {
                 execute_automatically: false,
                 memo_: None,
                 data_: None,
+                attachment: None,
             }
         }
 
@@ -1252,6 +1611,7 @@ mod test {
                 execute_automatically: false,
                 memo_: None,
                 data_: None,
+                attachment: None,
             }
         }
 
@@ -1272,6 +1632,7 @@ mod test {
                     amount: Default::default(),
                     symbol: Default::default(),
                     memo: None,
+                    reference: None,
                 }),
             );
             let bytes = minicbor::to_vec(&event).expect("Could not serialize");
@@ -1284,6 +1645,7 @@ mod test {
                 amount: Default::default(),
                 symbol: Default::default(),
                 memo: None,
+                reference: None,
             }));
             let bytes = minicbor::to_vec(&event).expect("Could not serialize");
             let map: BTreeMap<CborAny, CborAny> = minicbor::decode(&bytes).unwrap();
@@ -1314,6 +1676,7 @@ mod test {
                         symbol: identity(4),
                         amount: amount.into(),
                         memo: None,
+                        reference: None,
                     })),
                 );
             }
@@ -1334,12 +1697,14 @@ mod test {
                                     symbol: identity(4),
                                     amount: amount.into(),
                                     memo: None,
+                                    reference: None,
                                 })),
                                 threshold: None,
                                 timeout_in_secs: None,
                                 execute_automatically: None,
                                 data_: None,
                                 memo_: None,
+                                attachment: None,
                             }
                         )
                     )
@@ -1396,6 +1761,7 @@ mod tests {
             count: Some(1),
             order: None,
             filter: None,
+            cursor: None,
         };
         let mut mock = MockEventsModuleBackend::new();
         mock.expect_list()
@@ -1413,8 +1779,10 @@ mod tests {
                             symbol: Default::default(),
                             amount: TokenAmount::from(1000u64),
                             memo: None,
+                            reference: None,
                         },
                     }],
+                    next_cursor: None,
                 })
             });
         let module = super::EventsModule::new(Arc::new(Mutex::new(mock)));
@@ -0,0 +1,161 @@
+//! Merkle tree over the events emitted during a block.
+//!
+//! Leaves are the CBOR encoding of each `EventLog`, in the order of their IDs.
+//! Leaf and inner nodes are hashed with SHA3-256 using distinct prefixes so an
+//! inner node can never be mistaken for a leaf. When a level has an odd number
+//! of nodes, the last one is promoted to the next level as is.
+use minicbor::{Decode, Encode};
+use sha3::{Digest, Sha3_256};
+
+const LEAF_PREFIX: u8 = 0;
+const NODE_PREFIX: u8 = 1;
+
+fn hash_leaf(leaf: &[u8]) -> [u8; 32] {
+    Sha3_256::new()
+        .chain_update([LEAF_PREFIX])
+        .chain_update(leaf)
+        .finalize()
+        .into()
+}
+
+fn hash_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    Sha3_256::new()
+        .chain_update([NODE_PREFIX])
+        .chain_update(left)
+        .chain_update(right)
+        .finalize()
+        .into()
+}
+
+fn next_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => hash_node(left, right),
+            [single] => *single,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+/// The side of a sibling hash in an inclusion proof.
+#[derive(Clone, Copy, Debug, Encode, Decode, Eq, PartialEq)]
+#[cbor(index_only)]
+pub enum Side {
+    #[n(0)]
+    Left,
+    #[n(1)]
+    Right,
+}
+
+/// One step of an inclusion proof, from the leaf up to the root.
+#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct ProofStep {
+    #[n(0)]
+    pub side: Side,
+
+    #[n(1)]
+    #[cbor(with = "minicbor::bytes")]
+    pub hash: [u8; 32],
+}
+
+/// The digest of a block without any events.
+pub fn empty_digest() -> [u8; 32] {
+    Sha3_256::digest([]).into()
+}
+
+/// Compute the Merkle root of a list of encoded events.
+pub fn events_digest<T: AsRef<[u8]>>(events: &[T]) -> [u8; 32] {
+    let mut level: Vec<[u8; 32]> = events.iter().map(|e| hash_leaf(e.as_ref())).collect();
+    if level.is_empty() {
+        return empty_digest();
+    }
+
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level[0]
+}
+
+/// Build the proof that the event at `index` is part of the digest of
+/// `events`. Returns `None` if the index is out of bounds.
+pub fn inclusion_proof<T: AsRef<[u8]>>(events: &[T], index: usize) -> Option<Vec<ProofStep>> {
+    if index >= events.len() {
+        return None;
+    }
+
+    let mut level: Vec<[u8; 32]> = events.iter().map(|e| hash_leaf(e.as_ref())).collect();
+    let mut index = index;
+    let mut proof = Vec::new();
+    while level.len() > 1 {
+        let sibling = index ^ 1;
+        if let Some(hash) = level.get(sibling) {
+            let side = if sibling < index {
+                Side::Left
+            } else {
+                Side::Right
+            };
+            proof.push(ProofStep { side, hash: *hash });
+        }
+
+        level = next_level(&level);
+        index /= 2;
+    }
+    Some(proof)
+}
+
+/// Verify that an encoded event is part of a block whose events digest is
+/// `digest`.
+pub fn verify_inclusion(digest: &[u8], event: &[u8], proof: &[ProofStep]) -> bool {
+    let root = proof
+        .iter()
+        .fold(hash_leaf(event), |acc, step| match step.side {
+            Side::Left => hash_node(&step.hash, &acc),
+            Side::Right => hash_node(&acc, &step.hash),
+        });
+    root.as_slice() == digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty() {
+        assert_eq!(events_digest::<&[u8]>(&[]), empty_digest());
+        assert!(inclusion_proof::<&[u8]>(&[], 0).is_none());
+    }
+
+    #[test]
+    fn single() {
+        let events = [b"event"];
+        let digest = events_digest(&events);
+        assert_ne!(digest, empty_digest());
+
+        let proof = inclusion_proof(&events, 0).unwrap();
+        assert!(proof.is_empty());
+        assert!(verify_inclusion(&digest, b"event", &proof));
+        assert!(!verify_inclusion(&digest, b"other", &proof));
+    }
+
+    #[test]
+    fn order_matters() {
+        assert_ne!(events_digest(&[b"a", b"b"]), events_digest(&[b"b", b"a"]));
+    }
+
+    #[test]
+    fn proofs() {
+        for len in 1..10u8 {
+            let events: Vec<Vec<u8>> = (0..len).map(|i| vec![i; 4]).collect();
+            let digest = events_digest(&events);
+
+            for (i, event) in events.iter().enumerate() {
+                let proof = inclusion_proof(&events, i).unwrap();
+                assert!(verify_inclusion(&digest, event, &proof));
+                assert!(!verify_inclusion(&digest, &[len; 4], &proof));
+            }
+            assert!(inclusion_proof(&events, len as usize).is_none());
+        }
+    }
+}
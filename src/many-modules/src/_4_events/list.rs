@@ -1,6 +1,8 @@
 use crate::events;
-use many_types::SortOrder;
+use many_error::ManyError;
+use many_types::{CborRange, SortOrder};
 use minicbor::{Decode, Encode};
+use std::ops::Bound;
 
 #[derive(Clone, Debug, Default, Encode, Decode, Eq, PartialEq)]
 #[cbor(map)]
@@ -13,6 +15,39 @@ pub struct ListArgs {
 
     #[n(2)]
     pub filter: Option<events::EventFilter>,
+
+    /// Only list the events after this one (in the requested order), e.g.
+    /// the `next_cursor` of the previous page.
+    #[n(3)]
+    pub cursor: Option<events::EventId>,
+}
+
+impl ListArgs {
+    /// The range of event IDs to list, from the ID range of the filter and
+    /// the cursor.
+    pub fn id_range(&self) -> CborRange<events::EventId> {
+        let range = self
+            .filter
+            .as_ref()
+            .and_then(|f| f.id_range.clone())
+            .unwrap_or_default();
+        match &self.cursor {
+            None => range,
+            Some(cursor) => {
+                let after = match self.order.clone().unwrap_or_default() {
+                    SortOrder::Descending => CborRange {
+                        start: Bound::Unbounded,
+                        end: Bound::Excluded(cursor.clone()),
+                    },
+                    SortOrder::Indeterminate | SortOrder::Ascending => CborRange {
+                        start: Bound::Excluded(cursor.clone()),
+                        end: Bound::Unbounded,
+                    },
+                };
+                range.intersection(&after)
+            }
+        }
+    }
 }
 
 #[derive(Encode, Decode)]
@@ -23,4 +58,32 @@ pub struct ListReturns {
 
     #[n(1)]
     pub events: Vec<events::EventLog>,
+
+    /// Set if more events match the arguments. Pass it as the `cursor` of
+    /// the arguments to list the next page.
+    #[n(2)]
+    pub next_cursor: Option<events::EventId>,
+}
+
+impl ListReturns {
+    /// Take a page of `count` events, setting `next_cursor` if the iterator
+    /// has more.
+    pub fn page(
+        nb_events: u64,
+        events: impl Iterator<Item = Result<events::EventLog, ManyError>>,
+        count: usize,
+    ) -> Result<Self, ManyError> {
+        let mut events: Vec<events::EventLog> = events.take(count + 1).collect::<Result<_, _>>()?;
+        let next_cursor = if events.len() > count {
+            events.truncate(count);
+            events.last().map(|e| e.id.clone())
+        } else {
+            None
+        };
+        Ok(Self {
+            nb_events,
+            events,
+            next_cursor,
+        })
+    }
 }
@@ -4,7 +4,7 @@ pub mod query;
 pub mod types;
 pub use get_info::*;
 pub use info::*;
-use many_error::ManyError;
+use many_error::{define_attribute_many_error, ManyError};
 use many_identity::Address;
 use many_macros::many_module;
 use many_protocol::context::Context;
@@ -14,6 +14,12 @@ pub use types::*;
 #[cfg(test)]
 use mockall::{automock, predicate::*};
 
+define_attribute_many_error!(
+    attribute 5 => {
+        1: pub fn decimal_out_of_range(a, b) => "Decimal exponents are too far apart: {a} and {b}.",
+    }
+);
+
 #[many_module(name = DataModule, id = 5, namespace = data, many_modules_crate = crate)]
 #[cfg_attr(test, automock)]
 pub trait DataModuleBackend: Send {
@@ -114,6 +120,63 @@ mod tests {
         assert_eq!(results[&non_zero_account_total_count], nzatc);
     }
 
+    #[test]
+    fn decimal_gauge() {
+        let a: Decimal = "1.10".parse().unwrap();
+        let b: Decimal = "-0.2".parse().unwrap();
+        assert_eq!(a, Decimal::new(11, -1));
+        assert_eq!(a.checked_add(&b).unwrap().to_string(), "0.90");
+        assert_eq!(b.checked_sub(&a).unwrap().to_string(), "-1.30");
+        assert!(b < a);
+        assert!(Decimal::new(-1, 2) < Decimal::new(-99, 0));
+        assert!(Decimal::new(0, 5) < Decimal::new(1, -5));
+        assert_eq!(Decimal::new(1500, -3).normalize(), Decimal::new(15, -1));
+        assert_eq!(Decimal::new(15, 2).to_string(), "1500");
+
+        assert_eq!(
+            BigInt::try_from(Decimal::new(1200, -2)),
+            Ok(BigInt::from(12))
+        );
+        assert!(BigInt::try_from(Decimal::new(1201, -2)).is_err());
+
+        for d in [
+            a,
+            b,
+            Decimal::new(BigInt::from(u64::MAX) * 1000, -30),
+            Decimal::new(BigInt::from(i64::MIN) * 1000, 5),
+        ] {
+            let value = DataValue::Gauge(DataValueTypeGauge::Decimal(d.clone()));
+            let decoded: DataValue = minicbor::decode(&minicbor::to_vec(value).unwrap()).unwrap();
+            match decoded {
+                DataValue::Gauge(DataValueTypeGauge::Decimal(x)) => {
+                    assert_eq!(x, d);
+                    assert_eq!(x.exponent(), d.exponent());
+                }
+                _ => panic!("Expected a decimal gauge"),
+            }
+        }
+    }
+
+    #[test]
+    fn decimal_exponent_out_of_range() {
+        let tiny = Decimal::new(1, i64::MIN);
+        let huge = Decimal::new(1, i64::MAX);
+        assert!(tiny.checked_add(&huge).is_err());
+        assert!(huge.checked_sub(&Decimal::from(1)).is_err());
+        assert!(tiny < huge);
+        assert!(Decimal::new(-1, i64::MAX) < tiny);
+        assert_eq!(huge.to_string(), format!("1e{}", i64::MAX));
+        assert!(BigInt::try_from(huge).is_err());
+        assert!(format!("0.{}", "0".repeat(2000))
+            .parse::<Decimal>()
+            .is_err());
+
+        let bytes = minicbor::to_vec(Decimal::new(1, Decimal::MAX_EXPONENT + 1)).unwrap();
+        assert!(minicbor::decode::<Decimal>(&bytes).is_err());
+        let bytes = minicbor::to_vec(Decimal::new(1, -Decimal::MAX_EXPONENT)).unwrap();
+        assert!(minicbor::decode::<Decimal>(&bytes).is_ok());
+    }
+
     #[test]
     fn query() {
         // Arguments
@@ -1,6 +1,11 @@
+use many_error::ManyError;
 use many_types::AttributeRelatedIndex;
-use minicbor::{Decode, Encode};
-use num_bigint::BigInt;
+use minicbor::data::{Tag, Type};
+use minicbor::{Decode, Decoder, Encode, Encoder};
+use num_bigint::{BigInt, Sign};
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 
 pub type DataIndex = AttributeRelatedIndex;
 
@@ -41,6 +46,8 @@ pub enum DataValueTypeGauge {
     Float(#[n(0)] f64),
     #[n(2)]
     BigInt(#[cbor(n(0), decode_with = "decode_bigint", encode_with = "encode_bigint")] BigInt),
+    #[n(3)]
+    Decimal(#[n(0)] Decimal),
 }
 
 impl TryFrom<DataValueTypeGauge> for BigInt {
@@ -53,10 +60,263 @@ impl TryFrom<DataValueTypeGauge> for BigInt {
             DataValueTypeGauge::Float(_) => {
                 Err("Floats can't be converted to BigInt without loss".into())
             }
+            DataValueTypeGauge::Decimal(d) => d.try_into(),
         }
     }
 }
 
+/// An exact decimal number, `mantissa * 10^exponent`. Unlike floats, decimals
+/// can be added, subtracted and compared without any loss of precision.
+///
+/// It is encoded as a CBOR decimal fraction (tag 4). Decoded and parsed
+/// decimals have an exponent within `±MAX_EXPONENT`.
+#[derive(Clone, Debug)]
+pub struct Decimal {
+    mantissa: BigInt,
+    exponent: i64,
+}
+
+impl Decimal {
+    /// The largest exponent (in absolute value) of decimals read from the
+    /// network. Aligning two decimals scales a mantissa by up to
+    /// `10^(2 * MAX_EXPONENT)`, which keeps arithmetic cheap.
+    pub const MAX_EXPONENT: i64 = 1024;
+
+    pub fn new(mantissa: impl Into<BigInt>, exponent: i64) -> Self {
+        Self {
+            mantissa: mantissa.into(),
+            exponent,
+        }
+    }
+
+    pub fn mantissa(&self) -> &BigInt {
+        &self.mantissa
+    }
+
+    pub fn exponent(&self) -> i64 {
+        self.exponent
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.mantissa.sign() == Sign::NoSign
+    }
+
+    fn exponent_in_range(exponent: i64) -> bool {
+        (-Self::MAX_EXPONENT..=Self::MAX_EXPONENT).contains(&exponent)
+    }
+
+    /// Remove the trailing zeroes of the mantissa, e.g. `1500e-3` becomes
+    /// `15e-1`.
+    #[must_use]
+    pub fn normalize(mut self) -> Self {
+        if self.is_zero() {
+            return Self::new(0, 0);
+        }
+        let ten = BigInt::from(10);
+        while self.exponent < i64::MAX && (&self.mantissa % &ten).sign() == Sign::NoSign {
+            self.mantissa /= &ten;
+            self.exponent += 1;
+        }
+        self
+    }
+
+    /// The exponent of the most significant digit, e.g. 2 for `123`, give or
+    /// take one. It is estimated from the bit length of the mantissa.
+    fn magnitude(&self) -> i128 {
+        (self.mantissa.bits() as f64 * std::f64::consts::LOG10_2) as i128 + self.exponent as i128
+    }
+
+    /// Returns the mantissas of both numbers scaled to the same (smallest)
+    /// exponent, or an error if the exponents are too far apart.
+    fn align(&self, other: &Decimal) -> Result<(BigInt, BigInt, i64), ManyError> {
+        let exponent = self.exponent.min(other.exponent);
+        let diff = self.exponent.abs_diff(other.exponent);
+        if diff > 2 * Self::MAX_EXPONENT.unsigned_abs() {
+            return Err(super::decimal_out_of_range(self.exponent, other.exponent));
+        }
+        let scale = |d: &Decimal| {
+            // `diff` fits in a `u32`, so each difference does too.
+            let diff = d.exponent.abs_diff(exponent) as u32;
+            &d.mantissa * BigInt::from(10).pow(diff)
+        };
+        Ok((scale(self), scale(other), exponent))
+    }
+
+    pub fn checked_add(&self, rhs: &Decimal) -> Result<Decimal, ManyError> {
+        let (a, b, exponent) = self.align(rhs)?;
+        Ok(Decimal::new(a + b, exponent))
+    }
+
+    pub fn checked_sub(&self, rhs: &Decimal) -> Result<Decimal, ManyError> {
+        let (a, b, exponent) = self.align(rhs)?;
+        Ok(Decimal::new(a - b, exponent))
+    }
+}
+
+impl From<i64> for Decimal {
+    fn from(value: i64) -> Self {
+        Self::new(value, 0)
+    }
+}
+
+impl From<BigInt> for Decimal {
+    fn from(value: BigInt) -> Self {
+        Self::new(value, 0)
+    }
+}
+
+impl TryFrom<Decimal> for BigInt {
+    type Error = String;
+
+    fn try_from(value: Decimal) -> Result<Self, Self::Error> {
+        let value = value.normalize();
+        if value.exponent < 0 {
+            return Err("Decimals with a fractional part can't be converted to BigInt".into());
+        }
+        if value.exponent > Decimal::MAX_EXPONENT {
+            return Err(format!("Decimal exponent out of range: {}", value.exponent));
+        }
+        Ok(value.mantissa * BigInt::from(10).pow(value.exponent as u32))
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    /// Compares the signs and the magnitudes first, so the mantissas are only
+    /// scaled by about as many digits as they already have.
+    fn cmp(&self, other: &Self) -> Ordering {
+        let sign = self.mantissa.sign().cmp(&other.mantissa.sign());
+        if sign != Ordering::Equal || self.is_zero() {
+            return sign;
+        }
+        let (a, b) = (self.magnitude(), other.magnitude());
+        if a.abs_diff(b) > 1 {
+            return match self.mantissa.sign() {
+                Sign::Minus => b.cmp(&a),
+                _ => a.cmp(&b),
+            };
+        }
+        let exponent = self.exponent.min(other.exponent);
+        let scale = |d: &Decimal| {
+            // The magnitudes are close, so the difference is at most a few
+            // more than the number of digits of the other mantissa.
+            let diff = u32::try_from(d.exponent.abs_diff(exponent)).unwrap_or(u32::MAX);
+            &d.mantissa * BigInt::from(10).pow(diff)
+        };
+        scale(self).cmp(&scale(other))
+    }
+}
+
+impl Display for Decimal {
+    /// Decimals with an exponent out of range are written in scientific
+    /// notation, e.g. `15e2000`, instead of with thousands of digits.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if !Self::exponent_in_range(self.exponent) {
+            return write!(f, "{}e{}", self.mantissa, self.exponent);
+        }
+        if self.exponent >= 0 {
+            if self.is_zero() {
+                return write!(f, "0");
+            }
+            let zeroes = "0".repeat(self.exponent as usize);
+            return write!(f, "{}{zeroes}", self.mantissa);
+        }
+
+        let digits = self.mantissa.magnitude().to_string();
+        let scale = self.exponent.unsigned_abs() as usize;
+        let digits = format!("{digits:0>width$}", width = scale + 1);
+        let (int, frac) = digits.split_at(digits.len() - scale);
+        let sign = if self.mantissa.sign() == Sign::Minus {
+            "-"
+        } else {
+            ""
+        };
+        write!(f, "{sign}{int}.{frac}")
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (int, frac) = s.split_once('.').unwrap_or((s, ""));
+        let digits = format!("{int}{frac}");
+        let mantissa = BigInt::from_str(&digits).map_err(|e| format!("Invalid decimal: {e}"))?;
+        let exponent = -i64::try_from(frac.len()).map_err(|e| e.to_string())?;
+        if !Self::exponent_in_range(exponent) {
+            return Err(format!("Decimal exponent out of range: {exponent}"));
+        }
+        Ok(Self::new(mantissa, exponent))
+    }
+}
+
+impl<C> Encode<C> for Decimal {
+    fn encode<W: minicbor::encode::Write>(
+        &self,
+        e: &mut Encoder<W>,
+        _: &mut C,
+    ) -> Result<(), minicbor::encode::Error<W::Error>> {
+        e.tag(Tag::Decimal)?.array(2)?.i64(self.exponent)?;
+        match i64::try_from(&self.mantissa) {
+            Ok(m) => e.i64(m)?,
+            Err(_) => {
+                let (sign, bytes) = self.mantissa.to_bytes_be();
+                if sign == Sign::Minus {
+                    // Negative bignums are encoded as `-1 - n`.
+                    let n: BigInt = -&self.mantissa - 1;
+                    e.tag(Tag::NegBignum)?.bytes(&n.to_bytes_be().1)?
+                } else {
+                    e.tag(Tag::PosBignum)?.bytes(&bytes)?
+                }
+            }
+        };
+        Ok(())
+    }
+}
+
+impl<'b, C> Decode<'b, C> for Decimal {
+    fn decode(d: &mut Decoder<'b>, _: &mut C) -> Result<Self, minicbor::decode::Error> {
+        if d.tag()? != Tag::Decimal {
+            return Err(minicbor::decode::Error::message(
+                "Expected a decimal fraction.",
+            ));
+        }
+        if d.array()? != Some(2) {
+            return Err(minicbor::decode::Error::message(
+                "Decimal fractions must be an array of 2 elements.",
+            ));
+        }
+        let exponent = d.i64()?;
+        if !Decimal::exponent_in_range(exponent) {
+            return Err(minicbor::decode::Error::message(
+                "Decimal exponent out of range.",
+            ));
+        }
+        let mantissa = match d.datatype()? {
+            Type::Tag => match d.tag()? {
+                Tag::PosBignum => BigInt::from_bytes_be(Sign::Plus, d.bytes()?),
+                Tag::NegBignum => -BigInt::from_bytes_be(Sign::Plus, d.bytes()?) - 1,
+                _ => return Err(minicbor::decode::Error::message("Invalid mantissa tag.")),
+            },
+            _ => d.i64()?.into(),
+        };
+        Ok(Self::new(mantissa, exponent))
+    }
+}
+
 fn decode_bigint<C>(
     d: &mut minicbor::Decoder<'_>,
     _: &mut C,
@@ -9,7 +9,7 @@ mod send;
 
 pub use send::*;
 
-#[many_module(name = LedgerCommandsModule, id = 6, namespace = ledger, many_modules_crate = crate)]
+#[many_module(name = LedgerCommandsModule, id = 6, namespace = ledger, many_modules_crate = crate, mock_client = true)]
 #[cfg_attr(test, automock)]
 pub trait LedgerCommandsModuleBackend: Send {
     fn send(&mut self, sender: &Address, args: SendArgs) -> Result<SendReturns, ManyError>;
@@ -37,6 +37,7 @@ mod tests {
             symbol: Address::from_str("mqbfbahksdwaqeenayy2gxke32hgb7aq4ao4wt745lsfs6wiaaaaqnz")
                 .unwrap(),
             memo: None,
+            reference: None,
         };
         let mut mock = MockLedgerCommandsModuleBackend::new();
         mock.expect_send()
@@ -50,4 +51,26 @@ mod tests {
         )
         .unwrap();
     }
+
+    #[test]
+    fn mock_client() {
+        let data = SendArgs {
+            from: None,
+            to: identity(2),
+            amount: TokenAmount::from(512u16),
+            symbol: Address::from_str("mqbfbahksdwaqeenayy2gxke32hgb7aq4ao4wt745lsfs6wiaaaaqnz")
+                .unwrap(),
+            memo: None,
+            reference: None,
+        };
+        let mut mock = MockLedgerCommandsModuleBackend::new();
+        mock.expect_send()
+            .with(predicate::eq(identity(1)), predicate::eq(data.clone()))
+            .times(1)
+            .returning(|_, _| Ok(SendReturns {}));
+        let client = LedgerCommandsModuleMockClient::new(Arc::new(Mutex::new(mock)))
+            .with_sender(identity(1));
+
+        let _: SendReturns = smol::block_on(client.send(data)).unwrap();
+    }
 }
@@ -22,8 +22,16 @@ pub struct SendArgs {
 
     #[n(4)]
     pub memo: Option<Memo>,
+
+    /// An identifier chosen by the sender, e.g. an invoice number, to
+    /// reconcile the payment. It is recorded as is in the event.
+    #[n(5)]
+    pub reference: Option<String>,
 }
 
+/// The maximum length of a reference, in bytes.
+pub const MAX_REFERENCE_LENGTH: usize = 256;
+
 pub type SendReturns = EmptyReturn;
 
 impl AddressContainer for SendArgs {
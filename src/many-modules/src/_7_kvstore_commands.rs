@@ -6,11 +6,13 @@ use many_macros::many_module;
 use mockall::{automock, predicate::*};
 
 mod disable;
+mod enable;
 mod put;
 pub use disable::*;
+pub use enable::*;
 pub use put::*;
 
-#[many_module(name = KvStoreCommandsModule, id = 7, namespace = kvstore, many_modules_crate = crate)]
+#[many_module(name = KvStoreCommandsModule, id = 7, namespace = kvstore, many_modules_crate = crate, mock_client = true)]
 #[cfg_attr(test, automock)]
 pub trait KvStoreCommandsModuleBackend: Send {
     #[many(deny_anonymous)]
@@ -18,6 +20,10 @@ pub trait KvStoreCommandsModuleBackend: Send {
 
     #[many(deny_anonymous)]
     fn disable(&mut self, sender: &Address, args: DisableArgs) -> Result<DisableReturn, ManyError>;
+
+    /// Enable a key that was disabled by its owner.
+    #[many(deny_anonymous)]
+    fn enable(&mut self, sender: &Address, args: EnableArgs) -> Result<EnableReturn, ManyError>;
 }
 
 #[cfg(test)]
@@ -35,6 +41,7 @@ mod tests {
             key: ByteVec::from(vec![1]),
             value: ByteVec::from(vec![2]),
             alternative_owner: None,
+            roles: None,
         };
 
         let mut mock = MockKvStoreCommandsModuleBackend::new();
@@ -56,6 +63,7 @@ mod tests {
             key: ByteVec::from(vec![1]),
             alternative_owner: None,
             reason: None,
+            until: None,
         };
 
         let mut mock = MockKvStoreCommandsModuleBackend::new();
@@ -79,4 +87,30 @@ mod tests {
         )
         .unwrap();
     }
+
+    #[test]
+    fn enable() {
+        let data = EnableArgs {
+            key: ByteVec::from(vec![1]),
+            alternative_owner: None,
+        };
+
+        let mut mock = MockKvStoreCommandsModuleBackend::new();
+        mock.expect_enable()
+            .with(predicate::eq(identity(1)), predicate::eq(data.clone()))
+            .times(1)
+            .returning(|_sender, _args| Ok(EnableReturn {}));
+        let module = super::KvStoreCommandsModule::new(Arc::new(Mutex::new(mock)));
+
+        let _: EnableReturn = minicbor::decode(
+            &call_module_cbor(
+                1,
+                &module,
+                "kvstore.enable",
+                minicbor::to_vec(data).unwrap(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+    }
 }
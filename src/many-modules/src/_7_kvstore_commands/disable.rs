@@ -1,6 +1,7 @@
 use crate::EmptyReturn;
 use many_error::Reason;
 use many_identity::Address;
+use many_types::Timestamp;
 use minicbor::bytes::ByteVec;
 use minicbor::{Decode, Encode};
 
@@ -15,6 +16,10 @@ pub struct DisableArgs {
 
     #[n(2)]
     pub reason: Option<Reason<u64>>,
+
+    /// Disable the key until this time instead of until it is enabled again.
+    #[n(3)]
+    pub until: Option<Timestamp>,
 }
 
 pub type DisableReturn = EmptyReturn;
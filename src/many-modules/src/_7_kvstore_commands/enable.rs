@@ -0,0 +1,16 @@
+use crate::EmptyReturn;
+use many_identity::Address;
+use minicbor::bytes::ByteVec;
+use minicbor::{Decode, Encode};
+
+#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct EnableArgs {
+    #[n(0)]
+    pub key: ByteVec,
+
+    #[n(1)]
+    pub alternative_owner: Option<Address>,
+}
+
+pub type EnableReturn = EmptyReturn;
@@ -1,3 +1,4 @@
+use crate::kvstore::KeyRoles;
 use crate::EmptyReturn;
 use many_identity::Address;
 use minicbor::bytes::ByteVec;
@@ -22,6 +23,12 @@ pub struct PutArgs {
 
     #[n(2)]
     pub alternative_owner: Option<Address>,
+
+    /// Replace the roles on the key, including its owners. Only its owners
+    /// can change them, and a key must keep an owner. The sender of a new
+    /// key is always one of its owners. `None` keeps the current roles.
+    #[n(3)]
+    pub roles: Option<KeyRoles>,
 }
 
 /// Data decoder. Check if the key is less than or equal to the maximum allowed size
@@ -70,6 +77,7 @@ mod tests {
             key: ByteVec::from(vec![1u8; KVSTORE_KEY_MAX_SIZE + 1]),
             value: ByteVec::from(vec![2]),
             alternative_owner: None,
+            roles: None,
         };
 
         let enc = minicbor::to_vec(tx).unwrap();
@@ -87,6 +95,7 @@ mod tests {
             key: ByteVec::from(vec![1]),
             value: ByteVec::from(vec![1u8; KVSTORE_VALUE_MAX_SIZE + 1]),
             alternative_owner: None,
+            roles: None,
         };
 
         let enc = minicbor::to_vec(tx).unwrap();
@@ -12,6 +12,36 @@ use std::str::FromStr;
 pub mod errors;
 pub mod features;
 
+/// Free-form tags of an account, e.g. a cost center or an external ID.
+pub type AccountMetadata = BTreeMap<String, String>;
+
+pub const METADATA_MAX_ENTRIES: usize = 16;
+pub const METADATA_KEY_MAX_LENGTH: usize = 64;
+pub const METADATA_VALUE_MAX_LENGTH: usize = 256;
+
+/// Check that account metadata fits within the limits above. Keys cannot be
+/// empty.
+pub fn validate_metadata(metadata: &AccountMetadata) -> Result<(), ManyError> {
+    if metadata.len() > METADATA_MAX_ENTRIES {
+        return Err(errors::invalid_metadata(format!(
+            "more than {METADATA_MAX_ENTRIES} entries"
+        )));
+    }
+    for (key, value) in metadata {
+        if key.is_empty() || key.len() > METADATA_KEY_MAX_LENGTH {
+            return Err(errors::invalid_metadata(format!(
+                "key {key:?} must be between 1 and {METADATA_KEY_MAX_LENGTH} bytes"
+            )));
+        }
+        if value.len() > METADATA_VALUE_MAX_LENGTH {
+            return Err(errors::invalid_metadata(format!(
+                "value of {key:?} is longer than {METADATA_VALUE_MAX_LENGTH} bytes"
+            )));
+        }
+    }
+    Ok(())
+}
+
 #[derive(
     Copy,
     Clone,
@@ -186,6 +216,9 @@ pub struct Account {
 
     #[n(3)]
     pub disabled: Option<Either<bool, Reason<u64>>>,
+
+    #[n(4)]
+    pub metadata: Option<AccountMetadata>,
 }
 
 impl Account {
@@ -205,6 +238,7 @@ impl Account {
             roles,
             features,
             disabled: None,
+            metadata: None,
         }
     }
 
@@ -220,6 +254,11 @@ impl Account {
         self.description = desc.map(|d| d.to_string());
     }
 
+    /// Replace the metadata of the account. Empty metadata is removed.
+    pub fn set_metadata(&mut self, metadata: AccountMetadata) {
+        self.metadata = (!metadata.is_empty()).then_some(metadata);
+    }
+
     pub fn features(&self) -> &features::FeatureSet {
         &self.features
     }
@@ -329,6 +368,25 @@ impl AddressContainer for SetDescriptionArgs {
 
 pub type SetDescriptionReturn = EmptyReturn;
 
+#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct SetMetadataArgs {
+    #[n(0)]
+    pub account: Address,
+
+    /// The new metadata, replacing the previous one.
+    #[n(1)]
+    pub metadata: AccountMetadata,
+}
+
+impl AddressContainer for SetMetadataArgs {
+    fn addresses(&self) -> BTreeSet<Address> {
+        BTreeSet::from([self.account])
+    }
+}
+
+pub type SetMetadataReturn = EmptyReturn;
+
 #[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
 #[cbor(map)]
 pub struct ListRolesArgs {
@@ -421,6 +479,9 @@ pub struct InfoReturn {
 
     #[n(3)]
     pub disabled: Option<Either<bool, Reason<u64>>>,
+
+    #[n(4)]
+    pub metadata: Option<AccountMetadata>,
 }
 
 #[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
@@ -461,6 +522,75 @@ impl AddressContainer for AddFeaturesArgs {
 
 pub type AddFeaturesReturn = EmptyReturn;
 
+#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct ListSubresourcesArgs {
+    #[n(0)]
+    pub account: Address,
+
+    /// The first subresource ID to list. Defaults to 0.
+    #[n(1)]
+    pub start: Option<u32>,
+
+    #[n(2)]
+    pub count: Option<u64>,
+}
+
+#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct Subresource {
+    #[n(0)]
+    pub address: Address,
+
+    /// The height of the block where the subresource was allocated, if the
+    /// server recorded it.
+    #[n(1)]
+    pub height: Option<u64>,
+}
+
+#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct ListSubresourcesReturn {
+    #[n(0)]
+    pub subresources: Vec<Subresource>,
+
+    /// The ID of the next subresource that will be allocated under the
+    /// account. All IDs below it are allocated.
+    #[n(1)]
+    pub next_subresource: u32,
+}
+
+#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct ListForIdentityArgs {
+    #[n(0)]
+    pub address: Address,
+}
+
+#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct AccountMembership {
+    #[n(0)]
+    pub account: Address,
+
+    #[n(1)]
+    pub description: Option<String>,
+
+    /// The roles of the identity in the account.
+    #[n(2)]
+    pub roles: BTreeSet<Role>,
+
+    #[n(3)]
+    pub features: features::FeatureSet,
+}
+
+#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct ListForIdentityReturn {
+    #[n(0)]
+    pub accounts: Vec<AccountMembership>,
+}
+
 #[many_module(name = AccountModule, id = 9, namespace = account, many_modules_crate = crate)]
 #[cfg_attr(test, mockall::automock)]
 pub trait AccountModuleBackend: Send {
@@ -474,6 +604,13 @@ pub trait AccountModuleBackend: Send {
         args: SetDescriptionArgs,
     ) -> Result<SetDescriptionReturn, ManyError>;
 
+    /// Replace the metadata of an account. Only owners can call this.
+    fn set_metadata(
+        &mut self,
+        sender: &Address,
+        args: SetMetadataArgs,
+    ) -> Result<SetMetadataReturn, ManyError>;
+
     /// List all the roles supported by an account.
     fn list_roles(
         &self,
@@ -521,6 +658,22 @@ pub trait AccountModuleBackend: Send {
         sender: &Address,
         args: AddFeaturesArgs,
     ) -> Result<AddFeaturesReturn, ManyError>;
+
+    /// List the subresource addresses allocated under an address.
+    fn list_subresources(
+        &self,
+        sender: &Address,
+        args: ListSubresourcesArgs,
+        context: Context,
+    ) -> Result<ListSubresourcesReturn, ManyError>;
+
+    /// List the accounts where an address holds any role.
+    fn list_for_identity(
+        &self,
+        sender: &Address,
+        args: ListForIdentityArgs,
+        context: Context,
+    ) -> Result<ListForIdentityReturn, ManyError>;
 }
 
 #[cfg(test)]
@@ -567,6 +720,7 @@ mod module_tests {
                     roles: account.roles.clone(),
                     features: account.features.clone(),
                     disabled: None,
+                    metadata: account.metadata.clone(),
                 })
             }
         });
@@ -693,6 +847,81 @@ mod module_tests {
         let account_map = account_map.read().unwrap();
         assert!(account_map.inner.is_empty());
     }
+
+    #[test]
+    fn list_subresources() {
+        let data = ListSubresourcesArgs {
+            account: identity(0),
+            start: Some(1),
+            count: None,
+        };
+        let ret = ListSubresourcesReturn {
+            subresources: vec![Subresource {
+                address: identity(0).with_subresource_id(1).unwrap(),
+                height: Some(3),
+            }],
+            next_subresource: 2,
+        };
+        let mut mock = MockAccountModuleBackend::new();
+        mock.expect_list_subresources()
+            .with(
+                mockall::predicate::eq(identity(1)),
+                mockall::predicate::eq(data.clone()),
+                mockall::predicate::always(),
+            )
+            .times(1)
+            .return_const(Ok(ret.clone()));
+        let module = super::AccountModule::new(Arc::new(Mutex::new(mock)));
+
+        let result: ListSubresourcesReturn = minicbor::decode(
+            &crate::testutils::call_module_cbor(
+                1,
+                &module,
+                "account.listSubresources",
+                minicbor::to_vec(data).unwrap(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(result, ret);
+    }
+
+    #[test]
+    fn list_for_identity() {
+        let data = ListForIdentityArgs {
+            address: identity(1),
+        };
+        let ret = ListForIdentityReturn {
+            accounts: vec![AccountMembership {
+                account: identity(0).with_subresource_id(1).unwrap(),
+                description: Some("Foobar".to_string()),
+                roles: BTreeSet::from([Role::CanMultisigApprove]),
+                features: features::FeatureSet::empty(),
+            }],
+        };
+        let mut mock = MockAccountModuleBackend::new();
+        mock.expect_list_for_identity()
+            .with(
+                mockall::predicate::eq(identity(2)),
+                mockall::predicate::eq(data.clone()),
+                mockall::predicate::always(),
+            )
+            .times(1)
+            .return_const(Ok(ret.clone()));
+        let module = super::AccountModule::new(Arc::new(Mutex::new(mock)));
+
+        let result: ListForIdentityReturn = minicbor::decode(
+            &crate::testutils::call_module_cbor(
+                2,
+                &module,
+                "account.listForIdentity",
+                minicbor::to_vec(data).unwrap(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(result, ret);
+    }
 }
 
 #[test]
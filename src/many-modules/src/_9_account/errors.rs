@@ -7,5 +7,6 @@ define_attribute_many_error!(
         3: pub fn user_needs_role(role) => "Sender needs role '{role}' to perform this operation.",
         4: pub fn account_must_own_itself() => "Unable to remove owner role from the account itself.",
         5: pub fn empty_feature() => "At least one feature must be selected.",
+        6: pub fn invalid_metadata(reason) => "Invalid account metadata: {reason}.",
     }
 );
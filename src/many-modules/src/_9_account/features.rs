@@ -5,6 +5,7 @@ use many_types::cbor::CborAny;
 use minicbor::{Decode, Encode};
 use std::collections::BTreeSet;
 
+pub mod cosign;
 pub mod kvstore;
 pub mod ledger;
 pub mod multisig;
@@ -0,0 +1,231 @@
+//! Multisig transactions approved by co-signatures instead of approve calls.
+//!
+//! Signers each sign the same encoded [`CosignedTransaction`] and hand their
+//! signature to a submitter, who executes the transaction in a single
+//! `account.cosignExecute` call. Signatures are COSE_Sign1 envelopes with a
+//! detached payload, so they can be verified by the same verifiers as
+//! requests.
+use crate::account::features::{Feature, FeatureId, TryCreateFeature};
+use crate::account::Role;
+use crate::events::AccountMultisigTransaction;
+use coset::{CborSerializable, CoseSign1, CoseSign1Builder};
+use many_error::ManyError;
+use many_identity::{Address, Identity, Verifier};
+use many_macros::many_module;
+use many_protocol::ResponseMessage;
+use many_types::cbor::CborAny;
+use many_types::Timestamp;
+use minicbor::bytes::ByteVec;
+use minicbor::{Decode, Encode};
+use std::collections::{BTreeMap, BTreeSet};
+
+pub mod errors {
+    use many_error::define_attribute_many_error;
+    define_attribute_many_error!(
+        attribute 9 => {
+            110: pub fn invalid_cosignature(reason) => "Invalid co-signature: {reason}.",
+            111: pub fn not_enough_cosignatures(count, threshold) => "Not enough co-signatures, got {count} of {threshold}.",
+            112: pub fn cosigned_transaction_expired() => "This co-signed transaction has expired.",
+            113: pub fn cosigned_transaction_already_executed() => "This co-signed transaction was already executed.",
+        }
+    );
+}
+
+#[derive(Default, Clone, Debug, Encode, Decode)]
+#[cbor(map)]
+pub struct CosignAccountFeatureArg {
+    #[n(0)]
+    pub threshold: Option<u64>,
+}
+
+#[derive(Default)]
+pub struct CosignAccountFeature {
+    pub arg: CosignAccountFeatureArg,
+}
+
+impl CosignAccountFeature {
+    pub fn create(threshold: Option<u64>) -> Self {
+        Self {
+            arg: CosignAccountFeatureArg { threshold },
+        }
+    }
+}
+
+impl TryCreateFeature for CosignAccountFeature {
+    const ID: FeatureId = 4;
+
+    fn try_create(f: &Feature) -> Result<Self, ManyError> {
+        match f.arguments().as_slice() {
+            [CborAny::Map(m)] => {
+                let threshold = m.get(&CborAny::Int(0)).and_then(|v| match v {
+                    CborAny::Int(x) => (*x).try_into().ok(),
+                    _ => None,
+                });
+                Ok(Self::create(threshold))
+            }
+            _ => Err(ManyError::invalid_attribute_arguments()),
+        }
+    }
+}
+
+impl super::FeatureInfo for CosignAccountFeature {
+    fn as_feature(&self) -> Feature {
+        let mut map = BTreeMap::<CborAny, CborAny>::new();
+        if let Some(threshold) = self.arg.threshold {
+            map.insert(CborAny::Int(0), CborAny::Int(threshold as i64));
+        }
+
+        Feature::with_id(Self::ID).with_argument(CborAny::Map(map))
+    }
+
+    fn roles() -> BTreeSet<Role> {
+        BTreeSet::from([Role::CanMultisigSubmit, Role::CanMultisigApprove])
+    }
+}
+
+/// The payload signed by every signer.
+#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct CosignedTransaction {
+    #[n(0)]
+    pub account: Address,
+
+    #[n(1)]
+    pub transaction: Box<AccountMultisigTransaction>,
+
+    /// The transaction cannot be executed after this time. This also bounds
+    /// how long the ledger needs to remember it to prevent replays.
+    #[n(2)]
+    pub timeout: Timestamp,
+}
+
+impl CosignedTransaction {
+    /// Sign the encoded transaction, returning a signature with a detached
+    /// payload.
+    pub fn cosign(identity: &impl Identity, transaction: &[u8]) -> Result<ByteVec, ManyError> {
+        let envelope = CoseSign1Builder::new()
+            .payload(transaction.to_vec())
+            .build();
+        let mut envelope = identity.sign_1(envelope)?;
+        envelope.payload = None;
+        envelope
+            .to_vec()
+            .map(ByteVec::from)
+            .map_err(ManyError::serialization_error)
+    }
+
+    /// Verify a co-signature of the encoded transaction and return the
+    /// address of its signer.
+    pub fn verify(
+        verifier: &(impl Verifier + ?Sized),
+        signature: &[u8],
+        transaction: &[u8],
+    ) -> Result<Address, ManyError> {
+        let mut envelope = CoseSign1::from_slice(signature)
+            .map_err(|e| errors::invalid_cosignature(e.to_string()))?;
+        if envelope.payload.is_some() {
+            return Err(errors::invalid_cosignature("payload is not detached"));
+        }
+        envelope.payload = Some(transaction.to_vec());
+        let address = verifier
+            .verify_1(&envelope)
+            .map_err(|e| errors::invalid_cosignature(e.to_string()))?;
+        if address.is_anonymous() {
+            return Err(errors::invalid_cosignature("signer is anonymous"));
+        }
+        Ok(address)
+    }
+}
+
+#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct ExecuteArgs {
+    /// The encoded [`CosignedTransaction`], as it was signed.
+    #[n(0)]
+    pub transaction: ByteVec,
+
+    #[n(1)]
+    pub signatures: Vec<ByteVec>,
+}
+
+#[many_module(name = AccountCosignModule, namespace = account, many_modules_crate = crate)]
+pub trait AccountCosignModuleBackend: Send {
+    /// Execute a transaction if it has enough co-signatures. The sender
+    /// counts as a signer.
+    fn cosign_execute(
+        &mut self,
+        sender: &Address,
+        args: ExecuteArgs,
+    ) -> Result<ResponseMessage, ManyError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account::features::FeatureInfo;
+    use crate::ledger::SendArgs;
+    use many_identity::testing::identity;
+    use many_identity_dsa::ed25519::generate_random_ed25519_identity;
+    use many_identity_dsa::CoseKeyVerifier;
+    use many_types::ledger::TokenAmount;
+
+    fn transaction() -> Vec<u8> {
+        minicbor::to_vec(CosignedTransaction {
+            account: identity(1),
+            transaction: Box::new(AccountMultisigTransaction::Send(SendArgs {
+                from: Some(identity(1)),
+                to: identity(2),
+                symbol: identity(3),
+                amount: TokenAmount::from(10u64),
+                memo: None,
+                reference: None,
+            })),
+            timeout: Timestamp::new(1000).unwrap(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn feature() {
+        let feature = CosignAccountFeature::create(Some(2)).as_feature();
+        let f = CosignAccountFeature::try_create(&feature).unwrap();
+        assert_eq!(f.arg.threshold, Some(2));
+
+        let feature = CosignAccountFeature::create(None).as_feature();
+        let f = CosignAccountFeature::try_create(&feature).unwrap();
+        assert_eq!(f.arg.threshold, None);
+
+        assert!(CosignAccountFeature::try_create(&Feature::with_id(4)).is_err());
+    }
+
+    #[test]
+    fn cosign_and_verify() {
+        let id = generate_random_ed25519_identity();
+        let transaction = transaction();
+        let signature = CosignedTransaction::cosign(&id, &transaction).unwrap();
+
+        let signer = CosignedTransaction::verify(&CoseKeyVerifier, &signature, &transaction);
+        assert_eq!(signer.unwrap(), id.address());
+    }
+
+    #[test]
+    fn verify_other_transaction() {
+        let id = generate_random_ed25519_identity();
+        let signature = CosignedTransaction::cosign(&id, &transaction()).unwrap();
+
+        let err = CosignedTransaction::verify(&CoseKeyVerifier, &signature, b"other").unwrap_err();
+        assert_eq!(err.code(), errors::invalid_cosignature("").code());
+    }
+
+    #[test]
+    fn verify_attached_payload() {
+        let id = generate_random_ed25519_identity();
+        let transaction = transaction();
+        let envelope = CoseSign1Builder::new().payload(transaction.clone()).build();
+        let signature = id.sign_1(envelope).unwrap().to_vec().unwrap();
+
+        let err =
+            CosignedTransaction::verify(&CoseKeyVerifier, &signature, &transaction).unwrap_err();
+        assert_eq!(err.code(), errors::invalid_cosignature("").code());
+    }
+}
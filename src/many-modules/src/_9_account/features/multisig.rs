@@ -24,10 +24,17 @@ pub mod errors {
             102: pub fn transaction_type_unsupported() => "This transaction is not supported.",
             103: pub fn cannot_execute_transaction() => "This transaction cannot be executed yet.",
             104: pub fn transaction_expired_or_withdrawn() => "This transaction expired or was withdrawn.",
+            105: pub fn transaction_nested_too_deeply(max) => "Transactions cannot be nested more than {max} levels deep.",
+            106: pub fn attachment_not_found(hash) => "The attachment of the transaction is not in the object store: {hash}.",
         }
     );
 }
 
+/// The maximum number of transactions nested in each other, e.g. a submit of
+/// a send is 2 levels deep. Deeper transactions are refused when decoding,
+/// submitting and executing them.
+pub const MULTISIG_TRANSACTION_MAX_DEPTH: usize = 8;
+
 #[derive(Default, Clone, Debug, Encode, Decode)]
 #[cbor(map)]
 pub struct MultisigAccountFeatureArg {
@@ -148,6 +155,9 @@ pub struct SubmitTransactionArgs {
 
     #[n(7)]
     pub memo: Option<Memo>,
+
+    #[n(8)]
+    pub attachment: Option<Attachment>,
 }
 
 impl SubmitTransactionArgs {
@@ -166,6 +176,7 @@ impl SubmitTransactionArgs {
                 symbol,
                 amount,
                 memo,
+                reference: None,
             })),
             threshold: None,
             timeout_in_secs: None,
@@ -173,6 +184,7 @@ impl SubmitTransactionArgs {
             memo_: None,
             data_: None,
             memo: None,
+            attachment: None,
         }
     }
 }
@@ -185,6 +197,24 @@ impl AddressContainer for SubmitTransactionArgs {
     }
 }
 
+/// A document supporting a transaction, e.g. an invoice. Only the hash of the
+/// document is part of the transaction, so anyone can check that the document
+/// wasn't modified without it bloating the event log.
+#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct Attachment {
+    /// The hash of the content of the document, see
+    /// [`object_hash`](crate::store::object_hash).
+    #[n(0)]
+    pub hash: ByteVec,
+
+    /// Where to find the document if it's not in the object store of the
+    /// server. The server cannot check the content of external documents;
+    /// clients must compare their hash.
+    #[n(1)]
+    pub uri: Option<String>,
+}
+
 #[derive(Clone, Debug, Encode, Decode)]
 #[cbor(map)]
 pub struct SubmitTransactionReturn {
@@ -278,6 +308,9 @@ pub struct InfoReturn {
 
     #[n(9)]
     pub memo: Option<Memo>,
+
+    #[n(10)]
+    pub attachment: Option<Attachment>,
 }
 
 #[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
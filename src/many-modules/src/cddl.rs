@@ -0,0 +1,845 @@
+//! CDDL (RFC 8610) definitions of the types declared by the modules.
+//!
+//! The rules are generated by the build script from the minicbor attributes
+//! of the structs and enums, so they always match what is sent on the wire.
+//! Rules are named after the namespace of their module, e.g.
+//! `blockchain.ListArgs`. Types with a hand-written encoding (other than the
+//! ones in [`PRELUDE`]) are `any`, so every rule referenced is defined.
+use crate::base::EndpointDescriptor;
+use many_error::ManyError;
+use many_types::cbor::CborAny;
+use minicbor::data::{Tag, Type};
+use minicbor::Decoder;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+/// A CDDL type expression.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CddlType {
+    Any,
+    Bool,
+    Uint,
+    Int,
+    Float,
+    Text,
+    Bytes,
+    Nil,
+    /// An unsigned integer value, e.g. the index of an enum variant.
+    Literal(u64),
+    Array(&'static CddlType),
+    Map(&'static CddlType, &'static CddlType),
+    Tuple(&'static [CddlType]),
+    Choice(&'static [CddlType]),
+    Tagged(u64, &'static CddlType),
+    Rule(&'static str),
+}
+
+impl CddlType {
+    /// Whether a null value is valid for this type.
+    pub fn is_nullable(&self) -> bool {
+        match self {
+            CddlType::Any | CddlType::Nil => true,
+            CddlType::Choice(choices) => choices.iter().any(CddlType::is_nullable),
+            CddlType::Rule(name) => matches!(
+                rule(name),
+                Some(CddlRule {
+                    definition: CddlDefinition::Type(ty),
+                    ..
+                }) if ty.is_nullable()
+            ),
+            _ => false,
+        }
+    }
+}
+
+impl Display for CddlType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fn join(f: &mut Formatter<'_>, tys: &[CddlType], sep: &str) -> std::fmt::Result {
+            for (i, ty) in tys.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(sep)?;
+                }
+                write!(f, "{ty}")?;
+            }
+            Ok(())
+        }
+
+        match self {
+            CddlType::Any => f.write_str("any"),
+            CddlType::Bool => f.write_str("bool"),
+            CddlType::Uint => f.write_str("uint"),
+            CddlType::Int => f.write_str("int"),
+            CddlType::Float => f.write_str("float"),
+            CddlType::Text => f.write_str("tstr"),
+            CddlType::Bytes => f.write_str("bstr"),
+            CddlType::Nil => f.write_str("nil"),
+            CddlType::Literal(n) => write!(f, "{n}"),
+            CddlType::Array(ty) => write!(f, "[* {ty}]"),
+            CddlType::Map(k, v) => write!(f, "{{ * {k} => {v} }}"),
+            CddlType::Tuple(tys) => {
+                f.write_str("[")?;
+                join(f, tys, ", ")?;
+                f.write_str("]")
+            }
+            CddlType::Choice(tys) => join(f, tys, " / "),
+            CddlType::Tagged(tag, ty) => write!(f, "#6.{tag}({ty})"),
+            CddlType::Rule(name) => f.write_str(name),
+        }
+    }
+}
+
+/// A field of a struct, with its minicbor index.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CddlField {
+    pub index: u64,
+    pub name: &'static str,
+    pub ty: CddlType,
+
+    /// Whether the field can be omitted from the map.
+    pub optional: bool,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CddlDefinition {
+    /// A map keyed by field index (`#[cbor(map)]`).
+    Map(&'static [CddlField]),
+
+    /// An array of fields, ordered by index (the minicbor default).
+    Array(&'static [CddlField]),
+
+    /// A plain type (e.g. `#[cbor(transparent)]`).
+    Type(CddlType),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CddlRule {
+    pub name: &'static str,
+    pub definition: CddlDefinition,
+}
+
+impl Display for CddlRule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.definition {
+            CddlDefinition::Map(fields) => {
+                writeln!(f, "{} = {{", self.name)?;
+                for field in fields {
+                    let optional = if field.optional { "? " } else { "" };
+                    writeln!(
+                        f,
+                        "    {optional}{} => {}, ; {}",
+                        field.index, field.ty, field.name
+                    )?;
+                }
+                f.write_str("}")
+            }
+            CddlDefinition::Array(fields) => {
+                writeln!(f, "{} = [", self.name)?;
+                for field in fields {
+                    writeln!(f, "    {}: {},", field.name, field.ty)?;
+                }
+                f.write_str("]")
+            }
+            CddlDefinition::Type(ty) => write!(f, "{} = {ty}", self.name),
+        }
+    }
+}
+
+/// Rules for the common types of the specification used by the modules.
+pub static PRELUDE: &[CddlRule] = &[
+    CddlRule {
+        name: "address",
+        definition: CddlDefinition::Type(CddlType::Choice(&[
+            CddlType::Tagged(10000, &CddlType::Bytes),
+            CddlType::Text,
+        ])),
+    },
+    CddlRule {
+        name: "time",
+        definition: CddlDefinition::Type(CddlType::Tagged(1, &CddlType::Uint)),
+    },
+    CddlRule {
+        name: "sort-order",
+        definition: CddlDefinition::Type(CddlType::Choice(&[
+            CddlType::Literal(0),
+            CddlType::Literal(1),
+            CddlType::Literal(2),
+        ])),
+    },
+    CddlRule {
+        name: "biguint",
+        definition: CddlDefinition::Type(CddlType::Tagged(2, &CddlType::Bytes)),
+    },
+    CddlRule {
+        name: "ledger-amount",
+        definition: CddlDefinition::Type(CddlType::Choice(&[
+            CddlType::Uint,
+            CddlType::Rule("biguint"),
+        ])),
+    },
+];
+
+/// Rules generated from the module types, in module order.
+pub static RULES: &[CddlRule] = include!(concat!(env!("OUT_DIR"), "/cddl.rs"));
+
+/// Iterate over all the rules, starting with the prelude.
+pub fn rules() -> impl Iterator<Item = &'static CddlRule> {
+    PRELUDE.iter().chain(RULES.iter())
+}
+
+/// Find a rule by name.
+pub fn rule(name: &str) -> Option<&'static CddlRule> {
+    rules().find(|r| r.name == name)
+}
+
+/// Render all the rules as a CDDL document.
+pub fn cddl() -> String {
+    rules()
+        .map(|r| format!("{r}\n"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Find the rule of the argument of an endpoint, if it was generated.
+pub fn argument_rule(descriptor: &EndpointDescriptor) -> Option<&'static CddlRule> {
+    let argument = descriptor.argument.as_deref()?;
+    let name = argument.rsplit("::").next()?;
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return None;
+    }
+    let namespace = descriptor.name.split_once('.').map_or("base", |(ns, _)| ns);
+    rule(&format!("{namespace}.{name}"))
+}
+
+impl CddlRule {
+    /// Validate a CBOR value against this rule. Unlike the minicbor decoders,
+    /// unknown map keys are refused. Errors point at the offending field,
+    /// e.g. `$.symbols[1]`.
+    pub fn validate(&self, bytes: &[u8]) -> Result<(), ManyError> {
+        let mut d = Decoder::new(bytes);
+        validate_definition(&self.definition, &mut d, "$")?;
+        if d.position() != bytes.len() {
+            return Err(ManyError::invalid_argument(
+                "$",
+                "trailing bytes after value",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Fit a value converted from JSON to this rule, so arguments can be
+    /// written without knowing their wire format. Fields can be keyed by
+    /// name, bytes written in hex, integer keys written as strings and tags
+    /// are added where needed. Anything which doesn't fit is left as is for
+    /// [`CddlRule::validate`] to report.
+    pub fn coerce(&self, value: CborAny) -> CborAny {
+        coerce_definition(&self.definition, value)
+    }
+}
+
+fn find_field<'a>(fields: &'a [CddlField], key: &CborAny) -> Option<&'a CddlField> {
+    fields.iter().find(|f| match key {
+        CborAny::Int(i) => f.index as i64 == *i,
+        CborAny::String(s) => f.name == s || f.index.to_string() == *s,
+        _ => false,
+    })
+}
+
+fn coerce_definition(definition: &CddlDefinition, value: CborAny) -> CborAny {
+    match (definition, value) {
+        (CddlDefinition::Type(ty), value) => coerce_type(ty, value),
+        (CddlDefinition::Map(fields), CborAny::Map(m)) => CborAny::Map(
+            m.into_iter()
+                .map(|(k, v)| match find_field(fields, &k) {
+                    Some(f) => (CborAny::Int(f.index as i64), coerce_type(&f.ty, v)),
+                    None => (k, v),
+                })
+                .collect(),
+        ),
+        (CddlDefinition::Array(fields), CborAny::Array(items)) => CborAny::Array(
+            items
+                .into_iter()
+                .enumerate()
+                .map(|(i, v)| match fields.iter().find(|f| f.index == i as u64) {
+                    Some(f) => coerce_type(&f.ty, v),
+                    None => v,
+                })
+                .collect(),
+        ),
+        // An object with every field of an array, by name or index.
+        (CddlDefinition::Array(fields), CborAny::Map(m))
+            if m.keys().all(|k| find_field(fields, k).is_some()) =>
+        {
+            let mut by_index = BTreeMap::new();
+            for (k, v) in m {
+                if let Some(f) = find_field(fields, &k) {
+                    by_index.insert(f.index, coerce_type(&f.ty, v));
+                }
+            }
+            let len = fields.iter().map(|f| f.index + 1).max().unwrap_or(0);
+            CborAny::Array(
+                (0..len)
+                    .map(|i| by_index.remove(&i).unwrap_or(CborAny::Null))
+                    .collect(),
+            )
+        }
+        (_, value) => value,
+    }
+}
+
+fn coerce_type(ty: &CddlType, value: CborAny) -> CborAny {
+    match (ty, value) {
+        (CddlType::Uint | CddlType::Int | CddlType::Literal(_), CborAny::String(s)) => {
+            match s.parse() {
+                Ok(i) => CborAny::Int(i),
+                Err(_) => CborAny::String(s),
+            }
+        }
+        (CddlType::Bytes, CborAny::String(s)) => match hex::decode(&s) {
+            Ok(bytes) => CborAny::Bytes(bytes),
+            Err(_) => CborAny::String(s),
+        },
+        (CddlType::Array(item), CborAny::Array(items)) => {
+            CborAny::Array(items.into_iter().map(|v| coerce_type(item, v)).collect())
+        }
+        (CddlType::Map(key, value), CborAny::Map(m)) => CborAny::Map(
+            m.into_iter()
+                .map(|(k, v)| (coerce_type(key, k), coerce_type(value, v)))
+                .collect(),
+        ),
+        (CddlType::Tuple(tys), CborAny::Array(items)) => CborAny::Array(
+            items
+                .into_iter()
+                .enumerate()
+                .map(|(i, v)| match tys.get(i) {
+                    Some(ty) => coerce_type(ty, v),
+                    None => v,
+                })
+                .collect(),
+        ),
+        // The first choice the value can be made to fit.
+        (CddlType::Choice(choices), value) => choices
+            .iter()
+            .map(|choice| coerce_type(choice, value.clone()))
+            .find(|v| {
+                minicbor::to_vec(v).map_or(false, |bytes| {
+                    validate_type(ty, &mut Decoder::new(&bytes), "$").is_ok()
+                })
+            })
+            .unwrap_or(value),
+        (CddlType::Tagged(_, inner), CborAny::Tagged(t, v)) => {
+            CborAny::Tagged(t, Box::new(coerce_type(inner, *v)))
+        }
+        (CddlType::Tagged(n, inner), value) => {
+            CborAny::Tagged(tag(*n), Box::new(coerce_type(inner, value)))
+        }
+        (CddlType::Rule(name), value) => match rule(name) {
+            Some(rule) => rule.coerce(value),
+            None => value,
+        },
+        (_, value) => value,
+    }
+}
+
+fn malformed(path: &str, err: minicbor::decode::Error) -> ManyError {
+    ManyError::invalid_argument(path.to_string(), err.to_string())
+}
+
+fn mismatch(path: &str, expected: impl Display, actual: Type) -> ManyError {
+    ManyError::invalid_argument(
+        path.to_string(),
+        format!("expected {expected}, got {actual}"),
+    )
+}
+
+/// minicbor does not expose conversions from tag numbers, so decode one.
+fn tag(n: u64) -> Tag {
+    let mut bytes = minicbor::to_vec(n).expect("Encoding a number cannot fail");
+    bytes[0] |= 0xc0;
+    Decoder::new(&bytes)
+        .tag()
+        .expect("Decoding a tag header cannot fail")
+}
+
+/// Calls `f` for every item of an array or a map, definite or not.
+fn for_each_item(
+    d: &mut Decoder,
+    path: &str,
+    len: Option<u64>,
+    mut f: impl FnMut(&mut Decoder, u64) -> Result<(), ManyError>,
+) -> Result<(), ManyError> {
+    match len {
+        Some(len) => (0..len).try_for_each(|i| f(d, i)),
+        None => {
+            let mut i = 0;
+            while d.datatype().map_err(|e| malformed(path, e))? != Type::Break {
+                f(d, i)?;
+                i += 1;
+            }
+            // Skip the break byte.
+            d.set_position(d.position() + 1);
+            Ok(())
+        }
+    }
+}
+
+fn validate_definition(
+    definition: &CddlDefinition,
+    d: &mut Decoder,
+    path: &str,
+) -> Result<(), ManyError> {
+    match definition {
+        CddlDefinition::Type(ty) => validate_type(ty, d, path),
+        CddlDefinition::Map(fields) => {
+            let datatype = d.datatype().map_err(|e| malformed(path, e))?;
+            let len = d.map().map_err(|_| mismatch(path, "map", datatype))?;
+
+            let mut seen = Vec::new();
+            for_each_item(d, path, len, |d, _| {
+                let datatype = d.datatype().map_err(|e| malformed(path, e))?;
+                let index = d.u64().map_err(|_| mismatch(path, "uint key", datatype))?;
+                let field = fields.iter().find(|f| f.index == index).ok_or_else(|| {
+                    ManyError::invalid_argument(path.to_string(), format!("unknown key {index}"))
+                })?;
+                let field_path = format!("{path}.{}", field.name);
+                if seen.contains(&index) {
+                    return Err(ManyError::invalid_argument(field_path, "duplicate key"));
+                }
+                seen.push(index);
+
+                // Optional fields can also be null.
+                if field.optional && d.datatype().map_err(|e| malformed(path, e))? == Type::Null {
+                    return d.skip().map_err(|e| malformed(&field_path, e));
+                }
+                validate_type(&field.ty, d, &field_path)
+            })?;
+
+            match fields
+                .iter()
+                .find(|f| !f.optional && !seen.contains(&f.index))
+            {
+                Some(f) => Err(ManyError::required_field_missing(format!(
+                    "{path}.{}",
+                    f.name
+                ))),
+                None => Ok(()),
+            }
+        }
+        CddlDefinition::Array(fields) => {
+            let datatype = d.datatype().map_err(|e| malformed(path, e))?;
+            let len = d.array().map_err(|_| mismatch(path, "array", datatype))?;
+
+            let mut count = 0;
+            for_each_item(d, path, len, |d, i| {
+                count += 1;
+                match fields.iter().find(|f| f.index == i) {
+                    Some(field) => validate_type(&field.ty, d, &format!("{path}.{}", field.name)),
+                    None => d.skip().map_err(|e| malformed(path, e)),
+                }
+            })?;
+
+            // Trailing fields can be left out if they are nullable.
+            match fields
+                .iter()
+                .find(|f| f.index >= count && !f.ty.is_nullable())
+            {
+                Some(f) => Err(ManyError::required_field_missing(format!(
+                    "{path}.{}",
+                    f.name
+                ))),
+                None => Ok(()),
+            }
+        }
+    }
+}
+
+fn validate_type(ty: &CddlType, d: &mut Decoder, path: &str) -> Result<(), ManyError> {
+    let datatype = d.datatype().map_err(|e| malformed(path, e))?;
+    let mut expect = |ok: bool| {
+        if ok {
+            d.skip().map_err(|e| malformed(path, e))
+        } else {
+            Err(mismatch(path, ty, datatype))
+        }
+    };
+
+    match ty {
+        CddlType::Any => d.skip().map_err(|e| malformed(path, e)),
+        CddlType::Bool => expect(datatype == Type::Bool),
+        CddlType::Uint => expect(matches!(
+            datatype,
+            Type::U8 | Type::U16 | Type::U32 | Type::U64
+        )),
+        CddlType::Int => expect(matches!(
+            datatype,
+            Type::U8
+                | Type::U16
+                | Type::U32
+                | Type::U64
+                | Type::I8
+                | Type::I16
+                | Type::I32
+                | Type::I64
+                | Type::Int
+        )),
+        CddlType::Float => expect(matches!(datatype, Type::F16 | Type::F32 | Type::F64)),
+        CddlType::Text => expect(matches!(datatype, Type::String | Type::StringIndef)),
+        CddlType::Bytes => expect(matches!(datatype, Type::Bytes | Type::BytesIndef)),
+        CddlType::Nil => expect(datatype == Type::Null),
+        CddlType::Literal(n) => match d.u64() {
+            Ok(value) if value == *n => Ok(()),
+            _ => Err(mismatch(path, ty, datatype)),
+        },
+        CddlType::Array(item) => {
+            let len = d.array().map_err(|_| mismatch(path, ty, datatype))?;
+            for_each_item(d, path, len, |d, i| {
+                validate_type(item, d, &format!("{path}[{i}]"))
+            })
+        }
+        CddlType::Map(key, value) => {
+            let len = d.map().map_err(|_| mismatch(path, ty, datatype))?;
+            for_each_item(d, path, len, |d, i| {
+                let item_path = format!("{path}{{{i}}}");
+                validate_type(key, d, &item_path)?;
+                validate_type(value, d, &item_path)
+            })
+        }
+        CddlType::Tuple(items) => {
+            let len = d.array().map_err(|_| mismatch(path, ty, datatype))?;
+            if len != Some(items.len() as u64) {
+                return Err(mismatch(path, ty, datatype));
+            }
+            items
+                .iter()
+                .enumerate()
+                .try_for_each(|(i, item)| validate_type(item, d, &format!("{path}[{i}]")))
+        }
+        CddlType::Choice(choices) => {
+            let start = d.position();
+            let mut deepest = None;
+            for choice in choices.iter() {
+                match validate_type(choice, d, path) {
+                    Ok(()) => return Ok(()),
+                    // Keep the error of a choice that matched the value
+                    // itself but not one of its fields, as it is more precise.
+                    Err(e) if deepest.is_none() && e.argument("field") != Some(path) => {
+                        deepest = Some(e);
+                    }
+                    Err(_) => {}
+                }
+                d.set_position(start);
+            }
+            Err(deepest.unwrap_or_else(|| mismatch(path, ty, datatype)))
+        }
+        CddlType::Tagged(n, inner) => {
+            if datatype != Type::Tag || d.tag().map_err(|e| malformed(path, e))? != tag(*n) {
+                return Err(mismatch(path, ty, datatype));
+            }
+            validate_type(inner, d, path)
+        }
+        CddlType::Rule(name) => match rule(name) {
+            Some(rule) => validate_definition(&rule.definition, d, path),
+            // Rules from the specification are not checked.
+            None => d.skip().map_err(|e| malformed(path, e)),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use many_identity::Address;
+
+    #[test]
+    fn map_rule() {
+        let rule = rule("blockchain.ListArgs").unwrap();
+        assert_eq!(
+            rule.to_string(),
+            "blockchain.ListArgs = {\n    \
+                ? 0 => uint, ; count\n    \
+                ? 1 => sort-order, ; order\n    \
+                ? 2 => any, ; filter\n\
+            }"
+        );
+    }
+
+    #[test]
+    fn bytes_and_prelude() {
+        assert_eq!(
+            rule("blockchain.RequestReturns").unwrap().to_string(),
+            "blockchain.RequestReturns = {\n    0 => bstr, ; request\n}"
+        );
+        assert_eq!(
+            rule("ledger.BalanceArgs").unwrap().to_string(),
+            "ledger.BalanceArgs = {\n    \
+                ? 0 => address, ; account\n    \
+                ? 1 => address / [* address], ; symbols\n    \
+                ? 2 => uint, ; height\n\
+            }"
+        );
+    }
+
+    #[test]
+    fn transparent() {
+        assert_eq!(
+            rule("base.Endpoints").unwrap().to_string(),
+            "base.Endpoints = [* tstr]"
+        );
+    }
+
+    #[test]
+    fn type_decl() {
+        let rule = rule("tokens.TokenInfoArgs").unwrap();
+        assert!(matches!(
+            rule.definition,
+            CddlDefinition::Map([
+                CddlField {
+                    index: 0,
+                    name: "symbol",
+                    ty: CddlType::Rule("address"),
+                    optional: false,
+                },
+                ..
+            ])
+        ));
+    }
+
+    #[test]
+    fn validate() {
+        let rule = rule("ledger.BalanceArgs").unwrap();
+        let args = crate::ledger::BalanceArgs {
+            account: None,
+            symbols: Some(vec![Address::anonymous()].into()),
+            height: None,
+        };
+        assert!(rule.validate(&minicbor::to_vec(args).unwrap()).is_ok());
+
+        let mut e = minicbor::Encoder::new(vec![]);
+        e.map(1)
+            .unwrap()
+            .u8(1)
+            .unwrap()
+            .array(2)
+            .unwrap()
+            .encode(Address::anonymous())
+            .unwrap()
+            .u8(5)
+            .unwrap();
+        let err = rule.validate(&e.into_writer()).unwrap_err();
+        assert_eq!(err.argument("field"), Some("$.symbols[1]"));
+
+        let err = rule.validate(&minicbor::to_vec(1u8).unwrap()).unwrap_err();
+        assert_eq!(err.argument("field"), Some("$"));
+        assert_eq!(err.argument("details"), Some("expected map, got u8"));
+    }
+
+    #[test]
+    fn coerce() {
+        let json = |s: &str| -> CborAny { serde_json::from_str(s).unwrap() };
+
+        let balance = rule("ledger.BalanceArgs").unwrap();
+        let value = balance.coerce(json(&format!(
+            r#"{{ "account": "{0}", "1": ["{0}"] }}"#,
+            Address::anonymous()
+        )));
+        let bytes = minicbor::to_vec(&value).unwrap();
+        assert!(balance.validate(&bytes).is_ok());
+        let args: crate::ledger::BalanceArgs = minicbor::decode(&bytes).unwrap();
+        assert_eq!(args.account, Some(Address::anonymous()));
+
+        // Bytes in hex.
+        let value = rule("blockchain.RequestReturns")
+            .unwrap()
+            .coerce(json(r#"{ "request": "0102" }"#));
+        assert_eq!(
+            value,
+            CborAny::Map(BTreeMap::from([(
+                CborAny::Int(0),
+                CborAny::Bytes(vec![1, 2])
+            )]))
+        );
+
+        // Unknown fields are left for the validation to refuse.
+        let value = balance.coerce(json(r#"{ "foo": 1 }"#));
+        assert!(balance.validate(&minicbor::to_vec(value).unwrap()).is_err());
+    }
+
+    #[test]
+    fn enums() {
+        assert_eq!(
+            rule("kvstore.KeyRole").unwrap().to_string(),
+            "kvstore.KeyRole = 0 / 1 / 2"
+        );
+        assert_eq!(
+            rule("data.DataType").unwrap().to_string(),
+            "data.DataType = [0, []] / [1, []]"
+        );
+
+        let rule = rule("data.DataValue").unwrap();
+        let value = crate::data::DataValue::Counter(5);
+        assert!(rule.validate(&minicbor::to_vec(value).unwrap()).is_ok());
+        let value = crate::data::DataValue::Gauge(crate::data::DataValueTypeGauge::Int(-1));
+        assert!(rule.validate(&minicbor::to_vec(value).unwrap()).is_ok());
+        assert!(rule.validate(&minicbor::to_vec(5u8).unwrap()).is_err());
+    }
+
+    /// A parser of the subset of CDDL used by the rules.
+    struct Parser<'a> {
+        tokens: Vec<&'a str>,
+        pos: usize,
+        references: Vec<&'a str>,
+    }
+
+    impl<'a> Parser<'a> {
+        fn new(document: &'a str) -> Self {
+            let mut tokens = Vec::new();
+            for line in document.lines() {
+                let line = line.split(';').next().unwrap();
+                let mut rest = line.trim_start();
+                while !rest.is_empty() {
+                    let len = if rest.starts_with("=>") {
+                        2
+                    } else if rest.starts_with(|c: char| "=/{}[](),?*:".contains(c)) {
+                        1
+                    } else {
+                        rest.find(|c: char| c.is_whitespace() || "=/{}[](),?*:".contains(c))
+                            .unwrap_or(rest.len())
+                    };
+                    tokens.push(&rest[..len]);
+                    rest = rest[len..].trim_start();
+                }
+            }
+            Self {
+                tokens,
+                pos: 0,
+                references: Vec::new(),
+            }
+        }
+
+        fn peek(&self) -> Option<&'a str> {
+            self.tokens.get(self.pos).copied()
+        }
+
+        fn next(&mut self) -> Result<&'a str, String> {
+            let token = self.peek().ok_or("unexpected end")?;
+            self.pos += 1;
+            Ok(token)
+        }
+
+        fn expect(&mut self, expected: &str) -> Result<(), String> {
+            match self.next()? {
+                token if token == expected => Ok(()),
+                token => Err(format!("expected {expected:?}, got {token:?}")),
+            }
+        }
+
+        fn name(&mut self) -> Result<&'a str, String> {
+            let name = self.next()?;
+            let valid = name.starts_with(|c: char| c.is_ascii_alphabetic())
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c));
+            if valid {
+                Ok(name)
+            } else {
+                Err(format!("invalid name {name:?}"))
+            }
+        }
+
+        /// Parse the rules of the document and return their names.
+        fn document(&mut self) -> Result<Vec<&'a str>, String> {
+            let mut names = Vec::new();
+            while self.peek().is_some() {
+                names.push(self.name()?);
+                self.expect("=")?;
+                self.ty()?;
+            }
+            Ok(names)
+        }
+
+        fn ty(&mut self) -> Result<(), String> {
+            self.ty1()?;
+            while self.peek() == Some("/") {
+                self.next()?;
+                self.ty1()?;
+            }
+            Ok(())
+        }
+
+        fn ty1(&mut self) -> Result<(), String> {
+            match self.peek().ok_or("unexpected end")? {
+                "[" => {
+                    self.next()?;
+                    self.group("]")
+                }
+                "{" => {
+                    self.next()?;
+                    self.group("}")
+                }
+                token if token.starts_with("#6.") => {
+                    self.next()?;
+                    token[3..].parse::<u64>().map_err(|e| e.to_string())?;
+                    self.expect("(")?;
+                    self.ty()?;
+                    self.expect(")")
+                }
+                token if token.parse::<u64>().is_ok() => self.next().map(|_| ()),
+                _ => {
+                    let name = self.name()?;
+                    self.references.push(name);
+                    Ok(())
+                }
+            }
+        }
+
+        fn group(&mut self, close: &str) -> Result<(), String> {
+            while self.peek() != Some(close) {
+                if matches!(self.peek(), Some("?" | "*")) {
+                    self.next()?;
+                }
+                if self.tokens.get(self.pos + 1) == Some(&":") {
+                    // A `name: type` member.
+                    self.next()?;
+                    self.next()?;
+                    self.ty()?;
+                } else {
+                    self.ty()?;
+                    // A `key => type` member.
+                    if self.peek() == Some("=>") {
+                        self.next()?;
+                        self.ty()?;
+                    }
+                }
+                match self.peek() {
+                    Some(",") => {
+                        self.next()?;
+                    }
+                    Some(token) if token == close => {}
+                    token => return Err(format!("expected \",\" or {close:?}, got {token:?}")),
+                }
+            }
+            self.expect(close)
+        }
+    }
+
+    #[test]
+    fn document_parses() {
+        let document = cddl();
+        let mut parser = Parser::new(&document);
+        let names = parser.document().unwrap();
+        for name in &parser.references {
+            let standard = ["any", "bool", "uint", "int", "float", "tstr", "bstr", "nil"];
+            assert!(
+                standard.contains(name) || names.contains(name),
+                "Undefined rule {name}"
+            );
+        }
+    }
+
+    #[test]
+    fn unique_names() {
+        let mut names = std::collections::BTreeSet::new();
+        for r in rules() {
+            assert!(names.insert(r.name), "Duplicate rule {}", r.name);
+        }
+    }
+}
@@ -3,10 +3,13 @@
 
 use async_trait::async_trait;
 use many_error::ManyError;
+use many_identity::Address;
 use many_protocol::{RequestMessage, ResponseMessage};
 use many_types::attributes::Attribute;
+use many_types::BlockTime;
 use minicbor::encode::{Error, Write};
 use minicbor::{Decoder, Encoder};
+use std::collections::BTreeSet;
 use std::fmt::Debug;
 
 macro_rules! reexport_module {
@@ -34,11 +37,20 @@ reexport_module!(
     account: _9_account;
     compute: _15_compute;
     web: _16_web + _17_web_commands;
+    random: _18_random;
+    names: _19_names;
+    attest: _20_attest;
+    relay: _21_relay;
+    admin: _22_admin;
+    store: _23_store;
+    compliance: _24_compliance;
     abci_backend: _1000_abci_backend;
     abci_frontend: _1001_abci_frontend;
     idstore: _1002_idstore;
 );
 
+pub mod cddl;
+
 /// The specification says that some methods returns nothing (e.g. void or unit).
 /// Empty returns are empty semantically (unit type), but we don't want to break CBOR
 /// decoders so we use a null value instead.
@@ -91,6 +103,47 @@ pub struct ManyModuleInfo {
 
     /// The endpoints that this module exports.
     pub endpoints: Vec<String>,
+
+    /// The description of each endpoint exported by this module, in the same
+    /// order as `endpoints`.
+    pub descriptors: Vec<base::EndpointDescriptor>,
+}
+
+impl ManyModuleInfo {
+    /// The deprecation of an endpoint of this module, if it is deprecated.
+    pub fn deprecation(&self, endpoint: &str) -> Option<&base::Deprecation> {
+        self.descriptors
+            .iter()
+            .find(|d| d.name == endpoint)
+            .and_then(|d| d.deprecated.as_ref())
+    }
+
+    /// Mark an endpoint of this module deprecated, for modules which build
+    /// their info by hand.
+    pub fn deprecate(mut self, endpoint: &str, deprecation: base::Deprecation) -> Self {
+        if let Some(descriptor) = self.descriptors.iter_mut().find(|d| d.name == endpoint) {
+            descriptor.deprecated = Some(deprecation);
+        }
+        self
+    }
+
+    /// The keys of the argument of an endpoint which must not be logged. An
+    /// empty slice means the whole argument is sensitive.
+    pub fn redacted(&self, endpoint: &str) -> Option<&[u64]> {
+        self.descriptors
+            .iter()
+            .find(|d| d.name == endpoint)
+            .and_then(|d| d.redacted.as_deref())
+    }
+
+    /// Mark keys of the argument of an endpoint as sensitive, for modules
+    /// which build their info by hand. See [ManyModuleInfo::redacted].
+    pub fn redact(mut self, endpoint: &str, keys: Vec<u64>) -> Self {
+        if let Some(descriptor) = self.descriptors.iter_mut().find(|d| d.name == endpoint) {
+            descriptor.redacted = Some(keys);
+        }
+        self
+    }
 }
 
 /// A module ran by an many-server server.
@@ -110,8 +163,40 @@ pub trait ManyModule: Sync + Send + Debug {
         Ok(())
     }
 
+    /// Verify a message sent in a multi-signer envelope, once the signatures
+    /// of all its signers were verified. Modules reject these messages unless
+    /// they implement this.
+    fn validate_multi(
+        &self,
+        message: &RequestMessage,
+        _envelope: &coset::CoseSign,
+        _signers: &BTreeSet<Address>,
+    ) -> Result<(), ManyError> {
+        Err(ManyError::multi_signer_not_supported(
+            message.method.clone(),
+        ))
+    }
+
     /// Execute a message and returns its response.
     async fn execute(&self, message: RequestMessage) -> Result<ResponseMessage, ManyError>;
+
+    /// Execute a message in a block of the given time, passed to endpoints
+    /// through their [many_protocol::context::Context]. Servers call this
+    /// instead of [ManyModule::execute] so backends don't need the wall clock.
+    async fn execute_with_block_time(
+        &self,
+        message: RequestMessage,
+        _block_time: Option<BlockTime>,
+    ) -> Result<ResponseMessage, ManyError> {
+        self.execute(message).await
+    }
+
+    /// Report whether this module can do its work, e.g. that the background
+    /// tasks it relies on are still running. Servers call this on
+    /// `base.heartbeat`.
+    fn heartbeat(&self) -> Result<(), ManyError> {
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,14 @@
+//! Building and verifying the merkle proofs sent in the [PROOF](many_types::PROOF)
+//! attribute of responses.
+
+mod multiproof;
+mod prover;
+mod tree;
+mod verifier;
+
+pub use multiproof::merge;
+pub use verifier::{execute, verify, Hash, KeyValues, NodeHasher, HASH_LENGTH, NULL_HASH};
+
+// Used by the prover macros.
+#[doc(hidden)]
+pub use {many_error::ManyError, many_types::ProofOperation};
@@ -0,0 +1,114 @@
+use crate::tree::Tree;
+use many_error::ManyError;
+use many_types::ProofOperation;
+
+/// Merge proofs of different keys of the same tree into a single proof, where
+/// the nodes shared between the proofs are only included once. The proofs must
+/// have been made from the same root; the merged proof still needs to be
+/// verified against it.
+pub fn merge(
+    proofs: impl IntoIterator<Item = Vec<ProofOperation>>,
+) -> Result<Vec<ProofOperation>, ManyError> {
+    let mut tree: Option<Tree> = None;
+    for proof in proofs {
+        let other = Tree::from_operations(&proof)?;
+        tree = Some(match tree {
+            Some(tree) => tree.merge(other)?,
+            None => other,
+        });
+    }
+
+    let mut operations = Vec::new();
+    if let Some(tree) = tree {
+        tree.to_operations(&mut operations);
+    }
+    Ok(operations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verifier::tests::{kv, leaf, root, TestHasher};
+    use crate::verifier::{verify, NodeHasher};
+    use many_error::ManyErrorCode;
+    use std::collections::BTreeMap;
+    use ProofOperation::{Child, KeyValueHash, NodeHash, Parent};
+
+    fn proof_of_a() -> Vec<ProofOperation> {
+        vec![
+            kv("a", "1"),
+            KeyValueHash(TestHasher.kv_hash(b"b", b"2").to_vec()),
+            Parent,
+            NodeHash(leaf("c", "3").to_vec()),
+            Child,
+        ]
+    }
+
+    fn proof_of_c() -> Vec<ProofOperation> {
+        vec![
+            NodeHash(leaf("a", "1").to_vec()),
+            KeyValueHash(TestHasher.kv_hash(b"b", b"2").to_vec()),
+            Parent,
+            kv("c", "3"),
+            Child,
+        ]
+    }
+
+    #[test]
+    fn shared_nodes() {
+        let merged = merge([proof_of_a(), proof_of_c()]).unwrap();
+        assert_eq!(
+            merged,
+            vec![
+                kv("a", "1"),
+                KeyValueHash(TestHasher.kv_hash(b"b", b"2").to_vec()),
+                Parent,
+                kv("c", "3"),
+                Child,
+            ]
+        );
+
+        let pairs = verify(&merged, &root(), &TestHasher).unwrap();
+        assert_eq!(
+            pairs,
+            BTreeMap::from([
+                (b"a".to_vec(), b"1".to_vec()),
+                (b"c".to_vec(), b"3".to_vec())
+            ])
+        );
+    }
+
+    #[test]
+    fn same_proof() {
+        assert_eq!(merge([proof_of_a(), proof_of_a()]).unwrap(), proof_of_a());
+        assert_eq!(merge([proof_of_c()]).unwrap(), proof_of_c());
+        assert!(merge([]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn key_value_over_hash() {
+        let full = vec![kv("a", "1"), kv("b", "2"), Parent, kv("c", "3"), Child];
+        assert_eq!(merge([proof_of_a(), full.clone()]).unwrap(), full);
+    }
+
+    #[test]
+    fn conflicts() {
+        let other_root = vec![
+            NodeHash(leaf("a", "2").to_vec()),
+            KeyValueHash(TestHasher.kv_hash(b"b", b"2").to_vec()),
+            Parent,
+            kv("c", "3"),
+            Child,
+        ];
+        let err = merge([proof_of_c(), other_root]).unwrap_err();
+        assert_eq!(err.code(), ManyErrorCode::InvalidProof);
+
+        let other_shape = vec![kv("a", "1")];
+        let err = merge([proof_of_a(), other_shape]).unwrap_err();
+        assert_eq!(err.code(), ManyErrorCode::InvalidProof);
+
+        let other_value = vec![kv("a", "2"), kv("b", "2"), Parent, kv("c", "3"), Child];
+        let err = merge([proof_of_a(), other_value]).unwrap_err();
+        assert_eq!(err.code(), ManyErrorCode::InvalidProof);
+    }
+}
@@ -0,0 +1,38 @@
+// The ledger and the key-value store depend on different versions of merk, so
+// the prover helpers are macros using the `merk` crate of the caller.
+
+/// Convert the bytes of a merk proof into proof operations.
+#[macro_export]
+macro_rules! merk_operations {
+    ($proof:expr) => {
+        ::merk::proofs::Decoder::new($proof)
+            .map(|fallible_operation| {
+                fallible_operation.map(|operation| match operation {
+                    ::merk::proofs::Op::Child => $crate::ProofOperation::Child,
+                    ::merk::proofs::Op::Parent => $crate::ProofOperation::Parent,
+                    ::merk::proofs::Op::Push(::merk::proofs::Node::Hash(hash)) => {
+                        $crate::ProofOperation::NodeHash(hash.to_vec())
+                    }
+                    ::merk::proofs::Op::Push(::merk::proofs::Node::KV(key, value)) => {
+                        $crate::ProofOperation::KeyValuePair(key.into(), value.into())
+                    }
+                    ::merk::proofs::Op::Push(::merk::proofs::Node::KVHash(hash)) => {
+                        $crate::ProofOperation::KeyValueHash(hash.to_vec())
+                    }
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|error| $crate::ManyError::unknown(error.to_string()))
+    };
+}
+
+/// Prove a query on a merk store, returning the proof operations.
+#[macro_export]
+macro_rules! merk_prove {
+    ($store:expr, $query:expr) => {
+        $store
+            .prove($query)
+            .map_err(|error| $crate::ManyError::unknown(error.to_string()))
+            .and_then(|proof| $crate::merk_operations!(proof.as_slice()))
+    };
+}
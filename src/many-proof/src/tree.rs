@@ -0,0 +1,220 @@
+use crate::verifier::{Hash, KeyValues, NodeHasher, HASH_LENGTH, NULL_HASH};
+use many_error::ManyError;
+use many_types::ProofOperation;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Node {
+    /// The hash of a whole subtree.
+    Hash(Hash),
+    KvHash(Hash),
+    Kv(Vec<u8>, Vec<u8>),
+}
+
+/// The part of a merkle tree contained in a proof.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct Tree {
+    node: Node,
+    left: Option<Box<Tree>>,
+    right: Option<Box<Tree>>,
+}
+
+fn to_hash(bytes: &[u8]) -> Result<Hash, ManyError> {
+    bytes.try_into().map_err(|_| {
+        ManyError::invalid_proof(format!(
+            "expected a hash of {HASH_LENGTH} bytes, got {}",
+            bytes.len()
+        ))
+    })
+}
+
+fn pop(stack: &mut Vec<Tree>) -> Result<Tree, ManyError> {
+    stack
+        .pop()
+        .ok_or_else(|| ManyError::invalid_proof("not enough nodes on the stack"))
+}
+
+/// Merge two optional children at the same position of the tree.
+fn merge_child(a: Option<Box<Tree>>, b: Option<Box<Tree>>) -> Result<Option<Box<Tree>>, ManyError> {
+    match (a, b) {
+        (None, None) => Ok(None),
+        (Some(a), Some(b)) => Ok(Some(Box::new(a.merge(*b)?))),
+        _ => Err(ManyError::invalid_proof("proofs have different shapes")),
+    }
+}
+
+impl Tree {
+    fn new(node: Node) -> Self {
+        Self {
+            node,
+            left: None,
+            right: None,
+        }
+    }
+
+    /// Run the operations of a proof to rebuild its tree.
+    pub fn from_operations(operations: &[ProofOperation]) -> Result<Self, ManyError> {
+        let mut stack: Vec<Tree> = Vec::new();
+        let mut last_key: Option<Vec<u8>> = None;
+
+        for operation in operations {
+            match operation {
+                ProofOperation::Parent => {
+                    let mut parent = pop(&mut stack)?;
+                    let child = pop(&mut stack)?;
+                    parent.attach(true, child)?;
+                    stack.push(parent);
+                }
+                ProofOperation::Child => {
+                    let child = pop(&mut stack)?;
+                    let mut parent = pop(&mut stack)?;
+                    parent.attach(false, child)?;
+                    stack.push(parent);
+                }
+                ProofOperation::NodeHash(hash) => stack.push(Tree::new(Node::Hash(to_hash(hash)?))),
+                ProofOperation::KeyValueHash(hash) => {
+                    stack.push(Tree::new(Node::KvHash(to_hash(hash)?)))
+                }
+                ProofOperation::KeyValuePair(key, value) => {
+                    let key: Vec<u8> = key.clone().into();
+                    if last_key.as_ref().map_or(false, |last| &key <= last) {
+                        return Err(ManyError::invalid_proof("keys are not in ascending order"));
+                    }
+                    last_key = Some(key.clone());
+                    stack.push(Tree::new(Node::Kv(key, value.clone().into())));
+                }
+            }
+        }
+
+        match stack.len() {
+            1 => pop(&mut stack),
+            0 => Err(ManyError::invalid_proof("empty proof")),
+            n => Err(ManyError::invalid_proof(format!(
+                "expected a single root, got {n} nodes"
+            ))),
+        }
+    }
+
+    fn attach(&mut self, left: bool, child: Tree) -> Result<(), ManyError> {
+        if let Node::Hash(_) = self.node {
+            return Err(ManyError::invalid_proof(
+                "cannot attach a child to a subtree hash",
+            ));
+        }
+        let slot = if left {
+            &mut self.left
+        } else {
+            &mut self.right
+        };
+        if slot.is_some() {
+            return Err(ManyError::invalid_proof("child attached twice"));
+        }
+        *slot = Some(Box::new(child));
+        Ok(())
+    }
+
+    pub fn hash(&self, hasher: &impl NodeHasher) -> Hash {
+        let kv_hash = match &self.node {
+            Node::Hash(hash) => return *hash,
+            Node::KvHash(kv_hash) => *kv_hash,
+            Node::Kv(key, value) => hasher.kv_hash(key, value),
+        };
+        let child_hash =
+            |child: &Option<Box<Tree>>| child.as_ref().map_or(NULL_HASH, |c| c.hash(hasher));
+        hasher.node_hash(&kv_hash, &child_hash(&self.left), &child_hash(&self.right))
+    }
+
+    /// The key/value pairs of the tree.
+    pub fn pairs(&self, pairs: &mut KeyValues) {
+        if let Some(left) = &self.left {
+            left.pairs(pairs);
+        }
+        if let Node::Kv(key, value) = &self.node {
+            pairs.insert(key.clone(), value.clone());
+        }
+        if let Some(right) = &self.right {
+            right.pairs(pairs);
+        }
+    }
+
+    /// The operations to rebuild this tree, in the order merk would emit them.
+    pub fn to_operations(&self, operations: &mut Vec<ProofOperation>) {
+        if let Some(left) = &self.left {
+            left.to_operations(operations);
+        }
+        operations.push(match &self.node {
+            Node::Hash(hash) => ProofOperation::NodeHash(hash.to_vec()),
+            Node::KvHash(hash) => ProofOperation::KeyValueHash(hash.to_vec()),
+            Node::Kv(key, value) => {
+                ProofOperation::KeyValuePair(key.clone().into(), value.clone().into())
+            }
+        });
+        if self.left.is_some() {
+            operations.push(ProofOperation::Parent);
+        }
+        if let Some(right) = &self.right {
+            right.to_operations(operations);
+            operations.push(ProofOperation::Child);
+        }
+    }
+
+    /// Merge two proofs of the same tree, keeping the most detailed version of
+    /// each node. Hashes cannot be checked without the hasher of the store, so
+    /// a merged proof should still be verified.
+    pub fn merge(self, other: Tree) -> Result<Tree, ManyError> {
+        let Tree {
+            node: a,
+            left: a_left,
+            right: a_right,
+        } = self;
+        let Tree {
+            node: b,
+            left: b_left,
+            right: b_right,
+        } = other;
+
+        let node = match (a, b) {
+            (Node::Hash(a), Node::Hash(b)) => {
+                if a != b {
+                    return Err(ManyError::invalid_proof("proofs have different hashes"));
+                }
+                return Ok(Tree::new(Node::Hash(a)));
+            }
+            // A subtree hash is replaced by the subtree of the other proof.
+            (Node::Hash(_), node) => {
+                return Ok(Tree {
+                    node,
+                    left: b_left,
+                    right: b_right,
+                })
+            }
+            (node, Node::Hash(_)) => {
+                return Ok(Tree {
+                    node,
+                    left: a_left,
+                    right: a_right,
+                })
+            }
+            (Node::Kv(a_key, a_value), Node::Kv(b_key, b_value)) => {
+                if a_key != b_key || a_value != b_value {
+                    return Err(ManyError::invalid_proof("proofs have different pairs"));
+                }
+                Node::Kv(a_key, a_value)
+            }
+            (Node::KvHash(a), Node::KvHash(b)) => {
+                if a != b {
+                    return Err(ManyError::invalid_proof("proofs have different hashes"));
+                }
+                Node::KvHash(a)
+            }
+            (Node::Kv(key, value), Node::KvHash(_)) | (Node::KvHash(_), Node::Kv(key, value)) => {
+                Node::Kv(key, value)
+            }
+        };
+
+        Ok(Tree {
+            node,
+            left: merge_child(a_left, b_left)?,
+            right: merge_child(a_right, b_right)?,
+        })
+    }
+}
@@ -0,0 +1,199 @@
+use crate::tree::Tree;
+use many_error::ManyError;
+use many_types::ProofOperation;
+use std::collections::BTreeMap;
+
+pub const HASH_LENGTH: usize = 32;
+pub type Hash = [u8; HASH_LENGTH];
+
+/// The hash of a missing child.
+pub const NULL_HASH: Hash = [0; HASH_LENGTH];
+
+/// The key/value pairs contained in a proof.
+pub type KeyValues = BTreeMap<Vec<u8>, Vec<u8>>;
+
+/// The hash functions of the merkle tree a proof was built from. Stores use
+/// different hash functions, so the verifier needs to be given the ones of
+/// the store that made the proof.
+pub trait NodeHasher {
+    fn kv_hash(&self, key: &[u8], value: &[u8]) -> Hash;
+    fn node_hash(&self, kv_hash: &Hash, left: &Hash, right: &Hash) -> Hash;
+}
+
+/// Execute the operations of a proof, returning the root hash of the tree and
+/// the key/value pairs it contains.
+pub fn execute(
+    operations: &[ProofOperation],
+    hasher: &impl NodeHasher,
+) -> Result<(Hash, KeyValues), ManyError> {
+    let tree = Tree::from_operations(operations)?;
+    let mut pairs = KeyValues::new();
+    tree.pairs(&mut pairs);
+    Ok((tree.hash(hasher), pairs))
+}
+
+/// Verify that a proof resolves to the expected root hash, returning the
+/// key/value pairs it proves.
+pub fn verify(
+    operations: &[ProofOperation],
+    root_hash: &[u8],
+    hasher: &impl NodeHasher,
+) -> Result<KeyValues, ManyError> {
+    let (hash, pairs) = execute(operations, hasher)?;
+    if hash.as_slice() != root_hash {
+        return Err(ManyError::invalid_proof(format!(
+            "root hash {} does not match",
+            hex::encode(hash)
+        )));
+    }
+    Ok(pairs)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use many_error::ManyErrorCode;
+    use sha3::{Digest, Sha3_256};
+    use ProofOperation::{Child, KeyValueHash, KeyValuePair, NodeHash, Parent};
+
+    pub(crate) struct TestHasher;
+
+    impl NodeHasher for TestHasher {
+        fn kv_hash(&self, key: &[u8], value: &[u8]) -> Hash {
+            let mut hasher = Sha3_256::new();
+            hasher.update([0]);
+            hasher.update((key.len() as u32).to_be_bytes());
+            hasher.update(key);
+            hasher.update(value);
+            hasher.finalize().into()
+        }
+
+        fn node_hash(&self, kv_hash: &Hash, left: &Hash, right: &Hash) -> Hash {
+            let mut hasher = Sha3_256::new();
+            hasher.update([1]);
+            hasher.update(kv_hash);
+            hasher.update(left);
+            hasher.update(right);
+            hasher.finalize().into()
+        }
+    }
+
+    pub(crate) fn kv(key: &str, value: &str) -> ProofOperation {
+        KeyValuePair(
+            key.as_bytes().to_vec().into(),
+            value.as_bytes().to_vec().into(),
+        )
+    }
+
+    pub(crate) fn leaf(key: &str, value: &str) -> Hash {
+        TestHasher.node_hash(
+            &TestHasher.kv_hash(key.as_bytes(), value.as_bytes()),
+            &NULL_HASH,
+            &NULL_HASH,
+        )
+    }
+
+    /// The root of a tree with `b` at the root, and `a` and `c` as children.
+    pub(crate) fn root() -> Hash {
+        TestHasher.node_hash(
+            &TestHasher.kv_hash(b"b", b"2"),
+            &leaf("a", "1"),
+            &leaf("c", "3"),
+        )
+    }
+
+    fn invalid(operations: &[ProofOperation]) {
+        let err = execute(operations, &TestHasher).unwrap_err();
+        assert_eq!(err.code(), ManyErrorCode::InvalidProof);
+    }
+
+    #[test]
+    fn single() {
+        let (hash, pairs) = execute(&[kv("a", "1")], &TestHasher).unwrap();
+        assert_eq!(hash, leaf("a", "1"));
+        assert_eq!(pairs, BTreeMap::from([(b"a".to_vec(), b"1".to_vec())]));
+    }
+
+    #[test]
+    fn full() {
+        let operations = [kv("a", "1"), kv("b", "2"), Parent, kv("c", "3"), Child];
+        let pairs = verify(&operations, &root(), &TestHasher).unwrap();
+        assert_eq!(pairs.len(), 3);
+    }
+
+    #[test]
+    fn partial() {
+        let operations = [
+            NodeHash(leaf("a", "1").to_vec()),
+            kv("b", "2"),
+            Parent,
+            NodeHash(leaf("c", "3").to_vec()),
+            Child,
+        ];
+        let pairs = verify(&operations, &root(), &TestHasher).unwrap();
+        assert_eq!(pairs, BTreeMap::from([(b"b".to_vec(), b"2".to_vec())]));
+
+        let operations = [
+            kv("a", "1"),
+            KeyValueHash(TestHasher.kv_hash(b"b", b"2").to_vec()),
+            Parent,
+            NodeHash(leaf("c", "3").to_vec()),
+            Child,
+        ];
+        let pairs = verify(&operations, &root(), &TestHasher).unwrap();
+        assert_eq!(pairs, BTreeMap::from([(b"a".to_vec(), b"1".to_vec())]));
+    }
+
+    #[test]
+    fn wrong_root() {
+        let operations = [kv("a", "1"), kv("b", "2"), Parent, kv("c", "4"), Child];
+        let err = verify(&operations, &root(), &TestHasher).unwrap_err();
+        assert_eq!(err.code(), ManyErrorCode::InvalidProof);
+    }
+
+    #[test]
+    fn empty() {
+        invalid(&[]);
+    }
+
+    #[test]
+    fn stack_underflow() {
+        invalid(&[kv("a", "1"), Parent]);
+        invalid(&[Child]);
+    }
+
+    #[test]
+    fn many_roots() {
+        invalid(&[kv("a", "1"), kv("b", "2")]);
+    }
+
+    #[test]
+    fn hash_length() {
+        invalid(&[NodeHash(vec![1, 2, 3])]);
+        invalid(&[KeyValueHash(vec![0; HASH_LENGTH + 1])]);
+    }
+
+    #[test]
+    fn key_order() {
+        invalid(&[kv("b", "2"), kv("a", "1"), Child]);
+        invalid(&[kv("a", "1"), kv("a", "1"), Child]);
+    }
+
+    #[test]
+    fn attached_twice() {
+        invalid(&[
+            kv("a", "1"),
+            kv("b", "2"),
+            Parent,
+            kv("c", "3"),
+            Child,
+            kv("d", "4"),
+            Child,
+        ]);
+    }
+
+    #[test]
+    fn attach_to_hash() {
+        invalid(&[NodeHash(leaf("a", "1").to_vec()), kv("b", "2"), Child]);
+    }
+}
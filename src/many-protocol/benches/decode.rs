@@ -0,0 +1,64 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use many_protocol::{RequestMessage, RequestMessageRef, ResponseMessage, ResponseMessageRef};
+use many_types::Timestamp;
+
+fn request_bytes() -> Vec<u8> {
+    RequestMessage {
+        data: vec![0xAB; 4096],
+        timestamp: Some(Timestamp::new(1_000_000).unwrap()),
+        id: Some(1),
+        nonce: Some(vec![0xCD; 32]),
+        ..RequestMessage::default().with_method("ledger.send".to_string())
+    }
+    .to_bytes()
+    .unwrap()
+}
+
+fn response_bytes() -> Vec<u8> {
+    ResponseMessage {
+        data: Ok(vec![0xAB; 4096]),
+        timestamp: Some(Timestamp::new(1_000_000).unwrap()),
+        ..ResponseMessage::default()
+    }
+    .to_bytes()
+    .unwrap()
+}
+
+fn request(c: &mut Criterion) {
+    let bytes = request_bytes();
+    let mut group = c.benchmark_group("request");
+    group.bench_function("owned", |b| {
+        b.iter(|| RequestMessage::from_bytes(black_box(&bytes)).unwrap())
+    });
+    group.bench_function("ref", |b| {
+        b.iter(|| RequestMessageRef::from_bytes(black_box(&bytes)).unwrap())
+    });
+    group.finish();
+}
+
+fn response(c: &mut Criterion) {
+    let bytes = response_bytes();
+    let mut group = c.benchmark_group("response");
+    group.bench_function("owned", |b| {
+        b.iter(|| ResponseMessage::from_bytes(black_box(&bytes)).unwrap())
+    });
+    group.bench_function("ref", |b| {
+        b.iter(|| ResponseMessageRef::from_bytes(black_box(&bytes)).unwrap())
+    });
+    group.finish();
+}
+
+fn encode(c: &mut Criterion) {
+    let response = ResponseMessage::from_bytes(&response_bytes()).unwrap();
+    let mut group = c.benchmark_group("encode");
+    group.bench_function("to_vec", |b| {
+        b.iter(|| minicbor::to_vec(black_box(&response)).unwrap())
+    });
+    group.bench_function("pooled", |b| {
+        b.iter(|| many_protocol::buffer::encode_to_vec(black_box(&response)).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, request, response, encode);
+criterion_main!(benches);
@@ -0,0 +1,112 @@
+//! Per-thread pools of byte buffers, to encode and read messages without
+//! growing a new vector for every request.
+use minicbor::Encode;
+use std::cell::RefCell;
+use std::ops::{Deref, DerefMut};
+
+/// Buffers kept by each thread. More buffers in use at once are allocated
+/// and dropped as usual.
+const POOL_SIZE: usize = 8;
+
+/// Buffers which grew larger than this are not kept, so a single large
+/// message doesn't hold on to its memory.
+pub const MAX_POOLED_CAPACITY: usize = 1024 * 1024;
+
+thread_local! {
+    static POOL: RefCell<Vec<Vec<u8>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// An empty buffer from the pool of the current thread. It goes back to the
+/// pool when dropped.
+#[derive(Debug, Default)]
+pub struct PooledBuffer(Vec<u8>);
+
+impl PooledBuffer {
+    pub fn take() -> Self {
+        Self(
+            POOL.with(|pool| pool.borrow_mut().pop())
+                .unwrap_or_default(),
+        )
+    }
+}
+
+impl Deref for PooledBuffer {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for PooledBuffer {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl Drop for PooledBuffer {
+    fn drop(&mut self) {
+        let mut buffer = std::mem::take(&mut self.0);
+        if buffer.capacity() == 0 || buffer.capacity() > MAX_POOLED_CAPACITY {
+            return;
+        }
+        buffer.clear();
+        // The pool might already be destroyed if the thread is exiting.
+        let _ = POOL.try_with(|pool| {
+            let mut pool = pool.borrow_mut();
+            if pool.len() < POOL_SIZE {
+                pool.push(buffer);
+            }
+        });
+    }
+}
+
+/// Encode a value to CBOR in a pooled buffer, then copy it to a vector of
+/// the exact size. This replaces the successive reallocations of
+/// `minicbor::to_vec` with a single one.
+pub fn encode_to_vec<T: Encode<()>>(value: &T) -> Result<Vec<u8>, String> {
+    let mut buffer = PooledBuffer::take();
+    minicbor::encode(value, &mut *buffer).map_err(|e| format!("{e}"))?;
+    // Copies to a vector with the capacity of the content.
+    Ok(buffer.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reuse() {
+        let capacity = {
+            let mut buffer = PooledBuffer::take();
+            buffer.extend_from_slice(&[1; 100]);
+            buffer.capacity()
+        };
+        let buffer = PooledBuffer::take();
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.capacity(), capacity);
+    }
+
+    #[test]
+    fn large_buffers_are_dropped() {
+        {
+            let mut buffer = PooledBuffer::take();
+            buffer.reserve(MAX_POOLED_CAPACITY + 1);
+        }
+        assert!(PooledBuffer::take().capacity() <= MAX_POOLED_CAPACITY);
+    }
+
+    #[test]
+    fn encode() {
+        let value = (1u8, "hello", vec![0u8; 64]);
+        assert_eq!(
+            encode_to_vec(&value).unwrap(),
+            minicbor::to_vec(&value).unwrap()
+        );
+        // A second encoding uses the same buffer.
+        assert_eq!(
+            encode_to_vec(&value).unwrap(),
+            minicbor::to_vec(&value).unwrap()
+        );
+    }
+}
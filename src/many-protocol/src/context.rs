@@ -2,13 +2,19 @@ use {
     crate::RequestMessage,
     async_channel::Sender,
     many_error::ManyError,
-    many_types::{attributes::Attribute, cbor::CborAny, proof::Proof, ProofOperation, PROOF},
+    many_types::{
+        attributes::Attribute, cbor::CborAny, proof::Proof, BlockTime, ProofOperation, Timestamp,
+        Warning, PROOF, SIMULATE,
+    },
+    std::sync::{Arc, Mutex},
 };
 
 #[derive(Clone, Debug)]
 pub struct Context {
     request: RequestMessage,
     transmitter: Sender<ProofResult>,
+    warnings: Arc<Mutex<Vec<Warning>>>,
+    block_time: Option<BlockTime>,
 }
 
 pub enum ProofResult {
@@ -17,6 +23,27 @@ pub enum ProofResult {
     ProofNotRequested,
 }
 
+impl ProofResult {
+    /// Combine the results of multiple calls to [Context::prove] into a single
+    /// proof. Nodes shared between the proofs are only included once.
+    pub fn combine(results: impl IntoIterator<Item = ProofResult>) -> ProofResult {
+        let mut proofs = Vec::new();
+        for result in results {
+            match result {
+                Self::Error(e) => return Self::Error(e),
+                Self::Proof(proof) => proofs.push(proof),
+                Self::ProofNotRequested => {}
+            }
+        }
+        if proofs.is_empty() {
+            return Self::ProofNotRequested;
+        }
+        many_proof::merge(proofs)
+            .map(Self::Proof)
+            .unwrap_or_else(Self::Error)
+    }
+}
+
 impl IntoIterator for ProofResult {
     type Item = Result<Attribute, ManyError>;
     type IntoIter = std::vec::IntoIter<Self::Item>;
@@ -36,9 +63,32 @@ impl Context {
         Self {
             request,
             transmitter,
+            warnings: Default::default(),
+            block_time: None,
         }
     }
 
+    /// Set the time of the block the request is executed in.
+    pub fn with_block_time(mut self, block_time: Option<BlockTime>) -> Self {
+        self.block_time = block_time;
+        self
+    }
+
+    /// The time of the block the request is executed in, if the server runs
+    /// in a blockchain. Backends must use it instead of the wall clock, which
+    /// differs between nodes.
+    pub fn block_time(&self) -> Option<BlockTime> {
+        self.block_time
+    }
+
+    /// The time of the block, or the wall clock for servers outside of a
+    /// blockchain.
+    pub fn now(&self) -> Timestamp {
+        self.block_time.map_or_else(Timestamp::now, Into::into)
+    }
+
+    /// Send a proof of the response. This can be called multiple times by an
+    /// endpoint proving many keys; the proofs are then merged into one.
     pub fn prove<
         P: IntoIterator<Item = ProofOperation>,
         Prover: FnOnce() -> Result<P, ManyError>,
@@ -64,6 +114,32 @@ impl Context {
     pub fn proof_requested(&self) -> bool {
         self.request.attributes.contains(&PROOF)
     }
+
+    /// Whether the request is only simulated, in which case the server
+    /// discards its changes to the state once it is executed.
+    pub fn simulation_requested(&self) -> bool {
+        self.request.attributes.has_id(SIMULATE.id)
+    }
+
+    /// Report a warning to the caller. Warnings are only sent with a
+    /// successful response; they are dropped if the call returns an error.
+    pub fn warn(&self, warning: Warning) {
+        self.warnings
+            .lock()
+            .expect("Could not acquire warnings lock")
+            .push(warning);
+    }
+
+    /// Remove and return the warnings reported so far. Clones of this
+    /// context share the same warnings.
+    pub fn take_warnings(&self) -> Vec<Warning> {
+        std::mem::take(
+            &mut *self
+                .warnings
+                .lock()
+                .expect("Could not acquire warnings lock"),
+        )
+    }
 }
 
 impl AsRef<Context> for Context {
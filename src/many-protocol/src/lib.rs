@@ -1,14 +1,21 @@
 use coset::CoseSign1;
 use coset::CoseSign1Builder;
+use coset::{CoseSign, CoseSignBuilder};
 use many_error::ManyError;
 use many_identity::{Address, Identity, Verifier};
+use many_types::attributes::AttributeSet;
+use many_types::{DelegationChain, Timestamp};
+use std::collections::BTreeSet;
 
+pub mod buffer;
 pub mod context;
 pub mod request;
 pub mod response;
+mod version;
 
-pub use request::{RequestMessage, RequestMessageBuilder};
-pub use response::{ResponseMessage, ResponseMessageBuilder};
+pub use request::{RequestMessage, RequestMessageBuilder, RequestMessageRef};
+pub use response::{ResponseMessage, ResponseMessageBuilder, ResponseMessageRef};
+pub use version::*;
 
 pub type ManyUrl = url::Url;
 
@@ -16,20 +23,138 @@ pub fn decode_request_from_cose_sign1(
     envelope: &CoseSign1,
     verifier: &impl Verifier,
 ) -> Result<RequestMessage, ManyError> {
+    decode_request_ref_from_cose_sign1(envelope, verifier).map(Into::into)
+}
+
+/// Same as [decode_request_from_cose_sign1], but the request borrows its
+/// fields from the envelope payload instead of copying them.
+pub fn decode_request_ref_from_cose_sign1<'a>(
+    envelope: &'a CoseSign1,
+    verifier: &impl Verifier,
+) -> Result<RequestMessageRef<'a>, ManyError> {
+    decode_ref_with_signer(envelope, verifier, None).map(|(message, _)| message)
+}
+
+/// Decode a request which can be signed on behalf of its sender, through the
+/// delegation chain of its [DELEGATION](many_types::DELEGATION) attribute.
+/// The expiration of the delegations is checked against `now`, which must be
+/// the time of the server or of the block, not of the request. Returns the
+/// request and the address of its signer.
+pub fn decode_delegated_request_from_cose_sign1(
+    envelope: &CoseSign1,
+    verifier: &impl Verifier,
+    now: Timestamp,
+) -> Result<(RequestMessage, Address), ManyError> {
+    decode_ref_with_signer(envelope, verifier, Some(now))
+        .map(|(message, signer)| (message.into(), signer))
+}
+
+fn decode_ref_with_signer<'a>(
+    envelope: &'a CoseSign1,
+    verifier: &impl Verifier,
+    now: Option<Timestamp>,
+) -> Result<(RequestMessageRef<'a>, Address), ManyError> {
     let from_id = verifier.verify_1(envelope)?;
 
-    if from_id.is_illegal() {
-        return Err(ManyError::invalid_from_identity());
+    let message: RequestMessageRef = envelope.try_into()?;
+    check_sender(
+        &message.from(),
+        &BTreeSet::from([from_id]),
+        &message.method,
+        &message.attributes,
+        message.timestamp,
+        verifier,
+        now,
+    )?;
+    Ok((message, from_id))
+}
+
+/// Decode a request from a multi-signer envelope, verifying all of its
+/// signatures. Returns the request and the addresses of its signers, one of
+/// which must be the `from` field of the request.
+pub fn decode_request_from_cose_sign(
+    envelope: &CoseSign,
+    verifier: &impl Verifier,
+) -> Result<(RequestMessage, BTreeSet<Address>), ManyError> {
+    decode_with_signers(envelope, verifier, None)
+}
+
+/// Same as [decode_request_from_cose_sign], but the `from` field of the
+/// request can instead delegate to one of the signers. See
+/// [decode_delegated_request_from_cose_sign1].
+pub fn decode_delegated_request_from_cose_sign(
+    envelope: &CoseSign,
+    verifier: &impl Verifier,
+    now: Timestamp,
+) -> Result<(RequestMessage, BTreeSet<Address>), ManyError> {
+    decode_with_signers(envelope, verifier, Some(now))
+}
+
+fn decode_with_signers(
+    envelope: &CoseSign,
+    verifier: &impl Verifier,
+    now: Option<Timestamp>,
+) -> Result<(RequestMessage, BTreeSet<Address>), ManyError> {
+    if envelope.signatures.is_empty() {
+        return Err(ManyError::could_not_verify_signature(
+            "The envelope has no signature",
+        ));
     }
+    let signers = (0..envelope.signatures.len())
+        .map(|index| verifier.verify_multi(envelope, index))
+        .collect::<Result<BTreeSet<_>, _>>()?;
 
-    // Check the `from` field.
     let message: RequestMessage = envelope.try_into()?;
-    let message_from = message.from.unwrap_or_default();
-    if !from_id.matches(&message_from) || message_from.is_illegal() {
-        Err(ManyError::invalid_from_identity())
-    } else {
-        Ok(message)
+    check_sender(
+        &message.from(),
+        &signers,
+        &message.method,
+        &message.attributes,
+        message.timestamp,
+        verifier,
+        now,
+    )?;
+    Ok((message, signers))
+}
+
+/// Check that the sender of a request is one of its signers or, if `now` is
+/// given, that it delegated to one of them.
+fn check_sender(
+    from: &Address,
+    signers: &BTreeSet<Address>,
+    method: &str,
+    attributes: &AttributeSet,
+    timestamp: Option<Timestamp>,
+    verifier: &impl Verifier,
+    now: Option<Timestamp>,
+) -> Result<(), ManyError> {
+    if from.is_illegal() || signers.iter().any(Address::is_illegal) {
+        return Err(ManyError::invalid_from_identity());
+    }
+    if signers.iter().any(|signer| signer.matches(from)) {
+        return Ok(());
+    }
+
+    let (now, chain) = match (now, attributes.get::<Option<DelegationChain>>()?) {
+        (Some(now), Some(chain)) => (now, chain),
+        _ => return Err(ManyError::invalid_from_identity()),
+    };
+    // The timestamp of the request bounds how long it can be replayed, so a
+    // delegate cannot omit it.
+    if timestamp.is_none() {
+        return Err(ManyError::invalid_delegation(
+            "delegated requests must have a timestamp",
+        ));
+    }
+
+    let mut result = Err(ManyError::invalid_from_identity());
+    for signer in signers {
+        result = chain.verify(verifier, from, signer, method, now);
+        if result.is_ok() {
+            break;
+        }
     }
+    result
 }
 
 pub fn decode_response_from_cose_sign1(
@@ -76,6 +201,25 @@ pub fn encode_cose_sign1_from_request(
     }
 }
 
+/// Encode a request in a multi-signer envelope, signed by all identities in
+/// order.
+pub fn encode_cose_sign_from_request(
+    request: RequestMessage,
+    identities: &[&dyn Identity],
+) -> Result<CoseSign, ManyError> {
+    // We don't allow illegal from fields in requests.
+    if request.from == Some(Address::ILLEGAL) {
+        return Err(ManyError::invalid_from_identity());
+    }
+
+    let envelope = CoseSignBuilder::default()
+        .payload(request.to_bytes().map_err(ManyError::serialization_error)?)
+        .build();
+    identities
+        .iter()
+        .try_fold(envelope, |envelope, identity| identity.sign_multi(envelope))
+}
+
 #[test]
 fn encode_illegal() {
     let message = RequestMessage {
@@ -117,3 +261,158 @@ fn decode_illegal() {
         encode_cose_sign1_from_request(message, &many_identity::AnonymousIdentity).unwrap();
     assert!(decode_request_from_cose_sign1(&envelope, &IllegalVerifier).is_err());
 }
+
+#[test]
+fn decode_delegated() {
+    use coset::CoseSignature;
+    use many_types::attributes::AttributeSet;
+    use many_types::delegation::Delegation;
+
+    /// Signs by setting the key ID to its address, with no signature.
+    struct KeyIdIdentity(Address);
+    impl Identity for KeyIdIdentity {
+        fn address(&self) -> Address {
+            self.0
+        }
+        fn public_key(&self) -> Option<coset::CoseKey> {
+            None
+        }
+        fn sign_1(&self, mut envelope: CoseSign1) -> Result<CoseSign1, ManyError> {
+            envelope.protected.header.key_id = self.0.to_vec();
+            Ok(envelope)
+        }
+        fn sign_multi(&self, mut envelope: CoseSign) -> Result<CoseSign, ManyError> {
+            let mut signature = CoseSignature::default();
+            signature.protected.header.key_id = self.0.to_vec();
+            envelope.signatures.push(signature);
+            Ok(envelope)
+        }
+    }
+    struct KeyIdVerifier;
+    impl Verifier for KeyIdVerifier {
+        fn verify_1(&self, envelope: &CoseSign1) -> Result<Address, ManyError> {
+            Address::from_bytes(&envelope.protected.header.key_id)
+        }
+        fn verify_multi(&self, envelope: &CoseSign, index: usize) -> Result<Address, ManyError> {
+            Address::from_bytes(&envelope.signatures[index].protected.header.key_id)
+        }
+    }
+
+    let address = |seed: u8| {
+        let mut bytes = [seed; 29];
+        bytes[0] = 1;
+        Address::from_bytes(&bytes).unwrap()
+    };
+    let delegation = Delegation {
+        from: address(1),
+        to: address(2),
+        methods: Some(BTreeSet::from(["ledger.send".to_string()])),
+        expiration: Timestamp::new(100).unwrap(),
+    };
+    let chain = DelegationChain::new([delegation.sign(&KeyIdIdentity(address(1))).unwrap()]);
+    let attributes = AttributeSet::from_iter([chain.to_attribute().unwrap()]);
+
+    let request = |method: &str, timestamp: u64, attributes: &AttributeSet| RequestMessage {
+        from: Some(address(1)),
+        method: method.to_string(),
+        timestamp: Some(Timestamp::new(timestamp).unwrap()),
+        attributes: attributes.clone(),
+        ..Default::default()
+    };
+    // Requests are always encoded with a timestamp, so encode one by hand.
+    let untimed = {
+        let mut e = minicbor::Encoder::new(Vec::new());
+        e.tag(minicbor::data::Tag::Unassigned(10001))
+            .and_then(|e| e.map(3))
+            .and_then(|e| e.i8(1)?.encode(address(1)))
+            .and_then(|e| e.i8(3)?.str("ledger.send"))
+            .and_then(|e| e.i8(8)?.encode(&attributes))
+            .unwrap();
+        e.into_writer()
+    };
+    let decode = |message: RequestMessage, now: u64| {
+        let envelope = encode_cose_sign1_from_request(message, &KeyIdIdentity(address(2))).unwrap();
+        decode_delegated_request_from_cose_sign1(
+            &envelope,
+            &KeyIdVerifier,
+            Timestamp::new(now).unwrap(),
+        )
+    };
+
+    let (_, signer) = decode(request("ledger.send", 50, &attributes), 50).unwrap();
+    assert_eq!(signer, address(2));
+    assert!(decode(request("ledger.balance", 50, &attributes), 50).is_err());
+    assert!(decode(request("ledger.send", 50, &AttributeSet::new()), 50).is_err());
+
+    // The expiration is checked against the time of the server, so a
+    // backdated request cannot use an expired delegation.
+    assert!(decode(request("ledger.send", 50, &attributes), 150).is_err());
+
+    // Delegated requests must have a timestamp.
+    let envelope = KeyIdIdentity(address(2))
+        .sign_1(CoseSign1Builder::new().payload(untimed.clone()).build())
+        .unwrap();
+    assert_eq!(
+        decode_delegated_request_from_cose_sign1(
+            &envelope,
+            &KeyIdVerifier,
+            Timestamp::new(50).unwrap()
+        )
+        .unwrap_err()
+        .code(),
+        ManyError::invalid_delegation("").code()
+    );
+
+    // Delegations are only accepted when the caller gives the time.
+    let envelope = encode_cose_sign1_from_request(
+        request("ledger.send", 50, &attributes),
+        &KeyIdIdentity(address(2)),
+    )
+    .unwrap();
+    assert!(decode_request_from_cose_sign1(&envelope, &KeyIdVerifier).is_err());
+
+    // Multi-signer envelopes accept delegations the same way.
+    let decode_multi = |message: RequestMessage, now: u64| {
+        let envelope = encode_cose_sign_from_request(
+            message,
+            &[&KeyIdIdentity(address(2)), &KeyIdIdentity(address(3))],
+        )
+        .unwrap();
+        decode_delegated_request_from_cose_sign(
+            &envelope,
+            &KeyIdVerifier,
+            Timestamp::new(now).unwrap(),
+        )
+    };
+    let (_, signers) = decode_multi(request("ledger.send", 50, &attributes), 50).unwrap();
+    assert_eq!(signers, BTreeSet::from([address(2), address(3)]));
+    assert!(decode_multi(request("ledger.send", 50, &attributes), 150).is_err());
+    let envelope = KeyIdIdentity(address(2))
+        .sign_multi(CoseSignBuilder::new().payload(untimed).build())
+        .unwrap();
+    assert!(decode_delegated_request_from_cose_sign(
+        &envelope,
+        &KeyIdVerifier,
+        Timestamp::new(50).unwrap()
+    )
+    .is_err());
+}
+
+#[test]
+fn request_version() {
+    let message = RequestMessage {
+        version: Some(2),
+        method: "status".to_string(),
+        ..Default::default()
+    };
+    let bytes = message.to_bytes().unwrap();
+    assert_eq!(RequestMessage::from_bytes(&bytes).unwrap().version, Some(2));
+
+    // The current version is implied.
+    let message = RequestMessage {
+        version: Some(PROTOCOL_VERSION),
+        ..message
+    };
+    let bytes = message.to_bytes().unwrap();
+    assert_eq!(RequestMessage::from_bytes(&bytes).unwrap().version, None);
+}
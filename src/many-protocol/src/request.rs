@@ -1,4 +1,4 @@
-use coset::CoseSign1;
+use coset::{CoseSign, CoseSign1};
 use derive_builder::Builder;
 use many_error::ManyError;
 use many_identity::Address;
@@ -8,6 +8,7 @@ use minicbor::data::{Tag, Type};
 use minicbor::encode::{Error, Write};
 use minicbor::{Decode, Decoder, Encode, Encoder};
 use num_derive::{FromPrimitive, ToPrimitive};
+use std::borrow::Cow;
 use std::convert::TryFrom;
 use std::time::SystemTime;
 
@@ -93,6 +94,18 @@ impl<'a> TryFrom<&'a CoseSign1> for RequestMessage {
     }
 }
 
+impl<'a> TryFrom<&'a CoseSign> for RequestMessage {
+    type Error = ManyError;
+
+    fn try_from(envelope: &'a CoseSign) -> Result<Self, Self::Error> {
+        envelope
+            .payload
+            .as_ref()
+            .ok_or_else(ManyError::empty_envelope)
+            .and_then(|payload| Self::from_bytes(payload).map_err(ManyError::deserialization_error))
+    }
+}
+
 impl RequestMessage {
     pub fn with_method(mut self, method: String) -> Self {
         self.method = method;
@@ -115,7 +128,7 @@ impl RequestMessage {
     }
 
     pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
-        minicbor::to_vec(self).map_err(|e| format!("{e}"))
+        crate::buffer::encode_to_vec(self)
     }
 
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
@@ -156,10 +169,95 @@ impl RequestMessage {
     }
 }
 
+/// A request that borrows its method, argument and nonce instead of owning
+/// them. Decoding it does not copy these fields out of the payload, which is
+/// enough for code that only inspects a request (e.g. to route it).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RequestMessageRef<'a> {
+    pub version: Option<u8>,
+    pub from: Option<Address>,
+    pub to: Address,
+    pub method: Cow<'a, str>,
+    pub data: Cow<'a, [u8]>,
+    pub timestamp: Option<Timestamp>,
+    pub id: Option<u64>,
+    pub nonce: Option<Cow<'a, [u8]>>,
+    pub attributes: Cow<'a, AttributeSet>,
+}
+
+impl<'a> RequestMessageRef<'a> {
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, String> {
+        minicbor::decode(bytes).map_err(|e| format!("{e}"))
+    }
+
+    pub fn from(&self) -> Address {
+        self.from.unwrap_or_default()
+    }
+}
+
+impl<'a> TryFrom<&'a CoseSign1> for RequestMessageRef<'a> {
+    type Error = ManyError;
+
+    fn try_from(envelope: &'a CoseSign1) -> Result<Self, Self::Error> {
+        envelope
+            .payload
+            .as_ref()
+            .ok_or_else(ManyError::empty_envelope)
+            .and_then(|payload| Self::from_bytes(payload).map_err(ManyError::deserialization_error))
+    }
+}
+
+impl<'a> From<&'a RequestMessage> for RequestMessageRef<'a> {
+    fn from(message: &'a RequestMessage) -> Self {
+        Self {
+            version: message.version,
+            from: message.from,
+            to: message.to,
+            method: Cow::Borrowed(&message.method),
+            data: Cow::Borrowed(&message.data),
+            timestamp: message.timestamp,
+            id: message.id,
+            nonce: message.nonce.as_deref().map(Cow::Borrowed),
+            attributes: Cow::Borrowed(&message.attributes),
+        }
+    }
+}
+
+impl From<RequestMessageRef<'_>> for RequestMessage {
+    fn from(message: RequestMessageRef<'_>) -> Self {
+        Self {
+            version: message.version,
+            from: message.from,
+            to: message.to,
+            method: message.method.into_owned(),
+            data: message.data.into_owned(),
+            timestamp: message.timestamp,
+            id: message.id,
+            nonce: message.nonce.map(Cow::into_owned),
+            attributes: message.attributes.into_owned(),
+        }
+    }
+}
+
 impl<C> Encode<C> for RequestMessage {
+    fn encode<W: Write>(&self, e: &mut Encoder<W>, ctx: &mut C) -> Result<(), Error<W::Error>> {
+        let message: RequestMessageRef = self.into();
+        message.encode(e, ctx)
+    }
+}
+
+impl<'b, C> Decode<'b, C> for RequestMessage {
+    fn decode(d: &mut Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
+        RequestMessageRef::decode(d, ctx).map(Into::into)
+    }
+}
+
+impl<C> Encode<C> for RequestMessageRef<'_> {
     fn encode<W: Write>(&self, e: &mut Encoder<W>, _: &mut C) -> Result<(), Error<W::Error>> {
         e.tag(Tag::Unassigned(10001))?;
+        let version = self.version.filter(|v| *v != crate::PROTOCOL_VERSION);
         let l = 2
+            + u64::from(version.is_some())
             + u64::from(!(self.from.is_none() || self.from == Some(Address::anonymous())))
             + u64::from(!self.to.is_anonymous())
             + u64::from(!self.data.is_empty())
@@ -168,9 +266,10 @@ impl<C> Encode<C> for RequestMessage {
             + u64::from(!self.attributes.is_empty());
         e.map(l)?;
 
-        // Skip version for this version of the protocol. This message implementation
-        // only supports version 1.
-        // e.i8(RequestMessageCborKey::ProtocolVersion as i8)?.u8(*v)?;
+        // The version is only sent for versions other than the current one.
+        if let Some(v) = version {
+            e.i8(RequestMessageCborKey::ProtocolVersion as i8)?.u8(v)?;
+        }
 
         // No need to send the anonymous identity.
         if let Some(ref i) = self.from {
@@ -184,7 +283,7 @@ impl<C> Encode<C> for RequestMessage {
         }
 
         e.i8(RequestMessageCborKey::Endpoint as i8)?
-            .encode(&self.method)?;
+            .str(&self.method)?;
 
         if !self.data.is_empty() {
             e.i8(RequestMessageCborKey::Argument as i8)?
@@ -204,14 +303,14 @@ impl<C> Encode<C> for RequestMessage {
 
         if !self.attributes.is_empty() {
             e.i8(RequestMessageCborKey::Attributes as i8)?
-                .encode(&self.attributes)?;
+                .encode(self.attributes.as_ref())?;
         }
 
         Ok(())
     }
 }
 
-impl<'b, C> Decode<'b, C> for RequestMessage {
+impl<'b, C> Decode<'b, C> for RequestMessageRef<'b> {
     fn decode(d: &mut Decoder<'b>, _: &mut C) -> Result<Self, minicbor::decode::Error> {
         if d.tag()? != Tag::Unassigned(10001) {
             return Err(minicbor::decode::Error::message(
@@ -219,7 +318,7 @@ impl<'b, C> Decode<'b, C> for RequestMessage {
             ));
         };
 
-        let mut builder = RequestMessageBuilder::default();
+        let mut message = RequestMessageRef::default();
 
         let mut i = 0;
         let x = d.map()?;
@@ -232,23 +331,22 @@ impl<'b, C> Decode<'b, C> for RequestMessage {
             }
 
             match num_traits::FromPrimitive::from_i8(d.i8()?) {
-                None => &mut builder,
-                Some(RequestMessageCborKey::ProtocolVersion) => {
-                    let v = d.u8()?;
-                    // Only support version 1.
-                    if v != 1 {
-                        return Err(minicbor::decode::Error::message("Invalid version."));
-                    }
-                    builder.version(v)
+                None => {}
+                // Servers decide which versions they accept, see
+                // `ManyServer::add_version_hook`.
+                Some(RequestMessageCborKey::ProtocolVersion) => message.version = Some(d.u8()?),
+                Some(RequestMessageCborKey::From) => message.from = Some(d.decode()?),
+                Some(RequestMessageCborKey::To) => message.to = d.decode()?,
+                Some(RequestMessageCborKey::Endpoint) => message.method = Cow::Borrowed(d.str()?),
+                Some(RequestMessageCborKey::Argument) => message.data = Cow::Borrowed(d.bytes()?),
+                Some(RequestMessageCborKey::Timestamp) => message.timestamp = Some(d.decode()?),
+                Some(RequestMessageCborKey::Id) => message.id = Some(d.u64()?),
+                Some(RequestMessageCborKey::Nonce) => {
+                    message.nonce = Some(Cow::Borrowed(d.bytes()?))
+                }
+                Some(RequestMessageCborKey::Attributes) => {
+                    message.attributes = Cow::Owned(d.decode()?)
                 }
-                Some(RequestMessageCborKey::From) => builder.from(d.decode()?),
-                Some(RequestMessageCborKey::To) => builder.to(d.decode()?),
-                Some(RequestMessageCborKey::Endpoint) => builder.method(d.decode()?),
-                Some(RequestMessageCborKey::Argument) => builder.data(d.bytes()?.to_vec()),
-                Some(RequestMessageCborKey::Timestamp) => builder.timestamp(d.decode()?),
-                Some(RequestMessageCborKey::Id) => builder.id(d.u64()?),
-                Some(RequestMessageCborKey::Nonce) => builder.nonce(d.bytes()?.to_vec()),
-                Some(RequestMessageCborKey::Attributes) => builder.attributes(d.decode()?),
             };
 
             i += 1;
@@ -257,8 +355,27 @@ impl<'b, C> Decode<'b, C> for RequestMessage {
             }
         }
 
-        builder
-            .build()
-            .map_err(|_e| minicbor::decode::Error::message("could not build"))
+        Ok(message)
     }
 }
+
+#[test]
+fn decode_ref() {
+    let message = RequestMessage {
+        to: Address::anonymous(),
+        data: vec![1, 2, 3],
+        timestamp: Some(Timestamp::new(1_000_000).unwrap()),
+        id: Some(1),
+        nonce: Some(vec![4, 5, 6]),
+        ..RequestMessage::default().with_method("ledger.send".to_string())
+    };
+    let bytes = message.to_bytes().unwrap();
+
+    let message_ref = RequestMessageRef::from_bytes(&bytes).unwrap();
+    assert!(matches!(message_ref.method, Cow::Borrowed("ledger.send")));
+    assert!(matches!(message_ref.data, Cow::Borrowed([1, 2, 3])));
+    assert_eq!(message_ref, (&message).into());
+
+    let message: RequestMessage = message_ref.into();
+    assert_eq!(message.to_bytes().unwrap(), bytes);
+}
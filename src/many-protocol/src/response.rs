@@ -4,11 +4,15 @@ use derive_builder::Builder;
 use many_error::ManyError;
 use many_identity::{Address, Verifier};
 use many_types::attributes::{Attribute, AttributeSet};
+use many_types::cbor::CborAny;
+use many_types::simulation::{simulation_attribute, SIMULATE};
+use many_types::warning::{warnings_attribute, Warning, WARNINGS};
 use many_types::Timestamp;
 use minicbor::data::{Tag, Type};
 use minicbor::encode::{Error, Write};
 use minicbor::{Decode, Decoder, Encode, Encoder};
 use num_derive::{FromPrimitive, ToPrimitive};
+use std::borrow::Cow;
 
 #[derive(FromPrimitive, ToPrimitive)]
 #[repr(i8)]
@@ -119,8 +123,43 @@ impl ResponseMessage {
         self
     }
 
+    /// Add warnings to this response, after any warnings it already has.
+    pub fn with_warnings<T: IntoIterator<Item = Warning>>(
+        mut self,
+        warnings: T,
+    ) -> Result<Self, ManyError> {
+        let mut all = self.warnings()?;
+        all.extend(warnings);
+        if !all.is_empty() {
+            self.attributes.take(WARNINGS.id);
+            self.attributes.insert(warnings_attribute(&all)?);
+        }
+        Ok(self)
+    }
+
+    /// The warnings attached to this response. Empty if there are none.
+    pub fn warnings(&self) -> Result<Vec<Warning>, ManyError> {
+        self.attributes.get()
+    }
+
+    /// Mark this response as the result of a simulation, which would have
+    /// emitted these events.
+    pub fn with_simulated_events(mut self, events: Vec<CborAny>) -> Self {
+        self.attributes.take(SIMULATE.id);
+        self.attributes.insert(simulation_attribute(events));
+        self
+    }
+
+    /// The events of a simulated response, or `None` if the request was
+    /// actually executed.
+    pub fn simulated_events(&self) -> Option<&Vec<CborAny>> {
+        self.attributes
+            .get_attribute(SIMULATE.id)
+            .map(Attribute::arguments)
+    }
+
     pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
-        minicbor::to_vec(self).map_err(|e| format!("{e}"))
+        crate::buffer::encode_to_vec(self)
     }
 
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
@@ -128,7 +167,84 @@ impl ResponseMessage {
     }
 }
 
+/// A response that borrows its result instead of owning it. See
+/// [crate::RequestMessageRef].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ResponseMessageRef<'a> {
+    pub version: Option<u8>,
+    pub from: Address,
+    pub to: Option<Address>,
+    pub data: Result<Cow<'a, [u8]>, ManyError>,
+    pub timestamp: Option<Timestamp>,
+    pub id: Option<u64>,
+    pub attributes: Cow<'a, AttributeSet>,
+}
+
+impl Default for ResponseMessageRef<'_> {
+    fn default() -> Self {
+        Self {
+            version: None,
+            from: Address::anonymous(),
+            to: None,
+            data: Ok(Cow::Borrowed(&[])),
+            timestamp: None,
+            id: None,
+            attributes: Default::default(),
+        }
+    }
+}
+
+impl<'a> ResponseMessageRef<'a> {
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, String> {
+        minicbor::decode(bytes).map_err(|e| format!("{e}"))
+    }
+}
+
+impl<'a> From<&'a ResponseMessage> for ResponseMessageRef<'a> {
+    fn from(message: &'a ResponseMessage) -> Self {
+        Self {
+            version: message.version,
+            from: message.from,
+            to: message.to,
+            data: message
+                .data
+                .as_ref()
+                .map(|data| Cow::Borrowed(data.as_slice()))
+                .map_err(Clone::clone),
+            timestamp: message.timestamp,
+            id: message.id,
+            attributes: Cow::Borrowed(&message.attributes),
+        }
+    }
+}
+
+impl From<ResponseMessageRef<'_>> for ResponseMessage {
+    fn from(message: ResponseMessageRef<'_>) -> Self {
+        Self {
+            version: message.version,
+            from: message.from,
+            to: message.to,
+            data: message.data.map(Cow::into_owned),
+            timestamp: message.timestamp,
+            id: message.id,
+            attributes: message.attributes.into_owned(),
+        }
+    }
+}
+
 impl<C> Encode<C> for ResponseMessage {
+    fn encode<W: Write>(&self, e: &mut Encoder<W>, ctx: &mut C) -> Result<(), Error<W::Error>> {
+        ResponseMessageRef::from(self).encode(e, ctx)
+    }
+}
+
+impl<'b, C> Decode<'b, C> for ResponseMessage {
+    fn decode(d: &mut Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
+        ResponseMessageRef::decode(d, ctx).map(Into::into)
+    }
+}
+
+impl<C> Encode<C> for ResponseMessageRef<'_> {
     fn encode<W: Write>(&self, e: &mut Encoder<W>, _: &mut C) -> Result<(), Error<W::Error>> {
         e.tag(Tag::Unassigned(10002))?;
         let l = 2
@@ -168,14 +284,14 @@ impl<C> Encode<C> for ResponseMessage {
 
         if !self.attributes.is_empty() {
             e.i8(ResponseMessageCborKey::Attributes as i8)?
-                .encode(&self.attributes)?;
+                .encode(self.attributes.as_ref())?;
         }
 
         Ok(())
     }
 }
 
-impl<'b, C> Decode<'b, C> for ResponseMessage {
+impl<'b, C> Decode<'b, C> for ResponseMessageRef<'b> {
     fn decode(d: &mut Decoder<'b>, _: &mut C) -> Result<Self, minicbor::decode::Error> {
         if d.tag()? != Tag::Unassigned(10002) {
             return Err(minicbor::decode::Error::message(
@@ -183,7 +299,7 @@ impl<'b, C> Decode<'b, C> for ResponseMessage {
             ));
         };
 
-        let mut builder = ResponseMessageBuilder::default();
+        let mut message = ResponseMessageRef::default();
 
         let mut i = 0;
         let x = d.map()?;
@@ -196,17 +312,22 @@ impl<'b, C> Decode<'b, C> for ResponseMessage {
             }
 
             match num_traits::FromPrimitive::from_i64(d.i64()?) {
-                Some(ResponseMessageCborKey::ProtocolVersion) => builder.version(d.decode()?),
-                Some(ResponseMessageCborKey::From) => builder.from(d.decode()?),
-                Some(ResponseMessageCborKey::To) => builder.to(d.decode()?),
+                Some(ResponseMessageCborKey::ProtocolVersion) => {
+                    message.version = Some(d.decode()?)
+                }
+                Some(ResponseMessageCborKey::From) => message.from = d.decode()?,
+                Some(ResponseMessageCborKey::To) => message.to = Some(d.decode()?),
                 Some(ResponseMessageCborKey::Result) => match d.datatype()? {
-                    Type::Bytes => builder.data(Ok(d.bytes()?.to_vec())),
-                    Type::Map => builder.data(Err(d.decode()?)),
-                    _ => &mut builder,
+                    Type::Bytes => message.data = Ok(Cow::Borrowed(d.bytes()?)),
+                    Type::Map => message.data = Err(d.decode()?),
+                    _ => {}
                 },
-                Some(ResponseMessageCborKey::Timestamp) => builder.timestamp(d.decode()?),
-                Some(ResponseMessageCborKey::Attributes) => builder.attributes(d.decode()?),
-                _ => &mut builder,
+                Some(ResponseMessageCborKey::Timestamp) => message.timestamp = Some(d.decode()?),
+                Some(ResponseMessageCborKey::Id) => message.id = Some(d.u64()?),
+                Some(ResponseMessageCborKey::Attributes) => {
+                    message.attributes = Cow::Owned(d.decode()?)
+                }
+                _ => {}
             };
 
             i += 1;
@@ -215,9 +336,7 @@ impl<'b, C> Decode<'b, C> for ResponseMessage {
             }
         }
 
-        builder
-            .build()
-            .map_err(|_e| minicbor::decode::Error::message("could not build"))
+        Ok(message)
     }
 }
 
@@ -268,3 +387,44 @@ fn decode_illegal_verifier() {
 
     assert!(ResponseMessage::decode_and_verify(&envelope, &IllegalVerifier).is_err());
 }
+
+#[test]
+fn warnings() {
+    let message = ResponseMessage::default()
+        .with_warnings([Warning::partial("Only 3 tokens were burnt.")])
+        .unwrap()
+        .with_warnings([Warning::deprecated("Use ledger.send instead.")])
+        .unwrap();
+    let message = ResponseMessage::from_bytes(&message.to_bytes().unwrap()).unwrap();
+
+    assert_eq!(
+        message.warnings().unwrap(),
+        vec![
+            Warning::partial("Only 3 tokens were burnt."),
+            Warning::deprecated("Use ledger.send instead."),
+        ]
+    );
+    assert!(ResponseMessage::default().warnings().unwrap().is_empty());
+    assert!(ResponseMessage::default()
+        .with_warnings([])
+        .unwrap()
+        .attributes
+        .is_empty());
+}
+
+#[test]
+fn decode_ref() {
+    let message = ResponseMessage {
+        data: Ok(vec![1, 2, 3]),
+        timestamp: Some(Timestamp::new(1_000_000).unwrap()),
+        id: Some(42),
+        ..ResponseMessage::default()
+    }
+    .with_warnings([Warning::partial("Only 3 tokens were burnt.")])
+    .unwrap();
+    let bytes = message.to_bytes().unwrap();
+
+    let message_ref = ResponseMessageRef::from_bytes(&bytes).unwrap();
+    assert!(matches!(message_ref.data, Ok(Cow::Borrowed(_))));
+    assert_eq!(ResponseMessage::from(message_ref), message);
+}
@@ -0,0 +1,25 @@
+/// The version of the protocol implemented by this crate. Messages of this
+/// version do not need to include it.
+pub const PROTOCOL_VERSION: u8 = 1;
+
+/// The protocol versions this crate can send.
+pub const SUPPORTED_VERSIONS: &[u8] = &[PROTOCOL_VERSION];
+
+/// Returns the highest protocol version supported by both sides, if any.
+pub fn negotiate_version(ours: &[u8], theirs: &[u8]) -> Option<u8> {
+    ours.iter().filter(|v| theirs.contains(v)).max().copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negotiate() {
+        assert_eq!(negotiate_version(&[1], &[1]), Some(1));
+        assert_eq!(negotiate_version(&[1, 2], &[1]), Some(1));
+        assert_eq!(negotiate_version(&[1, 2, 3], &[3, 2]), Some(3));
+        assert_eq!(negotiate_version(&[2], &[1]), None);
+        assert_eq!(negotiate_version(&[], &[1]), None);
+    }
+}
@@ -1,6 +1,8 @@
-use coset::CoseSign1;
+use coset::{CoseSign, CoseSign1};
 use many_error::ManyError;
+use many_modules::r#async::AsyncToken;
 use many_protocol::ResponseMessage;
+use many_server::async_store::{AsyncTokenRecord, AsyncTokenStore};
 use many_server::RequestValidator;
 use sha2::Digest;
 use std::path::Path;
@@ -45,36 +47,49 @@ impl<T: RequestCacheBackend> RequestCacheValidator<T> {
     }
 }
 
-impl<T: RequestCacheBackend> RequestValidator for RequestCacheValidator<T> {
-    fn validate_envelope(&self, envelope: &CoseSign1) -> Result<(), ManyError> {
-        let payload = envelope
-            .payload
-            .as_ref()
-            .ok_or_else(ManyError::empty_envelope)?;
+impl<T: RequestCacheBackend> RequestCacheValidator<T> {
+    fn hash(payload: Option<&Vec<u8>>) -> Result<Vec<u8>, ManyError> {
+        let payload = payload.ok_or_else(ManyError::empty_envelope)?;
         let mut hasher = sha2::Sha512::default();
         hasher.update(payload);
-        let hash = hasher.finalize();
+        Ok(hasher.finalize().to_vec())
+    }
 
-        if self.backend.has(hash.as_ref()) {
+    fn validate_payload(&self, payload: Option<&Vec<u8>>) -> Result<(), ManyError> {
+        if self.backend.has(&Self::hash(payload)?) {
             Err(ManyError::duplicated_message())
         } else {
             Ok(())
         }
     }
+}
+
+impl<T: RequestCacheBackend> RequestValidator for RequestCacheValidator<T> {
+    fn validate_envelope(&self, envelope: &CoseSign1) -> Result<(), ManyError> {
+        self.validate_payload(envelope.payload.as_ref())
+    }
 
     fn message_executed(
         &mut self,
         envelope: &CoseSign1,
         _response: &ResponseMessage,
     ) -> Result<(), ManyError> {
-        let payload = envelope
-            .payload
-            .as_ref()
-            .ok_or_else(ManyError::empty_envelope)?;
-        let mut hasher = sha2::Sha512::default();
-        hasher.update(payload);
-        let hash = hasher.finalize();
-        self.backend.put(hash.as_ref());
+        self.backend.put(&Self::hash(envelope.payload.as_ref())?);
+        Ok(())
+    }
+
+    // A request has the same payload in both kinds of envelope, so it cannot
+    // be replayed in the other kind.
+    fn validate_multi_envelope(&self, envelope: &CoseSign) -> Result<(), ManyError> {
+        self.validate_payload(envelope.payload.as_ref())
+    }
+
+    fn multi_message_executed(
+        &mut self,
+        envelope: &CoseSign,
+        _response: &ResponseMessage,
+    ) -> Result<(), ManyError> {
+        self.backend.put(&Self::hash(envelope.payload.as_ref())?);
         Ok(())
     }
 }
@@ -122,3 +137,51 @@ impl RequestCacheBackend for SharedRocksDbCacheBackend {
         self.inner.write().unwrap().put(key)
     }
 }
+
+/// Keeps the state of async requests in a RocksDB database, so they survive
+/// restarts of the server.
+pub struct RocksDbAsyncTokenStore {
+    db: rocksdb::DB,
+}
+
+impl RocksDbAsyncTokenStore {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        let db = rocksdb::DB::open_default(path).unwrap();
+        Self { db }
+    }
+}
+
+impl AsyncTokenStore for RocksDbAsyncTokenStore {
+    fn get(&self, token: &AsyncToken) -> Result<Option<AsyncTokenRecord>, ManyError> {
+        self.db
+            .get(token)
+            .map_err(|e| ManyError::unknown(e.to_string()))?
+            .map(|bytes| AsyncTokenRecord::from_bytes(&bytes))
+            .transpose()
+    }
+
+    fn put(&self, token: &AsyncToken, record: &AsyncTokenRecord) -> Result<(), ManyError> {
+        self.db
+            .put(token, record.to_bytes()?)
+            .map_err(|e| ManyError::unknown(e.to_string()))
+    }
+
+    fn remove(&self, token: &AsyncToken) -> Result<(), ManyError> {
+        self.db
+            .delete(token)
+            .map_err(|e| ManyError::unknown(e.to_string()))
+    }
+
+    fn records(&self) -> Result<Vec<(AsyncToken, AsyncTokenRecord)>, ManyError> {
+        self.db
+            .iterator(rocksdb::IteratorMode::Start)
+            .map(|item| {
+                let (key, value) = item.map_err(|e| ManyError::unknown(e.to_string()))?;
+                Ok((
+                    AsyncToken::from(key.to_vec()),
+                    AsyncTokenRecord::from_bytes(&value)?,
+                ))
+            })
+            .collect()
+    }
+}
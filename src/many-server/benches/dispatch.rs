@@ -0,0 +1,26 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use many_identity::AnonymousIdentity;
+use many_protocol::{encode_cose_sign1_from_request, RequestMessage};
+use many_server::transport::LowLevelManyRequestHandler;
+use many_server::ManyServer;
+use many_types::Timestamp;
+
+/// Measure the time to dispatch a request through the server, from an
+/// envelope to the signed response of the base module.
+fn dispatch(c: &mut Criterion) {
+    let server = ManyServer::test(AnonymousIdentity);
+
+    c.bench_function("dispatch", |b| {
+        b.iter(|| {
+            let request = RequestMessage {
+                timestamp: Some(Timestamp::now()),
+                ..RequestMessage::default().with_method("status".to_string())
+            };
+            let envelope = encode_cose_sign1_from_request(request, &AnonymousIdentity).unwrap();
+            smol::block_on(server.execute(black_box(envelope))).unwrap()
+        })
+    });
+}
+
+criterion_group!(benches, dispatch);
+criterion_main!(benches);
@@ -0,0 +1,320 @@
+use crate::registry::ModuleRegistry;
+use many_error::ManyError;
+use many_identity::Address;
+use many_modules::admin;
+use std::fmt::{Debug, Formatter};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+type LogLevelFn = Box<dyn Fn(&str) -> Result<String, ManyError> + Send>;
+type StorageStatsFn = Box<dyn Fn() -> Result<admin::StorageStats, ManyError> + Send>;
+type CompactFn = Box<dyn FnMut() -> Result<(), ManyError> + Send>;
+
+/// A backend for the admin module which only accepts requests from a single
+/// administrator address. Each operation is provided by the server; the ones
+/// that are not return an `unsupported_operation` error.
+pub struct AdminModuleImpl {
+    admin: Address,
+    config: String,
+    log_level: Option<LogLevelFn>,
+    storage_stats: Option<StorageStatsFn>,
+    compact: Option<CompactFn>,
+    term_signal: Option<Arc<AtomicBool>>,
+    module_registry: Option<ModuleRegistry>,
+}
+
+impl AdminModuleImpl {
+    pub fn new(admin: Address) -> Self {
+        Self {
+            admin,
+            config: String::new(),
+            log_level: None,
+            storage_stats: None,
+            compact: None,
+            term_signal: None,
+            module_registry: None,
+        }
+    }
+
+    /// The configuration returned by `admin.config`.
+    pub fn with_config(mut self, config: impl Debug) -> Self {
+        self.config = format!("{config:#?}");
+        self
+    }
+
+    /// A function setting the log level of the server and returning the
+    /// previous one.
+    pub fn with_log_level(
+        mut self,
+        log_level: impl Fn(&str) -> Result<String, ManyError> + Send + 'static,
+    ) -> Self {
+        self.log_level = Some(Box::new(log_level));
+        self
+    }
+
+    /// A function returning the statistics of the persistent storage of the
+    /// server.
+    pub fn with_storage_stats(
+        mut self,
+        storage_stats: impl Fn() -> Result<admin::StorageStats, ManyError> + Send + 'static,
+    ) -> Self {
+        self.storage_stats = Some(Box::new(storage_stats));
+        self
+    }
+
+    /// A function compacting the persistent storage of the server.
+    pub fn with_compaction(
+        mut self,
+        compact: impl FnMut() -> Result<(), ManyError> + Send + 'static,
+    ) -> Self {
+        self.compact = Some(Box::new(compact));
+        self
+    }
+
+    /// The term signal of the transport, which is set on `admin.shutdown`.
+    pub fn with_term_signal(mut self, term_signal: Arc<AtomicBool>) -> Self {
+        self.term_signal = Some(term_signal);
+        self
+    }
+
+    /// The optional modules which can be enabled and disabled.
+    pub fn with_module_registry(mut self, module_registry: ModuleRegistry) -> Self {
+        self.module_registry = Some(module_registry);
+        self
+    }
+
+    fn module_registry(&self) -> Result<&ModuleRegistry, ManyError> {
+        self.module_registry
+            .as_ref()
+            .ok_or_else(|| admin::unsupported_operation("managing modules"))
+    }
+
+    fn check_admin(&self, sender: &Address) -> Result<(), ManyError> {
+        if sender == &self.admin {
+            Ok(())
+        } else {
+            Err(admin::not_an_admin(sender.to_string()))
+        }
+    }
+}
+
+impl Debug for AdminModuleImpl {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AdminModuleImpl")
+            .field("admin", &self.admin)
+            .finish()
+    }
+}
+
+impl admin::AdminModuleBackend for AdminModuleImpl {
+    fn config(
+        &self,
+        sender: &Address,
+        _args: admin::ConfigArgs,
+    ) -> Result<admin::ConfigReturns, ManyError> {
+        self.check_admin(sender)?;
+        Ok(admin::ConfigReturns {
+            config: self.config.clone(),
+        })
+    }
+
+    fn set_log_level(
+        &mut self,
+        sender: &Address,
+        args: admin::SetLogLevelArgs,
+    ) -> Result<admin::SetLogLevelReturns, ManyError> {
+        self.check_admin(sender)?;
+        let level = args.level.to_lowercase();
+        if !admin::LOG_LEVELS.contains(&level.as_str()) {
+            return Err(admin::invalid_log_level(args.level));
+        }
+        let log_level = self
+            .log_level
+            .as_ref()
+            .ok_or_else(|| admin::unsupported_operation("changing the log level"))?;
+
+        let previous = log_level(&level)?;
+        tracing::info!("Log level changed from {previous} to {level} by {sender}");
+        Ok(admin::SetLogLevelReturns { previous })
+    }
+
+    fn storage_stats(
+        &self,
+        sender: &Address,
+        _args: admin::StorageStatsArgs,
+    ) -> Result<admin::StorageStatsReturns, ManyError> {
+        self.check_admin(sender)?;
+        let storage_stats = self
+            .storage_stats
+            .as_ref()
+            .ok_or_else(|| admin::unsupported_operation("storage statistics"))?;
+
+        storage_stats()
+    }
+
+    fn compact(
+        &mut self,
+        sender: &Address,
+        _args: admin::CompactArgs,
+    ) -> Result<admin::CompactReturns, ManyError> {
+        self.check_admin(sender)?;
+        let compact = self
+            .compact
+            .as_mut()
+            .ok_or_else(|| admin::unsupported_operation("storage compaction"))?;
+
+        compact()?;
+        Ok(admin::CompactReturns {})
+    }
+
+    fn shutdown(
+        &mut self,
+        sender: &Address,
+        _args: admin::ShutdownArgs,
+    ) -> Result<admin::ShutdownReturns, ManyError> {
+        self.check_admin(sender)?;
+        let term_signal = self
+            .term_signal
+            .as_ref()
+            .ok_or_else(|| admin::unsupported_operation("shutting down"))?;
+
+        tracing::info!("Shutdown requested by {sender}");
+        term_signal.store(true, Ordering::Relaxed);
+        Ok(admin::ShutdownReturns {})
+    }
+
+    fn modules(
+        &self,
+        sender: &Address,
+        _args: admin::ModulesArgs,
+    ) -> Result<admin::ModulesReturns, ManyError> {
+        self.check_admin(sender)?;
+        Ok(admin::ModulesReturns {
+            modules: self.module_registry()?.modules()?,
+        })
+    }
+
+    fn enable_module(
+        &mut self,
+        sender: &Address,
+        args: admin::EnableModuleArgs,
+    ) -> Result<admin::EnableModuleReturns, ManyError> {
+        self.check_admin(sender)?;
+        self.module_registry()?.enable(args.attribute)?;
+        tracing::info!("Module {} enabled by {sender}", args.attribute);
+        Ok(admin::EnableModuleReturns {})
+    }
+
+    fn disable_module(
+        &mut self,
+        sender: &Address,
+        args: admin::DisableModuleArgs,
+    ) -> Result<admin::DisableModuleReturns, ManyError> {
+        self.check_admin(sender)?;
+        self.module_registry()?.disable(args.attribute)?;
+        tracing::info!("Module {} disabled by {sender}", args.attribute);
+        Ok(admin::DisableModuleReturns {})
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use many_identity::testing::identity;
+    use many_modules::admin::AdminModuleBackend;
+    use many_modules::EmptyArg;
+
+    #[test]
+    fn only_admin() {
+        let mut admin = AdminModuleImpl::new(identity(1)).with_config("config");
+
+        let err = admin.config(&identity(2), EmptyArg).unwrap_err();
+        assert_eq!(err.code(), admin::not_an_admin("").code());
+        assert_eq!(
+            admin.config(&identity(1), EmptyArg).unwrap().config,
+            "\"config\""
+        );
+
+        let err = admin.shutdown(&identity(2), EmptyArg).unwrap_err();
+        assert_eq!(err.code(), admin::not_an_admin("").code());
+    }
+
+    #[test]
+    fn set_log_level() {
+        let mut admin = AdminModuleImpl::new(identity(1));
+        let args = |level: &str| admin::SetLogLevelArgs {
+            level: level.to_string(),
+        };
+
+        let err = admin.set_log_level(&identity(1), args("info")).unwrap_err();
+        assert_eq!(err.code(), admin::unsupported_operation("").code());
+
+        let mut admin = admin.with_log_level(|level| {
+            assert_eq!(level, "debug");
+            Ok("info".to_string())
+        });
+        let err = admin
+            .set_log_level(&identity(1), args("verbose"))
+            .unwrap_err();
+        assert_eq!(err.code(), admin::invalid_log_level("").code());
+
+        let returns = admin.set_log_level(&identity(1), args("DEBUG")).unwrap();
+        assert_eq!(returns.previous, "info");
+    }
+
+    #[test]
+    fn shutdown() {
+        let term_signal = Arc::new(AtomicBool::new(false));
+        let mut admin = AdminModuleImpl::new(identity(1)).with_term_signal(term_signal.clone());
+
+        admin.shutdown(&identity(1), EmptyArg).unwrap();
+        assert!(term_signal.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn modules() {
+        use many_modules::kvstore::{KvStoreTransferModule, KvStoreTransferModuleBackend};
+
+        #[derive(Debug)]
+        struct Transfer;
+        impl KvStoreTransferModuleBackend for Transfer {
+            fn transfer(
+                &mut self,
+                _sender: &Address,
+                _args: many_modules::kvstore::TransferArgs,
+            ) -> Result<many_modules::kvstore::TransferReturn, ManyError> {
+                Ok(many_modules::EmptyReturn)
+            }
+        }
+
+        let admin = AdminModuleImpl::new(identity(1));
+        let err = admin.modules(&identity(1), EmptyArg).unwrap_err();
+        assert_eq!(err.code(), admin::unsupported_operation("").code());
+
+        let server = crate::ManyServer::test(many_identity::AnonymousIdentity);
+        let mut registry = ModuleRegistry::new(&server);
+        registry
+            .register(KvStoreTransferModule::new(Arc::new(std::sync::Mutex::new(
+                Transfer,
+            ))))
+            .unwrap();
+        let mut admin = admin.with_module_registry(registry);
+
+        let err = admin
+            .enable_module(&identity(2), admin::EnableModuleArgs { attribute: 13 })
+            .unwrap_err();
+        assert_eq!(err.code(), admin::not_an_admin("").code());
+
+        admin
+            .enable_module(&identity(1), admin::EnableModuleArgs { attribute: 13 })
+            .unwrap();
+        assert!(admin.modules(&identity(1), EmptyArg).unwrap().modules[0].enabled);
+        assert!(server.lock().unwrap().module(13).is_some());
+
+        admin
+            .disable_module(&identity(1), admin::DisableModuleArgs { attribute: 13 })
+            .unwrap();
+        assert!(!admin.modules(&identity(1), EmptyArg).unwrap().modules[0].enabled);
+        assert!(server.lock().unwrap().module(13).is_none());
+    }
+}
@@ -0,0 +1,368 @@
+use coset::{CborSerializable, CoseSign1};
+use many_error::ManyError;
+use many_identity::Address;
+use many_modules::r#async::{AsyncModuleBackend, AsyncToken, StatusArgs, StatusReturn};
+use many_types::Timestamp;
+use minicbor::bytes::ByteVec;
+use minicbor::{Decode, Encode};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+/// The state of an async request.
+#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+pub enum AsyncTokenState {
+    #[n(0)]
+    Queued,
+
+    #[n(1)]
+    Processing,
+
+    /// The request was executed. Holds its encoded response envelope.
+    #[n(2)]
+    Done(#[n(0)] ByteVec),
+
+    /// The request expired before it was collected. Its response, if any,
+    /// was dropped.
+    #[n(3)]
+    Expired,
+}
+
+impl Display for AsyncTokenState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Queued => "queued",
+            Self::Processing => "processing",
+            Self::Done(_) => "done",
+            Self::Expired => "expired",
+        })
+    }
+}
+
+/// An async request, as kept by an [AsyncTokenStore].
+#[derive(Clone, Debug, Encode, Decode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct AsyncTokenRecord {
+    #[n(0)]
+    pub state: AsyncTokenState,
+
+    /// When the request expires, unless its state changes before.
+    #[n(1)]
+    pub expires_at: Timestamp,
+}
+
+impl AsyncTokenRecord {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, ManyError> {
+        minicbor::to_vec(self).map_err(ManyError::serialization_error)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ManyError> {
+        minicbor::decode(bytes).map_err(ManyError::deserialization_error)
+    }
+}
+
+/// Implement this trait to keep the state of async requests, see
+/// [AsyncTokens]. The store only reads and writes records, the transitions
+/// and their expiry are handled by [AsyncTokens].
+pub trait AsyncTokenStore: Send + Sync {
+    fn get(&self, token: &AsyncToken) -> Result<Option<AsyncTokenRecord>, ManyError>;
+
+    fn put(&self, token: &AsyncToken, record: &AsyncTokenRecord) -> Result<(), ManyError>;
+
+    fn remove(&self, token: &AsyncToken) -> Result<(), ManyError>;
+
+    /// All the records of the store, to collect the expired ones.
+    fn records(&self) -> Result<Vec<(AsyncToken, AsyncTokenRecord)>, ManyError>;
+}
+
+impl<T: AsyncTokenStore + ?Sized> AsyncTokenStore for Arc<T> {
+    fn get(&self, token: &AsyncToken) -> Result<Option<AsyncTokenRecord>, ManyError> {
+        self.as_ref().get(token)
+    }
+
+    fn put(&self, token: &AsyncToken, record: &AsyncTokenRecord) -> Result<(), ManyError> {
+        self.as_ref().put(token, record)
+    }
+
+    fn remove(&self, token: &AsyncToken) -> Result<(), ManyError> {
+        self.as_ref().remove(token)
+    }
+
+    fn records(&self) -> Result<Vec<(AsyncToken, AsyncTokenRecord)>, ManyError> {
+        self.as_ref().records()
+    }
+}
+
+/// A store keeping the async requests in memory, lost when the server
+/// stops.
+#[derive(Debug, Default)]
+pub struct MemoryAsyncTokenStore {
+    records: Mutex<BTreeMap<Vec<u8>, AsyncTokenRecord>>,
+}
+
+impl AsyncTokenStore for MemoryAsyncTokenStore {
+    fn get(&self, token: &AsyncToken) -> Result<Option<AsyncTokenRecord>, ManyError> {
+        Ok(self.records.lock().unwrap().get(token.as_ref()).cloned())
+    }
+
+    fn put(&self, token: &AsyncToken, record: &AsyncTokenRecord) -> Result<(), ManyError> {
+        self.records
+            .lock()
+            .unwrap()
+            .insert(token.as_ref().to_vec(), record.clone());
+        Ok(())
+    }
+
+    fn remove(&self, token: &AsyncToken) -> Result<(), ManyError> {
+        self.records.lock().unwrap().remove(token.as_ref());
+        Ok(())
+    }
+
+    fn records(&self) -> Result<Vec<(AsyncToken, AsyncTokenRecord)>, ManyError> {
+        Ok(self
+            .records
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(token, record)| (AsyncToken::from(token.clone()), record.clone()))
+            .collect())
+    }
+}
+
+struct AsyncTokensInner {
+    store: Box<dyn AsyncTokenStore>,
+    ttl: Duration,
+
+    /// Tokens are the time this was created followed by a counter, so they
+    /// are not issued twice across restarts.
+    seed: u64,
+    counter: AtomicU64,
+
+    last_collection: Mutex<Timestamp>,
+}
+
+/// Issues async tokens and tracks the state of their requests in a store.
+/// A request expires when its state did not change for the TTL. Add it to a
+/// server with [crate::ManyServer::set_async_tokens] to serve `async.status`.
+#[derive(Clone)]
+pub struct AsyncTokens {
+    inner: Arc<AsyncTokensInner>,
+}
+
+impl AsyncTokens {
+    pub fn new(store: impl AsyncTokenStore + 'static, ttl: Duration) -> Self {
+        let seed = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+        Self {
+            inner: Arc::new(AsyncTokensInner {
+                store: Box::new(store),
+                ttl,
+                seed,
+                counter: AtomicU64::new(0),
+                last_collection: Mutex::new(Timestamp::now()),
+            }),
+        }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.inner.ttl
+    }
+
+    fn record(&self, state: AsyncTokenState) -> AsyncTokenRecord {
+        AsyncTokenRecord {
+            state,
+            expires_at: Timestamp::now()
+                .checked_add(self.inner.ttl)
+                .expect("The TTL of async tokens is out of range."),
+        }
+    }
+
+    /// Issue a token for a new request, queued. Expired requests are
+    /// collected at most once per TTL while issuing tokens.
+    pub fn issue(&self) -> Result<AsyncToken, ManyError> {
+        self.maybe_collect_garbage()?;
+
+        let counter = self.inner.counter.fetch_add(1, Ordering::Relaxed);
+        let mut token = self.inner.seed.to_be_bytes().to_vec();
+        token.extend_from_slice(&counter.to_be_bytes());
+        let token = AsyncToken::from(token);
+
+        self.inner
+            .store
+            .put(&token, &self.record(AsyncTokenState::Queued))?;
+        Ok(token)
+    }
+
+    fn transition(&self, token: &AsyncToken, to: AsyncTokenState) -> Result<(), ManyError> {
+        let record = self
+            .inner
+            .store
+            .get(token)?
+            .ok_or_else(|| ManyError::unknown_async_token(hex::encode(token)))?;
+        let from = self.current_state(record);
+
+        match (&from, &to) {
+            (AsyncTokenState::Queued, AsyncTokenState::Processing)
+            | (AsyncTokenState::Queued | AsyncTokenState::Processing, AsyncTokenState::Done(_)) => {
+                self.inner.store.put(token, &self.record(to))
+            }
+            _ => Err(ManyError::invalid_async_transition(from, to)),
+        }
+    }
+
+    /// The request of a token started executing.
+    pub fn processing(&self, token: &AsyncToken) -> Result<(), ManyError> {
+        self.transition(token, AsyncTokenState::Processing)
+    }
+
+    /// The request of a token was executed. Its response is kept for the
+    /// TTL.
+    pub fn done(&self, token: &AsyncToken, response: CoseSign1) -> Result<(), ManyError> {
+        let response = response
+            .to_vec()
+            .map_err(|e| ManyError::serialization_error(e.to_string()))?;
+        self.transition(token, AsyncTokenState::Done(response.into()))
+    }
+
+    /// The state of a record, expired if its TTL passed.
+    fn current_state(&self, record: AsyncTokenRecord) -> AsyncTokenState {
+        if record.expires_at <= Timestamp::now() {
+            AsyncTokenState::Expired
+        } else {
+            record.state
+        }
+    }
+
+    /// The state of the request of a token, or [None] if the token is
+    /// unknown or was collected.
+    pub fn state(&self, token: &AsyncToken) -> Result<Option<AsyncTokenState>, ManyError> {
+        Ok(self
+            .inner
+            .store
+            .get(token)?
+            .map(|record| self.current_state(record)))
+    }
+
+    /// Mark the requests whose TTL passed as expired, dropping their
+    /// responses, and remove the ones which expired more than a TTL ago.
+    /// Returns the number of requests removed.
+    pub fn collect_garbage(&self) -> Result<usize, ManyError> {
+        let now = Timestamp::now();
+        *self.inner.last_collection.lock().unwrap() = now;
+
+        let mut removed = 0;
+        for (token, record) in self.inner.store.records()? {
+            if record.expires_at > now {
+                continue;
+            }
+            if record.state == AsyncTokenState::Expired {
+                self.inner.store.remove(&token)?;
+                removed += 1;
+            } else {
+                self.inner
+                    .store
+                    .put(&token, &self.record(AsyncTokenState::Expired))?;
+            }
+        }
+        if removed > 0 {
+            tracing::debug!(removed, "Collected expired async tokens");
+        }
+        Ok(removed)
+    }
+
+    fn maybe_collect_garbage(&self) -> Result<(), ManyError> {
+        let last = *self.inner.last_collection.lock().unwrap();
+        match last.checked_add(self.inner.ttl) {
+            Some(next) if next > Timestamp::now() => Ok(()),
+            _ => self.collect_garbage().map(|_| ()),
+        }
+    }
+}
+
+impl AsyncModuleBackend for AsyncTokens {
+    fn status(&self, _sender: &Address, args: StatusArgs) -> Result<StatusReturn, ManyError> {
+        Ok(match self.state(&args.token)? {
+            None => StatusReturn::Unknown,
+            Some(AsyncTokenState::Queued) => StatusReturn::Queued,
+            Some(AsyncTokenState::Processing) => StatusReturn::Processing,
+            Some(AsyncTokenState::Done(response)) => StatusReturn::Done {
+                response: Box::new(
+                    CoseSign1::from_slice(&response)
+                        .map_err(|e| ManyError::deserialization_error(e.to_string()))?,
+                ),
+            },
+            Some(AsyncTokenState::Expired) => StatusReturn::Expired,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use many_identity::AnonymousIdentity;
+    use many_protocol::{encode_cose_sign1_from_response, ResponseMessage};
+
+    fn response() -> CoseSign1 {
+        encode_cose_sign1_from_response(ResponseMessage::default(), &AnonymousIdentity).unwrap()
+    }
+
+    #[test]
+    fn transitions() {
+        let tokens = AsyncTokens::new(MemoryAsyncTokenStore::default(), Duration::from_secs(60));
+        let token = tokens.issue().unwrap();
+        assert_ne!(tokens.issue().unwrap(), token);
+        assert_eq!(tokens.state(&token), Ok(Some(AsyncTokenState::Queued)));
+
+        tokens.processing(&token).unwrap();
+        assert_eq!(tokens.state(&token), Ok(Some(AsyncTokenState::Processing)));
+        assert_eq!(
+            tokens.processing(&token).unwrap_err().code(),
+            ManyError::invalid_async_transition("", "").code()
+        );
+
+        tokens.done(&token, response()).unwrap();
+        let status = tokens
+            .status(&Address::anonymous(), StatusArgs { token })
+            .unwrap();
+        assert!(matches!(status, StatusReturn::Done { .. }));
+
+        let unknown = AsyncToken::from(vec![1, 2, 3]);
+        assert_eq!(
+            tokens.processing(&unknown).unwrap_err().code(),
+            ManyError::unknown_async_token("").code()
+        );
+        assert!(matches!(
+            tokens.status(&Address::anonymous(), StatusArgs { token: unknown }),
+            Ok(StatusReturn::Unknown)
+        ));
+    }
+
+    #[test]
+    fn expiry() {
+        let store = Arc::new(MemoryAsyncTokenStore::default());
+        let tokens = AsyncTokens::new(store.clone(), Duration::ZERO);
+        let token = tokens.issue().unwrap();
+        assert_eq!(tokens.state(&token), Ok(Some(AsyncTokenState::Expired)));
+        assert!(tokens.done(&token, response()).is_err());
+
+        // Expired requests are marked, then removed a TTL later.
+        assert_eq!(tokens.collect_garbage(), Ok(0));
+        assert_eq!(
+            store.get(&token).unwrap().map(|r| r.state),
+            Some(AsyncTokenState::Expired)
+        );
+        assert_eq!(tokens.collect_garbage(), Ok(1));
+        assert_eq!(tokens.state(&token), Ok(None));
+    }
+
+    #[test]
+    fn served() {
+        let tokens = AsyncTokens::new(MemoryAsyncTokenStore::default(), Duration::from_secs(60));
+        let server = crate::ManyServer::test(AnonymousIdentity);
+        server.lock().unwrap().set_async_tokens(tokens);
+        assert!(server.lock().unwrap().has_endpoint("async.status"));
+    }
+}
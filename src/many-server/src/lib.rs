@@ -1,4 +1,14 @@
+pub mod admin;
+pub mod async_store;
+pub mod panic;
+pub mod queue;
+pub mod quota;
+pub mod registry;
+pub mod request_log;
 pub mod server;
+pub mod signers;
+pub mod simulation;
+pub mod transaction;
 pub mod transport;
 pub mod validator;
 
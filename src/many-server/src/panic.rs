@@ -0,0 +1,117 @@
+use many_error::ManyError;
+use std::any::Any;
+use std::future::Future;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll};
+
+/// What a server does when a module panics while executing a request.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum PanicPolicy {
+    /// Respond with an error and keep serving requests.
+    ///
+    /// The panic poisons the mutex of the module backend, so the later
+    /// requests to that module fail too, and writes of the backend may be
+    /// half applied. Servers replicating their state, like ABCI nodes, should
+    /// abort instead.
+    #[default]
+    Isolate,
+
+    /// Abort the process, e.g. when the state of the backend cannot be
+    /// trusted after a partial execution.
+    Abort,
+}
+
+/// Catches the panics of module executions, and counts them.
+///
+/// Panics are logged at the `error` level, with the `many_server::panics`
+/// target.
+#[derive(Debug, Default)]
+pub struct PanicGuard {
+    policy: PanicPolicy,
+    count: AtomicU64,
+}
+
+impl PanicGuard {
+    pub fn new(policy: PanicPolicy) -> Self {
+        Self {
+            policy,
+            count: AtomicU64::new(0),
+        }
+    }
+
+    pub fn policy(&self) -> PanicPolicy {
+        self.policy
+    }
+
+    /// The number of panics caught since the server started.
+    pub fn count(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    /// Run the execution of `method`, turning a panic into an error.
+    pub async fn run<T>(
+        &self,
+        method: &str,
+        execution: impl Future<Output = Result<T, ManyError>>,
+    ) -> Result<T, ManyError> {
+        match CatchUnwind(Box::pin(execution)).await {
+            Ok(result) => result,
+            Err(payload) => {
+                self.count.fetch_add(1, Ordering::Relaxed);
+                tracing::error!(
+                    target: "many_server::panics",
+                    method,
+                    "Module panicked: {}",
+                    panic_message(payload.as_ref()),
+                );
+                if self.policy == PanicPolicy::Abort {
+                    std::process::abort();
+                }
+                Err(ManyError::module_panicked(method))
+            }
+        }
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("<unknown>")
+}
+
+/// A future resolving to the panic payload if polling `F` panics.
+struct CatchUnwind<F>(F);
+
+impl<F: Future + Unpin> Future for CatchUnwind<F> {
+    type Output = Result<F::Output, Box<dyn Any + Send>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match catch_unwind(AssertUnwindSafe(|| Pin::new(&mut self.0).poll(cx))) {
+            Ok(poll) => poll.map(Ok),
+            Err(payload) => Poll::Ready(Err(payload)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn isolate() {
+        let guard = PanicGuard::default();
+        let ok: Result<u8, _> = smol::block_on(guard.run("a", async { Ok(1) }));
+        assert_eq!(ok.unwrap(), 1);
+
+        let err: Result<u8, _> = smol::block_on(guard.run("b", async { panic!("oops") }));
+        assert_eq!(
+            err.unwrap_err().code(),
+            ManyError::module_panicked("b").code()
+        );
+        assert_eq!(guard.count(), 1);
+    }
+}
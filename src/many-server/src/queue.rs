@@ -0,0 +1,264 @@
+use many_identity::Address;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{Context, Poll, Waker};
+
+/// The depth of an [ExecutionQueue] at one point in time.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct QueueMetrics {
+    /// Executions currently running.
+    pub running: usize,
+
+    /// Executions waiting for a slot.
+    pub waiting: usize,
+
+    /// Executions waiting for a slot, per sender. Senders without waiting
+    /// executions are omitted.
+    pub waiting_per_sender: BTreeMap<Address, usize>,
+}
+
+#[derive(Default)]
+struct QueueState {
+    running: usize,
+    next_ticket: u64,
+
+    /// Waiting tickets of each sender, oldest first.
+    waiting: BTreeMap<Address, VecDeque<u64>>,
+
+    /// Senders with waiting tickets, in the order they are served.
+    turns: VecDeque<Address>,
+
+    /// Tickets given a slot, which their future did not return yet.
+    granted: BTreeSet<u64>,
+    wakers: BTreeMap<u64, Waker>,
+}
+
+impl QueueState {
+    /// Give the free slots to the waiting tickets, one sender at a time.
+    fn dispatch(&mut self, slots: usize) {
+        while self.running < slots {
+            let Some(sender) = self.turns.pop_front() else {
+                break;
+            };
+            let Some(tickets) = self.waiting.get_mut(&sender) else {
+                continue;
+            };
+            let Some(ticket) = tickets.pop_front() else {
+                continue;
+            };
+            if tickets.is_empty() {
+                self.waiting.remove(&sender);
+            } else {
+                self.turns.push_back(sender);
+            }
+
+            self.running += 1;
+            self.granted.insert(ticket);
+            if let Some(waker) = self.wakers.remove(&ticket) {
+                waker.wake();
+            }
+        }
+    }
+
+    fn release(&mut self, slots: usize) {
+        self.running -= 1;
+        self.dispatch(slots);
+    }
+}
+
+/// Limits the number of module executions running at once, and serves the
+/// executions waiting for a slot round-robin across senders, so a sender
+/// flooding the server only delays its own requests.
+pub struct ExecutionQueue {
+    slots: usize,
+    state: Mutex<QueueState>,
+}
+
+impl ExecutionQueue {
+    /// A queue running at most `slots` executions at once.
+    pub fn new(slots: usize) -> Self {
+        assert!(slots > 0, "An execution queue needs at least one slot.");
+        Self {
+            slots,
+            state: Mutex::new(QueueState::default()),
+        }
+    }
+
+    pub fn slots(&self) -> usize {
+        self.slots
+    }
+
+    pub fn metrics(&self) -> QueueMetrics {
+        let state = self.state.lock().unwrap();
+        let waiting_per_sender: BTreeMap<Address, usize> = state
+            .waiting
+            .iter()
+            .map(|(sender, tickets)| (*sender, tickets.len()))
+            .collect();
+        QueueMetrics {
+            running: state.running,
+            waiting: waiting_per_sender.values().sum(),
+            waiting_per_sender,
+        }
+    }
+
+    /// Wait for a slot to execute a request of `sender`. The slot is freed
+    /// when the returned guard is dropped.
+    pub fn acquire(&self, sender: Address) -> Acquire<'_> {
+        let mut guard = self.state.lock().unwrap();
+        let state = &mut *guard;
+        let ticket = state.next_ticket;
+        state.next_ticket += 1;
+
+        if state.running < self.slots && state.turns.is_empty() {
+            state.running += 1;
+            state.granted.insert(ticket);
+        } else {
+            let tickets = state.waiting.entry(sender).or_default();
+            tickets.push_back(ticket);
+            if tickets.len() == 1 {
+                state.turns.push_back(sender);
+            }
+            tracing::trace!(
+                target: "many_server::queue",
+                %sender,
+                waiting = tickets.len(),
+                "Execution queued"
+            );
+        }
+
+        Acquire {
+            queue: self,
+            ticket: Some(ticket),
+        }
+    }
+}
+
+/// The future returned by [ExecutionQueue::acquire].
+pub struct Acquire<'a> {
+    queue: &'a ExecutionQueue,
+
+    /// The ticket waiting for a slot, or [None] once the slot is taken.
+    ticket: Option<u64>,
+}
+
+impl<'a> Future for Acquire<'a> {
+    type Output = QueueSlot<'a>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let queue = self.queue;
+        if let Some(ticket) = self.ticket {
+            let mut state = queue.state.lock().unwrap();
+            if !state.granted.remove(&ticket) {
+                state.wakers.insert(ticket, cx.waker().clone());
+                return Poll::Pending;
+            }
+        }
+        self.ticket = None;
+        Poll::Ready(QueueSlot { queue })
+    }
+}
+
+impl Drop for Acquire<'_> {
+    fn drop(&mut self) {
+        let Some(ticket) = self.ticket else {
+            return;
+        };
+
+        // The request was dropped before it got its slot, e.g. because its
+        // connection closed.
+        let mut state = self.queue.state.lock().unwrap();
+        state.wakers.remove(&ticket);
+        if state.granted.remove(&ticket) {
+            state.release(self.queue.slots);
+            return;
+        }
+        let sender = state.waiting.iter_mut().find_map(|(sender, tickets)| {
+            let index = tickets.iter().position(|t| *t == ticket)?;
+            tickets.remove(index);
+            Some((*sender, tickets.is_empty()))
+        });
+        if let Some((sender, true)) = sender {
+            state.waiting.remove(&sender);
+            state.turns.retain(|s| *s != sender);
+        }
+    }
+}
+
+/// A slot of an [ExecutionQueue], freed on drop.
+pub struct QueueSlot<'a> {
+    queue: &'a ExecutionQueue,
+}
+
+impl Drop for QueueSlot<'_> {
+    fn drop(&mut self) {
+        self.queue.state.lock().unwrap().release(self.queue.slots);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use many_identity::testing::identity;
+
+    fn poll<F: Future + Unpin>(future: &mut F) -> Poll<F::Output> {
+        struct Noop;
+        impl std::task::Wake for Noop {
+            fn wake(self: std::sync::Arc<Self>) {}
+        }
+        let waker = Waker::from(std::sync::Arc::new(Noop));
+        Pin::new(future).poll(&mut Context::from_waker(&waker))
+    }
+
+    #[test]
+    fn round_robin() {
+        let queue = ExecutionQueue::new(1);
+        let (flood, other) = (identity(1), identity(2));
+
+        let running = smol::block_on(queue.acquire(flood));
+        let mut flooding: Vec<_> = (0..3).map(|_| queue.acquire(flood)).collect();
+        let mut waiting = queue.acquire(other);
+        assert_eq!(
+            queue.metrics(),
+            QueueMetrics {
+                running: 1,
+                waiting: 4,
+                waiting_per_sender: BTreeMap::from([(flood, 3), (other, 1)]),
+            }
+        );
+
+        // The other sender is served after a single request of the flood.
+        drop(running);
+        let slot = match poll(&mut flooding[0]) {
+            Poll::Ready(slot) => slot,
+            Poll::Pending => panic!("The first queued request should run."),
+        };
+        assert!(poll(&mut waiting).is_pending());
+        drop(slot);
+        assert!(poll(&mut flooding[1]).is_pending());
+        let slot = poll(&mut waiting);
+        assert!(slot.is_ready());
+        assert_eq!(
+            queue.metrics().waiting_per_sender,
+            BTreeMap::from([(flood, 2)])
+        );
+    }
+
+    #[test]
+    fn cancelled() {
+        let queue = ExecutionQueue::new(1);
+        let running = smol::block_on(queue.acquire(identity(1)));
+        let waiting = queue.acquire(identity(2));
+        drop(waiting);
+        assert_eq!(queue.metrics().waiting, 0);
+
+        // A slot granted to a dropped request is given back.
+        let waiting = queue.acquire(identity(2));
+        drop(running);
+        assert_eq!(queue.metrics().running, 1);
+        drop(waiting);
+        assert_eq!(queue.metrics(), QueueMetrics::default());
+    }
+}
@@ -0,0 +1,279 @@
+use crate::RequestValidator;
+use many_error::ManyError;
+use many_identity::Address;
+use many_protocol::RequestMessage;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Limits applied to every identity over a window of time. A [None] limit is
+/// not enforced.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Quota {
+    pub window: Duration,
+    pub max_bytes: Option<u64>,
+    pub max_execution_time: Option<Duration>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Usage {
+    window_start: Instant,
+    bytes: u64,
+    execution_time: Duration,
+}
+
+#[derive(Default)]
+struct QuotaState {
+    usage: BTreeMap<Address, Usage>,
+    pruned_at: Option<Instant>,
+}
+
+/// A [RequestValidator] that accounts the argument bytes and the execution
+/// time used by each sender, and denies requests from senders that went over
+/// their [Quota] until the current window ends.
+///
+/// Execution time is only known after a request was executed, so a sender
+/// can go over the execution time quota with its last request of a window.
+///
+/// Windows start at the time of each node, so the validator must not be used
+/// by the nodes of a blockchain, which would disagree on the denied requests.
+pub struct QuotaValidator {
+    quota: Quota,
+    exempt: BTreeSet<Address>,
+    state: Mutex<QuotaState>,
+    clock: Arc<dyn Fn() -> Instant + Send + Sync>,
+}
+
+impl QuotaValidator {
+    pub fn new(quota: Quota) -> Self {
+        Self {
+            quota,
+            exempt: BTreeSet::new(),
+            state: Mutex::new(QuotaState::default()),
+            clock: Arc::new(Instant::now),
+        }
+    }
+
+    /// Senders that are never denied, e.g. the node operators.
+    pub fn with_exempt(mut self, exempt: impl IntoIterator<Item = Address>) -> Self {
+        self.exempt.extend(exempt);
+        self
+    }
+
+    pub fn set_clock<T>(&mut self, clock: T)
+    where
+        T: Fn() -> Instant + Send + Sync + 'static,
+    {
+        self.clock = Arc::new(clock);
+    }
+
+    /// The bytes and execution time used by an identity in its current window.
+    pub fn usage(&self, address: &Address) -> (u64, Duration) {
+        let now = (self.clock)();
+        let state = self.state.lock().unwrap();
+        state
+            .usage
+            .get(address)
+            .filter(|u| now.saturating_duration_since(u.window_start) < self.quota.window)
+            .map_or((0, Duration::ZERO), |u| (u.bytes, u.execution_time))
+    }
+
+    /// The number of identities with a usage in memory.
+    pub fn tracked(&self) -> usize {
+        self.state.lock().unwrap().usage.len()
+    }
+}
+
+impl RequestValidator for QuotaValidator {
+    fn validate_request(&self, request: &RequestMessage) -> Result<(), ManyError> {
+        let from = request.from();
+        if self.exempt.contains(&from) {
+            return Ok(());
+        }
+
+        let now = (self.clock)();
+        let window = self.quota.window;
+        let mut state = self.state.lock().unwrap();
+
+        // Forget the senders whose window ended, at most once per window so
+        // pruning stays cheap.
+        if state
+            .pruned_at
+            .map_or(true, |at| now.saturating_duration_since(at) >= window)
+        {
+            state
+                .usage
+                .retain(|_, u| now.saturating_duration_since(u.window_start) < window);
+            state.pruned_at = Some(now);
+        }
+
+        let usage = state.usage.entry(from).or_insert(Usage {
+            window_start: now,
+            bytes: 0,
+            execution_time: Duration::ZERO,
+        });
+        if now.saturating_duration_since(usage.window_start) >= window {
+            *usage = Usage {
+                window_start: now,
+                bytes: 0,
+                execution_time: Duration::ZERO,
+            };
+        }
+
+        let retry = (usage.window_start + window)
+            .saturating_duration_since(now)
+            .as_secs()
+            .max(1);
+        let bytes = usage.bytes + request.data.len() as u64;
+        if self.quota.max_bytes.map_or(false, |max| bytes > max) {
+            return Err(ManyError::quota_exceeded("bytes", retry));
+        }
+        if self
+            .quota
+            .max_execution_time
+            .map_or(false, |max| usage.execution_time >= max)
+        {
+            return Err(ManyError::quota_exceeded("execution time", retry));
+        }
+
+        usage.bytes = bytes;
+        Ok(())
+    }
+
+    fn request_timed(&mut self, request: &RequestMessage, execution_time: Duration) {
+        let state = self.state.get_mut().unwrap();
+        if let Some(usage) = state.usage.get_mut(&request.from()) {
+            usage.execution_time += execution_time;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use many_error::ManyErrorCode;
+    use many_identity::testing::identity;
+    use many_protocol::RequestMessageBuilder;
+
+    struct TestClock(Arc<Mutex<Instant>>);
+
+    impl TestClock {
+        fn advance(&self, duration: Duration) {
+            *self.0.lock().unwrap() += duration;
+        }
+    }
+
+    fn validator(quota: Quota) -> (QuotaValidator, TestClock) {
+        let now = Arc::new(Mutex::new(Instant::now()));
+        let mut validator = QuotaValidator::new(quota);
+        let clock = now.clone();
+        validator.set_clock(move || *clock.lock().unwrap());
+        (validator, TestClock(now))
+    }
+
+    fn request(from: u32, nonce: u8, data: Vec<u8>) -> RequestMessage {
+        RequestMessageBuilder::default()
+            .from(identity(from))
+            .method("test".to_string())
+            .nonce(vec![nonce])
+            .data(data)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn bytes() {
+        let (validator, clock) = validator(Quota {
+            window: Duration::from_secs(60),
+            max_bytes: Some(10),
+            max_execution_time: None,
+        });
+
+        assert!(validator
+            .validate_request(&request(1, 0, vec![0; 6]))
+            .is_ok());
+        let err = validator
+            .validate_request(&request(1, 1, vec![0; 6]))
+            .unwrap_err();
+        assert_eq!(err.code(), ManyErrorCode::QuotaExceeded);
+        assert_eq!(err.argument("resource"), Some("bytes"));
+        assert_eq!(validator.usage(&identity(1)), (6, Duration::ZERO));
+
+        // Other identities have their own quota.
+        assert!(validator
+            .validate_request(&request(2, 0, vec![0; 6]))
+            .is_ok());
+
+        // The quota resets with the window.
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(validator.usage(&identity(1)), (0, Duration::ZERO));
+        assert!(validator
+            .validate_request(&request(1, 1, vec![0; 6]))
+            .is_ok());
+    }
+
+    #[test]
+    fn execution_time() {
+        let (mut validator, clock) = validator(Quota {
+            window: Duration::from_secs(60),
+            max_bytes: None,
+            max_execution_time: Some(Duration::from_secs(2)),
+        });
+
+        for nonce in 0..2 {
+            let request = request(1, nonce, vec![]);
+            assert!(validator.validate_request(&request).is_ok());
+            clock.advance(Duration::from_secs(1));
+            validator.request_timed(&request, Duration::from_secs(1));
+        }
+        assert_eq!(validator.usage(&identity(1)), (0, Duration::from_secs(2)));
+
+        let err = validator
+            .validate_request(&request(1, 2, vec![]))
+            .unwrap_err();
+        assert_eq!(err.argument("resource"), Some("execution time"));
+        assert_eq!(err.argument("retry"), Some("58"));
+    }
+
+    #[test]
+    fn prunes_expired_usage() {
+        let (validator, clock) = validator(Quota {
+            window: Duration::from_secs(60),
+            max_bytes: Some(10),
+            max_execution_time: None,
+        });
+
+        for from in 1..=3 {
+            assert!(validator
+                .validate_request(&request(from, 0, vec![]))
+                .is_ok());
+        }
+        assert_eq!(validator.tracked(), 3);
+
+        clock.advance(Duration::from_secs(30));
+        assert!(validator.validate_request(&request(4, 0, vec![])).is_ok());
+        assert_eq!(validator.tracked(), 4);
+
+        // Only the sender whose window did not end yet is kept.
+        clock.advance(Duration::from_secs(30));
+        assert!(validator.validate_request(&request(5, 0, vec![])).is_ok());
+        assert_eq!(validator.tracked(), 2);
+    }
+
+    #[test]
+    fn exempt() {
+        let (validator, _) = validator(Quota {
+            window: Duration::from_secs(60),
+            max_bytes: Some(1),
+            max_execution_time: None,
+        });
+        let validator = validator.with_exempt([identity(1)]);
+
+        assert!(validator
+            .validate_request(&request(1, 0, vec![0; 6]))
+            .is_ok());
+        assert!(validator
+            .validate_request(&request(2, 0, vec![0; 6]))
+            .is_err());
+    }
+}
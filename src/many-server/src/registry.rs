@@ -0,0 +1,242 @@
+use crate::ManyServer;
+use many_error::ManyError;
+use many_modules::{admin, ManyModule};
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex, Weak};
+
+/// A set of optional modules which can be added to or removed from a running
+/// server by attribute, e.g. through the admin module. Which ones are enabled
+/// at startup can then come from the configuration of the node instead of
+/// its code.
+///
+/// The registry only holds a weak reference to the server, so it can be kept
+/// by a module of that same server.
+pub struct ModuleRegistry {
+    server: Weak<Mutex<ManyServer>>,
+    modules: BTreeMap<u32, Arc<dyn ManyModule + Send>>,
+}
+
+impl ModuleRegistry {
+    pub fn new(server: &Arc<Mutex<ManyServer>>) -> Self {
+        Self {
+            server: Arc::downgrade(server),
+            modules: BTreeMap::new(),
+        }
+    }
+
+    /// Register a module which can later be enabled. The module is not added
+    /// to the server. Only modules implementing an attribute can be
+    /// registered, and only one per attribute.
+    pub fn register<M>(&mut self, module: M) -> Result<&mut Self, ManyError>
+    where
+        M: ManyModule + 'static,
+    {
+        let info = module.info();
+        let id = info
+            .attribute
+            .as_ref()
+            .map(|a| a.id)
+            .ok_or_else(|| admin::module_conflict(&info.name))?;
+        if self.modules.contains_key(&id) {
+            return Err(admin::module_conflict(&info.name));
+        }
+
+        self.modules.insert(id, Arc::new(module));
+        Ok(self)
+    }
+
+    /// The attributes of the registered modules.
+    pub fn attributes(&self) -> impl Iterator<Item = u32> + '_ {
+        self.modules.keys().copied()
+    }
+
+    fn server(&self) -> Result<Arc<Mutex<ManyServer>>, ManyError> {
+        self.server
+            .upgrade()
+            .ok_or_else(ManyError::internal_server_error)
+    }
+
+    fn get(&self, attribute_id: u32) -> Result<&Arc<dyn ManyModule + Send>, ManyError> {
+        self.modules
+            .get(&attribute_id)
+            .ok_or_else(|| admin::unknown_module(attribute_id))
+    }
+
+    /// Whether the server is running this registered module.
+    fn is_enabled(server: &ManyServer, module: &Arc<dyn ManyModule + Send>, id: u32) -> bool {
+        server.module(id).map_or(false, |m| Arc::ptr_eq(&m, module))
+    }
+
+    /// Add a registered module to the server. Enabling a module which is
+    /// already enabled does nothing.
+    pub fn enable(&self, attribute_id: u32) -> Result<(), ManyError> {
+        let module = self.get(attribute_id)?;
+        let server = self.server()?;
+        let mut server = server.lock().unwrap();
+
+        server
+            .try_add_module_arc(module.clone())
+            .map_err(|conflict| admin::module_conflict(conflict.module))?;
+        Ok(())
+    }
+
+    /// Remove a registered module from the server. Disabling a module which
+    /// is not enabled does nothing.
+    pub fn disable(&self, attribute_id: u32) -> Result<(), ManyError> {
+        let module = self.get(attribute_id)?;
+        let server = self.server()?;
+        let mut server = server.lock().unwrap();
+
+        if Self::is_enabled(&server, module, attribute_id) {
+            server.remove_module(attribute_id);
+        }
+        Ok(())
+    }
+
+    /// The registered modules, and whether they are enabled.
+    pub fn modules(&self) -> Result<Vec<admin::ModuleStatus>, ManyError> {
+        let server = self.server()?;
+        let server = server.lock().unwrap();
+
+        Ok(self
+            .modules
+            .iter()
+            .map(|(id, module)| admin::ModuleStatus {
+                attribute: *id,
+                name: module.info().name.clone(),
+                enabled: Self::is_enabled(&server, module, *id),
+            })
+            .collect())
+    }
+}
+
+impl Debug for ModuleRegistry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ModuleRegistry")
+            .field("modules", &self.modules.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::LowLevelManyRequestHandler;
+    use many_identity::{AcceptAllVerifier, Address, AnonymousIdentity, Identity};
+    use many_identity_dsa::ed25519::generate_random_ed25519_identity;
+    use many_modules::base::BaseModuleBackend;
+    use many_modules::kvstore::{
+        KvStoreTransferModule, KvStoreTransferModuleBackend, TransferArgs, TransferReturn,
+    };
+    use many_protocol::{
+        decode_response_from_cose_sign1, encode_cose_sign1_from_request, RequestMessageBuilder,
+    };
+
+    #[derive(Debug)]
+    struct Transfer;
+
+    impl KvStoreTransferModuleBackend for Transfer {
+        fn transfer(
+            &mut self,
+            _sender: &Address,
+            _args: TransferArgs,
+        ) -> Result<TransferReturn, ManyError> {
+            Ok(many_modules::EmptyReturn)
+        }
+    }
+
+    fn transfer_module() -> KvStoreTransferModule<Transfer> {
+        KvStoreTransferModule::new(Arc::new(Mutex::new(Transfer)))
+    }
+
+    fn transfer(server: &Arc<Mutex<ManyServer>>) -> Result<(), ManyError> {
+        let id = generate_random_ed25519_identity();
+        let args = TransferArgs {
+            key: vec![1].into(),
+            alternative_owner: None,
+            new_owner: Address::anonymous(),
+        };
+        let request = RequestMessageBuilder::default()
+            .from(id.address())
+            .method("kvstore.transfer".to_string())
+            .data(minicbor::to_vec(args).unwrap())
+            .build()
+            .unwrap();
+        let envelope = encode_cose_sign1_from_request(request, &id).unwrap();
+        let response = smol::block_on(server.execute(envelope)).unwrap();
+        decode_response_from_cose_sign1(&response, None, &AcceptAllVerifier)
+            .unwrap()
+            .data
+            .map(|_| ())
+    }
+
+    #[test]
+    fn enable_disable() {
+        let server = ManyServer::test(AnonymousIdentity);
+        let mut registry = ModuleRegistry::new(&server);
+        registry.register(transfer_module()).unwrap();
+
+        let enabled = |registry: &ModuleRegistry| registry.modules().unwrap()[0].enabled;
+        let has_attribute = || {
+            server
+                .lock()
+                .unwrap()
+                .status()
+                .unwrap()
+                .attributes
+                .has_id(13)
+        };
+
+        assert!(!enabled(&registry));
+        assert!(!has_attribute());
+        assert_eq!(
+            transfer(&server).unwrap_err().code(),
+            ManyError::could_not_route_message().code()
+        );
+
+        registry.enable(13).unwrap();
+        registry.enable(13).unwrap();
+        assert!(enabled(&registry));
+        assert!(has_attribute());
+        assert!(server
+            .lock()
+            .unwrap()
+            .endpoints()
+            .unwrap()
+            .0
+            .contains("kvstore.transfer"));
+        assert!(transfer(&server).is_ok());
+
+        registry.disable(13).unwrap();
+        assert!(!enabled(&registry));
+        assert!(!has_attribute());
+        assert!(transfer(&server).is_err());
+
+        assert_eq!(
+            registry.enable(3).unwrap_err().code(),
+            admin::unknown_module("").code()
+        );
+    }
+
+    #[test]
+    fn conflict() {
+        let server = ManyServer::test(AnonymousIdentity);
+        let mut registry = ModuleRegistry::new(&server);
+        registry.register(transfer_module()).unwrap();
+        assert_eq!(
+            registry.register(transfer_module()).unwrap_err().code(),
+            admin::module_conflict("").code()
+        );
+
+        server.lock().unwrap().add_module(transfer_module());
+        assert_eq!(
+            registry.enable(13).unwrap_err().code(),
+            admin::module_conflict("").code()
+        );
+
+        // A module added by the server itself is not disabled by the registry.
+        registry.disable(13).unwrap();
+        assert!(server.lock().unwrap().module(13).is_some());
+    }
+}
@@ -0,0 +1,117 @@
+use many_modules::ManyModuleInfo;
+use many_protocol::RequestMessage;
+use many_types::cbor::CborAny;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+const REDACTED: &str = "<redacted>";
+
+/// Logs a sample of the requests executed by a server, with their method,
+/// sender and argument. Arguments the modules mark as sensitive (see
+/// [`ManyModuleInfo::redacted`]) are replaced before logging.
+///
+/// Requests are logged at the `info` level, with the `many_server::requests`
+/// target.
+#[derive(Debug)]
+pub struct RequestSampler {
+    percent: u8,
+    count: AtomicU64,
+}
+
+impl RequestSampler {
+    /// Log `percent`% of the requests, evenly spread. Values over 100 log all
+    /// requests.
+    pub fn new(percent: u8) -> Self {
+        Self {
+            percent: percent.min(100),
+            count: AtomicU64::new(0),
+        }
+    }
+
+    fn sample(&self) -> bool {
+        let percent = u64::from(self.percent);
+        let n = self.count.fetch_add(1, Ordering::Relaxed);
+        // True when the running count of sampled requests increases.
+        (n + 1) * percent / 100 != n * percent / 100
+    }
+
+    pub fn log(&self, message: &RequestMessage, info: &ManyModuleInfo) {
+        if !self.sample() {
+            return;
+        }
+
+        let argument = redact_argument(&message.data, info.redacted(&message.method));
+        tracing::info!(
+            target: "many_server::requests",
+            id = message.id,
+            method = %message.method,
+            from = %message.from(),
+            argument = %argument,
+        );
+    }
+}
+
+/// Render the CBOR of an argument without its sensitive keys.
+pub fn redact_argument(data: &[u8], redacted: Option<&[u64]>) -> String {
+    match redacted {
+        Some([]) => return REDACTED.to_string(),
+        Some(keys) => match minicbor::decode::<CborAny>(data) {
+            Ok(CborAny::Map(mut map)) => {
+                for (key, value) in map.iter_mut() {
+                    if matches!(key, CborAny::Int(k) if keys.iter().any(|r| i64::try_from(*r) == Ok(*k)))
+                    {
+                        *value = CborAny::String(REDACTED.to_string());
+                    }
+                }
+                return format!("{:?}", CborAny::Map(map));
+            }
+            // Keys are only redacted from maps, so don't risk logging
+            // anything else.
+            _ => return REDACTED.to_string(),
+        },
+        None => {}
+    }
+
+    match minicbor::decode::<CborAny>(data) {
+        Ok(value) => format!("{value:?}"),
+        Err(_) => format!("<invalid CBOR, {} bytes>", data.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn sample() {
+        let count = |percent| {
+            let sampler = RequestSampler::new(percent);
+            (0..1000).filter(|_| sampler.sample()).count()
+        };
+        assert_eq!(count(0), 0);
+        assert_eq!(count(10), 100);
+        assert_eq!(count(33), 330);
+        assert_eq!(count(100), 1000);
+        assert_eq!(count(200), 1000);
+    }
+
+    #[test]
+    fn redact() {
+        let map = CborAny::Map(BTreeMap::from([
+            (CborAny::Int(0), CborAny::String("public".to_string())),
+            (CborAny::Int(1), CborAny::Bytes(vec![0xAB; 4])),
+        ]));
+        let data = minicbor::to_vec(&map).unwrap();
+
+        let all = redact_argument(&data, None);
+        assert!(all.contains("public") && all.contains("abababab"));
+
+        let some = redact_argument(&data, Some(&[1]));
+        assert!(some.contains("public") && some.contains(REDACTED));
+        assert!(!some.contains("abababab"));
+
+        assert_eq!(redact_argument(&data, Some(&[])), REDACTED);
+        let not_a_map = minicbor::to_vec(CborAny::Int(1)).unwrap();
+        assert_eq!(redact_argument(&not_a_map, Some(&[0])), REDACTED);
+    }
+}
@@ -1,17 +1,28 @@
+use crate::async_store::AsyncTokens;
+use crate::panic::{PanicGuard, PanicPolicy};
+use crate::queue::{ExecutionQueue, QueueMetrics};
+use crate::request_log::RequestSampler;
+use crate::signers::SignerSet;
+use crate::simulation::Simulator;
 use crate::transport::LowLevelManyRequestHandler;
 use crate::RequestValidator;
+use async_lock::RwLock;
 use async_trait::async_trait;
-use coset::{CoseKey, CoseSign1};
+use coset::{CoseKey, CoseSign, CoseSign1};
 use many_error::ManyError;
-use many_identity::{Identity, Verifier};
-use many_modules::{base, ManyModule, ManyModuleInfo};
-use many_protocol::{RequestMessage, ResponseMessage};
+use many_identity::{Address, Identity, Verifier};
+use many_modules::{base, cddl, r#async, relay, ManyModule, ManyModuleInfo};
+use many_protocol::{RequestMessage, ResponseMessage, PROTOCOL_VERSION};
 use many_types::attributes::Attribute;
+use many_types::cbor::CborAny;
+use many_types::{BlockTime, NetworkId, Timestamp, SIMULATE};
 use std::cell::RefCell;
 use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::{Debug, Formatter};
+use std::str::FromStr;
 use std::sync::{Arc, Mutex};
-use std::time::SystemTime;
+use std::time::{Instant, SystemTime};
+use tracing::Instrument;
 
 trait ManyServerFallback: LowLevelManyRequestHandler + base::BaseModuleBackend {}
 
@@ -22,9 +33,322 @@ pub struct ManyModuleList {}
 
 pub const MANYSERVER_DEFAULT_TIMEOUT: u64 = 300;
 
-pub struct ManyServer {
+/// How the status of the fallback module is combined with the status of the
+/// server when their identity or versions differ, e.g. when the fallback is a
+/// reverse proxy to a server running another release.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum FallbackStatusPolicy {
+    /// Return an internal server error.
+    #[default]
+    Strict,
+
+    /// Use the identity and versions of this server, and the rest of the
+    /// fallback status.
+    Merge,
+
+    /// Use the status of this server, with the fallback status in extras
+    /// prefixed by `fallback.`. Attributes are still combined.
+    Namespace,
+}
+
+impl FromStr for FallbackStatusPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "strict" => Ok(Self::Strict),
+            "merge" => Ok(Self::Merge),
+            "namespace" => Ok(Self::Namespace),
+            _ => Err(format!("Unknown fallback status policy: {s}")),
+        }
+    }
+}
+
+/// The timestamp a server puts in its responses.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ResponseTimestampPolicy {
+    /// The time the response is signed.
+    #[default]
+    WallClock,
+
+    /// The time of the current block, given by the block time function of
+    /// the server (see [ManyServer::set_block_time_fn]). Falls back to the
+    /// wall clock without a block time.
+    BlockTime,
+
+    /// Always the same timestamp, so nodes executing the same request sign
+    /// the same response.
+    Fixed(Timestamp),
+}
+
+impl ResponseTimestampPolicy {
+    /// A fixed timestamp of 0, used by the ABCI frontend.
+    pub fn epoch() -> Self {
+        Self::Fixed(Timestamp::new(0).expect("0 is a valid timestamp."))
+    }
+
+    /// The timestamp of a response, given the time of the current block.
+    pub fn timestamp(&self, block_time: Option<Timestamp>) -> Timestamp {
+        match self {
+            Self::WallClock => Timestamp::now(),
+            Self::BlockTime => block_time.unwrap_or_else(Timestamp::now),
+            Self::Fixed(timestamp) => *timestamp,
+        }
+    }
+}
+
+impl FromStr for ResponseTimestampPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wall-clock" => Ok(Self::WallClock),
+            "block-time" => Ok(Self::BlockTime),
+            "epoch" => Ok(Self::epoch()),
+            _ => s
+                .strip_prefix("fixed:")
+                .and_then(|secs| secs.parse().ok())
+                .and_then(|secs| Timestamp::new(secs).ok())
+                .map(Self::Fixed)
+                .ok_or_else(|| format!("Unknown response timestamp policy: {s}")),
+        }
+    }
+}
+
+type BlockTimeFn = Arc<dyn Fn() -> Option<BlockTime> + Send + Sync>;
+
+type VersionHook = Arc<dyn Fn(RequestMessage) -> Result<RequestMessage, ManyError> + Send + Sync>;
+
+/// A module could not be added because another module of the server already
+/// implements its attribute or some of its endpoints.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ModuleConflict {
+    /// The name of the module which was not added.
+    pub module: String,
+
+    /// The name of the module already registered.
+    pub existing: String,
+
+    /// The attribute both modules implement, if any.
+    pub attribute: Option<u32>,
+
+    /// The endpoints both modules implement.
+    pub endpoints: Vec<String>,
+}
+
+impl std::fmt::Display for ModuleConflict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Module {} conflicts with module {}",
+            self.module, self.existing
+        )?;
+        if let Some(id) = self.attribute {
+            write!(f, " on attribute {id}")?;
+            if !self.endpoints.is_empty() {
+                write!(f, " and")?;
+            }
+        }
+        if !self.endpoints.is_empty() {
+            write!(f, " on endpoints {}", self.endpoints.join(", "))?;
+        }
+        write!(f, ".")
+    }
+}
+
+impl std::error::Error for ModuleConflict {}
+
+/// Modules of a server, or of one of its tenants, with the endpoints they
+/// implement.
+#[derive(Default)]
+struct ModuleSet {
     modules: Vec<Arc<dyn ManyModule + Send>>,
     method_cache: BTreeSet<String>,
+}
+
+impl ModuleSet {
+    /// Add a module. Adding a module which is already in the set does
+    /// nothing.
+    fn add(&mut self, module: Arc<dyn ManyModule + Send>) -> Result<(), ModuleConflict> {
+        if self.modules.iter().any(|m| Arc::ptr_eq(m, &module)) {
+            return Ok(());
+        }
+
+        let ManyModuleInfo {
+            name,
+            attribute,
+            endpoints,
+            ..
+        } = module.info();
+        let id = attribute.as_ref().map(|Attribute { id, .. }| *id);
+
+        for m in &self.modules {
+            let info = m.info();
+            let attribute = id.filter(|id| info.attribute.as_ref().map(|a| a.id) == Some(*id));
+            let shared: Vec<String> = endpoints
+                .iter()
+                .filter(|e| info.endpoints.contains(e))
+                .cloned()
+                .collect();
+            if attribute.is_some() || !shared.is_empty() {
+                return Err(ModuleConflict {
+                    module: name.clone(),
+                    existing: info.name.clone(),
+                    attribute,
+                    endpoints: shared,
+                });
+            }
+        }
+
+        // Update the cache.
+        for e in endpoints {
+            self.method_cache.insert(e.clone());
+        }
+        self.modules.push(module);
+        Ok(())
+    }
+
+    fn remove(&mut self, attribute_id: u32) -> Option<Arc<dyn ManyModule + Send>> {
+        let index = self
+            .modules
+            .iter()
+            .position(|m| m.info().attribute.as_ref().map(|a| a.id) == Some(attribute_id))?;
+        let module = self.modules.remove(index);
+        for e in &module.info().endpoints {
+            self.method_cache.remove(e);
+        }
+        Some(module)
+    }
+
+    fn get(&self, attribute_id: u32) -> Option<Arc<dyn ManyModule + Send>> {
+        self.modules
+            .iter()
+            .find(|m| m.info().attribute.as_ref().map(|a| a.id) == Some(attribute_id))
+            .cloned()
+    }
+
+    fn find(&self, method: &str) -> Option<Arc<dyn ManyModule + Send>> {
+        self.modules
+            .iter()
+            .find(|x| x.info().endpoints.iter().any(|e| e == method))
+            .cloned()
+    }
+
+    fn attributes(&self) -> BTreeSet<Attribute> {
+        self.modules
+            .iter()
+            .filter_map(|m| m.info().attribute.clone())
+            .collect()
+    }
+
+    /// Check every module, returning an error listing the ones which are
+    /// unhealthy.
+    fn heartbeat(&self) -> Result<(), ManyError> {
+        let unhealthy: Vec<String> = self
+            .modules
+            .iter()
+            .filter_map(|m| {
+                let name = &m.info().name;
+                m.heartbeat().err().map(|e| {
+                    tracing::warn!("Module {name} is unhealthy: {e}");
+                    format!("{name} ({e})")
+                })
+            })
+            .collect();
+        if unhealthy.is_empty() {
+            Ok(())
+        } else {
+            Err(ManyError::modules_unhealthy(unhealthy.join(", ")))
+        }
+    }
+
+    fn descriptors(&self) -> Vec<base::ModuleDescriptor> {
+        self.modules
+            .iter()
+            .map(|m| {
+                let info = m.info();
+                base::ModuleDescriptor {
+                    name: info.name.clone(),
+                    attribute: info.attribute.as_ref().map(|a| a.id),
+                    endpoints: info.descriptors.clone(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Another logical service hosted by a server, with its own identity and
+/// modules. See [`ManyServer::add_tenant`].
+struct Tenant {
+    name: String,
+    identity: Box<dyn Identity>,
+    public_key: Option<CoseKey>,
+    modules: ModuleSet,
+}
+
+/// The base module of a tenant, with its own status.
+struct TenantBase {
+    server: Arc<Mutex<ManyServer>>,
+    address: Address,
+}
+
+impl TenantBase {
+    fn with_tenant<T>(&self, f: impl FnOnce(&ManyServer, &Tenant) -> T) -> Result<T, ManyError> {
+        let server = self.server.lock().unwrap();
+        let tenant = server
+            .tenants
+            .get(&self.address)
+            .ok_or_else(|| ManyError::unknown_destination(self.address.to_string(), ""))?;
+        Ok(f(&server, tenant))
+    }
+}
+
+impl base::BaseModuleBackend for TenantBase {
+    fn endpoints(&self) -> Result<base::Endpoints, ManyError> {
+        self.with_tenant(|_, tenant| base::Endpoints(tenant.modules.method_cache.clone()))
+    }
+
+    fn describe(&self) -> Result<base::DescribeReturn, ManyError> {
+        self.with_tenant(|_, tenant| base::DescribeReturn(tenant.modules.descriptors()))
+    }
+
+    fn heartbeat(&self) -> Result<base::HeartbeatReturn, ManyError> {
+        self.with_tenant(|_, tenant| tenant.modules.heartbeat())??;
+        Ok(base::HeartbeatReturn {})
+    }
+
+    fn status(&self) -> Result<base::Status, ManyError> {
+        self.with_tenant(|server, tenant| {
+            let mut builder = base::StatusBuilder::default();
+            builder
+                .name(tenant.name.clone())
+                .version(PROTOCOL_VERSION)
+                .identity(self.address)
+                .timeout(server.timeout)
+                .supported_versions(server.supported_versions())
+                .attributes(tenant.modules.attributes().into_iter().collect())
+                .extras(BTreeMap::new());
+            if let Some(pk) = &tenant.public_key {
+                builder.public_key(pk.clone());
+            }
+            if let Some(sv) = server.version.clone() {
+                builder.server_version(sv);
+            }
+            if let Some(id) = &server.network_id {
+                builder.network_id(id.clone());
+            }
+            builder.build().map(|mut status| {
+                server.node_info().insert_into(&mut status.extras);
+                status
+            })
+        })?
+        .map_err(|x| ManyError::unknown(x.to_string()))
+    }
+}
+
+pub struct ManyServer {
+    modules: ModuleSet,
+    tenants: BTreeMap<Address, Tenant>,
     identity: Box<dyn Identity>,
     identity_verifier: Box<dyn Verifier>,
     validator: RefCell<Box<dyn RequestValidator + Send>>,
@@ -32,9 +356,37 @@ pub struct ManyServer {
     name: String,
     version: Option<String>,
     timeout: u64,
+    validate_arguments: bool,
+    network_id: Option<NetworkId>,
+    require_network_id: bool,
     fallback: Option<Arc<dyn ManyServerFallback + Send + 'static>>,
+    fallback_status_policy: FallbackStatusPolicy,
+    version_hooks: BTreeMap<u8, VersionHook>,
+    signer_sets: BTreeMap<Address, SignerSet>,
+
+    simulator: Option<Arc<Mutex<dyn Simulator>>>,
+
+    /// Held for writing while a request is simulated, and for reading while
+    /// any other request is executed.
+    execution_lock: Arc<RwLock<()>>,
 
     time_fn: Option<Arc<dyn Fn() -> Result<SystemTime, ManyError> + Send + Sync>>,
+    response_timestamp_policy: ResponseTimestampPolicy,
+
+    /// The time of the current block, passed to modules in the context of
+    /// their endpoints.
+    block_time_fn: Option<BlockTimeFn>,
+
+    /// The current block height, to reject calls to sunset endpoints.
+    height_fn: Option<Arc<dyn Fn() -> Result<u64, ManyError> + Send + Sync>>,
+
+    request_sampler: Option<RequestSampler>,
+    panic_guard: Arc<PanicGuard>,
+    execution_queue: Option<Arc<ExecutionQueue>>,
+
+    start_time: SystemTime,
+    git_sha: Option<String>,
+    features: Option<Vec<String>>,
 }
 
 impl ManyServer {
@@ -73,17 +425,34 @@ impl ManyServer {
         public_key: Option<CoseKey>,
     ) -> Arc<Mutex<Self>> {
         Arc::new(Mutex::new(Self {
-            modules: vec![],
+            modules: ModuleSet::default(),
+            tenants: BTreeMap::new(),
             name: name.to_string(),
             identity: Box::new(identity),
             identity_verifier: Box::new(verifier),
             validator: RefCell::new(Box::new(())),
             public_key,
             timeout: MANYSERVER_DEFAULT_TIMEOUT,
+            validate_arguments: false,
+            network_id: None,
+            require_network_id: false,
             fallback: None,
-            method_cache: Default::default(),
+            fallback_status_policy: FallbackStatusPolicy::default(),
+            version_hooks: BTreeMap::new(),
+            signer_sets: BTreeMap::new(),
+            simulator: None,
+            execution_lock: Default::default(),
             version: None,
             time_fn: None,
+            response_timestamp_policy: ResponseTimestampPolicy::default(),
+            block_time_fn: None,
+            height_fn: None,
+            request_sampler: None,
+            panic_guard: Default::default(),
+            execution_queue: None,
+            start_time: SystemTime::now(),
+            git_sha: None,
+            features: None,
         }))
     }
 
@@ -91,6 +460,22 @@ impl ManyServer {
         self.timeout = timeout_in_secs;
     }
 
+    /// Validate the arguments of requests against the CDDL rule of their
+    /// endpoint before executing them. Endpoints without a rule (see
+    /// [`many_modules::cddl`]) are not checked.
+    pub fn set_validate_arguments(&mut self, validate_arguments: bool) {
+        self.validate_arguments = validate_arguments;
+    }
+
+    /// Advertise the network of this server in its status, and refuse requests
+    /// signed for another network. If `required`, requests without a network
+    /// ID are refused too.
+    pub fn set_network_id(&mut self, id: NetworkId, required: bool) -> &mut Self {
+        self.network_id = Some(id);
+        self.require_network_id = required;
+        self
+    }
+
     pub fn set_time_fn<T>(&mut self, time_fn: T)
     where
         T: Fn() -> Result<SystemTime, ManyError> + Send + Sync + 'static,
@@ -98,6 +483,33 @@ impl ManyServer {
         self.time_fn = Some(Arc::new(time_fn));
     }
 
+    /// Which timestamp to put in responses. By default, the time they are
+    /// signed.
+    pub fn set_response_timestamp_policy(&mut self, policy: ResponseTimestampPolicy) -> &mut Self {
+        self.response_timestamp_policy = policy;
+        self
+    }
+
+    /// Give modules the time of the current block, through the context of
+    /// their endpoints (see [`many_protocol::context::Context::block_time`]).
+    /// The function is called before every execution.
+    pub fn set_block_time_fn<T>(&mut self, block_time_fn: T) -> &mut Self
+    where
+        T: Fn() -> Option<BlockTime> + Send + Sync + 'static,
+    {
+        self.block_time_fn = Some(Arc::new(block_time_fn));
+        self
+    }
+
+    /// Reject calls to deprecated endpoints from their sunset height. Without
+    /// this, deprecated endpoints only add a warning to their responses.
+    pub fn set_height_fn<T>(&mut self, height_fn: T)
+    where
+        T: Fn() -> Result<u64, ManyError> + Send + Sync + 'static,
+    {
+        self.height_fn = Some(Arc::new(height_fn));
+    }
+
     pub fn set_fallback_module<M>(&mut self, module: M) -> &mut Self
     where
         M: LowLevelManyRequestHandler + base::BaseModuleBackend + 'static,
@@ -106,6 +518,148 @@ impl ManyServer {
         self
     }
 
+    pub fn set_fallback_status_policy(&mut self, policy: FallbackStatusPolicy) -> &mut Self {
+        self.fallback_status_policy = policy;
+        self
+    }
+
+    /// Log a sample of the requests routed to modules.
+    pub fn set_request_sampler(&mut self, sampler: RequestSampler) -> &mut Self {
+        self.request_sampler = Some(sampler);
+        self
+    }
+
+    /// What to do when a module panics while executing a request. By default
+    /// the request fails with an error.
+    pub fn set_panic_policy(&mut self, policy: PanicPolicy) -> &mut Self {
+        self.panic_guard = Arc::new(PanicGuard::new(policy));
+        self
+    }
+
+    /// The number of module panics caught since the server started.
+    pub fn module_panics(&self) -> u64 {
+        self.panic_guard.count()
+    }
+
+    /// Run at most `slots` module executions at once, serving the waiting
+    /// ones round-robin across senders. By default executions are not
+    /// limited.
+    pub fn set_execution_slots(&mut self, slots: usize) -> &mut Self {
+        self.execution_queue = Some(Arc::new(ExecutionQueue::new(slots)));
+        self
+    }
+
+    /// The depth of the execution queue, if executions are limited.
+    pub fn execution_queue_metrics(&self) -> Option<QueueMetrics> {
+        self.execution_queue.as_ref().map(|q| q.metrics())
+    }
+
+    /// Report the commit and the features this server was built with in its
+    /// status, see [`base::NodeInfo`].
+    pub fn set_build_info<F: ToString>(
+        &mut self,
+        git_sha: impl ToString,
+        features: impl IntoIterator<Item = F>,
+    ) -> &mut Self {
+        self.git_sha = Some(git_sha.to_string());
+        self.features = Some(features.into_iter().map(|f| f.to_string()).collect());
+        self
+    }
+
+    /// The state of this node, added to the extras of every status.
+    fn node_info(&self) -> base::NodeInfo {
+        base::NodeInfo {
+            start_time: self
+                .start_time
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .ok()
+                .map(|d| d.as_secs()),
+            git_sha: self.git_sha.clone(),
+            features: self.features.clone(),
+            height: self.height_fn.as_ref().and_then(|f| f().ok()),
+            module_panics: Some(self.panic_guard.count()),
+        }
+    }
+
+    /// Serve the state of the requests of these async tokens with an async
+    /// module. Panics if the server already has an async module.
+    pub fn set_async_tokens(&mut self, tokens: AsyncTokens) -> &mut Self {
+        self.add_module(r#async::AsyncModule::new(Arc::new(Mutex::new(tokens))))
+    }
+
+    /// Simulate requests carrying the [`SIMULATE`] attribute using this
+    /// backend state. Without a simulator, they are refused. The simulator is
+    /// removed if it fails to roll back a simulation.
+    pub fn set_simulator(&mut self, simulator: Arc<Mutex<dyn Simulator>>) -> &mut Self {
+        self.simulator = Some(simulator);
+        self
+    }
+
+    /// Accept requests of another protocol version. The hook converts them to
+    /// the current version before they are validated and executed.
+    pub fn add_version_hook<F>(&mut self, version: u8, hook: F) -> &mut Self
+    where
+        F: Fn(RequestMessage) -> Result<RequestMessage, ManyError> + Send + Sync + 'static,
+    {
+        self.version_hooks.insert(version, Arc::new(hook));
+        self
+    }
+
+    /// The protocol versions accepted by this server, in ascending order.
+    pub fn supported_versions(&self) -> Vec<u8> {
+        let mut versions: BTreeSet<u8> = self.version_hooks.keys().copied().collect();
+        versions.insert(PROTOCOL_VERSION);
+        versions.into_iter().collect()
+    }
+
+    fn upgrade_request(&self, message: RequestMessage) -> Result<RequestMessage, ManyError> {
+        match message.version {
+            None => Ok(message),
+            Some(PROTOCOL_VERSION) => Ok(message),
+            Some(v) => {
+                let hook = self
+                    .version_hooks
+                    .get(&v)
+                    .ok_or_else(|| ManyError::unsupported_protocol_version(v))?;
+                hook(message)
+            }
+        }
+    }
+
+    /// Require the requests from an address to be signed by a threshold of
+    /// the signer set, in a multi-signer envelope. Single signer envelopes
+    /// from that address then only pass if the threshold is one and the
+    /// address is in the set.
+    pub fn add_signer_set(&mut self, address: Address, set: SignerSet) -> &mut Self {
+        self.signer_sets.insert(address, set);
+        self
+    }
+
+    /// The time delegations are checked against: the time of the current
+    /// block if there's one, so all nodes agree, or the time of the server.
+    /// Requests carry their own timestamp, but it is chosen by the delegate.
+    fn delegation_time(&self) -> Result<Timestamp, ManyError> {
+        if let Some(block_time) = self.block_time_fn.as_ref().and_then(|f| f()) {
+            return Ok(block_time.into());
+        }
+        let now = self
+            .time_fn
+            .as_ref()
+            .map_or_else(|| Ok(SystemTime::now()), |f| f())?;
+        Timestamp::from_system_time(now)
+    }
+
+    fn validate_signers(
+        &self,
+        message: &RequestMessage,
+        signers: &BTreeSet<Address>,
+    ) -> Result<(), ManyError> {
+        match self.signer_sets.get(&message.from()) {
+            Some(set) => set.validate(signers),
+            None => Ok(()),
+        }
+    }
+
     pub fn add_validator(
         &mut self,
         validator: impl RequestValidator + Send + 'static,
@@ -115,53 +669,159 @@ impl ManyServer {
         self
     }
 
+    /// Add a module. Panics if it conflicts with a module already added, see
+    /// [`ManyServer::try_add_module`].
     pub fn add_module<M>(&mut self, module: M) -> &mut Self
     where
         M: ManyModule + 'static,
     {
-        let info = module.info();
-        let ManyModuleInfo {
-            attribute,
-            endpoints,
-            ..
-        } = info;
+        self.add_module_arc(Arc::new(module))
+    }
 
-        if let Some(Attribute { id, .. }) = attribute {
-            if let Some(m) = self
-                .modules
-                .iter()
-                .find(|m| m.info().attribute.as_ref().map(|x| x.id) == Some(*id))
-            {
-                panic!(
-                    "Module {} already implements attribute {}.",
-                    m.info().name,
-                    id
-                );
-            }
+    /// Add a module which is already shared as a trait object, e.g. one kept
+    /// by a [`crate::registry::ModuleRegistry`] so it can be removed and added
+    /// again later. Adding the same module twice does nothing.
+    pub fn add_module_arc(&mut self, module: Arc<dyn ManyModule + Send>) -> &mut Self {
+        self.try_add_module_arc(module)
+            .unwrap_or_else(|conflict| panic!("{conflict}"))
+    }
+
+    /// Add a module, or return the module it conflicts with, if any.
+    pub fn try_add_module<M>(&mut self, module: M) -> Result<&mut Self, ModuleConflict>
+    where
+        M: ManyModule + 'static,
+    {
+        self.try_add_module_arc(Arc::new(module))
+    }
+
+    pub fn try_add_module_arc(
+        &mut self,
+        module: Arc<dyn ManyModule + Send>,
+    ) -> Result<&mut Self, ModuleConflict> {
+        self.modules.add(module)?;
+        Ok(self)
+    }
+
+    /// Remove the module implementing an attribute, along with its endpoints.
+    /// Requests already executing by this module are not interrupted.
+    pub fn remove_module(&mut self, attribute_id: u32) -> Option<Arc<dyn ManyModule + Send>> {
+        self.modules.remove(attribute_id)
+    }
+
+    /// Returns the module implementing an attribute, if any.
+    pub fn module(&self, attribute_id: u32) -> Option<Arc<dyn ManyModule + Send>> {
+        self.modules.get(attribute_id)
+    }
+
+    /// Whether a module of this server implements the endpoint.
+    pub fn has_endpoint(&self, endpoint: &str) -> bool {
+        self.modules.method_cache.contains(endpoint)
+    }
+
+    /// Host another logical service in this server. Requests sent to the
+    /// address of `identity` are routed to the modules of the tenant, added
+    /// with [`ManyServer::add_tenant_module`], and their responses are signed
+    /// by `identity`. The tenant has its own base module, but no fallback.
+    pub fn add_tenant(
+        server: &Arc<Mutex<Self>>,
+        name: impl ToString,
+        identity: impl Identity + 'static,
+    ) -> Address {
+        let address = identity.address();
+        let mut this = server.lock().unwrap();
+        if address == this.identity.address() || this.tenants.contains_key(&address) {
+            panic!("Identity {address} is already served.");
         }
 
-        for e in endpoints {
-            if self.method_cache.contains(e.as_str()) {
-                unreachable!(
-                    "Method '{}' already implemented, but there was no attribute conflict.",
-                    e
-                );
-            }
+        let mut modules = ModuleSet::default();
+        modules
+            .add(Arc::new(base::BaseModule::new(Arc::new(Mutex::new(
+                TenantBase {
+                    server: server.clone(),
+                    address,
+                },
+            )))))
+            .expect("An empty set has no conflicts.");
+        this.tenants.insert(
+            address,
+            Tenant {
+                name: name.to_string(),
+                public_key: identity.public_key(),
+                identity: Box::new(identity),
+                modules,
+            },
+        );
+        address
+    }
+
+    /// Add a module to the tenant of an address. Panics if there's no such
+    /// tenant, or if the module conflicts with one of the tenant's.
+    pub fn add_tenant_module<M>(&mut self, tenant: &Address, module: M) -> &mut Self
+    where
+        M: ManyModule + 'static,
+    {
+        self.add_tenant_module_arc(tenant, Arc::new(module))
+    }
+
+    pub fn add_tenant_module_arc(
+        &mut self,
+        tenant: &Address,
+        module: Arc<dyn ManyModule + Send>,
+    ) -> &mut Self {
+        self.tenants
+            .get_mut(tenant)
+            .unwrap_or_else(|| panic!("No tenant with address {tenant}."))
+            .modules
+            .add(module)
+            .unwrap_or_else(|conflict| panic!("{conflict}"));
+        self
+    }
+
+    /// Stop serving a tenant. Returns false if there was no such tenant.
+    pub fn remove_tenant(&mut self, tenant: &Address) -> bool {
+        self.tenants.remove(tenant).is_some()
+    }
+
+    /// The addresses of the tenants of this server.
+    pub fn tenants(&self) -> Vec<Address> {
+        self.tenants.keys().copied().collect()
+    }
+
+    /// The address responding to requests sent to an address: the tenant of
+    /// that address, or this server.
+    fn address_for(&self, to: &Address) -> Address {
+        if self.tenants.contains_key(to) {
+            *to
+        } else {
+            self.identity.address()
         }
+    }
 
-        // Update the cache.
-        for e in endpoints {
-            self.method_cache.insert(e.clone());
+    /// Sign a response with the identity of its sender, a tenant or this
+    /// server.
+    fn encode_response(&self, mut response: ResponseMessage) -> Result<CoseSign1, String> {
+        let block_time = self
+            .block_time_fn
+            .as_ref()
+            .and_then(|f| f())
+            .map(Timestamp::from);
+        response.timestamp = Some(self.response_timestamp_policy.timestamp(block_time));
+
+        match self.tenants.get(&response.from) {
+            Some(tenant) => {
+                many_protocol::encode_cose_sign1_from_response(response, &tenant.identity)
+            }
+            None => many_protocol::encode_cose_sign1_from_response(response, &self.identity),
         }
-        self.modules.push(Arc::new(module));
-        self
+        .map_err(|e| e.to_string())
     }
 
     pub fn validate_id(&self, message: &RequestMessage) -> Result<(), ManyError> {
         let to = &message.to;
 
-        // Verify that the message is for this server, if it's not anonymous.
-        if to.is_anonymous() || &self.identity.address() == to {
+        // Verify that the message is for this server or one of its tenants,
+        // if it's not anonymous.
+        if to.is_anonymous() || &self.identity.address() == to || self.tenants.contains_key(to) {
             Ok(())
         } else {
             Err(ManyError::unknown_destination(
@@ -171,23 +831,82 @@ impl ManyServer {
         }
     }
 
-    pub fn find_module(&self, message: &RequestMessage) -> Option<Arc<dyn ManyModule + Send>> {
-        self.modules
-            .iter()
-            .find(|x| x.info().endpoints.contains(&message.method))
-            .cloned()
-    }
-}
+    /// Validate a request decoded from its envelope, with the verified signers
+    /// of the envelope. Every request goes through this, including the ones
+    /// relayed by the relay module.
+    fn validate_message(
+        &self,
+        envelope: &Envelope,
+        request: RequestMessage,
+        signers: &BTreeSet<Address>,
+    ) -> Result<Validated, ManyError> {
+        let message = self.upgrade_request(request)?;
+
+        let now = self
+            .time_fn
+            .as_ref()
+            .map_or_else(|| Ok(SystemTime::now()), |f| f())?;
+
+        self.validator.borrow().validate_request(&message)?;
+        message.validate_time(now, self.timeout)?;
+        if let Some(id) = &self.network_id {
+            id.validate(&message.attributes, self.require_network_id)?;
+        }
 
-impl Debug for ManyServer {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("ManyServer").finish()
-    }
+        self.validate_id(&message)?;
+        self.validate_signers(&message, signers)?;
+
+        let module = self.find_module(&message);
+        if let Some(ref m) = module {
+            if self.validate_arguments {
+                validate_arguments(m.info(), &message)?;
+            }
+            match envelope {
+                Envelope::Single(envelope) => m.validate(&message, envelope)?,
+                Envelope::Multi(envelope) => m.validate_multi(&message, envelope, signers)?,
+            }
+            if let Some(sampler) = &self.request_sampler {
+                sampler.log(&message, m.info());
+            }
+        };
+
+        let deprecation = module
+            .as_ref()
+            .and_then(|m| m.info().deprecation(&message.method).cloned());
+        if let (Some(d), Some(height_fn)) = (&deprecation, &self.height_fn) {
+            if d.is_sunset(height_fn()?) {
+                return Err(ManyError::endpoint_sunset(
+                    message.method.clone(),
+                    d.sunset.unwrap_or_default(),
+                ));
+            }
+        }
+
+        Ok(Validated {
+            message,
+            module,
+            deprecation,
+        })
+    }
+
+    /// The module of the destination of the message implementing its method.
+    pub fn find_module(&self, message: &RequestMessage) -> Option<Arc<dyn ManyModule + Send>> {
+        match self.tenants.get(&message.to) {
+            Some(tenant) => tenant.modules.find(&message.method),
+            None => self.modules.find(&message.method),
+        }
+    }
+}
+
+impl Debug for ManyServer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ManyServer").finish()
+    }
 }
 
 impl base::BaseModuleBackend for ManyServer {
     fn endpoints(&self) -> Result<base::Endpoints, ManyError> {
-        let mut endpoints: BTreeSet<String> = self.method_cache.iter().cloned().collect();
+        let mut endpoints: BTreeSet<String> = self.modules.method_cache.clone();
 
         if let Some(fb) = &self.fallback {
             endpoints = endpoints
@@ -199,20 +918,35 @@ impl base::BaseModuleBackend for ManyServer {
         Ok(base::Endpoints(endpoints))
     }
 
+    fn describe(&self) -> Result<base::DescribeReturn, ManyError> {
+        let mut modules = self.modules.descriptors();
+
+        if let Some(fb) = &self.fallback {
+            modules.extend(fb.describe()?.0);
+        }
+
+        Ok(base::DescribeReturn(modules))
+    }
+
+    fn heartbeat(&self) -> Result<base::HeartbeatReturn, ManyError> {
+        self.modules.heartbeat()?;
+        if let Some(fb) = &self.fallback {
+            fb.heartbeat()?;
+        }
+        Ok(base::HeartbeatReturn {})
+    }
+
     fn status(&self) -> Result<base::Status, ManyError> {
-        let mut attributes: BTreeSet<Attribute> = self
-            .modules
-            .iter()
-            .filter_map(|m| m.info().attribute.clone())
-            .collect();
+        let mut attributes = self.modules.attributes();
 
         let mut builder = base::StatusBuilder::default();
 
         builder
             .name(self.name.clone())
-            .version(1)
+            .version(PROTOCOL_VERSION)
             .identity(self.identity.address())
             .timeout(self.timeout)
+            .supported_versions(self.supported_versions())
             .extras(BTreeMap::new());
 
         if let Some(ref pk) = self.public_key {
@@ -223,36 +957,154 @@ impl base::BaseModuleBackend for ManyServer {
             builder.server_version(sv);
         }
 
+        if let Some(ref id) = self.network_id {
+            builder.network_id(id.clone());
+        }
+
         if let Some(fb) = &self.fallback {
             let fb_status = fb.status()?;
-            if fb_status.identity != self.identity.address()
+            let mismatch = fb_status.identity != self.identity.address()
                 || fb_status.version != 1
-                || (fb_status.server_version != self.version && self.version.is_some())
-            {
-                tracing::error!(
+                || (fb_status.server_version != self.version && self.version.is_some());
+            if mismatch {
+                let message = format!(
                     "fallback status differs from internal status: {} != {} || {:?} != {:?}",
                     fb_status.identity,
                     self.identity.address(),
                     fb_status.server_version,
                     self.version
                 );
-                return Err(ManyError::internal_server_error());
+                if self.fallback_status_policy == FallbackStatusPolicy::Strict {
+                    tracing::error!("{message}");
+                    return Err(ManyError::internal_server_error());
+                }
+                tracing::debug!("{message}");
             }
 
-            if let Some(sv) = fb_status.server_version {
-                builder.server_version(sv);
+            match self.fallback_status_policy {
+                FallbackStatusPolicy::Strict | FallbackStatusPolicy::Merge => {
+                    // Only differs from our version if the policy allows it.
+                    if let (Some(sv), None) = (fb_status.server_version, &self.version) {
+                        builder.server_version(sv);
+                    }
+                    if let (Some(id), None) = (fb_status.network_id, &self.network_id) {
+                        builder.network_id(id);
+                    }
+                    builder.name(fb_status.name).extras(fb_status.extras);
+                }
+                FallbackStatusPolicy::Namespace => {
+                    builder.extras(namespace_fallback_status(&fb_status));
+                }
             }
 
-            builder.name(fb_status.name).extras(fb_status.extras);
-
             attributes = attributes.into_iter().chain(fb_status.attributes).collect();
         }
 
-        builder.attributes(attributes.into_iter().collect());
-
-        builder
+        let mut status = builder
+            .attributes(attributes.into_iter().collect())
             .build()
-            .map_err(|x| ManyError::unknown(x.to_string()))
+            .map_err(|x| ManyError::unknown(x.to_string()))?;
+        self.node_info().insert_into(&mut status.extras);
+        Ok(status)
+    }
+}
+
+impl relay::RelayRouter for ManyServer {
+    fn route(
+        &self,
+        envelope: &CoseSign1,
+    ) -> Result<(RequestMessage, Arc<dyn ManyModule + Send>), ManyError> {
+        self.validator.borrow().validate_envelope(envelope)?;
+        let (request, signer) = many_protocol::decode_delegated_request_from_cose_sign1(
+            envelope,
+            &self.identity_verifier,
+            self.delegation_time()?,
+        )?;
+        // A simulation would roll back the fee of the relayer.
+        if request.attributes.has_id(SIMULATE.id) {
+            return Err(relay::invalid_relayed_request(
+                "relayed requests cannot be simulated",
+            ));
+        }
+
+        let envelope = Envelope::Single(envelope.clone());
+        let Validated {
+            message, module, ..
+        } = self.validate_message(&envelope, request, &BTreeSet::from([signer]))?;
+        // The fallback is another server, which cannot be relayed to.
+        let module = module.ok_or_else(ManyError::could_not_route_message)?;
+        Ok((message, module))
+    }
+
+    fn executed(
+        &mut self,
+        envelope: &CoseSign1,
+        request: &RequestMessage,
+        module: &dyn ManyModule,
+        result: Result<ResponseMessage, ManyError>,
+    ) -> Result<ResponseMessage, ManyError> {
+        let response = match &result {
+            Ok(response) => response.clone(),
+            Err(e) => ResponseMessage::error(self.address_for(&request.to), request.id, e.clone()),
+        };
+        self.validator
+            .borrow_mut()
+            .message_executed(envelope, &response)?;
+
+        let deprecation = module.info().deprecation(&request.method);
+        result.and_then(|r| warn_deprecated(r, &request.method, deprecation))
+    }
+}
+
+/// The fallback status as status extras, with keys prefixed by `fallback.`.
+fn namespace_fallback_status(status: &base::Status) -> BTreeMap<String, CborAny> {
+    let mut extras = BTreeMap::from([
+        (
+            "fallback.name".to_string(),
+            CborAny::String(status.name.clone()),
+        ),
+        (
+            "fallback.identity".to_string(),
+            CborAny::String(status.identity.to_string()),
+        ),
+        (
+            "fallback.version".to_string(),
+            CborAny::Int(status.version.into()),
+        ),
+    ]);
+    if let Some(sv) = &status.server_version {
+        extras.insert(
+            "fallback.serverVersion".to_string(),
+            CborAny::String(sv.clone()),
+        );
+    }
+    for (k, v) in &status.extras {
+        extras.insert(format!("fallback.{k}"), v.clone());
+    }
+    extras
+}
+
+fn validate_arguments(info: &ManyModuleInfo, message: &RequestMessage) -> Result<(), ManyError> {
+    match info
+        .descriptors
+        .iter()
+        .find(|d| d.name == message.method)
+        .and_then(cddl::argument_rule)
+    {
+        Some(rule) => rule.validate(&message.data),
+        None => Ok(()),
+    }
+}
+
+/// Add the deprecation warning of the endpoint to a successful response.
+fn warn_deprecated(
+    response: ResponseMessage,
+    method: &str,
+    deprecation: Option<&base::Deprecation>,
+) -> Result<ResponseMessage, ManyError> {
+    match deprecation {
+        Some(d) if response.data.is_ok() => response.with_warnings([d.warning(method)]),
+        _ => Ok(response),
     }
 }
 
@@ -265,107 +1117,266 @@ impl LowLevelManyRequestHandler for Arc<Mutex<ManyServer>> {
                 let validator = this.validator.borrow();
 
                 validator.validate_envelope(&envelope).and_then(|_| {
-                    many_protocol::decode_request_from_cose_sign1(
+                    many_protocol::decode_delegated_request_from_cose_sign1(
                         &envelope,
                         &this.identity_verifier,
+                        this.delegation_time()?,
                     )
+                    .map(|(request, signer)| (request, BTreeSet::from([signer])))
                 })
             }
         };
-        let mut id = None;
+        execute_request(self, Envelope::Single(envelope), request).await
+    }
 
-        let response = {
+    async fn execute_multi(&self, envelope: CoseSign) -> Result<CoseSign1, String> {
+        let request = {
             let this = self.lock().unwrap();
-            let address = this.identity.address();
-
-            (|| {
-                let message = request?;
-
-                let now = this
-                    .time_fn
-                    .as_ref()
-                    .map_or_else(|| Ok(SystemTime::now()), |f| f())?;
-
-                this.validator.borrow().validate_request(&message)?;
-                message.validate_time(now, this.timeout)?;
+            {
+                let validator = this.validator.borrow();
 
-                id = message.id;
+                validator.validate_multi_envelope(&envelope).and_then(|_| {
+                    many_protocol::decode_delegated_request_from_cose_sign(
+                        &envelope,
+                        &this.identity_verifier,
+                        this.delegation_time()?,
+                    )
+                })
+            }
+        };
+        execute_request(self, Envelope::Multi(envelope), request).await
+    }
+}
 
-                this.validate_id(&message)?;
+/// The envelope of a request, passed to the validators and the module.
+enum Envelope {
+    Single(CoseSign1),
+    Multi(CoseSign),
+}
 
-                let maybe_module = this.find_module(&message);
-                if let Some(ref m) = maybe_module {
-                    m.validate(&message, &envelope)?;
-                };
+/// A request which passed the validation of the server, with the module
+/// executing it, if any.
+struct Validated {
+    message: RequestMessage,
+    module: Option<Arc<dyn ManyModule + Send>>,
+    deprecation: Option<base::Deprecation>,
+}
 
-                Ok((address, message, maybe_module, this.fallback.clone()))
-            })()
-            .map_err(|many_err| ResponseMessage::error(address, id, many_err))
-        };
+/// Execute a request once decoded from its envelope, with the verified
+/// signers of the envelope.
+async fn execute_request(
+    server: &Arc<Mutex<ManyServer>>,
+    envelope: Envelope,
+    request: Result<(RequestMessage, BTreeSet<Address>), ManyError>,
+) -> Result<CoseSign1, String> {
+    let mut id = None;
+
+    let response = {
+        let this = server.lock().unwrap();
+        let mut address = this.identity.address();
+
+        (|| {
+            let (request, signers) = request?;
+            // Keep the ID of the request for every response, including errors
+            // returned before the request is fully validated.
+            id = request.id;
+            address = this.address_for(&request.to);
+            let Validated {
+                message,
+                module: maybe_module,
+                deprecation,
+            } = this.validate_message(&envelope, request, &signers)?;
+
+            // Requests for the fallback are simulated, or not, by it.
+            let simulator = if message.attributes.has_id(SIMULATE.id) {
+                match (&maybe_module, &this.simulator) {
+                    (Some(_), None) => return Err(ManyError::simulation_not_supported()),
+                    (_, simulator) => simulator.clone(),
+                }
+            } else {
+                None
+            };
+
+            // The fallback only serves this server, not its tenants.
+            let fallback = if this.tenants.contains_key(&message.to) {
+                None
+            } else {
+                this.fallback.clone()
+            };
+
+            Ok((
+                address,
+                message,
+                maybe_module,
+                deprecation,
+                fallback,
+                simulator,
+                this.execution_lock.clone(),
+                this.panic_guard.clone(),
+                this.execution_queue.clone(),
+                this.block_time_fn.clone(),
+            ))
+        })()
+        .map_err(|many_err| ResponseMessage::error(address, id, many_err))
+    };
 
-        match response {
-            Ok((address, message, maybe_module, fallback)) => match (maybe_module, fallback) {
-                (Some(m), _) => {
-                    let mut response = match m.execute(message.clone()).await {
-                        Ok(response) => response,
+    match response {
+        Ok((
+            address,
+            message,
+            maybe_module,
+            deprecation,
+            fallback,
+            simulator,
+            execution_lock,
+            panic_guard,
+            execution_queue,
+            block_time_fn,
+        )) => {
+            let block_time = || block_time_fn.as_ref().and_then(|f| f());
+            match (maybe_module, fallback, simulator) {
+                (Some(m), _, Some(simulator)) => {
+                    let _lock = execution_lock.write().await;
+
+                    let span = tracing::debug_span!(
+                        "simulation",
+                        id = message.id,
+                        method = %message.method,
+                        from = %message.from(),
+                    );
+                    let begin = simulator.lock().unwrap().begin();
+                    let mut response = match begin {
+                        Ok(()) => {
+                            let result = panic_guard
+                                .run(
+                                    &message.method,
+                                    m.execute_with_block_time(message.clone(), block_time())
+                                        .instrument(span),
+                                )
+                                .await
+                                .and_then(|r| {
+                                    warn_deprecated(r, &message.method, deprecation.as_ref())
+                                });
+                            // The backend is poisoned if the module panicked.
+                            let rollback = match simulator.lock() {
+                                Ok(mut simulator) => simulator.rollback(),
+                                Err(_) => Err(ManyError::unknown("The simulator is poisoned.")),
+                            };
+                            match (result, rollback) {
+                                (Ok(response), Ok(events)) => {
+                                    response.with_simulated_events(events)
+                                }
+                                (Err(many_err), Ok(_)) => {
+                                    ResponseMessage::error(address, id, many_err)
+                                }
+                                (_, Err(many_err)) => {
+                                    // The changes of the simulation might be
+                                    // committed with the next block, so stop
+                                    // simulating requests.
+                                    tracing::error!("Could not roll back a simulation: {many_err}");
+                                    server.lock().unwrap().simulator = None;
+                                    ResponseMessage::error(address, id, many_err)
+                                }
+                            }
+                        }
                         Err(many_err) => ResponseMessage::error(address, id, many_err),
                     };
                     response.from = address;
+                    response.id = id;
 
-                    let this = self.lock().unwrap();
-                    let _ = this
-                        .validator
-                        .borrow_mut()
-                        .message_executed(&envelope, &response)
-                        .map_err(|e| {
-                            // There's nothing we can do here, since the backend has
-                            // already executed the message and updated its test.
-                            panic!(
-                                "message_executed failed: {e}\n\
-                                The backend and tendermint states might be inconsistent \
-                                and would need to revert to a previous block."
-                            );
-                        });
-                    many_protocol::encode_cose_sign1_from_response(response, &this.identity)
-                        .map_err(|e| e.to_string())
+                    // The request was not executed, so the validator doesn't
+                    // record it and it can still be sent afterward.
+                    let this = server.lock().unwrap();
+                    this.encode_response(response)
                 }
-                (None, Some(fb)) => {
-                    LowLevelManyRequestHandler::execute(fb.as_ref(), envelope).await
+                (Some(m), _, None) => {
+                    let _slot = match &execution_queue {
+                        Some(queue) => Some(queue.acquire(message.from()).await),
+                        None => None,
+                    };
+                    let _lock = execution_lock.read().await;
+
+                    let span = tracing::debug_span!(
+                        "request",
+                        id = message.id,
+                        method = %message.method,
+                        from = %message.from(),
+                    );
+                    let start = Instant::now();
+                    let result = panic_guard
+                        .run(
+                            &message.method,
+                            m.execute_with_block_time(message.clone(), block_time())
+                                .instrument(span),
+                        )
+                        .await
+                        .and_then(|r| warn_deprecated(r, &message.method, deprecation.as_ref()));
+                    let execution_time = start.elapsed();
+                    let mut response = match result {
+                        Ok(response) => response,
+                        Err(many_err) => ResponseMessage::error(address, id, many_err),
+                    };
+                    response.from = address;
+                    response.id = id;
+
+                    let this = server.lock().unwrap();
+                    let mut validator = this.validator.borrow_mut();
+                    validator.request_timed(&message, execution_time);
+                    let _ = match &envelope {
+                        Envelope::Single(envelope) => {
+                            validator.message_executed(envelope, &response)
+                        }
+                        Envelope::Multi(envelope) => {
+                            validator.multi_message_executed(envelope, &response)
+                        }
+                    }
+                    .map_err(|e| {
+                        // There's nothing we can do here, since the backend has
+                        // already executed the message and updated its test.
+                        panic!(
+                            "message_executed failed: {e}\n\
+                            The backend and tendermint states might be inconsistent \
+                            and would need to revert to a previous block."
+                        );
+                    });
+                    this.encode_response(response)
                 }
-                (None, None) => {
-                    let this = self.lock().unwrap();
-                    let identity = &this.identity;
-                    let address = identity.address();
-
+                (None, Some(fb), _) => match envelope {
+                    Envelope::Multi(envelope) => fb.execute_multi(envelope).await,
+                    Envelope::Single(envelope) => {
+                        LowLevelManyRequestHandler::execute(fb.as_ref(), envelope).await
+                    }
+                },
+                (None, None, _) => {
+                    let this = server.lock().unwrap();
                     let response =
                         ResponseMessage::error(address, id, ManyError::could_not_route_message());
-                    many_protocol::encode_cose_sign1_from_response(response, identity)
-                        .map_err(|e| e.to_string())
+                    this.encode_response(response)
                 }
-            },
-            Err(response) => {
-                let this = self.lock().unwrap();
-                many_protocol::encode_cose_sign1_from_response(response, &this.identity)
-                    .map_err(|e| e.to_string())
             }
         }
+        Err(response) => {
+            let this = server.lock().unwrap();
+            this.encode_response(response)
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use semver::{BuildMetadata, Prerelease, Version};
-    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
     use std::sync::RwLock;
     use std::time::Duration;
 
     use super::*;
-    use many_identity::{AcceptAllVerifier, Address, AnonymousIdentity};
+    use many_identity::{AcceptAllVerifier, AnonymousIdentity};
     use many_identity_dsa::ed25519::generate_random_ed25519_identity;
     use many_modules::base::Status;
     use many_protocol::{
         decode_response_from_cose_sign1, encode_cose_sign1_from_request, RequestMessageBuilder,
     };
+    use many_types::attributes::AttributeSet;
     use many_types::Timestamp;
     use proptest::prelude::*;
 
@@ -398,27 +1409,659 @@ mod tests {
             let request: RequestMessage = RequestMessageBuilder::default()
                 .version(1)
                 .from(id.address())
-                .to(server_address)
+                .to(server_address)
+                .method("status".to_string())
+                .data("null".as_bytes().to_vec())
+                .build()
+                .unwrap();
+
+            let envelope = encode_cose_sign1_from_request(request, &id).unwrap();
+            let response = smol::block_on(async { server.execute(envelope).await }).unwrap();
+            let response_message = decode_response_from_cose_sign1(&response, None, &AcceptAllVerifier).unwrap();
+
+            let status: Status = minicbor::decode(&response_message.data.unwrap()).unwrap();
+
+            assert_eq!(status.version, 1);
+            assert_eq!(status.name, name);
+            assert_eq!(status.public_key, Some(server_public_key));
+            assert_eq!(status.identity, server_address);
+            assert!(status.attributes.has_id(0));
+            assert_eq!(status.server_version, Some(version.to_string()));
+            assert_eq!(status.timeout, Some(MANYSERVER_DEFAULT_TIMEOUT));
+            assert_eq!(
+                status.extras.keys().collect::<Vec<_>>(),
+                [
+                    base::NodeInfo::MODULE_PANICS_KEY,
+                    base::NodeInfo::START_TIME_KEY
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn describe() {
+        let server = ManyServer::test(AnonymousIdentity);
+        let request: RequestMessage = RequestMessageBuilder::default()
+            .method("describe".to_string())
+            .data("null".as_bytes().to_vec())
+            .build()
+            .unwrap();
+        let envelope = encode_cose_sign1_from_request(request, &AnonymousIdentity).unwrap();
+        let response = smol::block_on(server.execute(envelope)).unwrap();
+        let response =
+            decode_response_from_cose_sign1(&response, None, &AcceptAllVerifier).unwrap();
+        let describe: base::DescribeReturn = minicbor::decode(&response.data.unwrap()).unwrap();
+
+        let base = describe
+            .0
+            .iter()
+            .find(|m| m.attribute == Some(0))
+            .expect("Base module should be described");
+        let names: BTreeSet<&str> = base.endpoints.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(
+            names,
+            BTreeSet::from(["describe", "endpoints", "heartbeat", "status"])
+        );
+    }
+
+    #[test]
+    fn tenants() {
+        use many_identity_dsa::CoseKeyVerifier;
+        use many_modules::kvstore::{
+            KvStoreTransferModule, KvStoreTransferModuleBackend, TransferArgs, TransferReturn,
+        };
+        use minicbor::bytes::ByteVec;
+
+        struct Transfer;
+        impl KvStoreTransferModuleBackend for Transfer {
+            fn transfer(
+                &mut self,
+                _sender: &Address,
+                _args: TransferArgs,
+            ) -> Result<TransferReturn, ManyError> {
+                Ok(many_modules::EmptyReturn)
+            }
+        }
+
+        let primary = generate_random_ed25519_identity();
+        let primary_address = primary.address();
+        let server = ManyServer::simple("primary", primary, AcceptAllVerifier, None);
+        let tenant = ManyServer::add_tenant(&server, "tenant", generate_random_ed25519_identity());
+        server.lock().unwrap().add_tenant_module(
+            &tenant,
+            KvStoreTransferModule::new(Arc::new(Mutex::new(Transfer))),
+        );
+        assert_eq!(server.lock().unwrap().tenants(), vec![tenant]);
+
+        let id = generate_random_ed25519_identity();
+        let call = |to: Address, method: &str, data: Vec<u8>| {
+            let request: RequestMessage = RequestMessageBuilder::default()
+                .from(id.address())
+                .to(to)
+                .method(method.to_string())
+                .data(data)
+                .build()
+                .unwrap();
+            let envelope = encode_cose_sign1_from_request(request, &id).unwrap();
+            let response = smol::block_on(server.execute(envelope)).unwrap();
+            // Responses are signed by the key of the destination.
+            decode_response_from_cose_sign1(&response, None, &CoseKeyVerifier).unwrap()
+        };
+        let status = |to: Address| {
+            let response = call(to, "status", b"null".to_vec());
+            assert_eq!(response.from, to);
+            let status: Status = minicbor::decode(&response.data.unwrap()).unwrap();
+            status
+        };
+
+        let primary_status = status(primary_address);
+        assert_eq!(primary_status.name, "primary");
+        assert_eq!(primary_status.identity, primary_address);
+        let tenant_status = status(tenant);
+        assert_eq!(tenant_status.name, "tenant");
+        assert_eq!(tenant_status.identity, tenant);
+        assert_eq!(
+            tenant_status
+                .attributes
+                .iter()
+                .map(|a| a.id)
+                .collect::<Vec<_>>(),
+            [0, 13]
+        );
+
+        let args = minicbor::to_vec(TransferArgs {
+            key: ByteVec::from(vec![1]),
+            alternative_owner: None,
+            new_owner: Address::anonymous(),
+        })
+        .unwrap();
+        assert!(call(tenant, "kvstore.transfer", args.clone()).data.is_ok());
+        let response = call(primary_address, "kvstore.transfer", args.clone());
+        assert_eq!(response.from, primary_address);
+        assert_eq!(
+            response.data.unwrap_err().code(),
+            ManyError::could_not_route_message().code()
+        );
+
+        assert!(server.lock().unwrap().remove_tenant(&tenant));
+        let response = call(tenant, "kvstore.transfer", args);
+        assert_eq!(response.from, primary_address);
+        assert!(response.data.is_err());
+    }
+
+    #[test]
+    fn module_conflicts() {
+        use many_modules::kvstore::{
+            KvStoreTransferModule, KvStoreTransferModuleBackend, TransferArgs, TransferReturn,
+        };
+
+        struct Transfer;
+        impl KvStoreTransferModuleBackend for Transfer {
+            fn transfer(
+                &mut self,
+                _sender: &Address,
+                _args: TransferArgs,
+            ) -> Result<TransferReturn, ManyError> {
+                Ok(many_modules::EmptyReturn)
+            }
+        }
+
+        let module: Arc<dyn ManyModule + Send> =
+            Arc::new(KvStoreTransferModule::new(Arc::new(Mutex::new(Transfer))));
+        let server = ManyServer::test(AnonymousIdentity);
+        let mut s = server.lock().unwrap();
+        s.try_add_module_arc(module.clone()).unwrap();
+
+        // The same module again is fine.
+        s.try_add_module_arc(module).unwrap();
+
+        let conflict = s
+            .try_add_module(KvStoreTransferModule::new(Arc::new(Mutex::new(Transfer))))
+            .unwrap_err();
+        assert_eq!(conflict.module, conflict.existing);
+        assert_eq!(conflict.attribute, Some(13));
+        assert_eq!(conflict.endpoints, vec!["kvstore.transfer".to_string()]);
+
+        // A module with other endpoints under the same attribute.
+        let base = base::BaseModule::new(server.clone());
+        let conflict = s.try_add_module(base).unwrap_err();
+        assert_eq!(conflict.attribute, Some(0));
+        assert_eq!(conflict.endpoints.len(), 4);
+    }
+
+    #[test]
+    fn validate_arguments() {
+        use many_modules::kvstore::{
+            KvStoreTransferModule, KvStoreTransferModuleBackend, TransferArgs, TransferReturn,
+        };
+        use minicbor::bytes::ByteVec;
+
+        struct Transfer;
+        impl KvStoreTransferModuleBackend for Transfer {
+            fn transfer(
+                &mut self,
+                _sender: &Address,
+                _args: TransferArgs,
+            ) -> Result<TransferReturn, ManyError> {
+                Ok(many_modules::EmptyReturn)
+            }
+        }
+
+        let id = generate_random_ed25519_identity();
+        let server = ManyServer::test(AnonymousIdentity);
+        {
+            let mut s = server.lock().unwrap();
+            s.add_module(KvStoreTransferModule::new(Arc::new(Mutex::new(Transfer))));
+            s.set_validate_arguments(true);
+        }
+        let call = |data: Vec<u8>| {
+            let request: RequestMessage = RequestMessageBuilder::default()
+                .from(id.address())
+                .method("kvstore.transfer".to_string())
+                .data(data)
+                .build()
+                .unwrap();
+            let envelope = encode_cose_sign1_from_request(request, &id).unwrap();
+            let response = smol::block_on(server.execute(envelope)).unwrap();
+            decode_response_from_cose_sign1(&response, None, &AcceptAllVerifier)
+                .unwrap()
+                .data
+        };
+
+        let args = TransferArgs {
+            key: ByteVec::from(vec![1]),
+            alternative_owner: None,
+            new_owner: Address::anonymous(),
+        };
+        assert!(call(minicbor::to_vec(&args).unwrap()).is_ok());
+
+        // An unknown key is accepted by the decoder, but not the rule.
+        let mut unknown = minicbor::Encoder::new(vec![]);
+        unknown
+            .map(3)
+            .unwrap()
+            .u8(0)
+            .unwrap()
+            .bytes(&[1])
+            .unwrap()
+            .u8(2)
+            .unwrap()
+            .encode(Address::anonymous())
+            .unwrap()
+            .u8(3)
+            .unwrap()
+            .null()
+            .unwrap();
+        let err = call(unknown.into_writer()).unwrap_err();
+        assert_eq!(err.argument("field"), Some("$"));
+
+        let mut wrong_type = minicbor::Encoder::new(vec![]);
+        wrong_type
+            .map(2)
+            .unwrap()
+            .u8(0)
+            .unwrap()
+            .bytes(&[1])
+            .unwrap()
+            .u8(2)
+            .unwrap()
+            .u8(1)
+            .unwrap();
+        let err = call(wrong_type.into_writer()).unwrap_err();
+        assert_eq!(err.argument("field"), Some("$.new_owner"));
+
+        let mut missing = minicbor::Encoder::new(vec![]);
+        missing.map(1).unwrap().u8(0).unwrap().bytes(&[1]).unwrap();
+        let err = call(missing.into_writer()).unwrap_err();
+        assert_eq!(err.argument("field"), Some("$.new_owner"));
+    }
+
+    #[test]
+    fn simulate() {
+        use crate::simulation::Simulator;
+        use many_modules::kvstore::{
+            KvStoreTransferModule, KvStoreTransferModuleBackend, TransferArgs, TransferReturn,
+        };
+
+        /// Counts transfers, in an overlay during simulations.
+        #[derive(Default)]
+        struct Transfer {
+            count: i64,
+            overlay: Option<i64>,
+            fail_rollback: bool,
+        }
+        impl KvStoreTransferModuleBackend for Transfer {
+            fn transfer(
+                &mut self,
+                _sender: &Address,
+                _args: TransferArgs,
+            ) -> Result<TransferReturn, ManyError> {
+                match self.overlay.as_mut() {
+                    Some(overlay) => *overlay += 1,
+                    None => self.count += 1,
+                }
+                Ok(many_modules::EmptyReturn)
+            }
+        }
+        impl Simulator for Transfer {
+            fn begin(&mut self) -> Result<(), ManyError> {
+                self.overlay = Some(0);
+                Ok(())
+            }
+            fn rollback(&mut self) -> Result<Vec<CborAny>, ManyError> {
+                let overlay = self.overlay.take().unwrap_or_default();
+                if self.fail_rollback {
+                    return Err(ManyError::unknown("Rollback failed."));
+                }
+                Ok(vec![CborAny::Int(overlay)])
+            }
+        }
+
+        let id = generate_random_ed25519_identity();
+        let backend = Arc::new(Mutex::new(Transfer::default()));
+        let server = ManyServer::test(AnonymousIdentity);
+        server
+            .lock()
+            .unwrap()
+            .add_module(KvStoreTransferModule::new(backend.clone()));
+
+        let call = |simulate: bool| {
+            let args = TransferArgs {
+                key: vec![1].into(),
+                alternative_owner: None,
+                new_owner: Address::anonymous(),
+            };
+            let attributes = if simulate {
+                [SIMULATE].into_iter().collect()
+            } else {
+                Default::default()
+            };
+            let request: RequestMessage = RequestMessageBuilder::default()
+                .from(id.address())
+                .method("kvstore.transfer".to_string())
+                .data(minicbor::to_vec(args).unwrap())
+                .attributes(attributes)
+                .build()
+                .unwrap();
+            let envelope = encode_cose_sign1_from_request(request, &id).unwrap();
+            let response = smol::block_on(server.execute(envelope)).unwrap();
+            decode_response_from_cose_sign1(&response, None, &AcceptAllVerifier).unwrap()
+        };
+
+        assert_eq!(
+            call(true).data.unwrap_err().code(),
+            ManyError::simulation_not_supported().code()
+        );
+        assert_eq!(backend.lock().unwrap().count, 0);
+
+        server.lock().unwrap().set_simulator(backend.clone());
+        let response = call(true);
+        assert!(response.data.is_ok());
+        assert_eq!(response.simulated_events(), Some(&vec![CborAny::Int(1)]));
+        assert_eq!(backend.lock().unwrap().count, 0);
+
+        let response = call(false);
+        assert!(response.data.is_ok());
+        assert_eq!(response.simulated_events(), None);
+        assert_eq!(backend.lock().unwrap().count, 1);
+
+        // The server stops simulating after a failed rollback.
+        backend.lock().unwrap().fail_rollback = true;
+        assert_eq!(
+            call(true).data.unwrap_err().code(),
+            ManyError::unknown("").code()
+        );
+        assert_eq!(
+            call(true).data.unwrap_err().code(),
+            ManyError::simulation_not_supported().code()
+        );
+    }
+
+    #[test]
+    fn block_time_in_context() {
+        use many_modules::kvstore::{self, KvStoreModule, KvStoreModuleBackend};
+        use many_protocol::context::Context;
+
+        // Returns the block time of its context as the value of every key.
+        struct Clock;
+        impl KvStoreModuleBackend for Clock {
+            fn info(
+                &self,
+                _: &Address,
+                _: kvstore::InfoArg,
+            ) -> Result<kvstore::InfoReturns, ManyError> {
+                unimplemented!()
+            }
+            fn get(
+                &self,
+                _: &Address,
+                _: kvstore::GetArgs,
+                context: Context,
+            ) -> Result<kvstore::GetReturns, ManyError> {
+                Ok(kvstore::GetReturns {
+                    value: context
+                        .block_time()
+                        .map(|t| t.secs().to_be_bytes().to_vec().into()),
+                })
+            }
+            fn query(
+                &self,
+                _: &Address,
+                _: kvstore::QueryArgs,
+                _: Context,
+            ) -> Result<kvstore::QueryReturns, ManyError> {
+                unimplemented!()
+            }
+            fn list(
+                &self,
+                _: &Address,
+                _: kvstore::list::ListArgs,
+            ) -> Result<kvstore::list::ListReturns, ManyError> {
+                unimplemented!()
+            }
+            fn prove(
+                &self,
+                _: &Address,
+                _: kvstore::ProveArgs,
+            ) -> Result<kvstore::ProveReturns, ManyError> {
+                unimplemented!()
+            }
+        }
+
+        let server = ManyServer::test(AnonymousIdentity);
+        server
+            .lock()
+            .unwrap()
+            .add_module(KvStoreModule::new(Arc::new(Mutex::new(Clock))));
+        let id = generate_random_ed25519_identity();
+        let get = || {
+            let request = RequestMessageBuilder::default()
+                .from(id.address())
+                .method("kvstore.get".to_string())
+                .data(
+                    minicbor::to_vec(kvstore::GetArgs {
+                        key: vec![1].into(),
+                    })
+                    .unwrap(),
+                )
+                .build()
+                .unwrap();
+            let envelope = encode_cose_sign1_from_request(request, &id).unwrap();
+            let response = smol::block_on(server.execute(envelope)).unwrap();
+            let response =
+                decode_response_from_cose_sign1(&response, None, &AcceptAllVerifier).unwrap();
+            minicbor::decode::<kvstore::GetReturns>(&response.data.unwrap())
+                .unwrap()
+                .value
+        };
+
+        assert_eq!(get(), None);
+        server
+            .lock()
+            .unwrap()
+            .set_block_time_fn(|| Some(BlockTime::from_secs(1234)));
+        assert_eq!(get(), Some(1234u64.to_be_bytes().to_vec().into()));
+    }
+
+    #[test]
+    fn deprecated_endpoint() {
+        use many_modules::kvstore::{
+            KvStoreTransferModule, KvStoreTransferModuleBackend, TransferArgs, TransferReturn,
+        };
+        use many_types::warning::Warning;
+
+        struct Transfer;
+        impl KvStoreTransferModuleBackend for Transfer {
+            fn transfer(
+                &mut self,
+                _sender: &Address,
+                _args: TransferArgs,
+            ) -> Result<TransferReturn, ManyError> {
+                Ok(many_modules::EmptyReturn)
+            }
+        }
+
+        #[derive(Debug)]
+        struct Deprecated(KvStoreTransferModule<Transfer>, ManyModuleInfo);
+        #[async_trait]
+        impl ManyModule for Deprecated {
+            fn info(&self) -> &ManyModuleInfo {
+                &self.1
+            }
+            async fn execute(&self, message: RequestMessage) -> Result<ResponseMessage, ManyError> {
+                self.0.execute(message).await
+            }
+        }
+
+        let module = KvStoreTransferModule::new(Arc::new(Mutex::new(Transfer)));
+        let deprecation = base::Deprecation {
+            replacement: Some("kvstore.put".to_string()),
+            sunset: Some(10),
+        };
+        let info = module
+            .info()
+            .clone()
+            .deprecate("kvstore.transfer", deprecation.clone());
+        let id = generate_random_ed25519_identity();
+        let server = ManyServer::test(AnonymousIdentity);
+        server.lock().unwrap().add_module(Deprecated(module, info));
+
+        let call = || {
+            let args = TransferArgs {
+                key: vec![1].into(),
+                alternative_owner: None,
+                new_owner: Address::anonymous(),
+            };
+            let request: RequestMessage = RequestMessageBuilder::default()
+                .from(id.address())
+                .method("kvstore.transfer".to_string())
+                .data(minicbor::to_vec(args).unwrap())
+                .build()
+                .unwrap();
+            let envelope = encode_cose_sign1_from_request(request, &id).unwrap();
+            let response = smol::block_on(server.execute(envelope)).unwrap();
+            decode_response_from_cose_sign1(&response, None, &AcceptAllVerifier).unwrap()
+        };
+
+        // Without a height, the endpoint is only deprecated.
+        let response = call();
+        assert!(response.data.is_ok());
+        let warnings: Vec<Warning> = response.warnings().unwrap();
+        assert_eq!(warnings, vec![deprecation.warning("kvstore.transfer")]);
+
+        let height = Arc::new(AtomicU64::new(9));
+        let h = height.clone();
+        server
+            .lock()
+            .unwrap()
+            .set_height_fn(move || Ok(h.load(Ordering::SeqCst)));
+        assert!(call().data.is_ok());
+
+        height.store(10, Ordering::SeqCst);
+        assert_eq!(
+            call().data.unwrap_err().code(),
+            ManyError::endpoint_sunset("", 0).code()
+        );
+    }
+
+    #[test]
+    fn heartbeat_unhealthy_module() {
+        use std::sync::atomic::AtomicBool;
+
+        #[derive(Debug)]
+        struct Worker(ManyModuleInfo, Arc<AtomicBool>);
+        #[async_trait]
+        impl ManyModule for Worker {
+            fn info(&self) -> &ManyModuleInfo {
+                &self.0
+            }
+            async fn execute(&self, _: RequestMessage) -> Result<ResponseMessage, ManyError> {
+                unreachable!()
+            }
+            fn heartbeat(&self) -> Result<(), ManyError> {
+                if self.1.load(Ordering::SeqCst) {
+                    Ok(())
+                } else {
+                    Err(ManyError::unknown("task stopped"))
+                }
+            }
+        }
+
+        let alive = Arc::new(AtomicBool::new(true));
+        let info = ManyModuleInfo {
+            name: "Worker".to_string(),
+            attribute: None,
+            endpoints: vec![],
+            descriptors: vec![],
+        };
+        let server = ManyServer::test(AnonymousIdentity);
+        server
+            .lock()
+            .unwrap()
+            .add_module(Worker(info, alive.clone()));
+        let heartbeat = || base::BaseModuleBackend::heartbeat(&*server.lock().unwrap());
+
+        assert!(heartbeat().is_ok());
+
+        alive.store(false, Ordering::SeqCst);
+        let err = heartbeat().unwrap_err();
+        assert_eq!(err.code(), ManyError::modules_unhealthy("").code());
+        assert!(err.to_string().contains("Worker (task stopped)"));
+    }
+
+    #[test]
+    fn status_node_info() {
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let server = ManyServer::test(AnonymousIdentity);
+        {
+            let mut s = server.lock().unwrap();
+            s.set_build_info("0123abcd", ["balance_testing", "migration_testing"]);
+            s.set_height_fn(|| Ok(42));
+        }
+
+        let status = base::BaseModuleBackend::status(&*server.lock().unwrap()).unwrap();
+        let info = status.node_info();
+        assert!(info.start_time.unwrap().abs_diff(now) <= 1);
+        assert_eq!(info.git_sha.as_deref(), Some("0123abcd"));
+        assert_eq!(
+            info.features,
+            Some(vec![
+                "balance_testing".to_string(),
+                "migration_testing".to_string()
+            ])
+        );
+        assert_eq!(info.height, Some(42));
+    }
+
+    #[test]
+    fn response_timestamp_policy() {
+        // The server clock and the block time differ, so responses show
+        // which one a policy uses.
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let server = ManyServer::test(AnonymousIdentity);
+        server.lock().unwrap().set_time_fn(move || Ok(now));
+        let id = generate_random_ed25519_identity();
+
+        let timestamp = |policy: ResponseTimestampPolicy| {
+            server.lock().unwrap().set_response_timestamp_policy(policy);
+            let request = RequestMessageBuilder::default()
+                .from(id.address())
                 .method("status".to_string())
                 .data("null".as_bytes().to_vec())
+                .timestamp(Timestamp::from_system_time(now).unwrap())
                 .build()
                 .unwrap();
-
             let envelope = encode_cose_sign1_from_request(request, &id).unwrap();
-            let response = smol::block_on(async { server.execute(envelope).await }).unwrap();
-            let response_message = decode_response_from_cose_sign1(&response, None, &AcceptAllVerifier).unwrap();
+            let response_e = smol::block_on(server.execute(envelope)).unwrap();
+            decode_response_from_cose_sign1(&response_e, None, &AcceptAllVerifier)
+                .unwrap()
+                .timestamp
+                .unwrap()
+        };
 
-            let status: Status = minicbor::decode(&response_message.data.unwrap()).unwrap();
+        assert!(timestamp(ResponseTimestampPolicy::WallClock).secs() > 1_000_000);
+        // Without a block time, the wall clock is used.
+        assert!(timestamp(ResponseTimestampPolicy::BlockTime).secs() > 1_000_000);
 
-            assert_eq!(status.version, 1);
-            assert_eq!(status.name, name);
-            assert_eq!(status.public_key, Some(server_public_key));
-            assert_eq!(status.identity, server_address);
-            assert!(status.attributes.has_id(0));
-            assert_eq!(status.server_version, Some(version.to_string()));
-            assert_eq!(status.timeout, Some(MANYSERVER_DEFAULT_TIMEOUT));
-            assert_eq!(status.extras, BTreeMap::new());
-        }
+        server
+            .lock()
+            .unwrap()
+            .set_block_time_fn(|| Some(BlockTime::from_secs(900_000)));
+        assert_eq!(
+            timestamp(ResponseTimestampPolicy::BlockTime),
+            Timestamp::new(900_000).unwrap()
+        );
+        assert_eq!(
+            timestamp(ResponseTimestampPolicy::epoch()),
+            Timestamp::new(0).unwrap()
+        );
+        assert_eq!(
+            "fixed:42".parse(),
+            Ok(ResponseTimestampPolicy::Fixed(Timestamp::new(42).unwrap()))
+        );
     }
 
     #[test]
@@ -455,6 +2098,388 @@ mod tests {
         assert!(response.data.is_err());
     }
 
+    #[test]
+    fn signer_set() {
+        use crate::signers::SignerSet;
+        use many_identity_dsa::CoseKeyVerifier;
+        use many_protocol::encode_cose_sign_from_request;
+
+        let [a, b, c] = [(); 3].map(|_| generate_random_ed25519_identity());
+        let server = ManyServer::simple("test", AnonymousIdentity, CoseKeyVerifier, None);
+        server.lock().unwrap().add_signer_set(
+            a.address(),
+            SignerSet::new([b.address(), c.address()], 2).unwrap(),
+        );
+        let request: RequestMessage = RequestMessageBuilder::default()
+            .from(a.address())
+            .method("status".to_string())
+            .data("null".as_bytes().to_vec())
+            .build()
+            .unwrap();
+        let execute_multi = |identities: &[&dyn Identity]| {
+            let envelope = encode_cose_sign_from_request(request.clone(), identities).unwrap();
+            let response = smol::block_on(server.execute_multi(envelope)).unwrap();
+            decode_response_from_cose_sign1(&response, None, &AcceptAllVerifier)
+                .unwrap()
+                .data
+        };
+
+        assert!(execute_multi(&[&a, &b, &c]).is_ok());
+        assert_eq!(
+            execute_multi(&[&a, &b]).unwrap_err().code(),
+            ManyError::not_enough_signers(1, 2).code()
+        );
+
+        // The sender must be one of the signers.
+        assert_eq!(
+            execute_multi(&[&b, &c]).unwrap_err().code(),
+            ManyError::invalid_from_identity().code()
+        );
+
+        // A single signer envelope does not meet the threshold.
+        let envelope = encode_cose_sign1_from_request(request, &a).unwrap();
+        let response = smol::block_on(server.execute(envelope)).unwrap();
+        let response =
+            decode_response_from_cose_sign1(&response, None, &AcceptAllVerifier).unwrap();
+        assert_eq!(
+            response.data.unwrap_err().code(),
+            ManyError::not_enough_signers(0, 2).code()
+        );
+    }
+
+    #[test]
+    fn multi_signer_envelopes() {
+        use many_identity_dsa::CoseKeyVerifier;
+        use many_protocol::encode_cose_sign_from_request;
+
+        struct Validator(Mutex<Vec<usize>>);
+        impl RequestValidator for Arc<Validator> {
+            fn multi_message_executed(
+                &mut self,
+                envelope: &CoseSign,
+                _response: &ResponseMessage,
+            ) -> Result<(), ManyError> {
+                self.0.lock().unwrap().push(envelope.signatures.len());
+                Ok(())
+            }
+        }
+
+        #[derive(Debug)]
+        struct SingleSigner(ManyModuleInfo);
+        #[async_trait]
+        impl ManyModule for SingleSigner {
+            fn info(&self) -> &ManyModuleInfo {
+                &self.0
+            }
+            async fn execute(&self, message: RequestMessage) -> Result<ResponseMessage, ManyError> {
+                Ok(ResponseMessage::from_request(
+                    &message,
+                    &message.to,
+                    Ok(vec![]),
+                ))
+            }
+        }
+
+        let [a, b] = [(); 2].map(|_| generate_random_ed25519_identity());
+        let validator = Arc::new(Validator(Mutex::new(vec![])));
+        let server = ManyServer::simple("test", AnonymousIdentity, CoseKeyVerifier, None);
+        {
+            let mut server = server.lock().unwrap();
+            server.add_validator(validator.clone());
+            server.add_module(SingleSigner(ManyModuleInfo {
+                name: "SingleSigner".to_string(),
+                attribute: None,
+                endpoints: vec!["single".to_string()],
+                descriptors: vec![],
+            }));
+        }
+        let execute_multi = |method: &str| {
+            let request: RequestMessage = RequestMessageBuilder::default()
+                .from(a.address())
+                .method(method.to_string())
+                .data("null".as_bytes().to_vec())
+                .build()
+                .unwrap();
+            let envelope = encode_cose_sign_from_request(request, &[&a, &b]).unwrap();
+            let response = smol::block_on(server.execute_multi(envelope)).unwrap();
+            decode_response_from_cose_sign1(&response, None, &AcceptAllVerifier)
+                .unwrap()
+                .data
+        };
+
+        // Validators see the envelope with all its signatures.
+        assert!(execute_multi("status").is_ok());
+        assert_eq!(*validator.0.lock().unwrap(), vec![2]);
+
+        // Modules which don't validate multi-signer envelopes reject them.
+        assert_eq!(
+            execute_multi("single").unwrap_err().code(),
+            ManyError::multi_signer_not_supported("").code()
+        );
+    }
+
+    #[test]
+    fn delegation_expires_at_block_time() {
+        use many_identity_dsa::CoseKeyVerifier;
+        use many_types::delegation::Delegation;
+        use many_types::DelegationChain;
+
+        let [a, b] = [(); 2].map(|_| generate_random_ed25519_identity());
+        let chain = DelegationChain::new([Delegation {
+            from: a.address(),
+            to: b.address(),
+            methods: None,
+            expiration: Timestamp::new(100).unwrap(),
+        }
+        .sign(&a)
+        .unwrap()]);
+
+        let server = ManyServer::simple("test", AnonymousIdentity, CoseKeyVerifier, None);
+        server
+            .lock()
+            .unwrap()
+            .set_time_fn(|| Ok(SystemTime::UNIX_EPOCH + Duration::from_secs(50)));
+        let execute = || {
+            // The delegate signs a request dated before the expiration.
+            let request = RequestMessageBuilder::default()
+                .from(a.address())
+                .method("status".to_string())
+                .data("null".as_bytes().to_vec())
+                .timestamp(Timestamp::new(50).unwrap())
+                .build()
+                .unwrap()
+                .with_attribute(chain.to_attribute().unwrap());
+            let envelope = encode_cose_sign1_from_request(request, &b).unwrap();
+            let response = smol::block_on(server.execute(envelope)).unwrap();
+            decode_response_from_cose_sign1(&response, None, &AcceptAllVerifier)
+                .unwrap()
+                .data
+        };
+        assert!(execute().is_ok());
+
+        server
+            .lock()
+            .unwrap()
+            .set_block_time_fn(|| Some(BlockTime::from_secs(150)));
+        assert_eq!(
+            execute().unwrap_err().code(),
+            ManyError::invalid_delegation("").code()
+        );
+    }
+
+    #[test]
+    fn fallback_status_policy() {
+        #[derive(Debug)]
+        struct Fallback;
+
+        #[async_trait]
+        impl LowLevelManyRequestHandler for Fallback {
+            async fn execute(&self, _envelope: CoseSign1) -> Result<CoseSign1, String> {
+                Err("unused".to_string())
+            }
+        }
+
+        impl base::BaseModuleBackend for Fallback {
+            fn endpoints(&self) -> Result<base::Endpoints, ManyError> {
+                Ok(base::Endpoints(BTreeSet::new()))
+            }
+
+            fn status(&self) -> Result<Status, ManyError> {
+                let mut builder = base::StatusBuilder::default();
+                builder
+                    .name("fallback".to_string())
+                    .version(1)
+                    .identity(Address::anonymous())
+                    .server_version("2.0.0".to_string())
+                    .attributes([Attribute::id(3)].into_iter().collect())
+                    .extras(BTreeMap::from([("height".to_string(), CborAny::Int(10))]));
+                Ok(builder.build().unwrap())
+            }
+        }
+
+        let id = generate_random_ed25519_identity();
+        let address = id.address();
+        let server = ManyServer::simple("server", id, AcceptAllVerifier, Some("1.0.0".into()));
+        server.lock().unwrap().set_fallback_module(Fallback);
+        let status = |policy| {
+            let mut s = server.lock().unwrap();
+            s.set_fallback_status_policy(policy);
+            base::BaseModuleBackend::status(&*s)
+        };
+
+        let err = status(FallbackStatusPolicy::Strict).unwrap_err();
+        assert_eq!(err.code(), ManyError::internal_server_error().code());
+
+        let merged = status(FallbackStatusPolicy::Merge).unwrap();
+        assert_eq!(merged.name, "fallback");
+        assert_eq!(merged.identity, address);
+        assert_eq!(merged.server_version, Some("1.0.0".to_string()));
+        assert!(merged.attributes.has_id(0) && merged.attributes.has_id(3));
+        assert_eq!(merged.extras.get("height"), Some(&CborAny::Int(10)));
+
+        let namespaced = status(FallbackStatusPolicy::Namespace).unwrap();
+        assert_eq!(namespaced.name, "server");
+        assert_eq!(namespaced.identity, address);
+        assert!(namespaced.attributes.has_id(3));
+        assert_eq!(
+            namespaced.extras.get("fallback.serverVersion"),
+            Some(&CborAny::String("2.0.0".to_string()))
+        );
+        assert_eq!(
+            namespaced.extras.get("fallback.height"),
+            Some(&CborAny::Int(10))
+        );
+        assert!(!namespaced.extras.contains_key("height"));
+    }
+
+    #[test]
+    fn version_hooks() {
+        let id = generate_random_ed25519_identity();
+        let server = ManyServer::test(AnonymousIdentity);
+        let execute = |version: u8| {
+            let request = RequestMessageBuilder::default()
+                .version(version)
+                .from(id.address())
+                .method("status".to_string())
+                .build()
+                .unwrap();
+            let envelope = encode_cose_sign1_from_request(request, &id).unwrap();
+            let response_e = smol::block_on(server.execute(envelope)).unwrap();
+            decode_response_from_cose_sign1(&response_e, None, &AcceptAllVerifier)
+                .unwrap()
+                .data
+        };
+
+        assert!(execute(1).is_ok());
+        assert_eq!(
+            execute(2).unwrap_err().code(),
+            ManyError::unsupported_protocol_version(2).code()
+        );
+
+        server.lock().unwrap().add_version_hook(2, Ok);
+        let status: Status = minicbor::decode(&execute(2).unwrap()).unwrap();
+        assert_eq!(status.supported_versions, Some(vec![1, 2]));
+    }
+
+    #[test]
+    fn response_id() {
+        let server = ManyServer::test(AnonymousIdentity);
+        let execute = |request: RequestMessage| {
+            let envelope = encode_cose_sign1_from_request(request, &AnonymousIdentity).unwrap();
+            let response = smol::block_on(server.execute(envelope)).unwrap();
+            decode_response_from_cose_sign1(&response, None, &AcceptAllVerifier).unwrap()
+        };
+        let request = |timestamp: SystemTime| {
+            RequestMessageBuilder::default()
+                .id(42)
+                .method("status".to_string())
+                .data("null".as_bytes().to_vec())
+                .timestamp(Timestamp::from_system_time(timestamp).unwrap())
+                .build()
+                .unwrap()
+        };
+
+        let response = execute(request(SystemTime::now()));
+        assert!(response.data.is_ok());
+        assert_eq!(response.id, Some(42));
+
+        // Errors returned before the request is validated also keep the ID.
+        let response = execute(request(SystemTime::now() - Duration::from_secs(3600)));
+        assert!(response.data.is_err());
+        assert_eq!(response.id, Some(42));
+    }
+
+    #[test]
+    fn network_id() {
+        let server = ManyServer::test(AnonymousIdentity);
+        server
+            .lock()
+            .unwrap()
+            .set_network_id(NetworkId::from("mainnet"), true);
+        let execute = |network_id: Option<&str>| {
+            let mut request = RequestMessageBuilder::default()
+                .method("status".to_string())
+                .data("null".as_bytes().to_vec())
+                .build()
+                .unwrap();
+            if let Some(id) = network_id {
+                request = request.with_attribute(NetworkId::from(id).into());
+            }
+            let envelope = encode_cose_sign1_from_request(request, &AnonymousIdentity).unwrap();
+            let response = smol::block_on(server.execute(envelope)).unwrap();
+            decode_response_from_cose_sign1(&response, None, &AcceptAllVerifier)
+                .unwrap()
+                .data
+        };
+
+        let status: Status = minicbor::decode(&execute(Some("mainnet")).unwrap()).unwrap();
+        assert_eq!(status.network_id, Some(NetworkId::from("mainnet")));
+        assert_eq!(
+            execute(Some("testnet")).unwrap_err().code(),
+            ManyError::network_id_mismatch("", "").code()
+        );
+        assert_eq!(
+            execute(None).unwrap_err().code(),
+            ManyError::network_id_missing("").code()
+        );
+    }
+
+    #[test]
+    fn module_panic() {
+        use many_modules::kvstore::{
+            KvStoreTransferModule, KvStoreTransferModuleBackend, TransferArgs, TransferReturn,
+        };
+
+        struct Transfer;
+        impl KvStoreTransferModuleBackend for Transfer {
+            fn transfer(
+                &mut self,
+                _sender: &Address,
+                _args: TransferArgs,
+            ) -> Result<TransferReturn, ManyError> {
+                panic!("Transfer failed");
+            }
+        }
+
+        let id = generate_random_ed25519_identity();
+        let server = ManyServer::test(AnonymousIdentity);
+        server
+            .lock()
+            .unwrap()
+            .add_module(KvStoreTransferModule::new(Arc::new(Mutex::new(Transfer))));
+
+        let call = |method: &str, data: Vec<u8>| {
+            let request: RequestMessage = RequestMessageBuilder::default()
+                .from(id.address())
+                .method(method.to_string())
+                .data(data)
+                .build()
+                .unwrap();
+            let envelope = encode_cose_sign1_from_request(request, &id).unwrap();
+            let response = smol::block_on(server.execute(envelope)).unwrap();
+            decode_response_from_cose_sign1(&response, None, &AcceptAllVerifier)
+                .unwrap()
+                .data
+        };
+
+        let args = TransferArgs {
+            key: vec![1].into(),
+            alternative_owner: None,
+            new_owner: Address::anonymous(),
+        };
+        assert_eq!(
+            call("kvstore.transfer", minicbor::to_vec(args).unwrap())
+                .unwrap_err()
+                .code(),
+            ManyError::module_panicked("").code()
+        );
+        assert_eq!(server.lock().unwrap().module_panics(), 1);
+
+        // The server still serves requests, and reports the panic.
+        let status = base::Status::from_bytes(&call("status", b"null".to_vec()).unwrap()).unwrap();
+        assert_eq!(status.node_info().module_panics, Some(1));
+    }
+
     #[test]
     fn validate_time() {
         let timestamp = SystemTime::now();
@@ -637,4 +2662,271 @@ mod tests {
             decode_response_from_cose_sign1(&response_e, None, &AcceptAllVerifier).unwrap();
         assert!(response.data.is_err());
     }
+
+    /// A module returning the block time it executes its requests at.
+    #[derive(Debug)]
+    struct Probe(ManyModuleInfo);
+
+    #[async_trait]
+    impl ManyModule for Probe {
+        fn info(&self) -> &ManyModuleInfo {
+            &self.0
+        }
+        async fn execute(&self, message: RequestMessage) -> Result<ResponseMessage, ManyError> {
+            self.execute_with_block_time(message, None).await
+        }
+        async fn execute_with_block_time(
+            &self,
+            message: RequestMessage,
+            block_time: Option<BlockTime>,
+        ) -> Result<ResponseMessage, ManyError> {
+            let data = minicbor::to_vec(block_time.map(|t| t.secs())).unwrap();
+            Ok(ResponseMessage::from_request(
+                &message,
+                &message.to,
+                Ok(data),
+            ))
+        }
+    }
+
+    struct Relay;
+    impl relay::RelayModuleBackend for Relay {
+        fn info(
+            &self,
+            _sender: &Address,
+            _args: relay::InfoArgs,
+        ) -> Result<relay::InfoReturns, ManyError> {
+            unimplemented!()
+        }
+        fn relay(
+            &mut self,
+            _relayer: &Address,
+            _request: &RequestMessage,
+        ) -> Result<(), ManyError> {
+            Ok(())
+        }
+    }
+
+    /// A server relaying requests to a [Probe] module, whose `probe` endpoint
+    /// is deprecated.
+    fn relay_server() -> Arc<Mutex<ManyServer>> {
+        use many_identity_dsa::CoseKeyVerifier;
+        use many_modules::base::EndpointDescriptor;
+
+        let info = ManyModuleInfo {
+            name: "Probe".to_string(),
+            attribute: None,
+            endpoints: vec!["probe".to_string()],
+            descriptors: vec![EndpointDescriptor {
+                name: "probe".to_string(),
+                argument: None,
+                returns: "Probe".to_string(),
+                deprecated: None,
+                redacted: None,
+            }],
+        }
+        .deprecate(
+            "probe",
+            base::Deprecation {
+                replacement: None,
+                sunset: Some(10),
+            },
+        );
+        let server = ManyServer::simple("test", AnonymousIdentity, CoseKeyVerifier, None);
+        {
+            let mut s = server.lock().unwrap();
+            s.add_module(Probe(info));
+            s.add_module(relay::RelayModule::new(
+                Arc::new(Mutex::new(Relay)),
+                server.clone(),
+            ));
+        }
+        server
+    }
+
+    fn probe_request(from: Address, attributes: AttributeSet) -> RequestMessage {
+        RequestMessageBuilder::default()
+            .from(from)
+            .method("probe".to_string())
+            .attributes(attributes)
+            .build()
+            .unwrap()
+    }
+
+    /// Relay a request in its envelope, with a `relay.execute` request of a
+    /// new relayer.
+    fn relay_envelope(
+        server: &Arc<Mutex<ManyServer>>,
+        envelope: CoseSign1,
+        attributes: AttributeSet,
+    ) -> ResponseMessage {
+        use coset::CborSerializable;
+
+        let relayer = generate_random_ed25519_identity();
+        let args = relay::ExecuteArgs {
+            envelope: envelope.to_vec().unwrap().into(),
+        };
+        let request = RequestMessageBuilder::default()
+            .from(relayer.address())
+            .method("relay.execute".to_string())
+            .data(minicbor::to_vec(args).unwrap())
+            .attributes(attributes)
+            .build()
+            .unwrap();
+        let envelope = encode_cose_sign1_from_request(request, &relayer).unwrap();
+        let response = smol::block_on(server.execute(envelope)).unwrap();
+        decode_response_from_cose_sign1(&response, None, &AcceptAllVerifier).unwrap()
+    }
+
+    #[test]
+    fn relay_runs_validators() {
+        struct Validator(AtomicBool, Mutex<Vec<String>>);
+        impl RequestValidator for Arc<Validator> {
+            fn validate_request(&self, request: &RequestMessage) -> Result<(), ManyError> {
+                if request.method == "probe" && self.0.load(Ordering::Relaxed) {
+                    Err(ManyError::unknown("test validator failed"))
+                } else {
+                    Ok(())
+                }
+            }
+            fn message_executed(
+                &mut self,
+                envelope: &CoseSign1,
+                _response: &ResponseMessage,
+            ) -> Result<(), ManyError> {
+                let request = RequestMessage::try_from(envelope).unwrap();
+                self.1.lock().unwrap().push(request.method);
+                Ok(())
+            }
+        }
+
+        let server = relay_server();
+        let validator = Arc::new(Validator(AtomicBool::new(false), Mutex::new(vec![])));
+        server.lock().unwrap().add_validator(validator.clone());
+        let id = generate_random_ed25519_identity();
+        let envelope = || {
+            encode_cose_sign1_from_request(probe_request(id.address(), AttributeSet::new()), &id)
+                .unwrap()
+        };
+
+        assert!(relay_envelope(&server, envelope(), AttributeSet::new())
+            .data
+            .is_ok());
+        assert_eq!(
+            *validator.1.lock().unwrap(),
+            vec!["probe".to_string(), "relay.execute".to_string()]
+        );
+
+        validator.0.store(true, Ordering::Relaxed);
+        assert!(relay_envelope(&server, envelope(), AttributeSet::new())
+            .data
+            .is_err());
+    }
+
+    #[test]
+    fn relay_checks_network_id() {
+        let server = relay_server();
+        server
+            .lock()
+            .unwrap()
+            .set_network_id(NetworkId::from("mainnet"), true);
+        let network = || AttributeSet::from_iter([NetworkId::from("mainnet").into()]);
+        let id = generate_random_ed25519_identity();
+        let relay = |attributes| {
+            let request = probe_request(id.address(), attributes);
+            let envelope = encode_cose_sign1_from_request(request, &id).unwrap();
+            relay_envelope(&server, envelope, network()).data
+        };
+
+        assert!(relay(network()).is_ok());
+        assert_eq!(
+            relay(AttributeSet::new()).unwrap_err().code(),
+            ManyError::network_id_missing("").code()
+        );
+    }
+
+    #[test]
+    fn relay_checks_signer_sets() {
+        use crate::signers::SignerSet;
+
+        let server = relay_server();
+        let [a, b] = [(); 2].map(|_| generate_random_ed25519_identity());
+        server
+            .lock()
+            .unwrap()
+            .add_signer_set(a.address(), SignerSet::new([b.address()], 1).unwrap());
+        let relay = |identity: &dyn Identity| {
+            let request = probe_request(a.address(), AttributeSet::new());
+            let envelope = encode_cose_sign1_from_request(request, identity).unwrap();
+            relay_envelope(&server, envelope, AttributeSet::new()).data
+        };
+
+        assert_eq!(
+            relay(&a).unwrap_err().code(),
+            ManyError::not_enough_signers(0, 1).code()
+        );
+    }
+
+    #[test]
+    fn relay_checks_sunset() {
+        use many_types::warning::Warning;
+
+        let server = relay_server();
+        let height = Arc::new(AtomicU64::new(9));
+        let h = height.clone();
+        server
+            .lock()
+            .unwrap()
+            .set_height_fn(move || Ok(h.load(Ordering::SeqCst)));
+        let id = generate_random_ed25519_identity();
+        let relay = || {
+            let request = probe_request(id.address(), AttributeSet::new());
+            let envelope = encode_cose_sign1_from_request(request, &id).unwrap();
+            relay_envelope(&server, envelope, AttributeSet::new())
+        };
+
+        let response = relay();
+        assert!(response.data.is_ok());
+        let warnings: Vec<Warning> = response.warnings().unwrap();
+        assert_eq!(warnings.len(), 1);
+
+        height.store(10, Ordering::SeqCst);
+        assert_eq!(
+            relay().data.unwrap_err().code(),
+            ManyError::endpoint_sunset("", 0).code()
+        );
+    }
+
+    #[test]
+    fn relay_passes_block_time() {
+        let server = relay_server();
+        server
+            .lock()
+            .unwrap()
+            .set_block_time_fn(|| Some(BlockTime::from_secs(1234)));
+        let id = generate_random_ed25519_identity();
+        let request = probe_request(id.address(), AttributeSet::new());
+        let envelope = encode_cose_sign1_from_request(request, &id).unwrap();
+
+        let data = relay_envelope(&server, envelope, AttributeSet::new())
+            .data
+            .unwrap();
+        assert_eq!(minicbor::decode::<Option<u64>>(&data).unwrap(), Some(1234));
+    }
+
+    #[test]
+    fn relay_cannot_simulate() {
+        let server = relay_server();
+        let id = generate_random_ed25519_identity();
+        let request = probe_request(id.address(), AttributeSet::from_iter([SIMULATE]));
+        let envelope = encode_cose_sign1_from_request(request, &id).unwrap();
+
+        assert_eq!(
+            relay_envelope(&server, envelope, AttributeSet::new())
+                .data
+                .unwrap_err()
+                .code(),
+            relay::invalid_relayed_request("").code()
+        );
+    }
 }
@@ -0,0 +1,82 @@
+//! Signer sets, requiring the requests from an address to be signed by a
+//! threshold of identities, e.g. a hardware key and a delegation key, in a
+//! multi-signer envelope.
+use many_error::ManyError;
+use many_identity::Address;
+use std::collections::BTreeSet;
+
+/// A threshold of N signers.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignerSet {
+    signers: BTreeSet<Address>,
+    threshold: usize,
+}
+
+impl SignerSet {
+    /// Fails if the threshold is zero or more than the number of signers.
+    pub fn new(
+        signers: impl IntoIterator<Item = Address>,
+        threshold: usize,
+    ) -> Result<Self, ManyError> {
+        let signers = BTreeSet::from_iter(signers);
+        if threshold == 0 || threshold > signers.len() {
+            return Err(ManyError::invalid_argument(
+                "threshold",
+                format!("must be between 1 and {}", signers.len()),
+            ));
+        }
+        Ok(Self { signers, threshold })
+    }
+
+    pub fn signers(&self) -> &BTreeSet<Address> {
+        &self.signers
+    }
+
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// Check that enough members of the set are among the signers of a
+    /// request. Other signers are ignored.
+    pub fn validate(&self, signers: &BTreeSet<Address>) -> Result<(), ManyError> {
+        let count = self.signers.intersection(signers).count();
+        if count < self.threshold {
+            Err(ManyError::not_enough_signers(count, self.threshold))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use many_identity::testing::identity;
+
+    #[test]
+    fn new() {
+        assert!(SignerSet::new([identity(1), identity(2)], 2).is_ok());
+        assert!(SignerSet::new([identity(1), identity(2)], 0).is_err());
+        assert!(SignerSet::new([identity(1), identity(2)], 3).is_err());
+
+        // Duplicates count once.
+        assert!(SignerSet::new([identity(1), identity(1)], 2).is_err());
+    }
+
+    #[test]
+    fn validate() {
+        let set = SignerSet::new([identity(1), identity(2), identity(3)], 2).unwrap();
+        assert!(set
+            .validate(&BTreeSet::from([identity(1), identity(3)]))
+            .is_ok());
+        assert!(set
+            .validate(&BTreeSet::from([identity(1), identity(2), identity(3)]))
+            .is_ok());
+        assert_eq!(
+            set.validate(&BTreeSet::from([identity(1), identity(4)]))
+                .unwrap_err()
+                .code(),
+            ManyError::not_enough_signers(1, 2).code()
+        );
+    }
+}
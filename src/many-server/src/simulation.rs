@@ -0,0 +1,17 @@
+use many_error::ManyError;
+use many_types::cbor::CborAny;
+
+/// The state of a backend which can be set aside while a request carrying
+/// the [`many_types::SIMULATE`] attribute is executed, so wallets can show
+/// the exact effects of a command before sending it.
+///
+/// The server executes nothing else during a simulation, so the overlay
+/// only ever holds the changes of the simulated request.
+pub trait Simulator: Send {
+    /// Start sending the changes to the state to a throwaway overlay.
+    fn begin(&mut self) -> Result<(), ManyError>;
+
+    /// Drop the overlay and return the encoded events the request would have
+    /// emitted.
+    fn rollback(&mut self) -> Result<Vec<CborAny>, ManyError>;
+}
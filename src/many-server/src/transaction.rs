@@ -0,0 +1,401 @@
+use many_error::ManyError;
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
+
+/// A backend storage which can commit or undo the changes made since the
+/// start of a transaction, so a command changing the state of several
+/// backends (e.g. ledger balances and key-value store entries) applies all
+/// its changes or none of them.
+pub trait StorageTransaction: Send {
+    /// Start recording the changes to the state. Transactions cannot be
+    /// nested.
+    fn begin(&mut self) -> Result<(), ManyError>;
+
+    /// Keep the changes made since `begin`. Other storages of the same
+    /// transaction may have committed already, so this should only fail if
+    /// there is no open transaction.
+    fn commit(&mut self) -> Result<(), ManyError>;
+
+    /// Undo the changes made since `begin`.
+    fn rollback(&mut self) -> Result<(), ManyError>;
+}
+
+/// The previous values of the keys written during a transaction, to restore
+/// them on rollback.
+#[derive(Debug, Default)]
+pub struct UndoLog {
+    entries: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+}
+
+impl UndoLog {
+    /// Record the value of a key before it is written, or `None` if the key
+    /// does not exist.
+    pub fn record(&mut self, key: Vec<u8>, previous: Option<Vec<u8>>) {
+        self.entries.push((key, previous));
+    }
+
+    /// The values to restore, latest write first. A key written several
+    /// times is restored to its value at the start of the transaction.
+    pub fn into_undo(self) -> impl Iterator<Item = (Vec<u8>, Option<Vec<u8>>)> {
+        self.entries.into_iter().rev()
+    }
+}
+
+/// An error of a persistent store.
+#[derive(Debug)]
+pub struct StoreError(String);
+
+impl Display for StoreError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl StoreError {
+    pub fn new(message: impl ToString) -> Self {
+        Self(message.to_string())
+    }
+}
+
+/// The operations of the persistent key-value store of a backend, e.g. a
+/// merk tree, which a [JournaledStore] journals.
+pub trait StoreBackend {
+    type Store;
+    type Op;
+
+    fn get(store: &Self::Store, key: &[u8]) -> Result<Option<Vec<u8>>, StoreError>;
+    fn apply(store: &mut Self::Store, batch: &[(Vec<u8>, Self::Op)]) -> Result<(), StoreError>;
+    fn commit(store: &mut Self::Store, aux: &[(Vec<u8>, Self::Op)]) -> Result<(), StoreError>;
+
+    /// The operation writing back the previous value of a key, or deleting
+    /// the key if it did not exist.
+    fn restore(previous: Option<Vec<u8>>) -> Self::Op;
+}
+
+/// The calls to a persistent store which will fail, to test that a backend
+/// recovers or fails cleanly when its store fails. Counts start at the next
+/// call, and a failure is only injected once.
+#[derive(Debug, Default)]
+pub struct Faults {
+    apply: Option<usize>,
+    commit: Option<usize>,
+}
+
+/// Whether the call counted down by `slot` fails.
+fn countdown(slot: &mut Option<usize>) -> bool {
+    match slot {
+        Some(0) => {
+            *slot = None;
+            true
+        }
+        Some(n) => {
+            *n -= 1;
+            false
+        }
+        None => false,
+    }
+}
+
+impl Faults {
+    /// Fail the call to `apply` after `n` successful ones.
+    pub fn fail_apply(&mut self, n: usize) -> &mut Self {
+        self.apply = Some(n);
+        self
+    }
+
+    /// Fail the call to `commit` after `n` successful ones.
+    pub fn fail_commit(&mut self, n: usize) -> &mut Self {
+        self.commit = Some(n);
+        self
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    fn check_apply(&mut self) -> Result<(), StoreError> {
+        if countdown(&mut self.apply) {
+            Err(StoreError::new("Injected apply failure"))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn check_commit(&mut self) -> Result<(), StoreError> {
+        if countdown(&mut self.commit) {
+            Err(StoreError::new("Injected commit failure"))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// The persistent store of a backend. While a transaction is open, the
+/// previous value of every key written is recorded so the writes can be
+/// undone. Writes made to the inner store directly are not recorded.
+pub struct JournaledStore<B: StoreBackend> {
+    inner: B::Store,
+    undo: Option<UndoLog>,
+    faults: Faults,
+}
+
+impl<B: StoreBackend> JournaledStore<B> {
+    pub fn new(inner: B::Store) -> Self {
+        Self {
+            inner,
+            undo: None,
+            faults: Faults::default(),
+        }
+    }
+
+    pub fn apply(&mut self, batch: &[(Vec<u8>, B::Op)]) -> Result<(), StoreError> {
+        self.faults.check_apply()?;
+
+        if let Some(undo) = &mut self.undo {
+            for (key, _) in batch {
+                undo.record(key.clone(), B::get(&self.inner, key)?);
+            }
+        }
+        B::apply(&mut self.inner, batch)
+    }
+
+    pub fn commit(&mut self, aux: &[(Vec<u8>, B::Op)]) -> Result<(), StoreError> {
+        self.faults.check_commit()?;
+        B::commit(&mut self.inner, aux)
+    }
+
+    /// Start recording the writes to the store.
+    pub fn begin(&mut self) {
+        self.undo = Some(UndoLog::default());
+    }
+
+    /// Stop recording the writes, keeping them.
+    pub fn end(&mut self) {
+        self.undo = None;
+    }
+
+    /// Undo the writes recorded since `begin`, and stop recording.
+    pub fn undo(&mut self) -> Result<(), StoreError> {
+        let undo = self.undo.take().unwrap_or_default();
+        for (key, previous) in undo.into_undo() {
+            self.apply(&[(key, B::restore(previous))])?;
+        }
+        Ok(())
+    }
+
+    /// The failures to inject in the calls to the store. Only tests should
+    /// inject failures.
+    pub fn faults(&mut self) -> &mut Faults {
+        &mut self.faults
+    }
+}
+
+impl<B: StoreBackend> Deref for JournaledStore<B> {
+    type Target = B::Store;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<B: StoreBackend> DerefMut for JournaledStore<B> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+/// Run `f` in a transaction over all the storages. If `f` returns an error,
+/// the changes of every storage are undone and the error is returned.
+pub fn atomically<R>(
+    storages: &[Arc<Mutex<dyn StorageTransaction>>],
+    f: impl FnOnce() -> Result<R, ManyError>,
+) -> Result<R, ManyError> {
+    let rollback = |storages: &[Arc<Mutex<dyn StorageTransaction>>]| {
+        for storage in storages.iter().rev() {
+            if let Err(e) = storage.lock().unwrap().rollback() {
+                tracing::error!("Unable to rollback a storage transaction: {e}");
+            }
+        }
+    };
+
+    for (i, storage) in storages.iter().enumerate() {
+        if let Err(e) = storage.lock().unwrap().begin() {
+            rollback(&storages[..i]);
+            return Err(e);
+        }
+    }
+
+    match f() {
+        Ok(result) => {
+            for storage in storages {
+                storage.lock().unwrap().commit()?;
+            }
+            Ok(result)
+        }
+        Err(e) => {
+            rollback(storages);
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[derive(Default)]
+    struct Store {
+        values: BTreeMap<Vec<u8>, Vec<u8>>,
+        undo: Option<UndoLog>,
+    }
+
+    impl Store {
+        fn put(&mut self, key: &[u8], value: &[u8]) {
+            let previous = self.values.insert(key.to_vec(), value.to_vec());
+            if let Some(undo) = &mut self.undo {
+                undo.record(key.to_vec(), previous);
+            }
+        }
+    }
+
+    impl StorageTransaction for Store {
+        fn begin(&mut self) -> Result<(), ManyError> {
+            if self.undo.is_some() {
+                return Err(ManyError::unknown("Transaction already open."));
+            }
+            self.undo = Some(UndoLog::default());
+            Ok(())
+        }
+
+        fn commit(&mut self) -> Result<(), ManyError> {
+            self.undo
+                .take()
+                .map(|_| ())
+                .ok_or_else(|| ManyError::unknown("No open transaction."))
+        }
+
+        fn rollback(&mut self) -> Result<(), ManyError> {
+            let undo = self
+                .undo
+                .take()
+                .ok_or_else(|| ManyError::unknown("No open transaction."))?;
+            for (key, previous) in undo.into_undo() {
+                match previous {
+                    Some(value) => self.values.insert(key, value),
+                    None => self.values.remove(&key),
+                };
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn commit_and_rollback() {
+        let a = Arc::new(Mutex::new(Store::default()));
+        let b = Arc::new(Mutex::new(Store::default()));
+        a.lock().unwrap().put(b"x", b"0");
+        let storages: [Arc<Mutex<dyn StorageTransaction>>; 2] = [a.clone(), b.clone()];
+
+        let result = atomically(&storages, || {
+            a.lock().unwrap().put(b"x", b"1");
+            b.lock().unwrap().put(b"y", b"1");
+            Ok(())
+        });
+        assert!(result.is_ok());
+        assert_eq!(a.lock().unwrap().values[b"x".as_slice()], b"1");
+        assert_eq!(b.lock().unwrap().values[b"y".as_slice()], b"1");
+
+        let result: Result<(), _> = atomically(&storages, || {
+            a.lock().unwrap().put(b"x", b"2");
+            a.lock().unwrap().put(b"x", b"3");
+            b.lock().unwrap().put(b"z", b"2");
+            Err(ManyError::unknown("failed"))
+        });
+        assert!(result.is_err());
+        assert_eq!(a.lock().unwrap().values[b"x".as_slice()], b"1");
+        assert!(!b.lock().unwrap().values.contains_key(b"z".as_slice()));
+        assert!(a.lock().unwrap().undo.is_none());
+        assert!(b.lock().unwrap().undo.is_none());
+    }
+
+    struct MapBackend;
+
+    impl StoreBackend for MapBackend {
+        type Store = BTreeMap<Vec<u8>, Vec<u8>>;
+        type Op = Option<Vec<u8>>;
+
+        fn get(store: &Self::Store, key: &[u8]) -> Result<Option<Vec<u8>>, StoreError> {
+            Ok(store.get(key).cloned())
+        }
+
+        fn apply(store: &mut Self::Store, batch: &[(Vec<u8>, Self::Op)]) -> Result<(), StoreError> {
+            for (key, op) in batch {
+                match op {
+                    Some(value) => store.insert(key.clone(), value.clone()),
+                    None => store.remove(key),
+                };
+            }
+            Ok(())
+        }
+
+        fn commit(_: &mut Self::Store, _: &[(Vec<u8>, Self::Op)]) -> Result<(), StoreError> {
+            Ok(())
+        }
+
+        fn restore(previous: Option<Vec<u8>>) -> Self::Op {
+            previous
+        }
+    }
+
+    #[test]
+    fn journaled_store() {
+        let mut store = JournaledStore::<MapBackend>::new(BTreeMap::new());
+        store
+            .apply(&[(b"x".to_vec(), Some(b"0".to_vec()))])
+            .unwrap();
+
+        store.begin();
+        store
+            .apply(&[(b"x".to_vec(), Some(b"1".to_vec()))])
+            .unwrap();
+        store
+            .apply(&[(b"x".to_vec(), None), (b"y".to_vec(), Some(b"1".to_vec()))])
+            .unwrap();
+        store.undo().unwrap();
+        assert_eq!(*store, BTreeMap::from([(b"x".to_vec(), b"0".to_vec())]));
+
+        store.begin();
+        store
+            .apply(&[(b"y".to_vec(), Some(b"2".to_vec()))])
+            .unwrap();
+        store.end();
+        // Nothing is recorded once the transaction ended.
+        store.undo().unwrap();
+        assert_eq!(store.get(b"y".as_slice()), Some(&b"2".to_vec()));
+    }
+
+    #[test]
+    fn injected_faults() {
+        let mut store = JournaledStore::<MapBackend>::new(BTreeMap::new());
+        store.faults().fail_apply(1).fail_commit(0);
+        assert!(store.commit(&[]).is_err());
+        assert!(store.commit(&[]).is_ok());
+        assert!(store.apply(&[]).is_ok());
+        assert!(store.apply(&[]).is_err());
+        assert!(store.apply(&[]).is_ok());
+    }
+
+    #[test]
+    fn begin_failure() {
+        let a = Arc::new(Mutex::new(Store::default()));
+        let b = Arc::new(Mutex::new(Store::default()));
+        b.lock().unwrap().begin().unwrap();
+        let storages: [Arc<Mutex<dyn StorageTransaction>>; 2] = [a.clone(), b];
+
+        assert!(atomically(&storages, || Ok(())).is_err());
+        // The transaction of the first storage was rolled back.
+        assert!(a.lock().unwrap().undo.is_none());
+    }
+}
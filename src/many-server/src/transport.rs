@@ -1,5 +1,5 @@
 use async_trait::async_trait;
-use coset::CoseSign1;
+use coset::{CoseSign, CoseSign1};
 use many_error::ManyError;
 use many_protocol::{RequestMessage, ResponseMessage};
 use std::fmt::Debug;
@@ -9,6 +9,12 @@ pub mod http;
 #[async_trait]
 pub trait LowLevelManyRequestHandler: Send + Sync + Debug {
     async fn execute(&self, envelope: CoseSign1) -> Result<CoseSign1, String>;
+
+    /// Execute a request in a multi-signer envelope. Handlers which do not
+    /// support them fail.
+    async fn execute_multi(&self, _envelope: CoseSign) -> Result<CoseSign1, String> {
+        Err("Multi-signer envelopes are not supported.".to_string())
+    }
 }
 
 /// A simpler version of the [ManyRequestHandler] which only deals with methods and payloads.
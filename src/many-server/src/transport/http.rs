@@ -1,6 +1,7 @@
 use crate::transport::LowLevelManyRequestHandler;
 use anyhow::anyhow;
-use coset::{CoseSign1, TaggedCborSerializable};
+use coset::{CborSerializable, CoseError, CoseSign, CoseSign1, TaggedCborSerializable};
+use many_protocol::buffer::PooledBuffer;
 use std::fmt::Debug;
 use std::io::Cursor;
 use std::net::ToSocketAddrs;
@@ -13,10 +14,51 @@ use tracing::info;
 /// Maximum of 5MB per HTTP request.
 const READ_BUFFER_LEN: usize = 1024 * 1024 * 5;
 
+/// Which COSE Sign1 envelopes the server accepts. Responses are always
+/// tagged.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum EnvelopeTagging {
+    /// Only accept envelopes with the COSE Sign1 CBOR tag (18).
+    Strict,
+
+    /// Also accept untagged envelopes, as sent by some COSE libraries.
+    #[default]
+    Lenient,
+}
+
+impl EnvelopeTagging {
+    pub fn decode(&self, bytes: &[u8]) -> Result<CoseSign1, CoseError> {
+        match CoseSign1::from_tagged_slice(bytes) {
+            Err(_) if *self == EnvelopeTagging::Lenient => CoseSign1::from_slice(bytes),
+            result => result,
+        }
+    }
+}
+
+/// A single or multi-signer envelope.
+#[derive(Debug)]
+enum Envelope {
+    Single(CoseSign1),
+    Multi(CoseSign),
+}
+
+impl Envelope {
+    /// Multi-signer envelopes are only accepted with their CBOR tag (98).
+    fn decode(tagging: EnvelopeTagging, bytes: &[u8]) -> Result<Self, CoseError> {
+        match tagging.decode(bytes) {
+            Ok(envelope) => Ok(Self::Single(envelope)),
+            Err(e) => CoseSign::from_tagged_slice(bytes)
+                .map(Self::Multi)
+                .map_err(|_| e),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct HttpServer<E: LowLevelManyRequestHandler> {
     executor: E,
     term_signal: Arc<AtomicBool>,
+    tagging: EnvelopeTagging,
 }
 
 impl<E: LowLevelManyRequestHandler> HttpServer<E> {
@@ -24,9 +66,15 @@ impl<E: LowLevelManyRequestHandler> HttpServer<E> {
         Self {
             executor,
             term_signal: Arc::new(AtomicBool::new(false)),
+            tagging: EnvelopeTagging::default(),
         }
     }
 
+    pub fn with_envelope_tagging(mut self, tagging: EnvelopeTagging) -> Self {
+        self.tagging = tagging;
+        self
+    }
+
     async fn handle_request(&self, request: &mut Request) -> Response<std::io::Cursor<Vec<u8>>> {
         match request.body_length() {
             Some(x) if x > READ_BUFFER_LEN => {
@@ -38,30 +86,32 @@ impl<E: LowLevelManyRequestHandler> HttpServer<E> {
             _ => {}
         }
 
-        let mut v = Vec::new();
-        let _ = request.as_reader().read_to_end(&mut v);
-
-        let bytes = &v;
-
-        tracing::debug!("request  len={}", bytes.len());
-        tracing::trace!("request  {}", hex::encode(bytes));
-
-        let envelope = match CoseSign1::from_tagged_slice(bytes) {
-            Ok(cs) => cs,
-            Err(e) => {
-                tracing::error!(
-                    r#"Error decoding envelope. Error description="{}""#,
-                    e.to_string()
-                );
-                return Response::empty(500u16).with_data(Cursor::new(vec![]), Some(0));
+        let envelope = {
+            // The envelope copies what it needs, so the buffer can go back to
+            // the pool right away.
+            let mut bytes = PooledBuffer::take();
+            let _ = request.as_reader().read_to_end(&mut bytes);
+
+            tracing::debug!("request  len={}", bytes.len());
+            tracing::trace!("request  {}", hex::encode(&*bytes));
+
+            match Envelope::decode(self.tagging, &bytes) {
+                Ok(cs) => cs,
+                Err(e) => {
+                    tracing::error!(
+                        r#"Error decoding envelope. Error description="{}""#,
+                        e.to_string()
+                    );
+                    return Response::empty(500u16).with_data(Cursor::new(vec![]), Some(0));
+                }
             }
         };
 
-        let response = self
-            .executor
-            .execute(envelope)
-            .await
-            .and_then(|r| r.to_tagged_vec().map_err(|e| e.to_string()));
+        let response = match envelope {
+            Envelope::Single(envelope) => self.executor.execute(envelope).await,
+            Envelope::Multi(envelope) => self.executor.execute_multi(envelope).await,
+        }
+        .and_then(|r| r.to_tagged_vec().map_err(|e| e.to_string()));
         let bytes = match response {
             Ok(bytes) => bytes,
             Err(e) => {
@@ -103,3 +153,47 @@ impl<E: LowLevelManyRequestHandler> HttpServer<E> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use coset::{CoseSign1Builder, CoseSignBuilder};
+
+    #[test]
+    fn envelope_tagging() {
+        let envelope = CoseSign1Builder::new().payload(vec![1, 2, 3]).build();
+        let tagged = envelope.clone().to_tagged_vec().unwrap();
+        let untagged = envelope.clone().to_vec().unwrap();
+
+        for tagging in [EnvelopeTagging::Strict, EnvelopeTagging::Lenient] {
+            assert_eq!(tagging.decode(&tagged).unwrap().payload, envelope.payload);
+        }
+        assert!(EnvelopeTagging::Strict.decode(&untagged).is_err());
+        assert_eq!(
+            EnvelopeTagging::Lenient.decode(&untagged).unwrap().payload,
+            envelope.payload
+        );
+        assert!(EnvelopeTagging::Lenient.decode(&[0xFF]).is_err());
+    }
+
+    #[test]
+    fn multi_signer_envelope() {
+        let envelope = CoseSignBuilder::new().payload(vec![1, 2, 3]).build();
+        let tagged = envelope.clone().to_tagged_vec().unwrap();
+        let untagged = envelope.to_vec().unwrap();
+
+        for tagging in [EnvelopeTagging::Strict, EnvelopeTagging::Lenient] {
+            assert!(matches!(
+                Envelope::decode(tagging, &tagged),
+                Ok(Envelope::Multi(e)) if e.payload == Some(vec![1, 2, 3])
+            ));
+            assert!(Envelope::decode(tagging, &untagged).is_err());
+        }
+
+        let single = CoseSign1Builder::new().payload(vec![1]).build();
+        assert!(matches!(
+            Envelope::decode(EnvelopeTagging::Strict, &single.to_tagged_vec().unwrap()),
+            Ok(Envelope::Single(_))
+        ));
+    }
+}
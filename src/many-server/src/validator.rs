@@ -1,6 +1,7 @@
-use coset::CoseSign1;
+use coset::{CoseSign, CoseSign1};
 use many_error::ManyError;
 use many_protocol::{RequestMessage, ResponseMessage};
+use std::time::Duration;
 
 /// A trait for transforming a request.
 pub trait RequestValidator {
@@ -14,6 +15,11 @@ pub trait RequestValidator {
     fn validate_request(&self, _request: &RequestMessage) -> Result<(), ManyError> {
         Ok(())
     }
+
+    /// The time spent by the module executing a request, which is only known
+    /// once it was executed. Simulated requests are not timed.
+    fn request_timed(&mut self, _request: &RequestMessage, _execution_time: Duration) {}
+
     fn message_executed(
         &mut self,
         _request_envelope: &CoseSign1,
@@ -21,6 +27,18 @@ pub trait RequestValidator {
     ) -> Result<(), ManyError> {
         Ok(())
     }
+
+    /// Validate a multi-signer envelope, prior to executing the message.
+    fn validate_multi_envelope(&self, _envelope: &CoseSign) -> Result<(), ManyError> {
+        Ok(())
+    }
+    fn multi_message_executed(
+        &mut self,
+        _request_envelope: &CoseSign,
+        _response: &ResponseMessage,
+    ) -> Result<(), ManyError> {
+        Ok(())
+    }
 }
 
 /// A RequestValidator that does not run message_executed(), but only validate
@@ -40,6 +58,9 @@ impl<T: RequestValidator> RequestValidator for ValidateOnlyRequestValidator<T> {
     fn validate_request(&self, request: &RequestMessage) -> Result<(), ManyError> {
         self.0.validate_request(request)
     }
+    fn validate_multi_envelope(&self, envelope: &CoseSign) -> Result<(), ManyError> {
+        self.0.validate_multi_envelope(envelope)
+    }
 }
 
 impl RequestValidator for () {}
@@ -51,6 +72,9 @@ impl<A: RequestValidator + ?Sized> RequestValidator for Box<A> {
     fn validate_request(&self, request: &RequestMessage) -> Result<(), ManyError> {
         self.as_ref().validate_request(request)
     }
+    fn request_timed(&mut self, request: &RequestMessage, execution_time: Duration) {
+        self.as_mut().request_timed(request, execution_time)
+    }
     fn message_executed(
         &mut self,
         request_envelope: &CoseSign1,
@@ -58,6 +82,17 @@ impl<A: RequestValidator + ?Sized> RequestValidator for Box<A> {
     ) -> Result<(), ManyError> {
         self.as_mut().message_executed(request_envelope, response)
     }
+    fn validate_multi_envelope(&self, envelope: &CoseSign) -> Result<(), ManyError> {
+        self.as_ref().validate_multi_envelope(envelope)
+    }
+    fn multi_message_executed(
+        &mut self,
+        request_envelope: &CoseSign,
+        response: &ResponseMessage,
+    ) -> Result<(), ManyError> {
+        self.as_mut()
+            .multi_message_executed(request_envelope, response)
+    }
 }
 
 impl<A, B> RequestValidator for (A, B)
@@ -73,6 +108,10 @@ where
         self.0.validate_request(request)?;
         self.1.validate_request(request)
     }
+    fn request_timed(&mut self, request: &RequestMessage, execution_time: Duration) {
+        self.0.request_timed(request, execution_time);
+        self.1.request_timed(request, execution_time);
+    }
     fn message_executed(
         &mut self,
         envelope: &CoseSign1,
@@ -81,4 +120,16 @@ where
         self.0.message_executed(envelope, response)?;
         self.1.message_executed(envelope, response)
     }
+    fn validate_multi_envelope(&self, envelope: &CoseSign) -> Result<(), ManyError> {
+        self.0.validate_multi_envelope(envelope)?;
+        self.1.validate_multi_envelope(envelope)
+    }
+    fn multi_message_executed(
+        &mut self,
+        envelope: &CoseSign,
+        response: &ResponseMessage,
+    ) -> Result<(), ManyError> {
+        self.0.multi_message_executed(envelope, response)?;
+        self.1.multi_message_executed(envelope, response)
+    }
 }
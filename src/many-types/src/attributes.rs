@@ -27,6 +27,11 @@ impl AttributeSet {
         self.0.insert(attr)
     }
 
+    /// Remove and return the attribute with this ID, if any.
+    pub fn take(&mut self, id: AttributeId) -> Option<Attribute> {
+        self.0.take(&Attribute::id(id))
+    }
+
     pub fn has_id(&self, id: AttributeId) -> bool {
         self.0.iter().any(|a| id == a.id)
     }
@@ -2,9 +2,13 @@ use base64::{engine::general_purpose, Engine as _};
 use minicbor::data::{Tag, Type};
 use minicbor::encode::{Error, Write};
 use minicbor::{Decode, Decoder, Encode, Encoder};
+use serde::de::{self, MapAccess, SeqAccess, Unexpected, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::BTreeMap;
 use std::fmt::{Debug, Formatter};
 
+pub mod diff;
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct CborNull;
 
@@ -123,6 +127,97 @@ impl<'d, C> Decode<'d, C> for CborAny {
     }
 }
 
+/// Converts to the JSON data model, e.g. for inputs or outputs of a CLI.
+/// Tags are dropped and bytes are serialized as a sequence of numbers by
+/// most formats.
+impl Serialize for CborAny {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            CborAny::Bool(b) => serializer.serialize_bool(*b),
+            CborAny::Int(i) => serializer.serialize_i64(*i),
+            CborAny::String(s) => serializer.serialize_str(s),
+            CborAny::Bytes(b) => serializer.serialize_bytes(b),
+            CborAny::Array(arr) => serializer.collect_seq(arr),
+            CborAny::Map(m) => serializer.collect_map(m),
+            CborAny::Tagged(_, v) => v.serialize(serializer),
+            CborAny::Null => serializer.serialize_unit(),
+        }
+    }
+}
+
+struct CborAnyVisitor;
+
+impl<'de> Visitor<'de> for CborAnyVisitor {
+    type Value = CborAny;
+
+    fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+        f.write_str("a value without floating point numbers")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+        Ok(CborAny::Bool(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(CborAny::Int(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        i64::try_from(v)
+            .map(CborAny::Int)
+            .map_err(|_| E::invalid_value(Unexpected::Unsigned(v), &"a 64 bits signed integer"))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(CborAny::String(v.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+        Ok(CborAny::String(v))
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Ok(CborAny::Bytes(v.to_vec()))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(CborAny::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(CborAny::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, d: D) -> Result<Self::Value, D::Error> {
+        d.deserialize_any(self)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut arr = Vec::new();
+        while let Some(v) = seq.next_element()? {
+            arr.push(v);
+        }
+        Ok(CborAny::Array(arr))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut m = BTreeMap::new();
+        while let Some((k, v)) = map.next_entry()? {
+            m.insert(k, v);
+        }
+        Ok(CborAny::Map(m))
+    }
+}
+
+/// Converts from the JSON data model. Keys of maps are kept as strings, as
+/// JSON has no other kind of key; see `many_modules::cddl::CddlRule::coerce`
+/// to fit a value to the type of an argument.
+impl<'de> Deserialize<'de> for CborAny {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(CborAnyVisitor)
+    }
+}
+
 /// Encode/Decode cbor in a Base64 String instead of its CBOR value. `T` must be
 /// transformable to (Deref) and from (FromIterator<u8>) a byte array.
 #[derive(Clone, Debug, Ord, PartialOrd, Eq, PartialEq)]
@@ -197,6 +292,130 @@ where
     }
 }
 
+/// Limits on the size of untrusted CBOR values. The declared lengths of
+/// arrays, maps and strings are checked before anything is decoded, so a
+/// small hostile payload cannot make a decoder allocate large buffers or
+/// recurse until the stack overflows.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DecodeLimits {
+    pub max_array_len: u64,
+    pub max_map_entries: u64,
+    /// The maximum length of byte and text strings, in bytes.
+    pub max_bytes_len: u64,
+    pub max_depth: usize,
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self {
+            max_array_len: 1 << 16,
+            max_map_entries: 1 << 16,
+            max_bytes_len: 64 << 20,
+            max_depth: 64,
+        }
+    }
+}
+
+impl DecodeLimits {
+    /// Check the limits then decode a value.
+    pub fn decode<'b, T: Decode<'b, ()>>(
+        &self,
+        bytes: &'b [u8],
+    ) -> Result<T, minicbor::decode::Error> {
+        self.check(bytes)?;
+        minicbor::decode(bytes)
+    }
+
+    /// Check that all values in `bytes` are within the limits.
+    pub fn check(&self, bytes: &[u8]) -> Result<(), minicbor::decode::Error> {
+        let mut d = Decoder::new(bytes);
+        while d.position() < bytes.len() {
+            self.check_value(&mut d, 0)?;
+        }
+        Ok(())
+    }
+
+    fn check_len(&self, what: &str, len: u64, max: u64) -> Result<(), minicbor::decode::Error> {
+        if len > max {
+            Err(minicbor::decode::Error::message(format!(
+                "{what} of length {len} exceeds the limit of {max}"
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn check_value(&self, d: &mut Decoder, depth: usize) -> Result<(), minicbor::decode::Error> {
+        if depth > self.max_depth {
+            return Err(minicbor::decode::Error::message(format!(
+                "Value nested deeper than the limit of {}",
+                self.max_depth
+            )));
+        }
+        // Every item takes at least a byte, so declared lengths larger than
+        // the input are refused before iterating on them.
+        let remaining = (d.input().len() - d.position()) as u64;
+
+        match d.datatype()? {
+            Type::Array | Type::ArrayIndef => match d.array()? {
+                Some(len) => {
+                    self.check_len("Array", len, self.max_array_len.min(remaining))?;
+                    for _ in 0..len {
+                        self.check_value(d, depth + 1)?;
+                    }
+                }
+                None => {
+                    let mut len = 0;
+                    while d.datatype()? != Type::Break {
+                        len += 1;
+                        self.check_len("Array", len, self.max_array_len)?;
+                        self.check_value(d, depth + 1)?;
+                    }
+                    d.skip()?;
+                }
+            },
+            Type::Map | Type::MapIndef => match d.map()? {
+                Some(len) => {
+                    self.check_len("Map", len, self.max_map_entries.min(remaining / 2))?;
+                    for _ in 0..len * 2 {
+                        self.check_value(d, depth + 1)?;
+                    }
+                }
+                None => {
+                    let mut len = 0;
+                    while d.datatype()? != Type::Break {
+                        len += 1;
+                        self.check_len("Map", len, self.max_map_entries)?;
+                        self.check_value(d, depth + 1)?;
+                        self.check_value(d, depth + 1)?;
+                    }
+                    d.skip()?;
+                }
+            },
+            Type::Bytes | Type::BytesIndef => {
+                let mut len = 0;
+                for chunk in d.bytes_iter()? {
+                    len += chunk?.len() as u64;
+                    self.check_len("Byte string", len, self.max_bytes_len)?;
+                }
+            }
+            Type::String | Type::StringIndef => {
+                let mut len = 0;
+                for chunk in d.str_iter()? {
+                    len += chunk?.len() as u64;
+                    self.check_len("Text string", len, self.max_bytes_len)?;
+                }
+            }
+            Type::Tag => {
+                d.tag()?;
+                self.check_value(d, depth + 1)?;
+            }
+            _ => d.skip()?,
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
@@ -211,6 +430,82 @@ pub mod tests {
         assert_eq!(hex::encode(enc), "f6");
     }
 
+    #[test]
+    fn decode_limits() {
+        let limits = DecodeLimits {
+            max_array_len: 3,
+            max_map_entries: 2,
+            max_bytes_len: 4,
+            max_depth: 2,
+        };
+
+        for diag in [
+            "[1, 2, 3]",
+            "[_ 1, 2, 3]",
+            "{1: [1, 2], 2: h'01020304'}",
+            "\"abcd\"",
+            "[[1]]",
+            "1(2)",
+            "",
+        ] {
+            let bytes = cbor_diag::parse_diag(diag).map_or(vec![], |v| v.to_bytes());
+            assert!(limits.check(&bytes).is_ok(), "{diag}");
+        }
+
+        for diag in [
+            "[1, 2, 3, 4]",
+            "[_ 1, 2, 3, 4]",
+            "{1: 1, 2: 2, 3: 3}",
+            "h'0102030405'",
+            "(_ h'0102', h'030405')",
+            "\"abcde\"",
+            "[[[1]]]",
+        ] {
+            let bytes = cbor_diag::parse_diag(diag).unwrap().to_bytes();
+            assert!(limits.check(&bytes).is_err(), "{diag}");
+        }
+
+        // A huge declared length with no content.
+        let bytes = hex::decode("9b00000000ffffffff").unwrap();
+        assert!(DecodeLimits::default().check(&bytes).is_err());
+        assert!(DecodeLimits::default().decode::<Vec<u64>>(&bytes).is_err());
+    }
+
+    #[test]
+    fn serde() {
+        use serde_test::{assert_de_tokens, assert_ser_tokens, assert_tokens, Token};
+
+        let value = CborAny::Map(BTreeMap::from([
+            (CborAny::String("a".to_string()), CborAny::Int(-1)),
+            (
+                CborAny::String("b".to_string()),
+                CborAny::Array(vec![CborAny::Bool(true), CborAny::Null]),
+            ),
+        ]));
+        assert_tokens(
+            &value,
+            &[
+                Token::Map { len: Some(2) },
+                Token::Str("a"),
+                Token::I64(-1),
+                Token::Str("b"),
+                Token::Seq { len: Some(2) },
+                Token::Bool(true),
+                Token::Unit,
+                Token::SeqEnd,
+                Token::MapEnd,
+            ],
+        );
+
+        assert_de_tokens(&CborAny::Int(1), &[Token::U64(1)]);
+        assert_de_tokens(&CborAny::Null, &[Token::None]);
+        assert_ser_tokens(
+            &CborAny::Tagged(Tag::Timestamp, Box::new(CborAny::Int(1))),
+            &[Token::I64(1)],
+        );
+        assert_ser_tokens(&CborAny::Bytes(vec![1, 2]), &[Token::Bytes(&[1, 2])]);
+    }
+
     /// Generate arbitraty CborAny value.
     ///
     /// Recursive structures depth, size and branch size are limited
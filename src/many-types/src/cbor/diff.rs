@@ -0,0 +1,155 @@
+//! Structural differences between two CBOR values.
+use super::CborAny;
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
+
+/// A value which differs between two CBOR values. A value missing on one side
+/// was added or removed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Difference {
+    /// The path of the value from the root, e.g. `$[4].name[0]`. String keys
+    /// of maps are written after a dot, other keys and indices in brackets.
+    pub path: String,
+    pub before: Option<CborAny>,
+    pub after: Option<CborAny>,
+}
+
+impl Display for Difference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (&self.before, &self.after) {
+            (None, Some(after)) => write!(f, "+ {}: {after:?}", self.path),
+            (Some(before), None) => write!(f, "- {}: {before:?}", self.path),
+            (Some(before), Some(after)) => {
+                write!(f, "~ {}: {before:?} -> {after:?}", self.path)
+            }
+            (None, None) => write!(f, "= {}", self.path),
+        }
+    }
+}
+
+/// The differences between two values, in the order of their paths. Maps are
+/// compared key by key and arrays index by index; tagged values are compared
+/// by their content if they have the same tag.
+pub fn diff(before: &CborAny, after: &CborAny) -> Vec<Difference> {
+    let mut differences = Vec::new();
+    diff_at("$".to_string(), Some(before), Some(after), &mut differences);
+    differences
+}
+
+fn key_segment(key: &CborAny) -> String {
+    match key {
+        CborAny::String(s) => format!(".{s}"),
+        other => format!("[{other:?}]"),
+    }
+}
+
+fn diff_at(
+    path: String,
+    before: Option<&CborAny>,
+    after: Option<&CborAny>,
+    differences: &mut Vec<Difference>,
+) {
+    match (before, after) {
+        (Some(CborAny::Array(b)), Some(CborAny::Array(a))) => {
+            for i in 0..b.len().max(a.len()) {
+                diff_at(format!("{path}[{i}]"), b.get(i), a.get(i), differences);
+            }
+        }
+        (Some(CborAny::Map(b)), Some(CborAny::Map(a))) => {
+            let keys: BTreeSet<&CborAny> = b.keys().chain(a.keys()).collect();
+            for key in keys {
+                let path = format!("{path}{}", key_segment(key));
+                diff_at(path, b.get(key), a.get(key), differences);
+            }
+        }
+        (Some(CborAny::Tagged(tb, b)), Some(CborAny::Tagged(ta, a))) if tb == ta => {
+            diff_at(path, Some(b), Some(a), differences);
+        }
+        (b, a) if b != a => differences.push(Difference {
+            path,
+            before: b.cloned(),
+            after: a.cloned(),
+        }),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use minicbor::data::Tag;
+    use std::collections::BTreeMap;
+
+    fn map(entries: Vec<(CborAny, CborAny)>) -> CborAny {
+        CborAny::Map(BTreeMap::from_iter(entries))
+    }
+
+    #[test]
+    fn same() {
+        let value = map(vec![(
+            CborAny::Int(1),
+            CborAny::Array(vec![CborAny::Bool(true), CborAny::Null]),
+        )]);
+        assert!(diff(&value, &value).is_empty());
+    }
+
+    #[test]
+    fn structural() {
+        let before = CborAny::Tagged(
+            Tag::Unassigned(10002),
+            Box::new(map(vec![
+                (CborAny::Int(1), CborAny::Bytes(vec![1, 2])),
+                (
+                    CborAny::Int(4),
+                    map(vec![(
+                        CborAny::String("amounts".to_string()),
+                        CborAny::Array(vec![CborAny::Int(1), CborAny::Int(2)]),
+                    )]),
+                ),
+                (CborAny::Int(5), CborAny::Int(100)),
+            ])),
+        );
+        let after = CborAny::Tagged(
+            Tag::Unassigned(10002),
+            Box::new(map(vec![
+                (CborAny::Int(1), CborAny::Bytes(vec![1, 2])),
+                (
+                    CborAny::Int(4),
+                    map(vec![(
+                        CborAny::String("amounts".to_string()),
+                        CborAny::Array(vec![CborAny::Int(1), CborAny::Int(3), CborAny::Int(4)]),
+                    )]),
+                ),
+                (CborAny::Int(6), CborAny::Int(7)),
+            ])),
+        );
+
+        let differences: Vec<String> = diff(&before, &after)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            differences,
+            [
+                "~ $[4].amounts[1]: 2 -> 3",
+                "+ $[4].amounts[2]: 4",
+                "- $[5]: 100",
+                "+ $[6]: 7",
+            ]
+        );
+    }
+
+    #[test]
+    fn tags() {
+        let before = CborAny::Tagged(Tag::Timestamp, Box::new(CborAny::Int(1)));
+        let after = CborAny::Tagged(Tag::Unassigned(1000), Box::new(CborAny::Int(1)));
+        assert_eq!(
+            diff(&before, &after),
+            [Difference {
+                path: "$".to_string(),
+                before: Some(before),
+                after: Some(after),
+            }]
+        );
+    }
+}
@@ -0,0 +1,272 @@
+//! Delegations let an address authorize another key to sign requests on its
+//! behalf, e.g. so a service can act for its users without holding their keys.
+//!
+//! A delegation is a COSE Sign1 envelope signed by the delegator, whose
+//! payload is an encoded [Delegation]. Delegates can delegate further, so the
+//! [DELEGATION] attribute of a request carries a chain of envelopes going from
+//! the `from` address of the request to the key signing the request.
+use crate::attributes::{Attribute, AttributeSet, TryFromAttributeSet};
+use crate::cbor::CborAny;
+use crate::Timestamp;
+use coset::{CoseSign1, CoseSign1Builder, TaggedCborSerializable};
+use many_error::ManyError;
+use many_identity::{Address, Identity, Verifier};
+use minicbor::{Decode, Encode};
+use std::collections::BTreeSet;
+
+/// Request attribute carrying a delegation chain, with the tagged COSE Sign1
+/// envelope of each link as arguments.
+pub const DELEGATION: Attribute = Attribute::id(7);
+
+/// The maximum number of links in a delegation chain.
+pub const MAX_CHAIN_LENGTH: usize = 8;
+
+/// `from` authorizes `to` to sign requests on its behalf.
+#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct Delegation {
+    #[n(0)]
+    pub from: Address,
+
+    #[n(1)]
+    pub to: Address,
+
+    /// The methods the delegate can call, or all of them if `None`.
+    #[n(2)]
+    pub methods: Option<BTreeSet<String>>,
+
+    /// The delegation cannot be used from this time on.
+    #[n(3)]
+    pub expiration: Timestamp,
+}
+
+impl Delegation {
+    /// Whether the delegation allows calling this method at this time.
+    pub fn allows(&self, method: &str, now: Timestamp) -> bool {
+        now < self.expiration
+            && self
+                .methods
+                .as_ref()
+                .map_or(true, |methods| methods.contains(method))
+    }
+
+    /// Sign the delegation with the identity of the delegator.
+    pub fn sign(&self, identity: &impl Identity) -> Result<CoseSign1, ManyError> {
+        if !identity.address().matches(&self.from) {
+            return Err(ManyError::invalid_delegation(
+                "it must be signed by its delegator",
+            ));
+        }
+        let payload = minicbor::to_vec(self).map_err(ManyError::serialization_error)?;
+        identity.sign_1(CoseSign1Builder::new().payload(payload).build())
+    }
+
+    fn decode(link: &CoseSign1) -> Result<Self, ManyError> {
+        link.payload
+            .as_ref()
+            .ok_or_else(ManyError::empty_envelope)
+            .and_then(|payload| minicbor::decode(payload).map_err(ManyError::deserialization_error))
+    }
+}
+
+/// Signed delegations, each one delegating to the delegator of the next.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DelegationChain(pub Vec<CoseSign1>);
+
+impl DelegationChain {
+    pub fn new(links: impl IntoIterator<Item = CoseSign1>) -> Self {
+        Self(links.into_iter().collect())
+    }
+
+    /// The address the chain acts on behalf of, i.e. the delegator of its
+    /// first link.
+    pub fn delegator(&self) -> Result<Address, ManyError> {
+        let link = self
+            .0
+            .first()
+            .ok_or_else(|| ManyError::invalid_delegation("the chain is empty"))?;
+        Delegation::decode(link).map(|delegation| delegation.from)
+    }
+
+    /// Verify that the chain authorizes `signer` to call `method` on behalf of
+    /// `from` at time `now`. Each link must be signed by its delegator, as
+    /// resolved by the verifier.
+    pub fn verify(
+        &self,
+        verifier: &impl Verifier,
+        from: &Address,
+        signer: &Address,
+        method: &str,
+        now: Timestamp,
+    ) -> Result<(), ManyError> {
+        if self.0.is_empty() || self.0.len() > MAX_CHAIN_LENGTH {
+            return Err(ManyError::invalid_delegation(format!(
+                "the chain must have between 1 and {MAX_CHAIN_LENGTH} links"
+            )));
+        }
+
+        let mut expected = *from;
+        for link in &self.0 {
+            let delegation = Delegation::decode(link)?;
+            let delegator = verifier.verify_1(link)?;
+            if delegation.from != expected || !delegator.matches(&delegation.from) {
+                return Err(ManyError::invalid_delegation(format!(
+                    "a link must be signed by {expected}"
+                )));
+            }
+            if !delegation.allows(method, now) {
+                return Err(ManyError::invalid_delegation(format!(
+                    "{} cannot call '{method}' for {}",
+                    delegation.to, delegation.from
+                )));
+            }
+            expected = delegation.to;
+        }
+
+        if signer.matches(&expected) {
+            Ok(())
+        } else {
+            Err(ManyError::invalid_delegation(format!(
+                "the chain delegates to {expected}, not to the signer {signer}"
+            )))
+        }
+    }
+
+    pub fn to_attribute(&self) -> Result<Attribute, ManyError> {
+        let links = self
+            .0
+            .iter()
+            .map(|link| {
+                link.clone()
+                    .to_tagged_vec()
+                    .map(CborAny::Bytes)
+                    .map_err(ManyError::serialization_error)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Attribute::new(DELEGATION.id, links))
+    }
+}
+
+/// Returns the delegation chain of a set, or `None` if the set has none.
+impl TryFromAttributeSet for Option<DelegationChain> {
+    fn try_from_set(set: &AttributeSet) -> Result<Self, ManyError> {
+        set.get_attribute(DELEGATION.id)
+            .map(|attr| {
+                attr.arguments()
+                    .iter()
+                    .map(|argument| match argument {
+                        CborAny::Bytes(bytes) => CoseSign1::from_tagged_slice(bytes)
+                            .map_err(|_| ManyError::invalid_attribute_arguments()),
+                        _ => Err(ManyError::invalid_attribute_arguments()),
+                    })
+                    .collect::<Result<_, _>>()
+                    .map(DelegationChain)
+            })
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use coset::CoseKey;
+
+    fn identity(seed: u8) -> Address {
+        let mut bytes = [seed; 29];
+        bytes[0] = 1;
+        Address::from_bytes(&bytes).unwrap()
+    }
+
+    /// Signs by setting the key ID to its address, with no signature.
+    struct KeyIdIdentity(Address);
+
+    impl Identity for KeyIdIdentity {
+        fn address(&self) -> Address {
+            self.0
+        }
+
+        fn public_key(&self) -> Option<CoseKey> {
+            None
+        }
+
+        fn sign_1(&self, mut envelope: CoseSign1) -> Result<CoseSign1, ManyError> {
+            envelope.protected.header.key_id = self.0.to_vec();
+            Ok(envelope)
+        }
+    }
+
+    /// Resolves the key ID, with no verification.
+    struct KeyIdVerifier;
+
+    impl Verifier for KeyIdVerifier {
+        fn verify_1(&self, envelope: &CoseSign1) -> Result<Address, ManyError> {
+            Address::from_bytes(&envelope.protected.header.key_id)
+        }
+    }
+
+    fn link(from: u8, to: u8, methods: Option<&[&str]>) -> CoseSign1 {
+        Delegation {
+            from: identity(from),
+            to: identity(to),
+            methods: methods.map(|m| m.iter().map(|s| s.to_string()).collect()),
+            expiration: Timestamp::new(100).unwrap(),
+        }
+        .sign(&KeyIdIdentity(identity(from)))
+        .unwrap()
+    }
+
+    #[test]
+    fn verify() {
+        let now = Timestamp::new(50).unwrap();
+        let verify = |chain: &DelegationChain, signer: u8, method: &str, now: Timestamp| {
+            chain.verify(&KeyIdVerifier, &identity(1), &identity(signer), method, now)
+        };
+
+        let chain = DelegationChain::new([link(1, 2, None), link(2, 3, Some(&["ledger.send"]))]);
+        assert_eq!(chain.delegator().unwrap(), identity(1));
+        assert!(verify(&chain, 3, "ledger.send", now).is_ok());
+
+        // Every link must allow the method.
+        assert!(verify(&chain, 3, "ledger.balance", now).is_err());
+
+        // The chain must end with the signer.
+        assert!(verify(&chain, 2, "ledger.send", now).is_err());
+
+        // Delegations expire.
+        assert!(verify(&chain, 3, "ledger.send", Timestamp::new(100).unwrap()).is_err());
+
+        // Links must follow each other.
+        let broken = DelegationChain::new([link(1, 2, None), link(4, 3, None)]);
+        assert!(verify(&broken, 3, "ledger.send", now).is_err());
+
+        assert!(verify(&DelegationChain::default(), 1, "ledger.send", now).is_err());
+    }
+
+    #[test]
+    fn sign_as_delegator_only() {
+        let delegation = Delegation {
+            from: identity(1),
+            to: identity(2),
+            methods: None,
+            expiration: Timestamp::new(100).unwrap(),
+        };
+        assert!(delegation.sign(&KeyIdIdentity(identity(2))).is_err());
+    }
+
+    #[test]
+    fn attribute() {
+        let chain = DelegationChain::new([link(1, 2, None), link(2, 3, None)]);
+        let set = AttributeSet::from_iter([chain.to_attribute().unwrap()]);
+        let decoded = set.get::<Option<DelegationChain>>().unwrap().unwrap();
+        assert_eq!(
+            decoded.0.iter().map(|l| &l.payload).collect::<Vec<_>>(),
+            chain.0.iter().map(|l| &l.payload).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            AttributeSet::new()
+                .get::<Option<DelegationChain>>()
+                .unwrap(),
+            None
+        );
+    }
+}
@@ -1,9 +1,10 @@
 use crate::{cbor::CborNull, cbor_type_decl, Either, Percent};
+use many_error::ManyError;
 use many_identity::Address;
 use minicbor::data::{Tag, Type};
 use minicbor::{encode, Decode, Decoder, Encode, Encoder};
 use num_bigint::{BigInt, BigUint};
-use num_traits::{Num, ToPrimitive};
+use num_traits::{CheckedAdd, CheckedSub, Num, ToPrimitive};
 use serde::de::Unexpected;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
@@ -70,6 +71,86 @@ impl TokenAmount {
     pub fn to_vec(&self) -> Vec<u8> {
         self.0.to_bytes_be()
     }
+
+    pub fn checked_add(&self, other: &TokenAmount) -> Option<TokenAmount> {
+        self.0.checked_add(&other.0).map(Self)
+    }
+
+    /// Returns `None` if `other` is larger than `self`.
+    pub fn checked_sub(&self, other: &TokenAmount) -> Option<TokenAmount> {
+        self.0.checked_sub(&other.0).map(Self)
+    }
+
+    /// The largest number of decimals of a token. Amounts are formatted and
+    /// parsed with one digit per decimal, so it also bounds their length.
+    pub const MAXIMUM_DECIMALS: u64 = 38;
+
+    fn check_decimals(decimals: u64) -> Result<usize, ManyError> {
+        if decimals > Self::MAXIMUM_DECIMALS {
+            return Err(ManyError::unknown(format!(
+                "Tokens cannot have more than {} decimals, not {decimals}.",
+                Self::MAXIMUM_DECIMALS
+            )));
+        }
+        Ok(decimals as usize)
+    }
+
+    /// Parse a decimal amount (e.g. `"1.5"`) for a token with the given
+    /// number of decimals. Fails if the amount is more precise than what
+    /// the token supports, or if the token has more than
+    /// [`MAXIMUM_DECIMALS`](Self::MAXIMUM_DECIMALS).
+    ///
+    /// ```
+    /// use many_types::ledger::TokenAmount;
+    /// let amount = TokenAmount::from_decimal_str("1.5", 9).unwrap();
+    /// assert_eq!(amount, TokenAmount::from(1_500_000_000u64));
+    /// assert!(TokenAmount::from_decimal_str("0.0001", 3).is_err());
+    /// ```
+    pub fn from_decimal_str(s: &str, decimals: u64) -> Result<Self, ManyError> {
+        let width = Self::check_decimals(decimals)?;
+        let (int, frac) = s.split_once('.').unwrap_or((s, ""));
+        if (int.is_empty() && frac.is_empty())
+            || !int.bytes().all(|c| c.is_ascii_digit())
+            || !frac.bytes().all(|c| c.is_ascii_digit())
+        {
+            return Err(ManyError::unknown(format!("Invalid amount: '{s}'.")));
+        }
+
+        let frac = frac.trim_end_matches('0');
+        if frac.len() as u64 > decimals {
+            return Err(ManyError::unknown(format!(
+                "Amount '{s}' has more than {decimals} decimals."
+            )));
+        }
+
+        let digits = format!("{int}{frac:0<width$}");
+        if digits.is_empty() {
+            return Ok(Self::zero());
+        }
+        BigUint::from_str_radix(&digits, 10)
+            .map(Self)
+            .map_err(ManyError::unknown)
+    }
+
+    /// Format the amount for a token with the given number of decimals,
+    /// without trailing zeroes (e.g. `"1.5"`). Fails if the token has more
+    /// than [`MAXIMUM_DECIMALS`](Self::MAXIMUM_DECIMALS).
+    pub fn to_decimal_string(&self, decimals: u64) -> Result<String, ManyError> {
+        let decimals = Self::check_decimals(decimals)?;
+        let digits = self.0.to_string();
+        if decimals == 0 {
+            return Ok(digits);
+        }
+
+        let digits = format!("{digits:0>width$}", width = decimals + 1);
+        let (int, frac) = digits.split_at(digits.len() - decimals);
+        let frac = frac.trim_end_matches('0');
+        Ok(if frac.is_empty() {
+            int.to_string()
+        } else {
+            format!("{int}.{frac}")
+        })
+    }
 }
 
 impl std::ops::Mul<Percent> for TokenAmount {
@@ -390,6 +471,51 @@ mod test {
     use super::*;
     use serde_test::{assert_de_tokens, assert_ser_tokens, Token};
 
+    #[test]
+    fn token_amount_decimal() {
+        let parse = |s, decimals| TokenAmount::from_decimal_str(s, decimals);
+        assert_eq!(parse("1.5", 9).unwrap(), 1_500_000_000u64);
+        assert_eq!(parse("1", 9).unwrap(), 1_000_000_000u64);
+        assert_eq!(parse(".25", 2).unwrap(), 25u64);
+        assert_eq!(parse("3.", 2).unwrap(), 300u64);
+        assert_eq!(parse("12.3400", 2).unwrap(), 1234u64);
+        assert_eq!(parse("42", 0).unwrap(), 42u64);
+        assert_eq!(parse("0", 0).unwrap(), 0u64);
+        assert!(parse("1.234", 2).is_err());
+        assert!(parse("", 2).is_err());
+        assert!(parse(".", 2).is_err());
+        assert!(parse("1.2.3", 2).is_err());
+        assert!(parse("-1", 2).is_err());
+        assert!(parse("1e5", 2).is_err());
+        assert_eq!(parse("1", 38).unwrap(), TokenAmount::from(10u128.pow(38)));
+        assert!(parse("1", 39).is_err());
+        assert!(parse("1", u64::MAX).is_err());
+
+        let fmt = |amount: u64, decimals| {
+            TokenAmount::from(amount)
+                .to_decimal_string(decimals)
+                .unwrap()
+        };
+        assert_eq!(fmt(1_500_000_000, 9), "1.5");
+        assert_eq!(fmt(1_000_000_000, 9), "1");
+        assert_eq!(fmt(25, 2), "0.25");
+        assert_eq!(fmt(5, 3), "0.005");
+        assert_eq!(fmt(0, 9), "0");
+        assert_eq!(fmt(42, 0), "42");
+        assert_eq!(fmt(1, 38), format!("0.{}1", "0".repeat(37)));
+        assert!(TokenAmount::from(1u64).to_decimal_string(39).is_err());
+        assert!(TokenAmount::from(1u64).to_decimal_string(u64::MAX).is_err());
+    }
+
+    #[test]
+    fn token_amount_checked() {
+        let a = TokenAmount::from(5u64);
+        let b = TokenAmount::from(3u64);
+        assert_eq!(a.checked_add(&b), Some(TokenAmount::from(8u64)));
+        assert_eq!(a.checked_sub(&b), Some(TokenAmount::from(2u64)));
+        assert_eq!(b.checked_sub(&a), None);
+    }
+
     #[test]
     fn serde_token_amount() {
         let token = TokenAmount::from(123u32);
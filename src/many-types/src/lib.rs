@@ -11,19 +11,27 @@ pub mod attributes;
 pub mod blockchain;
 pub mod cbor;
 pub mod compute;
+pub mod delegation;
 pub mod either;
 pub mod identity {
     pub use many_identity::*;
 }
 pub mod ledger;
 pub mod memo;
+pub mod network;
 pub mod proof;
+pub mod simulation;
+pub mod warning;
 pub mod web;
 
 use attributes::AttributeId;
+pub use delegation::{DelegationChain, DELEGATION};
 pub use either::Either;
 pub use memo::Memo;
+pub use network::{NetworkId, NETWORK_ID};
 pub use proof::{ProofOperation, PROOF};
+pub use simulation::SIMULATE;
+pub use warning::{Warning, WARNINGS};
 
 pub mod legacy {
     pub use crate::memo::DataLegacy;
@@ -98,6 +106,37 @@ impl<T> VecOrSingle<T> {
     pub fn iter(&self) -> impl Iterator<Item = &T> {
         self.0.iter()
     }
+
+    pub fn contains(&self, item: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.0.contains(item)
+    }
+
+    /// Iterate over the items of this collection that are also in `other`.
+    pub fn intersection<'a>(&'a self, other: &'a VecOrSingle<T>) -> impl Iterator<Item = &'a T>
+    where
+        T: PartialEq,
+    {
+        self.iter().filter(move |x| other.contains(x))
+    }
+}
+
+impl<'a, T> IntoIterator for &'a VecOrSingle<T> {
+    type Item = &'a T;
+
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl<T> FromIterator<T> for VecOrSingle<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
 }
 
 impl<T> IntoIterator for VecOrSingle<T> {
@@ -194,6 +233,25 @@ impl Timestamp {
     pub fn secs(&self) -> u64 {
         self.0
     }
+
+    /// Add a duration, truncated to seconds. Returns `None` on overflow.
+    pub fn checked_add(&self, duration: std::time::Duration) -> Option<Self> {
+        self.0.checked_add(duration.as_secs()).map(Self)
+    }
+
+    /// Subtract a duration, truncated to seconds. Returns `None` if the result
+    /// would be before the epoch.
+    pub fn checked_sub(&self, duration: std::time::Duration) -> Option<Self> {
+        self.0.checked_sub(duration.as_secs()).map(Self)
+    }
+
+    /// The time elapsed between `earlier` and this timestamp, or `None` if
+    /// `earlier` is later than this timestamp.
+    pub fn duration_since(&self, earlier: Timestamp) -> Option<std::time::Duration> {
+        self.0
+            .checked_sub(earlier.0)
+            .map(std::time::Duration::from_secs)
+    }
 }
 
 impl std::ops::Add<u64> for Timestamp {
@@ -222,6 +280,45 @@ impl<'b, C> Decode<'b, C> for Timestamp {
     }
 }
 
+/// The time of a block, as agreed by consensus. Unlike [`Timestamp::now`], it
+/// is the same on every node, so it is the only time that can be used for
+/// state changes when running behind a blockchain. It can be turned into a
+/// [`Timestamp`], but not the other way around, so wall-clock time cannot be
+/// mistaken for block time.
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Ord, PartialOrd, Eq, PartialEq, Encode, Decode)]
+#[cbor(transparent)]
+#[must_use]
+pub struct BlockTime(#[n(0)] Timestamp);
+
+impl BlockTime {
+    pub const fn from_secs(secs: u64) -> Self {
+        Self(Timestamp(secs))
+    }
+
+    pub fn secs(&self) -> u64 {
+        self.0.secs()
+    }
+
+    pub fn as_timestamp(&self) -> Timestamp {
+        self.0
+    }
+
+    pub fn checked_add(&self, duration: std::time::Duration) -> Option<Self> {
+        self.0.checked_add(duration).map(Self)
+    }
+
+    pub fn duration_since(&self, earlier: BlockTime) -> Option<std::time::Duration> {
+        self.0.duration_since(earlier.0)
+    }
+}
+
+impl From<BlockTime> for Timestamp {
+    fn from(t: BlockTime) -> Self {
+        t.0
+    }
+}
+
 #[derive(Copy, Clone, Eq, PartialEq)]
 #[must_use]
 pub struct CborRange<T> {
@@ -289,6 +386,130 @@ impl<T: PartialOrd<T>> CborRange<T> {
     {
         <Self as RangeBounds<T>>::contains(self, item)
     }
+
+    /// Whether no value can be contained in this range.
+    pub fn is_empty(&self) -> bool {
+        match (&self.start, &self.end) {
+            (Bound::Included(s), Bound::Included(e)) => s > e,
+            (Bound::Included(s), Bound::Excluded(e))
+            | (Bound::Excluded(s), Bound::Included(e))
+            | (Bound::Excluded(s), Bound::Excluded(e)) => s >= e,
+            _ => false,
+        }
+    }
+
+    /// Swap the bounds of a reversed range (e.g. `10..=5` becomes `5..=10`).
+    /// Users sometimes send ranges backward when listing in descending
+    /// order.
+    pub fn normalize(self) -> Self {
+        let reversed = match (&self.start, &self.end) {
+            (Bound::Included(s) | Bound::Excluded(s), Bound::Included(e) | Bound::Excluded(e)) => {
+                s > e
+            }
+            _ => false,
+        };
+        if reversed {
+            Self {
+                start: self.end,
+                end: self.start,
+            }
+        } else {
+            self
+        }
+    }
+
+    /// The range of values contained in both ranges. The result might be
+    /// empty (see [`CborRange::is_empty`]).
+    pub fn intersection(&self, other: &Self) -> Self
+    where
+        T: Clone,
+    {
+        fn pick<T: PartialOrd + Clone>(a: &Bound<T>, b: &Bound<T>, start: bool) -> Bound<T> {
+            match (a, b) {
+                (Bound::Unbounded, x) | (x, Bound::Unbounded) => x.clone(),
+                (
+                    Bound::Included(x) | Bound::Excluded(x),
+                    Bound::Included(y) | Bound::Excluded(y),
+                ) => {
+                    // Keep the tightest bound; on a tie, exclusion is tighter.
+                    let tighter = if start { x > y } else { x < y };
+                    if tighter || (x == y && matches!(a, Bound::Excluded(_))) {
+                        a.clone()
+                    } else {
+                        b.clone()
+                    }
+                }
+            }
+        }
+
+        Self {
+            start: pick(&self.start, &other.start, true),
+            end: pick(&self.end, &other.end, false),
+        }
+    }
+}
+
+/// Iterate over all the values in the range, e.g. block heights.
+impl IntoIterator for CborRange<u64> {
+    type Item = u64;
+
+    type IntoIter = std::ops::RangeInclusive<u64>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let start = match self.start {
+            Bound::Included(x) => Some(x),
+            Bound::Excluded(x) => x.checked_add(1),
+            Bound::Unbounded => Some(0),
+        };
+        let end = match self.end {
+            Bound::Included(x) => Some(x),
+            Bound::Excluded(x) => x.checked_sub(1),
+            Bound::Unbounded => Some(u64::MAX),
+        };
+        match (start, end) {
+            (Some(start), Some(end)) => start..=end,
+            #[allow(clippy::reversed_empty_ranges)]
+            _ => 1..=0,
+        }
+    }
+}
+
+macro_rules! cbor_range_from {
+    ( $( $range: ty => |$r: ident| ($start: expr, $end: expr); )* ) => {
+        $(
+            impl<T> From<$range> for CborRange<T> {
+                fn from($r: $range) -> Self {
+                    Self {
+                        start: $start,
+                        end: $end,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+cbor_range_from!(
+    std::ops::Range<T> => |r| (Bound::Included(r.start), Bound::Excluded(r.end));
+    std::ops::RangeFrom<T> => |r| (Bound::Included(r.start), Bound::Unbounded);
+    std::ops::RangeTo<T> => |r| (Bound::Unbounded, Bound::Excluded(r.end));
+    std::ops::RangeToInclusive<T> => |r| (Bound::Unbounded, Bound::Included(r.end));
+);
+
+impl<T> From<std::ops::RangeInclusive<T>> for CborRange<T> {
+    fn from(r: std::ops::RangeInclusive<T>) -> Self {
+        let (start, end) = r.into_inner();
+        Self {
+            start: Bound::Included(start),
+            end: Bound::Included(end),
+        }
+    }
+}
+
+impl<T> From<std::ops::RangeFull> for CborRange<T> {
+    fn from(_: std::ops::RangeFull) -> Self {
+        Self::default()
+    }
 }
 
 impl<T, C> Encode<C> for CborRange<T>
@@ -650,3 +871,112 @@ fn either_works() {
     );
     assert_eq!(&minicbor::to_vec(EitherTest::Left(true)).unwrap(), &[0xF5]);
 }
+
+#[test]
+fn vec_or_single_helpers() {
+    let v: VecOrSingle<u8> = (1..=4).collect();
+    assert!(v.contains(&3));
+    assert!(!v.contains(&5));
+
+    let other = VecOrSingle(vec![2, 4, 6]);
+    assert_eq!(v.intersection(&other).copied().collect::<Vec<_>>(), [2, 4]);
+    assert_eq!((&v).into_iter().sum::<u8>(), 10);
+}
+
+#[test]
+fn cbor_range_normalize() {
+    let reversed = CborRange {
+        start: Bound::Included(10),
+        end: Bound::Included(5),
+    };
+    assert!(!reversed.contains(&7));
+    let r = reversed.normalize();
+    assert_eq!(r, CborRange::from(5..=10));
+    assert!(r.contains(&7));
+
+    let r = CborRange {
+        start: Bound::Excluded(10),
+        end: Bound::Included(5),
+    };
+    assert_eq!(
+        r.normalize(),
+        CborRange {
+            start: Bound::Included(5),
+            end: Bound::Excluded(10),
+        }
+    );
+    assert_eq!(CborRange::from(3..).normalize(), CborRange::from(3..));
+}
+
+#[test]
+fn cbor_range_intersection() {
+    let a = CborRange::from(0..10);
+    let b = CborRange::from(5..=20);
+    assert_eq!(a.intersection(&b), CborRange::from(5..10));
+    assert_eq!(a.intersection(&CborRange::from(..)), a,);
+    assert_eq!(
+        CborRange::from(..=5).intersection(&CborRange::from(..5)),
+        CborRange::from(..5)
+    );
+    assert!(a.intersection(&CborRange::from(10..)).is_empty());
+    assert!(!a.intersection(&CborRange::from(9..)).is_empty());
+}
+
+#[test]
+fn cbor_range_into_iter() {
+    assert_eq!(
+        CborRange::from(3..6).into_iter().collect::<Vec<u64>>(),
+        [3, 4, 5]
+    );
+    assert_eq!(
+        CborRange::from(..=2).into_iter().collect::<Vec<u64>>(),
+        [0, 1, 2]
+    );
+    assert_eq!(CborRange::from(0..0).into_iter().count(), 0);
+    assert_eq!(CborRange::from(u64::MAX..).into_iter().count(), 1);
+}
+
+#[test]
+fn timestamp_arithmetic() {
+    use std::time::Duration;
+
+    let t = Timestamp::new(100).unwrap();
+    assert_eq!(
+        t.checked_add(Duration::from_secs(20)),
+        Timestamp::new(120).ok()
+    );
+    assert_eq!(
+        t.checked_add(Duration::from_millis(1_999)),
+        Timestamp::new(101).ok()
+    );
+    assert_eq!(
+        t.checked_sub(Duration::from_secs(100)),
+        Timestamp::new(0).ok()
+    );
+    assert_eq!(t.checked_sub(Duration::from_secs(101)), None);
+    assert_eq!(
+        Timestamp::new(u64::MAX)
+            .unwrap()
+            .checked_add(Duration::from_secs(1)),
+        None
+    );
+
+    let earlier = Timestamp::new(40).unwrap();
+    assert_eq!(t.duration_since(earlier), Some(Duration::from_secs(60)));
+    assert_eq!(earlier.duration_since(t), None);
+}
+
+#[test]
+fn block_time() {
+    let t = BlockTime::from_secs(1_000);
+    assert_eq!(Timestamp::from(t), Timestamp::new(1_000).unwrap());
+    assert_eq!(
+        BlockTime::from_secs(1_030).duration_since(t),
+        Some(std::time::Duration::from_secs(30))
+    );
+
+    // Block times are encoded as timestamps.
+    let bytes = minicbor::to_vec(t).unwrap();
+    assert_eq!(bytes, minicbor::to_vec(t.as_timestamp()).unwrap());
+    assert_eq!(minicbor::decode::<BlockTime>(&bytes).unwrap(), t);
+}
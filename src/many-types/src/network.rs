@@ -0,0 +1,94 @@
+use crate::attributes::{Attribute, AttributeSet, TryFromAttributeSet};
+use crate::cbor::CborAny;
+use many_error::ManyError;
+use std::fmt::{Display, Formatter};
+
+/// Request attribute binding a request to a network, with the ID of the
+/// network as its only argument. Servers advertise their network ID in their
+/// status, and refuse requests for another network, so a request signed for
+/// a test network cannot be replayed on the main network.
+pub const NETWORK_ID: Attribute = Attribute::id(6);
+
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct NetworkId(pub String);
+
+impl NetworkId {
+    pub fn new(id: impl ToString) -> Self {
+        Self(id.to_string())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Check the network of a request against this one. Requests without a
+    /// network ID are only refused if `required`.
+    pub fn validate(&self, attributes: &AttributeSet, required: bool) -> Result<(), ManyError> {
+        match attributes.get::<Option<NetworkId>>()? {
+            Some(id) if &id != self => Err(ManyError::network_id_mismatch(id, self)),
+            None if required => Err(ManyError::network_id_missing(self)),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Display for NetworkId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<&str> for NetworkId {
+    fn from(id: &str) -> Self {
+        Self(id.to_string())
+    }
+}
+
+impl From<NetworkId> for Attribute {
+    fn from(id: NetworkId) -> Self {
+        Attribute::new(NETWORK_ID.id, vec![CborAny::String(id.0)])
+    }
+}
+
+/// Returns the network ID of a set, or `None` if the set has none.
+impl TryFromAttributeSet for Option<NetworkId> {
+    fn try_from_set(set: &AttributeSet) -> Result<Self, ManyError> {
+        match set.get_attribute(NETWORK_ID.id) {
+            None => Ok(None),
+            Some(attr) => match attr.arguments().as_slice() {
+                [CborAny::String(id)] => Ok(Some(NetworkId(id.clone()))),
+                _ => Err(ManyError::invalid_attribute_arguments()),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate() {
+        let mainnet = NetworkId::from("mainnet");
+        let with = |id: &str| AttributeSet::from_iter([NetworkId::from(id).into()]);
+
+        assert!(mainnet.validate(&with("mainnet"), true).is_ok());
+        assert_eq!(
+            mainnet
+                .validate(&with("testnet"), false)
+                .unwrap_err()
+                .code(),
+            ManyError::network_id_mismatch("", "").code()
+        );
+
+        let none = AttributeSet::new();
+        assert!(mainnet.validate(&none, false).is_ok());
+        assert_eq!(
+            mainnet.validate(&none, true).unwrap_err().code(),
+            ManyError::network_id_missing("").code()
+        );
+
+        let invalid = AttributeSet::from_iter([Attribute::new(NETWORK_ID.id, vec![])]);
+        assert!(mainnet.validate(&invalid, false).is_err());
+    }
+}
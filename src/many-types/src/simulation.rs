@@ -0,0 +1,13 @@
+use crate::attributes::Attribute;
+use crate::cbor::CborAny;
+
+/// Request attribute asking for a command to be executed against a throwaway
+/// copy of the state, without committing anything. The response carries the
+/// same attribute, with the events the command would have emitted as
+/// arguments.
+pub const SIMULATE: Attribute = Attribute::id(5);
+
+/// Build the [SIMULATE] attribute of a response from the encoded events.
+pub fn simulation_attribute(events: Vec<CborAny>) -> Attribute {
+    Attribute::new(SIMULATE.id, events)
+}
@@ -0,0 +1,134 @@
+use {
+    crate::{
+        attributes::{Attribute, AttributeSet, TryFromAttributeSet},
+        cbor::CborAny,
+    },
+    many_error::ManyError,
+    minicbor::{Decode, Encode},
+    std::fmt::{Display, Formatter},
+};
+
+/// Response attribute carrying the warnings of a successful call. Each
+/// warning is a separate argument of the attribute.
+pub const WARNINGS: Attribute = Attribute::id(4);
+
+/// A non-fatal condition reported alongside a successful result, e.g. when
+/// only part of a request could be performed or the endpoint is deprecated.
+#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
+#[cbor(map)]
+pub struct Warning {
+    #[n(0)]
+    pub code: i64,
+
+    #[n(1)]
+    pub message: String,
+}
+
+impl Warning {
+    /// A warning without a more specific code.
+    pub const UNKNOWN: i64 = 0;
+
+    /// The endpoint called is deprecated and might be removed in the future.
+    pub const DEPRECATED: i64 = 1;
+
+    /// The request was only partially performed.
+    pub const PARTIAL: i64 = 2;
+
+    pub fn new(code: i64, message: impl ToString) -> Self {
+        Self {
+            code,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn deprecated(message: impl ToString) -> Self {
+        Self::new(Self::DEPRECATED, message)
+    }
+
+    pub fn partial(message: impl ToString) -> Self {
+        Self::new(Self::PARTIAL, message)
+    }
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (warning {})", self.message, self.code)
+    }
+}
+
+impl TryFrom<&Warning> for CborAny {
+    type Error = ManyError;
+
+    fn try_from(warning: &Warning) -> Result<Self, Self::Error> {
+        minicbor::to_vec(warning)
+            .map_err(ManyError::unknown)
+            .and_then(|bytes| {
+                minicbor::decode::<CborAny>(bytes.as_slice()).map_err(ManyError::unknown)
+            })
+    }
+}
+
+impl TryFrom<&CborAny> for Warning {
+    type Error = ManyError;
+
+    fn try_from(value: &CborAny) -> Result<Self, Self::Error> {
+        let bytes = minicbor::to_vec(value).map_err(ManyError::unknown)?;
+        minicbor::decode(&bytes).map_err(|_| ManyError::invalid_attribute_arguments())
+    }
+}
+
+/// Build the [WARNINGS] attribute from a list of warnings.
+pub fn warnings_attribute<'a>(
+    warnings: impl IntoIterator<Item = &'a Warning>,
+) -> Result<Attribute, ManyError> {
+    Ok(Attribute::new(
+        WARNINGS.id,
+        warnings
+            .into_iter()
+            .map(CborAny::try_from)
+            .collect::<Result<_, _>>()?,
+    ))
+}
+
+/// Returns the warnings of a set, or an empty list if the set has none.
+impl TryFromAttributeSet for Vec<Warning> {
+    fn try_from_set(set: &AttributeSet) -> Result<Self, ManyError> {
+        set.get_attribute(WARNINGS.id).map_or_else(
+            || Ok(vec![]),
+            |attr| attr.arguments().iter().map(Warning::try_from).collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attribute() {
+        let warnings = vec![
+            Warning::partial("Only 3 tokens were burnt."),
+            Warning::deprecated("Use ledger.send instead."),
+        ];
+        let set = AttributeSet::from_iter([warnings_attribute(&warnings).unwrap()]);
+        assert_eq!(set.get::<Vec<Warning>>().unwrap(), warnings);
+
+        let bytes = minicbor::to_vec(&set).unwrap();
+        let set: AttributeSet = minicbor::decode(&bytes).unwrap();
+        assert_eq!(set.get::<Vec<Warning>>().unwrap(), warnings);
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(
+            AttributeSet::new().get::<Vec<Warning>>().unwrap(),
+            Vec::<Warning>::new()
+        );
+    }
+
+    #[test]
+    fn invalid_argument() {
+        let set = AttributeSet::from_iter([WARNINGS.with_argument(CborAny::Int(1))]);
+        assert!(set.get::<Vec<Warning>>().is_err());
+    }
+}
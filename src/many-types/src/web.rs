@@ -1,3 +1,4 @@
+use crate::Timestamp;
 use many_error::ManyError;
 use many_identity::Address;
 use minicbor::bytes::ByteVec;
@@ -46,6 +47,26 @@ pub struct WebDeploymentInfo {
     pub domain: Option<String>,
 }
 
+/// A deployment of a website, kept so the website can be rolled back to it.
+#[derive(Clone, Debug, Eq, PartialEq, Encode, Decode)]
+#[cbor(map)]
+pub struct WebDeploymentVersion {
+    #[n(0)]
+    pub version: u64,
+
+    #[n(1)]
+    pub source_hash: String,
+
+    #[n(2)]
+    pub timestamp: Timestamp,
+
+    #[n(3)]
+    pub site_description: Option<String>,
+
+    #[n(4)]
+    pub domain: Option<String>,
+}
+
 #[derive(Clone, Debug, Encode, Decode, Display, Eq, PartialEq)]
 #[cbor(map)]
 pub enum WebDeploymentSource {
@@ -23,6 +23,9 @@ define_attribute_many_error!(
         18: pub fn invalid_domain(domain) => "Invalid domain: {domain}.",
         19: pub fn page_size_too_large(size) => "Page size too large: {size}.",
         20: pub fn domain_already_in_use(domain) => "Domain already in use: {domain}.",
+        21: pub fn nonexistent_version(version) => "Nonexistent version: {version}.",
+        22: pub fn unable_to_compress_asset(err) => "Unable to compress asset: {err}.",
+        23: pub fn unable_to_decompress_asset(err) => "Unable to decompress asset: {err}.",
     }
 );
 
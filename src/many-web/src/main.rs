@@ -1,9 +1,8 @@
 use clap::Parser;
+use many_cli_helpers::verifiers::VerifierFlags;
 use many_cli_helpers::CommonCliFlags;
-use many_identity::verifiers::AnonymousVerifier;
 use many_identity::{Address, Identity};
-use many_identity_dsa::{CoseKeyIdentity, CoseKeyVerifier};
-use many_identity_webauthn::WebAuthnVerifier;
+use many_identity_dsa::CoseKeyIdentity;
 use many_modules::{abci_backend, events, kvstore, web};
 use many_protocol::ManyUrl;
 use many_server::transport::http::HttpServer;
@@ -57,6 +56,9 @@ struct Opts {
     #[clap(long)]
     allow_origin: Option<Vec<ManyUrl>>,
 
+    #[clap(flatten)]
+    verifier_flags: VerifierFlags,
+
     /// Path to a JSON file containing an array of MANY addresses
     /// Only addresses from this array will be able to execute commands, e.g., send, put, ...
     /// Any addresses will be able to execute queries, e.g., balance, get, ...
@@ -83,6 +85,7 @@ fn main() {
         persistent,
         clean,
         allow_origin,
+        verifier_flags,
         allow_addrs,
         cache_db,
         domain,
@@ -92,6 +95,9 @@ fn main() {
     many_web::DOMAIN.set(domain).unwrap();
 
     common_flags.init_logging().unwrap();
+    let verifier_config = verifier_flags
+        .config()
+        .expect("Could not load the verifier config");
 
     debug!("{:?}", Opts::parse());
     info!(
@@ -151,11 +157,7 @@ fn main() {
     let many = ManyServer::simple(
         "many-web",
         key,
-        (
-            AnonymousVerifier,
-            CoseKeyVerifier,
-            WebAuthnVerifier::new(allow_origin),
-        ),
+        verifier_config.build(allow_origin),
         Some(env!("CARGO_PKG_VERSION").to_string()),
     );
 
@@ -1,6 +1,5 @@
 use crate::error;
-use crate::storage::{url_for_website, WebStorage, HTTP_ROOT};
-use base64::{engine::general_purpose, Engine as _};
+use crate::storage::{decode_asset, url_for_website, WebStorage, HTTP_ROOT};
 use many_error::ManyError;
 use many_identity::Address;
 use many_modules::abci_backend::{
@@ -10,10 +9,12 @@ use many_modules::abci_backend::{
 use many_modules::kvstore::{GetArgs, GetReturns, KvStoreModuleBackend, QueryArgs, QueryReturns};
 use many_modules::web::{
     DeployArgs, DeployReturns, InfoArg, InfoReturns, ListArgs, ListReturns, RemoveArgs,
-    RemoveReturns, UpdateArgs, UpdateReturns, WebCommandsModuleBackend, WebModuleBackend,
+    RemoveReturns, RollbackArgs, RollbackReturns, UpdateArgs, UpdateReturns, VersionsArgs,
+    VersionsReturns, WebCommandsModuleBackend, WebModuleBackend,
 };
+use many_protocol::context::Context;
 use many_types::web::{WebDeploymentInfo, WebDeploymentSource};
-use many_types::Timestamp;
+use many_types::BlockTime;
 use sha2::Digest;
 use std::collections::BTreeMap;
 use std::io::Cursor;
@@ -32,6 +33,16 @@ pub mod events;
 pub struct InitialStateJson {
     identity: Address,
     hash: Option<String>,
+    /// Store the files of websites compressed. This cannot be changed once
+    /// the chain is created.
+    compress_assets: Option<bool>,
+}
+
+impl InitialStateJson {
+    pub fn with_compressed_assets(mut self, compress_assets: bool) -> Self {
+        self.compress_assets = Some(compress_assets);
+        self
+    }
 }
 
 #[derive(Debug)]
@@ -55,8 +66,13 @@ impl WebModuleImpl {
         persistence_store_path: P,
         blockchain: bool,
     ) -> Result<Self, ManyError> {
-        let storage = WebStorage::new(initial_state.identity, persistence_store_path, blockchain)
-            .map_err(ManyError::unknown)?;
+        let storage = WebStorage::new(
+            initial_state.identity,
+            persistence_store_path,
+            blockchain,
+            initial_state.compress_assets.unwrap_or(false),
+        )
+        .map_err(ManyError::unknown)?;
 
         if let Some(h) = initial_state.hash {
             // Verify the hash.
@@ -87,6 +103,8 @@ impl ManyAbciModuleBackend for WebModuleImpl {
                 ("web.remove".to_string(), EndpointInfo { is_command: true }),
                 ("web.update".to_string(), EndpointInfo { is_command: true }),
                 ("web.list".to_string(), EndpointInfo { is_command: false }),
+                ("web.versions".to_string(), EndpointInfo { is_command: false }),
+                ("web.rollback".to_string(), EndpointInfo { is_command: true }),
                 // KvStore
                 ("kvstore.get".to_string(), EndpointInfo { is_command: false }),
                 ("kvstore.info".to_string(), EndpointInfo { is_command: false }),
@@ -111,8 +129,7 @@ impl ManyAbciModuleBackend for WebModuleImpl {
         );
 
         if let Some(time) = time {
-            let time = Timestamp::new(time)?;
-            self.storage.set_time(time);
+            self.storage.set_time(BlockTime::from_secs(time));
         }
 
         Ok(BeginBlockReturn {})
@@ -129,6 +146,7 @@ impl ManyAbciModuleBackend for WebModuleImpl {
         Ok(AbciInfo {
             height: storage.get_height()?,
             hash: storage.hash().into(),
+            events_digest: None,
         })
     }
 
@@ -238,6 +256,21 @@ impl WebModuleBackend for WebModuleImpl {
             deployments,
         })
     }
+
+    fn versions(&self, sender: &Address, args: VersionsArgs) -> Result<VersionsReturns, ManyError> {
+        let VersionsArgs { owner, site_name } = args;
+        let owner = owner.as_ref().unwrap_or(sender);
+        let site_name = _transform_site_name(site_name);
+
+        if !self.storage.site_exists(owner, &site_name)? {
+            return Err(error::nonexistent_site(site_name));
+        }
+
+        Ok(VersionsReturns {
+            versions: self.storage.list_versions(owner, &site_name)?,
+            current: self.storage.get_current_version(owner, &site_name)?,
+        })
+    }
 }
 
 impl WebCommandsModuleBackend for WebModuleImpl {
@@ -419,6 +452,58 @@ impl WebCommandsModuleBackend for WebModuleImpl {
             },
         })
     }
+
+    fn rollback(
+        &mut self,
+        sender: &Address,
+        args: RollbackArgs,
+    ) -> Result<RollbackReturns, ManyError> {
+        let RollbackArgs {
+            owner,
+            site_name,
+            version,
+            memo,
+        } = args;
+
+        // Check that the sender is the owner, for now.
+        // TODO: Support accounts
+        if let Some(owner) = owner {
+            if sender != &owner {
+                return Err(error::invalid_owner(owner));
+            }
+        }
+
+        let site_name = _transform_site_name(site_name);
+
+        if !self.storage.site_exists(sender, &site_name)? {
+            return Err(error::nonexistent_site(site_name));
+        }
+
+        let version = self
+            .storage
+            .get_version(sender, &site_name, version)?
+            .ok_or_else(|| error::nonexistent_version(version))?;
+
+        if let Some(domain) = &version.domain {
+            let meta = self.storage.get_deployment_meta(sender, &site_name)?;
+            if meta.domain.as_ref() != Some(domain) && self.storage.has_domain(domain) {
+                return Err(error::domain_already_in_use(domain));
+            }
+        }
+
+        self.storage
+            .rollback_website(sender, site_name.clone(), &version, memo)?;
+
+        Ok(RollbackReturns {
+            info: WebDeploymentInfo {
+                owner: *sender,
+                url: Some(url_for_website(sender, &site_name)),
+                site_name,
+                site_description: version.site_description,
+                domain: version.domain,
+            },
+        })
+    }
 }
 
 impl KvStoreModuleBackend for WebModuleImpl {
@@ -432,7 +517,12 @@ impl KvStoreModuleBackend for WebModuleImpl {
         })
     }
 
-    fn get(&self, _sender: &Address, args: GetArgs) -> Result<GetReturns, ManyError> {
+    fn get(
+        &self,
+        _sender: &Address,
+        args: GetArgs,
+        _context: Context,
+    ) -> Result<GetReturns, ManyError> {
         let GetArgs { key } = args;
 
         if !key.starts_with(HTTP_ROOT.as_ref()) {
@@ -440,21 +530,21 @@ impl KvStoreModuleBackend for WebModuleImpl {
         }
 
         let value = self.storage.get(key.as_slice())?;
-        match value {
-            Some(value) => Ok(GetReturns {
-                value: Some(
-                    general_purpose::STANDARD
-                        .decode(value)
-                        .map_err(ManyError::deserialization_error)?
-                        .into(),
-                ),
-            }),
-            None => Ok(GetReturns { value: None }),
-        }
+        Ok(GetReturns {
+            value: value
+                .map(|value| decode_asset(&value))
+                .transpose()?
+                .map(Into::into),
+        })
     }
 
     // We do not expose this endpoint
-    fn query(&self, _sender: &Address, _args: QueryArgs) -> Result<QueryReturns, ManyError> {
+    fn query(
+        &self,
+        _sender: &Address,
+        _args: QueryArgs,
+        _context: Context,
+    ) -> Result<QueryReturns, ManyError> {
         Err(ManyError::unknown("Unimplemented"))
     }
 
@@ -466,10 +556,29 @@ impl KvStoreModuleBackend for WebModuleImpl {
     ) -> Result<many_modules::kvstore::list::ListReturns, ManyError> {
         Err(ManyError::unknown("Unimplemented"))
     }
+
+    // We do not expose this endpoint
+    fn prove(
+        &self,
+        _sender: &Address,
+        _args: many_modules::kvstore::ProveArgs,
+    ) -> Result<many_modules::kvstore::ProveReturns, ManyError> {
+        Err(ManyError::unknown("Unimplemented"))
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    #[test]
+    fn decode_asset() {
+        let content = b"<h1>Hello</h1>".repeat(10);
+        let compressed = zstd::encode_all(content.as_slice(), 19).unwrap();
+        assert_eq!(super::decode_asset(&compressed).unwrap(), content);
+
+        let encoded = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &content);
+        assert_eq!(super::decode_asset(encoded.as_bytes()).unwrap(), content);
+    }
+
     #[test]
     fn valid_domain() {
         let domain = "foobar.com";
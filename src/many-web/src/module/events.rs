@@ -16,10 +16,12 @@ impl events::EventsModuleBackend for WebModuleImpl {
     }
 
     fn list(&self, args: events::ListArgs) -> Result<events::ListReturns, ManyError> {
+        let id_range = args.id_range();
         let events::ListArgs {
             count,
             order,
             filter,
+            ..
         } = args;
         let filter = filter.unwrap_or_default();
 
@@ -29,10 +31,7 @@ impl events::EventsModuleBackend for WebModuleImpl {
 
         let storage = &self.storage;
         let nb_events = storage.nb_events()?;
-        let iter = storage.iter_events(
-            filter.id_range.unwrap_or_default(),
-            order.unwrap_or_default(),
-        );
+        let iter = storage.iter_events(id_range, order.unwrap_or_default());
 
         let iter = Box::new(iter.map(|item| {
             let (_k, v) = item.map_err(ManyError::unknown)?;
@@ -44,9 +43,7 @@ impl events::EventsModuleBackend for WebModuleImpl {
         let iter = filter_event_kind(iter, filter.kind);
         let iter = filter_date(iter, filter.date_range.unwrap_or_default());
 
-        let events: Vec<events::EventLog> = iter.take(count).collect::<Result<_, _>>()?;
-
-        Ok(events::ListReturns { nb_events, events })
+        events::ListReturns::page(nb_events, iter, count)
     }
 }
 
@@ -57,7 +54,6 @@ fn filter_account<'a>(
     account: Option<VecOrSingle<Address>>,
 ) -> Box<dyn Iterator<Item = EventLogResult> + 'a> {
     if let Some(account) = account {
-        let account: Vec<Address> = account.into();
         Box::new(it.filter(move |t| match t {
             // Propagate the errors.
             Err(_) => true,
@@ -73,7 +69,6 @@ fn filter_event_kind<'a>(
     event_kind: Option<VecOrSingle<events::EventKind>>,
 ) -> Box<dyn Iterator<Item = EventLogResult> + 'a> {
     if let Some(k) = event_kind {
-        let k: Vec<events::EventKind> = k.into();
         Box::new(it.filter(move |t| match t {
             Err(_) => true,
             Ok(t) => k.contains(&t.kind()),
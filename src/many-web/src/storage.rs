@@ -1,12 +1,12 @@
 use crate::error;
 use crate::storage::iterator::WebIterator;
-use base64::engine::general_purpose;
+use base64::{engine::general_purpose, Engine as _};
 use many_error::ManyError;
 use many_identity::Address;
 use many_modules::abci_backend::AbciCommitInfo;
 use many_modules::events::{EventId, EventInfo};
-use many_types::web::{WebDeploymentFilter, WebDeploymentInfo};
-use many_types::{Memo, SortOrder, Timestamp};
+use many_types::web::{WebDeploymentFilter, WebDeploymentInfo, WebDeploymentVersion};
+use many_types::{BlockTime, Memo, SortOrder, Timestamp};
 use merk::{BatchEntry, Op};
 use std::fs;
 use std::io::Write;
@@ -19,6 +19,29 @@ pub mod iterator;
 
 pub const HTTP_ROOT: &str = "/http"; // Where website files are stored.
 const META_ROOT: &str = "/meta"; // Where website metadata are stored.
+const VERSION_HTTP_ROOT: &str = "/version_http"; // Where the files of every version are stored.
+const VERSION_META_ROOT: &str = "/version_meta"; // Where the metadata of every version are stored.
+const CURRENT_VERSION_ROOT: &str = "/version"; // Where the version currently served is stored.
+const LATEST_VERSION_ROOT: &str = "/version_latest"; // Where the last version deployed is stored.
+
+// Files are compressed with zstd when the chain was created with compressed
+// assets. The output of zstd depends on its version and level, which must then
+// be the same on every node.
+const ASSET_COMPRESSION_LEVEL: i32 = 19;
+
+// Files stored uncompressed are base64 encoded, which never starts with this.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Decode a file as stored by `store_website`.
+pub fn decode_asset(value: &[u8]) -> Result<Vec<u8>, ManyError> {
+    if value.starts_with(&ZSTD_MAGIC) {
+        zstd::decode_all(value).map_err(error::unable_to_decompress_asset)
+    } else {
+        general_purpose::STANDARD
+            .decode(value)
+            .map_err(ManyError::deserialization_error)
+    }
+}
 
 fn key_for_website(owner: &Address, site_name: &str) -> Vec<u8> {
     format!("{HTTP_ROOT}/{owner}/{site_name}/").into_bytes()
@@ -32,6 +55,34 @@ fn key_for_website_meta(owner: &Address, site_name: &str) -> String {
     format!("{META_ROOT}/{owner}/{site_name}")
 }
 
+fn key_for_website_versions(owner: &Address, site_name: &str) -> Vec<u8> {
+    format!("{VERSION_META_ROOT}/{owner}/{site_name}/").into_bytes()
+}
+
+fn key_for_website_version_meta(owner: &Address, site_name: &str, version: u64) -> String {
+    format!("{VERSION_META_ROOT}/{owner}/{site_name}/{version:020}")
+}
+
+fn key_for_website_version_files(
+    owner: &Address,
+    site_name: &str,
+    version: Option<u64>,
+) -> Vec<u8> {
+    match version {
+        Some(version) => format!("{VERSION_HTTP_ROOT}/{owner}/{site_name}/{version:020}/"),
+        None => format!("{VERSION_HTTP_ROOT}/{owner}/{site_name}/"),
+    }
+    .into_bytes()
+}
+
+fn key_for_website_current_version(owner: &Address, site_name: &str) -> String {
+    format!("{CURRENT_VERSION_ROOT}/{owner}/{site_name}")
+}
+
+fn key_for_website_latest_version(owner: &Address, site_name: &str) -> String {
+    format!("{LATEST_VERSION_ROOT}/{owner}/{site_name}")
+}
+
 pub fn url_for_website(owner: &Address, site_name: &str) -> String {
     let domain = crate::DOMAIN.get_or_init(|| "localhost:8880".to_string());
     format!("https://{site_name}-{owner}.{domain}")
@@ -46,12 +97,13 @@ pub struct WebStorage {
     blockchain: bool,
 
     latest_event_id: EventId,
-    current_time: Option<Timestamp>,
+    current_time: Option<BlockTime>,
     current_hash: Option<Vec<u8>>,
     #[allow(dead_code)]
     next_subresource: u32,
     #[allow(dead_code)]
     root_identity: Address,
+    compress_assets: bool,
 }
 
 impl std::fmt::Debug for WebStorage {
@@ -62,12 +114,12 @@ impl std::fmt::Debug for WebStorage {
 
 impl WebStorage {
     #[inline]
-    pub fn set_time(&mut self, time: Timestamp) {
+    pub fn set_time(&mut self, time: BlockTime) {
         self.current_time = Some(time);
     }
     #[inline]
     pub fn now(&self) -> Timestamp {
-        self.current_time.unwrap_or_else(Timestamp::now)
+        self.current_time.map_or_else(Timestamp::now, Into::into)
     }
 
     #[inline]
@@ -107,6 +159,11 @@ impl WebStorage {
         )
         .map_err(ManyError::deserialization_error)?;
 
+        let compress_assets = persistent_store
+            .get(b"/config/compress_assets")
+            .map_err(error::storage_get_failed)?
+            .is_some();
+
         let latest_event_id = minicbor::decode(
             &persistent_store
                 .get(b"/latest_event_id")
@@ -123,6 +180,7 @@ impl WebStorage {
             latest_event_id,
             next_subresource,
             root_identity,
+            compress_assets,
         })
     }
 
@@ -130,12 +188,16 @@ impl WebStorage {
         identity: Address,
         persistent_path: P,
         blockchain: bool,
+        compress_assets: bool,
     ) -> Result<Self, ManyError> {
         let mut persistent_store =
             merk::Merk::open(persistent_path).map_err(error::unable_to_open_storage)?;
 
-        let batch: Vec<BatchEntry> =
-            vec![(b"/config/identity".to_vec(), Op::Put(identity.to_vec()))];
+        let mut batch: Vec<BatchEntry> = Vec::new();
+        if compress_assets {
+            batch.push((b"/config/compress_assets".to_vec(), Op::Put(vec![1])));
+        }
+        batch.push((b"/config/identity".to_vec(), Op::Put(identity.to_vec())));
 
         persistent_store
             .apply(batch.as_slice())
@@ -163,6 +225,7 @@ impl WebStorage {
             latest_event_id,
             next_subresource: 0,
             root_identity: identity,
+            compress_assets,
         })
     }
 
@@ -239,6 +302,7 @@ impl WebStorage {
         Ok(AbciCommitInfo {
             retain_height,
             hash: hash.into(),
+            events_digest: None,
         })
     }
 
@@ -268,7 +332,7 @@ impl WebStorage {
         let mut batch: Vec<BatchEntry> = Vec::new();
 
         // Walk the directory tree, ignoring hidden files and directories.
-        // Add each file content to the batch as base64
+        // Add each file content to the batch, compressed or as base64
         trace!("Walking directory tree");
         for entry in WalkDir::new(&path)
             .into_iter()
@@ -288,13 +352,19 @@ impl WebStorage {
                 .ok_or_else(error::unable_to_convert_to_str)?;
             trace!("Found file {}", file_path);
 
-            trace!("Encoding file");
-            let mut enc = base64::write::EncoderWriter::new(Vec::new(), &general_purpose::STANDARD);
-            enc.write_all(&fs::read(entry_path).map_err(error::io_error)?)
-                .map_err(error::io_error)?;
-
+            let content = fs::read(entry_path).map_err(error::io_error)?;
+            let data = if self.compress_assets {
+                trace!("Compressing file");
+                zstd::encode_all(content.as_slice(), ASSET_COMPRESSION_LEVEL)
+                    .map_err(error::unable_to_compress_asset)?
+            } else {
+                trace!("Encoding file");
+                let mut enc =
+                    base64::write::EncoderWriter::new(Vec::new(), &general_purpose::STANDARD);
+                enc.write_all(&content).map_err(error::io_error)?;
+                enc.finish().map_err(ManyError::unknown)?
+            };
             trace!("Finished encoding file");
-            let data = enc.finish().map_err(ManyError::unknown)?;
 
             trace!(
                 "Storing file to {}",
@@ -340,7 +410,16 @@ impl WebStorage {
         path: impl AsRef<Path>,
         domain: Option<String>,
     ) -> Result<(), ManyError> {
-        let batch = self._store_website(owner, &site_name, &site_description, path, &domain)?;
+        let mut batch = self._store_website(owner, &site_name, &site_description, path, &domain)?;
+        batch.extend(self._store_version(
+            owner,
+            &site_name,
+            &site_description,
+            source_hash.clone(),
+            &domain,
+            &batch,
+        )?);
+        batch.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
 
         trace!("Applying batch");
         self.persistent_store
@@ -386,13 +465,140 @@ impl WebStorage {
         Ok(batch)
     }
 
+    /// The current version of a website. Websites deployed before versions
+    /// were tracked are at version 0, which cannot be rolled back to.
+    pub fn get_current_version(&self, owner: &Address, site_name: &str) -> Result<u64, ManyError> {
+        self.get_version_number(key_for_website_current_version(owner, site_name))
+    }
+
+    fn get_version_number(&self, key: String) -> Result<u64, ManyError> {
+        self.get(key.as_bytes())?.map_or(Ok(0), |x| {
+            let bytes: [u8; 8] = x
+                .as_slice()
+                .try_into()
+                .map_err(ManyError::deserialization_error)?;
+            Ok(u64::from_be_bytes(bytes))
+        })
+    }
+
+    pub fn get_version(
+        &self,
+        owner: &Address,
+        site_name: &str,
+        version: u64,
+    ) -> Result<Option<WebDeploymentVersion>, ManyError> {
+        self.get(key_for_website_version_meta(owner, site_name, version).as_bytes())?
+            .map(|meta| minicbor::decode(&meta).map_err(ManyError::deserialization_error))
+            .transpose()
+    }
+
+    /// All the versions of a website, from the oldest to the newest.
+    pub fn list_versions(
+        &self,
+        owner: &Address,
+        site_name: &str,
+    ) -> Result<Vec<WebDeploymentVersion>, ManyError> {
+        WebIterator::website_versions(&self.persistent_store, owner, site_name)
+            .map(|item| {
+                let (_, v) = item.map_err(error::storage_get_failed)?;
+                minicbor::decode(&v).map_err(ManyError::deserialization_error)
+            })
+            .collect()
+    }
+
+    /// Copy the files of a deployment to a new version of the website, and make
+    /// it the current version.
+    fn _store_version(
+        &self,
+        owner: &Address,
+        site_name: &str,
+        site_description: &Option<String>,
+        source_hash: String,
+        domain: &Option<String>,
+        files: &[BatchEntry],
+    ) -> Result<Vec<BatchEntry>, ManyError> {
+        let version =
+            self.get_version_number(key_for_website_latest_version(owner, site_name))? + 1;
+        trace!("Storing version {version} of website {site_name}");
+
+        let prefix = key_for_website(owner, site_name);
+        let version_prefix = key_for_website_version_files(owner, site_name, Some(version));
+        let mut batch: Vec<BatchEntry> = files
+            .iter()
+            .filter_map(|(key, op)| {
+                let file_name = key.strip_prefix(prefix.as_slice())?;
+                let data = match op {
+                    Op::Put(data) => data.clone(),
+                    _ => return None,
+                };
+                Some((
+                    [version_prefix.as_slice(), file_name].concat(),
+                    Op::Put(data),
+                ))
+            })
+            .collect();
+
+        batch.push((
+            key_for_website_version_meta(owner, site_name, version).into_bytes(),
+            Op::Put(
+                minicbor::to_vec(WebDeploymentVersion {
+                    version,
+                    source_hash,
+                    timestamp: self.now(),
+                    site_description: site_description.clone(),
+                    domain: domain.clone(),
+                })
+                .map_err(ManyError::serialization_error)?,
+            ),
+        ));
+        batch.push((
+            key_for_website_current_version(owner, site_name).into_bytes(),
+            Op::Put(version.to_be_bytes().to_vec()),
+        ));
+        batch.push((
+            key_for_website_latest_version(owner, site_name).into_bytes(),
+            Op::Put(version.to_be_bytes().to_vec()),
+        ));
+
+        Ok(batch)
+    }
+
+    fn _remove_versions(
+        &self,
+        owner: &Address,
+        site_name: &str,
+    ) -> Result<Vec<BatchEntry>, ManyError> {
+        trace!("Removing versions of website {}", site_name);
+        let files =
+            WebIterator::website_version_files(&self.persistent_store, owner, site_name, None);
+        let metas = WebIterator::website_versions(&self.persistent_store, owner, site_name);
+
+        let mut batch: Vec<BatchEntry> = Vec::new();
+        for item in files.chain(metas) {
+            let (key, _) = item.map_err(error::storage_get_failed)?;
+            batch.push((key.to_vec(), Op::Delete));
+        }
+        for key in [
+            key_for_website_current_version(owner, site_name),
+            key_for_website_latest_version(owner, site_name),
+        ] {
+            if self.get(key.as_bytes())?.is_some() {
+                batch.push((key.into_bytes(), Op::Delete));
+            }
+        }
+
+        Ok(batch)
+    }
+
     pub fn remove_website(
         &mut self,
         owner: &Address,
         site_name: String,
         memo: Option<Memo>,
     ) -> Result<(), ManyError> {
-        let batch = self._remove_website(owner, &site_name)?;
+        let mut batch = self._remove_website(owner, &site_name)?;
+        batch.extend(self._remove_versions(owner, &site_name)?);
+        batch.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
 
         self.persistent_store
             .apply(&batch)
@@ -424,7 +630,16 @@ impl WebStorage {
         let batch_r = self._remove_website(owner, &site_name)?;
 
         trace!("Storing updated website");
-        let batch_s = self._store_website(owner, &site_name, &site_description, path, &domain)?;
+        let mut batch_s =
+            self._store_website(owner, &site_name, &site_description, path, &domain)?;
+        batch_s.extend(self._store_version(
+            owner,
+            &site_name,
+            &site_description,
+            source_hash.clone(),
+            &domain,
+            &batch_s,
+        )?);
 
         // `merk` doesn't support applying `b1` and `b2` where
         // - `b1` contains a `Delete` operation and
@@ -472,6 +687,76 @@ impl WebStorage {
         Ok(())
     }
 
+    /// Serve the files of a previous version of a website, replacing the
+    /// current ones in a single batch.
+    pub fn rollback_website(
+        &mut self,
+        owner: &Address,
+        site_name: String,
+        version: &WebDeploymentVersion,
+        memo: Option<Memo>,
+    ) -> Result<(), ManyError> {
+        trace!(
+            "Rolling back website {} to version {}",
+            site_name,
+            version.version
+        );
+        let batch_r = self._remove_website(owner, &site_name)?;
+
+        let prefix = key_for_website(owner, &site_name);
+        let version_prefix =
+            key_for_website_version_files(owner, &site_name, Some(version.version));
+        let mut batch_s: Vec<BatchEntry> = Vec::new();
+        for item in WebIterator::website_version_files(
+            &self.persistent_store,
+            owner,
+            &site_name,
+            Some(version.version),
+        ) {
+            let (key, value) = item.map_err(error::storage_get_failed)?;
+            let file_name = key
+                .strip_prefix(version_prefix.as_slice())
+                .ok_or_else(|| error::unable_to_strip_prefix(hex::encode(&key)))?;
+            batch_s.push(([prefix.as_slice(), file_name].concat(), Op::Put(value)));
+        }
+        batch_s.push((
+            key_for_website_meta(owner, &site_name).into_bytes(),
+            Op::Put(
+                minicbor::to_vec(WebDeploymentInfo {
+                    owner: *owner,
+                    site_name: site_name.clone(),
+                    site_description: version.site_description.clone(),
+                    url: Some(url_for_website(owner, &site_name)),
+                    domain: version.domain.clone(),
+                })
+                .map_err(ManyError::serialization_error)?,
+            ),
+        ));
+        batch_s.push((
+            key_for_website_current_version(owner, &site_name).into_bytes(),
+            Op::Put(version.version.to_be_bytes().to_vec()),
+        ));
+
+        // See `update_website` for why the batches are combined this way.
+        let mut combined: Vec<_> = batch_s.into_iter().chain(batch_r).collect();
+        combined.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
+        combined.dedup_by(|(k1, _), (k2, _)| k1 == k2);
+
+        self.persistent_store
+            .apply(&combined)
+            .map_err(error::storage_apply_failed)?;
+
+        self.log_event(EventInfo::WebRollback {
+            owner: *owner,
+            site_name,
+            version: version.version,
+            memo,
+        })?;
+        self.maybe_commit()?;
+
+        Ok(())
+    }
+
     pub fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, ManyError> {
         self.persistent_store
             .get(key)
@@ -1,5 +1,7 @@
 use crate::storage::events::{key_for_event, EVENTS_ROOT};
-use crate::storage::{key_for_website, META_ROOT};
+use crate::storage::{
+    key_for_website, key_for_website_version_files, key_for_website_versions, META_ROOT,
+};
 use many_identity::Address;
 use many_modules::events::EventId;
 use many_types::{CborRange, SortOrder};
@@ -44,6 +46,42 @@ impl<'a> WebIterator<'a> {
         Self { inner }
     }
 
+    pub fn website_versions<S: AsRef<str>>(
+        merk: &'a merk::Merk,
+        owner: &Address,
+        site_name: &S,
+    ) -> Self {
+        let mut options = ReadOptions::default();
+        options.set_iterate_range(rocksdb::PrefixRange(key_for_website_versions(
+            owner,
+            site_name.as_ref(),
+        )));
+
+        let inner = merk.iter_opt(IteratorMode::Start, options);
+
+        Self { inner }
+    }
+
+    /// The files of a version of a website, or of all its versions if `version`
+    /// is `None`.
+    pub fn website_version_files<S: AsRef<str>>(
+        merk: &'a merk::Merk,
+        owner: &Address,
+        site_name: &S,
+        version: Option<u64>,
+    ) -> Self {
+        let mut options = ReadOptions::default();
+        options.set_iterate_range(rocksdb::PrefixRange(key_for_website_version_files(
+            owner,
+            site_name.as_ref(),
+            version,
+        )));
+
+        let inner = merk.iter_opt(IteratorMode::Start, options);
+
+        Self { inner }
+    }
+
     pub fn events_scoped_by_id(
         merk: &'a merk::Merk,
         range: CborRange<EventId>,
@@ -11,6 +11,18 @@ Scenario: Deploy a single website
 <img src="logo.webp"></img>
 """
 
+@web
+Scenario: Deploy a single website with compressed assets
+  Given a web server storing compressed assets
+  And a website zip source "504b0304140300000800814df9560f5bea312f000000300000000a000000696e6465782e68746d6cb3c930b4f348cdc9c95708f1f154b4d10772b96c3273d3158a8b926d9572f2d3f3f5ca53930a94ec6cf481a2765c00504b03041403000008004a78f856308c5073c20e0000140f0000090000006c6f676f2e7765627045567938d47918ffce6118578331c608e3be652c25c73626e46e28d19226d3312bf7466ce48711c3b8d2a19041ec94bb342b295452d80c2d5993a229cd6e6e42e5d8d9e3d97d9ff7f3bceff77ddfe7f3be7fbccffb7c7ddd5c5c64b700e0ef4ca11ea0da04480300f06204fcade288a7a93c00db02000ee3651126d8ae18a6baf279f9b04df8894cd12612ba352b31fa692fb6aee463d67181c9ddfadad0fc7938e790b26f03f9e848247f90b47589d6595da0bb27f6f59c64fc44e5ea942c52a7e0fa1427df89936cac4df4615fe185f7698e119194fed2f53210e0518a06e02968eb59d5e4a54212b9b192d21d133790d0dc853859f120ab6c521bd2d46873b3e5cad2da06361d4391d26292d4f53f3497daf519f7a3276577662096620efc7ec8ebf9ce0f7edea5f7fcdf7ae1e96c858ad54d46a0f11d3f5ba0decdf31378d7bdafabacac31f8462c82819f9e0ee43d0da7ede96397178f3f8df128baf2fb6272abede877c521ba6eb9badd263edb87c7b7522fed3ea782a134de71aeec41840baac3053c650fd5f004012f5cacc5bd62f397de1fc6dfcef525ba9e27341d4cf47ca6c535860a1610c8a5d137e65c8ee22231717bc0b6e9049775cdd308bbb4041be3dd211a73b6b559b3f0f1e0da2c2fd296a73a836f9e5f2e92b4976beff06f0c188163f53bba027016c9f648b08a3bcbec020816b2bc1ed6de590c62b766fb5600cdce86b9980e9a92ea29ed0bb2182e18975995b8168d8560f1aa6733f2e0e7b84e283908f094a4c830dc93bc3907e88564d15962c6b0ddd92fa103b965e36a181ecc4917c000d8790a093d066df8f6c14469aabb7ccdde99d51f5a83c6349bedef80742782c00e095028c6f5f3f8eaf20febc2bc857b5dbb8325f0b7779864fc6a327c6d79ead9667b2cef9417cec5c1079649fc9149060cdf810dced5f6a4866bdfbe1c5ab2197e321a0fcfe77632cf4f3e9fdc8c29ada4dd6a3e578b9482a38105e4594165f8f64cbe64ecca5e48a6a4a49360f5dd832332e7b26f183290035523d719ee9e3feae02ba026c59748c5701bccb4cac0da35a66d0a122bad34ce30cd593c57bbcfaa8236c67dc6fde660059c0cc06863ee7231a3fd512aaa51955e2c47e600d6c3c900558bfc342316c0d62e9e27cd908c0119313acbd825b3e72a8a1a926f8471e4e4a402594fe8debc3a28baff592be347b42e87e3b1fdc918fd0dc18a9bb3e0df5ce6bca8a28836415d21030c10401f264eac6239f9083480531fcff66ff6be79bed1eb7ca870efa82883ca4044304e7be45712a97868cbb84abc04adeff59a780005d1f99fb6257efe7ca97dcd4418da255505d2c8dab07b976e2250ae18c29cdc9aeb413c6db99a79c41450f921b161f66ff0dbda221cf4d521277ed257965607cd0455ffe416940aab93b0dfd51d4a58cc9237038a290f9cc9b7312ecad24ae25e6a9ce3911bf98ffc0a8f32bd1696fac69eaca3dd1080bc021b259b22a541d9a36d73b55a5add71eff6b83899981b1ca8b1d94bc092071d9752cb25c4eba29fb8e50d1c2ad7461fe1f02a0d1951c32d76b7abbf2346705e1a5dac8bc83e3cae038a2c500f8980a3d07855105a4dd0210275a7c1243545dd1cac2262eec81aaa57789c5c712ed54956ceacf5a875f79891841c060d39813853d40e234dbada9cc501ffed8fa768907c1bd2f2bb20eb033f9458f34274e51ca7e26238847cc33e50fff8bc0da3f1522659b2c0893d4d21d62baa1686ecaf50cc97827f21d3c274225bcde4bbceb784353a23090f5329a754fddd3a6bf35c2a748828053826654cee988b2cc6901c42db7dcb56032f9feaad5c4e941e5028be6e54edac65ac2545e4533aeaf5ee0fc1a34c2e75cb43cc3418825820eabf46c5e81d3450d6f1b4bd483e8a41e297c29f725fc34b72f5a27b26700269a8e3019e454ec1faa349462e2dac14efd69f258911c78dc3f2de470b1c39fa5ed1a4c5b51bd1ad7cac320672da464c85b47d58352ee5d5a53c9114b33c0dfad6d5360ca014595f9b6cef9370514a115b2a09feddbcbc88af0f2f473af4f45520f247108e060a90f680829a55114e8e88b082f393a68ff5a22fd3ddb6c640f850b645c3fc470fcdb257cb570e07bf9e2c8a03002c3bba35790f4c987e7f03eea4cd70d2a62cd90748dda4160149ba5b50f4190367a3c952dbeed2e23cff29fafcc8368d9d96a1037aeb245efb596942f410fc49570b1759d17e95c991ca872821f21fe9b1f63167b88c466e7cbcfd96137fdc4e8e7fc7df8b2baef5295c270169e3e88b58d274738bd1af0f4215f14390d311f82bf6fba617d3a5152e2e6ca734af8539bfab069b7359885d574e0d032bcec0ba37c90a5be1d1e44df0b4cb74276388a293308a8ee5cd7a9801ddad245ae0eccebe284536310f2c69ef5b62bd697bcb07580231ed3ec95d7fd8ed7273112e533a530b49596a06cd4d4dd6fb8e9caccdb48b58af3ab398616a5316d0be9af15ea71da62b8d418992a84425b5ca40869d978a148be82ea5e8d085ccacbbd0ddd3d0b25c684692916c44591facf34df7db11b4a5c80214d0ee450add9066e9e6db65bcdfbfdb2025245f48ae089336d24de50cdccb274bd6971f1b1d7ff6dbc6eab2964b3aa75cef40f7ebcb89a28ce04f96ea6c1804777799f2347093044d071b3b9ffdf2528d77c840f3b5a141ee17e3f7e89e0786d9f9709082024f3b001cc8491f289c5f3bf7bbe50f333a27356d1e37198990216dcbb85729bf5d0a8fb6a151d0e9998e83908b011c12e106bc4ceb351a040b715fe67396a759f2b89446c227bf9edd1710e5e2bd8490ef001ccde0e3560c8283e6a36608055525afabf28e688c63b2fd0b6187dbbe384c5826aabfe9a82a02f40f85a3bf136edf3e189478474da938573b6fc0f128834a415c9df5fdb4dfb49c8954a8fa464a7ceacccc09357e255e03f9c20a2c1b6e73c121faeb0d55233df987f7e634edb300166976a30b55b86f6861e174cedee32f060a005c3dcd33e500d13368e6c7c0c8bbc7d2ad8924c0881e0ab1fe2199c2e391e108f7688b1c09e9f4c7f0da8ccbb4c03e22a675e4d4a870e8ede0c9b82f474f54989045443e8909cee76a59c662a96a1f050dd9920dbfd4a904c8307ca24fbf9d71723e09b2dd6cae2b030e503a9bcd4fbc1eb3d05a73cbb75726d2acbfbd991501b3b4d625030050224f05bf70b76139f3d3ecec432f5717488fc658437dad0359be8d8ad8778ed6de0058e076bc2e39bcf3c35b61d1cebb5f346cef4555dfea6ca538c662d0a730fcc251296e1763c2fa49d9e985aa62c1ed1a86a574000b34cad72cc2c1b8d99960d6c178415a439e69c3fdbd6a65f6f41706b1d75d9672d081bb8f4988d795d1bc37227172ac77c7b5208749ae2b12c8d7d583f8b16bfef03fe4b63c385cfe597d91f54bc84d75f1f54203da6f09efa44e4f0a0f151fbebbaf2cfefa37bbcdc0f81c416f76e4b337f9fe91fe93f3b9ca5591301a595bd4a171b348d76073be216cb6bf21f1c7cff0e902265f72ffd6e0c89d830dbb93ee94223b56e6644c617857373e24aa04fbad0266845d4261f1faea460cfb9be082fdbfc2518dc892057dd90e8339b5b6ad37d97deee71908a7e42e784958eea7be95afc29f7b2b335fe0249441923b01e0251350e38fda1db6ad8e8f355c0e5b55fa5616efab9d8ef269ea3f79893dbcc6bba3b92cf40cd4f7f2c921e8f14d780a6c84dc0ed8e383377695150f5d4eaca71842dc8bb0a57d1521ccae64fb84b0af3331f687f212062b08ca0a7a7c0f941416356e249940e43cba6b9ff48a8b55343a25bb1c6f444b60de56120a62d71ee42a5473e3599ce4e3edaad8468bfaae2c519a6c2be7ddb1bd5eae7a74113b75e4f2e86205b67d2a4f7399ae8363e9d25970fe4ffb93761745eddac8f8799c083d6a3b94c9f251a0d3cb5e1a5e9955dbfcee4ee40add2ffc9a15953e3b050c3b980f2f05287fa4e11238064199689f81593f9fa818da2b07d283df349303479142a60fec0c3a5db523254041453dc6323d29b837bd7e7eddf66cb0649f121753659cd48e90831e9243501fdecaacea9b13d8c6d5451a84e46b9a6f0ee5660c4d9a49633110798b1e064a79cc2c9e576b53d9a355904605c604d3959c137b23decc74170e553f935296ed791d74045b7d55a3f4b4c9e6f4d259191e423734f30400dedbefbeca8c5c11febceef35896ed70c1273c4c870add08f18d5b13fcb68e7bc5dcf3eb3b72b0b2c5ee7b731f13d7e21382e24d0b53b3f67fa9e9e0776c47e9773c0b20fc3451bd921cfd03372dac4b2a6f4af88c2e14acc73fd058dc6a664ce4cfdeb938daa2d04b57113d2d7497779668b0b132cfcde96ff876fee5065e4f694aee211c2b9d62d0643edc55f90809231a1b5f2a9dd73f7c38e92debbdd24a8d1995ddbc1c7f8c41a683e38d06c1691fcf69438b592f3e156d9d88549b2e5e6f096c72254ccd5310706a11f8897e3248b24501483c128546a8742f7c3abb76e2fb4276532710e9a54d2b11795d6e1073a2befcc4b3d22f7d87bf5686c88bfa1dad746186e4b76e53ec2418584c7350e2eb7f186deb8a0d95fc31593badae527ef9c9f3c00f0513a680a5b08c3cd27949e5f993419b8855930d50b3e3ce6973e568bb038fb707feb2182f5b9a1500f55c70ed3b42af657d0509fb8f62e887ca8af3a1f5267b733b73b8d5c99a90282d5e9742f7e431460b3ad5aea9ce6b8f867a5528521a8d1f513a5331c195922df5c9215782281b5e7712f47b4b2a5e00c4c3f986657b74dc5c6831bca84619775cf3b842b3d936dcebd909e0e914b0bd5b666e6b18b4358b0a6e10e4b63dc2502ac1373684d987cc5dc28d898dfe0c671802c67280bcc3fc64cdbb875b80310b96865e09f107ae464e80ff55dfa3236d4421030e08e79426e7f625a24ec0f5b14459cb1e9ea51590068d3be2ba523bbfa214bb276ca632053fd7abc4d5035f6bf1e72f202cdb2e97134275055339632449c799294d04ba453150582214c5704f7620c4ac08458404042b9ab3887bd219e59424cecb0a84219e2f21748afa3cae4fc36f9f0b4cd1dda6ede3c6c0b3fb45f983ac55cc5956607e54d0394b24adf2f4c6eacbc0999255a1a5de3a4ef0d0feb8fe8d097f9873809b4b2b00c039fefbe300b8b9190329f10b051461080003e06f1060127fdb0362a8fdeb078b61f86f0d5c0c25f8fff5df6720ffb6c7c13f126f090722c4ff160540be24408a331696240b58c13f3e4ccc0038ffc649161630ce3f8c9b9be20e9c7f180f8a81f8cfff47fe04504b01023f03140300000800814df9560f5bea312f000000300000000a0024000000000000002080a48100000000696e6465782e68746d6c0a0020000000000001001800003d2c12febed901003d2c12febed901003d2c12febed901504b01023f031403000008004a78f856308c5073c20e0000140f0000090024000000000000002080a481570000006c6f676f2e776562700a002000000000000100180000060e5f61bed90100060e5f61bed90100060e5f61bed901504b05060000000002000200b7000000400f00000000"
+  And a website name "test_dweb"
+  And a website description "This is a test"
+  When the website is deployed as identity 1
+  Then the "index.html" value of website "test_dweb" for owner identity 1 is
+    """<h1>Hello TLI!</h1>
+<img src="logo.webp"></img>
+"""
+
 @web
 Scenario: Deploy two different websites, different owners, same site name
   Given a website zip source "504b0304140300000800814df9560f5bea312f000000300000000a000000696e6465782e68746d6cb3c930b4f348cdc9c95708f1f154b4d10772b96c3273d3158a8b926d9572f2d3f3f5ca53930a94ec6cf481a2765c00504b03041403000008004a78f856308c5073c20e0000140f0000090000006c6f676f2e7765627045567938d47918ffce6118578331c608e3be652c25c73626e46e28d19226d3312bf7466ce48711c3b8d2a19041ec94bb342b295452d80c2d5993a229cd6e6e42e5d8d9e3d97d9ff7f3bceff77ddfe7f3be7fbccffb7c7ddd5c5c64b700e0ef4ca11ea0da04480300f06204fcade288a7a93c00db02000ee3651126d8ae18a6baf279f9b04df8894cd12612ba352b31fa692fb6aee463d67181c9ddfadad0fc7938e790b26f03f9e848247f90b47589d6595da0bb27f6f59c64fc44e5ea942c52a7e0fa1427df89936cac4df4615fe185f7698e119194fed2f53210e0518a06e02968eb59d5e4a54212b9b192d21d133790d0dc853859f120ab6c521bd2d46873b3e5cad2da06361d4391d26292d4f53f3497daf519f7a3276577662096620efc7ec8ebf9ce0f7edea5f7fcdf7ae1e96c858ad54d46a0f11d3f5ba0decdf31378d7bdafabacac31f8462c82819f9e0ee43d0da7ede96397178f3f8df128baf2fb6272abede877c521ba6eb9badd263edb87c7b7522fed3ea782a134de71aeec41840baac3053c650fd5f004012f5cacc5bd62f397de1fc6dfcef525ba9e27341d4cf47ca6c535860a1610c8a5d137e65c8ee22231717bc0b6e9049775cdd308bbb4041be3dd211a73b6b559b3f0f1e0da2c2fd296a73a836f9e5f2e92b4976beff06f0c188163f53bba027016c9f648b08a3bcbec020816b2bc1ed6de590c62b766fb5600cdce86b9980e9a92ea29ed0bb2182e18975995b8168d8560f1aa6733f2e0e7b84e283908f094a4c830dc93bc3907e88564d15962c6b0ddd92fa103b965e36a181ecc4917c000d8790a093d066df8f6c14469aabb7ccdde99d51f5a83c6349bedef80742782c00e095028c6f5f3f8eaf20febc2bc857b5dbb8325f0b7779864fc6a327c6d79ead9667b2cef9417cec5c1079649fc9149060cdf810dced5f6a4866bdfbe1c5ab2197e321a0fcfe77632cf4f3e9fdc8c29ada4dd6a3e578b9482a38105e4594165f8f64cbe64ecca5e48a6a4a49360f5dd832332e7b26f183290035523d719ee9e3feae02ba026c59748c5701bccb4cac0da35a66d0a122bad34ce30cd593c57bbcfaa8236c67dc6fde660059c0cc06863ee7231a3fd512aaa51955e2c47e600d6c3c900558bfc342316c0d62e9e27cd908c0119313acbd825b3e72a8a1a926f8471e4e4a402594fe8debc3a28baff592be347b42e87e3b1fdc918fd0dc18a9bb3e0df5ce6bca8a28836415d21030c10401f264eac6239f9083480531fcff66ff6be79bed1eb7ca870efa82883ca4044304e7be45712a97868cbb84abc04adeff59a780005d1f99fb6257efe7ca97dcd4418da255505d2c8dab07b976e2250ae18c29cdc9aeb413c6db99a79c41450f921b161f66ff0dbda221cf4d521277ed257965607cd0455ffe416940aab93b0dfd51d4a58cc9237038a290f9cc9b7312ecad24ae25e6a9ce3911bf98ffc0a8f32bd1696fac69eaca3dd1080bc021b259b22a541d9a36d73b55a5add71eff6b83899981b1ca8b1d94bc092071d9752cb25c4eba29fb8e50d1c2ad7461fe1f02a0d1951c32d76b7abbf2346705e1a5dac8bc83e3cae038a2c500f8980a3d07855105a4dd0210275a7c1243545dd1cac2262eec81aaa57789c5c712ed54956ceacf5a875f79891841c060d39813853d40e234dbada9cc501ffed8fa768907c1bd2f2bb20eb033f9458f34274e51ca7e26238847cc33e50fff8bc0da3f1522659b2c0893d4d21d62baa1686ecaf50cc97827f21d3c274225bcde4bbceb784353a23090f5329a754fddd3a6bf35c2a748828053826654cee988b2cc6901c42db7dcb56032f9feaad5c4e941e5028be6e54edac65ac2545e4533aeaf5ee0fc1a34c2e75cb43cc3418825820eabf46c5e81d3450d6f1b4bd483e8a41e297c29f725fc34b72f5a27b26700269a8e3019e454ec1faa349462e2dac14efd69f258911c78dc3f2de470b1c39fa5ed1a4c5b51bd1ad7cac320672da464c85b47d58352ee5d5a53c9114b33c0dfad6d5360ca014595f9b6cef9370514a115b2a09feddbcbc88af0f2f473af4f45520f247108e060a90f680829a55114e8e88b082f393a68ff5a22fd3ddb6c640f850b645c3fc470fcdb257cb570e07bf9e2c8a03002c3bba35790f4c987e7f03eea4cd70d2a62cd90748dda4160149ba5b50f4190367a3c952dbeed2e23cff29fafcc8368d9d96a1037aeb245efb596942f410fc49570b1759d17e95c991ca872821f21fe9b1f63167b88c466e7cbcfd96137fdc4e8e7fc7df8b2baef5295c270169e3e88b58d274738bd1af0f4215f14390d311f82bf6fba617d3a5152e2e6ca734af8539bfab069b7359885d574e0d032bcec0ba37c90a5be1d1e44df0b4cb74276388a293308a8ee5cd7a9801ddad245ae0eccebe284536310f2c69ef5b62bd697bcb07580231ed3ec95d7fd8ed7273112e533a530b49596a06cd4d4dd6fb8e9caccdb48b58af3ab398616a5316d0be9af15ea71da62b8d418992a84425b5ca40869d978a148be82ea5e8d085ccacbbd0ddd3d0b25c684692916c44591facf34df7db11b4a5c80214d0ee450add9066e9e6db65bcdfbfdb2025245f48ae089336d24de50cdccb274bd6971f1b1d7ff6dbc6eab2964b3aa75cef40f7ebcb89a28ce04f96ea6c1804777799f2347093044d071b3b9ffdf2528d77c840f3b5a141ee17e3f7e89e0786d9f9709082024f3b001cc8491f289c5f3bf7bbe50f333a27356d1e37198990216dcbb85729bf5d0a8fb6a151d0e9998e83908b011c12e106bc4ceb351a040b715fe67396a759f2b89446c227bf9edd1710e5e2bd8490ef001ccde0e3560c8283e6a36608055525afabf28e688c63b2fd0b6187dbbe384c5826aabfe9a82a02f40f85a3bf136edf3e189478474da938573b6fc0f128834a415c9df5fdb4dfb49c8954a8fa464a7ceacccc09357e255e03f9c20a2c1b6e73c121faeb0d55233df987f7e634edb300166976a30b55b86f6861e174cedee32f060a005c3dcd33e500d13368e6c7c0c8bbc7d2ad8924c0881e0ab1fe2199c2e391e108f7688b1c09e9f4c7f0da8ccbb4c03e22a675e4d4a870e8ede0c9b82f474f54989045443e8909cee76a59c662a96a1f050dd9920dbfd4a904c8307ca24fbf9d71723e09b2dd6cae2b030e503a9bcd4fbc1eb3d05a73cbb75726d2acbfbd991501b3b4d625030050224f05bf70b76139f3d3ecec432f5717488fc658437dad0359be8d8ad8778ed6de0058e076bc2e39bcf3c35b61d1cebb5f346cef4555dfea6ca538c662d0a730fcc251296e1763c2fa49d9e985aa62c1ed1a86a574000b34cad72cc2c1b8d99960d6c178415a439e69c3fdbd6a65f6f41706b1d75d9672d081bb8f4988d795d1bc37227172ac77c7b5208749ae2b12c8d7d583f8b16bfef03fe4b63c385cfe597d91f54bc84d75f1f54203da6f09efa44e4f0a0f151fbebbaf2cfefa37bbcdc0f81c416f76e4b337f9fe91fe93f3b9ca5591301a595bd4a171b348d76073be216cb6bf21f1c7cff0e902265f72ffd6e0c89d830dbb93ee94223b56e6644c617857373e24aa04fbad0266845d4261f1faea460cfb9be082fdbfc2518dc892057dd90e8339b5b6ad37d97deee71908a7e42e784958eea7be95afc29f7b2b335fe0249441923b01e0251350e38fda1db6ad8e8f355c0e5b55fa5616efab9d8ef269ea3f79893dbcc6bba3b92cf40cd4f7f2c921e8f14d780a6c84dc0ed8e383377695150f5d4eaca71842dc8bb0a57d1521ccae64fb84b0af3331f687f212062b08ca0a7a7c0f941416356e249940e43cba6b9ff48a8b55343a25bb1c6f444b60de56120a62d71ee42a5473e3599ce4e3edaad8468bfaae2c519a6c2be7ddb1bd5eae7a74113b75e4f2e86205b67d2a4f7399ae8363e9d25970fe4ffb93761745eddac8f8799c083d6a3b94c9f251a0d3cb5e1a5e9955dbfcee4ee40add2ffc9a15953e3b050c3b980f2f05287fa4e11238064199689f81593f9fa818da2b07d283df349303479142a60fec0c3a5db523254041453dc6323d29b837bd7e7eddf66cb0649f121753659cd48e90831e9243501fdecaacea9b13d8c6d5451a84e46b9a6f0ee5660c4d9a49633110798b1e064a79cc2c9e576b53d9a355904605c604d3959c137b23decc74170e553f935296ed791d74045b7d55a3f4b4c9e6f4d259191e423734f30400dedbefbeca8c5c11febceef35896ed70c1273c4c870add08f18d5b13fcb68e7bc5dcf3eb3b72b0b2c5ee7b731f13d7e21382e24d0b53b3f67fa9e9e0776c47e9773c0b20fc3451bd921cfd03372dac4b2a6f4af88c2e14acc73fd058dc6a664ce4cfdeb938daa2d04b57113d2d7497779668b0b132cfcde96ff876fee5065e4f694aee211c2b9d62d0643edc55f90809231a1b5f2a9dd73f7c38e92debbdd24a8d1995ddbc1c7f8c41a683e38d06c1691fcf69438b592f3e156d9d88549b2e5e6f096c72254ccd5310706a11f8897e3248b24501483c128546a8742f7c3abb76e2fb4276532710e9a54d2b11795d6e1073a2befcc4b3d22f7d87bf5686c88bfa1dad746186e4b76e53ec2418584c7350e2eb7f186deb8a0d95fc31593badae527ef9c9f3c00f0513a680a5b08c3cd27949e5f993419b8855930d50b3e3ce6973e568bb038fb707feb2182f5b9a1500f55c70ed3b42af657d0509fb8f62e887ca8af3a1f5267b733b73b8d5c99a90282d5e9742f7e431460b3ad5aea9ce6b8f867a5528521a8d1f513a5331c195922df5c9215782281b5e7712f47b4b2a5e00c4c3f986657b74dc5c6831bca84619775cf3b842b3d936dcebd909e0e914b0bd5b666e6b18b4358b0a6e10e4b63dc2502ac1373684d987cc5dc28d898dfe0c671802c67280bcc3fc64cdbb875b80310b96865e09f107ae464e80ff55dfa3236d4421030e08e79426e7f625a24ec0f5b14459cb1e9ea51590068d3be2ba523bbfa214bb276ca632053fd7abc4d5035f6bf1e72f202cdb2e97134275055339632449c799294d04ba453150582214c5704f7620c4ac08458404042b9ab3887bd219e59424cecb0a84219e2f21748afa3cae4fc36f9f0b4cd1dda6ede3c6c0b3fb45f983ac55cc5956607e54d0394b24adf2f4c6eacbc0999255a1a5de3a4ef0d0feb8fe8d097f9873809b4b2b00c039fefbe300b8b9190329f10b051461080003e06f1060127fdb0362a8fdeb078b61f86f0d5c0c25f8fff5df6720ffb6c7c13f126f090722c4ff160540be24408a331696240b58c13f3e4ccc0038ffc649161630ce3f8c9b9be20e9c7f180f8a81f8cfff47fe04504b01023f03140300000800814df9560f5bea312f000000300000000a0024000000000000002080a48100000000696e6465782e68746d6c0a0020000000000001001800003d2c12febed901003d2c12febed901003d2c12febed901504b01023f031403000008004a78f856308c5073c20e0000140f0000090024000000000000002080a481570000006c6f676f2e776562700a002000000000000100180000060e5f61bed90100060e5f61bed90100060e5f61bed901504b05060000000002000200b7000000400f00000000"
@@ -62,6 +74,44 @@ Scenario: Deploy and update a website
   Then "logo.webp" of website "test_dweb" for identity 1 is empty
     """"""
 
+@web
+Scenario: Deploy, update and roll back a website
+  Given a website zip source "504b0304140300000800814df9560f5bea312f000000300000000a000000696e6465782e68746d6cb3c930b4f348cdc9c95708f1f154b4d10772b96c3273d3158a8b926d9572f2d3f3f5ca53930a94ec6cf481a2765c00504b03041403000008004a78f856308c5073c20e0000140f0000090000006c6f676f2e7765627045567938d47918ffce6118578331c608e3be652c25c73626e46e28d19226d3312bf7466ce48711c3b8d2a19041ec94bb342b295452d80c2d5993a229cd6e6e42e5d8d9e3d97d9ff7f3bceff77ddfe7f3be7fbccffb7c7ddd5c5c64b700e0ef4ca11ea0da04480300f06204fcade288a7a93c00db02000ee3651126d8ae18a6baf279f9b04df8894cd12612ba352b31fa692fb6aee463d67181c9ddfadad0fc7938e790b26f03f9e848247f90b47589d6595da0bb27f6f59c64fc44e5ea942c52a7e0fa1427df89936cac4df4615fe185f7698e119194fed2f53210e0518a06e02968eb59d5e4a54212b9b192d21d133790d0dc853859f120ab6c521bd2d46873b3e5cad2da06361d4391d26292d4f53f3497daf519f7a3276577662096620efc7ec8ebf9ce0f7edea5f7fcdf7ae1e96c858ad54d46a0f11d3f5ba0decdf31378d7bdafabacac31f8462c82819f9e0ee43d0da7ede96397178f3f8df128baf2fb6272abede877c521ba6eb9badd263edb87c7b7522fed3ea782a134de71aeec41840baac3053c650fd5f004012f5cacc5bd62f397de1fc6dfcef525ba9e27341d4cf47ca6c535860a1610c8a5d137e65c8ee22231717bc0b6e9049775cdd308bbb4041be3dd211a73b6b559b3f0f1e0da2c2fd296a73a836f9e5f2e92b4976beff06f0c188163f53bba027016c9f648b08a3bcbec020816b2bc1ed6de590c62b766fb5600cdce86b9980e9a92ea29ed0bb2182e18975995b8168d8560f1aa6733f2e0e7b84e283908f094a4c830dc93bc3907e88564d15962c6b0ddd92fa103b965e36a181ecc4917c000d8790a093d066df8f6c14469aabb7ccdde99d51f5a83c6349bedef80742782c00e095028c6f5f3f8eaf20febc2bc857b5dbb8325f0b7779864fc6a327c6d79ead9667b2cef9417cec5c1079649fc9149060cdf810dced5f6a4866bdfbe1c5ab2197e321a0fcfe77632cf4f3e9fdc8c29ada4dd6a3e578b9482a38105e4594165f8f64cbe64ecca5e48a6a4a49360f5dd832332e7b26f183290035523d719ee9e3feae02ba026c59748c5701bccb4cac0da35a66d0a122bad34ce30cd593c57bbcfaa8236c67dc6fde660059c0cc06863ee7231a3fd512aaa51955e2c47e600d6c3c900558bfc342316c0d62e9e27cd908c0119313acbd825b3e72a8a1a926f8471e4e4a402594fe8debc3a28baff592be347b42e87e3b1fdc918fd0dc18a9bb3e0df5ce6bca8a28836415d21030c10401f264eac6239f9083480531fcff66ff6be79bed1eb7ca870efa82883ca4044304e7be45712a97868cbb84abc04adeff59a780005d1f99fb6257efe7ca97dcd4418da255505d2c8dab07b976e2250ae18c29cdc9aeb413c6db99a79c41450f921b161f66ff0dbda221cf4d521277ed257965607cd0455ffe416940aab93b0dfd51d4a58cc9237038a290f9cc9b7312ecad24ae25e6a9ce3911bf98ffc0a8f32bd1696fac69eaca3dd1080bc021b259b22a541d9a36d73b55a5add71eff6b83899981b1ca8b1d94bc092071d9752cb25c4eba29fb8e50d1c2ad7461fe1f02a0d1951c32d76b7abbf2346705e1a5dac8bc83e3cae038a2c500f8980a3d07855105a4dd0210275a7c1243545dd1cac2262eec81aaa57789c5c712ed54956ceacf5a875f79891841c060d39813853d40e234dbada9cc501ffed8fa768907c1bd2f2bb20eb033f9458f34274e51ca7e26238847cc33e50fff8bc0da3f1522659b2c0893d4d21d62baa1686ecaf50cc97827f21d3c274225bcde4bbceb784353a23090f5329a754fddd3a6bf35c2a748828053826654cee988b2cc6901c42db7dcb56032f9feaad5c4e941e5028be6e54edac65ac2545e4533aeaf5ee0fc1a34c2e75cb43cc3418825820eabf46c5e81d3450d6f1b4bd483e8a41e297c29f725fc34b72f5a27b26700269a8e3019e454ec1faa349462e2dac14efd69f258911c78dc3f2de470b1c39fa5ed1a4c5b51bd1ad7cac320672da464c85b47d58352ee5d5a53c9114b33c0dfad6d5360ca014595f9b6cef9370514a115b2a09feddbcbc88af0f2f473af4f45520f247108e060a90f680829a55114e8e88b082f393a68ff5a22fd3ddb6c640f850b645c3fc470fcdb257cb570e07bf9e2c8a03002c3bba35790f4c987e7f03eea4cd70d2a62cd90748dda4160149ba5b50f4190367a3c952dbeed2e23cff29fafcc8368d9d96a1037aeb245efb596942f410fc49570b1759d17e95c991ca872821f21fe9b1f63167b88c466e7cbcfd96137fdc4e8e7fc7df8b2baef5295c270169e3e88b58d274738bd1af0f4215f14390d311f82bf6fba617d3a5152e2e6ca734af8539bfab069b7359885d574e0d032bcec0ba37c90a5be1d1e44df0b4cb74276388a293308a8ee5cd7a9801ddad245ae0eccebe284536310f2c69ef5b62bd697bcb07580231ed3ec95d7fd8ed7273112e533a530b49596a06cd4d4dd6fb8e9caccdb48b58af3ab398616a5316d0be9af15ea71da62b8d418992a84425b5ca40869d978a148be82ea5e8d085ccacbbd0ddd3d0b25c684692916c44591facf34df7db11b4a5c80214d0ee450add9066e9e6db65bcdfbfdb2025245f48ae089336d24de50cdccb274bd6971f1b1d7ff6dbc6eab2964b3aa75cef40f7ebcb89a28ce04f96ea6c1804777799f2347093044d071b3b9ffdf2528d77c840f3b5a141ee17e3f7e89e0786d9f9709082024f3b001cc8491f289c5f3bf7bbe50f333a27356d1e37198990216dcbb85729bf5d0a8fb6a151d0e9998e83908b011c12e106bc4ceb351a040b715fe67396a759f2b89446c227bf9edd1710e5e2bd8490ef001ccde0e3560c8283e6a36608055525afabf28e688c63b2fd0b6187dbbe384c5826aabfe9a82a02f40f85a3bf136edf3e189478474da938573b6fc0f128834a415c9df5fdb4dfb49c8954a8fa464a7ceacccc09357e255e03f9c20a2c1b6e73c121faeb0d55233df987f7e634edb300166976a30b55b86f6861e174cedee32f060a005c3dcd33e500d13368e6c7c0c8bbc7d2ad8924c0881e0ab1fe2199c2e391e108f7688b1c09e9f4c7f0da8ccbb4c03e22a675e4d4a870e8ede0c9b82f474f54989045443e8909cee76a59c662a96a1f050dd9920dbfd4a904c8307ca24fbf9d71723e09b2dd6cae2b030e503a9bcd4fbc1eb3d05a73cbb75726d2acbfbd991501b3b4d625030050224f05bf70b76139f3d3ecec432f5717488fc658437dad0359be8d8ad8778ed6de0058e076bc2e39bcf3c35b61d1cebb5f346cef4555dfea6ca538c662d0a730fcc251296e1763c2fa49d9e985aa62c1ed1a86a574000b34cad72cc2c1b8d99960d6c178415a439e69c3fdbd6a65f6f41706b1d75d9672d081bb8f4988d795d1bc37227172ac77c7b5208749ae2b12c8d7d583f8b16bfef03fe4b63c385cfe597d91f54bc84d75f1f54203da6f09efa44e4f0a0f151fbebbaf2cfefa37bbcdc0f81c416f76e4b337f9fe91fe93f3b9ca5591301a595bd4a171b348d76073be216cb6bf21f1c7cff0e902265f72ffd6e0c89d830dbb93ee94223b56e6644c617857373e24aa04fbad0266845d4261f1faea460cfb9be082fdbfc2518dc892057dd90e8339b5b6ad37d97deee71908a7e42e784958eea7be95afc29f7b2b335fe0249441923b01e0251350e38fda1db6ad8e8f355c0e5b55fa5616efab9d8ef269ea3f79893dbcc6bba3b92cf40cd4f7f2c921e8f14d780a6c84dc0ed8e383377695150f5d4eaca71842dc8bb0a57d1521ccae64fb84b0af3331f687f212062b08ca0a7a7c0f941416356e249940e43cba6b9ff48a8b55343a25bb1c6f444b60de56120a62d71ee42a5473e3599ce4e3edaad8468bfaae2c519a6c2be7ddb1bd5eae7a74113b75e4f2e86205b67d2a4f7399ae8363e9d25970fe4ffb93761745eddac8f8799c083d6a3b94c9f251a0d3cb5e1a5e9955dbfcee4ee40add2ffc9a15953e3b050c3b980f2f05287fa4e11238064199689f81593f9fa818da2b07d283df349303479142a60fec0c3a5db523254041453dc6323d29b837bd7e7eddf66cb0649f121753659cd48e90831e9243501fdecaacea9b13d8c6d5451a84e46b9a6f0ee5660c4d9a49633110798b1e064a79cc2c9e576b53d9a355904605c604d3959c137b23decc74170e553f935296ed791d74045b7d55a3f4b4c9e6f4d259191e423734f30400dedbefbeca8c5c11febceef35896ed70c1273c4c870add08f18d5b13fcb68e7bc5dcf3eb3b72b0b2c5ee7b731f13d7e21382e24d0b53b3f67fa9e9e0776c47e9773c0b20fc3451bd921cfd03372dac4b2a6f4af88c2e14acc73fd058dc6a664ce4cfdeb938daa2d04b57113d2d7497779668b0b132cfcde96ff876fee5065e4f694aee211c2b9d62d0643edc55f90809231a1b5f2a9dd73f7c38e92debbdd24a8d1995ddbc1c7f8c41a683e38d06c1691fcf69438b592f3e156d9d88549b2e5e6f096c72254ccd5310706a11f8897e3248b24501483c128546a8742f7c3abb76e2fb4276532710e9a54d2b11795d6e1073a2befcc4b3d22f7d87bf5686c88bfa1dad746186e4b76e53ec2418584c7350e2eb7f186deb8a0d95fc31593badae527ef9c9f3c00f0513a680a5b08c3cd27949e5f993419b8855930d50b3e3ce6973e568bb038fb707feb2182f5b9a1500f55c70ed3b42af657d0509fb8f62e887ca8af3a1f5267b733b73b8d5c99a90282d5e9742f7e431460b3ad5aea9ce6b8f867a5528521a8d1f513a5331c195922df5c9215782281b5e7712f47b4b2a5e00c4c3f986657b74dc5c6831bca84619775cf3b842b3d936dcebd909e0e914b0bd5b666e6b18b4358b0a6e10e4b63dc2502ac1373684d987cc5dc28d898dfe0c671802c67280bcc3fc64cdbb875b80310b96865e09f107ae464e80ff55dfa3236d4421030e08e79426e7f625a24ec0f5b14459cb1e9ea51590068d3be2ba523bbfa214bb276ca632053fd7abc4d5035f6bf1e72f202cdb2e97134275055339632449c799294d04ba453150582214c5704f7620c4ac08458404042b9ab3887bd219e59424cecb0a84219e2f21748afa3cae4fc36f9f0b4cd1dda6ede3c6c0b3fb45f983ac55cc5956607e54d0394b24adf2f4c6eacbc0999255a1a5de3a4ef0d0feb8fe8d097f9873809b4b2b00c039fefbe300b8b9190329f10b051461080003e06f1060127fdb0362a8fdeb078b61f86f0d5c0c25f8fff5df6720ffb6c7c13f126f090722c4ff160540be24408a331696240b58c13f3e4ccc0038ffc649161630ce3f8c9b9be20e9c7f180f8a81f8cfff47fe04504b01023f03140300000800814df9560f5bea312f000000300000000a0024000000000000002080a48100000000696e6465782e68746d6c0a0020000000000001001800003d2c12febed901003d2c12febed901003d2c12febed901504b01023f031403000008004a78f856308c5073c20e0000140f0000090024000000000000002080a481570000006c6f676f2e776562700a002000000000000100180000060e5f61bed90100060e5f61bed90100060e5f61bed901504b05060000000002000200b7000000400f00000000"
+  And a website name "test_dweb"
+  And a website description "This is a test"
+  When the website is deployed as identity 1
+  Then the "index.html" value of website "test_dweb" for owner identity 1 is
+    """<h1>Hello TLI!</h1>
+<img src="logo.webp"></img>
+"""
+  Given a website zip source "504b03040a0300000000af680857dbff951917000000170000000a000000696e6465782e68746d6c3c68313e48656c6c6f20466f6f626172213c2f68313e0a504b01023f030a0300000000af680857dbff951917000000170000000a0024000000000000002080a48100000000696e6465782e68746d6c0a00200000000000010018000029f7881acad9010029f7881acad9010029f7881acad901504b050600000000010001005c0000003f0000000000"
+  And a website name "test_dweb"
+  And a website description "This is a test"
+  When the website is updated as identity 1
+  Then the "index.html" value of website "test_dweb" for owner identity 1 is
+    """<h1>Hello Foobar!</h1>
+"""
+  Then "logo.webp" of website "test_dweb" for identity 1 is empty
+    """"""
+  When the website "test_dweb" is rolled back to version 1 as identity 1
+  Then the website "test_dweb" of owner identity 1 has 2 versions and serves version 1
+  Then the "index.html" value of website "test_dweb" for owner identity 1 is
+    """<h1>Hello TLI!</h1>
+<img src="logo.webp"></img>
+"""
+  When the website "test_dweb" is rolled back to version 2 as identity 1
+  Then the website "test_dweb" of owner identity 1 has 2 versions and serves version 2
+  Then "logo.webp" of website "test_dweb" for identity 1 is empty
+    """"""
+
+@web
+Scenario: Roll back to a nonexistent version
+  Given a website zip source "504b0304140300000800814df9560f5bea312f000000300000000a000000696e6465782e68746d6cb3c930b4f348cdc9c95708f1f154b4d10772b96c3273d3158a8b926d9572f2d3f3f5ca53930a94ec6cf481a2765c00504b03041403000008004a78f856308c5073c20e0000140f0000090000006c6f676f2e7765627045567938d47918ffce6118578331c608e3be652c25c73626e46e28d19226d3312bf7466ce48711c3b8d2a19041ec94bb342b295452d80c2d5993a229cd6e6e42e5d8d9e3d97d9ff7f3bceff77ddfe7f3be7fbccffb7c7ddd5c5c64b700e0ef4ca11ea0da04480300f06204fcade288a7a93c00db02000ee3651126d8ae18a6baf279f9b04df8894cd12612ba352b31fa692fb6aee463d67181c9ddfadad0fc7938e790b26f03f9e848247f90b47589d6595da0bb27f6f59c64fc44e5ea942c52a7e0fa1427df89936cac4df4615fe185f7698e119194fed2f53210e0518a06e02968eb59d5e4a54212b9b192d21d133790d0dc853859f120ab6c521bd2d46873b3e5cad2da06361d4391d26292d4f53f3497daf519f7a3276577662096620efc7ec8ebf9ce0f7edea5f7fcdf7ae1e96c858ad54d46a0f11d3f5ba0decdf31378d7bdafabacac31f8462c82819f9e0ee43d0da7ede96397178f3f8df128baf2fb6272abede877c521ba6eb9badd263edb87c7b7522fed3ea782a134de71aeec41840baac3053c650fd5f004012f5cacc5bd62f397de1fc6dfcef525ba9e27341d4cf47ca6c535860a1610c8a5d137e65c8ee22231717bc0b6e9049775cdd308bbb4041be3dd211a73b6b559b3f0f1e0da2c2fd296a73a836f9e5f2e92b4976beff06f0c188163f53bba027016c9f648b08a3bcbec020816b2bc1ed6de590c62b766fb5600cdce86b9980e9a92ea29ed0bb2182e18975995b8168d8560f1aa6733f2e0e7b84e283908f094a4c830dc93bc3907e88564d15962c6b0ddd92fa103b965e36a181ecc4917c000d8790a093d066df8f6c14469aabb7ccdde99d51f5a83c6349bedef80742782c00e095028c6f5f3f8eaf20febc2bc857b5dbb8325f0b7779864fc6a327c6d79ead9667b2cef9417cec5c1079649fc9149060cdf810dced5f6a4866bdfbe1c5ab2197e321a0fcfe77632cf4f3e9fdc8c29ada4dd6a3e578b9482a38105e4594165f8f64cbe64ecca5e48a6a4a49360f5dd832332e7b26f183290035523d719ee9e3feae02ba026c59748c5701bccb4cac0da35a66d0a122bad34ce30cd593c57bbcfaa8236c67dc6fde660059c0cc06863ee7231a3fd512aaa51955e2c47e600d6c3c900558bfc342316c0d62e9e27cd908c0119313acbd825b3e72a8a1a926f8471e4e4a402594fe8debc3a28baff592be347b42e87e3b1fdc918fd0dc18a9bb3e0df5ce6bca8a28836415d21030c10401f264eac6239f9083480531fcff66ff6be79bed1eb7ca870efa82883ca4044304e7be45712a97868cbb84abc04adeff59a780005d1f99fb6257efe7ca97dcd4418da255505d2c8dab07b976e2250ae18c29cdc9aeb413c6db99a79c41450f921b161f66ff0dbda221cf4d521277ed257965607cd0455ffe416940aab93b0dfd51d4a58cc9237038a290f9cc9b7312ecad24ae25e6a9ce3911bf98ffc0a8f32bd1696fac69eaca3dd1080bc021b259b22a541d9a36d73b55a5add71eff6b83899981b1ca8b1d94bc092071d9752cb25c4eba29fb8e50d1c2ad7461fe1f02a0d1951c32d76b7abbf2346705e1a5dac8bc83e3cae038a2c500f8980a3d07855105a4dd0210275a7c1243545dd1cac2262eec81aaa57789c5c712ed54956ceacf5a875f79891841c060d39813853d40e234dbada9cc501ffed8fa768907c1bd2f2bb20eb033f9458f34274e51ca7e26238847cc33e50fff8bc0da3f1522659b2c0893d4d21d62baa1686ecaf50cc97827f21d3c274225bcde4bbceb784353a23090f5329a754fddd3a6bf35c2a748828053826654cee988b2cc6901c42db7dcb56032f9feaad5c4e941e5028be6e54edac65ac2545e4533aeaf5ee0fc1a34c2e75cb43cc3418825820eabf46c5e81d3450d6f1b4bd483e8a41e297c29f725fc34b72f5a27b26700269a8e3019e454ec1faa349462e2dac14efd69f258911c78dc3f2de470b1c39fa5ed1a4c5b51bd1ad7cac320672da464c85b47d58352ee5d5a53c9114b33c0dfad6d5360ca014595f9b6cef9370514a115b2a09feddbcbc88af0f2f473af4f45520f247108e060a90f680829a55114e8e88b082f393a68ff5a22fd3ddb6c640f850b645c3fc470fcdb257cb570e07bf9e2c8a03002c3bba35790f4c987e7f03eea4cd70d2a62cd90748dda4160149ba5b50f4190367a3c952dbeed2e23cff29fafcc8368d9d96a1037aeb245efb596942f410fc49570b1759d17e95c991ca872821f21fe9b1f63167b88c466e7cbcfd96137fdc4e8e7fc7df8b2baef5295c270169e3e88b58d274738bd1af0f4215f14390d311f82bf6fba617d3a5152e2e6ca734af8539bfab069b7359885d574e0d032bcec0ba37c90a5be1d1e44df0b4cb74276388a293308a8ee5cd7a9801ddad245ae0eccebe284536310f2c69ef5b62bd697bcb07580231ed3ec95d7fd8ed7273112e533a530b49596a06cd4d4dd6fb8e9caccdb48b58af3ab398616a5316d0be9af15ea71da62b8d418992a84425b5ca40869d978a148be82ea5e8d085ccacbbd0ddd3d0b25c684692916c44591facf34df7db11b4a5c80214d0ee450add9066e9e6db65bcdfbfdb2025245f48ae089336d24de50cdccb274bd6971f1b1d7ff6dbc6eab2964b3aa75cef40f7ebcb89a28ce04f96ea6c1804777799f2347093044d071b3b9ffdf2528d77c840f3b5a141ee17e3f7e89e0786d9f9709082024f3b001cc8491f289c5f3bf7bbe50f333a27356d1e37198990216dcbb85729bf5d0a8fb6a151d0e9998e83908b011c12e106bc4ceb351a040b715fe67396a759f2b89446c227bf9edd1710e5e2bd8490ef001ccde0e3560c8283e6a36608055525afabf28e688c63b2fd0b6187dbbe384c5826aabfe9a82a02f40f85a3bf136edf3e189478474da938573b6fc0f128834a415c9df5fdb4dfb49c8954a8fa464a7ceacccc09357e255e03f9c20a2c1b6e73c121faeb0d55233df987f7e634edb300166976a30b55b86f6861e174cedee32f060a005c3dcd33e500d13368e6c7c0c8bbc7d2ad8924c0881e0ab1fe2199c2e391e108f7688b1c09e9f4c7f0da8ccbb4c03e22a675e4d4a870e8ede0c9b82f474f54989045443e8909cee76a59c662a96a1f050dd9920dbfd4a904c8307ca24fbf9d71723e09b2dd6cae2b030e503a9bcd4fbc1eb3d05a73cbb75726d2acbfbd991501b3b4d625030050224f05bf70b76139f3d3ecec432f5717488fc658437dad0359be8d8ad8778ed6de0058e076bc2e39bcf3c35b61d1cebb5f346cef4555dfea6ca538c662d0a730fcc251296e1763c2fa49d9e985aa62c1ed1a86a574000b34cad72cc2c1b8d99960d6c178415a439e69c3fdbd6a65f6f41706b1d75d9672d081bb8f4988d795d1bc37227172ac77c7b5208749ae2b12c8d7d583f8b16bfef03fe4b63c385cfe597d91f54bc84d75f1f54203da6f09efa44e4f0a0f151fbebbaf2cfefa37bbcdc0f81c416f76e4b337f9fe91fe93f3b9ca5591301a595bd4a171b348d76073be216cb6bf21f1c7cff0e902265f72ffd6e0c89d830dbb93ee94223b56e6644c617857373e24aa04fbad0266845d4261f1faea460cfb9be082fdbfc2518dc892057dd90e8339b5b6ad37d97deee71908a7e42e784958eea7be95afc29f7b2b335fe0249441923b01e0251350e38fda1db6ad8e8f355c0e5b55fa5616efab9d8ef269ea3f79893dbcc6bba3b92cf40cd4f7f2c921e8f14d780a6c84dc0ed8e383377695150f5d4eaca71842dc8bb0a57d1521ccae64fb84b0af3331f687f212062b08ca0a7a7c0f941416356e249940e43cba6b9ff48a8b55343a25bb1c6f444b60de56120a62d71ee42a5473e3599ce4e3edaad8468bfaae2c519a6c2be7ddb1bd5eae7a74113b75e4f2e86205b67d2a4f7399ae8363e9d25970fe4ffb93761745eddac8f8799c083d6a3b94c9f251a0d3cb5e1a5e9955dbfcee4ee40add2ffc9a15953e3b050c3b980f2f05287fa4e11238064199689f81593f9fa818da2b07d283df349303479142a60fec0c3a5db523254041453dc6323d29b837bd7e7eddf66cb0649f121753659cd48e90831e9243501fdecaacea9b13d8c6d5451a84e46b9a6f0ee5660c4d9a49633110798b1e064a79cc2c9e576b53d9a355904605c604d3959c137b23decc74170e553f935296ed791d74045b7d55a3f4b4c9e6f4d259191e423734f30400dedbefbeca8c5c11febceef35896ed70c1273c4c870add08f18d5b13fcb68e7bc5dcf3eb3b72b0b2c5ee7b731f13d7e21382e24d0b53b3f67fa9e9e0776c47e9773c0b20fc3451bd921cfd03372dac4b2a6f4af88c2e14acc73fd058dc6a664ce4cfdeb938daa2d04b57113d2d7497779668b0b132cfcde96ff876fee5065e4f694aee211c2b9d62d0643edc55f90809231a1b5f2a9dd73f7c38e92debbdd24a8d1995ddbc1c7f8c41a683e38d06c1691fcf69438b592f3e156d9d88549b2e5e6f096c72254ccd5310706a11f8897e3248b24501483c128546a8742f7c3abb76e2fb4276532710e9a54d2b11795d6e1073a2befcc4b3d22f7d87bf5686c88bfa1dad746186e4b76e53ec2418584c7350e2eb7f186deb8a0d95fc31593badae527ef9c9f3c00f0513a680a5b08c3cd27949e5f993419b8855930d50b3e3ce6973e568bb038fb707feb2182f5b9a1500f55c70ed3b42af657d0509fb8f62e887ca8af3a1f5267b733b73b8d5c99a90282d5e9742f7e431460b3ad5aea9ce6b8f867a5528521a8d1f513a5331c195922df5c9215782281b5e7712f47b4b2a5e00c4c3f986657b74dc5c6831bca84619775cf3b842b3d936dcebd909e0e914b0bd5b666e6b18b4358b0a6e10e4b63dc2502ac1373684d987cc5dc28d898dfe0c671802c67280bcc3fc64cdbb875b80310b96865e09f107ae464e80ff55dfa3236d4421030e08e79426e7f625a24ec0f5b14459cb1e9ea51590068d3be2ba523bbfa214bb276ca632053fd7abc4d5035f6bf1e72f202cdb2e97134275055339632449c799294d04ba453150582214c5704f7620c4ac08458404042b9ab3887bd219e59424cecb0a84219e2f21748afa3cae4fc36f9f0b4cd1dda6ede3c6c0b3fb45f983ac55cc5956607e54d0394b24adf2f4c6eacbc0999255a1a5de3a4ef0d0feb8fe8d097f9873809b4b2b00c039fefbe300b8b9190329f10b051461080003e06f1060127fdb0362a8fdeb078b61f86f0d5c0c25f8fff5df6720ffb6c7c13f126f090722c4ff160540be24408a331696240b58c13f3e4ccc0038ffc649161630ce3f8c9b9be20e9c7f180f8a81f8cfff47fe04504b01023f03140300000800814df9560f5bea312f000000300000000a0024000000000000002080a48100000000696e6465782e68746d6c0a0020000000000001001800003d2c12febed901003d2c12febed901003d2c12febed901504b01023f031403000008004a78f856308c5073c20e0000140f0000090024000000000000002080a481570000006c6f676f2e776562700a002000000000000100180000060e5f61bed90100060e5f61bed90100060e5f61bed901504b05060000000002000200b7000000400f00000000"
+  And a website name "test_dweb"
+  When the website is deployed as identity 1
+  Then the website "test_dweb" of owner identity 1 has 1 versions and serves version 1
+  Then rolling back the website "test_dweb" to version 2 fails with "Nonexistent version: 2."
+
 @web
 Scenario: List two websites
   Given a website zip source "504b0304140300000800814df9560f5bea312f000000300000000a000000696e6465782e68746d6cb3c930b4f348cdc9c95708f1f154b4d10772b96c3273d3158a8b926d9572f2d3f3f5ca53930a94ec6cf481a2765c00504b03041403000008004a78f856308c5073c20e0000140f0000090000006c6f676f2e7765627045567938d47918ffce6118578331c608e3be652c25c73626e46e28d19226d3312bf7466ce48711c3b8d2a19041ec94bb342b295452d80c2d5993a229cd6e6e42e5d8d9e3d97d9ff7f3bceff77ddfe7f3be7fbccffb7c7ddd5c5c64b700e0ef4ca11ea0da04480300f06204fcade288a7a93c00db02000ee3651126d8ae18a6baf279f9b04df8894cd12612ba352b31fa692fb6aee463d67181c9ddfadad0fc7938e790b26f03f9e848247f90b47589d6595da0bb27f6f59c64fc44e5ea942c52a7e0fa1427df89936cac4df4615fe185f7698e119194fed2f53210e0518a06e02968eb59d5e4a54212b9b192d21d133790d0dc853859f120ab6c521bd2d46873b3e5cad2da06361d4391d26292d4f53f3497daf519f7a3276577662096620efc7ec8ebf9ce0f7edea5f7fcdf7ae1e96c858ad54d46a0f11d3f5ba0decdf31378d7bdafabacac31f8462c82819f9e0ee43d0da7ede96397178f3f8df128baf2fb6272abede877c521ba6eb9badd263edb87c7b7522fed3ea782a134de71aeec41840baac3053c650fd5f004012f5cacc5bd62f397de1fc6dfcef525ba9e27341d4cf47ca6c535860a1610c8a5d137e65c8ee22231717bc0b6e9049775cdd308bbb4041be3dd211a73b6b559b3f0f1e0da2c2fd296a73a836f9e5f2e92b4976beff06f0c188163f53bba027016c9f648b08a3bcbec020816b2bc1ed6de590c62b766fb5600cdce86b9980e9a92ea29ed0bb2182e18975995b8168d8560f1aa6733f2e0e7b84e283908f094a4c830dc93bc3907e88564d15962c6b0ddd92fa103b965e36a181ecc4917c000d8790a093d066df8f6c14469aabb7ccdde99d51f5a83c6349bedef80742782c00e095028c6f5f3f8eaf20febc2bc857b5dbb8325f0b7779864fc6a327c6d79ead9667b2cef9417cec5c1079649fc9149060cdf810dced5f6a4866bdfbe1c5ab2197e321a0fcfe77632cf4f3e9fdc8c29ada4dd6a3e578b9482a38105e4594165f8f64cbe64ecca5e48a6a4a49360f5dd832332e7b26f183290035523d719ee9e3feae02ba026c59748c5701bccb4cac0da35a66d0a122bad34ce30cd593c57bbcfaa8236c67dc6fde660059c0cc06863ee7231a3fd512aaa51955e2c47e600d6c3c900558bfc342316c0d62e9e27cd908c0119313acbd825b3e72a8a1a926f8471e4e4a402594fe8debc3a28baff592be347b42e87e3b1fdc918fd0dc18a9bb3e0df5ce6bca8a28836415d21030c10401f264eac6239f9083480531fcff66ff6be79bed1eb7ca870efa82883ca4044304e7be45712a97868cbb84abc04adeff59a780005d1f99fb6257efe7ca97dcd4418da255505d2c8dab07b976e2250ae18c29cdc9aeb413c6db99a79c41450f921b161f66ff0dbda221cf4d521277ed257965607cd0455ffe416940aab93b0dfd51d4a58cc9237038a290f9cc9b7312ecad24ae25e6a9ce3911bf98ffc0a8f32bd1696fac69eaca3dd1080bc021b259b22a541d9a36d73b55a5add71eff6b83899981b1ca8b1d94bc092071d9752cb25c4eba29fb8e50d1c2ad7461fe1f02a0d1951c32d76b7abbf2346705e1a5dac8bc83e3cae038a2c500f8980a3d07855105a4dd0210275a7c1243545dd1cac2262eec81aaa57789c5c712ed54956ceacf5a875f79891841c060d39813853d40e234dbada9cc501ffed8fa768907c1bd2f2bb20eb033f9458f34274e51ca7e26238847cc33e50fff8bc0da3f1522659b2c0893d4d21d62baa1686ecaf50cc97827f21d3c274225bcde4bbceb784353a23090f5329a754fddd3a6bf35c2a748828053826654cee988b2cc6901c42db7dcb56032f9feaad5c4e941e5028be6e54edac65ac2545e4533aeaf5ee0fc1a34c2e75cb43cc3418825820eabf46c5e81d3450d6f1b4bd483e8a41e297c29f725fc34b72f5a27b26700269a8e3019e454ec1faa349462e2dac14efd69f258911c78dc3f2de470b1c39fa5ed1a4c5b51bd1ad7cac320672da464c85b47d58352ee5d5a53c9114b33c0dfad6d5360ca014595f9b6cef9370514a115b2a09feddbcbc88af0f2f473af4f45520f247108e060a90f680829a55114e8e88b082f393a68ff5a22fd3ddb6c640f850b645c3fc470fcdb257cb570e07bf9e2c8a03002c3bba35790f4c987e7f03eea4cd70d2a62cd90748dda4160149ba5b50f4190367a3c952dbeed2e23cff29fafcc8368d9d96a1037aeb245efb596942f410fc49570b1759d17e95c991ca872821f21fe9b1f63167b88c466e7cbcfd96137fdc4e8e7fc7df8b2baef5295c270169e3e88b58d274738bd1af0f4215f14390d311f82bf6fba617d3a5152e2e6ca734af8539bfab069b7359885d574e0d032bcec0ba37c90a5be1d1e44df0b4cb74276388a293308a8ee5cd7a9801ddad245ae0eccebe284536310f2c69ef5b62bd697bcb07580231ed3ec95d7fd8ed7273112e533a530b49596a06cd4d4dd6fb8e9caccdb48b58af3ab398616a5316d0be9af15ea71da62b8d418992a84425b5ca40869d978a148be82ea5e8d085ccacbbd0ddd3d0b25c684692916c44591facf34df7db11b4a5c80214d0ee450add9066e9e6db65bcdfbfdb2025245f48ae089336d24de50cdccb274bd6971f1b1d7ff6dbc6eab2964b3aa75cef40f7ebcb89a28ce04f96ea6c1804777799f2347093044d071b3b9ffdf2528d77c840f3b5a141ee17e3f7e89e0786d9f9709082024f3b001cc8491f289c5f3bf7bbe50f333a27356d1e37198990216dcbb85729bf5d0a8fb6a151d0e9998e83908b011c12e106bc4ceb351a040b715fe67396a759f2b89446c227bf9edd1710e5e2bd8490ef001ccde0e3560c8283e6a36608055525afabf28e688c63b2fd0b6187dbbe384c5826aabfe9a82a02f40f85a3bf136edf3e189478474da938573b6fc0f128834a415c9df5fdb4dfb49c8954a8fa464a7ceacccc09357e255e03f9c20a2c1b6e73c121faeb0d55233df987f7e634edb300166976a30b55b86f6861e174cedee32f060a005c3dcd33e500d13368e6c7c0c8bbc7d2ad8924c0881e0ab1fe2199c2e391e108f7688b1c09e9f4c7f0da8ccbb4c03e22a675e4d4a870e8ede0c9b82f474f54989045443e8909cee76a59c662a96a1f050dd9920dbfd4a904c8307ca24fbf9d71723e09b2dd6cae2b030e503a9bcd4fbc1eb3d05a73cbb75726d2acbfbd991501b3b4d625030050224f05bf70b76139f3d3ecec432f5717488fc658437dad0359be8d8ad8778ed6de0058e076bc2e39bcf3c35b61d1cebb5f346cef4555dfea6ca538c662d0a730fcc251296e1763c2fa49d9e985aa62c1ed1a86a574000b34cad72cc2c1b8d99960d6c178415a439e69c3fdbd6a65f6f41706b1d75d9672d081bb8f4988d795d1bc37227172ac77c7b5208749ae2b12c8d7d583f8b16bfef03fe4b63c385cfe597d91f54bc84d75f1f54203da6f09efa44e4f0a0f151fbebbaf2cfefa37bbcdc0f81c416f76e4b337f9fe91fe93f3b9ca5591301a595bd4a171b348d76073be216cb6bf21f1c7cff0e902265f72ffd6e0c89d830dbb93ee94223b56e6644c617857373e24aa04fbad0266845d4261f1faea460cfb9be082fdbfc2518dc892057dd90e8339b5b6ad37d97deee71908a7e42e784958eea7be95afc29f7b2b335fe0249441923b01e0251350e38fda1db6ad8e8f355c0e5b55fa5616efab9d8ef269ea3f79893dbcc6bba3b92cf40cd4f7f2c921e8f14d780a6c84dc0ed8e383377695150f5d4eaca71842dc8bb0a57d1521ccae64fb84b0af3331f687f212062b08ca0a7a7c0f941416356e249940e43cba6b9ff48a8b55343a25bb1c6f444b60de56120a62d71ee42a5473e3599ce4e3edaad8468bfaae2c519a6c2be7ddb1bd5eae7a74113b75e4f2e86205b67d2a4f7399ae8363e9d25970fe4ffb93761745eddac8f8799c083d6a3b94c9f251a0d3cb5e1a5e9955dbfcee4ee40add2ffc9a15953e3b050c3b980f2f05287fa4e11238064199689f81593f9fa818da2b07d283df349303479142a60fec0c3a5db523254041453dc6323d29b837bd7e7eddf66cb0649f121753659cd48e90831e9243501fdecaacea9b13d8c6d5451a84e46b9a6f0ee5660c4d9a49633110798b1e064a79cc2c9e576b53d9a355904605c604d3959c137b23decc74170e553f935296ed791d74045b7d55a3f4b4c9e6f4d259191e423734f30400dedbefbeca8c5c11febceef35896ed70c1273c4c870add08f18d5b13fcb68e7bc5dcf3eb3b72b0b2c5ee7b731f13d7e21382e24d0b53b3f67fa9e9e0776c47e9773c0b20fc3451bd921cfd03372dac4b2a6f4af88c2e14acc73fd058dc6a664ce4cfdeb938daa2d04b57113d2d7497779668b0b132cfcde96ff876fee5065e4f694aee211c2b9d62d0643edc55f90809231a1b5f2a9dd73f7c38e92debbdd24a8d1995ddbc1c7f8c41a683e38d06c1691fcf69438b592f3e156d9d88549b2e5e6f096c72254ccd5310706a11f8897e3248b24501483c128546a8742f7c3abb76e2fb4276532710e9a54d2b11795d6e1073a2befcc4b3d22f7d87bf5686c88bfa1dad746186e4b76e53ec2418584c7350e2eb7f186deb8a0d95fc31593badae527ef9c9f3c00f0513a680a5b08c3cd27949e5f993419b8855930d50b3e3ce6973e568bb038fb707feb2182f5b9a1500f55c70ed3b42af657d0509fb8f62e887ca8af3a1f5267b733b73b8d5c99a90282d5e9742f7e431460b3ad5aea9ce6b8f867a5528521a8d1f513a5331c195922df5c9215782281b5e7712f47b4b2a5e00c4c3f986657b74dc5c6831bca84619775cf3b842b3d936dcebd909e0e914b0bd5b666e6b18b4358b0a6e10e4b63dc2502ac1373684d987cc5dc28d898dfe0c671802c67280bcc3fc64cdbb875b80310b96865e09f107ae464e80ff55dfa3236d4421030e08e79426e7f625a24ec0f5b14459cb1e9ea51590068d3be2ba523bbfa214bb276ca632053fd7abc4d5035f6bf1e72f202cdb2e97134275055339632449c799294d04ba453150582214c5704f7620c4ac08458404042b9ab3887bd219e59424cecb0a84219e2f21748afa3cae4fc36f9f0b4cd1dda6ede3c6c0b3fb45f983ac55cc5956607e54d0394b24adf2f4c6eacbc0999255a1a5de3a4ef0d0feb8fe8d097f9873809b4b2b00c039fefbe300b8b9190329f10b051461080003e06f1060127fdb0362a8fdeb078b61f86f0d5c0c25f8fff5df6720ffb6c7c13f126f090722c4ff160540be24408a331696240b58c13f3e4ccc0038ffc649161630ce3f8c9b9be20e9c7f180f8a81f8cfff47fe04504b01023f03140300000800814df9560f5bea312f000000300000000a0024000000000000002080a48100000000696e6465782e68746d6c0a0020000000000001001800003d2c12febed901003d2c12febed901003d2c12febed901504b01023f031403000008004a78f856308c5073c20e0000140f0000090024000000000000002080a481570000006c6f676f2e776562700a002000000000000100180000060e5f61bed90100060e5f61bed90100060e5f61bed901504b05060000000002000200b7000000400f00000000"
@@ -1,11 +1,14 @@
+use async_channel::unbounded;
 use cucumber::gherkin::Step;
 use cucumber::{given, then, when, World as _};
 use many_identity::testing::identity;
 use many_identity::Address;
 use many_modules::kvstore::{GetArgs, KvStoreModuleBackend};
 use many_modules::web::{
-    DeployArgs, ListArgs, UpdateArgs, WebCommandsModuleBackend, WebModuleBackend,
+    DeployArgs, ListArgs, RollbackArgs, UpdateArgs, VersionsArgs, WebCommandsModuleBackend,
+    WebModuleBackend,
 };
+use many_protocol::{context::Context, RequestMessage};
 use many_types::web::{WebDeploymentFilter, WebDeploymentSource};
 use many_types::Memo;
 use many_web::module::{InitialStateJson, WebModuleImpl};
@@ -60,9 +63,7 @@ fn given_site_description(w: &mut World, description: String) {
 #[given(expr = "a website zip source {string}")]
 fn given_site_source(w: &mut World, source: String) {
     let b = hex::decode(source).expect("Unable to decode hex string");
-    match &mut w.source {
-        WebDeploymentSource::Archive(bytes) => *bytes = b.into(),
-    }
+    w.source = WebDeploymentSource::Archive(b.into());
 }
 
 #[given(expr = "a website memo {string}")]
@@ -75,6 +76,19 @@ fn given_site_domain(w: &mut World, domain: String) {
     w.domain = Some(domain);
 }
 
+#[given(expr = "a web server storing compressed assets")]
+fn given_compressed_assets(w: &mut World) {
+    w.module = WebModuleImpl::new(
+        InitialStateJson::default().with_compressed_assets(true),
+        Builder::new()
+            .prefix("many-web")
+            .tempdir()
+            .expect("Unable to create temporary directory"),
+        false,
+    )
+    .expect("Unable to create web module");
+}
+
 #[given(expr = "a website owner identity {int}")]
 fn given_site_owner(w: &mut World, seed: u32) {
     w.owner = Some(identity(seed));
@@ -128,6 +142,40 @@ fn when_remove(w: &mut World, site_name: String, seed: u32) {
         .expect("Website removal failed");
 }
 
+#[when(expr = "the website {string} is rolled back to version {int} as identity {int}")]
+fn when_rollback(w: &mut World, site_name: String, version: u64, seed: u32) {
+    w.module
+        .rollback(
+            &identity(seed),
+            RollbackArgs {
+                owner: w.owner,
+                site_name,
+                version,
+                memo: w.memo.clone(),
+            },
+        )
+        .expect("Website rollback failed");
+}
+
+#[allow(clippy::needless_pass_by_ref_mut)]
+#[then(
+    expr = "the website {string} of owner identity {int} has {int} versions and serves version {int}"
+)]
+fn then_versions(w: &mut World, site_name: String, seed: u32, count: usize, current: u64) {
+    let ret = w
+        .module
+        .versions(
+            &identity(0),
+            VersionsArgs {
+                owner: Some(identity(seed)),
+                site_name,
+            },
+        )
+        .expect("Website versions failed");
+    assert_eq!(ret.versions.len(), count);
+    assert_eq!(ret.current, current);
+}
+
 #[allow(clippy::needless_pass_by_ref_mut)]
 #[then(expr = "the {string} value of website {string} for owner identity {int} is")]
 fn then_live(w: &mut World, step: &Step, file: String, site_name: String, seed: u32) {
@@ -141,6 +189,7 @@ fn then_live(w: &mut World, step: &Step, file: String, site_name: String, seed:
                     .into_bytes()
                     .into(),
             },
+            Context::new(RequestMessage::default(), unbounded().0),
         )
         .expect("Website not found");
     assert_eq!(
@@ -161,6 +210,7 @@ fn then_empty(w: &mut World, file: String, site_name: String, seed: u32) {
                     .into_bytes()
                     .into(),
             },
+            Context::new(RequestMessage::default(), unbounded().0),
         )
         .expect("Website not found");
     assert_eq!(ret.value, None);
@@ -314,6 +364,22 @@ fn then_remove_failed(w: &mut World, error: String) {
     ));
 }
 
+#[then(expr = "rolling back the website {string} to version {int} fails with {string}")]
+fn then_rollback_failed(w: &mut World, site_name: String, version: u64, error: String) {
+    assert!(matches!(
+        w.module.rollback(
+            &identity(1),
+            RollbackArgs {
+                owner: w.owner,
+                site_name,
+                version,
+                memo: w.memo.clone(),
+            }
+        ),
+        Err(e) if e.to_string() == error
+    ));
+}
+
 #[tokio::main]
 async fn main() {
     // Support both Cargo and Bazel paths
@@ -0,0 +1,57 @@
+//! Shell completions for the CLI.
+//!
+//! On top of the static completions generated by clap, the bash, zsh and fish
+//! scripts complete the method of `many message` with the endpoints of the
+//! server in `$MANY_SERVER`, using `many endpoints`.
+use clap::Command;
+use clap_complete::Shell;
+use std::io::Write;
+
+const BASH_METHODS: &str = r#"
+_many_with_methods() {
+    if [[ "${COMP_WORDS[1]}" == "message" && -n "$MANY_SERVER" && "${COMP_WORDS[COMP_CWORD]}" != -* ]]; then
+        local methods
+        methods="$(many endpoints "$MANY_SERVER" 2>/dev/null)"
+        if [[ -n "$methods" ]]; then
+            COMPREPLY=( $(compgen -W "$methods" -- "${COMP_WORDS[COMP_CWORD]}") )
+            return 0
+        fi
+    fi
+    _many "$@"
+}
+
+complete -F _many_with_methods -o bashdefault -o default many
+"#;
+
+const ZSH_METHODS: &str = r#"
+_many_with_methods() {
+    if [[ "${words[2]}" == "message" && -n "$MANY_SERVER" && "${words[CURRENT]}" != -* ]]; then
+        local -a methods
+        methods=(${(f)"$(many endpoints "$MANY_SERVER" 2>/dev/null)"})
+        if (( ${#methods} )); then
+            compadd -a methods
+            return
+        fi
+    fi
+    _many "$@"
+}
+
+compdef _many_with_methods many
+"#;
+
+const FISH_METHODS: &str = r#"
+complete -c many -n "__fish_seen_subcommand_from message; and set -q MANY_SERVER" -f -a "(many endpoints \$MANY_SERVER 2>/dev/null)"
+"#;
+
+/// Write the completion script of `cmd` for `shell`.
+pub fn generate(shell: Shell, cmd: &mut Command, out: &mut dyn Write) -> std::io::Result<()> {
+    let name = cmd.get_name().to_string();
+    clap_complete::generate(shell, cmd, name, out);
+
+    match shell {
+        Shell::Bash => out.write_all(BASH_METHODS.as_bytes()),
+        Shell::Zsh => out.write_all(ZSH_METHODS.as_bytes()),
+        Shell::Fish => out.write_all(FISH_METHODS.as_bytes()),
+        _ => Ok(()),
+    }
+}
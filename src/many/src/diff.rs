@@ -0,0 +1,56 @@
+//! Structural diffs of envelopes, e.g. to compare the responses of several
+//! validators to the same request.
+//!
+//! Only the payloads are compared; signatures and headers differ between
+//! signers anyway.
+use coset::{CborSerializable, CoseSign1, TaggedCborSerializable};
+use many_types::cbor::diff::{diff, Difference};
+use many_types::cbor::CborAny;
+
+/// The key of the argument of requests and of the result of responses.
+const DATA_KEY: i64 = 4;
+
+/// Decode the data of a message, which is a CBOR encoded byte string, so
+/// that it is compared structurally.
+fn expand_data(message: CborAny) -> CborAny {
+    match message {
+        CborAny::Tagged(tag, inner) => CborAny::Tagged(tag, Box::new(expand_data(*inner))),
+        CborAny::Map(mut map) => {
+            let data = match map.get(&CborAny::Int(DATA_KEY)) {
+                Some(CborAny::Bytes(bytes)) => minicbor::decode::<CborAny>(bytes).ok(),
+                _ => None,
+            };
+            if let Some(data) = data {
+                map.insert(CborAny::Int(DATA_KEY), data);
+            }
+            CborAny::Map(map)
+        }
+        other => other,
+    }
+}
+
+/// Read an envelope, as hexadecimal or from a file (binary or hexadecimal),
+/// and decode its payload. Inputs which aren't envelopes are decoded as CBOR
+/// directly, e.g. a message on its own.
+pub fn read(arg: &str) -> Result<CborAny, String> {
+    let bytes = match hex::decode(arg.trim()) {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            let content = std::fs::read(arg).map_err(|e| format!("Could not read {arg}: {e}"))?;
+            hex::decode(String::from_utf8_lossy(&content).trim()).unwrap_or(content)
+        }
+    };
+
+    let payload = CoseSign1::from_tagged_slice(&bytes)
+        .or_else(|_| CoseSign1::from_slice(&bytes))
+        .ok()
+        .and_then(|envelope| envelope.payload)
+        .unwrap_or(bytes);
+    let message = minicbor::decode(&payload).map_err(|e| format!("Invalid CBOR: {e}"))?;
+    Ok(expand_data(message))
+}
+
+/// The differences between the payloads of two envelopes.
+pub fn envelopes(before: &str, after: &str) -> Result<Vec<Difference>, String> {
+    Ok(diff(&read(before)?, &read(after)?))
+}
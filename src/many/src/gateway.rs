@@ -0,0 +1,229 @@
+//! A gateway forwarding requests to upstream MANY servers, so a single public
+//! endpoint can front several internal module servers.
+//!
+//! Envelopes are forwarded as they were received; the gateway only reads the
+//! destination and method of a request to route it, and never signs it again.
+//! Requests which don't match a route are executed by the local server, whose
+//! status aggregates the status of the upstreams.
+use async_trait::async_trait;
+use coset::CoseSign1;
+use many_client::client::send_envelope;
+use many_client::ManyClient;
+use many_error::ManyError;
+use many_identity::{Address, AnonymousIdentity, Identity};
+use many_modules::base;
+use many_protocol::{RequestMessageRef, ResponseMessage};
+use many_server::transport::LowLevelManyRequestHandler;
+use many_server::ManyServer;
+use many_types::attributes::AttributeSet;
+use many_types::cbor::CborAny;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Debug, Formatter};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use tracing::{debug, info, warn};
+use url::Url;
+
+/// What a route matches in a request.
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum RouteKey {
+    /// Requests sent to this address.
+    Destination(Address),
+
+    /// Requests of a method namespace, e.g. `ledger` for `ledger.send`.
+    Namespace(String),
+}
+
+impl FromStr for RouteKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(address) = Address::from_str(s) {
+            Ok(Self::Destination(address))
+        } else if !s.is_empty() && !s.contains('.') {
+            Ok(Self::Namespace(s.to_string()))
+        } else {
+            Err(format!(
+                "Invalid route '{s}', expected an address or a namespace."
+            ))
+        }
+    }
+}
+
+/// Parse a `KEY=URL` route, where the key is a destination address or a
+/// method namespace.
+pub fn parse_route(s: &str) -> Result<(RouteKey, Url), String> {
+    let (key, url) = s
+        .split_once('=')
+        .ok_or_else(|| format!("Invalid route '{s}', expected KEY=URL."))?;
+    Ok((key.parse()?, Url::parse(url).map_err(|e| e.to_string())?))
+}
+
+/// Maps destination addresses and method namespaces to upstream servers.
+/// Destinations take precedence over namespaces.
+#[derive(Clone, Debug, Default)]
+pub struct RoutingTable {
+    routes: BTreeMap<RouteKey, Url>,
+}
+
+impl RoutingTable {
+    pub fn new(routes: impl IntoIterator<Item = (RouteKey, Url)>) -> Self {
+        Self {
+            routes: routes.into_iter().collect(),
+        }
+    }
+
+    /// The distinct upstream servers of this table.
+    pub fn upstreams(&self) -> BTreeSet<&Url> {
+        self.routes.values().collect()
+    }
+
+    pub fn upstream(&self, to: &Address, method: &str) -> Option<&Url> {
+        let namespace = method.split_once('.').map_or(method, |(ns, _)| ns);
+        self.routes
+            .get(&RouteKey::Destination(*to))
+            .or_else(|| self.routes.get(&RouteKey::Namespace(namespace.to_string())))
+    }
+}
+
+/// The transport handler of the gateway.
+#[derive(Debug)]
+pub struct Gateway {
+    server: Arc<Mutex<ManyServer>>,
+    routes: RoutingTable,
+}
+
+impl Gateway {
+    pub fn new(server: Arc<Mutex<ManyServer>>, routes: RoutingTable) -> Self {
+        Self { server, routes }
+    }
+}
+
+#[async_trait]
+impl LowLevelManyRequestHandler for Gateway {
+    async fn execute(&self, envelope: CoseSign1) -> Result<CoseSign1, String> {
+        let upstream = RequestMessageRef::try_from(&envelope)
+            .ok()
+            .and_then(|message| self.routes.upstream(&message.to, &message.method).cloned());
+
+        match upstream {
+            Some(url) => {
+                debug!("Forwarding request to {url}");
+                send_envelope(url.clone(), envelope).await.map_err(|e| {
+                    warn!("Upstream {url} failed: {e}");
+                    e.to_string()
+                })
+            }
+            None => self.server.execute(envelope).await,
+        }
+    }
+}
+
+/// The fallback module of the local server of a gateway. It reports the
+/// endpoints and attributes of all upstreams, as they were when the gateway
+/// started.
+pub struct UpstreamStatus<I: Identity> {
+    name: String,
+    identity: I,
+    attributes: AttributeSet,
+    endpoints: BTreeSet<String>,
+    extras: BTreeMap<String, CborAny>,
+}
+
+impl<I: Identity> UpstreamStatus<I> {
+    /// Fetch the status and endpoints of each upstream. Upstreams which cannot
+    /// be reached are left out of the status, but requests are still
+    /// forwarded to them. The identity of each upstream is listed in the
+    /// extras under its index, e.g. `upstream.0`, as their URLs are internal.
+    pub async fn fetch(name: String, identity: I, routes: &RoutingTable) -> Self {
+        let mut attributes = AttributeSet::new();
+        let mut endpoints = BTreeSet::new();
+        let mut extras = BTreeMap::new();
+
+        for (i, url) in routes.upstreams().into_iter().enumerate() {
+            info!("Upstream {i} is {url}");
+            let client = ManyClient::new(url.clone(), Address::anonymous(), AnonymousIdentity);
+            let client = match client {
+                Ok(client) => client,
+                Err(e) => {
+                    warn!("Could not create a client for upstream {url}: {e}");
+                    continue;
+                }
+            };
+            let status = match client.status().await {
+                Ok(status) => status,
+                Err(e) => {
+                    warn!("Could not get the status of upstream {url}: {e}");
+                    continue;
+                }
+            };
+            match client.call_("endpoints", ()).await.and_then(|bytes| {
+                minicbor::decode::<base::Endpoints>(&bytes)
+                    .map_err(ManyError::deserialization_error)
+            }) {
+                Ok(e) => endpoints.extend(e.0),
+                Err(e) => warn!("Could not get the endpoints of upstream {url}: {e}"),
+            }
+
+            attributes.extend(status.attributes);
+            extras.insert(
+                format!("upstream.{i}"),
+                CborAny::String(status.identity.to_string()),
+            );
+        }
+
+        Self {
+            name,
+            identity,
+            attributes,
+            endpoints,
+            extras,
+        }
+    }
+}
+
+impl<I: Identity> Debug for UpstreamStatus<I> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UpstreamStatus")
+            .field("identity", &self.identity.address())
+            .field("endpoints", &self.endpoints)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl<I: Identity + Send + Sync> LowLevelManyRequestHandler for UpstreamStatus<I> {
+    /// Only requests for an upstream endpoint which doesn't match any route
+    /// end up here.
+    async fn execute(&self, envelope: CoseSign1) -> Result<CoseSign1, String> {
+        let id = RequestMessageRef::try_from(&envelope)
+            .ok()
+            .and_then(|message| message.id);
+        let response = ResponseMessage::error(
+            self.identity.address(),
+            id,
+            ManyError::could_not_route_message(),
+        );
+        many_protocol::encode_cose_sign1_from_response(response, &self.identity)
+            .map_err(|e| e.to_string())
+    }
+}
+
+impl<I: Identity> base::BaseModuleBackend for UpstreamStatus<I> {
+    fn endpoints(&self) -> Result<base::Endpoints, ManyError> {
+        Ok(base::Endpoints(self.endpoints.clone()))
+    }
+
+    fn status(&self) -> Result<base::Status, ManyError> {
+        let mut builder = base::StatusBuilder::default();
+        builder
+            .name(self.name.clone())
+            .version(1)
+            .identity(self.identity.address())
+            .attributes(self.attributes.clone())
+            .extras(self.extras.clone());
+        builder
+            .build()
+            .map_err(|e| ManyError::unknown(e.to_string()))
+    }
+}
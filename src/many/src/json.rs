@@ -0,0 +1,62 @@
+//! Arguments of `many message` written in JSON instead of CBOR diagnostic
+//! notation. The JSON value is fit to the CDDL rule of the argument of the
+//! method, so fields can be named and bytes written in hex, and it is
+//! validated before being sent.
+use many_client::ManyClient;
+use many_identity::{Address, AnonymousIdentity};
+use many_modules::base;
+use many_modules::cddl::{self, CddlRule};
+use many_types::cbor::CborAny;
+use tracing::debug;
+use url::Url;
+
+/// Find the rule of the argument of a method. The server describes its
+/// endpoints if it can, otherwise the rule is guessed from the method name,
+/// e.g. `ledger.SendArgs` for `ledger.send`.
+pub async fn argument_rule(server: Option<&Url>, method: &str) -> Option<&'static CddlRule> {
+    if let Some(server) = server {
+        match describe(server).await {
+            Ok(modules) => {
+                if let Some(descriptor) = modules
+                    .0
+                    .iter()
+                    .flat_map(|m| m.endpoints.iter())
+                    .find(|e| e.name == method)
+                {
+                    return cddl::argument_rule(descriptor);
+                }
+            }
+            Err(e) => debug!("Could not describe the server: {e}"),
+        }
+    }
+
+    let (namespace, name) = method.split_once('.').unwrap_or(("base", method));
+    let expected = format!("{namespace}.{}Args", name.replace('_', ""));
+    cddl::rules().find(|r| r.name.eq_ignore_ascii_case(&expected))
+}
+
+async fn describe(server: &Url) -> Result<base::DescribeReturn, String> {
+    let client = ManyClient::new(server.clone(), Address::anonymous(), AnonymousIdentity)?;
+    let bytes = client
+        .call_("describe", ())
+        .await
+        .map_err(|e| e.to_string())?;
+    minicbor::decode(&bytes).map_err(|e| e.to_string())
+}
+
+/// Convert a JSON argument to CBOR. With a rule, the value is fit to it and,
+/// if `validate` is set, refused when it doesn't match.
+pub fn encode(json: &str, rule: Option<&CddlRule>, validate: bool) -> Result<Vec<u8>, String> {
+    let value: CborAny = serde_json::from_str(json).map_err(|e| format!("Invalid JSON: {e}"))?;
+    let value = match rule {
+        Some(rule) => rule.coerce(value),
+        None => value,
+    };
+    let bytes = minicbor::to_vec(value).map_err(|e| e.to_string())?;
+
+    if let (Some(rule), true) = (rule, validate) {
+        rule.validate(&bytes)
+            .map_err(|e| format!("The argument does not match {}: {e}", rule.name))?;
+    }
+    Ok(bytes)
+}
@@ -1,10 +1,12 @@
 use anyhow::anyhow;
 use async_recursion::async_recursion;
 use base64::{engine::general_purpose, Engine as _};
-use clap::{ArgGroup, Parser};
+use clap::{ArgGroup, CommandFactory, Parser};
 use coset::{CborSerializable, CoseSign1};
+use gateway::{parse_route, Gateway, RouteKey, RoutingTable, UpstreamStatus};
 use many_cli_helpers::error::ClientServerError;
 use many_client::ManyClient;
+use many_error::ManyError;
 use many_identity::verifiers::AnonymousVerifier;
 use many_identity::{Address, AnonymousIdentity, Identity};
 use many_identity_dsa::{CoseKeyIdentity, CoseKeyVerifier};
@@ -13,13 +15,16 @@ use many_identity_webauthn::WebAuthnIdentity;
 use many_mock::{parse_mockfile, server::ManyMockServer, MockEntries};
 use many_modules::r#async::attributes::AsyncAttribute;
 use many_modules::r#async::{StatusArgs, StatusReturn};
-use many_modules::{idstore, ledger};
+use many_modules::{base, cddl, idstore, ledger};
 use many_protocol::{
     encode_cose_sign1_from_request, ManyUrl, RequestMessage, RequestMessageBuilder, ResponseMessage,
 };
+use many_server::server::FallbackStatusPolicy;
 use many_server::transport::http::HttpServer;
 use many_server::ManyServer;
-use many_types::{attributes::Attribute, Timestamp};
+use many_types::{attributes::AttributeSet, Timestamp, PROOF, SIMULATE};
+use qrcode::render::unicode::Dense1x2;
+use qrcode::QrCode;
 use std::convert::TryFrom;
 use std::io::{stderr, IsTerminal};
 use std::net::SocketAddr;
@@ -27,9 +32,16 @@ use std::path::PathBuf;
 use std::process;
 use std::sync::Arc;
 use std::time::{Duration, SystemTime};
-use tracing::{error, info, trace};
+use tracing::{error, info, trace, warn};
 use url::Url;
 
+mod completions;
+mod diff;
+mod gateway;
+mod json;
+mod signerd;
+mod vanity;
+
 #[derive(Parser)]
 struct Opts {
     #[clap(flatten)]
@@ -56,21 +68,91 @@ enum SubCommand {
     Message(Box<MessageOpt>),
 
     /// Starts a base server that can also be used for reverse proxying
-    /// to another MANY server.
+    /// to other MANY servers.
     Server(ServerOpt),
 
     /// Get the token ID per string of a ledger's token.
     GetTokenId(GetTokenIdOpt),
+
+    /// Print the CDDL definitions of the arguments and returns of all modules.
+    Cddl(CddlOpt),
+
+    /// Starts a local daemon signing request messages for other processes
+    /// with the given key.
+    Signerd(SignerdOpt),
+
+    /// Print the completion script of this CLI for a shell. The bash, zsh
+    /// and fish scripts also complete the methods of `many message` with the
+    /// endpoints of the server in the `MANY_SERVER` environment variable.
+    Completions(CompletionsOpt),
+
+    /// Print the endpoints of a server, one per line.
+    Endpoints(EndpointsOpt),
+
+    /// Decode the payloads of two envelopes and print their structural
+    /// differences, e.g. between the responses of two validators to the same
+    /// request.
+    Diff(DiffOpt),
 }
 
 #[derive(Parser)]
+#[clap(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct IdOpt {
     /// An hexadecimal value to encode, an identity textual format to decode or
     /// a PEM file to read
-    arg: String,
+    #[clap(required = true)]
+    arg: Option<String>,
 
     /// Allow to generate the identity with a specific subresource ID.
     subid: Option<u32>,
+
+    #[clap(subcommand)]
+    command: Option<IdCommand>,
+}
+
+#[derive(Parser)]
+enum IdCommand {
+    /// Print an address as a QR code in the terminal, e.g. for mobile wallets
+    /// to scan.
+    Qr(IdQrOpt),
+
+    /// Generate Ed25519 keys until one has an address matching a prefix or a
+    /// pattern, and output its PEM.
+    Vanity(IdVanityOpt),
+}
+
+#[derive(Parser)]
+struct IdQrOpt {
+    /// The address, in its textual format or as scanned from a QR code.
+    address: String,
+
+    /// Swap the dark and light modules, for terminals with a light
+    /// background.
+    #[clap(long)]
+    invert: bool,
+}
+
+#[derive(Parser)]
+#[clap(group(ArgGroup::new("match").args(&["prefix", "pattern"]).required(true)))]
+struct IdVanityOpt {
+    /// The start of the address, e.g. `magood`. Addresses of keys always
+    /// start with `mae`, `maf`, `mag` or `mah`.
+    #[clap(long)]
+    prefix: Option<String>,
+
+    /// A text the address must contain anywhere after its first three
+    /// characters.
+    #[clap(long)]
+    pattern: Option<String>,
+
+    /// The number of threads generating keys. Defaults to the number of
+    /// CPUs.
+    #[clap(long)]
+    threads: Option<usize>,
+
+    /// Write the PEM to this file instead of the standard output.
+    #[clap(long, short)]
+    output: Option<PathBuf>,
 }
 
 #[derive(Parser)]
@@ -198,11 +280,27 @@ struct MessageOpt {
     /// The content of the message itself (its payload).
     data: Option<String>,
 
+    /// The content of the message, in JSON. Fields can be named and bytes
+    /// written in hex; the value is converted to match the argument of the
+    /// method, which is looked up on the server if there is one.
+    #[clap(long, conflicts_with("data"), requires("method"))]
+    data_json: Option<String>,
+
+    /// Send the JSON content even if it does not match the argument of the
+    /// method.
+    #[clap(long, requires("data-json"))]
+    skip_validation: bool,
+
     /// Request a proof of the value. This may cause an error if the server
     /// does not support proofs, and might not work on all endpoints. Consult
     /// the specification for more information.
     #[clap(long)]
     proof: Option<bool>,
+
+    /// Only simulate the command; the server shows its result and the events
+    /// it would emit, without committing anything.
+    #[clap(long)]
+    simulate: bool,
 }
 
 #[derive(Parser)]
@@ -223,6 +321,14 @@ struct ServerOpt {
     /// Default is mockfile.toml, gives an error if the file does not exist
     #[clap(long, short, value_parser = parse_mockfile)]
     mockfile: Option<MockEntries>,
+
+    /// Forward requests to an upstream MANY server, as `KEY=URL`. The key
+    /// is either a destination address, matching requests sent to it, or a
+    /// method namespace (e.g. `ledger`). Destinations are matched first.
+    /// Envelopes are forwarded unchanged. Multiple occurences of this
+    /// argument can be given.
+    #[clap(long = "route", value_parser = parse_route, conflicts_with = "mockfile")]
+    routes: Vec<(RouteKey, Url)>,
 }
 
 #[derive(Parser)]
@@ -235,6 +341,102 @@ struct GetTokenIdOpt {
     symbol: String,
 }
 
+#[derive(Parser)]
+struct CompletionsOpt {
+    /// The shell to generate the script for.
+    #[clap(arg_enum)]
+    shell: clap_complete::Shell,
+}
+
+#[derive(Parser)]
+struct EndpointsOpt {
+    /// The server to list the endpoints of.
+    server: url::Url,
+}
+
+#[derive(Parser)]
+struct DiffOpt {
+    /// An envelope in hexadecimal, or a file containing one in binary or
+    /// hexadecimal.
+    before: String,
+
+    /// The envelope to compare with, in the same format.
+    after: String,
+}
+
+#[derive(Parser)]
+struct CddlOpt {
+    /// Only print the rules of this namespace (e.g. `ledger`). The prelude
+    /// rules are always printed.
+    #[clap(long)]
+    namespace: Option<String>,
+}
+
+/// Open a user session on an HSM and return its identity. This prompts for
+/// the user PIN.
+fn hsm_identity(module: PathBuf, slot: u64, keyid: String) -> HsmIdentity {
+    trace!("Getting user PIN");
+    let pin = rpassword::prompt_password("Please enter the HSM user PIN: ")
+        .expect("I/O error when reading HSM PIN");
+    let keyid = hex::decode(keyid).expect("Failed to decode keyid to hex");
+
+    {
+        let mut hsm = Hsm::get_instance().expect("HSM mutex poisoned");
+        hsm.init(module, keyid)
+            .expect("Failed to initialize HSM module");
+
+        // The session will stay open until the application terminates
+        hsm.open_session(slot, HsmSessionType::RO, Some(HsmUserType::User), Some(pin))
+            .expect("Failed to open HSM session");
+    }
+
+    // Only ECDSA is supported at the moment. It should be easy to add support for
+    // new EC mechanisms.
+    HsmIdentity::new(HsmMechanismType::ECDSA).expect("Unable to create CoseKeyIdentity from HSM")
+}
+
+#[derive(Parser)]
+#[clap(
+    group(
+        ArgGroup::new("hsm")
+            .multiple(true)
+            .args(&["module", "slot", "keyid"])
+            .requires_all(&["module", "slot", "keyid"])
+    ),
+    group(
+        ArgGroup::new("key")
+            .args(&["pem", "module"])
+            .required(true)
+    )
+)]
+struct SignerdOpt {
+    /// The loopback address and port to listen to.
+    #[clap(long, short, default_value = "127.0.0.1:8100")]
+    addr: SocketAddr,
+
+    /// A file with the token clients must send as an `Authorization: Bearer`
+    /// header. It must only be accessible by its owner; if it does not exist,
+    /// it is created with a random token.
+    #[clap(long)]
+    token_file: PathBuf,
+
+    /// A pem file with the key to sign with.
+    #[clap(long)]
+    pem: Option<PathBuf>,
+
+    /// HSM PKCS#11 module path
+    #[clap(long, conflicts_with("pem"))]
+    module: Option<PathBuf>,
+
+    /// HSM PKCS#11 slot ID
+    #[clap(long, conflicts_with("pem"))]
+    slot: Option<u64>,
+
+    /// HSM PKCS#11 key ID
+    #[clap(long, conflicts_with("pem"))]
+    keyid: Option<String>,
+}
+
 #[async_recursion(?Send)]
 async fn show_response<'a>(
     response: &'a ResponseMessage,
@@ -299,6 +501,19 @@ async fn show_response<'a>(
             }
         }
     } else {
+        for warning in response.warnings()? {
+            warn!("{warning}");
+        }
+        if let Some(events) = response.simulated_events() {
+            info!("Simulated; nothing was committed. Events:");
+            for event in events {
+                let bytes = minicbor::to_vec(event).map_err(|e| anyhow!(e))?;
+                info!(
+                    "{}",
+                    cbor_diag::parse_bytes(&bytes).unwrap().to_diag_pretty()
+                );
+            }
+        }
         println!(
             "{}",
             cbor_diag::parse_bytes(&payload).unwrap().to_diag_pretty()
@@ -308,6 +523,13 @@ async fn show_response<'a>(
     Ok(())
 }
 
+fn request_attributes(proof: bool, simulate: bool) -> AttributeSet {
+    [(proof, PROOF), (simulate, SIMULATE)]
+        .into_iter()
+        .filter_map(|(requested, attribute)| requested.then_some(attribute))
+        .collect()
+}
+
 #[allow(clippy::too_many_arguments)]
 async fn message(
     s: Url,
@@ -317,7 +539,7 @@ async fn message(
     data: Vec<u8>,
     timestamp: Option<SystemTime>,
     r#async: bool,
-    proof: bool,
+    attributes: AttributeSet,
 ) -> Result<(), ClientServerError> {
     let address = key.address();
     let client = ManyClient::new(s, to, key).unwrap();
@@ -333,15 +555,7 @@ async fn message(
         .method(method)
         .data(data)
         .nonce(nonce.to_vec())
-        .attributes(
-            if proof {
-                vec![Attribute::id(3)]
-            } else {
-                vec![]
-            }
-            .into_iter()
-            .collect(),
-        );
+        .attributes(attributes);
 
     if let Some(ts) = timestamp {
         builder.timestamp(Timestamp::from_system_time(ts)?);
@@ -431,8 +645,62 @@ async fn main() {
         .init();
 
     match subcommand {
+        SubCommand::Id(IdOpt {
+            command: Some(IdCommand::Qr(o)),
+            ..
+        }) => {
+            let address = Address::from_qr(&o.address).unwrap_or_else(|e| {
+                error!("Address did not parse: {e}");
+                process::exit(1);
+            });
+            let (dark, light) = if o.invert {
+                (Dense1x2::Dark, Dense1x2::Light)
+            } else {
+                (Dense1x2::Light, Dense1x2::Dark)
+            };
+            let code = QrCode::new(address.to_qr_string()).expect("Address too long for a QR code");
+            println!(
+                "{}",
+                code.render::<Dense1x2>()
+                    .dark_color(dark)
+                    .light_color(light)
+                    .build()
+            );
+            println!("{address}");
+        }
+        SubCommand::Id(IdOpt {
+            command: Some(IdCommand::Vanity(o)),
+            ..
+        }) => {
+            let matcher = match (o.prefix, o.pattern) {
+                (Some(prefix), _) => vanity::Matcher::prefix(&prefix),
+                (None, Some(pattern)) => vanity::Matcher::pattern(&pattern),
+                (None, None) => unreachable!("Argument required by clap"),
+            }
+            .unwrap_or_else(|e| {
+                error!("{e}");
+                process::exit(1);
+            });
+            let threads = o
+                .threads
+                .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, Into::into));
+
+            let id = vanity::search(&matcher, threads.max(1));
+            let pem = id.to_pem().expect("Could not encode the key as PEM");
+            match o.output {
+                Some(path) => {
+                    std::fs::write(&path, pem).expect("Could not write the PEM file");
+                    println!("{}", id.address());
+                }
+                None => {
+                    eprintln!("{}", id.address());
+                    print!("{pem}");
+                }
+            }
+        }
         SubCommand::Id(o) => {
-            if let Ok(data) = hex::decode(&o.arg) {
+            let arg = o.arg.expect("Argument required by clap");
+            if let Ok(data) = hex::decode(&arg) {
                 match Address::try_from(data.as_slice()) {
                     Ok(mut i) => {
                         if let Some(subid) = o.subid {
@@ -447,14 +715,14 @@ async fn main() {
                         std::process::exit(1);
                     }
                 }
-            } else if let Ok(mut i) = Address::try_from(o.arg.clone()) {
+            } else if let Ok(mut i) = Address::try_from(arg.clone()) {
                 if let Some(subid) = o.subid {
                     i = i
                         .with_subresource_id(subid)
                         .expect("Invalid subresource id");
                 }
                 println!("{}", hex::encode(i.to_vec()));
-            } else if let Ok(pem_content) = std::fs::read_to_string(&o.arg) {
+            } else if let Ok(pem_content) = std::fs::read_to_string(&arg) {
                 // Create the identity from the public key hash.
                 let mut i = CoseKeyIdentity::from_pem(pem_content).unwrap().address();
                 if let Some(subid) = o.subid {
@@ -505,34 +773,25 @@ async fn main() {
                     .checked_add(Duration::new(secs, 0))
                     .expect("Invalid timestamp")
             });
-            let data = o
-                .data
-                .map_or(vec![], |d| cbor_diag::parse_diag(d).unwrap().to_bytes());
+            let data = if let Some(json) = o.data_json {
+                let method = o.method.as_deref().unwrap_or_default();
+                let rule = json::argument_rule(o.server.as_ref(), method).await;
+                if rule.is_none() {
+                    warn!("Unknown argument for {method}, sending the JSON as is.");
+                }
+                json::encode(&json, rule, !o.skip_validation).unwrap_or_else(|e| {
+                    error!("{e}");
+                    process::exit(1);
+                })
+            } else {
+                o.data
+                    .map_or(vec![], |d| cbor_diag::parse_diag(d).unwrap().to_bytes())
+            };
 
             let from_identity: Box<dyn Identity> = if let (Some(module), Some(slot), Some(keyid)) =
                 (o.module, o.slot, o.keyid)
             {
-                trace!("Getting user PIN");
-                let pin = rpassword::prompt_password("Please enter the HSM user PIN: ")
-                    .expect("I/O error when reading HSM PIN");
-                let keyid = hex::decode(keyid).expect("Failed to decode keyid to hex");
-
-                {
-                    let mut hsm = Hsm::get_instance().expect("HSM mutex poisoned");
-                    hsm.init(module, keyid)
-                        .expect("Failed to initialize HSM module");
-
-                    // The session will stay open until the application terminates
-                    hsm.open_session(slot, HsmSessionType::RO, Some(HsmUserType::User), Some(pin))
-                        .expect("Failed to open HSM session");
-                }
-
-                // Only ECDSA is supported at the moment. It should be easy to add support for
-                // new EC mechanisms.
-                Box::new(
-                    HsmIdentity::new(HsmMechanismType::ECDSA)
-                        .expect("Unable to create CoseKeyIdentity from HSM"),
-                )
+                Box::new(hsm_identity(module, slot, keyid))
             } else if let Some(p) = o.pem {
                 // If `pem` is not provided, use anonymous and don't sign.
                 Box::new(CoseKeyIdentity::from_pem(std::fs::read_to_string(p).unwrap()).unwrap())
@@ -566,7 +825,7 @@ async fn main() {
                         data,
                         timestamp,
                         o.r#async,
-                        o.proof.unwrap_or_default(),
+                        request_attributes(o.proof.unwrap_or_default(), o.simulate),
                     )
                     .await
                 };
@@ -586,14 +845,7 @@ async fn main() {
                     .to(to_identity)
                     .method(o.method.expect("--method is required"))
                     .data(data)
-                    .attributes(
-                        match o.proof {
-                            Some(false) | None => vec![],
-                            Some(true) => vec![Attribute::id(3)],
-                        }
-                        .into_iter()
-                        .collect(),
-                    );
+                    .attributes(request_attributes(o.proof.unwrap_or_default(), o.simulate));
                 if let Some(ts) = timestamp {
                     builder.timestamp(Timestamp::from_system_time(ts).unwrap());
                 }
@@ -619,7 +871,7 @@ async fn main() {
             );
 
             let many = ManyServer::simple(
-                o.name,
+                o.name.clone(),
                 Arc::clone(&key),
                 (AnonymousVerifier, CoseKeyVerifier),
                 Some(std::env!("CARGO_PKG_VERSION").to_string()),
@@ -630,7 +882,23 @@ async fn main() {
                 let mock_server = ManyMockServer::new(mockfile, None, key);
                 many_locked.set_fallback_module(mock_server);
             }
-            HttpServer::new(many).bind(o.addr).await.unwrap();
+
+            if o.routes.is_empty() {
+                HttpServer::new(many).bind(o.addr).await.unwrap();
+            } else {
+                let routes = RoutingTable::new(o.routes);
+                let upstreams = UpstreamStatus::fetch(o.name, key, &routes).await;
+                {
+                    let mut many_locked = many.lock().unwrap();
+                    many_locked.set_fallback_module(upstreams);
+                    // The upstreams don't report the version of this binary.
+                    many_locked.set_fallback_status_policy(FallbackStatusPolicy::Merge);
+                }
+                HttpServer::new(Gateway::new(many, routes))
+                    .bind(o.addr)
+                    .await
+                    .unwrap();
+            }
         }
         SubCommand::GetTokenId(o) => {
             let client = ManyClient::new(o.server, Address::anonymous(), AnonymousIdentity)
@@ -663,5 +931,69 @@ async fn main() {
 
             println!("{id}");
         }
+        SubCommand::Signerd(o) => {
+            let result =
+                if let (Some(module), Some(slot), Some(keyid)) = (o.module, o.slot, o.keyid) {
+                    signerd::run(o.addr, &o.token_file, hsm_identity(module, slot, keyid))
+                } else {
+                    let pem = std::fs::read_to_string(o.pem.expect("Must pass a PEM file"))
+                        .expect("Could not read PEM file.");
+                    let key = CoseKeyIdentity::from_pem(pem)
+                        .expect("Could not generate identity from PEM file.");
+                    signerd::run(o.addr, &o.token_file, key)
+                };
+            if let Err(e) = result {
+                error!("{e}");
+                process::exit(1);
+            }
+        }
+        SubCommand::Completions(o) => {
+            completions::generate(o.shell, &mut Opts::command(), &mut std::io::stdout())
+                .expect("Could not write the completion script");
+        }
+        SubCommand::Endpoints(o) => {
+            let client = ManyClient::new(o.server, Address::anonymous(), AnonymousIdentity)
+                .expect("Could not create a client");
+            let endpoints: base::Endpoints = client
+                .call_("endpoints", ())
+                .await
+                .and_then(|bytes| {
+                    minicbor::decode(&bytes).map_err(ManyError::deserialization_error)
+                })
+                .unwrap_or_else(|e| {
+                    error!("Could not get the endpoints of the server: {e}");
+                    process::exit(1);
+                });
+            for endpoint in endpoints.0 {
+                println!("{endpoint}");
+            }
+        }
+        SubCommand::Diff(o) => {
+            let differences = diff::envelopes(&o.before, &o.after).unwrap_or_else(|e| {
+                error!("{e}");
+                process::exit(1);
+            });
+            if differences.is_empty() {
+                info!("The payloads are identical.");
+            }
+            for difference in differences {
+                println!("{difference}");
+            }
+        }
+        SubCommand::Cddl(o) => {
+            let rules = match o.namespace {
+                Some(ns) => {
+                    let prefix = format!("{ns}.");
+                    cddl::PRELUDE
+                        .iter()
+                        .chain(cddl::RULES.iter().filter(|r| r.name.starts_with(&prefix)))
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join("\n\n")
+                }
+                None => cddl::cddl(),
+            };
+            println!("{}", rules.trim_end());
+        }
     }
 }
@@ -0,0 +1,186 @@
+//! A local daemon signing requests for other processes, so they never need
+//! access to the key itself.
+//!
+//! `POST` a CBOR encoded request message and the daemon returns the tagged
+//! CBOR encoding of its signed envelope. `GET` returns the address of the
+//! signing identity. The daemon only listens on loopback addresses, and every
+//! request must carry the token of the daemon as an `Authorization: Bearer`
+//! header, so other local users and web pages cannot use the key.
+use coset::TaggedCborSerializable;
+use many_identity::Identity;
+use many_protocol::{encode_cose_sign1_from_request, RequestMessage};
+use std::fs::OpenOptions;
+use std::io::{Cursor, Read, Write};
+use std::net::SocketAddr;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::Path;
+use tiny_http::{Method, Request, Response, Server};
+use tracing::{debug, info, warn};
+
+/// Requests are small; anything larger than this is refused.
+const MAXIMUM_REQUEST_SIZE: usize = 1024 * 1024 * 5;
+
+type HttpResponse = Response<Cursor<Vec<u8>>>;
+
+/// Read the token from a file only its owner can access, or create the file
+/// with a random token if it does not exist.
+fn load_token(path: &Path) -> Result<String, String> {
+    let display = path.display();
+    match OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)
+    {
+        Ok(mut file) => {
+            let mut bytes = [0u8; 32];
+            rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut bytes);
+            let token = hex::encode(bytes);
+            file.write_all(token.as_bytes())
+                .map_err(|e| format!("Could not write {display}: {e}"))?;
+            info!("Wrote a new token to {display}");
+            return Ok(token);
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(format!("Could not create {display}: {e}")),
+    }
+
+    let mode = std::fs::metadata(path)
+        .map_err(|e| format!("Could not read {display}: {e}"))?
+        .permissions()
+        .mode();
+    if mode & 0o077 != 0 {
+        return Err(format!(
+            "{display} must only be accessible by its owner (mode 0600), not {:o}.",
+            mode & 0o777
+        ));
+    }
+    let token = std::fs::read_to_string(path)
+        .map_err(|e| format!("Could not read {display}: {e}"))?
+        .trim()
+        .to_string();
+    if token.is_empty() {
+        return Err(format!("{display} does not contain a token."));
+    }
+    Ok(token)
+}
+
+/// Compare in a time independent of the position of the first difference.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn header<'a>(request: &'a Request, name: &'static str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv(name))
+        .map(|h| h.value.as_str())
+}
+
+/// Refuse requests for another host, e.g. from a web page which rebound its
+/// domain to the loopback address.
+fn is_allowed_host(addr: &SocketAddr, host: Option<&str>) -> bool {
+    let port = addr.port();
+    host.map_or(false, |host| {
+        host == addr.to_string() || host == format!("localhost:{port}")
+    })
+}
+
+fn is_authorized(token: &str, authorization: Option<&str>) -> bool {
+    authorization
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map_or(false, |given| {
+            constant_time_eq(given.trim().as_bytes(), token.as_bytes())
+        })
+}
+
+/// Sign an encoded request message. The `from` field of the request must
+/// either be missing or be the address of the identity.
+pub fn sign(identity: &impl Identity, bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let mut message = RequestMessage::from_bytes(bytes)?;
+    let address = identity.address();
+    match message.from {
+        None => message.from = Some(address),
+        Some(from) if from == address => {}
+        Some(from) => {
+            return Err(format!(
+                "Cannot sign a request from {from}, this signer is {address}."
+            ))
+        }
+    }
+
+    let envelope = encode_cose_sign1_from_request(message, identity).map_err(|e| e.to_string())?;
+    envelope.to_tagged_vec().map_err(|e| e.to_string())
+}
+
+fn error(status: u16, message: impl ToString) -> HttpResponse {
+    Response::from_string(message.to_string()).with_status_code(status)
+}
+
+fn handle(
+    identity: &impl Identity,
+    addr: &SocketAddr,
+    token: &str,
+    request: &mut Request,
+) -> HttpResponse {
+    if !is_allowed_host(addr, header(request, "Host")) {
+        return error(421, "Invalid host.");
+    }
+    if !is_authorized(token, header(request, "Authorization")) {
+        return error(401, "Invalid or missing token.");
+    }
+
+    match request.method() {
+        Method::Get => Response::from_string(identity.address().to_string()),
+        Method::Post => {
+            if request.body_length().unwrap_or(0) > MAXIMUM_REQUEST_SIZE {
+                return error(413, "Request too large.");
+            }
+            // The length of chunked bodies is unknown until they are read.
+            let mut bytes = Vec::new();
+            if let Err(e) = request
+                .as_reader()
+                .take(MAXIMUM_REQUEST_SIZE as u64 + 1)
+                .read_to_end(&mut bytes)
+            {
+                return error(400, e);
+            }
+            if bytes.len() > MAXIMUM_REQUEST_SIZE {
+                return error(413, "Request too large.");
+            }
+
+            match sign(identity, &bytes) {
+                Ok(envelope) => Response::from_data(envelope),
+                Err(e) => {
+                    warn!("Refused to sign a request: {e}");
+                    error(400, e)
+                }
+            }
+        }
+        _ => error(405, "Only GET and POST are supported."),
+    }
+}
+
+/// Serve signing requests until the process is stopped. The token requests
+/// must carry is read from `token_file`, see [load_token].
+pub fn run(addr: SocketAddr, token_file: &Path, identity: impl Identity) -> Result<(), String> {
+    if !addr.ip().is_loopback() {
+        return Err(format!(
+            "The signer must listen on a loopback address, not {addr}."
+        ));
+    }
+    let token = load_token(token_file)?;
+
+    let server = Server::http(addr).map_err(|e| e.to_string())?;
+    info!("Signing as {} on {addr}", identity.address());
+
+    for mut request in server.incoming_requests() {
+        debug!("{} {}", request.method(), request.url());
+        let response = handle(&identity, &addr, &token, &mut request);
+        if let Err(e) = request.respond(response) {
+            warn!("Could not respond: {e}");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,134 @@
+//! Search for Ed25519 keys whose address matches a text, e.g. to recognize an
+//! account at a glance.
+use many_identity::Identity;
+use many_identity_dsa::ed25519::Ed25519Identity;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+const BASE32_ALPHABET: &str = "abcdefghijklmnopqrstuvwxyz234567";
+
+/// The first byte of the addresses of public keys is fixed, so their textual
+/// format always starts with one of these.
+const ADDRESS_HEADERS: [&str; 4] = ["mae", "maf", "mag", "mah"];
+
+/// The characters of the hash in a textual address, between the header and
+/// the checksum.
+const HASH_LEN: usize = 45;
+
+pub enum Matcher {
+    Prefix(String),
+    Pattern(String),
+}
+
+fn validate(text: &str) -> Result<String, String> {
+    let text = text.to_ascii_lowercase();
+    if let Some(c) = text.chars().find(|c| !BASE32_ALPHABET.contains(*c)) {
+        return Err(format!(
+            "Invalid character '{c}', addresses only contain a-z and 2-7"
+        ));
+    }
+    Ok(text)
+}
+
+impl Matcher {
+    /// The address must start with this prefix, including its header.
+    pub fn prefix(prefix: &str) -> Result<Self, String> {
+        let prefix = validate(prefix)?;
+        let header_len = prefix.len().min(3);
+        if !ADDRESS_HEADERS
+            .iter()
+            .any(|h| h[..header_len] == prefix[..header_len])
+        {
+            return Err(format!(
+                "Addresses of keys start with one of {}",
+                ADDRESS_HEADERS.join(", ")
+            ));
+        }
+        if prefix.len() > 3 + HASH_LEN {
+            return Err("Prefix longer than an address".to_string());
+        }
+        Ok(Self::Prefix(prefix))
+    }
+
+    /// The address must contain this text anywhere after its header.
+    pub fn pattern(pattern: &str) -> Result<Self, String> {
+        let pattern = validate(pattern)?;
+        if pattern.is_empty() || pattern.len() > HASH_LEN {
+            return Err(format!("Pattern must be 1 to {HASH_LEN} characters"));
+        }
+        Ok(Self::Pattern(pattern))
+    }
+
+    fn matches(&self, address: &str) -> bool {
+        match self {
+            Matcher::Prefix(prefix) => address.starts_with(prefix.as_str()),
+            Matcher::Pattern(pattern) => address[3..3 + HASH_LEN].contains(pattern.as_str()),
+        }
+    }
+
+    /// The average number of keys to generate before finding a match.
+    pub fn difficulty(&self) -> f64 {
+        match self {
+            Matcher::Prefix(prefix) if prefix.len() < 3 => 1.,
+            Matcher::Prefix(prefix) => 4. * 32f64.powi(prefix.len() as i32 - 3),
+            Matcher::Pattern(pattern) => {
+                32f64.powi(pattern.len() as i32) / (HASH_LEN + 1 - pattern.len()) as f64
+            }
+        }
+    }
+}
+
+fn report(tried: u64, start: Instant, difficulty: f64) {
+    let rate = tried as f64 / start.elapsed().as_secs_f64();
+    let probability = 100. * (1. - (-(tried as f64) / difficulty).exp());
+    eprint!(
+        "\r{tried} keys tried, {rate:.0} keys/s, {probability:.1}% chance of a match so far    "
+    );
+}
+
+/// Generate keys on `threads` threads until one matches, reporting progress
+/// on stderr every second.
+pub fn search(matcher: &Matcher, threads: usize) -> Ed25519Identity {
+    let tried = AtomicU64::new(0);
+    let found = AtomicBool::new(false);
+    let (tx, rx) = mpsc::channel();
+    let start = Instant::now();
+    let difficulty = matcher.difficulty();
+    eprintln!("Searching on {threads} threads, about {difficulty:.0} keys to try on average.");
+
+    std::thread::scope(|s| {
+        for _ in 0..threads {
+            let (tx, tried, found) = (tx.clone(), &tried, &found);
+            s.spawn(move || {
+                let mut rng = rand::thread_rng();
+                let mut secret_key = [0u8; 32];
+                while !found.load(Ordering::Relaxed) {
+                    rand::RngCore::fill_bytes(&mut rng, &mut secret_key);
+                    let id = Ed25519Identity::from_secret_key(&secret_key)
+                        .expect("Could not create an identity from a random key");
+                    tried.fetch_add(1, Ordering::Relaxed);
+                    if matcher.matches(&id.address().to_string()) {
+                        found.store(true, Ordering::Relaxed);
+                        let _ = tx.send(id);
+                    }
+                }
+            });
+        }
+        drop(tx);
+
+        loop {
+            match rx.recv_timeout(Duration::from_secs(1)) {
+                Ok(id) => {
+                    report(tried.load(Ordering::Relaxed), start, difficulty);
+                    eprintln!();
+                    break id;
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    report(tried.load(Ordering::Relaxed), start, difficulty)
+                }
+                Err(RecvTimeoutError::Disconnected) => unreachable!("Workers stopped early"),
+            }
+        }
+    })
+}
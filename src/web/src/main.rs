@@ -9,6 +9,9 @@ use many_modules::{r#async, web};
 use many_protocol::ResponseMessage;
 use many_types::web::{WebDeploymentFilter, WebDeploymentSource};
 use many_types::{Memo, SortOrder};
+use sha2::Digest;
+use std::io::Read;
+use std::net::IpAddr;
 use std::path::PathBuf;
 use std::time::Duration;
 use tracing::{debug, error, info};
@@ -48,6 +51,12 @@ enum SubCommand {
 
     /// Update website
     Update(UpdateOpt),
+
+    /// List the versions of a website
+    Versions(VersionsOpt),
+
+    /// Serve a previous version of a website
+    Rollback(RollbackOpt),
 }
 
 #[derive(Debug, Parser)]
@@ -59,8 +68,18 @@ struct DeployOpt {
     #[clap(long)]
     site_description: Option<String>,
 
-    /// Site source
-    source: PathBuf,
+    /// Site source, a zip archive
+    #[clap(required_unless_present = "url")]
+    source: Option<PathBuf>,
+
+    /// URL of a zip archive to download and deploy instead of the source.
+    /// Only HTTPS URLs to public hosts are accepted
+    #[clap(long, conflicts_with = "source", requires = "hash")]
+    url: Option<String>,
+
+    /// Hex encoded SHA-256 hash of the archive at the URL
+    #[clap(long)]
+    hash: Option<String>,
 
     /// MANY address of the website owner
     #[clap(long)]
@@ -84,8 +103,18 @@ struct UpdateOpt {
     #[clap(long)]
     site_description: Option<String>,
 
-    /// Site source
-    source: PathBuf,
+    /// Site source, a zip archive
+    #[clap(required_unless_present = "url")]
+    source: Option<PathBuf>,
+
+    /// URL of a zip archive to download and deploy instead of the source.
+    /// Only HTTPS URLs to public hosts are accepted
+    #[clap(long, conflicts_with = "source", requires = "hash")]
+    url: Option<String>,
+
+    /// Hex encoded SHA-256 hash of the archive at the URL
+    #[clap(long)]
+    hash: Option<String>,
 
     /// MANY address of the website owner
     #[clap(long)]
@@ -114,6 +143,33 @@ struct RemoveOpt {
     memo: Option<Memo>,
 }
 
+#[derive(Debug, Parser)]
+struct VersionsOpt {
+    /// Site name
+    site_name: String,
+
+    /// MANY address of the website owner
+    #[clap(long)]
+    owner: Option<Address>,
+}
+
+#[derive(Debug, Parser)]
+struct RollbackOpt {
+    /// Site name
+    site_name: String,
+
+    /// Version to serve
+    version: u64,
+
+    /// MANY address of the website owner
+    #[clap(long)]
+    owner: Option<Address>,
+
+    /// A memo to attach to the transaction
+    #[clap(long, parse(try_from_str = Memo::try_from))]
+    memo: Option<Memo>,
+}
+
 #[derive(Debug, Parser)]
 struct ListOpt {
     /// Count
@@ -133,22 +189,133 @@ struct ListOpt {
     page: Option<usize>,
 }
 
+/// The maximum size of an archive downloaded from a URL.
+const MAXIMUM_REMOTE_ARCHIVE_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Whether an IP address can be reached from outside of the local network.
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast())
+        }
+        IpAddr::V6(ip) => {
+            let first = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || (first & 0xfe00) == 0xfc00 // Unique local
+                || (first & 0xffc0) == 0xfe80) // Link local
+        }
+    }
+}
+
+/// Parse the URL of an archive. Only HTTPS URLs to public hosts are allowed,
+/// so a deployment cannot be made of internal resources.
+fn archive_url(url: &str) -> Result<reqwest::Url, ManyError> {
+    let invalid = || {
+        ManyError::unknown("Invalid archive URL. Only HTTPS URLs to public hosts are supported.")
+    };
+    let url = reqwest::Url::parse(url).map_err(|_| invalid())?;
+    let host = url
+        .host_str()
+        .ok_or_else(invalid)?
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    let public = match host.parse::<IpAddr>() {
+        Ok(ip) => is_public_ip(ip),
+        Err(_) => host != "localhost" && !host.ends_with(".localhost"),
+    };
+    if url.scheme() != "https" || !public {
+        return Err(invalid());
+    }
+    Ok(url)
+}
+
+/// Download a zip archive and verify its SHA-256 hash. The archive is fetched
+/// by the client and deployed as any other archive; servers never fetch it.
+fn fetch_archive(url: &str, hash: &[u8]) -> Result<Vec<u8>, ManyError> {
+    let url = archive_url(url)?;
+    let failed = |e: &dyn std::fmt::Display| {
+        debug!("Unable to fetch archive: {e}");
+        ManyError::unknown("Unable to fetch the archive.")
+    };
+    let too_large = || {
+        ManyError::unknown(format!(
+            "Archive too large. The maximum size is {MAXIMUM_REMOTE_ARCHIVE_SIZE} bytes."
+        ))
+    };
+
+    info!("Fetching archive from {url}");
+    let client = reqwest::blocking::Client::builder()
+        .connect_timeout(Duration::from_secs(10))
+        .timeout(Duration::from_secs(300))
+        .build()
+        .map_err(|e| failed(&e))?;
+    let response = client
+        .get(url)
+        .send()
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| failed(&e))?;
+    if response
+        .content_length()
+        .map_or(false, |len| len > MAXIMUM_REMOTE_ARCHIVE_SIZE)
+    {
+        return Err(too_large());
+    }
+
+    let mut bytes = Vec::new();
+    response
+        .take(MAXIMUM_REMOTE_ARCHIVE_SIZE + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| failed(&e))?;
+    if bytes.len() as u64 > MAXIMUM_REMOTE_ARCHIVE_SIZE {
+        return Err(too_large());
+    }
+
+    if sha2::Sha256::digest(&bytes).as_slice() != hash {
+        return Err(ManyError::unknown("The archive does not match its hash."));
+    }
+    Ok(bytes)
+}
+
+fn deployment_source(
+    source: Option<PathBuf>,
+    url: Option<String>,
+    hash: Option<String>,
+) -> Result<WebDeploymentSource, ManyError> {
+    let source = match (source, url, hash) {
+        (_, Some(url), Some(hash)) => {
+            let hash = hex::decode(hash).map_err(ManyError::unknown)?;
+            fetch_archive(&url, &hash)?
+        }
+        // Read the source file
+        (Some(source), None, _) => std::fs::read(source).map_err(ManyError::unknown)?,
+        _ => {
+            return Err(ManyError::unknown(
+                "Either a source or a URL and its hash must be specified.",
+            ))
+        }
+    };
+    Ok(WebDeploymentSource::Archive(source.into()))
+}
+
 fn deploy(
     client: ManyClient<impl Identity>,
     site_name: String,
     site_description: Option<String>,
-    source: PathBuf,
+    source: WebDeploymentSource,
     owner: Option<Address>,
     memo: Option<Memo>,
     domain: Option<String>,
 ) -> Result<(), ManyError> {
-    // Read the source file
-    let source = std::fs::read(source).map_err(ManyError::unknown)?;
     let arguments = web::DeployArgs {
         owner,
         site_name,
         site_description,
-        source: WebDeploymentSource::Archive(source.into()),
+        source,
         memo,
         domain,
     };
@@ -165,18 +332,16 @@ fn update(
     client: ManyClient<impl Identity>,
     site_name: String,
     site_description: Option<String>,
-    source: PathBuf,
+    source: WebDeploymentSource,
     owner: Option<Address>,
     memo: Option<Memo>,
     domain: Option<String>,
 ) -> Result<(), ManyError> {
-    // Read the source file
-    let source = std::fs::read(source).map_err(ManyError::unknown)?;
     let arguments = web::UpdateArgs {
         owner,
         site_name,
         site_description,
-        source: WebDeploymentSource::Archive(source.into()),
+        source,
         memo,
         domain,
     };
@@ -231,6 +396,43 @@ fn list(
     Ok(())
 }
 
+fn versions(
+    client: ManyClient<impl Identity>,
+    site_name: String,
+    owner: Option<Address>,
+) -> Result<(), ManyError> {
+    let args = web::VersionsArgs { owner, site_name };
+    let response = client.call("web.versions", args)?;
+    let payload = wait_response(client, response)?;
+    println!(
+        "{}",
+        cbor_diag::parse_bytes(payload).unwrap().to_diag_pretty()
+    );
+    Ok(())
+}
+
+fn rollback(
+    client: ManyClient<impl Identity>,
+    site_name: String,
+    version: u64,
+    owner: Option<Address>,
+    memo: Option<Memo>,
+) -> Result<(), ManyError> {
+    let arguments = web::RollbackArgs {
+        owner,
+        site_name,
+        version,
+        memo,
+    };
+    let response = client.call("web.rollback", arguments)?;
+    let payload = wait_response(client, response)?;
+    println!(
+        "{}",
+        cbor_diag::parse_bytes(payload).unwrap().to_diag_pretty()
+    );
+    Ok(())
+}
+
 pub(crate) fn wait_response(
     client: ManyClient<impl Identity>,
     response: ResponseMessage,
@@ -321,18 +523,22 @@ fn main() {
             site_name,
             site_description,
             source,
+            url,
+            hash,
             owner,
             memo,
             domain,
-        }) => deploy(
-            client,
-            site_name,
-            site_description,
-            source,
-            owner,
-            memo,
-            domain,
-        ),
+        }) => deployment_source(source, url, hash).and_then(|source| {
+            deploy(
+                client,
+                site_name,
+                site_description,
+                source,
+                owner,
+                memo,
+                domain,
+            )
+        }),
         SubCommand::Remove(RemoveOpt {
             site_name,
             owner,
@@ -348,18 +554,31 @@ fn main() {
             site_name,
             site_description,
             source,
+            url,
+            hash,
             owner,
             memo,
             domain,
-        }) => update(
-            client,
+        }) => deployment_source(source, url, hash).and_then(|source| {
+            update(
+                client,
+                site_name,
+                site_description,
+                source,
+                owner,
+                memo,
+                domain,
+            )
+        }),
+        SubCommand::Versions(VersionsOpt { site_name, owner }) => {
+            versions(client, site_name, owner)
+        }
+        SubCommand::Rollback(RollbackOpt {
             site_name,
-            site_description,
-            source,
+            version,
             owner,
             memo,
-            domain,
-        ),
+        }) => rollback(client, site_name, version, owner, memo),
     };
 
     if let Err(err) = result {
@@ -113,6 +113,23 @@ function teardown() {
   assert_output --partial "sad"
 }
 
+@test "$SUITE: can disable then enable" {
+  call_kvstore --pem=1 --port=8000 put "445566" "foobar"
+  call_kvstore --pem=1 --port=8000 disable "445566"
+  call_kvstore --pem=1 --port=8000 get "445566"
+  assert_output --partial "The key was disabled by its owner."
+
+  call_kvstore --pem=2 --port=8000 enable "445566"
+  assert_output --partial "You do not have the authorization to modify this key."
+
+  call_kvstore --pem=1 --port=8000 enable "445566"
+  call_kvstore --pem=1 --port=8000 get "445566"
+  assert_output --partial "foobar"
+
+  call_kvstore --pem=1 --port=8000 enable "445566"
+  assert_output --partial "The key is not disabled."
+}
+
 @test "$SUITE: can transfer" {
   call_kvstore --pem=1 --port=8000 put "112233" "foobar"
   call_kvstore --pem=1 --port=8000 query "112233"